@@ -0,0 +1,12 @@
+#!/bin/bash
+# Runs the keys crate criterion benchmarks, comparing against a saved baseline if one exists.
+# Usage: ./bin/bench_keys.sh [baseline_name]
+# Save a baseline first with: cargo bench -p redgold-keys -- --save-baseline main
+
+BASELINE=${1:-main}
+
+if [ -d "./target/criterion" ] && ls ./target/criterion/*/*/"$BASELINE" >/dev/null 2>&1; then
+  cargo bench -p redgold-keys -- --baseline "$BASELINE"
+else
+  cargo bench -p redgold-keys -- --save-baseline "$BASELINE"
+fi
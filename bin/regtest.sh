@@ -0,0 +1,33 @@
+#!/bin/bash
+
+# Starts a local bitcoin regtest node + electrum server for Local / Debug network environments
+# Optionally funds an address: ./bin/regtest.sh <btc_address>
+
+script_dir="$( cd "$( dirname "${BASH_SOURCE[0]}" )" &> /dev/null && pwd )"
+compose_file="$script_dir/../src/resources/infra/regtest.yml"
+
+docker compose -f "$compose_file" up -d
+
+cli() {
+  docker exec redgold-regtest-bitcoind bitcoin-cli -regtest -rpcuser=redgold -rpcpassword=redgold "$@"
+}
+
+until cli getblockchaininfo >/dev/null 2>&1; do
+  sleep 1
+done
+
+cli createwallet redgold >/dev/null 2>&1 || cli loadwallet redgold >/dev/null 2>&1
+miner_address=$(cli getnewaddress)
+
+# Coinbase outputs require 100 confirmations before they are spendable
+cli generatetoaddress 101 "$miner_address" >/dev/null
+
+if [ -n "$1" ]
+then
+    cli sendtoaddress "$1" 1.0
+    cli generatetoaddress 1 "$miner_address" >/dev/null
+    echo "Funded $1"
+fi
+
+export REDGOLD_BTC_REGTEST_ELECTRUM_URL="tcp://127.0.0.1:50001"
+echo "Regtest electrum available at $REDGOLD_BTC_REGTEST_ELECTRUM_URL"
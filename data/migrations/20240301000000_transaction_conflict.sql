@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS transaction_conflict (
+                                    utxo_transaction_hash BLOB NOT NULL,
+                                    utxo_output_index INTEGER NOT NULL,
+                                    transaction_hash BLOB NOT NULL,
+                                    conflicting_transaction_hash BLOB NOT NULL,
+                                    time INTEGER NOT NULL,
+                                    PRIMARY KEY (utxo_transaction_hash, utxo_output_index, transaction_hash, conflicting_transaction_hash)
+);
+
+CREATE INDEX IF NOT EXISTS transaction_conflict_transaction_hash
+    ON transaction_conflict (transaction_hash DESC);
+
+CREATE INDEX IF NOT EXISTS transaction_conflict_conflicting_transaction_hash
+    ON transaction_conflict (conflicting_transaction_hash DESC);
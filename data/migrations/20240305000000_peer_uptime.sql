@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS peer_uptime (
+                                    public_key BLOB PRIMARY KEY NOT NULL,
+                                    checks INTEGER NOT NULL,
+                                    successes INTEGER NOT NULL,
+                                    last_check INTEGER NOT NULL,
+                                    last_success INTEGER
+);
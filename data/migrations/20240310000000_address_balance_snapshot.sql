@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS address_balance_snapshot (
+                                    address BLOB NOT NULL,
+                                    day INTEGER NOT NULL,
+                                    balance INTEGER NOT NULL,
+                                    time INTEGER NOT NULL,
+                                    PRIMARY KEY (address, day)
+);
@@ -0,0 +1,25 @@
+-- Bridge rows are unique per external txid and direction, keeping the first row of any duplicates
+CREATE TABLE IF NOT EXISTS multiparty_bridge_direction (
+                                    txid BLOB,
+                                    secondary_txid BLOB,
+                                    outgoing INTEGER,
+                                    network INTEGER,
+                                    source_address BLOB,
+                                    destination_address BLOB,
+                                    timestamp INTEGER,
+                                    amount INTEGER,
+                                    PRIMARY KEY (txid, outgoing)
+);
+
+INSERT OR IGNORE INTO multiparty_bridge_direction (txid, secondary_txid, outgoing, network,
+                                                   source_address, destination_address, timestamp, amount)
+SELECT txid, secondary_txid, outgoing, network, source_address, destination_address, timestamp, amount
+FROM multiparty_bridge
+WHERE rowid IN (SELECT MIN(rowid) FROM multiparty_bridge GROUP BY txid, outgoing);
+
+DROP TABLE multiparty_bridge;
+
+ALTER TABLE multiparty_bridge_direction RENAME TO multiparty_bridge;
+
+CREATE INDEX IF NOT EXISTS multiparty_bridge_secondary_txid
+    ON multiparty_bridge (secondary_txid);
@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS name_registry (
+                                    name TEXT PRIMARY KEY,
+                                    address BLOB NOT NULL,
+                                    owner BLOB NOT NULL,
+                                    expiry_time INTEGER NOT NULL,
+                                    transaction_hash BLOB NOT NULL,
+                                    time INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS name_registry_address ON name_registry (address);
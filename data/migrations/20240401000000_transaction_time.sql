@@ -0,0 +1,6 @@
+ALTER TABLE transactions ADD COLUMN transaction_time INTEGER;
+
+UPDATE transactions SET transaction_time = time WHERE transaction_time IS NULL;
+
+CREATE INDEX IF NOT EXISTS transaction_transaction_time
+    ON transactions (transaction_time);
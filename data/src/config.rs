@@ -1,12 +1,106 @@
+use std::marker::PhantomData;
 use sqlx::Row;
 use redgold_schema::structs::{DynamicNodeMetadata, ErrorInfo, Transaction};
-use redgold_schema::{EasyJsonDeser, ErrorInfoContext, ProtoSerde, RgResult};
+use redgold_schema::{error_info, EasyJsonDeser, ErrorInfoContext, ProtoSerde, RgResult};
 use crate::DataStoreContext;
 use crate::schema::SafeOption;
-use crate::schema::json;
+use crate::schema::{json, json_from};
 use serde::{Deserialize, Serialize};
 use redgold_keys::TestConstants;
 use redgold_schema::local_stored_state::LocalStoredState;
+use redgold_schema::servers::Server;
+
+/// Upgrades a stored json value by one version.
+pub type ConfigMigration = fn(serde_json::Value) -> RgResult<serde_json::Value>;
+
+/// Typed handle for a config entry. Values are stored with their version, and
+/// `migrations[i]` upgrades a value stored at version `i` to version `i + 1`, so
+/// older layouts are converted on read rather than failing to deserialize.
+/// Entries written before versioning was introduced are treated as version 0.
+pub struct ConfigKey<T> {
+    pub name: &'static str,
+    pub version: u32,
+    pub migrations: &'static [ConfigMigration],
+    _value: PhantomData<fn() -> T>,
+}
+
+impl<T> ConfigKey<T> {
+    pub const fn new(name: &'static str) -> Self {
+        Self { name, version: 0, migrations: &[], _value: PhantomData }
+    }
+
+    pub const fn versioned(name: &'static str, migrations: &'static [ConfigMigration]) -> Self {
+        Self { name, version: migrations.len() as u32, migrations, _value: PhantomData }
+    }
+}
+
+impl<T: Serialize + for<'de> Deserialize<'de>> ConfigKey<T> {
+    /// Parses a stored entry, returning the value along with the version it was stored at.
+    pub fn decode(&self, raw: &str) -> RgResult<(T, u32)> {
+        let envelope = ConfigEnvelope::parse(raw)?;
+        let migrated = migrate_config_value(self.name, envelope.version, self.migrations, envelope.payload)?;
+        let res: T = serde_json::from_value(migrated)
+            .error_info(format!("Failed to deserialize config {}", self.name))?;
+        Ok((res, envelope.version))
+    }
+
+    pub fn encode(&self, value: &T) -> RgResult<String> {
+        let payload = serde_json::to_value(value).error_info("Failed to serialize config")?;
+        json(&ConfigEnvelope { version: self.version, payload })
+    }
+}
+
+/// Wrapper every JSON config entry is persisted in.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ConfigEnvelope {
+    #[serde(default)]
+    pub version: u32,
+    pub payload: serde_json::Value,
+}
+
+impl ConfigEnvelope {
+    /// Entries written before the envelope was introduced are read as a version 0 payload.
+    pub fn parse(raw: &str) -> RgResult<Self> {
+        let value: serde_json::Value = json_from(raw)?;
+        let is_envelope = value.as_object()
+            .map(|o| o.contains_key("payload") && o.keys().all(|k| k == "payload" || k == "version"))
+            .unwrap_or(false);
+        if is_envelope {
+            serde_json::from_value(value).error_info("Invalid config envelope")
+        } else {
+            Ok(Self { version: 0, payload: value })
+        }
+    }
+}
+
+/// Applies any migrations needed to bring a stored value up to the key's current version.
+pub fn migrate_config_value(
+    name: &str, version: u32, migrations: &[ConfigMigration], mut value: serde_json::Value
+) -> RgResult<serde_json::Value> {
+    if version as usize > migrations.len() {
+        return Err(error_info(format!(
+            "Config {} stored at version {} newer than supported version {}", name, version, migrations.len()
+        )));
+    }
+    for m in migrations.iter().skip(version as usize) {
+        value = m(value)?;
+    }
+    Ok(value)
+}
+
+// Early local state was written before its list fields were optional in practice.
+fn default_local_stored_state_lists(mut value: serde_json::Value) -> RgResult<serde_json::Value> {
+    let obj = value.as_object_mut().ok_or(error_info("Local stored state is not an object"))?;
+    for field in ["servers", "xpubs", "trust", "contacts", "watched_address", "identities"] {
+        obj.entry(field).or_insert(serde_json::Value::Array(vec![]));
+    }
+    Ok(value)
+}
+
+pub const LOCAL_STORED_STATE: ConfigKey<LocalStoredState> = ConfigKey::versioned(
+    "local_stored_state", &[default_local_stored_state_lists]
+);
+pub const SERVERS: ConfigKey<Vec<Server>> = ConfigKey::new("servers");
 
 #[derive(Clone)]
 pub struct ConfigStore {
@@ -57,24 +151,14 @@ impl ConfigStore {
         Ok(rows_m.last_insert_rowid())
     }
 
+    /// Stores an untyped entry at version 0, prefer a `ConfigKey` for anything with a known layout.
     pub async fn insert_update_json<T: Serialize, S: Into<String>>(
         &self,
         key: S,
         value: T
     ) -> Result<i64, ErrorInfo> {
-        let key: String = key.into();
-        let mut pool = self.ctx.pool().await?;
-        let val = json(&value)?;
-        let rows = sqlx::query!(
-            r#"
-        INSERT OR REPLACE INTO config ( key_name, value_data ) VALUES ( ?1, ?2)
-                "#,
-            key, val
-        )
-            .execute(&mut *pool)
-            .await;
-        let rows_m = DataStoreContext::map_err_sqlx(rows)?;
-        Ok(rows_m.last_insert_rowid())
+        let payload = serde_json::to_value(&value).error_info("Failed to serialize config")?;
+        self.insert_update(key.into(), json(&ConfigEnvelope { version: 0, payload })?).await
     }
 
     pub async fn select_config(
@@ -137,13 +221,56 @@ impl ConfigStore {
     pub async fn get_json<T: for<'de> Deserialize<'de> + Clone>(&self, key: impl Into<String>) -> RgResult<Option<T>> {
         let option = self.select_config(key.into()).await?;
         if let Some(str) = option {
-            let res: T = str.json_from()?;
-            Ok(Some(res.clone()))
+            let payload = ConfigEnvelope::parse(&str)?.payload;
+            let res: T = serde_json::from_value(payload).error_info("Failed to deserialize config")?;
+            Ok(Some(res))
         } else {
             Ok(None)
         }
     }
 
+    /// Reads a typed entry, upgrading and rewriting it if it was stored at an older version.
+    pub async fn get_typed<T: Serialize + for<'de> Deserialize<'de>>(&self, key: &ConfigKey<T>) -> RgResult<Option<T>> {
+        let option = self.select_config(key.name.to_string()).await?;
+        if let Some(str) = option {
+            let (res, version) = key.decode(&str)?;
+            if version < key.version {
+                self.set_typed(key, &res).await?;
+            }
+            Ok(Some(res))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub async fn set_typed<T: Serialize + for<'de> Deserialize<'de>>(&self, key: &ConfigKey<T>, value: &T) -> RgResult<i64> {
+        self.insert_update(key.name.to_string(), key.encode(value)?).await
+    }
+
+    pub async fn select_all_config(&self) -> RgResult<Vec<(String, Option<String>)>> {
+        let mut pool = self.ctx.pool().await?;
+        let rows = sqlx::query(
+            "SELECT key_name, value_data FROM config ORDER BY key_name",
+        ).fetch_all(&mut *pool).await;
+        let rows2 = DataStoreContext::map_err_sqlx(rows)?;
+        let mut res = vec![];
+        for row in rows2 {
+            let key: String = row.try_get("key_name").error_info("key_name not found")?;
+            let value: Option<String> = row.try_get("value_data").error_info("value_data not found")?;
+            res.push((key, value));
+        }
+        Ok(res)
+    }
+
+    pub async fn delete_config(&self, key: impl Into<String>) -> RgResult<u64> {
+        let mut pool = self.ctx.pool().await?;
+        let rows = sqlx::query(
+            "DELETE FROM config WHERE key_name = ?1",
+        ).bind(key.into()).execute(&mut *pool).await;
+        let rows_m = DataStoreContext::map_err_sqlx(rows)?;
+        Ok(rows_m.rows_affected())
+    }
+
     pub async fn get_peer_tx(&self) -> RgResult<Option<Transaction>> {
         self.get_maybe_proto("peer_tx").await
     }
@@ -169,11 +296,19 @@ impl ConfigStore {
     }
 
     pub async fn get_stored_state(&self) -> RgResult<LocalStoredState> {
-        Ok(self.get_json("local_stored_state").await?.unwrap_or(Default::default()))
+        Ok(self.get_typed(&LOCAL_STORED_STATE).await?.unwrap_or(Default::default()))
     }
 
     pub async fn update_stored_state(&self, local_stored_state: LocalStoredState) -> RgResult<()> {
-        self.insert_update_json("local_stored_state", local_stored_state).await.map(|_| ())
+        self.set_typed(&LOCAL_STORED_STATE, &local_stored_state).await.map(|_| ())
+    }
+
+    pub async fn get_servers(&self) -> RgResult<Vec<Server>> {
+        Ok(self.get_typed(&SERVERS).await?.unwrap_or(vec![]))
+    }
+
+    pub async fn set_servers(&self, servers: &Vec<Server>) -> RgResult<()> {
+        self.set_typed(&SERVERS, servers).await.map(|_| ())
     }
 
     pub async fn store_genesis(&self, gen: &Transaction) -> RgResult<i64> {
@@ -186,3 +321,32 @@ impl ConfigStore {
 
 
 }
+
+#[test]
+fn config_migrations_apply_from_stored_version() {
+    fn add_field(mut v: serde_json::Value) -> RgResult<serde_json::Value> {
+        v["added"] = serde_json::Value::from(1);
+        Ok(v)
+    }
+    const KEY: ConfigKey<serde_json::Value> = ConfigKey::versioned("test_key", &[add_field]);
+    let (migrated, version) = KEY.decode("{\"a\": 2}").expect("decode");
+    assert_eq!(version, 0);
+    assert_eq!(migrated["added"], 1);
+    let stored = KEY.encode(&migrated).expect("encode");
+    let (reloaded, version) = KEY.decode(&stored).expect("decode");
+    assert_eq!(version, KEY.version);
+    assert_eq!(reloaded, migrated);
+    // Entries from a newer release aren't silently downgraded
+    assert!(KEY.decode("{\"version\": 2, \"payload\": {}}").is_err());
+}
+
+#[test]
+fn legacy_config_fixtures_load() {
+    let (state, version) = LOCAL_STORED_STATE.decode(include_str!("resources/local_stored_state_v0.json"))
+        .expect("legacy local stored state");
+    assert_eq!(version, 0);
+    assert_eq!(state.servers.len(), 1);
+    assert!(state.contacts.is_empty());
+    let (servers, _) = SERVERS.decode(include_str!("resources/servers_v0.json")).expect("legacy servers");
+    assert_eq!(servers.len(), 1);
+}
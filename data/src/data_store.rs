@@ -7,7 +7,7 @@ use itertools::Itertools;
 use log::info;
 use metrics::gauge;
 use sqlx::SqlitePool;
-use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode};
+use sqlx::sqlite::{SqliteAutoVacuum, SqliteConnectOptions, SqliteJournalMode};
 
 use crate::address_block::AddressBlockStore;
 use crate::config::ConfigStore;
@@ -24,6 +24,7 @@ use crate::schema::structs::{
 };
 use crate::state_store::StateStore;
 use crate::utxo_store::UtxoStore;
+use crate::name_store::NameStore;
 
 #[derive(Clone)]
 pub struct DataStore {
@@ -39,6 +40,7 @@ pub struct DataStore {
     pub ctx: DataStoreContext,
     pub state: StateStore,
     pub utxo: UtxoStore,
+    pub name_store: NameStore,
 }
 
 impl DataStore {
@@ -154,6 +156,8 @@ WHERE
         let options = SqliteConnectOptions::new()
             .create_if_missing(true)
             .journal_mode(SqliteJournalMode::Wal) // Set journal mode to WAL
+            // Lets pruning release pages without a full VACUUM, existing files switch on their next VACUUM
+            .auto_vacuum(SqliteAutoVacuum::Incremental)
             .busy_timeout(std::time::Duration::from_secs(60)) // Set busy timeout to 10 seconds
             .filename(Path::new(&path.clone()));
         /*
@@ -178,6 +182,7 @@ WHERE
             multiparty_store: MultipartyStore { ctx: ctx.clone() },
             observation: ObservationStore { ctx: ctx.clone() },
             state: StateStore { ctx: ctx.clone() },
+            name_store: NameStore { ctx: ctx.clone() },
         }
     }
 
@@ -21,6 +21,8 @@ pub mod observation_store;
 pub mod data_store;
 pub mod state_store;
 pub mod utxo_store;
+pub mod retention;
+pub mod name_store;
 
 #[derive(Clone)]
 pub struct DataStoreContext {
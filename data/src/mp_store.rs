@@ -1,10 +1,26 @@
 use redgold_keys::TestConstants;
+use sqlx::Row;
 use redgold_schema::structs::{Address, ErrorInfo, SupportedCurrency, InitiateMultipartyKeygenRequest, InitiateMultipartySigningRequest, Proof, PublicKey};
-use redgold_schema::{ProtoHashable, ProtoSerde, SafeBytesAccess};
+use redgold_schema::{ErrorInfoContext, ProtoHashable, ProtoSerde, RgResult, SafeBytesAccess};
 use crate::DataStoreContext;
 use crate::schema::SafeOption;
 use redgold_schema::util;
 
+/// Identifying columns of a stored bridge row, used when reconciling against fulfillment records.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BridgeRowKey {
+    pub txid: Option<Vec<u8>>,
+    pub secondary_txid: Option<Vec<u8>>,
+    pub outgoing: Option<bool>,
+}
+
+// Rows sharing a txid and direction beyond the first, only possible for NULL keys
+// which SQLite doesn't consider equal under the primary key constraint.
+const DUPLICATE_BRIDGE_ROWS: &str = r#"SELECT COUNT(*) as count FROM multiparty_bridge
+    WHERE rowid NOT IN (SELECT MIN(rowid) FROM multiparty_bridge GROUP BY txid, outgoing)"#;
+const REMOVE_DUPLICATE_BRIDGE_ROWS: &str = r#"DELETE FROM multiparty_bridge
+    WHERE rowid NOT IN (SELECT MIN(rowid) FROM multiparty_bridge GROUP BY txid, outgoing)"#;
+
 #[derive(Clone)]
 pub struct MultipartyStore {
     pub ctx: DataStoreContext
@@ -134,7 +150,7 @@ impl MultipartyStore {
         destination_address: &Address,
         timestamp: i64,
         amount: i64
-    ) -> Result<i64, ErrorInfo> {
+    ) -> Result<bool, ErrorInfo> {
         let mut pool = self.ctx.pool().await?;
         let network = network as i32;
         let source_address = source_address.address.safe_bytes()?;
@@ -142,7 +158,8 @@ impl MultipartyStore {
         let rows = sqlx::query!(
             r#"INSERT INTO multiparty_bridge (txid, secondary_txid, outgoing,
             network, source_address, destination_address, timestamp, amount)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)"#,
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            ON CONFLICT (txid, outgoing) DO NOTHING"#,
             txid,
             secondary_txid,
             outgoing,
@@ -155,7 +172,41 @@ impl MultipartyStore {
             .execute(&mut *pool)
             .await;
         let r = DataStoreContext::map_err_sqlx(rows)?;
-        Ok(r.last_insert_rowid())
+        // Zero rows affected means this txid and direction was already recorded, so retrying
+        // after a partial failure is safe.
+        Ok(r.rows_affected() > 0)
+    }
+
+    pub async fn bridge_row_keys(&self) -> RgResult<Vec<BridgeRowKey>> {
+        let mut pool = self.ctx.pool().await?;
+        let rows = sqlx::query("SELECT txid, secondary_txid, outgoing FROM multiparty_bridge")
+            .fetch_all(&mut *pool).await;
+        let rows = DataStoreContext::map_err_sqlx(rows)?;
+        let mut res = vec![];
+        for row in rows {
+            res.push(BridgeRowKey {
+                txid: row.try_get("txid").error_info("txid not found")?,
+                secondary_txid: row.try_get("secondary_txid").error_info("secondary_txid not found")?,
+                outgoing: row.try_get("outgoing").error_info("outgoing not found")?,
+            });
+        }
+        Ok(res)
+    }
+
+    pub async fn count_duplicate_bridge_rows(&self) -> RgResult<u64> {
+        let mut pool = self.ctx.pool().await?;
+        let row = sqlx::query(DUPLICATE_BRIDGE_ROWS).fetch_one(&mut *pool).await;
+        let row = DataStoreContext::map_err_sqlx(row)?;
+        let count: i64 = row.try_get("count").error_info("count not found")?;
+        Ok(count as u64)
+    }
+
+    /// Deletes all but the first row recorded for each txid and direction.
+    pub async fn remove_duplicate_bridge_rows(&self) -> RgResult<u64> {
+        let mut pool = self.ctx.pool().await?;
+        let rows = sqlx::query(REMOVE_DUPLICATE_BRIDGE_ROWS).execute(&mut *pool).await;
+        let rows = DataStoreContext::map_err_sqlx(rows)?;
+        Ok(rows.rows_affected())
     }
 
     //
@@ -0,0 +1,127 @@
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+use sqlx::sqlite::SqliteRow;
+use redgold_schema::name_registry::NAME_RENEWAL_GRACE_MS;
+use redgold_schema::structs::{Address, Hash, NameRegistration};
+use redgold_schema::{ErrorInfoContext, ProtoSerde, RgResult, SafeBytesAccess};
+use crate::DataStoreContext;
+use crate::schema::SafeOption;
+
+/// Current binding of a registered name, from the latest accepted registration of it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NameRecord {
+    pub name: String,
+    pub address: Address,
+    pub owner: Address,
+    pub expiry_time: i64,
+    pub transaction_hash: Hash,
+    pub time: i64,
+}
+
+impl NameRecord {
+    pub fn active(&self, now: i64) -> bool {
+        now < self.expiry_time
+    }
+
+    fn from_row(row: &SqliteRow) -> RgResult<Self> {
+        let address: Vec<u8> = row.try_get("address").error_info("address not found")?;
+        let owner: Vec<u8> = row.try_get("owner").error_info("owner not found")?;
+        let transaction_hash: Vec<u8> = row.try_get("transaction_hash").error_info("transaction_hash not found")?;
+        Ok(Self {
+            name: row.try_get("name").error_info("name not found")?,
+            address: Address::proto_deserialize(address)?,
+            owner: Address::proto_deserialize(owner)?,
+            expiry_time: row.try_get("expiry_time").error_info("expiry_time not found")?,
+            transaction_hash: Hash::new(transaction_hash),
+            time: row.try_get("time").error_info("time not found")?,
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct NameStore {
+    pub ctx: DataStoreContext
+}
+
+impl NameStore {
+
+    /// Records an accepted registration, ignoring it if a later registration of the name is
+    /// already stored, as happens when history is downloaded out of order, or if the name is
+    /// still held by another owner.
+    pub async fn insert_registration(
+        &self,
+        registration: &NameRegistration,
+        owner: &Address,
+        transaction_hash: &Hash,
+        time: i64
+    ) -> RgResult<()> {
+        let address = registration.address.safe_get_msg("Missing name registration address")?.proto_serialize();
+        let mut pool = self.ctx.pool().await?;
+        let rows = sqlx::query(r#"
+            INSERT INTO name_registry (name, address, owner, expiry_time, transaction_hash, time)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            ON CONFLICT (name) DO UPDATE SET
+                address = excluded.address,
+                owner = excluded.owner,
+                expiry_time = excluded.expiry_time,
+                transaction_hash = excluded.transaction_hash,
+                time = excluded.time
+            WHERE excluded.time >= name_registry.time
+                AND (excluded.owner = name_registry.owner OR excluded.time >= name_registry.expiry_time + ?7)"#)
+            .bind(registration.name.clone())
+            .bind(address)
+            .bind(owner.proto_serialize())
+            .bind(registration.expiry_time)
+            .bind(transaction_hash.safe_bytes()?)
+            .bind(time)
+            .bind(NAME_RENEWAL_GRACE_MS)
+            .execute(&mut *pool).await;
+        DataStoreContext::map_err_sqlx(rows)?;
+        Ok(())
+    }
+
+    pub async fn query_name(&self, name: &str) -> RgResult<Option<NameRecord>> {
+        let mut pool = self.ctx.pool().await?;
+        let row = sqlx::query("SELECT * FROM name_registry WHERE name = ?1")
+            .bind(name.to_string())
+            .fetch_optional(&mut *pool).await;
+        let row = DataStoreContext::map_err_sqlx(row)?;
+        row.as_ref().map(NameRecord::from_row).transpose()
+    }
+
+    /// Names currently or previously bound to the address, for reverse lookups.
+    pub async fn names_for_address(&self, address: &Address) -> RgResult<Vec<NameRecord>> {
+        let mut pool = self.ctx.pool().await?;
+        let rows = sqlx::query("SELECT * FROM name_registry WHERE address = ?1 ORDER BY name")
+            .bind(address.proto_serialize())
+            .fetch_all(&mut *pool).await;
+        let rows = DataStoreContext::map_err_sqlx(rows)?;
+        rows.iter().map(NameRecord::from_row).collect()
+    }
+}
+
+#[tokio::test]
+async fn held_name_keeps_its_owner() {
+    use crate::data_store::DataStore;
+    let path = std::env::temp_dir().join(format!("redgold_name_owner_{}.sqlite", std::process::id()));
+    std::fs::remove_file(&path).ok();
+    let ds = DataStore::from_file_path(path.to_string_lossy().to_string()).await;
+    ds.run_migrations().await.expect("migrations");
+    let owner = Address::script_hash("owner").expect("address");
+    let other = Address::script_hash("other").expect("address");
+    let registration = |address: &Address, expiry_time: i64| NameRegistration {
+        name: "alice".to_string(),
+        address: Some(address.clone()),
+        expiry_time,
+    };
+    let hash = Hash::from_string_calculate("tx");
+    ds.name_store.insert_registration(&registration(&owner, 1000), &owner, &hash, 10).await.expect("insert");
+    // A later registration by another owner while the name is held doesn't replace it
+    ds.name_store.insert_registration(&registration(&other, 2000), &other, &hash, 20).await.expect("insert");
+    assert_eq!(ds.name_store.query_name("alice").await.expect("query").expect("record").owner, owner);
+    // Once lapsed past the grace period anyone can take it
+    let lapsed = 1000 + NAME_RENEWAL_GRACE_MS;
+    ds.name_store.insert_registration(&registration(&other, lapsed + 10), &other, &hash, lapsed).await.expect("insert");
+    assert_eq!(ds.name_store.query_name("alice").await.expect("query").expect("record").owner, other);
+    std::fs::remove_file(&path).ok();
+}
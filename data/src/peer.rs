@@ -21,6 +21,26 @@ pub struct PeerTrustQueryResult {
     pub trust: f64,
 }
 
+/// Availability checks recorded against a peer node, used for seed quality scoring
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct PeerUptime {
+    pub public_key: String,
+    pub checks: i64,
+    pub successes: i64,
+    pub last_check: i64,
+    pub last_success: Option<i64>,
+}
+
+impl PeerUptime {
+    pub fn uptime_ratio(&self) -> f64 {
+        if self.checks == 0 {
+            0.0
+        } else {
+            self.successes as f64 / self.checks as f64
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct PeerIdNode {
     pub peer_id: PeerId,
@@ -158,6 +178,19 @@ impl PeerStore {
         Ok(())
     }
 
+    pub async fn node_last_seen(&self, node: &PublicKey) -> RgResult<Option<i64>> {
+        let mut pool = self.ctx.pool().await?;
+        let bytes = node.bytes.safe_bytes()?;
+        let rows = sqlx::query!(
+            r#"SELECT last_seen FROM nodes WHERE public_key = ?1"#,
+            bytes
+        )
+            .fetch_optional(&mut *pool)
+            .await;
+        let row = DataStoreContext::map_err_sqlx(rows)?;
+        Ok(row.and_then(|r| r.last_seen))
+    }
+
     // TODO: Add node transaction as well
     // pub async fn add_peer(&self, tx: &Transaction, trust: f64) -> Result<(), ErrorInfo> {
     //     // return Err(ErrorInfo::error_info("debug error return"));
@@ -403,6 +436,57 @@ impl PeerStore {
     }
 
 
+    pub async fn record_peer_check(&self, node: &PublicKey, success: bool, time: i64) -> RgResult<()> {
+        let mut pool = self.ctx.pool().await?;
+        let bytes = node.bytes.safe_bytes()?;
+        let success_count = if success { 1 } else { 0 };
+        let last_success = if success { Some(time) } else { None };
+        let rows = sqlx::query!(
+            r#"INSERT INTO peer_uptime (public_key, checks, successes, last_check, last_success)
+            VALUES (?1, 1, ?2, ?3, ?4)
+            ON CONFLICT(public_key) DO UPDATE SET
+            checks = checks + 1,
+            successes = successes + ?2,
+            last_check = ?3,
+            last_success = COALESCE(?4, last_success)"#,
+            bytes,
+            success_count,
+            time,
+            last_success
+        )
+            .execute(&mut *pool)
+            .await;
+        DataStoreContext::map_err_sqlx(rows)?;
+        Ok(())
+    }
+
+    pub async fn peer_uptime(&self) -> RgResult<Vec<PeerUptime>> {
+        let mut pool = self.ctx.pool().await?;
+        let rows = sqlx::query!(
+            r#"SELECT public_key, checks, successes, last_check, last_success FROM peer_uptime"#
+        )
+            .fetch_all(&mut *pool)
+            .await;
+        let rows_m = DataStoreContext::map_err_sqlx(rows)?;
+        Ok(rows_m.into_iter().map(|r| PeerUptime {
+            public_key: PublicKey::from_bytes(r.public_key).hex_or(),
+            checks: r.checks,
+            successes: r.successes,
+            last_check: r.last_check,
+            last_success: r.last_success,
+        }).collect_vec())
+    }
+
+    pub async fn remove_peer_uptime(&self, node: &PublicKey) -> RgResult<()> {
+        let mut pool = self.ctx.pool().await?;
+        let bytes = node.bytes.safe_bytes()?;
+        let rows = sqlx::query!("DELETE FROM peer_uptime WHERE public_key = ?1", bytes)
+            .execute(&mut *pool)
+            .await;
+        DataStoreContext::map_err_sqlx(rows)?;
+        Ok(())
+    }
+
     pub async fn remove_node(&self, p0: &PublicKey) -> RgResult<()> {
         let pid = self.peer_id_for_node_pk(p0).await?;
         if let Some(p) = pid {
@@ -0,0 +1,209 @@
+use std::path::PathBuf;
+use sqlx::Row;
+use redgold_schema::{ErrorInfoContext, RgResult, SafeBytesAccess};
+use redgold_schema::structs::Address;
+use crate::data_store::DataStore;
+use crate::DataStoreContext;
+
+/// Rows removed by a single pruning pass, along with the datastore file size around it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PruneSummary {
+    pub transactions: u64,
+    pub address_transactions: u64,
+    pub transaction_edges: u64,
+    pub transaction_conflicts: u64,
+    pub observation_edges: u64,
+    pub observations: u64,
+    pub balance_snapshots: u64,
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+}
+
+impl PruneSummary {
+    pub fn total_rows(&self) -> u64 {
+        self.transactions + self.address_transactions + self.transaction_edges +
+            self.transaction_conflicts + self.observation_edges + self.observations + self.balance_snapshots
+    }
+
+    pub fn reclaimed_bytes(&self) -> u64 {
+        self.bytes_before.saturating_sub(self.bytes_after)
+    }
+
+    pub fn add(&mut self, other: &PruneSummary) {
+        self.transactions += other.transactions;
+        self.address_transactions += other.address_transactions;
+        self.transaction_edges += other.transaction_edges;
+        self.transaction_conflicts += other.transaction_conflicts;
+        self.observation_edges += other.observation_edges;
+        self.observations += other.observations;
+        self.balance_snapshots += other.balance_snapshots;
+        self.bytes_after = other.bytes_after;
+    }
+}
+
+// Statements run in order, later ones rely on the rows removed by earlier ones. Transactions
+// still holding unspent outputs, referenced by bridge records or touching a protected address
+// are never removed, nor is any observation edge proving a retained transaction, nor each
+// node's latest observation. Protected addresses are bound as a JSON array of upper case hex.
+// Transactions timed at or after ?3 make up retained state checkpoints and are kept as well.
+const PRUNE_TRANSACTIONS: &str = r#"DELETE FROM transactions WHERE time < ?1
+    AND COALESCE(transaction_time, time) < ?3
+    AND hash NOT IN (SELECT transaction_hash FROM utxo)
+    AND hash NOT IN (SELECT txid FROM multiparty_bridge WHERE txid IS NOT NULL)
+    AND hash NOT IN (SELECT secondary_txid FROM multiparty_bridge WHERE secondary_txid IS NOT NULL)
+    AND hash NOT IN (SELECT tx_hash FROM address_transaction
+        WHERE hex(address) IN (SELECT value FROM json_each(?2)))"#;
+const PRUNE_ADDRESS_TRANSACTIONS: &str = r#"DELETE FROM address_transaction WHERE time < ?1
+    AND tx_hash NOT IN (SELECT hash FROM transactions)"#;
+const PRUNE_TRANSACTION_EDGES: &str = r#"DELETE FROM transaction_edge WHERE time < ?1
+    AND transaction_hash NOT IN (SELECT hash FROM transactions)"#;
+const PRUNE_TRANSACTION_CONFLICTS: &str = r#"DELETE FROM transaction_conflict WHERE time < ?1"#;
+const PRUNE_OBSERVATION_EDGES: &str = r#"DELETE FROM observation_edge WHERE time < ?1
+    AND observed_hash NOT IN (SELECT hash FROM transactions)"#;
+const PRUNE_OBSERVATIONS: &str = r#"DELETE FROM observation WHERE time < ?1
+    AND hash NOT IN (SELECT observation_hash FROM observation_edge)
+    AND height < (SELECT MAX(o.height) FROM observation o WHERE o.public_key = observation.public_key)"#;
+const PRUNE_BALANCE_SNAPSHOTS: &str = r#"DELETE FROM address_balance_snapshot WHERE time < ?1"#;
+
+impl DataStore {
+
+    async fn prune_statement(&self, statement: &str, cutoff_time: i64) -> RgResult<u64> {
+        let mut pool = self.ctx.pool().await?;
+        let rows = sqlx::query(statement).bind(cutoff_time).execute(&mut *pool).await;
+        let rows_m = DataStoreContext::map_err_sqlx(rows)?;
+        Ok(rows_m.rows_affected())
+    }
+
+    async fn prune_transactions(&self, cutoff_time: i64, protected: &Vec<Address>, checkpointed_since: Option<i64>) -> RgResult<u64> {
+        let hexes = protected.iter()
+            .map(|a| a.address.safe_bytes().map(hex::encode_upper))
+            .collect::<RgResult<Vec<String>>>()?;
+        let protected_json = serde_json::to_string(&hexes).error_info("protected addresses json")?;
+        let mut pool = self.ctx.pool().await?;
+        let rows = sqlx::query(PRUNE_TRANSACTIONS).bind(cutoff_time).bind(protected_json)
+            .bind(checkpointed_since.unwrap_or(i64::MAX))
+            .execute(&mut *pool).await;
+        let rows_m = DataStoreContext::map_err_sqlx(rows)?;
+        Ok(rows_m.rows_affected())
+    }
+
+    /// Removes historical data older than the cutoff which isn't needed to validate or spend
+    /// current outputs, then releases the freed pages. The full history of protected addresses
+    /// is kept, as the AMM watcher replays it to find unfulfilled orders, as are transactions
+    /// timed from `checkpointed_since` on, which state checkpoints and proofs are rebuilt from.
+    pub async fn prune_before(
+        &self, cutoff_time: i64, protected: &Vec<Address>, checkpointed_since: Option<i64>
+    ) -> RgResult<PruneSummary> {
+        let mut summary = PruneSummary::default();
+        summary.bytes_before = self.file_size_bytes()?;
+        summary.transactions = self.prune_transactions(cutoff_time, protected, checkpointed_since).await?;
+        summary.address_transactions = self.prune_statement(PRUNE_ADDRESS_TRANSACTIONS, cutoff_time).await?;
+        summary.transaction_edges = self.prune_statement(PRUNE_TRANSACTION_EDGES, cutoff_time).await?;
+        summary.transaction_conflicts = self.prune_statement(PRUNE_TRANSACTION_CONFLICTS, cutoff_time).await?;
+        summary.observation_edges = self.prune_statement(PRUNE_OBSERVATION_EDGES, cutoff_time).await?;
+        summary.observations = self.prune_statement(PRUNE_OBSERVATIONS, cutoff_time).await?;
+        summary.balance_snapshots = self.prune_statement(PRUNE_BALANCE_SNAPSHOTS, cutoff_time).await?;
+        if summary.total_rows() > 0 {
+            self.incremental_vacuum().await?;
+        }
+        summary.bytes_after = self.file_size_bytes()?;
+        Ok(summary)
+    }
+
+    /// Releases free pages back to the filesystem without rewriting the database, a no-op for
+    /// files created before incremental auto vacuum was enabled until their next full vacuum.
+    pub async fn incremental_vacuum(&self) -> RgResult<()> {
+        let mut pool = self.ctx.pool().await?;
+        DataStoreContext::map_err_sqlx(sqlx::query("PRAGMA incremental_vacuum").execute(&mut *pool).await)?;
+        DataStoreContext::map_err_sqlx(
+            sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)").execute(&mut *pool).await
+        )?;
+        Ok(())
+    }
+
+    /// Rewrites the whole database to defragment it, switching it to incremental auto vacuum.
+    /// Holds the database for as long as the rewrite takes, so it's only run on request.
+    pub async fn vacuum(&self) -> RgResult<()> {
+        let mut pool = self.ctx.pool().await?;
+        DataStoreContext::map_err_sqlx(sqlx::query("PRAGMA auto_vacuum = INCREMENTAL").execute(&mut *pool).await)?;
+        DataStoreContext::map_err_sqlx(sqlx::query("VACUUM").execute(&mut *pool).await)?;
+        DataStoreContext::map_err_sqlx(
+            sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)").execute(&mut *pool).await
+        )?;
+        Ok(())
+    }
+
+    /// Oldest stored transaction time, used as the starting point for size based pruning.
+    pub async fn oldest_transaction_time(&self) -> RgResult<Option<i64>> {
+        let mut pool = self.ctx.pool().await?;
+        let row = sqlx::query("SELECT MIN(time) as min_time FROM transactions")
+            .fetch_one(&mut *pool).await;
+        let row = DataStoreContext::map_err_sqlx(row)?;
+        let min: Option<i64> = row.try_get("min_time").error_info("min_time not found")?;
+        Ok(min)
+    }
+
+    pub fn file_path(&self) -> Option<PathBuf> {
+        let path = self.connection_path.trim_start_matches("file:");
+        if path.contains("mode=memory") {
+            return None;
+        }
+        Some(PathBuf::from(path.split('?').next().unwrap_or(path)))
+    }
+
+    /// Size on disk of the database file including its write ahead log, 0 for in memory stores.
+    pub fn file_size_bytes(&self) -> RgResult<u64> {
+        let path = match self.file_path() {
+            Some(p) => p,
+            None => return Ok(0),
+        };
+        let mut total = 0;
+        for p in [path.clone(), PathBuf::from(format!("{}-wal", path.to_string_lossy()))] {
+            if let Ok(m) = std::fs::metadata(&p) {
+                total += m.len();
+            }
+        }
+        Ok(total)
+    }
+}
+
+#[test]
+fn prune_summary_totals() {
+    let mut summary = PruneSummary { transactions: 2, observations: 3, bytes_before: 100, bytes_after: 80, ..Default::default() };
+    assert_eq!(summary.total_rows(), 5);
+    assert_eq!(summary.reclaimed_bytes(), 20);
+    summary.add(&PruneSummary { observation_edges: 4, bytes_before: 80, bytes_after: 50, ..Default::default() });
+    assert_eq!(summary.total_rows(), 9);
+    assert_eq!(summary.reclaimed_bytes(), 50);
+}
+
+#[tokio::test]
+async fn prune_keeps_protected_address_history() {
+    let path = std::env::temp_dir().join(format!("redgold_prune_protected_{}.sqlite", std::process::id()));
+    std::fs::remove_file(&path).ok();
+    let ds = DataStore::from_file_path(path.to_string_lossy().to_string()).await;
+    ds.run_migrations().await.expect("migrations");
+    let mut party = Address::default();
+    party.address = redgold_schema::bytes_data(vec![1u8; 32]);
+    let mut other = Address::default();
+    other.address = redgold_schema::bytes_data(vec![2u8; 32]);
+    let party_tx = vec![11u8; 32];
+    for (address, hash) in [(&party, party_tx.clone()), (&other, vec![12u8; 32])] {
+        let mut pool = ds.ctx.pool().await.expect("pool");
+        sqlx::query("INSERT INTO transactions (hash, raw, time, accepted) VALUES (?1, ?2, 10, 1)")
+            .bind(hash.clone()).bind(vec![0u8]).execute(&mut *pool).await.expect("tx insert");
+        sqlx::query("INSERT INTO address_transaction (address, tx_hash, time, incoming) VALUES (?1, ?2, 10, 1)")
+            .bind(address.address.safe_bytes().expect("bytes")).bind(hash)
+            .execute(&mut *pool).await.expect("address insert");
+    }
+    let summary = ds.prune_before(100, &vec![party.clone()], None).await.expect("prune");
+    assert_eq!(summary.transactions, 1);
+    assert_eq!(summary.address_transactions, 1);
+    let mut pool = ds.ctx.pool().await.expect("pool");
+    let remaining: Vec<Vec<u8>> = sqlx::query_scalar("SELECT hash FROM transactions")
+        .fetch_all(&mut *pool).await.expect("remaining");
+    assert_eq!(remaining, vec![party_tx.clone()]);
+    let summary = ds.prune_before(100, &vec![], Some(5)).await.expect("prune");
+    assert_eq!(summary.transactions, 0);
+    std::fs::remove_file(&path).ok();
+}
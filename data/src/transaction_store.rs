@@ -2,11 +2,21 @@ use std::collections::HashSet;
 use itertools::Itertools;
 use metrics::gauge;
 use redgold_keys::TestConstants;
-use redgold_schema::structs::{Address, ErrorInfo, UtxoId, Hash, Output, Transaction, TransactionEntry, UtxoEntry};
-use redgold_schema::{from_hex, ProtoHashable, ProtoSerde, RgResult, SafeBytesAccess, structs, WithMetadataHashable};
+use redgold_schema::structs::{Address, ErrorInfo, UtxoId, Hash, Output, Transaction, TransactionConflict, TransactionEntry, UtxoEntry};
+use redgold_schema::{ErrorInfoContext, from_hex, ProtoHashable, ProtoSerde, RgResult, SafeBytesAccess, structs, WithMetadataHashable};
+use sqlx::Row;
+use crate::name_store::NameStore;
 use crate::DataStoreContext;
 use crate::schema::SafeOption;
 
+/// Balance of an address recorded once per day, used for charting balance history
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct BalanceSnapshot {
+    pub day: i64,
+    pub time: i64,
+    pub balance: i64,
+}
+
 #[derive(Clone)]
 pub struct TransactionStore {
     pub ctx: DataStoreContext
@@ -96,6 +106,32 @@ impl TransactionStore {
         Ok(option)
     }
 
+    /// Time the transaction was accepted locally, which is what checkpoint segments are keyed by.
+    pub async fn accepted_transaction_time(&self, transaction_hash: &Hash) -> RgResult<Option<i64>> {
+        let bytes = transaction_hash.safe_bytes()?;
+        let row = sqlx::query(
+            "SELECT time FROM transactions WHERE hash = ?1 AND rejection_reason IS NULL AND accepted = 1"
+        ).bind(bytes).fetch_optional(&mut *self.ctx.pool().await?).await;
+        let row = DataStoreContext::map_err_sqlx(row)?;
+        match row {
+            Some(r) => Ok(Some(r.try_get("time").error_info("time not found")?)),
+            None => Ok(None),
+        }
+    }
+
+    /// The accepted transaction's own time, as used to segment checkpoints.
+    pub async fn accepted_transaction_own_time(&self, transaction_hash: &Hash) -> RgResult<Option<i64>> {
+        let bytes = transaction_hash.safe_bytes()?;
+        let row = sqlx::query(
+            "SELECT transaction_time FROM transactions WHERE hash = ?1 AND rejection_reason IS NULL AND accepted = 1"
+        ).bind(bytes).fetch_optional(&mut *self.ctx.pool().await?).await;
+        let row = DataStoreContext::map_err_sqlx(row)?;
+        match row {
+            Some(r) => Ok(r.try_get("transaction_time").error_info("transaction_time not found")?),
+            None => Ok(None),
+        }
+    }
+
     // #[tracing::instrument()]
     pub async fn query_recent_transactions(
         &self, limit: Option<i64>,
@@ -144,6 +180,23 @@ impl TransactionStore {
             .collect_vec())
     }
 
+    /// Accepted transaction hashes with their own time in [start, end) in a deterministic order
+    pub async fn accepted_transaction_hashes_between(
+        &self,
+        start: i64,
+        end: i64
+    ) -> RgResult<Vec<Hash>> {
+        Ok(DataStoreContext::map_err_sqlx(sqlx::query!(
+            r#"SELECT hash FROM transactions
+            WHERE rejection_reason IS NULL AND accepted = 1 AND transaction_time >= ?1 AND transaction_time < ?2
+            ORDER BY hash"#,
+            start,
+            end
+        ).fetch_all(&mut *self.ctx.pool().await?).await)?.iter()
+            .map(|t| Hash::new(t.hash.clone()))
+            .collect_vec())
+    }
+
     pub async fn count_total_accepted_transactions(
         &self
     ) -> Result<i64, ErrorInfo> {
@@ -474,6 +527,118 @@ impl TransactionStore {
             .map(|o| (Hash::new(o.child_transaction_hash), o.child_input_index)))
     }
 
+    pub async fn insert_conflict(
+        &self,
+        conflict: &TransactionConflict
+    ) -> RgResult<i64> {
+        let utxo_id = conflict.utxo_id.safe_get_msg("Missing conflict utxo_id")?;
+        let utxo_hash = utxo_id.transaction_hash.safe_bytes()?;
+        let output_index = utxo_id.output_index;
+        let hash = conflict.transaction_hash.safe_bytes()?;
+        let conflicting_hash = conflict.conflicting_transaction_hash.safe_bytes()?;
+        let time = conflict.time;
+        let rows = DataStoreContext::map_err_sqlx(sqlx::query!(
+            r#"
+        INSERT OR IGNORE INTO transaction_conflict
+        (utxo_transaction_hash, utxo_output_index, transaction_hash, conflicting_transaction_hash, time)
+        VALUES (?1, ?2, ?3, ?4, ?5)"#,
+            utxo_hash,
+            output_index,
+            hash,
+            conflicting_hash,
+            time
+        )
+            .execute(&mut *self.ctx.pool().await?)
+            .await)?;
+        Ok(rows.last_insert_rowid())
+    }
+
+    /// All conflicts where the hash is either competing transaction or the parent of the contested utxo
+    pub async fn query_conflicts(
+        &self,
+        hash: &Hash
+    ) -> RgResult<Vec<TransactionConflict>> {
+        let bytes = hash.safe_bytes()?;
+        let rows = DataStoreContext::map_err_sqlx(sqlx::query!(
+            r#"SELECT utxo_transaction_hash, utxo_output_index, transaction_hash, conflicting_transaction_hash, time
+            FROM transaction_conflict
+            WHERE transaction_hash = ?1 OR conflicting_transaction_hash = ?1 OR utxo_transaction_hash = ?1
+            ORDER BY time DESC"#,
+            bytes
+        )
+            .fetch_all(&mut *self.ctx.pool().await?)
+            .await)?;
+        Ok(rows.into_iter().map(|r| TransactionConflict {
+            utxo_id: Some(UtxoId {
+                transaction_hash: Some(Hash::new(r.utxo_transaction_hash)),
+                output_index: r.utxo_output_index,
+            }),
+            transaction_hash: Some(Hash::new(r.transaction_hash)),
+            conflicting_transaction_hash: Some(Hash::new(r.conflicting_transaction_hash)),
+            time: r.time,
+        }).collect_vec())
+    }
+
+
+    /// Addresses appearing in any transaction which don't yet have a snapshot for the given day
+    pub async fn addresses_missing_balance_snapshot(
+        &self,
+        day: i64,
+        limit: i64
+    ) -> RgResult<Vec<Address>> {
+        let rows = DataStoreContext::map_err_sqlx(sqlx::query!(
+            r#"SELECT DISTINCT address FROM address_transaction
+            WHERE address NOT IN (SELECT address FROM address_balance_snapshot WHERE day = ?1)
+            LIMIT ?2"#,
+            day,
+            limit
+        )
+            .fetch_all(&mut *self.ctx.pool().await?)
+            .await)?;
+        Ok(rows.into_iter().map(|r| Address::new_raw(r.address)).collect_vec())
+    }
+
+    pub async fn insert_balance_snapshot(
+        &self,
+        address: &Address,
+        snapshot: &BalanceSnapshot
+    ) -> RgResult<i64> {
+        let bytes = address.address.safe_bytes()?;
+        let rows = DataStoreContext::map_err_sqlx(sqlx::query!(
+            r#"
+        INSERT OR REPLACE INTO address_balance_snapshot
+        (address, day, balance, time) VALUES (?1, ?2, ?3, ?4)"#,
+            bytes,
+            snapshot.day,
+            snapshot.balance,
+            snapshot.time
+        )
+            .execute(&mut *self.ctx.pool().await?)
+            .await)?;
+        Ok(rows.last_insert_rowid())
+    }
+
+    /// Most recent snapshots for an address, returned in ascending day order
+    pub async fn query_balance_snapshots(
+        &self,
+        address: &Address,
+        limit: i64
+    ) -> RgResult<Vec<BalanceSnapshot>> {
+        let bytes = address.address.safe_bytes()?;
+        let rows = DataStoreContext::map_err_sqlx(sqlx::query!(
+            r#"SELECT day, balance, time FROM address_balance_snapshot
+            WHERE address = ?1 ORDER BY day DESC LIMIT ?2"#,
+            bytes,
+            limit
+        )
+            .fetch_all(&mut *self.ctx.pool().await?)
+            .await)?;
+        Ok(rows.into_iter().rev().map(|r| BalanceSnapshot {
+            day: r.day,
+            time: r.time,
+            balance: r.balance,
+        }).collect_vec())
+    }
 
     pub async fn insert_transaction_raw(
         &self,
@@ -487,12 +652,14 @@ impl TransactionStore {
         let is_test = tx.is_test();
         let hash_vec = tx.hash_bytes()?;
         let ser = tx.proto_serialize();
+        // The transaction's own time, which unlike the local acceptance time is the same on every node
+        let transaction_time = tx.time()?.clone();
 
         let rows = sqlx::query!(
             r#"
         INSERT OR REPLACE INTO transactions
-        (hash, raw, time, rejection_reason, accepted, is_test) VALUES (?1, ?2, ?3, ?4, ?5, ?6)"#,
-           hash_vec, ser, time, rejection_ser, accepted, is_test
+        (hash, raw, time, rejection_reason, accepted, is_test, transaction_time) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)"#,
+           hash_vec, ser, time, rejection_ser, accepted, is_test, transaction_time
         )
             .execute(&mut *pool)
             .await;
@@ -641,10 +808,27 @@ impl TransactionStore {
             }
         }
         self.insert_transaction_indexes(&tx, time).await?;
+        if accepted {
+            self.insert_name_registrations(tx, time).await?;
+        }
         gauge!("redgold.transaction.accepted.total").increment(1.0);
         return Ok(i);
     }
 
+    async fn insert_name_registrations(&self, tx: &Transaction, time: i64) -> RgResult<()> {
+        let registrations = tx.name_registrations();
+        if registrations.is_empty() {
+            return Ok(());
+        }
+        let owner = tx.name_owner()?;
+        // Ordered by acceptance time, the transaction's own time is chosen by the client
+        let names = NameStore { ctx: self.ctx.clone() };
+        for r in registrations {
+            names.insert_registration(r, &owner, &tx.hash_or(), time).await?;
+        }
+        Ok(())
+    }
+
     async fn insert_transaction_indexes(&self, tx: &&Transaction, time: i64) -> Result<(), ErrorInfo> {
         for (i, x) in tx.inputs.iter().enumerate() {
             if let Some(utxo) = &x.utxo_id {
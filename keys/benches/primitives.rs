@@ -0,0 +1,87 @@
+use bdk::bitcoin::hashes::Hash as BitcoinHash;
+use bdk::bitcoin::util::psbt::PartiallySignedTransaction;
+use bdk::bitcoin::{OutPoint, PackedLockTime, Script, Sequence, Transaction, TxIn, TxOut, Txid, Witness};
+use criterion::{black_box, BenchmarkId, Criterion, criterion_group, criterion_main};
+use redgold_keys::proof_support::ProofSupport;
+use redgold_keys::TestConstants;
+use redgold_keys::util::btc_wallet::psbt_signable_hashes;
+use redgold_keys::util::dhash_vec;
+use redgold_schema::structs::{Hash, Proof};
+
+fn address_derivation(c: &mut Criterion) {
+    let tc = TestConstants::new();
+    let mut group = c.benchmark_group("address_derivation");
+    group.bench_function("public_at", |b| {
+        b.iter(|| tc.words_pass.public_at(black_box("m/44'/16180'/0'/0/0")).expect("public key"))
+    });
+    group.bench_function("public_at_address", |b| {
+        b.iter(|| tc.words_pass.public_at(black_box("m/44'/16180'/0'/0/0")).expect("public key")
+            .address().expect("address"))
+    });
+    group.finish();
+}
+
+fn hashing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hashing");
+    for size in [32, 1024, 10_000] {
+        let data = vec![7u8; size];
+        group.bench_with_input(BenchmarkId::new("hash_digest", size), &data, |b, data| {
+            b.iter(|| Hash::digest(data.clone()))
+        });
+        group.bench_with_input(BenchmarkId::new("dhash", size), &data, |b, data| {
+            b.iter(|| dhash_vec(data))
+        });
+    }
+    group.finish();
+}
+
+fn proofs(c: &mut Criterion) {
+    let tc = TestConstants::new();
+    let hash = Hash::from_string_calculate("proof benchmark");
+    let proof = Proof::new(&hash, &tc.secret, &tc.public);
+    let mut group = c.benchmark_group("proof");
+    group.bench_function("create", |b| {
+        b.iter(|| Proof::new(black_box(&hash), &tc.secret, &tc.public))
+    });
+    group.bench_function("verify", |b| {
+        b.iter(|| proof.verify(black_box(&hash)).expect("valid"))
+    });
+    group.finish();
+}
+
+// Unsigned psbt spending fake p2wpkh outputs, enough for sighash extraction without a wallet backend
+fn test_psbt(inputs: usize) -> PartiallySignedTransaction {
+    let tc = TestConstants::new();
+    let wpkh = bdk::bitcoin::PublicKey::new(tc.public).wpubkey_hash().expect("compressed key");
+    let script = Script::new_v0_p2wpkh(&wpkh);
+    let tx = Transaction {
+        version: 2,
+        lock_time: PackedLockTime(0),
+        input: (0..inputs).map(|i| TxIn {
+            previous_output: OutPoint { txid: Txid::all_zeros(), vout: i as u32 },
+            script_sig: Script::new(),
+            sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+            witness: Witness::new(),
+        }).collect(),
+        output: vec![TxOut { value: 10_000, script_pubkey: script.clone() }],
+    };
+    let mut psbt = PartiallySignedTransaction::from_unsigned_tx(tx).expect("psbt");
+    for input in psbt.inputs.iter_mut() {
+        input.witness_utxo = Some(TxOut { value: 20_000, script_pubkey: script.clone() });
+    }
+    psbt
+}
+
+fn btc_sighash(c: &mut Criterion) {
+    let mut group = c.benchmark_group("btc_sighash");
+    for inputs in [1, 10, 50] {
+        let psbt = test_psbt(inputs);
+        group.bench_with_input(BenchmarkId::new("psbt_signable_hashes", inputs), &psbt, |b, psbt| {
+            b.iter(|| psbt_signable_hashes(psbt).expect("sighashes"))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, address_derivation, hashing, proofs, btc_sighash);
+criterion_main!(benches);
@@ -0,0 +1,34 @@
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main, Throughput};
+use redgold_keys::batch_verify::BatchVerifier;
+use redgold_keys::proof_support::ProofSupport;
+use redgold_keys::TestConstants;
+use redgold_schema::structs::{Hash, Proof};
+
+fn signed_hashes(n: usize) -> Vec<(Proof, Hash)> {
+    let tc = TestConstants::new();
+    (0..n).map(|i| {
+        let hash = Hash::from_string_calculate(&format!("bench {}", i));
+        (Proof::new(&hash, &tc.secret, &tc.public), hash)
+    }).collect()
+}
+
+fn signature_verification(c: &mut Criterion) {
+    let mut group = c.benchmark_group("signature_verification");
+    let parallel = BatchVerifier::new(0).expect("thread pool");
+    let sequential = BatchVerifier::sequential();
+    for n in [1, 16, 256, 1024] {
+        let signed = signed_hashes(n);
+        let items = signed.iter().map(|(p, h)| (p, h)).collect::<Vec<(&Proof, &Hash)>>();
+        group.throughput(Throughput::Elements(n as u64));
+        group.bench_with_input(BenchmarkId::new("sequential", n), &items, |b, items| {
+            b.iter(|| sequential.verify_all(items).expect("valid"))
+        });
+        group.bench_with_input(BenchmarkId::new("parallel", n), &items, |b, items| {
+            b.iter(|| parallel.verify_all(items).expect("valid"))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, signature_verification);
+criterion_main!(benches);
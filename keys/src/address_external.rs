@@ -1,10 +1,11 @@
 
-use bdk::bitcoin::{Address, Network};
+use bdk::bitcoin::Address;
 use redgold_schema::structs::{ErrorInfo, NetworkEnvironment, PublicKey};
 use bdk::bitcoin::util::key;
 use redgold_schema::{ErrorInfoContext, structs};
 use sha3::{Digest, Keccak256};
 use crate::util::ToPublicKey;
+use crate::util::btc_wallet::bitcoin_network;
 
 pub trait ToBitcoinAddress {
     fn to_bitcoin_address(&self, network: &NetworkEnvironment) -> Result<String, ErrorInfo>;
@@ -19,11 +20,7 @@ impl ToBitcoinAddress for PublicKey {
     fn to_bitcoin_address(&self, network: &NetworkEnvironment) -> Result<String, ErrorInfo> {
 
         let pk = &key::PublicKey::from_slice(&self.bytes()?).error_info("public key conversion")?;
-        let network1 = if network == &NetworkEnvironment::Main {
-            Network::Bitcoin
-        } else {
-            Network::Testnet
-        };
+        let network1 = bitcoin_network(network);
         let address = Address::p2wpkh(pk, network1).error_info("bitcoin address conversion")?;
         Ok(address.to_string())
     }
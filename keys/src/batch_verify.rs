@@ -0,0 +1,102 @@
+use std::sync::Arc;
+use rayon::prelude::*;
+use rayon::ThreadPool;
+use redgold_schema::{ErrorInfoContext, RgResult};
+use redgold_schema::structs::{Hash, Proof, Transaction};
+use crate::proof_support::ProofSupport;
+
+/// Verifies many signatures at once, spread across a dedicated thread pool.
+/// secp256k1 has no batch ECDSA verification, so the speedup comes from parallelism alone.
+#[derive(Clone)]
+pub struct BatchVerifier {
+    // None verifies sequentially on the calling thread
+    pool: Option<Arc<ThreadPool>>,
+}
+
+impl Default for BatchVerifier {
+    fn default() -> Self {
+        Self::sequential()
+    }
+}
+
+impl BatchVerifier {
+    pub fn sequential() -> Self {
+        Self { pool: None }
+    }
+
+    /// Parallelism of 0 uses one thread per available core, 1 disables parallel verification.
+    pub fn new(parallelism: usize) -> RgResult<Self> {
+        if parallelism == 1 {
+            return Ok(Self::sequential());
+        }
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(parallelism)
+            .thread_name(|i| format!("signature-verify-{}", i))
+            .build()
+            .error_info("Failed to build signature verification thread pool")?;
+        Ok(Self { pool: Some(Arc::new(pool)) })
+    }
+
+    pub fn parallelism(&self) -> usize {
+        self.pool.as_ref().map(|p| p.current_num_threads()).unwrap_or(1)
+    }
+
+    /// Result of verifying each proof against its hash, in the same order as given.
+    pub fn verify_each(&self, items: &Vec<(&Proof, &Hash)>) -> Vec<RgResult<()>> {
+        match &self.pool {
+            None => items.iter().map(|(p, h)| p.verify(h)).collect(),
+            Some(pool) => pool.install(|| items.par_iter().map(|(p, h)| p.verify(h)).collect()),
+        }
+    }
+
+    pub fn verify_all(&self, items: &Vec<(&Proof, &Hash)>) -> RgResult<()> {
+        self.verify_each(items).into_iter().collect::<RgResult<Vec<()>>>()?;
+        Ok(())
+    }
+
+    /// Signature checks for every input against the transaction's signable hash, one result per
+    /// input. Like verify_signatures_only, this doesn't check proofs match the spent address.
+    pub fn verify_inputs(&self, tx: &Transaction) -> Vec<RgResult<()>> {
+        let hash = tx.signable_hash();
+        let items = tx.inputs.iter()
+            .enumerate()
+            .flat_map(|(i, input)| input.proof.iter().map(move |p| (i, p)))
+            .collect::<Vec<(usize, &Proof)>>();
+        let results = self.verify_each(&items.iter().map(|(_, p)| (*p, &hash)).collect());
+        let mut per_input: Vec<RgResult<()>> = tx.inputs.iter().map(|_| Ok(())).collect();
+        for ((i, _), r) in items.iter().zip(results) {
+            if let Err(e) = r {
+                if per_input[*i].is_ok() {
+                    per_input[*i] = Err(e);
+                }
+            }
+        }
+        per_input
+    }
+
+    pub fn verify_transaction(&self, tx: &Transaction) -> RgResult<()> {
+        for (i, r) in self.verify_inputs(tx).into_iter().enumerate() {
+            r.map_err(|mut e| {
+                e.with_detail("input_index", i.to_string());
+                e
+            })?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn batch_verifier_matches_sequential() {
+    let tc = crate::TestConstants::new();
+    let hash = Hash::new(tc.hash_vec.clone());
+    let valid = Proof::new(&hash, &tc.secret, &tc.public);
+    let mut invalid = valid.clone();
+    invalid.public_key = crate::util::public_key_ser(&tc.public2);
+    let items = vec![(&valid, &hash), (&invalid, &hash), (&valid, &hash)];
+    let parallel = BatchVerifier::new(2).expect("pool");
+    assert_eq!(parallel.parallelism(), 2);
+    let results = parallel.verify_each(&items).into_iter().map(|r| r.is_ok()).collect::<Vec<bool>>();
+    assert_eq!(results, vec![true, false, true]);
+    assert!(parallel.verify_all(&items).is_err());
+    assert!(BatchVerifier::sequential().verify_all(&vec![(&valid, &hash)]).is_ok());
+}
@@ -0,0 +1,98 @@
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use redgold_schema::{HashClear, ProtoHashable, ProtoSerde, RgResult, SafeOption, struct_metadata, VERSION};
+use redgold_schema::structs::{Address, Hash, Input, Output, Proof, Transaction, UtxoId};
+use crate::proof_support::ProofSupport;
+use crate::TestConstants;
+
+/// Committed golden vectors, regenerate with
+/// `redgold vectors --output keys/src/resources/canonical_vectors.json` only for intentional changes.
+pub const CANONICAL_VECTORS: &str = include_str!("resources/canonical_vectors.json");
+
+// Fixed so the transaction vector does not depend on the current time
+const VECTOR_TIME: i64 = 1_700_000_000_000;
+
+/// Encoded bytes and hash of a fixed object, any change to either breaks existing hashes.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CanonicalVector {
+    pub name: String,
+    pub schema_version: i64,
+    pub hex: String,
+    pub hash: String,
+}
+
+/// Canonical bytes of a hashable object, the exact preimage used for its hash. Prost encodes
+/// fields in tag order and maps are generated as BTreeMaps, so the encoding is deterministic.
+pub fn canonical_bytes<T: ProtoSerde + HashClear + Clone>(t: &T) -> Vec<u8> {
+    let mut clone = t.clone();
+    clone.hash_clear();
+    clone.proto_serialize()
+}
+
+fn vector(name: &str, bytes: Vec<u8>, hash: Hash) -> CanonicalVector {
+    CanonicalVector {
+        name: name.to_string(),
+        schema_version: VERSION as i64,
+        hex: hex::encode(bytes),
+        hash: hash.hex(),
+    }
+}
+
+pub fn vector_transaction() -> RgResult<Transaction> {
+    let tc = TestConstants::new();
+    let mut tx = Transaction::default();
+    let mut input = Input::default();
+    input.utxo_id = Some(UtxoId {
+        transaction_hash: Some(tc.rhash_1.clone()),
+        output_index: 0,
+    });
+    tx.inputs.push(input);
+    tx.outputs.push(Output::new(&tc.address_1, 1000));
+    tx.struct_metadata = struct_metadata(VECTOR_TIME);
+    let signable = tx.signable_hash();
+    let proof = Proof::from_keypair_hash(&signable, &tc.key_pair());
+    tx.inputs.get_mut(0).safe_get_msg("Missing vector input")?.proof.push(proof);
+    Ok(tx)
+}
+
+/// Deterministic vectors for addresses, proofs and transactions derived from the test keys.
+pub fn generate_vectors() -> RgResult<Vec<CanonicalVector>> {
+    let tc = TestConstants::new();
+    let address: Address = tc.address_1.clone();
+    let proof = Proof::from_keypair_hash(&tc.rhash_1, &tc.key_pair());
+    let tx = vector_transaction()?;
+    let mut unsigned = tx.clone();
+    unsigned.inputs.iter_mut().for_each(|i| i.proof.clear());
+    Ok(vec![
+        vector("address", address.proto_serialize(), Hash::digest(address.proto_serialize())),
+        vector("proof", canonical_bytes(&proof), Hash::digest(canonical_bytes(&proof))),
+        vector("transaction_signable", canonical_bytes(&unsigned), tx.signable_hash()),
+        vector("transaction", canonical_bytes(&tx), tx.calculate_hash()),
+    ])
+}
+
+/// Describes every committed vector which no longer matches the current encoding.
+pub fn mismatched_vectors(expected: &Vec<CanonicalVector>) -> RgResult<Vec<String>> {
+    let current = generate_vectors()?;
+    Ok(expected.iter().filter_map(|e| {
+        match current.iter().find(|c| c.name == e.name) {
+            None => Some(format!("{}: vector no longer generated", e.name)),
+            Some(c) if c.hex != e.hex => Some(format!("{}: encoding changed from {} to {}", e.name, e.hex, c.hex)),
+            Some(c) if c.hash != e.hash => Some(format!("{}: hash changed from {} to {}", e.name, e.hash, c.hash)),
+            Some(_) => None,
+        }
+    }).collect_vec())
+}
+
+#[test]
+fn canonical_vectors_match_fixtures() {
+    use redgold_schema::json_from;
+    let expected = json_from::<Vec<CanonicalVector>>(CANONICAL_VECTORS).expect("fixtures");
+    let mismatched = mismatched_vectors(&expected).expect("vectors");
+    assert!(mismatched.is_empty(), "Hash breaking change, run `redgold vectors` if intended:\n{}", mismatched.join("\n"));
+    // Generation must be stable within a build as well, signatures are RFC6979 deterministic
+    assert_eq!(generate_vectors().expect("vectors"), generate_vectors().expect("vectors"));
+    let tx = vector_transaction().expect("tx");
+    let decoded = Transaction::proto_deserialize(tx.proto_serialize()).expect("decode");
+    assert_eq!(canonical_bytes(&decoded), canonical_bytes(&tx));
+}
@@ -0,0 +1,64 @@
+use bdk::bitcoin::secp256k1::{PublicKey as LibPublicKey, Secp256k1};
+use bdk::bitcoin::util::bip32::{ChainCode, ChildNumber, ExtendedPubKey, Fingerprint};
+use redgold_schema::{ErrorInfoContext, RgResult, structs};
+use redgold_schema::structs::{Hash, NetworkEnvironment};
+use crate::address_external::ToBitcoinAddress;
+use crate::util::btc_wallet::bitcoin_network;
+use crate::util::keys::ToPublicKeyFromLib;
+use crate::xpub_wrapper::XpubWrapper;
+
+// Domain separation so the chain code can't collide with any other hash over the party key
+const DEPOSIT_CHAIN_CODE_PREFIX: &[u8] = b"redgold_deposit_address";
+// Deposit keys are the non hardened children of the party xpub on this chain
+const DEPOSIT_CHAIN: u32 = 0;
+
+/// Extended public key of the party used for deposit addresses, the aggregate party key with a
+/// chain code any node can compute from it.
+pub fn party_deposit_xpub(party_key: &structs::PublicKey, network: &NetworkEnvironment) -> RgResult<ExtendedPubKey> {
+    let public_key = LibPublicKey::from_slice(&party_key.bytes()?).error_info("Invalid party public key")?;
+    let mut preimage = DEPOSIT_CHAIN_CODE_PREFIX.to_vec();
+    preimage.extend(party_key.bytes()?);
+    let chain_code = Hash::digest(preimage).vec();
+    Ok(ExtendedPubKey {
+        network: bitcoin_network(network),
+        depth: 0,
+        parent_fingerprint: Fingerprint::default(),
+        child_number: ChildNumber::from_normal_idx(0).error_info("Invalid child number")?,
+        public_key,
+        chain_code: ChainCode::from(&chain_code[..32]),
+    })
+}
+
+/// Deposit public key at `index` of the party xpub's deposit chain. Anyone can derive and verify
+/// it from public data, only the party holding the aggregate secret can spend from it.
+pub fn derive_deposit_public_key(
+    party_key: &structs::PublicKey, index: u32, network: &NetworkEnvironment
+) -> RgResult<structs::PublicKey> {
+    let path = vec![XpubWrapper::child_num(DEPOSIT_CHAIN as usize)?, XpubWrapper::child_num(index as usize)?];
+    let child = party_deposit_xpub(party_key, network)?
+        .derive_pub(&Secp256k1::verification_only(), &path)
+        .error_info("Failed to derive deposit public key")?;
+    Ok(child.public_key.to_struct_public_key())
+}
+
+pub fn derive_deposit_btc_address(
+    party_key: &structs::PublicKey, index: u32, network: &NetworkEnvironment
+) -> RgResult<String> {
+    derive_deposit_public_key(party_key, index, network)?.to_bitcoin_address(network)
+}
+
+#[test]
+fn deposit_addresses_are_unique_per_index() {
+    use crate::TestConstants;
+    let party = TestConstants::new().key_pair().public_key();
+    let network = NetworkEnvironment::Dev;
+    let a0 = derive_deposit_btc_address(&party, 0, &network).expect("derive");
+    let a1 = derive_deposit_btc_address(&party, 1, &network).expect("derive");
+    assert_ne!(a0, a1);
+    assert_eq!(a0, derive_deposit_btc_address(&party, 0, &network).expect("derive"));
+    assert_ne!(a0, party.to_bitcoin_address(&network).expect("party"));
+    // Matches plain BIP32 public derivation from the serialized xpub
+    let xpub = XpubWrapper::new(party_deposit_xpub(&party, &network).expect("xpub").to_string());
+    assert_eq!(xpub.public_at(DEPOSIT_CHAIN as usize, 1).expect("derive"),
+               derive_deposit_public_key(&party, 1, &network).expect("derive"));
+}
@@ -166,6 +166,8 @@ impl EthHistoricalClient {
                     amount: amount as u64,
                     incoming,
                     currency: SupportedCurrency::Ethereum,
+                    other_output_amounts: vec![],
+                    fee: None,
                 });
             }
         }
@@ -7,6 +7,7 @@ use crate::util::keys::ToPublicKeyFromLib;
 use crate::util::mnemonic_support::WordsPass;
 
 pub mod proof_support;
+pub mod state_proof_support;
 pub mod request_support;
 pub mod transaction_support;
 pub mod util;
@@ -15,6 +16,10 @@ pub mod xpub_wrapper;
 pub mod address_external;
 pub mod eth;
 pub mod address_support;
+pub mod message_signing;
+pub mod batch_verify;
+pub mod canonical;
+pub mod deposit_address;
 
 
 pub struct TestConstants {
@@ -104,6 +109,10 @@ impl KeyPair {
         self.public_key.to_struct_public_key()
     }
 
+    pub fn private_hex(&self) -> String {
+        hex::encode(self.secret_key.secret_bytes().to_vec())
+    }
+
     pub fn from_private_hex(hex: String) -> RgResult<Self> {
         let secret_key = bdk::bitcoin::secp256k1::SecretKey::from_str(&*hex)
             .error_info("Unable to parse private key hex")?;
@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+use redgold_schema::{error_info, json_from, RgResult, SafeOption};
+use redgold_schema::structs::{Hash, Proof};
+use crate::KeyPair;
+use crate::proof_support::ProofSupport;
+
+// Prevents a signed message from ever doubling as a valid signature over a transaction hash
+const MESSAGE_PREFIX: &str = "Redgold Signed Message:\n";
+
+pub fn message_hash(message: &str) -> Hash {
+    Hash::from_string_calculate(&format!("{}{}", MESSAGE_PREFIX, message))
+}
+
+/// Portable proof that the holder of an address's key signed a message, exchanged as JSON.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SignedMessage {
+    pub message: String,
+    pub address: String,
+    pub proof: Proof,
+}
+
+impl SignedMessage {
+
+    pub fn sign(message: impl Into<String>, keypair: &KeyPair) -> RgResult<Self> {
+        let message = message.into();
+        let proof = Proof::from_keypair_hash(&message_hash(&message), keypair);
+        Self::from_proof(message, proof)
+    }
+
+    /// Wraps a proof produced elsewhere, i.e. by a hardware wallet over `message_hash`.
+    pub fn from_proof(message: impl Into<String>, proof: Proof) -> RgResult<Self> {
+        let address = proof.public_key.safe_get_msg("Missing public key on proof")?
+            .address()?.render_string()?;
+        Ok(Self { message: message.into(), address, proof })
+    }
+
+    pub fn parse(json: &str) -> RgResult<Self> {
+        json_from(json.trim())
+    }
+
+    /// Checks the signature and that the signing key belongs to the claimed address.
+    pub fn verify(&self) -> RgResult<()> {
+        self.proof.verify(&message_hash(&self.message))?;
+        let signer = self.proof.public_key.safe_get_msg("Missing public key on proof")?
+            .address()?.render_string()?;
+        if signer != self.address {
+            return Err(error_info(format!("Message signed by {} not claimed address {}", signer, self.address)));
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn sign_and_verify_message() {
+    use crate::TestConstants;
+    let tc = TestConstants::new();
+    let kp = tc.key_pair();
+    let signed = SignedMessage::sign("I own this address", &kp).expect("sign");
+    assert_eq!(signed.address, kp.address_typed().render_string().expect("address"));
+    let parsed = SignedMessage::parse(&serde_json::to_string(&signed).expect("json")).expect("parse");
+    assert!(parsed.verify().is_ok());
+    let mut tampered = parsed.clone();
+    tampered.message = "I own another address".to_string();
+    assert!(tampered.verify().is_err());
+    let mut wrong_address = parsed;
+    wrong_address.address = KeyPair::new(&tc.secret2, &tc.public2).address_typed().render_string().expect("address");
+    assert!(wrong_address.verify().is_err());
+}
@@ -1,7 +1,10 @@
 use bdk::bitcoin::hashes::hex::ToHex;
+use bdk::bitcoin::secp256k1::rand;
+use bdk::bitcoin::secp256k1::rand::RngCore;
 use log::info;
 use redgold_schema::{EasyJson, error_info, ProtoHashable, RgResult, SafeBytesAccess, SafeOption};
 use redgold_schema::errors::EnhanceErrorInfo;
+use redgold_schema::util::current_time_millis;
 use redgold_schema::structs::{AboutNodeResponse, ErrorInfo, NodeMetadata, Proof, PublicKey, Request, Response};
 use crate::{KeyPair, TestConstants};
 use crate::proof_support::ProofSupport;
@@ -15,6 +18,10 @@ pub trait RequestSupport {
 impl RequestSupport for Request {
 
     fn with_auth(mut self, key_pair: &KeyPair) -> Request {
+        let mut nonce = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        self.auth_time = Some(current_time_millis());
+        self.auth_nonce = Some(hex::encode(nonce));
         let hash = self.calculate_hash();
         // println!("with_auth hash: {:?}", hash.hex());
         let proof = Proof::from_keypair_hash(&hash, &key_pair);
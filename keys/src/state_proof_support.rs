@@ -0,0 +1,145 @@
+use itertools::Itertools;
+use redgold_schema::{error_info, RgResult, SafeOption, WithMetadataHashable};
+use redgold_schema::state_proof::{unspent_attestation_hash, verify_merkle_path};
+use redgold_schema::structs::{Hash, ObservationProof, PublicKey, State, UtxoEntry, UtxoStateProof};
+use crate::proof_support::ProofSupport;
+
+// Signer of the observation transaction the proof belongs to, if its signature over that
+// transaction is valid and the transaction's merkle root is the one the proof's path leads to
+fn observation_signer(state_proof: &UtxoStateProof, o: &ObservationProof, root: &Hash) -> Option<PublicKey> {
+    let observation_hash = o.observation_hash.as_ref()?;
+    let obs_tx = state_proof.observation_transactions.iter()
+        .find(|t| t.hash_or().vec() == observation_hash.vec())?;
+    let committed_root = obs_tx.observation().ok()?.merkle_root.as_ref()?;
+    if committed_root.vec() != root.vec() {
+        return None;
+    }
+    let proof = o.proof.as_ref()?;
+    let signer = proof.public_key.as_ref()?;
+    if obs_tx.observation_public_key().ok()? != signer || proof.verify(&obs_tx.signable_hash()).is_err() {
+        return None;
+    }
+    Some(signer.clone())
+}
+
+pub trait StateProofSupport {
+    fn checkpoint_signers(&self) -> RgResult<Vec<PublicKey>>;
+    fn observation_attestors(&self) -> RgResult<Vec<PublicKey>>;
+    fn verify(&self, min_signers: usize) -> RgResult<UtxoEntry>;
+}
+
+impl StateProofSupport for UtxoStateProof {
+
+    /// Keys with a valid signature over the checkpoint, the serving node's first.
+    fn checkpoint_signers(&self) -> RgResult<Vec<PublicKey>> {
+        let cp = self.checkpoint.safe_get_msg("Missing checkpoint")?;
+        let hash = cp.signing_hash()?;
+        let proof = cp.proof.safe_get_msg("Missing checkpoint proof")?;
+        proof.verify(&hash)?;
+        let mut signers = vec![proof.public_key.safe_get_msg("Missing checkpoint signer")?.clone()];
+        for p in &cp.peer_proofs {
+            if let Some(pk) = p.public_key.as_ref().filter(|_| p.verify(&hash).is_ok()) {
+                signers.push(pk.clone());
+            }
+        }
+        Ok(signers.into_iter().unique().collect_vec())
+    }
+
+    /// Peers whose observation of the creating transaction accepts it and is included in the
+    /// merkle root of an observation transaction they signed.
+    fn observation_attestors(&self) -> RgResult<Vec<PublicKey>> {
+        let tx_hash = self.transaction.safe_get_msg("Missing transaction")?.hash_or();
+        let mut res = vec![];
+        for o in &self.observation_proofs {
+            let metadata = o.metadata.safe_get_msg("Missing observation metadata")?;
+            let observed = metadata.observed_hash.safe_get_msg("Missing observed hash")?;
+            if observed.vec() != tx_hash.vec() || metadata.state != State::Accepted as i32 {
+                continue;
+            }
+            let merkle = o.merkle_proof.safe_get_msg("Missing observation merkle proof")?;
+            let root = match verify_merkle_path(merkle, &metadata.hash_or()) {
+                Ok(r) => r,
+                Err(_) => continue,
+            };
+            let signer = observation_signer(self, o, &root);
+            if let Some(pk) = signer {
+                res.push(pk);
+            }
+        }
+        Ok(res.into_iter().unique().collect_vec())
+    }
+
+    /// Full light client check, requiring at least `min_signers` distinct checkpoint signatures
+    /// and the serving node's unspent attestation. Callers should still compare the signers
+    /// against the peers they trust.
+    fn verify(&self, min_signers: usize) -> RgResult<UtxoEntry> {
+        let entry = self.verify_inclusion()?;
+        let signers = self.checkpoint_signers()?;
+        if signers.len() < min_signers {
+            return Err(error_info(format!(
+                "Checkpoint has {} valid signatures, {} required", signers.len(), min_signers
+            )));
+        }
+        let cp = self.checkpoint.safe_get_msg("Missing checkpoint")?;
+        let utxo_id = self.utxo_id.safe_get_msg("Missing UTXO id")?;
+        let unspent = self.unspent_proof.safe_get_msg("Missing unspent attestation")?;
+        unspent.verify(&unspent_attestation_hash(utxo_id, cp.time)?)?;
+        let attester = unspent.public_key.safe_get_msg("Missing unspent attestation signer")?;
+        if !signers.contains(attester) {
+            return Err(error_info("Unspent attestation is not signed by a checkpoint signer"));
+        }
+        Ok(entry)
+    }
+}
+
+#[test]
+fn observation_attestors_require_signed_observation() {
+    use redgold_schema::structs::{Input, Observation, ObservationMetadata, Output, Proof, StandardData, Transaction, UtxoId};
+    use redgold_schema::util::merkle::build_root;
+    use crate::{KeyPair, TestConstants};
+    let tc = TestConstants::new();
+    let kp = tc.key_pair();
+    let mut tx = Transaction::default();
+    tx.outputs.push(Output::default());
+    let mut metadata = ObservationMetadata::default();
+    metadata.observed_hash = Some(tx.hash_or());
+    metadata.state = State::Accepted as i32;
+    let tree = build_root(vec![Hash::from_string_calculate("a"), metadata.hash_or(), Hash::from_string_calculate("b")])
+        .expect("tree");
+    let parent = UtxoId::new(&Hash::from_string_calculate("parent"), 0);
+    let mut observation = Observation::default();
+    observation.merkle_root = Some(tree.root.clone());
+    observation.observations = vec![metadata.clone()];
+    observation.parent_id = Some(parent.clone());
+    let mut obs_tx = Transaction::default();
+    obs_tx.outputs.push(Output::from_data(StandardData::observation(observation)));
+    let mut input = Input::default();
+    input.utxo_id = Some(parent);
+    obs_tx.inputs.push(input);
+    let signed = |signer: &KeyPair| {
+        let mut t = obs_tx.clone();
+        t.inputs[0].proof.push(Proof::from_keypair_hash(&t.signable_hash(), signer));
+        t
+    };
+    let attested = |obs_tx: Transaction, include: bool| {
+        let mut op = ObservationProof::default();
+        op.metadata = Some(metadata.clone());
+        op.merkle_proof = Some(tree.proof(metadata.hash_or()));
+        op.proof = obs_tx.observation_proof().ok().cloned();
+        op.observation_hash = Some(obs_tx.hash_or());
+        let mut proof = UtxoStateProof::default();
+        proof.transaction = Some(tx.clone());
+        proof.observation_proofs.push(op);
+        if include {
+            proof.observation_transactions.push(obs_tx);
+        }
+        proof.observation_attestors().expect("attestors")
+    };
+    assert_eq!(attested(signed(&kp), true), vec![kp.public_key()]);
+    assert!(attested(signed(&kp), false).is_empty());
+    // A proof claiming a key which didn't sign the observation
+    let mut forged = signed(&kp);
+    let other = KeyPair { secret_key: tc.secret2, public_key: tc.public2 };
+    forged.inputs[0].proof[0].public_key = Some(other.public_key());
+    assert!(attested(forged, true).is_empty());
+}
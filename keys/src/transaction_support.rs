@@ -18,6 +18,8 @@ pub trait TransactionSupport {
     // TODO: Move all of this to TransactionBuilder
     fn verify_utxo_entry_proof(&self, utxo_entry: &UtxoEntry) -> Result<(), ErrorInfo>;
     fn prevalidate(&self) -> Result<(), ErrorInfo>;
+    // Everything in prevalidate except signature verification, for callers batching signatures
+    fn prevalidate_structure(&self) -> Result<(), ErrorInfo>;
 
     fn input_bitcoin_address(&self, network: &NetworkEnvironment, other_address: &String) -> bool;
     fn output_swap_amount_of_multi(&self, pk_address: &structs::PublicKey, network_environment: &NetworkEnvironment) -> RgResult<i64>;
@@ -94,6 +96,15 @@ impl TransactionSupport for Transaction {
     }
 
     fn prevalidate(&self) -> RgResult<()> {
+        self.prevalidate_structure()?;
+        let signable_hash = self.signable_hash();
+        for input in self.inputs.iter() {
+            input.verify_signatures_only(&signable_hash)?;
+        }
+        Ok(())
+    }
+
+    fn prevalidate_structure(&self) -> RgResult<()> {
 
         let size_bytes = self.proto_serialize().len();
         if size_bytes > 10_000 {
@@ -157,7 +168,6 @@ impl TransactionSupport for Transaction {
                                       )))?;
                 }
             }
-            input.verify_signatures_only(&self.signable_hash())?;
         }
 
         for _output in self.outputs.iter() {
@@ -0,0 +1,104 @@
+use bdk::bitcoin::{Network, Transaction, Txid};
+use bdk::blockchain::{Blockchain, ElectrumBlockchain, EsploraBlockchain, GetTx};
+use bdk::database::MemoryDatabase;
+use bdk::electrum_client::Client;
+use bdk::{SyncOptions, Wallet};
+use redgold_schema::structs::NetworkEnvironment;
+use redgold_schema::{ErrorInfoContext, RgResult};
+use serde::{Deserialize, Serialize};
+use crate::util::btc_wallet::bitcoin_network;
+
+pub const DEFAULT_ELECTRUM_URL: &str = "ssl://electrum.blockstream.info:60002";
+pub const DEFAULT_REGTEST_ELECTRUM_URL: &str = "tcp://127.0.0.1:50001";
+pub const DEFAULT_ESPLORA_URL: &str = "https://blockstream.info/api";
+pub const DEFAULT_TESTNET_ESPLORA_URL: &str = "https://blockstream.info/testnet/api";
+pub const DEFAULT_REGTEST_ESPLORA_URL: &str = "http://127.0.0.1:3002";
+const ESPLORA_STOP_GAP: usize = 20;
+
+pub fn default_electrum_url(network: &NetworkEnvironment) -> String {
+    match bitcoin_network(network) {
+        Network::Regtest => std::env::var("REDGOLD_BTC_REGTEST_ELECTRUM_URL")
+            .unwrap_or(DEFAULT_REGTEST_ELECTRUM_URL.to_string()),
+        _ => DEFAULT_ELECTRUM_URL.to_string()
+    }
+}
+
+pub fn default_esplora_url(network: &NetworkEnvironment) -> String {
+    match bitcoin_network(network) {
+        Network::Bitcoin => DEFAULT_ESPLORA_URL.to_string(),
+        Network::Regtest => std::env::var("REDGOLD_BTC_REGTEST_ESPLORA_URL")
+            .unwrap_or(DEFAULT_REGTEST_ESPLORA_URL.to_string()),
+        _ => DEFAULT_TESTNET_ESPLORA_URL.to_string()
+    }
+}
+
+/// Chain data source for the bitcoin wallet, urls default per network when empty.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum BitcoinBackendConfig {
+    Electrum(Option<String>),
+    Esplora(Option<String>),
+}
+
+impl Default for BitcoinBackendConfig {
+    fn default() -> Self {
+        BitcoinBackendConfig::Electrum(None)
+    }
+}
+
+impl BitcoinBackendConfig {
+
+    pub fn url(&self, network: &NetworkEnvironment) -> String {
+        match self {
+            BitcoinBackendConfig::Electrum(url) => url.clone().unwrap_or(default_electrum_url(network)),
+            BitcoinBackendConfig::Esplora(url) => url.clone().unwrap_or(default_esplora_url(network)),
+        }
+    }
+
+    pub fn build(&self, network: &NetworkEnvironment) -> RgResult<Box<dyn BitcoinBackend>> {
+        let url = self.url(network);
+        let backend: Box<dyn BitcoinBackend> = match self {
+            BitcoinBackendConfig::Electrum(_) => {
+                let client = Client::new(&url)
+                    .error_info(format!("Error building bdk client for {}", url))?;
+                Box::new(ElectrumBlockchain::from(client))
+            }
+            BitcoinBackendConfig::Esplora(_) => {
+                Box::new(EsploraBlockchain::new(&url, ESPLORA_STOP_GAP))
+            }
+        };
+        Ok(backend)
+    }
+}
+
+/// Operations the wallet needs from a chain data source, implemented for any bdk blockchain
+/// so electrum and esplora can be swapped without changing the wallet.
+pub trait BitcoinBackend: Send + Sync {
+    fn sync_wallet(&self, wallet: &Wallet<MemoryDatabase>) -> RgResult<()>;
+    fn fetch_tx(&self, txid: &Txid) -> RgResult<Option<Transaction>>;
+    fn broadcast_tx(&self, tx: &Transaction) -> RgResult<()>;
+}
+
+impl<B> BitcoinBackend for B where B: Blockchain + GetTx + Send + Sync {
+    fn sync_wallet(&self, wallet: &Wallet<MemoryDatabase>) -> RgResult<()> {
+        wallet.sync(self, SyncOptions::default()).error_info("Error syncing BDK wallet")
+    }
+
+    fn fetch_tx(&self, txid: &Txid) -> RgResult<Option<Transaction>> {
+        self.get_tx(txid).error_info("Error getting tx")
+    }
+
+    fn broadcast_tx(&self, tx: &Transaction) -> RgResult<()> {
+        self.broadcast(tx).error_info("Error broadcasting transaction")
+    }
+}
+
+#[test]
+fn backend_default_urls() {
+    let electrum = BitcoinBackendConfig::default();
+    assert_eq!(electrum.url(&NetworkEnvironment::Main), DEFAULT_ELECTRUM_URL);
+    let esplora = BitcoinBackendConfig::Esplora(None);
+    assert_eq!(esplora.url(&NetworkEnvironment::Main), DEFAULT_ESPLORA_URL);
+    assert_eq!(esplora.url(&NetworkEnvironment::Dev), DEFAULT_TESTNET_ESPLORA_URL);
+    let custom = BitcoinBackendConfig::Esplora(Some("http://localhost:3000".to_string()));
+    assert_eq!(custom.url(&NetworkEnvironment::Main), "http://localhost:3000");
+}
@@ -0,0 +1,100 @@
+use bdk::bitcoin::{Network, Script};
+use bdk::database::{BatchOperations, Database, MemoryDatabase, SyncTime};
+use bdk::{KeychainKind, LocalUtxo, TransactionDetails};
+use serde::{Deserialize, Serialize};
+use redgold_schema::{error_info, ErrorInfoContext, RgResult};
+use redgold_schema::util::current_time_millis;
+
+/// Known script history of a wallet as of its last sync, imported into a fresh wallet database
+/// so a restarted node only fetches what changed since, rather than the whole history.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BtcWalletCheckpoint {
+    pub descriptor: String,
+    pub network: Network,
+    pub sync_time: Option<SyncTime>,
+    pub script_pubkeys: Vec<(Script, KeychainKind, u32)>,
+    pub last_index: Vec<(KeychainKind, u32)>,
+    pub utxos: Vec<LocalUtxo>,
+    // Including raw transactions, which are most of the cost of a first sync
+    pub transactions: Vec<TransactionDetails>,
+    pub created_time: i64,
+}
+
+impl BtcWalletCheckpoint {
+
+    pub fn from_database<D: Database>(db: &D, descriptor: &str, network: Network) -> RgResult<Self> {
+        let mut script_pubkeys = vec![];
+        for s in db.iter_script_pubkeys(None).error_info("Error listing wallet scripts")? {
+            if let Some((k, i)) = db.get_path_from_script_pubkey(&s).error_info("Error reading script path")? {
+                script_pubkeys.push((s, k, i));
+            }
+        }
+        let mut last_index = vec![];
+        for k in [KeychainKind::External, KeychainKind::Internal] {
+            if let Some(i) = db.get_last_index(k).error_info("Error reading last index")? {
+                last_index.push((k, i));
+            }
+        }
+        Ok(Self {
+            descriptor: descriptor.to_string(),
+            network,
+            sync_time: db.get_sync_time().error_info("Error reading sync time")?,
+            script_pubkeys,
+            last_index,
+            utxos: db.iter_utxos().error_info("Error listing wallet utxos")?,
+            transactions: db.iter_txs(true).error_info("Error listing wallet transactions")?,
+            created_time: current_time_millis(),
+        })
+    }
+
+    /// Height of the chain tip the checkpointed wallet was last synced to.
+    pub fn height(&self) -> Option<u32> {
+        self.sync_time.as_ref().map(|s| s.block_time.height)
+    }
+
+    /// Database pre-populated from the checkpoint, refusing one taken for another wallet.
+    pub fn database(&self, descriptor: &str, network: Network) -> RgResult<MemoryDatabase> {
+        if self.descriptor != descriptor || self.network != network {
+            return Err(error_info(format!(
+                "Checkpoint for {} on {} does not match wallet {} on {}",
+                self.descriptor, self.network, descriptor, network
+            )));
+        }
+        let mut db = MemoryDatabase::default();
+        for (s, k, i) in &self.script_pubkeys {
+            db.set_script_pubkey(s, *k, *i).error_info("Error importing wallet script")?;
+        }
+        for (k, i) in &self.last_index {
+            db.set_last_index(*k, *i).error_info("Error importing last index")?;
+        }
+        for u in &self.utxos {
+            db.set_utxo(u).error_info("Error importing wallet utxo")?;
+        }
+        for t in &self.transactions {
+            db.set_tx(t).error_info("Error importing wallet transaction")?;
+        }
+        if let Some(s) = &self.sync_time {
+            db.set_sync_time(s.clone()).error_info("Error importing sync time")?;
+        }
+        Ok(db)
+    }
+}
+
+#[test]
+fn checkpoint_round_trips_database() {
+    use bdk::BlockTime;
+    let mut db = MemoryDatabase::default();
+    let script = Script::from(vec![0u8, 20]);
+    db.set_script_pubkey(&script, KeychainKind::External, 0).expect("script");
+    db.set_last_index(KeychainKind::External, 0).expect("index");
+    db.set_sync_time(SyncTime { block_time: BlockTime { height: 800_000, timestamp: 1 } }).expect("sync");
+    let cp = BtcWalletCheckpoint::from_database(&db, "wpkh(abc)", Network::Regtest).expect("checkpoint");
+    assert_eq!(cp.height(), Some(800_000));
+    let json = serde_json::to_string(&cp).expect("ser");
+    let cp: BtcWalletCheckpoint = serde_json::from_str(&json).expect("deser");
+    let restored = cp.database("wpkh(abc)", Network::Regtest).expect("db");
+    assert_eq!(restored.get_last_index(KeychainKind::External).expect("index"), Some(0));
+    assert_eq!(restored.get_path_from_script_pubkey(&script).expect("path"), Some((KeychainKind::External, 0)));
+    assert!(cp.database("wpkh(other)", Network::Regtest).is_err());
+    assert!(cp.database("wpkh(abc)", Network::Bitcoin).is_err());
+}
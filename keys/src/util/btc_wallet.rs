@@ -3,17 +3,18 @@ use std::io::Read;
 use std::str::FromStr;
 use std::sync::{Arc, RwLock};
 
-use bdk::{Balance, FeeRate, KeychainKind, SignOptions, SyncOptions, TransactionDetails, Wallet};
-use bdk::bitcoin::{Address, ecdsa, EcdsaSighashType, Network, Script, Sighash, TxIn, TxOut};
+use bdk::{Balance, FeeRate, KeychainKind, SignOptions, TransactionDetails, Wallet};
+use bdk::bitcoin::{Address, ecdsa, EcdsaSighashType, Network, SchnorrSighashType, Script, Sighash, TxIn, TxOut};
 use bdk::bitcoin::blockdata::opcodes;
 use bdk::bitcoin::blockdata::script::Builder as ScriptBuilder;
 use bdk::bitcoin::hashes::Hash;
-use bdk::bitcoin::secp256k1::{All, Secp256k1, Signature};
+use bdk::bitcoin::secp256k1::{All, Message, Secp256k1, Signature, XOnlyPublicKey};
+use bdk::bitcoin::secp256k1::schnorr;
 use bdk::bitcoin::util::{psbt, sighash};
+use bdk::bitcoin::util::schnorr::{SchnorrSig, TapTweak};
+use bdk::bitcoin::util::sighash::Prevouts;
 use bdk::bitcoin::util::psbt::PartiallySignedTransaction;
-use bdk::blockchain::{Blockchain, ElectrumBlockchain, GetTx};
 use bdk::database::MemoryDatabase;
-use bdk::electrum_client::Client;
 use bdk::signer::{InputSigner, SignerCommon, SignerError, SignerId, SignerOrdering};
 // use crate::util::cli::commands::send;
 use redgold_schema::{EasyJson, error_info, ErrorInfoContext, RgResult, SafeBytesAccess, SafeOption, structs};
@@ -21,6 +22,8 @@ use redgold_schema::structs::{ErrorInfo, NetworkEnvironment, Proof, PublicKey, S
 use serde::{Deserialize, Serialize};
 use crate::{KeyPair, TestConstants};
 use crate::proof_support::ProofSupport;
+use crate::util::btc_backend::{BitcoinBackend, BitcoinBackendConfig};
+use crate::util::btc_checkpoint::BtcWalletCheckpoint;
 use crate::util::keys::ToPublicKeyFromLib;
 use crate::util::mnemonic_support::{test_pkey_hex, test_pubk};
 
@@ -32,6 +35,24 @@ fn schnorr_test() {
 
 }
 
+/// Bitcoin network backing a Redgold environment, local and debug environments run against a
+/// local regtest node so wallet and AMM flows can be tested without public infrastructure.
+pub fn bitcoin_network(network: &NetworkEnvironment) -> Network {
+    match network {
+        NetworkEnvironment::Main => Network::Bitcoin,
+        NetworkEnvironment::Local | NetworkEnvironment::Debug => Network::Regtest,
+        _ => Network::Testnet,
+    }
+}
+
+#[test]
+fn bitcoin_network_by_environment() {
+    assert_eq!(bitcoin_network(&NetworkEnvironment::Main), Network::Bitcoin);
+    assert_eq!(bitcoin_network(&NetworkEnvironment::Local), Network::Regtest);
+    assert_eq!(bitcoin_network(&NetworkEnvironment::Debug), Network::Regtest);
+    assert_eq!(bitcoin_network(&NetworkEnvironment::Dev), Network::Testnet);
+}
+
 pub fn struct_public_to_address(pk: structs::PublicKey, network: Network) -> Result<Address, ErrorInfo> {
     let pk2 = bdk::bitcoin::util::key::PublicKey::from_slice(&*pk.bytes.safe_bytes()?)
         .error_info("Unable to convert destination pk to bdk public key")?;
@@ -46,6 +67,103 @@ pub fn struct_public_to_bdk_pubkey(pk: &structs::PublicKey) -> Result<bdk::bitco
     Ok(pk2)
 }
 
+pub fn struct_public_to_x_only(pk: &structs::PublicKey) -> Result<XOnlyPublicKey, ErrorInfo> {
+    Ok(struct_public_to_bdk_pubkey(pk)?.inner.x_only_public_key().0)
+}
+
+/// Output type a single key wallet receives to and spends from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BtcAddressType {
+    // P2WPKH with bech32 addresses
+    NativeSegwit,
+    // P2TR key path only with bech32m addresses, the internal key is the x-only public key
+    Taproot,
+}
+
+impl Default for BtcAddressType {
+    fn default() -> Self {
+        BtcAddressType::NativeSegwit
+    }
+}
+
+impl BtcAddressType {
+    pub fn all() -> Vec<Self> {
+        vec![BtcAddressType::NativeSegwit, BtcAddressType::Taproot]
+    }
+
+    pub fn descriptor(&self, pk: &structs::PublicKey) -> Result<String, ErrorInfo> {
+        match self {
+            BtcAddressType::NativeSegwit => Ok(format!("wpkh({})", pk.hex_or())),
+            BtcAddressType::Taproot => Ok(format!("tr({})", struct_public_to_x_only(pk)?)),
+        }
+    }
+
+    pub fn address(&self, pk: &structs::PublicKey, network: Network) -> Result<Address, ErrorInfo> {
+        match self {
+            BtcAddressType::NativeSegwit => struct_public_to_address(pk.clone(), network),
+            BtcAddressType::Taproot => {
+                let internal_key = struct_public_to_x_only(pk)?;
+                Ok(Address::p2tr(&Secp256k1::verification_only(), internal_key, None, network))
+            }
+        }
+    }
+}
+
+/// Size and fee of a prepared transaction, reviewed before any signatures are requested.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct BtcFeePreview {
+    pub vsize: u64,
+    pub fee: u64,
+    // Satoshis per virtual byte
+    pub fee_rate: f64,
+}
+
+impl BtcAddressType {
+    // Witness weight of a single key spend, ECDSA signatures are at most 73 bytes with the sighash byte
+    pub fn input_witness_weight(&self) -> usize {
+        match self {
+            BtcAddressType::NativeSegwit => 1 + 1 + 73 + 1 + 33,
+            BtcAddressType::Taproot => 1 + 1 + 64,
+        }
+    }
+}
+
+/// Virtual size of the transaction once signed, estimating witnesses from the address type
+/// since unsigned transactions carry none.
+pub fn estimate_signed_vsize(tx: &bdk::bitcoin::Transaction, address_type: BtcAddressType) -> u64 {
+    // Segwit marker and flag bytes are only serialized once a witness is present
+    let weight = tx.weight() + 2 + tx.input.len() * address_type.input_witness_weight();
+    ((weight + 3) / 4) as u64
+}
+
+#[test]
+fn signed_vsize_estimate() {
+    let tc = TestConstants::new();
+    let script = struct_public_to_address(tc.public.to_struct_public_key(), Network::Testnet)
+        .expect("address").script_pubkey();
+    let tx = bdk::bitcoin::Transaction {
+        version: 2,
+        lock_time: bdk::bitcoin::PackedLockTime(0),
+        input: vec![TxIn::default()],
+        output: vec![TxOut { value: 10_000, script_pubkey: script }],
+    };
+    // Typical one input one output P2WPKH spend is 110 vbytes
+    assert_eq!(estimate_signed_vsize(&tx, BtcAddressType::NativeSegwit), 110);
+    assert!(estimate_signed_vsize(&tx, BtcAddressType::Taproot) < 110);
+}
+
+#[test]
+fn taproot_address_and_descriptor() {
+    let tc = TestConstants::new();
+    let pk = tc.public.to_struct_public_key();
+    let tr = BtcAddressType::Taproot;
+    assert!(tr.descriptor(&pk).expect("descriptor").starts_with("tr("));
+    let addr = tr.address(&pk, Network::Testnet).expect("address").to_string();
+    assert!(addr.starts_with("tb1p"));
+    let wpkh = BtcAddressType::NativeSegwit.address(&pk, Network::Testnet).expect("address").to_string();
+    assert!(wpkh.starts_with("tb1q"));
+}
+
 
 // use log::error;
 
@@ -133,6 +251,40 @@ fn segwit_sighash(
 }
 
 
+/// Segwit signature hash of every input in the psbt, in input order.
+pub fn psbt_signable_hashes(psbt: &PartiallySignedTransaction) -> Result<Vec<(Vec<u8>, EcdsaSighashType)>, ErrorInfo> {
+    let mut res = vec![];
+    for (input_index, _input) in psbt.inputs.iter().enumerate() {
+        // TODO: Port SignerContext if necessary
+        // let (hash, sighash) = match input.witness_utxo {
+        //     Some(_) => segwitv0_sighash(&psbt, input_index).error_info("segwitv0_sighash extraction failure")?,
+        //     None => legacy_sighash(&psbt, input_index).error_info("segwitv0_legacy signature hash extraction failure")?,
+        // };
+        let (hash, sighash) = segwit_sighash(psbt, input_index, ())
+            .error_info("segwitv0_sighash extraction failure")?;
+        let data = hash.into_inner().to_vec();
+        res.push((data, sighash));
+    };
+    Ok(res)
+}
+
+/// BIP-341 key path signature hash of every input in the psbt, in input order. Every input
+/// needs its witness utxo since taproot sighashes commit to all spent outputs.
+pub fn psbt_taproot_signable_hashes(psbt: &PartiallySignedTransaction) -> Result<Vec<Vec<u8>>, ErrorInfo> {
+    let prevouts = psbt.inputs.iter()
+        .map(|i| i.witness_utxo.clone().ok_or(error_info("Missing witness utxo for taproot input")))
+        .collect::<RgResult<Vec<TxOut>>>()?;
+    let mut cache = sighash::SighashCache::new(&psbt.unsigned_tx);
+    let mut res = vec![];
+    for input_index in 0..psbt.inputs.len() {
+        let hash = cache.taproot_key_spend_signature_hash(
+            input_index, &Prevouts::All(&prevouts), SchnorrSighashType::Default
+        ).error_info("taproot key spend sighash extraction failure")?;
+        res.push(hash.into_inner().to_vec());
+    }
+    Ok(res)
+}
+
 #[derive(Debug, Clone)]
 struct MultipartySigner {
     public_key: structs::PublicKey,
@@ -211,8 +363,9 @@ pub struct SingleKeyBitcoinWallet {
     network: Network,
     pub psbt: Option<PartiallySignedTransaction>,
     pub transaction_details: Option<TransactionDetails>,
-    client: ElectrumBlockchain,
-    custom_signer: Arc<MultipartySigner>
+    client: Box<dyn BitcoinBackend>,
+    custom_signer: Arc<MultipartySigner>,
+    pub address_type: BtcAddressType,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -230,6 +383,11 @@ pub struct ExternalTimedTransaction {
     pub amount: u64,
     pub incoming: bool,
     pub currency: SupportedCurrency,
+    // Amount paid to each of the other output addresses, in output order
+    #[serde(default)]
+    pub other_output_amounts: Vec<(String, u64)>,
+    #[serde(default)]
+    pub fee: Option<u64>,
 }
 
 impl ExternalTimedTransaction {
@@ -257,17 +415,47 @@ impl SingleKeyBitcoinWallet {
         network: NetworkEnvironment,
         do_sync: bool
     ) -> Result<Self, ErrorInfo> {
-        let network = if network == NetworkEnvironment::Main {
-            Network::Bitcoin
-        } else {
-            Network::Testnet
+        Self::new_wallet_backend(public_key, network, do_sync, &BitcoinBackendConfig::default())
+    }
+
+    /// Wallet backed by the configured electrum or esplora server.
+    pub fn new_wallet_backend(
+        public_key: structs::PublicKey,
+        network_environment: NetworkEnvironment,
+        do_sync: bool,
+        backend: &BitcoinBackendConfig
+    ) -> Result<Self, ErrorInfo> {
+        Self::new_wallet_typed(public_key, network_environment, do_sync, backend, BtcAddressType::NativeSegwit)
+    }
+
+    /// Wallet for a single key with the descriptor chosen by address type.
+    pub fn new_wallet_typed(
+        public_key: structs::PublicKey,
+        network_environment: NetworkEnvironment,
+        do_sync: bool,
+        backend: &BitcoinBackendConfig,
+        address_type: BtcAddressType
+    ) -> Result<Self, ErrorInfo> {
+        Self::new_wallet_checkpointed(public_key, network_environment, do_sync, backend, address_type, None)
+    }
+
+    /// Wallet starting from a previously exported checkpoint when given, so the first sync
+    /// only fetches history since the checkpoint was taken.
+    pub fn new_wallet_checkpointed(
+        public_key: structs::PublicKey,
+        network_environment: NetworkEnvironment,
+        do_sync: bool,
+        backend: &BitcoinBackendConfig,
+        address_type: BtcAddressType,
+        checkpoint: Option<&BtcWalletCheckpoint>
+    ) -> Result<Self, ErrorInfo> {
+        let network = bitcoin_network(&network_environment);
+        let client = backend.build(&network_environment)?;
+        let descr = address_type.descriptor(&public_key)?;
+        let database = match checkpoint {
+            Some(cp) => cp.database(&descr, network)?,
+            None => MemoryDatabase::default(),
         };
-        let client = Client::new("ssl://electrum.blockstream.info:60002")
-            .error_info("Error building bdk client")?;
-        let client = ElectrumBlockchain::from(client);
-        let database = MemoryDatabase::default();
-        let hex = public_key.hex_or();
-        let descr = format!("wpkh({})", hex);
         let wallet = Wallet::new(
             &*descr,
             Some(&*descr),
@@ -283,6 +471,7 @@ impl SingleKeyBitcoinWallet {
             transaction_details: None,
             client,
             custom_signer: custom_signer.clone(),
+            address_type,
         };
         // Adding the multiparty signer to the BDK wallet
         bitcoin_wallet.wallet.add_signer(
@@ -351,15 +540,17 @@ impl SingleKeyBitcoinWallet {
     // }
 
     pub fn sync(&self) -> Result<(), ErrorInfo> {
-        self.wallet.sync(&self.client, SyncOptions::default()).error_info("Error syncing BDK wallet")?;
-        Ok(())
+        self.client.sync_wallet(&self.wallet)
+    }
+
+    /// Snapshot of the synced wallet database for import with `new_wallet_checkpointed`.
+    pub fn export_checkpoint(&self) -> RgResult<BtcWalletCheckpoint> {
+        let descr = self.address_type.descriptor(&self.public_key)?;
+        BtcWalletCheckpoint::from_database(&*self.wallet.database(), &descr, self.network)
     }
 
     pub fn address(&self) -> Result<String, ErrorInfo> {
-        let pk2 = bdk::bitcoin::util::key::PublicKey::from_slice(&*self.public_key.bytes.safe_bytes()?)
-            .error_info("Unable to convert destination pk to bdk public key")?;
-        let addr = bdk::bitcoin::util::address::Address::p2wpkh(&pk2, self.network)
-            .error_info("Unable to convert destination pk to bdk address")?;
+        let addr = self.address_type.address(&self.public_key, self.network)?;
         Ok(addr.to_string())
     }
 
@@ -376,13 +567,15 @@ impl SingleKeyBitcoinWallet {
             let tx = x.transaction.safe_get_msg("Error getting transaction")?;
             let mut to_self_output_amount: Option<u64> = None;
             let mut non_self_addrs_output = vec![];
+            let mut non_self_output_amounts = vec![];
             for o in &tx.output {
                 if let Some(a) = Address::from_script(&o.script_pubkey, self.network).ok() {
                     if a.to_string() == self_addr {
                         // sum value here instead?
                         to_self_output_amount = Some(o.value)
                     } else {
-                        non_self_addrs_output.push(a.to_string())
+                        non_self_addrs_output.push(a.to_string());
+                        non_self_output_amounts.push((a.to_string(), o.value));
                     }
                 }
             }
@@ -392,7 +585,7 @@ impl SingleKeyBitcoinWallet {
             for i in &tx.input {
                 let txid = i.previous_output.txid;
                 let vout = i.previous_output.vout;
-                let prev_tx = self.client.get_tx(&txid).error_info("Error getting tx")?;
+                let prev_tx = self.client.fetch_tx(&txid)?;
                 let prev_tx = prev_tx.safe_get_msg("No tx found")?;
                 let prev_output = prev_tx.output.get(vout as usize);
                 let prev_output = prev_output.safe_get_msg("Error getting output")?;
@@ -423,6 +616,8 @@ impl SingleKeyBitcoinWallet {
                     amount: value,
                     incoming: true,
                     currency: SupportedCurrency::Bitcoin,
+                    other_output_amounts: non_self_output_amounts,
+                    fee: x.fee,
                 };
                 res.push(ett)
             }
@@ -446,7 +641,7 @@ impl SingleKeyBitcoinWallet {
         for i in tx_ins {
             let txid = i.previous_output.txid;
             let vout = i.previous_output.vout;
-            let prev_tx = self.client.get_tx(&txid).error_info("Error getting tx")?;
+            let prev_tx = self.client.fetch_tx(&txid)?;
             let prev_tx = prev_tx.safe_get_msg("No tx found")?;
             let prev_output = prev_tx.output.get(vout as usize);
             let prev_output = prev_output.safe_get_msg("Error getting output")?;
@@ -468,13 +663,11 @@ impl SingleKeyBitcoinWallet {
         for x in result.iter() {
             let tx = x.transaction.safe_get_msg("Error getting transaction")?;
             let output_amounts = self.outputs_convert(&tx.output);
-            let other_output_addresses = output_amounts.iter().filter_map(|(x,_y)| {
-                if x != &self_addr {
-                    Some(x.clone())
-                } else {
-                    None
-                }
-            }).collect();
+            let other_output_amounts = output_amounts.iter()
+                .filter(|(x, _y)| x != &self_addr)
+                .cloned()
+                .collect::<Vec<(String, u64)>>();
+            let other_output_addresses = other_output_amounts.iter().map(|(x, _y)| x.clone()).collect();
             let input_addrs = self.convert_tx_inputs_address(&tx.input)?;
 
             // Not needed?
@@ -506,6 +699,8 @@ impl SingleKeyBitcoinWallet {
                     amount: value,
                     incoming,
                     currency: SupportedCurrency::Bitcoin,
+                    other_output_amounts,
+                    fee: x.fee,
                 };
                 res.push(ett)
             }
@@ -578,26 +773,81 @@ impl SingleKeyBitcoinWallet {
         Ok(())
     }
 
+    /// Spends every UTXO in the wallet to a single destination, the miner fee is deducted from
+    /// the swept amount.
+    pub fn create_drain_transaction(&mut self, destination: String) -> Result<(), ErrorInfo> {
+
+        self.sync()?;
+
+        let addr = Address::from_str(&*destination).error_info("Unable to parse address")?;
+        let mut builder = self.wallet.build_tx();
+        builder
+            .drain_wallet()
+            .drain_to(addr.script_pubkey())
+            .enable_rbf()
+            .fee_rate(FeeRate::from_sat_per_vb(1.0));
+
+        let (psbt, details) = builder
+            .finish()
+            .error_info("Builder TX issue")?;
+
+        self.transaction_details = Some(details);
+        self.psbt = Some(psbt);
+        Ok(())
+    }
+
+    pub fn fee_preview(&self) -> RgResult<BtcFeePreview> {
+        let psbt = self.psbt.safe_get_msg("No psbt found")?;
+        let fee = self.transaction_details.as_ref().and_then(|d| d.fee)
+            .ok_or(error_info("Missing fee on prepared transaction"))?;
+        let vsize = estimate_signed_vsize(&psbt.unsigned_tx, self.address_type);
+        Ok(BtcFeePreview {
+            vsize,
+            fee,
+            fee_rate: fee as f64 / vsize.max(1) as f64,
+        })
+    }
+
     pub fn txid(&self) -> Result<String, ErrorInfo> {
         let txid = self.transaction_details.safe_get_msg("No psbt found")?.txid;
         Ok(txid.to_string())
     }
 
     pub fn signable_hashes(&mut self) -> Result<Vec<(Vec<u8>, EcdsaSighashType)>, ErrorInfo> {
-        let psbt = self.psbt.safe_get_msg("No psbt found")?.clone();
-        let mut res = vec![];
-        for (input_index, _input) in psbt.inputs.iter().enumerate() {
-            // TODO: Port SignerContext if necessary
-            // let (hash, sighash) = match input.witness_utxo {
-            //     Some(_) => segwitv0_sighash(&psbt, input_index).error_info("segwitv0_sighash extraction failure")?,
-            //     None => legacy_sighash(&psbt, input_index).error_info("segwitv0_legacy signature hash extraction failure")?,
-            // };
-            let (hash, sighash) = segwit_sighash(&psbt, input_index, ())
-                .error_info("segwitv0_sighash extraction failure")?;
-            let data = hash.into_inner().to_vec();
-            res.push((data, sighash));
-        };
-        Ok(res)
+        if self.address_type == BtcAddressType::Taproot {
+            // The multiparty keysign produces ECDSA signatures, which taproot key paths can't use
+            return Err(error_info("Taproot inputs require schnorr signatures, use taproot_signable_hashes"));
+        }
+        let psbt = self.psbt.safe_get_msg("No psbt found")?;
+        psbt_signable_hashes(psbt)
+    }
+
+    pub fn taproot_signable_hashes(&self) -> Result<Vec<Vec<u8>>, ErrorInfo> {
+        let psbt = self.psbt.safe_get_msg("No psbt found")?;
+        psbt_taproot_signable_hashes(psbt)
+    }
+
+    /// Sets the key path signature of an input, the signature must be made with the tweaked
+    /// output key over the taproot_signable_hashes entry for that input.
+    pub fn affix_schnorr_signature(&mut self, input_index: usize, signature: &[u8]) -> RgResult<()> {
+        let sig = schnorr::Signature::from_slice(signature).error_info("Invalid schnorr signature")?;
+        let psbt = self.psbt.as_mut().ok_or(error_info("No psbt found"))?;
+        let input = psbt.inputs.get_mut(input_index).ok_or(error_info("No psbt input found"))?;
+        input.tap_key_sig = Some(SchnorrSig { sig, hash_ty: SchnorrSighashType::Default });
+        Ok(())
+    }
+
+    fn local_sign_taproot(&mut self, kp: &KeyPair) -> RgResult<()> {
+        let secp = Secp256k1::new();
+        let tweaked = bdk::bitcoin::secp256k1::KeyPair::from_secret_key(&secp, &kp.secret_key)
+            .tap_tweak(&secp, None)
+            .to_inner();
+        for (i, hash) in self.taproot_signable_hashes()?.iter().enumerate() {
+            let msg = Message::from_slice(hash).error_info("Invalid taproot sighash")?;
+            let sig = secp.sign_schnorr_no_aux_rand(&msg, &tweaked);
+            self.affix_schnorr_signature(i, &sig[..])?;
+        }
+        Ok(())
     }
 
     // pub fn pre_signing(&mut self) -> Result<(), ErrorInfo> {
@@ -610,6 +860,12 @@ impl SingleKeyBitcoinWallet {
 
     pub fn sign(&mut self)
         -> Result<bool, ErrorInfo> {
+        if self.address_type == BtcAddressType::Taproot {
+            // Key path signatures are already set on the inputs, only finalization is left
+            let psbt = self.psbt.as_mut().ok_or(error_info("No psbt found"))?;
+            return self.wallet.finalize_psbt(psbt, SignOptions::default())
+                .error_info("Taproot psbt finalization failure");
+        }
         let res = if let Some(psbt) = self.psbt.as_mut() {
             self.wallet.sign(psbt, SignOptions::default())
                 .map_err(|_e| self.custom_signer.err.read().unwrap().clone().unwrap().clone())
@@ -625,7 +881,7 @@ impl SingleKeyBitcoinWallet {
     pub fn broadcast_tx(&mut self) -> Result<(), ErrorInfo> {
         let psbt = self.psbt.safe_get()?;
         let transaction = psbt.clone().extract_tx();
-        self.client.broadcast(&transaction).error_info("Error broadcasting transaction")?;
+        self.client.broadcast_tx(&transaction)?;
         Ok(())
     }
 
@@ -649,6 +905,22 @@ impl SingleKeyBitcoinWallet {
         self.render_json()
     }
 
+    /// Input and output (address, satoshis) pairs along with the fee of the prepared psbt.
+    pub fn prepared_amounts(&self) -> RgResult<(Vec<(String, u64)>, Vec<(String, u64)>, u64)> {
+        let psbt = self.psbt.safe_get_msg("No psbt found")?;
+        let render = |script: &Script| Address::from_script(script, self.network)
+            .map(|a| a.to_string())
+            .unwrap_or(script.to_string());
+        let inputs = psbt.inputs.iter().filter_map(|i| {
+            i.witness_utxo.as_ref().map(|o| (render(&o.script_pubkey), o.value))
+        }).collect();
+        let outputs = psbt.unsigned_tx.output.iter()
+            .map(|o| (render(&o.script_pubkey), o.value))
+            .collect();
+        let fee = self.transaction_details.as_ref().and_then(|d| d.fee).unwrap_or(0);
+        Ok((inputs, outputs, fee))
+    }
+
     pub fn render_json(&self) -> RgResult<String> {
         RawTransaction {
             psbt: self.psbt.clone(),
@@ -663,12 +935,7 @@ impl SingleKeyBitcoinWallet {
 
     pub fn local_sign_single(&mut self, pkey_hex: String) -> RgResult<String> {
         let kp = KeyPair::from_private_hex(pkey_hex)?;
-        let signables = self.signable_hashes()?;
-        for (i, (hash, sighashtype)) in signables.iter().enumerate() {
-            // println!("signable {}: {}", i, hex::encode(hash));
-            let prf = Proof::from_keypair(hash, kp);
-            self.affix_input_signature(i, &prf, sighashtype);
-        }
+        self.local_sign_inputs(&kp)?;
         let finalized = self.sign()?;
         if !finalized {
             return Err(error_info("Not finalized"));
@@ -676,18 +943,25 @@ impl SingleKeyBitcoinWallet {
         self.render_json()
     }
 
+    // Signs every input with a local key, schnorr for taproot and ECDSA through the multiparty signer otherwise
+    fn local_sign_inputs(&mut self, kp: &KeyPair) -> RgResult<()> {
+        if self.address_type == BtcAddressType::Taproot {
+            return self.local_sign_taproot(kp);
+        }
+        let signables = self.signable_hashes()?;
+        for (i, (hash, sighashtype)) in signables.iter().enumerate() {
+            let prf = Proof::from_keypair(hash, *kp);
+            self.affix_input_signature(i, &prf, sighashtype);
+        }
+        Ok(())
+    }
+
     pub fn send_local(&mut self, dest: String, amount: u64, pkey_hex: String) -> RgResult<String> {
         self.create_transaction_output_batch(vec![(dest, amount)])?;
         let kp = KeyPair::from_private_hex(pkey_hex)?;
         // let d = w.transaction_details.clone().expect("d");
         // println!("txid: {:?}", d.txid);
-        let signables = self.signable_hashes()?;
-        // println!("num signable hashes: {:?}", signables.len());
-        for (i, (hash, sighashtype)) in signables.iter().enumerate() {
-            // println!("signable {}: {}", i, hex::encode(hash));
-            let prf = Proof::from_keypair(hash, kp);
-            self.affix_input_signature(i, &prf, sighashtype);
-        }
+        self.local_sign_inputs(&kp)?;
         let finalized = self.sign()?;
         if !finalized {
             return Err(error_info("Not finalized"));
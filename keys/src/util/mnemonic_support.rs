@@ -1,6 +1,7 @@
 use std::str::FromStr;
 
 use bdk::bitcoin::Network;
+use bdk::bitcoin::hashes::{hmac, sha512, Hash as BitcoinHash, HashEngine};
 use bdk::bitcoin::secp256k1::{rand, Secp256k1};
 use bdk::bitcoin::secp256k1::rand::RngCore;
 use bdk::bitcoin::util::bip32::{DerivationPath, ExtendedPrivKey, ExtendedPubKey};
@@ -19,6 +20,35 @@ use crate::address_external::{ToBitcoinAddress, ToEthereumAddress};
 use crate::KeyPair;
 use crate::util::btc_wallet::SingleKeyBitcoinWallet;
 
+// BIP-85 key for deriving child entropy from a derived private key
+const BIP85_ENTROPY_HMAC_KEY: &[u8] = b"bip-entropy-from-k";
+
+/// BIP-85 derivation path for an English BIP-39 child mnemonic.
+pub fn bip85_bip39_path(word_count: usize, index: u32) -> String {
+    // Application 39, language 0 (English)
+    format!("m/83696968'/39'/0'/{}'/{}'", word_count, index)
+}
+
+/// BIP-85 child entropy, HMAC-SHA512 of the private key derived at the given path.
+pub fn bip85_entropy(xprv: &ExtendedPrivKey, path: impl Into<String>) -> RgResult<[u8; 64]> {
+    let dp = DerivationPath::from_str(path.into().as_str())
+        .error_info("Failed to parse derivation path")?;
+    let key = xprv.derive_priv(&Secp256k1::new(), &dp)
+        .error_info("Failed to derive private key")?;
+    let mut engine = hmac::HmacEngine::<sha512::Hash>::new(BIP85_ENTROPY_HMAC_KEY);
+    engine.input(&key.private_key.secret_bytes());
+    Ok(hmac::Hmac::<sha512::Hash>::from_engine(engine).into_inner())
+}
+
+pub fn bip85_mnemonic(xprv: &ExtendedPrivKey, word_count: usize, index: u32) -> RgResult<Mnemonic> {
+    if ![12, 18, 24].contains(&word_count) {
+        return Err(error_info(format!("Unsupported BIP-85 word count {}, expected 12, 18 or 24", word_count)));
+    }
+    let entropy = bip85_entropy(xprv, bip85_bip39_path(word_count, index))?;
+    let entropy_bytes = word_count * 4 / 3;
+    Mnemonic::from_entropy(&entropy[..entropy_bytes]).error_info("Failed to derive mnemonic from entropy")
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct WordsPass {
     pub words: String,
@@ -54,6 +84,16 @@ pub struct WordsPassMetadata {
     pub executable_checksum: String
 }
 
+/// Diagnostics used for verifying a mnemonic backup is well formed before relying on it.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct WordsPassHealth {
+    pub word_count: usize,
+    pub valid_bip39: bool,
+    pub entropy_bits: Option<usize>,
+    pub checksum_words: Option<String>,
+    pub error: Option<String>,
+}
+
 impl WordsPassMetadata {
     pub fn with_exe_checksum(&mut self, sum: impl Into<String>) -> &mut WordsPassMetadata {
         self.executable_checksum = sum.into();
@@ -139,6 +179,16 @@ impl WordsPass {
         })
     }
 
+    /// Standard BIP-85 child mnemonic, interoperable with other wallets unlike hash_derive_words.
+    /// The passphrase applies to the parent seed only, the child has none.
+    pub fn bip85_derive_words(&self, word_count: usize, index: u32) -> RgResult<Self> {
+        let m = bip85_mnemonic(&self.xprv()?, word_count, index)?;
+        Ok(Self {
+            words: m.to_string(),
+            passphrase: None
+        })
+    }
+
     pub fn new(words: impl Into<String>, passphrase: Option<String>) -> Self {
         Self {
             words: words.into(),
@@ -194,6 +244,46 @@ impl WordsPass {
         Ok(self)
     }
 
+    pub fn word_list(&self) -> Vec<String> {
+        self.words.split_whitespace().map(|w| w.to_string()).collect_vec()
+    }
+
+    pub fn health(&self) -> WordsPassHealth {
+        let word_count = self.word_list().len();
+        match self.mnemonic() {
+            Ok(m) => WordsPassHealth {
+                word_count,
+                valid_bip39: true,
+                entropy_bits: Some(m.to_entropy().len() * 8),
+                checksum_words: self.checksum_words().ok(),
+                error: None,
+            },
+            Err(e) => WordsPassHealth {
+                word_count,
+                valid_bip39: false,
+                entropy_bits: None,
+                checksum_words: None,
+                error: Some(e.message.clone()),
+            }
+        }
+    }
+
+    /// Random zero-indexed word positions used to quiz a user on their backup.
+    pub fn random_word_positions(&self, count: usize) -> Vec<usize> {
+        let len = self.word_list().len();
+        let mut rng = rand::thread_rng();
+        let mut positions = rand::seq::index::sample(&mut rng, len, count.min(len)).into_vec();
+        positions.sort();
+        positions
+    }
+
+    pub fn verify_word_positions(&self, answers: &Vec<(usize, String)>) -> bool {
+        let words = self.word_list();
+        !answers.is_empty() && answers.iter().all(|(i, w)| {
+            words.get(*i).map(|expected| expected == &w.trim().to_lowercase()).unwrap_or(false)
+        })
+    }
+
     pub fn pair(&self) -> RgResult<(Mnemonic, Option<String>)> {
         Ok((self.mnemonic()?, self.passphrase.clone()))
     }
@@ -273,6 +363,39 @@ pub fn generate_test() {
     assert_eq!(24, w.words.split(" ").collect_vec().len());
 }
 
+#[test]
+pub fn health_and_word_positions() {
+    let w = WordsPass::generate().expect("words");
+    let health = w.health();
+    assert!(health.valid_bip39);
+    assert_eq!(24, health.word_count);
+    assert_eq!(Some(256), health.entropy_bits);
+    let positions = w.random_word_positions(3);
+    assert_eq!(3, positions.len());
+    let words = w.word_list();
+    let answers = positions.iter().map(|i| (*i, words[*i].to_uppercase())).collect_vec();
+    assert!(w.verify_word_positions(&answers));
+    let wrong = positions.iter().map(|i| (*i, "zzz".to_string())).collect_vec();
+    assert!(!w.verify_word_positions(&wrong));
+    assert!(!WordsPass::words("not a valid mnemonic".to_string()).health().valid_bip39);
+}
+
+#[test]
+pub fn bip85_test_vectors() {
+    // From the BIP-85 specification
+    let xprv = ExtendedPrivKey::from_str(
+        "xprv9s21ZrQH143K2LBWUUQRFXhucrQqBpKdRRxNVq2zBqsx8HVqFk2uYo8kmbaLLHRdqtQpUm98uKfu3vca1LqdGhUtyoFnCNkfmXRyPXLjbKb"
+    ).expect("xprv");
+    let m12 = bip85_mnemonic(&xprv, 12, 0).expect("12 words");
+    assert_eq!(m12.to_string(), "girl mad pet galaxy egg matter matrix prison refuse sense ordinary nose");
+    let m24 = bip85_mnemonic(&xprv, 24, 0).expect("24 words");
+    assert_eq!(
+        m24.to_string(),
+        "puppy ocean match cereal symbol another shed magic wrap hammer bulb intact gadget divorce twin tonight reason outdoor destroy simple truth cigar social volcano"
+    );
+    assert!(bip85_mnemonic(&xprv, 15, 0).is_err());
+}
+
 #[test]
 pub fn generate_xpub() {
     let w = WordsPass::generate().expect("words");
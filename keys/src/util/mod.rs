@@ -14,8 +14,11 @@ use crate::{TestConstants, util};
 
 pub mod mnemonic_builder;
 pub mod mnemonic_support;
+pub mod shamir_backup;
 pub mod bitcoin_message_signer;
 pub mod btc_wallet;
+pub mod btc_backend;
+pub mod btc_checkpoint;
 pub mod keys;
 
 // TODO: Replace with our own signature type
@@ -0,0 +1,69 @@
+use bdk::keys::bip39::Mnemonic;
+use itertools::Itertools;
+use redgold_schema::{error_info, ErrorInfoContext, RgResult};
+use crate::util::mnemonic_support::WordsPass;
+
+// SLIP-39 PBKDF2 iteration exponent, 10000 * 2^e rounds encrypting the master secret
+const SLIP39_ITERATION_EXPONENT: u8 = 1;
+
+/// Splits a mnemonic into SLIP-39 shares, any `threshold` of which recover it.
+///
+/// The shared secret is the BIP-39 entropy, so recovery gives back the original words. A
+/// BIP-39 passphrase on the WordsPass is not part of the shares and must be backed up separately.
+pub fn slip39_shares(words: &WordsPass, threshold: u8, share_count: u8) -> RgResult<Vec<String>> {
+    if threshold == 0 || threshold > share_count || share_count > 16 {
+        return Err(error_info(format!(
+            "Invalid SLIP-39 {}-of-{} split, need 1 <= threshold <= shares <= 16", threshold, share_count
+        )));
+    }
+    let entropy = words.mnemonic()?.to_entropy();
+    let groups = sssmc39::generate_mnemonics(1, &[(threshold, share_count)], &entropy, "", SLIP39_ITERATION_EXPONENT)
+        .map_err(|e| error_info(format!("Failed to generate SLIP-39 shares: {}", e)))?;
+    let mut shares = vec![];
+    for g in groups {
+        let list = g.mnemonic_list()
+            .map_err(|e| error_info(format!("Failed to encode SLIP-39 shares: {}", e)))?;
+        shares.extend(list.into_iter().map(|w| w.join(" ")));
+    }
+    Ok(shares)
+}
+
+/// Recovers the original mnemonic from at least a threshold of SLIP-39 shares.
+pub fn slip39_recover(shares: &Vec<String>) -> RgResult<WordsPass> {
+    let mnemonics = shares.iter()
+        .map(|s| s.split_whitespace().map(|w| w.to_lowercase()).collect_vec())
+        .filter(|w| !w.is_empty())
+        .collect_vec();
+    let entropy = sssmc39::combine_mnemonics(&mnemonics, "")
+        .map_err(|e| error_info(format!("Failed to recover from SLIP-39 shares: {}", e)))?;
+    let m = Mnemonic::from_entropy(&entropy).error_info("Recovered secret is not valid mnemonic entropy")?;
+    Ok(WordsPass::words(m.to_string()))
+}
+
+/// Checks that every threshold sized combination of the shares recovers the given mnemonic,
+/// returning the first combination (by share index) that doesn't.
+pub fn slip39_verify_shares(words: &WordsPass, shares: &Vec<String>, threshold: usize) -> RgResult<()> {
+    let expected = words.mnemonic()?.to_string();
+    for combination in (0..shares.len()).combinations(threshold) {
+        let subset = combination.iter().map(|i| shares[*i].clone()).collect_vec();
+        let recovered = slip39_recover(&subset)?;
+        if recovered.words != expected {
+            return Err(error_info(format!(
+                "Shares {} recover a different mnemonic", combination.iter().map(|i| (i + 1).to_string()).join(", ")
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn slip39_split_and_recover() {
+    let words = WordsPass::generate().expect("words");
+    let shares = slip39_shares(&words, 2, 3).expect("shares");
+    assert_eq!(shares.len(), 3);
+    assert!(slip39_verify_shares(&words, &shares, 2).is_ok());
+    let recovered = slip39_recover(&vec![shares[2].clone(), shares[0].clone()]).expect("recover");
+    assert_eq!(recovered.words, words.words);
+    assert!(slip39_recover(&vec![shares[1].clone()]).is_err());
+    assert!(slip39_shares(&words, 4, 3).is_err());
+}
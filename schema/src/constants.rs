@@ -41,3 +41,12 @@ pub fn default_node_internal_derivation_path(account: i64) -> String {
 pub fn redgold_keypair_change_path(change: i64) -> String {
     format!("m/{REDGOLD_PURPOSE}'/{REDGOLD_KEY_DERIVATION_PATH}'/0'/0/{change}")
 }
+
+pub fn redgold_account_path() -> String {
+    format!("m/{REDGOLD_PURPOSE}'/{REDGOLD_KEY_DERIVATION_PATH}'/0'")
+}
+
+// Internal chain used for change outputs, as opposed to the external receive chain above
+pub fn redgold_keypair_internal_change_path(index: i64) -> String {
+    format!("m/{REDGOLD_PURPOSE}'/{REDGOLD_KEY_DERIVATION_PATH}'/0'/1/{index}")
+}
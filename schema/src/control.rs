@@ -6,6 +6,8 @@ impl ControlRequest {
         Self {
             control_multiparty_keygen_request: None,
             control_multiparty_signing_request: None,
+            control_log_level_request: None,
+            control_cold_sweep_request: None,
         }
     }
 }
\ No newline at end of file
@@ -52,6 +52,10 @@ pub mod transaction_info;
 pub mod exec;
 pub mod contract;
 pub mod local_stored_state;
+pub mod payment_uri;
+pub mod state_proof;
+pub mod name_registry;
+pub mod order_book;
 mod weighting;
 
 
@@ -660,7 +664,27 @@ impl Request {
     }
 
     pub fn auth_required(&self) -> bool {
-        self.initiate_keygen.is_some() || self.initiate_signing.is_some()
+        self.initiate_keygen.is_some() || self.initiate_signing.is_some() || self.watcher_heartbeat.is_some()
+    }
+
+    /// Read only queries which are safe to retry or race against several peers. A request also
+    /// carrying any gossip, submission or multiparty payload is never treated as one.
+    pub fn is_idempotent_read(&self) -> bool {
+        let read = self.about_node_request.is_some() || self.hash_search_request.is_some()
+            || self.download_request.is_some() || self.lookup_transaction_request.is_some()
+            || self.get_peers_info_request.is_some() || self.genesis_request.is_some()
+            || self.state_checkpoint_request.is_some();
+        let mutating = self.gossip_transaction_request.is_some()
+            || self.gossip_observation_request.is_some()
+            || !self.gossip_observation_proof.is_empty()
+            || self.submit_transaction_request.is_some()
+            || self.utxo_conflict_resolve_request.is_some()
+            || self.initiate_keygen.is_some()
+            || self.initiate_signing.is_some()
+            || self.multiparty_authentication_request.is_some()
+            || self.faucet_request.is_some()
+            || self.watcher_heartbeat.is_some();
+        read && !mutating
     }
 
 }
@@ -676,6 +700,17 @@ mod tests {
         // let result = add(2, 2);
         // assert_eq!(result, 4);
     }
+
+    #[test]
+    fn idempotent_reads_exclude_mutations() {
+        let mut r = Request::empty();
+        assert!(!r.is_idempotent_read());
+        r.download_request = Some(structs::DownloadRequest::default());
+        assert!(r.is_idempotent_read());
+        r.submit_transaction_request = Some(structs::SubmitTransactionRequest::default());
+        assert!(!r.is_idempotent_read());
+        assert!(Request::empty().about().is_idempotent_read());
+    }
 }
 
 impl NetworkEnvironment {
@@ -919,4 +954,10 @@ impl ContentionKey {
         s.selector = selector.cloned();
         s
     }
+
+    pub fn name_registration(name: &str) -> ContentionKey {
+        let mut s = Self::default();
+        s.name = name.to_string();
+        s
+    }
 }
@@ -1,14 +1,45 @@
 use itertools::{Either, Itertools};
 use serde::{Deserialize, Serialize};
 use crate::servers::Server;
-use crate::structs::{Address, PeerId, PublicKey, TrustRatingLabel};
+use crate::structs::{Address, PeerId, PublicKey, SupportedCurrency, TrustRatingLabel};
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct NamedXpub {
     pub name: String,
     pub derivation_path: String,
     pub xpub: String,
-    pub hot_offset: Option<String>
+    pub hot_offset: Option<String>,
+    // Whether a BIP-39 passphrase was applied when deriving this xpub, None when unknown
+    // i.e. hardware entries or those saved before this was recorded
+    #[serde(default)]
+    pub uses_passphrase: Option<bool>,
+    // Checksum of the passphrase including seed of software entries, to detect a wrong passphrase on load
+    #[serde(default)]
+    pub seed_checksum: Option<String>,
+    // Hardened account component of the derivation path, i.e. 50 for m/44'/0'/50'
+    #[serde(default)]
+    pub account_index: Option<u32>,
+}
+
+impl NamedXpub {
+    pub fn account_from_path(derivation_path: &str) -> Option<u32> {
+        derivation_path.split('/').nth(3)
+            .and_then(|a| a.trim_end_matches(|c| c == '\'' || c == 'h').parse::<u32>().ok())
+    }
+
+    /// Fills metadata derivable from older stored entries, returning whether anything changed.
+    pub fn migrate(&mut self) -> bool {
+        let mut changed = false;
+        if self.account_index.is_none() {
+            self.account_index = Self::account_from_path(&self.derivation_path);
+            changed |= self.account_index.is_some();
+        }
+        if self.hot_offset.as_ref().map(|o| o.is_empty()).unwrap_or(false) {
+            self.hot_offset = None;
+            changed = true;
+        }
+        changed
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -20,10 +51,20 @@ pub struct SavedAddress {
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct WatchedAddress {
-    name: String,
-    address: Address,
-    alert_all: bool,
-    alert_outgoing: bool
+    pub name: String,
+    pub address: Address,
+    pub alert_all: bool,
+    pub alert_outgoing: bool
+}
+
+impl WatchedAddress {
+    /// Whether a balance change from previous to current should raise a notification
+    pub fn should_alert(&self, previous: i64, current: i64) -> bool {
+        if current == previous {
+            return false;
+        }
+        self.alert_all || (self.alert_outgoing && current < previous)
+    }
 }
 
 
@@ -51,15 +92,89 @@ pub struct StoredMnemonic {
     pub name: String,
     pub mnemonic: String,
     pub persist_disk: Option<bool>,
+    // Result of the most recent backup verification quiz, if any was taken
+    pub backup_verified: Option<bool>,
+    pub backup_verified_time: Option<i64>,
+    // Secret held in the OS keychain, the mnemonic field is left empty on disk
+    pub os_keystore: Option<bool>,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq)]
 pub struct StoredPrivateKey {
     pub name: String,
     pub key_hex: String,
+    // Secret held in the OS keychain, the key field is left empty on disk
+    pub os_keystore: Option<bool>,
 }
 
 
+/// Per-session limit on hot key spending in the GUI, sends past it need extra confirmation.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct SpendingLimits {
+    // Total USD value signed by hot keys in one session before confirmation is required
+    pub session_limit_usd: f64,
+    // Re-enter the session password instead of waiting out the countdown
+    pub require_password: bool,
+    pub confirm_countdown_secs: u64,
+}
+
+impl Default for SpendingLimits {
+    fn default() -> Self {
+        Self {
+            session_limit_usd: 100.0,
+            require_password: false,
+            confirm_countdown_secs: 10,
+        }
+    }
+}
+
+impl SpendingLimits {
+    /// Whether signing a send worth `usd` after `spent_usd` this session needs confirmation,
+    /// unpriced sends always do since their value can't be checked.
+    pub fn requires_confirmation(&self, spent_usd: f64, usd: Option<f64>) -> bool {
+        usd.map(|u| spent_usd + u > self.session_limit_usd).unwrap_or(true)
+    }
+}
+
+/// A named wallet account backed by a stored xpub or hot key, with display preferences.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Account {
+    pub name: String,
+    // Name of the NamedXpub this account is derived from
+    pub xpub_name: Option<String>,
+    // Name of the stored mnemonic or private key for hot accounts
+    pub key_name: Option<String>,
+    // Display color as #RRGGBB hex
+    pub color: Option<String>,
+    // Currencies shown for this account, all when empty
+    #[serde(default)]
+    pub currencies: Vec<SupportedCurrency>,
+}
+
+impl Account {
+    pub fn for_xpub(xpub_name: impl Into<String>) -> Self {
+        let name = xpub_name.into();
+        Self {
+            name: name.clone(),
+            xpub_name: Some(name),
+            key_name: None,
+            color: None,
+            currencies: vec![],
+        }
+    }
+
+    pub fn shows_currency(&self, currency: &SupportedCurrency) -> bool {
+        self.currencies.is_empty() || self.currencies.contains(currency)
+    }
+}
+
+// Next unused index on the internal (change) chain of an xpub
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct ChangeIndex {
+    pub xpub: String,
+    pub next_index: i64,
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct LocalStoredState {
     pub servers: Vec<Server>,
@@ -71,7 +186,22 @@ pub struct LocalStoredState {
     pub email_alert_config: Option<String>,
     pub identities: Vec<Identity>,
     pub mnemonics: Option<Vec<StoredMnemonic>>,
-    pub private_keys: Option<Vec<StoredPrivateKey>>
+    pub private_keys: Option<Vec<StoredPrivateKey>>,
+    pub change_indexes: Option<Vec<ChangeIndex>>,
+    // Addresses not owned by local keys, monitored for balance changes
+    pub watched_addresses: Option<Vec<WatchedAddress>>,
+    pub accounts: Option<Vec<Account>>,
+    pub spending_limits: Option<SpendingLimits>,
+    // GUI language code, English when unset
+    pub locale: Option<String>,
+    // GUI pixels per point, the default scale when unset
+    pub ui_scale: Option<f32>,
+    // GUI body text size in points, other text styles scale with it
+    pub font_size: Option<f32>,
+    // Keep hot mnemonics and private keys in the OS keychain instead of this state
+    pub use_os_keystore: Option<bool>,
+    // Ids of incoming payment requests already accepted or declined
+    pub handled_payment_requests: Option<Vec<String>>,
 }
 
 impl LocalStoredState {
@@ -81,6 +211,20 @@ impl LocalStoredState {
                 mnemonic.persist_disk.unwrap_or(true)
             }).map(|d| d.clone()).collect_vec()
         });
+        for m in self.mnemonics.iter_mut().flatten() {
+            if m.os_keystore.unwrap_or(false) {
+                m.mnemonic = "".to_string();
+            }
+        }
+        for k in self.private_keys.iter_mut().flatten() {
+            if k.os_keystore.unwrap_or(false) {
+                k.key_hex = "".to_string();
+            }
+        }
+    }
+
+    pub fn os_keystore_enabled(&self) -> bool {
+        self.use_os_keystore.unwrap_or(false)
     }
 }
 
@@ -114,6 +258,66 @@ impl LocalStoredState {
     }
 }
 
+impl LocalStoredState {
+    pub fn next_change_index(&self, xpub: &String) -> i64 {
+        self.change_indexes.as_ref()
+            .and_then(|c| c.iter().find(|c| &c.xpub == xpub))
+            .map(|c| c.next_index)
+            .unwrap_or(0)
+    }
+
+    /// Mark a change index as used so the next transaction derives a fresh one.
+    pub fn mark_change_index_used(&mut self, xpub: &String, index: i64) {
+        let next_index = (index + 1).max(self.next_change_index(xpub));
+        let mut updated = self.change_indexes.clone().unwrap_or(vec![]).into_iter()
+            .filter(|c| &c.xpub != xpub)
+            .collect_vec();
+        updated.push(ChangeIndex { xpub: xpub.clone(), next_index });
+        self.change_indexes = Some(updated);
+    }
+}
+
+impl LocalStoredState {
+    pub fn watched_addresses(&self) -> Vec<WatchedAddress> {
+        self.watched_addresses.clone().unwrap_or(vec![])
+    }
+
+    /// Stored accounts followed by an implicit account for each xpub not yet assigned to one.
+    pub fn accounts(&self) -> Vec<Account> {
+        let mut accounts = self.accounts.clone().unwrap_or(vec![]);
+        for x in self.xpubs.iter() {
+            if !accounts.iter().any(|a| a.xpub_name.as_ref() == Some(&x.name)) {
+                accounts.push(Account::for_xpub(x.name.clone()));
+            }
+        }
+        accounts
+    }
+
+    pub fn account(&self, name: &String) -> Option<Account> {
+        self.accounts().into_iter().find(|a| &a.name == name)
+    }
+
+    pub fn account_xpub(&self, account: &Account) -> Option<&NamedXpub> {
+        account.xpub_name.as_ref().and_then(|n| self.xpubs.iter().find(|x| &x.name == n))
+    }
+
+    pub fn upsert_account(&mut self, account: Account) {
+        let mut updated = self.accounts.clone().unwrap_or(vec![]).into_iter()
+            .filter(|a| a.name != account.name)
+            .collect_vec();
+        updated.push(account);
+        self.accounts = Some(updated);
+    }
+
+    pub fn migrate_xpubs(&mut self) -> bool {
+        let mut changed = false;
+        for x in self.xpubs.iter_mut() {
+            changed |= x.migrate();
+        }
+        changed
+    }
+}
+
 impl Default for LocalStoredState {
     fn default() -> Self {
         Self {
@@ -127,6 +331,42 @@ impl Default for LocalStoredState {
             identities: vec![],
             mnemonics: None,
             private_keys: None,
+            change_indexes: None,
+            watched_addresses: None,
+            accounts: None,
+            spending_limits: None,
+            locale: None,
+            ui_scale: None,
+            font_size: None,
+            use_os_keystore: None,
+            handled_payment_requests: None,
         }
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn named_xpub_migration() {
+    let mut x = NamedXpub {
+        name: "test".to_string(),
+        derivation_path: "m/44'/0'/50'".to_string(),
+        xpub: "".to_string(),
+        hot_offset: Some("".to_string()),
+        uses_passphrase: None,
+        seed_checksum: None,
+        account_index: None,
+    };
+    assert!(x.migrate());
+    assert_eq!(x.account_index, Some(50));
+    assert_eq!(x.hot_offset, None);
+    assert!(!x.migrate());
+    assert_eq!(NamedXpub::account_from_path("m/84h/0h/3h"), Some(3));
+    assert_eq!(NamedXpub::account_from_path("m/44'"), None);
+}
+
+#[test]
+fn spending_limit_confirmation() {
+    let limits = SpendingLimits::default();
+    assert!(!limits.requires_confirmation(0.0, Some(50.0)));
+    assert!(limits.requires_confirmation(60.0, Some(50.0)));
+    assert!(limits.requires_confirmation(0.0, None));
+}
@@ -0,0 +1,119 @@
+use crate::structs::{Address, Error as RGError, NameRegistration, StandardData, Transaction};
+use crate::{error_message, RgResult, SafeOption};
+
+pub const MIN_NAME_LENGTH: usize = 3;
+pub const MAX_NAME_LENGTH: usize = 32;
+// Registrations and renewals can't extend further ahead than this
+pub const MAX_NAME_REGISTRATION_MS: i64 = 1000 * 60 * 60 * 24 * 366;
+// A lapsed name stays reserved for its previous owner to renew for this long
+pub const NAME_RENEWAL_GRACE_MS: i64 = 1000 * 60 * 60 * 24 * 30;
+// Registering transactions must be stamped within this long of the accepting node's clock
+pub const MAX_NAME_REGISTRATION_TIME_SKEW_MS: i64 = 1000 * 60 * 5;
+
+/// Names are lowercase ascii letters, digits and inner hyphens, so they can't be confused with
+/// addresses, which are longer, or with each other through case.
+pub fn validate_name(name: &str) -> RgResult<()> {
+    let valid_chars = name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+    let valid = valid_chars
+        && name.len() >= MIN_NAME_LENGTH
+        && name.len() <= MAX_NAME_LENGTH
+        && !name.starts_with('-')
+        && !name.ends_with('-');
+    if !valid {
+        return Err(error_message(RGError::InvalidName, format!(
+            "Name {} must be {} to {} lowercase letters, digits or inner hyphens",
+            name, MIN_NAME_LENGTH, MAX_NAME_LENGTH
+        )));
+    }
+    Ok(())
+}
+
+/// Checks a registration by `owner` at `now` against the name's current holder and expiry, if
+/// any. Renewals by the holder are allowed at any time, other owners only once the name has
+/// lapsed past the grace period.
+pub fn validate_registration(
+    registration: &NameRegistration,
+    owner: &Address,
+    now: i64,
+    current: Option<(&Address, i64)>,
+) -> RgResult<()> {
+    validate_name(&registration.name)?;
+    registration.address.safe_get_msg("Missing name registration address")?;
+    if registration.expiry_time <= now {
+        return Err(error_message(RGError::InvalidName, "Name registration expiry is in the past"));
+    }
+    if registration.expiry_time > now + MAX_NAME_REGISTRATION_MS {
+        return Err(error_message(RGError::InvalidName, "Name registration expiry is too far in the future"));
+    }
+    if let Some((current_owner, current_expiry)) = current {
+        let reserved = now < current_expiry + NAME_RENEWAL_GRACE_MS;
+        if reserved && current_owner != owner {
+            return Err(error_message(RGError::NameUnavailable, format!(
+                "Name {} is registered to another owner", registration.name
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Rejects registrations whose client chosen time is far from the node clock, so a forged time
+/// can't be used to claim a name early.
+pub fn validate_registration_time(tx_time: i64, now: i64) -> RgResult<()> {
+    if (tx_time - now).abs() > MAX_NAME_REGISTRATION_TIME_SKEW_MS {
+        return Err(error_message(RGError::InvalidName, format!(
+            "Name registration transaction time {} is too far from node time {}", tx_time, now
+        )));
+    }
+    Ok(())
+}
+
+impl StandardData {
+    pub fn name_registration(name: impl Into<String>, address: &Address, expiry_time: i64) -> Self {
+        let mut sd = Self::default();
+        sd.name_registration = Some(NameRegistration {
+            name: name.into(),
+            address: Some(address.clone()),
+            expiry_time,
+        });
+        sd
+    }
+}
+
+impl Transaction {
+    pub fn name_registrations(&self) -> Vec<&NameRegistration> {
+        self.outputs.iter()
+            .filter_map(|o| o.data.as_ref())
+            .filter_map(|d| d.name_registration.as_ref())
+            .collect()
+    }
+
+    /// Owner of any name registered by this transaction, the signer of its first input.
+    pub fn name_owner(&self) -> RgResult<Address> {
+        self.inputs.first().safe_get_msg("Name registration requires an input")?.address()
+    }
+}
+
+#[test]
+fn name_registration_rules() {
+    assert!(validate_name("alice-01").is_ok());
+    let long = "a".repeat(MAX_NAME_LENGTH + 1);
+    for bad in vec!["ab", "Alice", "-alice", "alice-", "al ice", long.as_str()] {
+        assert!(validate_name(bad).is_err(), "{}", bad);
+    }
+    let owner = Address::script_hash("owner").expect("address");
+    let other = Address::script_hash("other").expect("address");
+    let now = 1000;
+    let mut reg = StandardData::name_registration("alice", &owner, now + 10).name_registration.expect("reg");
+    assert!(validate_registration(&reg, &owner, now, None).is_ok());
+    assert!(validate_registration_time(now + MAX_NAME_REGISTRATION_TIME_SKEW_MS, now).is_ok());
+    assert!(validate_registration_time(now + MAX_NAME_REGISTRATION_TIME_SKEW_MS + 1, now).is_err());
+    assert!(validate_registration_time(now - MAX_NAME_REGISTRATION_TIME_SKEW_MS - 1, now).is_err());
+    // Held by someone else until the grace period after expiry ends
+    assert!(validate_registration(&reg, &owner, now, Some((&other, now + 5))).is_err());
+    let lapsed = now - NAME_RENEWAL_GRACE_MS - 1;
+    assert!(validate_registration(&reg, &owner, now, Some((&other, lapsed))).is_ok());
+    // Renewal by the holder
+    assert!(validate_registration(&reg, &owner, now, Some((&owner, now + 5))).is_ok());
+    reg.expiry_time = now + MAX_NAME_REGISTRATION_MS + 1;
+    assert!(validate_registration(&reg, &owner, now, None).is_err());
+}
@@ -0,0 +1,50 @@
+use serde::{Deserialize, Serialize};
+
+/// Liquidity available at one price of the AMM curve, priced in RDG per BTC with both amounts
+/// in satoshis and the volume in RDG.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct OrderBookLevel {
+    pub price: f64,
+    pub volume: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct OrderBookCurve {
+    pub bids: Vec<OrderBookLevel>,
+    pub asks: Vec<OrderBookLevel>,
+    pub center_price: f64,
+}
+
+/// Deposit bounds outside of which orders are refunded rather than filled.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct OrderBookLimits {
+    pub min_btc: Option<u64>,
+    pub max_btc: Option<u64>,
+    pub min_rdg: Option<u64>,
+    pub max_rdg: Option<u64>,
+    pub active_since: i64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct OrderBookFill {
+    pub time: i64,
+    // BTC deposit filled from the asks, otherwise an RDG deposit filled from the bids
+    pub is_ask: bool,
+    pub price_btc_per_rdg: f64,
+    pub rdg_amount: u64,
+}
+
+/// The AMM pool as published on the node's `order-book` endpoint, for clients which don't
+/// depend on the node crate.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct OrderBook {
+    pub public_key: String,
+    pub rdg_address: String,
+    pub rdg_balance: f64,
+    pub btc_address: String,
+    pub btc_balance: f64,
+    pub bid_ask: OrderBookCurve,
+    pub order_limits: Option<OrderBookLimits>,
+    #[serde(default)]
+    pub recent_fills: Vec<OrderBookFill>,
+}
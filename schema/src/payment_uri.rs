@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+use crate::{error_info, RgResult};
+use crate::structs::{Address, SupportedCurrency};
+
+/// Payment link for an address with an optional requested amount, `redgold:` for RDG and
+/// BIP-21 `bitcoin:` for BTC.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PaymentUri {
+    pub currency: SupportedCurrency,
+    pub address: String,
+    pub amount: Option<f64>,
+}
+
+// Amounts are rendered with at most satoshi precision and no trailing zeros
+fn format_amount(amount: f64) -> String {
+    let s = format!("{:.8}", amount);
+    s.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+impl PaymentUri {
+    pub fn new(currency: SupportedCurrency, address: impl Into<String>, amount: Option<f64>) -> Self {
+        Self {
+            currency,
+            address: address.into(),
+            amount,
+        }
+    }
+
+    pub fn scheme(&self) -> &'static str {
+        match self.currency {
+            SupportedCurrency::Bitcoin => "bitcoin",
+            _ => "redgold",
+        }
+    }
+
+    pub fn to_uri(&self) -> String {
+        let mut uri = format!("{}:{}", self.scheme(), self.address);
+        if let Some(a) = self.amount.filter(|a| *a > 0.0) {
+            uri.push_str(&format!("?amount={}", format_amount(a)));
+        }
+        uri
+    }
+
+    /// Parses a `redgold:` or `bitcoin:` link, reading the `amount` query parameter and
+    /// ignoring any others.
+    pub fn parse(uri: impl Into<String>) -> RgResult<Self> {
+        let uri = uri.into();
+        let uri = uri.trim();
+        let (scheme, rest) = uri.split_once(':')
+            .ok_or(error_info(format!("Missing scheme in payment uri {}", uri)))?;
+        let currency = match scheme.to_lowercase().as_str() {
+            "redgold" => SupportedCurrency::Redgold,
+            "bitcoin" => SupportedCurrency::Bitcoin,
+            _ => return Err(error_info(format!("Unsupported payment uri scheme {}", scheme))),
+        };
+        let rest = rest.trim_start_matches("//");
+        let (address, query) = rest.split_once('?').unwrap_or((rest, ""));
+        if address.is_empty() {
+            return Err(error_info(format!("Missing address in payment uri {}", uri)));
+        }
+        let mut amount = None;
+        for (k, v) in query.split('&').filter_map(|kv| kv.split_once('=')) {
+            if k.eq_ignore_ascii_case("amount") {
+                let a = v.parse::<f64>()
+                    .map_err(|e| error_info(format!("Invalid amount {} in payment uri: {}", v, e)))?;
+                amount = Some(a);
+            }
+        }
+        if currency == SupportedCurrency::Redgold {
+            Address::parse(address.to_string())?;
+        }
+        Ok(Self::new(currency, address, amount))
+    }
+
+    /// Recognizes a payment link or a bare address in arbitrary text, such as clipboard contents.
+    pub fn detect(text: &str) -> Option<Self> {
+        let text = text.trim();
+        if text.is_empty() || text.contains(char::is_whitespace) {
+            return None;
+        }
+        if let Ok(uri) = Self::parse(text) {
+            return Some(uri);
+        }
+        if Address::parse(text.to_string()).is_ok() {
+            return Some(Self::new(SupportedCurrency::Redgold, text, None));
+        }
+        if looks_like_bitcoin_address(text) {
+            return Some(Self::new(SupportedCurrency::Bitcoin, text, None));
+        }
+        None
+    }
+}
+
+// Shape check only, the send flow validates against the active network before signing
+fn looks_like_bitcoin_address(s: &str) -> bool {
+    let lower = s.to_lowercase();
+    let bech32 = ["bc1", "tb1", "bcrt1"].iter().any(|p| lower.starts_with(p)) && (14..=90).contains(&s.len());
+    let base58 = ['1', '3', 'm', 'n', '2'].iter().any(|p| s.starts_with(*p)) && (26..=35).contains(&s.len());
+    (bech32 || base58) && s.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+#[test]
+fn payment_uri_render() {
+    let uri = PaymentUri::new(SupportedCurrency::Redgold, "abc", Some(1.5));
+    assert_eq!(uri.to_uri(), "redgold:abc?amount=1.5");
+    let uri = PaymentUri::new(SupportedCurrency::Bitcoin, "tb1q", Some(0.00010000));
+    assert_eq!(uri.to_uri(), "bitcoin:tb1q?amount=0.0001");
+    assert_eq!(PaymentUri::new(SupportedCurrency::Redgold, "abc", None).to_uri(), "redgold:abc");
+}
+
+#[test]
+fn payment_uri_parse_and_detect() {
+    let uri = PaymentUri::parse("bitcoin:tb1qxyz?amount=0.0001&label=test").expect("parse");
+    assert_eq!(uri, PaymentUri::new(SupportedCurrency::Bitcoin, "tb1qxyz", Some(0.0001)));
+    assert_eq!(PaymentUri::parse(uri.to_uri()).expect("roundtrip"), uri);
+    assert!(PaymentUri::parse("redgold:nothex").is_err());
+    assert!(PaymentUri::parse("mailto:abc").is_err());
+    assert!(PaymentUri::parse("bitcoin:tb1q?amount=x").is_err());
+    let detected = PaymentUri::detect(" tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx ").expect("detect");
+    assert_eq!(detected.currency, SupportedCurrency::Bitcoin);
+    assert!(PaymentUri::detect("hello world").is_none());
+    assert!(PaymentUri::detect("").is_none());
+}
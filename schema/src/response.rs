@@ -72,6 +72,8 @@ impl ControlResponse {
             response_metadata: response_metadata(),
             control_multiparty_keygen_response: None,
             control_multiparty_signing_response: None,
+            control_log_level_response: None,
+            control_cold_sweep_response: None,
         }
     }
 
@@ -19,10 +19,17 @@ pub struct Server {
     pub username: Option<String>,
     pub ipv4: Option<String>,
     pub node_name: Option<String>,
-    pub external_host: Option<String>
+    pub external_host: Option<String>,
+    // Pinned docker image tag for this server, defaults to the network environment tag
+    pub image_tag: Option<String>,
 }
 
 impl Server {
+    /// Key used to record per server deployment history
+    pub fn deploy_key(&self, network: &NetworkEnvironment) -> String {
+        format!("{}-{}-{}", network.to_std_string(), self.index, self.host)
+    }
+
     pub fn network_environment(&self) -> NetworkEnvironment {
         NetworkEnvironment::parse(self.network_environment.clone())
     }
@@ -97,6 +104,7 @@ impl Server {
             // TODO: Change to mainnet later
             network_environment: NetworkEnvironment::All.to_std_string(),
             external_host: Some(host),
+            image_tag: None,
         }
     }
 
@@ -108,6 +116,37 @@ impl Server {
     pub fn parse(contents: String) -> Result<Vec<Self>, ErrorInfo> {
         parse_servers(&contents)
     }
+
+    /// Renders servers in the same CSV layout read by parse.
+    pub fn to_csv(servers: &Vec<Self>) -> RgResult<String> {
+        let mut wtr = csv::Writer::from_writer(vec![]);
+        for s in servers {
+            wtr.serialize(s).error_info("server line write failure")?;
+        }
+        let bytes = wtr.into_inner().error_info("servers csv flush failure")?;
+        String::from_utf8(bytes).error_info("servers csv utf8 failure")
+    }
+
+    pub fn write_to_file(path: PathBuf, servers: &Vec<Self>) -> RgResult<()> {
+        Self::validate_all(servers)?;
+        fs::write(path, Self::to_csv(servers)?).error_info("Servers file write failure")
+    }
+
+    /// Deploys, offline key generation and metrics address servers by index, so it must be unique.
+    pub fn validate_all(servers: &Vec<Self>) -> RgResult<()> {
+        for s in servers {
+            if s.host.trim().is_empty() {
+                return Err(error_info(format!("Server {} is missing a host", s.index)));
+            }
+            if s.index < 0 || s.peer_id_index < 0 {
+                return Err(error_info(format!("Server {} has a negative index", s.host)));
+            }
+        }
+        if servers.iter().map(|s| s.index).unique().count() != servers.len() {
+            return Err(error_info("Duplicate server index"));
+        }
+        Ok(())
+    }
 }
 
 #[test]
@@ -115,4 +154,18 @@ fn parse_server_example() {
     let str = include_str!("./resources/example_servers");
     let servers = Server::parse(str.to_string()).unwrap();
     println!("{}", json_pretty(&servers).expect(""))
+}
+
+#[test]
+fn servers_csv_round_trip() {
+    let str = include_str!("./resources/example_servers");
+    let mut servers = Server::parse(str.to_string()).unwrap();
+    Server::validate_all(&servers).expect("valid");
+    let written = Server::to_csv(&servers).expect("csv");
+    let parsed = Server::parse(written).expect("parse");
+    assert_eq!(parsed.len(), servers.len());
+    assert_eq!(parsed[1].username, Some("ubuntu".to_string()));
+    assert_eq!(parsed[2].external_host, servers[2].external_host);
+    servers[1].index = 0;
+    assert!(Server::validate_all(&servers).is_err());
 }
\ No newline at end of file
@@ -0,0 +1,92 @@
+use crate::structs::{Hash, MerkleProof, StateCheckpoint, UtxoEntry, UtxoId, UtxoStateProof};
+use crate::{error_info, RgResult, SafeOption, WithMetadataHashable};
+
+impl StateCheckpoint {
+    /// Excludes the observation height, which differs per node, so peers sign the same hash.
+    pub fn signing_hash(&self) -> RgResult<Hash> {
+        let root = self.state_root.safe_get_msg("Missing checkpoint state root")?;
+        Ok(Hash::from_string_calculate(&format!(
+            "{}:{}:{}", self.time, self.transaction_count, root.hex()
+        )))
+    }
+}
+
+/// Hash the serving node signs to attest a UTXO was unspent as of a checkpoint.
+pub fn unspent_attestation_hash(utxo_id: &UtxoId, checkpoint_time: i64) -> RgResult<Hash> {
+    let tx_hash = utxo_id.transaction_hash.safe_get_msg("Missing UTXO transaction hash")?;
+    Ok(Hash::from_string_calculate(&format!(
+        "unspent:{}:{}:{}", tx_hash.hex(), utxo_id.output_index, checkpoint_time
+    )))
+}
+
+/// Verifies the path starts at the expected leaf and returns the root it commits to.
+pub fn verify_merkle_path(proof: &MerkleProof, leaf: &Hash) -> RgResult<Hash> {
+    let proof_leaf = proof.leaf.safe_get_msg("Missing merkle proof leaf")?;
+    if proof_leaf.vec() != leaf.vec() {
+        return Err(error_info(format!("Merkle proof leaf {} does not match {}", proof_leaf.hex(), leaf.hex())));
+    }
+    // An empty path would accept any root
+    if proof.nodes.is_empty() || proof.nodes.len() % 2 != 0 {
+        return Err(error_info("Malformed merkle proof path"));
+    }
+    proof.verify()?;
+    Ok(proof.root.safe_get_msg("Missing merkle proof root")?.clone())
+}
+
+impl UtxoStateProof {
+
+    /// Checks the output belongs to the transaction and that the transaction is committed to by
+    /// the checkpoint root through the chain of later segment roots. Signatures are checked by
+    /// the keys crate.
+    pub fn verify_inclusion(&self) -> RgResult<UtxoEntry> {
+        let utxo_id = self.utxo_id.safe_get_msg("Missing UTXO id")?;
+        let tx = self.transaction.safe_get_msg("Missing transaction")?;
+        let tx_hash = tx.hash_or();
+        let expected_hash = utxo_id.transaction_hash.safe_get_msg("Missing UTXO transaction hash")?;
+        if expected_hash.vec() != tx_hash.vec() {
+            return Err(error_info(format!("Transaction {} does not create UTXO {}", tx_hash.hex(), utxo_id.format_str())));
+        }
+        let output = usize::try_from(utxo_id.output_index).ok()
+            .and_then(|i| tx.outputs.get(i))
+            .safe_get_msg("UTXO output index out of range")?;
+        let mut root = verify_merkle_path(self.transaction_proof.safe_get_msg("Missing transaction proof")?, &tx_hash)?;
+        for p in &self.chain_proofs {
+            root = verify_merkle_path(p, &root)?;
+        }
+        let cp = self.checkpoint.safe_get_msg("Missing checkpoint")?;
+        let state_root = cp.state_root.safe_get_msg("Missing checkpoint state root")?;
+        if root.vec() != state_root.vec() {
+            return Err(error_info(format!("Proof root {} does not match checkpoint root {}", root.hex(), state_root.hex())));
+        }
+        Ok(UtxoEntry::from_output_new(output, &tx_hash, utxo_id.output_index, cp.time))
+    }
+}
+
+#[test]
+fn utxo_state_proof_chains_to_checkpoint_root() {
+    use crate::structs::{Output, Transaction};
+    use crate::util::merkle::build_root;
+    let mut tx = Transaction::default();
+    tx.outputs.push(Output::default());
+    let tx_hash = tx.hash_or();
+    let first = build_root(vec![Hash::from_string_calculate("a"), tx_hash.clone(), Hash::from_string_calculate("b")])
+        .expect("tree");
+    let second = build_root(vec![first.root.clone(), Hash::from_string_calculate("c")]).expect("tree");
+    let mut cp = StateCheckpoint::default();
+    cp.state_root = Some(second.root.clone());
+
+    let mut proof = UtxoStateProof::default();
+    proof.utxo_id = Some(UtxoId::new(&tx_hash, 0));
+    proof.transaction = Some(tx.clone());
+    proof.transaction_proof = Some(first.proof(tx_hash.clone()));
+    proof.chain_proofs = vec![second.proof(first.root.clone())];
+    proof.checkpoint = Some(cp);
+    assert!(proof.verify_inclusion().is_ok());
+
+    let mut missing_link = proof.clone();
+    missing_link.chain_proofs = vec![];
+    assert!(missing_link.verify_inclusion().is_err());
+    let mut bad_index = proof.clone();
+    bad_index.utxo_id = Some(UtxoId::new(&tx_hash, 1));
+    assert!(bad_index.verify_inclusion().is_err());
+}
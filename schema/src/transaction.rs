@@ -376,6 +376,31 @@ impl Transaction {
         total
     }
 
+    /// Total of the outputs explicitly marked as fees.
+    pub fn fee_amount(&self) -> i64 {
+        self.outputs.iter()
+            .filter(|o| o.output_type == Some(structs::OutputType::Fee as i32))
+            .filter_map(|o| o.opt_amount())
+            .sum()
+    }
+
+    /// Total of the fee marked outputs paying one of `fee_addresses`, fees sent anywhere else
+    /// don't compensate the nodes relaying the transaction.
+    pub fn fee_amount_paid_to(&self, fee_addresses: &[Address]) -> i64 {
+        self.outputs.iter()
+            .filter(|o| o.output_type == Some(structs::OutputType::Fee as i32))
+            .filter(|o| o.address.as_ref().map(|a| fee_addresses.contains(a)).unwrap_or(false))
+            .filter_map(|o| o.opt_amount())
+            .sum()
+    }
+
+    /// Whether any amount is sent other than as a fee, observations and metadata updates don't.
+    pub fn transfers_currency(&self) -> bool {
+        self.outputs.iter()
+            .filter(|o| o.output_type != Some(structs::OutputType::Fee as i32))
+            .any(|o| o.opt_amount().is_some())
+    }
+
     pub fn floating_inputs(&self) -> impl Iterator<Item = &FloatingUtxoId> {
         self.inputs.iter().filter_map(|i| i.floating_utxo_id.as_ref())
     }
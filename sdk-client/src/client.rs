@@ -0,0 +1,132 @@
+use std::sync::OnceLock;
+use std::time::Duration;
+use serde::de::DeserializeOwned;
+use redgold_schema::errors::EnhanceErrorInfo;
+use redgold_schema::{error_info, ErrorInfoContext, ProtoSerde, RgResult, SafeOption};
+use redgold_schema::order_book::OrderBook;
+use redgold_schema::structs::{AboutNodeRequest, AboutNodeResponse, Address, AddressInfo, FaucetRequest, FaucetResponse, HashSearchRequest, HashSearchResponse, Request, Response, SubmitTransactionRequest, SubmitTransactionResponse, Transaction};
+use redgold_schema::transaction::rounded_balance_i64;
+
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+// One connection pool for every client, timeouts are applied per request
+fn shared_http_client() -> reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(reqwest::Client::new).clone()
+}
+
+/// Typed client for the public node API, depending only on the schema crate.
+#[derive(Clone)]
+pub struct RedgoldClient {
+    pub url: String,
+    pub port: u16,
+    pub timeout: Duration,
+    pub api_key: Option<String>,
+    client: reqwest::Client,
+}
+
+impl RedgoldClient {
+    pub fn new(url: impl Into<String>, port: u16) -> Self {
+        Self {
+            url: url.into(),
+            port,
+            timeout: Duration::from_secs(30),
+            api_key: None,
+            client: shared_http_client(),
+        }
+    }
+
+    pub fn with_api_key(mut self, api_key: Option<String>) -> Self {
+        self.api_key = api_key;
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    fn formatted_url(&self) -> String {
+        format!("http://{}:{}", self.url, self.port)
+    }
+
+    fn with_headers(&self, builder: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        let builder = builder.timeout(self.timeout);
+        match self.api_key.as_ref() {
+            Some(k) => builder.header(API_KEY_HEADER, k),
+            None => builder,
+        }
+    }
+
+    /// Sends an unsigned protobuf request, returning an error if the node responded with one.
+    pub async fn request(&self, request: &Request) -> RgResult<Response> {
+        let post = self.client.post(format!("{}/request_proto", self.formatted_url()))
+            .body(request.proto_serialize());
+        let response = self.with_headers(post).send().await
+            .map_err(|e| error_info(format!("Proto request failure: {}", e.to_string())))?;
+        let bytes = response.bytes().await
+            .map_err(|e| error_info(format!("Proto request bytes decode failure: {}", e.to_string())))?;
+        let response = Response::proto_deserialize(bytes.to_vec())
+            .add("Proto request response decode failure")?;
+        response.as_error_info()?;
+        Ok(response)
+    }
+
+    /// GET a JSON endpoint of the public API, e.g. `order-book`.
+    pub async fn get_json<T: DeserializeOwned>(&self, path: impl Into<String>) -> RgResult<T> {
+        let get = self.client.get(format!("{}/{}", self.formatted_url(), path.into()));
+        let response = self.with_headers(get).send().await.error_info("GET request failure")?;
+        let text = response.text().await.error_info("Failed to get response text")?;
+        serde_json::from_str::<T>(&text)
+            .map_err(|e| error_info(format!("{} {}", e.to_string(), text)))
+    }
+
+    pub async fn about(&self) -> RgResult<AboutNodeResponse> {
+        let mut request = Request::default();
+        request.about_node_request = Some(AboutNodeRequest { verbose: true });
+        self.request(&request).await?.about_node_response.ok_msg("Missing about node response")
+    }
+
+    pub async fn submit(&self, tx: &Transaction, sync: bool) -> RgResult<SubmitTransactionResponse> {
+        let mut request = Request::default();
+        request.submit_transaction_request = Some(SubmitTransactionRequest {
+            transaction: Some(tx.clone()),
+            sync_query_response: sync,
+        });
+        self.request(&request).await?.submit_transaction_response.ok_msg("Missing submit transaction response")
+    }
+
+    pub async fn faucet(&self, address: &Address) -> RgResult<FaucetResponse> {
+        let mut request = Request::default();
+        request.faucet_request = Some(FaucetRequest {
+            address: Some(address.clone()),
+            token: None,
+        });
+        self.request(&request).await?.faucet_response.ok_msg("Missing faucet response")
+    }
+
+    /// Searches addresses, transactions, UTXOs and peers by hash or address string.
+    pub async fn hash_search(&self, search_string: impl Into<String>) -> RgResult<HashSearchResponse> {
+        let mut request = Request::default();
+        request.hash_search_request = Some(HashSearchRequest {
+            search_string: search_string.into()
+        });
+        self.request(&request).await?.hash_search_response.ok_msg("Missing hash search response")
+    }
+
+    pub async fn address_info(&self, address: &Address) -> RgResult<AddressInfo> {
+        let response = self.hash_search(address.render_string()?).await?;
+        response.address_info.ok_msg("missing address_info")
+    }
+
+    /// Balance in RDG, rounded for display.
+    pub async fn balance(&self, address: &Address) -> RgResult<f64> {
+        Ok(rounded_balance_i64(self.address_info(address).await?.balance))
+    }
+
+    /// The node's AMM pool with its price levels and limits, or None where no party has formed.
+    pub async fn order_book(&self) -> RgResult<Option<OrderBook>> {
+        self.get_json("order-book").await
+    }
+}
+
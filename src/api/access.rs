@@ -0,0 +1,163 @@
+use std::convert::Infallible;
+use warp::{Filter, Rejection, Reply};
+use warp::http::StatusCode;
+use redgold_schema::structs::ErrorInfo;
+use redgold_schema::error_info;
+use crate::util::key_bundle::constant_time_eq;
+
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Groups of endpoints which can be protected independently with their own api key
+#[derive(Clone, Debug, PartialEq)]
+pub enum EndpointClass {
+    PublicRead,
+    Faucet,
+    Control,
+}
+
+/// Reason a request was refused by an api key check
+#[derive(Clone, Debug, PartialEq)]
+pub enum AccessDenied {
+    /// No key was provided or it doesn't match any configured key
+    Unauthorized,
+    /// The key is valid, but only for a different endpoint class
+    Forbidden,
+}
+
+impl AccessDenied {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            AccessDenied::Unauthorized => StatusCode::UNAUTHORIZED,
+            AccessDenied::Forbidden => StatusCode::FORBIDDEN,
+        }
+    }
+
+    pub fn message(&self) -> &'static str {
+        match self {
+            AccessDenied::Unauthorized => "Invalid or missing api key",
+            AccessDenied::Forbidden => "Api key not permitted for this endpoint",
+        }
+    }
+
+    pub fn error_info(&self) -> ErrorInfo {
+        error_info(self.message())
+    }
+}
+
+/// CORS and api key settings applied at the API server layer, empty origins allow any origin
+/// and a missing key leaves that endpoint class open.
+#[derive(Clone, Debug, Default)]
+pub struct ApiAccessConfig {
+    pub cors_origins: Vec<String>,
+    pub public_read_api_key: Option<String>,
+    pub faucet_api_key: Option<String>,
+    pub control_api_key: Option<String>,
+    /// Key attached to outgoing requests made by this node's api clients
+    pub client_api_key: Option<String>,
+}
+
+impl ApiAccessConfig {
+
+    pub fn api_key(&self, class: &EndpointClass) -> Option<&String> {
+        match class {
+            EndpointClass::PublicRead => self.public_read_api_key.as_ref(),
+            EndpointClass::Faucet => self.faucet_api_key.as_ref(),
+            EndpointClass::Control => self.control_api_key.as_ref(),
+        }
+    }
+
+    pub fn check(&self, class: &EndpointClass, provided: Option<&String>) -> Result<(), AccessDenied> {
+        let expected = match self.api_key(class) {
+            None => return Ok(()),
+            Some(expected) => expected,
+        };
+        let provided = match provided {
+            None => return Err(AccessDenied::Unauthorized),
+            Some(provided) => provided,
+        };
+        if constant_time_eq(expected.as_bytes(), provided.as_bytes()) {
+            return Ok(());
+        }
+        let other_class = [EndpointClass::PublicRead, EndpointClass::Faucet, EndpointClass::Control]
+            .iter()
+            .filter_map(|c| self.api_key(c))
+            .any(|k| constant_time_eq(k.as_bytes(), provided.as_bytes()));
+        if other_class {
+            Err(AccessDenied::Forbidden)
+        } else {
+            Err(AccessDenied::Unauthorized)
+        }
+    }
+
+    pub fn authorized(&self, class: &EndpointClass, provided: Option<&String>) -> bool {
+        self.check(class, provided).is_ok()
+    }
+
+    pub fn cors(&self) -> warp::cors::Builder {
+        let cors = warp::cors()
+            .allow_methods(vec!["GET", "POST", "OPTIONS"])
+            .allow_headers(vec![API_KEY_HEADER, "content-type"]);
+        if self.cors_origins.is_empty() {
+            cors.allow_any_origin()
+        } else {
+            cors.allow_origins(self.cors_origins.iter().map(|o| o.as_str()))
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct InvalidApiKey;
+
+impl warp::reject::Reject for InvalidApiKey {}
+
+#[derive(Debug)]
+pub struct InsufficientScope;
+
+impl warp::reject::Reject for InsufficientScope {}
+
+/// Rejects requests missing the configured api key for the endpoint class
+pub fn api_key_filter(config: &ApiAccessConfig, class: EndpointClass)
+    -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    let config = config.clone();
+    warp::header::optional::<String>(API_KEY_HEADER)
+        .and_then(move |provided: Option<String>| {
+            let result = config.check(&class, provided.as_ref());
+            async move {
+                match result {
+                    Ok(_) => Ok(()),
+                    Err(AccessDenied::Unauthorized) => Err(warp::reject::custom(InvalidApiKey)),
+                    Err(AccessDenied::Forbidden) => Err(warp::reject::custom(InsufficientScope)),
+                }
+            }
+        })
+        .untuple_one()
+}
+
+pub async fn handle_rejection(err: Rejection) -> Result<impl Reply, Infallible> {
+    let (code, message) = if err.find::<InvalidApiKey>().is_some() {
+        (AccessDenied::Unauthorized.status_code(), AccessDenied::Unauthorized.message())
+    } else if err.find::<InsufficientScope>().is_some() {
+        (AccessDenied::Forbidden.status_code(), AccessDenied::Forbidden.message())
+    } else if err.is_not_found() {
+        (StatusCode::NOT_FOUND, "Not found")
+    } else {
+        (StatusCode::BAD_REQUEST, "Bad request")
+    };
+    Ok(warp::reply::with_status(message.to_string(), code))
+}
+
+#[test]
+fn api_key_authorization() {
+    let mut config = ApiAccessConfig::default();
+    assert!(config.authorized(&EndpointClass::Control, None));
+    config.control_api_key = Some("secret".to_string());
+    assert!(!config.authorized(&EndpointClass::Control, None));
+    assert!(!config.authorized(&EndpointClass::Control, Some(&"wrong".to_string())));
+    assert!(config.authorized(&EndpointClass::Control, Some(&"secret".to_string())));
+    assert!(config.authorized(&EndpointClass::Faucet, None));
+    config.faucet_api_key = Some("faucet".to_string());
+    assert_eq!(config.check(&EndpointClass::Control, None), Err(AccessDenied::Unauthorized));
+    assert_eq!(config.check(&EndpointClass::Control, Some(&"wrong".to_string())), Err(AccessDenied::Unauthorized));
+    assert_eq!(config.check(&EndpointClass::Control, Some(&"faucet".to_string())), Err(AccessDenied::Forbidden));
+    assert_eq!(config.check(&EndpointClass::Faucet, Some(&"faucet".to_string())), Ok(()));
+}
@@ -0,0 +1,57 @@
+use redgold_keys::xpub_wrapper::XpubWrapper;
+use redgold_schema::{error_info, RgResult};
+use redgold_schema::structs::{Address, AddressBalance, AddressInfo, BatchBalanceRequest, BatchBalanceResponse};
+use crate::core::relay::Relay;
+
+/// Maximum number of addresses resolved in a single batch balance request
+pub const MAX_BATCH_BALANCE_ADDRESSES: usize = 500;
+
+pub fn batch_addresses(request: &BatchBalanceRequest) -> RgResult<Vec<Address>> {
+    let mut addresses = request.addresses.clone();
+    if let Some(xpub) = &request.xpub {
+        let start = request.start_index.unwrap_or(0);
+        let end = request.end_index.unwrap_or(start + 20);
+        if start < 0 || end < start {
+            return Err(error_info(format!("Invalid xpub index range {} to {}", start, end)));
+        }
+        if (end - start) as usize + addresses.len() > MAX_BATCH_BALANCE_ADDRESSES {
+            return Err(error_info(format!("Batch balance request exceeds limit of {} addresses", MAX_BATCH_BALANCE_ADDRESSES)));
+        }
+        let w = XpubWrapper::new(xpub.clone());
+        for i in start..end {
+            addresses.push(w.public_at(0, i as usize)?.address()?);
+        }
+    }
+    if addresses.len() > MAX_BATCH_BALANCE_ADDRESSES {
+        return Err(error_info(format!("Batch balance request exceeds limit of {} addresses", MAX_BATCH_BALANCE_ADDRESSES)));
+    }
+    Ok(addresses)
+}
+
+pub async fn batch_balance(request: &BatchBalanceRequest, relay: &Relay) -> RgResult<BatchBalanceResponse> {
+    let mut balances = vec![];
+    for address in batch_addresses(request)? {
+        let entries = relay.ds.transaction_store.query_utxo_address(&address).await?;
+        let utxo_count = entries.len() as i64;
+        let info = AddressInfo::from_utxo_entries(address.clone(), entries);
+        balances.push(AddressBalance {
+            address: Some(address),
+            balance: info.balance,
+            utxo_count,
+        });
+    }
+    Ok(BatchBalanceResponse { balances })
+}
+
+#[test]
+fn batch_balance_limit() {
+    let mut request = BatchBalanceRequest::default();
+    request.addresses = vec![Address::default(); MAX_BATCH_BALANCE_ADDRESSES + 1];
+    assert!(batch_addresses(&request).is_err());
+    request.addresses.truncate(MAX_BATCH_BALANCE_ADDRESSES);
+    assert!(batch_addresses(&request).is_ok());
+    request.xpub = Some("unused".to_string());
+    request.start_index = Some(5);
+    request.end_index = Some(4);
+    assert!(batch_addresses(&request).is_err());
+}
@@ -0,0 +1,180 @@
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use dashmap::DashMap;
+use metrics::{counter, gauge};
+use reqwest::Client;
+use redgold_schema::{error_message, ErrorInfoContext, RgResult};
+use redgold_schema::structs::Error;
+
+static CLIENT_POOL: OnceLock<ClientPool> = OnceLock::new();
+
+#[derive(Clone, Debug)]
+pub struct ClientPoolConfig {
+    pub pool_idle_timeout: Duration,
+    pub max_idle_per_host: usize,
+    pub tcp_keepalive: Duration,
+    pub connect_timeout: Duration,
+    // Consecutive failures to a destination before requests to it are short circuited
+    pub breaker_failure_threshold: u32,
+    // How long an open breaker rejects requests before letting a single trial through, and how
+    // long a trial with no recorded result blocks the next one
+    pub breaker_cooldown: Duration,
+}
+
+impl Default for ClientPoolConfig {
+    fn default() -> Self {
+        Self {
+            pool_idle_timeout: Duration::from_secs(90),
+            max_idle_per_host: 8,
+            tcp_keepalive: Duration::from_secs(30),
+            connect_timeout: Duration::from_secs(5),
+            breaker_failure_threshold: 5,
+            breaker_cooldown: Duration::from_secs(30),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum BreakerState {
+    Closed,
+    Open(Instant),
+    // Cooldown elapsed and a single trial request is in flight since this time
+    HalfOpen(Instant),
+}
+
+#[derive(Clone, Debug)]
+pub struct CircuitBreaker {
+    pub state: BreakerState,
+    pub consecutive_failures: u32,
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self {
+            state: BreakerState::Closed,
+            consecutive_failures: 0,
+        }
+    }
+}
+
+impl CircuitBreaker {
+    pub fn allow(&mut self, now: Instant, cooldown: Duration) -> bool {
+        match self.state {
+            BreakerState::Closed => true,
+            BreakerState::Open(since) if now.duration_since(since) >= cooldown => {
+                self.state = BreakerState::HalfOpen(now);
+                true
+            }
+            // A trial whose future was dropped never records, so it's abandoned after the cooldown
+            BreakerState::HalfOpen(since) if now.duration_since(since) >= cooldown => {
+                self.state = BreakerState::HalfOpen(now);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn record(&mut self, success: bool, now: Instant, threshold: u32) {
+        if success {
+            self.consecutive_failures = 0;
+            self.state = BreakerState::Closed;
+        } else {
+            self.consecutive_failures += 1;
+            if matches!(self.state, BreakerState::HalfOpen(_)) || self.consecutive_failures >= threshold {
+                self.state = BreakerState::Open(now);
+            }
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.state != BreakerState::Closed
+    }
+}
+
+/// Shared keep-alive HTTP clients keyed by destination host, so repeated peer calls reuse
+/// idle connections instead of opening a new one per request.
+#[derive(Clone)]
+pub struct ClientPool {
+    config: ClientPoolConfig,
+    clients: Arc<DashMap<String, Client>>,
+    breakers: Arc<DashMap<String, CircuitBreaker>>,
+}
+
+impl ClientPool {
+    pub fn new(config: ClientPoolConfig) -> Self {
+        Self {
+            config,
+            clients: Arc::new(DashMap::new()),
+            breakers: Arc::new(DashMap::new()),
+        }
+    }
+
+    pub fn global() -> &'static ClientPool {
+        CLIENT_POOL.get_or_init(|| ClientPool::new(ClientPoolConfig::default()))
+    }
+
+    pub fn client(&self, host: &String) -> RgResult<Client> {
+        if let Some(c) = self.clients.get(host) {
+            counter!("redgold.http.pool.hit").increment(1);
+            return Ok(c.clone());
+        }
+        counter!("redgold.http.pool.miss").increment(1);
+        let client = Client::builder()
+            .pool_idle_timeout(self.config.pool_idle_timeout)
+            .pool_max_idle_per_host(self.config.max_idle_per_host)
+            .tcp_keepalive(self.config.tcp_keepalive)
+            .connect_timeout(self.config.connect_timeout)
+            .build()
+            .error_info("Failed to build pooled http client")?;
+        self.clients.insert(host.clone(), client.clone());
+        gauge!("redgold.http.pool.hosts").set(self.clients.len() as f64);
+        Ok(client)
+    }
+
+    /// Errors without attempting the request while the destination's breaker is open.
+    pub fn check_breaker(&self, host: &String) -> RgResult<()> {
+        let allowed = self.breakers.entry(host.clone()).or_default()
+            .allow(Instant::now(), self.config.breaker_cooldown);
+        if !allowed {
+            counter!("redgold.http.breaker.rejected").increment(1);
+            return Err(error_message(Error::PeerUnavailable, format!("Circuit breaker open for {}", host)));
+        }
+        Ok(())
+    }
+
+    pub fn record_result(&self, host: &String, success: bool) {
+        let opened = {
+            let mut b = self.breakers.entry(host.clone()).or_default();
+            let was_open = b.is_open();
+            b.record(success, Instant::now(), self.config.breaker_failure_threshold);
+            !was_open && b.is_open()
+        };
+        if opened {
+            counter!("redgold.http.breaker.opened").increment(1);
+        }
+        let open = self.breakers.iter().filter(|b| b.is_open()).count();
+        gauge!("redgold.http.breaker.open").set(open as f64);
+    }
+}
+
+#[test]
+fn breaker_opens_and_recovers() {
+    let mut b = CircuitBreaker::default();
+    let now = Instant::now();
+    let cooldown = Duration::from_secs(10);
+    b.record(false, now, 2);
+    assert!(b.allow(now, cooldown));
+    b.record(false, now, 2);
+    assert!(!b.allow(now, cooldown));
+    // Single trial after cooldown, failing it reopens immediately
+    assert!(b.allow(now + cooldown, cooldown));
+    assert!(!b.allow(now + cooldown, cooldown));
+    b.record(false, now + cooldown, 2);
+    assert!(!b.allow(now + cooldown, cooldown));
+    assert!(b.allow(now + cooldown * 2, cooldown));
+    // Trial dropped without a result, another is let through once the cooldown passes again
+    assert!(!b.allow(now + cooldown * 2, cooldown));
+    assert!(b.allow(now + cooldown * 3, cooldown));
+    b.record(true, now + cooldown * 3, 2);
+    assert_eq!(b.state, BreakerState::Closed);
+}
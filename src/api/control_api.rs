@@ -13,13 +13,16 @@ use uuid::Uuid;
 use warp::{Filter, Rejection};
 use warp::reply::Json;
 use redgold_schema::{json_or, response_metadata, RgResult, SafeOption, structs};
-use redgold_schema::structs::{BytesData, ControlMultipartyKeygenRequest, ControlMultipartyKeygenResponse, ControlMultipartySigningRequest, ControlMultipartySigningResponse, ErrorInfo, InitiateMultipartyKeygenRequest, InitiateMultipartyKeygenResponse, InitiateMultipartySigningRequest, InitiateMultipartySigningResponse, MultipartyIdentifier, Request};
+use redgold_schema::structs::{BytesData, ControlColdSweepRequest, ControlColdSweepResponse, ControlLogLevelRequest, ControlLogLevelResponse, ControlMultipartyKeygenRequest, ControlMultipartyKeygenResponse, ControlMultipartySigningRequest, ControlMultipartySigningResponse, ErrorInfo, InitiateMultipartyKeygenRequest, InitiateMultipartyKeygenResponse, InitiateMultipartySigningRequest, InitiateMultipartySigningResponse, MultipartyIdentifier, Request};
 use crate::api::{as_warp_json_response, RgHttpClient};
+use crate::api::access::{api_key_filter, EndpointClass, handle_rejection};
 use crate::api::rosetta::models::Error;
 
 // use crate::util::to_libp2p_peer_id;
 
 use crate::core::relay::Relay;
+use crate::observability::trace_setup::{current_log_filter, update_log_level};
+use crate::multiparty::watcher::DepositWatcher;
 use crate::multiparty::initiate_mp::{fill_identifier, find_multiparty_key_pairs, initiate_mp_keygen, initiate_mp_keysign};
 use crate::schema::structs::{
     ControlRequest, ControlResponse, ResponseMetadata,
@@ -62,12 +65,35 @@ impl ControlClient {
         res.control_multiparty_signing_response.ok_or(ErrorInfo::error_info("No response"))
     }
 
+    pub async fn log_level(&self, level: Option<String>, target: Option<String>)
+        -> RgResult<ControlLogLevelResponse> {
+        let mut cr = ControlRequest::empty();
+        let mut req = ControlLogLevelRequest::default();
+        req.level = level;
+        req.target = target;
+        cr.control_log_level_request = Some(req);
+        let res: ControlResponse = self.request(cr).await?;
+        res.control_log_level_response.ok_or(ErrorInfo::error_info("No response"))
+    }
+
+    pub async fn cold_sweep(&self, req: ControlColdSweepRequest) -> RgResult<ControlColdSweepResponse> {
+        let mut cr = ControlRequest::empty();
+        cr.control_cold_sweep_request = Some(req);
+        let res: ControlResponse = self.request(cr).await?;
+        res.control_cold_sweep_response.ok_or(ErrorInfo::error_info("No response"))
+    }
+
     pub fn local(port: u16) -> Self {
         Self {
             client: RgHttpClient::new("localhost".to_string(), port, None)
         }
     }
 
+    pub fn with_api_key(mut self, api_key: Option<String>) -> Self {
+        self.client.api_key = api_key;
+        self
+    }
+
     pub fn new(client: RgHttpClient) -> Self {
         Self {
             client
@@ -94,7 +120,23 @@ impl ControlServer {
 
         let mut response = ControlResponse::empty();
 
-        if relay.node_config.network.is_local_debug() {
+        if let Some(req) = request.control_log_level_request {
+            let filter = match req.level {
+                None => current_log_filter()?,
+                Some(level) => update_log_level(&level, req.target.clone())?
+            };
+            let mut res = ControlLogLevelResponse::default();
+            res.filter = filter;
+            response.control_log_level_response = Some(res);
+        }
+
+        if let Some(req) = request.control_cold_sweep_request {
+            let w = DepositWatcher::new(relay.clone());
+            w.update_cold_storage(&req).await?;
+            response.control_cold_sweep_response = Some(w.cold_sweep(req.execute).await?);
+        }
+
+        if relay.node_config.features.debug_multiparty_control {
             // TODO: Shouldn't both of these really be in the initiate function?
             if let Some(mps) = request.control_multiparty_keygen_request {
                 info!("Initiate multiparty request: {}", json_or(&mps));
@@ -169,6 +211,7 @@ impl ControlServer {
         let control_relay = relay.clone();
         let control_single_json = warp::post()
             .and(warp::path("control"))
+            .and(api_key_filter(&relay.node_config.api_access, EndpointClass::Control))
             // Only accept bodies smaller than 16kb...
             .and(warp::body::content_length_limit(1024 * 16))
             .and(warp::body::json::<ControlRequest>())
@@ -183,7 +226,7 @@ impl ControlServer {
                 }
             });
 
-        warp::serve(control_single_json)
+        warp::serve(control_single_json.recover(handle_rejection))
             .run(([127, 0, 0, 1], relay2.node_config.control_port()))
             .await;
         Ok(())
@@ -0,0 +1,178 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use redgold_keys::address_support::AddressSupport;
+use redgold_schema::{RgResult, SafeOption, WithMetadataHashable};
+use redgold_schema::structs::{Hash, Transaction, UtxoId};
+use redgold_schema::transaction::rounded_balance_i64;
+use crate::core::relay::Relay;
+
+pub const MAX_GRAPH_HOPS: i64 = 6;
+pub const DEFAULT_GRAPH_HOPS: i64 = 2;
+// Traversal stops adding transactions past this, marking the response as truncated
+pub const MAX_GRAPH_NODES: usize = 250;
+// Recent transactions of an address used as the starting points for an address query
+const ADDRESS_ROOT_LIMIT: i64 = 25;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphDirection {
+    Ancestors,
+    Descendants,
+    #[default]
+    Both,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct TxGraphQuery {
+    pub hops: Option<i64>,
+    pub direction: Option<GraphDirection>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TxGraphNode {
+    pub transaction_hash: String,
+    pub time: i64,
+    pub total_amount: f64,
+    // Negative for ancestors, positive for descendants, zero for the roots
+    pub hop: i64,
+}
+
+/// Funds moved from an output of `from` into an input of `to`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TxGraphEdge {
+    pub from: String,
+    pub to: String,
+    pub output_index: i64,
+    pub address: String,
+    pub amount: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct TxGraphResponse {
+    pub roots: Vec<String>,
+    pub nodes: Vec<TxGraphNode>,
+    pub edges: Vec<TxGraphEdge>,
+    // Set when the node limit cut the traversal short
+    pub truncated: bool,
+}
+
+fn edge(parent: &Transaction, output_index: i64, child: &Hash) -> TxGraphEdge {
+    let output = parent.outputs.get(output_index as usize);
+    TxGraphEdge {
+        from: parent.hash_or().hex(),
+        to: child.hex(),
+        output_index,
+        address: output.and_then(|o| o.address.as_ref()).and_then(|a| a.render_string().ok()).unwrap_or("".to_string()),
+        amount: output.and_then(|o| o.opt_amount()).map(rounded_balance_i64).unwrap_or(0.0),
+    }
+}
+
+async fn roots(input: &String, r: &Relay) -> RgResult<Vec<Transaction>> {
+    if let Ok(a) = input.parse_address() {
+        return r.ds.transaction_store.get_all_tx_for_address(&a, ADDRESS_ROOT_LIMIT, 0).await;
+    }
+    let hash = Hash::from_hex(input.clone())?;
+    let tx = r.ds.transaction_store.query_maybe_transaction(&hash).await?
+        .map(|(t, _)| t)
+        .safe_get_msg("Transaction not found")?
+        .clone();
+    Ok(vec![tx])
+}
+
+/// Local spend graph around a transaction or an address's recent transactions, walked breadth
+/// first up to the requested hops. Each transaction is visited once so cycles terminate.
+pub async fn handle_transaction_graph(input: String, query: TxGraphQuery, r: Relay) -> RgResult<TxGraphResponse> {
+    let hops = query.hops.unwrap_or(DEFAULT_GRAPH_HOPS).max(0).min(MAX_GRAPH_HOPS);
+    let direction = query.direction.unwrap_or_default();
+    let roots = roots(&input, &r).await?;
+    let mut res = TxGraphResponse::default();
+    res.roots = roots.iter().map(|t| t.hash_or().hex()).collect_vec();
+
+    let mut visited: HashMap<Hash, Transaction> = HashMap::new();
+    let mut edges_seen: HashSet<(String, i64)> = HashSet::new();
+    // (transaction, signed hop distance from the roots)
+    let mut queue: VecDeque<(Transaction, i64)> = VecDeque::new();
+    for t in roots {
+        if visited.insert(t.hash_or(), t.clone()).is_none() {
+            queue.push_back((t, 0));
+        }
+    }
+
+    while let Some((tx, hop)) = queue.pop_front() {
+        let hash = tx.hash_or();
+        res.nodes.push(TxGraphNode {
+            transaction_hash: hash.hex(),
+            time: tx.time().cloned().unwrap_or(0),
+            total_amount: tx.total_output_amount_float(),
+            hop,
+        });
+        let mut next: Vec<(Transaction, i64)> = vec![];
+        if direction != GraphDirection::Descendants && hop <= 0 && hop > -hops {
+            for i in tx.inputs.iter() {
+                let id = match i.utxo_id.as_ref() {
+                    Some(id) => id,
+                    None => continue,
+                };
+                let parent_hash = match id.transaction_hash.as_ref() {
+                    Some(h) => h,
+                    None => continue,
+                };
+                let parent = match visited.get(parent_hash) {
+                    Some(p) => Some(p.clone()),
+                    None => r.ds.transaction_store.query_maybe_transaction(parent_hash).await?.map(|(t, _)| t),
+                };
+                if let Some(p) = parent {
+                    if edges_seen.insert((parent_hash.hex(), id.output_index)) {
+                        res.edges.push(edge(&p, id.output_index, &hash));
+                    }
+                    next.push((p, hop - 1));
+                }
+            }
+        }
+        if direction != GraphDirection::Ancestors && hop >= 0 && hop < hops {
+            for (index, _) in tx.outputs.iter().enumerate() {
+                let id = UtxoId { transaction_hash: Some(hash.clone()), output_index: index as i64 };
+                let child_hash = match r.ds.transaction_store.utxo_used(&id).await? {
+                    Some((h, _)) => h,
+                    None => continue,
+                };
+                if edges_seen.insert((hash.hex(), index as i64)) {
+                    res.edges.push(edge(&tx, index as i64, &child_hash));
+                }
+                let child = match visited.get(&child_hash) {
+                    Some(c) => Some(c.clone()),
+                    None => r.ds.transaction_store.query_maybe_transaction(&child_hash).await?.map(|(t, _)| t),
+                };
+                if let Some(c) = child {
+                    next.push((c, hop + 1));
+                }
+            }
+        }
+        for (t, h) in next {
+            let th = t.hash_or();
+            if visited.contains_key(&th) {
+                continue;
+            }
+            if visited.len() >= MAX_GRAPH_NODES {
+                res.truncated = true;
+                continue;
+            }
+            visited.insert(th, t.clone());
+            queue.push_back((t, h));
+        }
+    }
+    // Edges may point at transactions dropped by the node limit
+    if res.truncated {
+        let kept = res.nodes.iter().map(|n| n.transaction_hash.clone()).collect::<HashSet<String>>();
+        res.edges.retain(|e| kept.contains(&e.from) && kept.contains(&e.to));
+    }
+    Ok(res)
+}
+
+#[test]
+fn graph_direction_parses_lowercase() {
+    let q: TxGraphQuery = serde_json::from_str(r#"{"hops": 3, "direction": "ancestors"}"#).expect("parse");
+    assert_eq!(q.direction, Some(GraphDirection::Ancestors));
+    assert_eq!(GraphDirection::default(), GraphDirection::Both);
+}
@@ -1,5 +1,6 @@
 pub mod server;
 pub mod debug_test;
+pub mod graph;
 
 use std::cmp::Ordering;
 use std::collections::{HashMap, HashSet};
@@ -17,19 +18,23 @@ use crate::api::hash_query::hash_query;
 use crate::core::relay::Relay;
 use serde::{Serialize, Deserialize};
 use redgold_data::peer::PeerTrustQueryResult;
-use redgold_schema::structs::{AddressInfo, ErrorInfo, FaucetRequest, FaucetResponse, HashType, NetworkEnvironment, NodeType, Observation, ObservationMetadata, PartyInfo, PeerId, PeerIdInfo, PeerNodeInfo, PublicKey, QueryTransactionResponse, Request, State, SubmitTransactionResponse, SupportedCurrency, Transaction, TrustRatingLabel, UtxoEntry, ValidationType};
+use redgold_schema::structs::{AddressInfo, ErrorInfo, FaucetRequest, FaucetResponse, HashType, NetworkEnvironment, NodeType, Observation, ObservationMetadata, PartyInfo, PeerId, PeerIdInfo, PeerNodeInfo, PublicKey, QueryTransactionResponse, Request, State, SubmitTransactionResponse, SupportedCurrency, Transaction, TrustRatingLabel, UtxoEntry, UtxoId, ValidationType};
 use strum_macros::EnumString;
 use tokio::time::Instant;
 use warp::get;
 use redgold_schema::transaction::{rounded_balance, rounded_balance_i64};
 use crate::api::public_api::{Pagination, TokenParam};
-use crate::multiparty::watcher::{BidAsk, DepositWatcher, DepositWatcherConfig};
+use crate::multiparty::admission::{ADMISSION_STATE, AdmissionState};
+use crate::multiparty::party_stream::{AMM_RECENT_FILLS, AMM_VOLUME_SUMMARY, RecentFill};
+use crate::multiparty::watcher::{BidAsk, DEPOSIT_WATCHER_CONFIG, DepositWatcher, DepositWatcherConfig, OrderLimits};
 use crate::util;
 use redgold_keys::address_external::ToBitcoinAddress;
 use redgold_keys::address_support::AddressSupport;
 use crate::api::faucet::faucet_request;
 use crate::observability::logging::Loggable;
 use crate::util::current_time_millis_i64;
+use crate::core::balance_snapshots::MAX_BALANCE_HISTORY_DAYS;
+use crate::node_config::ObservationConfig;
 
 #[derive(Serialize, Deserialize)]
 pub struct HashResponse {
@@ -78,7 +83,8 @@ pub struct NodeSignerDetailed {
 pub struct DetailedInput {
     pub transaction_hash: String,
     pub output_index: i64,
-    pub address: String
+    pub address: String,
+    pub conflict_detected: bool,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -87,6 +93,17 @@ pub struct DetailedOutput {
     pub address: String,
     pub available: bool,
     pub amount: f64,
+    pub conflict_detected: bool,
+}
+
+/// A conflicting spend attempt detected on a utxo
+#[derive(Serialize, Deserialize)]
+pub struct DetailedConflict {
+    pub utxo_transaction_hash: String,
+    pub utxo_output_index: i64,
+    pub transaction_hash: String,
+    pub conflicting_transaction_hash: String,
+    pub time: i64,
 }
 
 
@@ -101,6 +118,10 @@ pub struct DetailedTransaction {
     pub num_pending_signers: i64,
     pub num_accepted_signers: i64,
     pub accepted: bool,
+    // Summed trust of accepting nodes compared against this node's configured threshold
+    pub acceptance_weight: f64,
+    pub acceptance_threshold: f64,
+    pub finalized: bool,
     pub signers: Vec<PeerSignerDetailed>,
     pub inputs: Vec<DetailedInput>,
     pub outputs: Vec<DetailedOutput>,
@@ -110,15 +131,22 @@ pub struct DetailedTransaction {
 }
 
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct AddressPoolInfo {
-    public_key: String,
+    pub public_key: String,
     // rdg_pk_address: String,
-    rdg_address: String,
-    rdg_balance: f64,
-    btc_address: String,
-    btc_balance: f64,
-    bid_ask: BidAsk,
+    pub rdg_address: String,
+    pub rdg_balance: f64,
+    pub btc_address: String,
+    pub btc_balance: f64,
+    pub bid_ask: BidAsk,
+    // Orders outside these bounds are refunded, published so clients can pre-validate
+    pub order_limits: Option<OrderLimits>,
+    #[serde(default)]
+    pub recent_fills: Vec<RecentFill>,
+    // Set when the bid_ask above has sides withdrawn due to a backlog or high BTC fees
+    #[serde(default)]
+    pub admission: Option<AdmissionState>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -214,6 +242,7 @@ pub struct ExplorerHashSearchResponse {
     pub observation: Option<DetailedObservation>,
     pub peer: Option<DetailedPeer>,
     pub peer_node: Option<DetailedPeerNode>,
+    pub conflicts: Vec<DetailedConflict>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -222,6 +251,94 @@ pub struct ExplorerFaucetResponse {
 }
 
 
+#[derive(Serialize, Deserialize)]
+pub struct NetworkStatsResponse {
+    pub total_transactions: i64,
+    pub total_utxos: i64,
+    pub total_observations: i64,
+    pub observation_height: i64,
+    pub num_active_peers: i64,
+    pub num_known_peers: i64,
+    pub pending_transactions: i64,
+    pub amm_btc_volume: f64,
+    pub amm_rdg_volume: f64,
+    pub amm_fulfillments: i64,
+    pub amm_volume_updated_time: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BalanceHistoryPoint {
+    pub time: i64,
+    pub balance: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BalanceHistoryResponse {
+    pub address: String,
+    pub current_balance: f64,
+    pub history: Vec<BalanceHistoryPoint>,
+}
+
+/// A single peer node's observation of a transaction
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TransactionAttestation {
+    pub node_id: String,
+    pub peer_id: Option<String>,
+    pub trust: Option<f64>,
+    pub observation_hash: String,
+    pub observation_type: String,
+    pub state: String,
+    pub time: i64,
+    pub height: Option<i64>,
+}
+
+// Accepted transactions older than this are no longer checked for finalization
+pub const PENDING_ACCEPTED_WINDOW_MS: i64 = 1000 * 60 * 15;
+const PENDING_ACCEPTED_LIMIT: i64 = 100;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum PendingStage {
+    // Received but not yet validated
+    Queued,
+    // Being validated and resolved against conflicting transactions
+    Processing,
+    // Accepted by this node, waiting on enough peer observations to be final
+    Accepted,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PendingTransaction {
+    pub transaction_hash: String,
+    pub stage: PendingStage,
+    pub time: i64,
+    pub from: String,
+    pub amount: f64,
+    pub acceptance_weight: f64,
+    pub acceptance_threshold: f64,
+    pub raw_transaction: Transaction,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct PendingTransactionsResponse {
+    pub transactions: Vec<PendingTransaction>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct PendingQuery {
+    pub address: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TransactionObservationsResponse {
+    pub transaction_hash: String,
+    pub num_peers: i64,
+    pub num_accepted: i64,
+    pub acceptance_weight: f64,
+    pub acceptance_threshold: f64,
+    pub finalized: bool,
+    pub attestations: Vec<TransactionAttestation>,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct RecentDashboardResponse {
     pub recent_transactions: Vec<BriefTransaction>,
@@ -243,7 +360,7 @@ pub fn convert_utxo(u: &UtxoEntry) -> RgResult<BriefUtxoEntry> {
 
 pub async fn get_address_pool_info(r: Relay) -> RgResult<Option<AddressPoolInfo>> {
 
-    let res: Option<DepositWatcherConfig> = r.ds.config_store.get_json::<DepositWatcherConfig>("deposit_watcher_config").await?;
+    let res: Option<DepositWatcherConfig> = r.ds.config_store.get_typed(&DEPOSIT_WATCHER_CONFIG).await?;
     let res = match res {
         None => {
             None
@@ -253,13 +370,19 @@ pub async fn get_address_pool_info(r: Relay) -> RgResult<Option<AddressPoolInfo>
             let btc_swap_address = a.key.to_bitcoin_address(&r.node_config.network.clone())?;
             let btc_amount = (a.balance_btc as f64) / 1e8;
             let rdg_amount = (a.balance_rdg as f64) / 1e8;
+            let admission = r.ds.config_store.get_typed(&ADMISSION_STATE).await?
+                .filter(|s| s.paused());
+            let bid_ask = admission.as_ref().map(|s| s.apply(&d.bid_ask)).unwrap_or(d.bid_ask.clone());
             Some(AddressPoolInfo {
                 public_key: a.key.hex_or(),
                 rdg_address: a.key.address()?.render_string()?,
                 rdg_balance: rdg_amount,
                 btc_address: btc_swap_address,
                 btc_balance: btc_amount,
-                bid_ask: d.bid_ask.clone(),
+                bid_ask,
+                order_limits: d.order_limits.clone(),
+                recent_fills: r.ds.config_store.get_typed(&AMM_RECENT_FILLS).await?.unwrap_or(vec![]),
+                admission,
             })
         }
     };
@@ -553,7 +676,19 @@ pub async fn handle_explorer_hash(hash_input: String, r: Relay, pagination: Pagi
         observation: None,
         peer: None,
         peer_node: None,
+        conflicts: vec![],
     };
+    for c in &hq.conflicts {
+        let utxo_id = c.utxo_id.safe_get_msg("Missing conflict utxo_id")?;
+        h.conflicts.push(DetailedConflict {
+            utxo_transaction_hash: utxo_id.transaction_hash.safe_get_msg("Missing utxo hash")?.hex(),
+            utxo_output_index: utxo_id.output_index,
+            transaction_hash: c.transaction_hash.safe_get_msg("Missing conflict hash")?.hex(),
+            conflicting_transaction_hash: c.conflicting_transaction_hash.safe_get_msg("Missing conflict hash")?.hex(),
+            time: c.time,
+        });
+    }
+    let contested = |u: &UtxoId| hq.conflicts.iter().any(|c| c.utxo_id.as_ref() == Some(u));
     if let Some(ai) = &hq.address_info {
         h.address = Some(handle_address_info(ai, &r, limit, offset).await?);
     }
@@ -664,6 +799,7 @@ pub async fn handle_explorer_hash(hash_input: String, r: Relay, pagination: Pagi
                 transaction_hash: u.transaction_hash.clone().map(|t| t.hex()).safe_get_msg("Missing transaction hash?")?.clone(),
                 output_index: u.output_index.clone(),
                 address: i.address()?.render_string()?,
+                conflict_detected: contested(u),
             };
             inputs.push(input);
         }
@@ -673,7 +809,11 @@ pub async fn handle_explorer_hash(hash_input: String, r: Relay, pagination: Pagi
                 output_index: i.clone() as i32,
                 address: o.address.safe_get()?.render_string()?,
                 available: t.valid_utxo_index.contains(&(i as i32)),
-                amount: o.opt_amount_typed().map(|a| a.to_fractional()).unwrap_or(0.0)
+                amount: o.opt_amount_typed().map(|a| a.to_fractional()).unwrap_or(0.0),
+                conflict_detected: contested(&UtxoId {
+                    transaction_hash: Some(tx.hash_or()),
+                    output_index: i as i64,
+                }),
             };
             outputs.push(output);
         }
@@ -688,6 +828,12 @@ pub async fn handle_explorer_hash(hash_input: String, r: Relay, pagination: Pagi
 
         let num_pending_signers = counts.get(&(State::Pending as i32)).unwrap_or(&0).clone() as i64;
         let num_accepted_signers = counts.get(&(State::Accepted as i32)).unwrap_or(&0).clone() as i64;
+        let accepted_trust = signers.iter().flat_map(|s| s.nodes.iter()
+            .filter(|n| n.signed_finalized_time.is_some())
+            .map(|n| (n.node_id.clone(), s.trust))
+        ).collect_vec();
+        let acceptance_weight = ObservationConfig::acceptance_weight(&accepted_trust);
+        let acceptance_threshold = r.node_config.observation.acceptance_weight_threshold;
         let detailed = DetailedTransaction{
             info: brief_transaction(tx)?,
             confirmation_score: 1.0,
@@ -696,6 +842,9 @@ pub async fn handle_explorer_hash(hash_input: String, r: Relay, pagination: Pagi
             num_pending_signers,
             num_accepted_signers,
             accepted: t.accepted,
+            acceptance_weight,
+            acceptance_threshold,
+            finalized: t.accepted && r.node_config.observation.is_finalized(acceptance_weight),
             signers,
             inputs,
             outputs,
@@ -794,4 +943,142 @@ pub async fn handle_explorer_recent(r: Relay, is_test: Option<bool>) -> RgResult
 
 pub async fn handle_explorer_swap(relay: Relay) -> RgResult<Option<AddressPoolInfo>> {
     get_address_pool_info(relay).await
+}
+
+pub async fn handle_explorer_balance_history(address: String, days: Option<i64>, r: Relay) -> RgResult<BalanceHistoryResponse> {
+    let addr = address.parse_address()?;
+    let limit = days.unwrap_or(MAX_BALANCE_HISTORY_DAYS).min(MAX_BALANCE_HISTORY_DAYS).max(1);
+    let snapshots = r.ds.transaction_store.query_balance_snapshots(&addr, limit).await?;
+    let current = r.ds.transaction_store.get_balance(&addr).await?.unwrap_or(0);
+    Ok(BalanceHistoryResponse {
+        address,
+        current_balance: rounded_balance_i64(current),
+        history: snapshots.into_iter().map(|s| BalanceHistoryPoint {
+            time: s.time,
+            balance: rounded_balance_i64(s.balance),
+        }).collect_vec(),
+    })
+}
+
+pub async fn handle_explorer_observations(hash_input: String, r: Relay) -> RgResult<TransactionObservationsResponse> {
+    let hash = redgold_schema::structs::Hash::from_hex(hash_input.clone())?;
+    let proofs = r.ds.observation.select_observation_edge(&hash).await?;
+    let mut attestations = vec![];
+    for p in proofs.iter() {
+        let metadata = p.metadata.safe_get_msg("Missing observation metadata")?;
+        let pk = p.proof.as_ref().and_then(|p| p.public_key.as_ref()).safe_get_msg("Missing observation public key")?;
+        let observation_hash = p.observation_hash.safe_get_msg("Missing observation hash")?;
+        let time = metadata.struct_metadata.as_ref().and_then(|s| s.time).unwrap_or(0);
+        let peer_id = r.peer_id_for_node_pk(pk).await.ok().and_then(identity);
+        let trust = if pk == &r.node_config.public_key() {
+            Some(10.0)
+        } else {
+            // Known peers without a trust score count fully, matching the transaction detail view
+            r.get_trust_of_node_as_query(pk).await.ok().and_then(identity).map(|t| t.trust)
+                .or(peer_id.as_ref().map(|_| 1.0))
+                .map(|t| t * 10.0)
+        };
+        let height = r.ds.observation.query_observation(observation_hash).await?
+            .and_then(|o| o.height().ok());
+        attestations.push(TransactionAttestation {
+            node_id: pk.hex_or(),
+            peer_id: peer_id.and_then(|p| p.peer_id).and_then(|pk| pk.bytes.safe_bytes().ok()).map(hex::encode),
+            trust,
+            observation_hash: observation_hash.hex(),
+            observation_type: ValidationType::from_i32(metadata.observation_type)
+                .map(|v| format!("{:?}", v)).unwrap_or("".to_string()),
+            state: State::from_i32(metadata.state).map(|s| format!("{:?}", s)).unwrap_or("".to_string()),
+            time,
+            height,
+        });
+    }
+    attestations.sort_by(|a, b| a.time.cmp(&b.time));
+    let num_peers = attestations.iter().map(|a| a.node_id.clone()).unique().count() as i64;
+    let num_accepted = attestations.iter()
+        .filter(|a| a.state == format!("{:?}", State::Accepted))
+        .map(|a| a.node_id.clone()).unique().count() as i64;
+    let accepted_trust = attestations.iter()
+        .filter(|a| a.state == format!("{:?}", State::Accepted))
+        .map(|a| (a.node_id.clone(), a.trust.unwrap_or(0.0)))
+        .collect_vec();
+    let acceptance_weight = ObservationConfig::acceptance_weight(&accepted_trust);
+    Ok(TransactionObservationsResponse {
+        transaction_hash: hash_input,
+        num_peers,
+        num_accepted,
+        acceptance_weight,
+        acceptance_threshold: r.node_config.observation.acceptance_weight_threshold,
+        finalized: r.node_config.observation.is_finalized(acceptance_weight),
+        attestations,
+    })
+}
+
+fn involves(tx: &Transaction, address: &Option<redgold_schema::structs::Address>) -> bool {
+    match address {
+        None => true,
+        Some(a) => tx.input_addresses().contains(a) || tx.outputs.iter().any(|o| o.address.as_ref() == Some(a)),
+    }
+}
+
+/// Transactions this node has received or accepted which are not yet finalized, newest first.
+pub async fn handle_pending_transactions(query: PendingQuery, r: Relay) -> RgResult<PendingTransactionsResponse> {
+    let address = query.address.map(|a| a.parse_address()).transpose()?;
+    let mut pending: Vec<(PendingStage, Transaction)> = vec![];
+    for e in r.mempool_entries.iter() {
+        pending.push((PendingStage::Queued, e.value().clone()));
+    }
+    for e in r.transaction_channels.iter() {
+        pending.push((PendingStage::Processing, e.value().transaction.clone()));
+    }
+    let min_time = current_time_millis_i64() - PENDING_ACCEPTED_WINDOW_MS;
+    let recent = r.ds.transaction_store.recent_transaction_hashes(Some(PENDING_ACCEPTED_LIMIT), Some(min_time)).await?;
+    for h in recent.iter() {
+        if let Some(tx) = r.ds.transaction_store.query_maybe_transaction(h).await?.map(|(t, _)| t) {
+            pending.push((PendingStage::Accepted, tx));
+        }
+    }
+    let mut res = vec![];
+    for (stage, tx) in pending.into_iter().unique_by(|(_, t)| t.hash_or()) {
+        if !involves(&tx, &address) {
+            continue;
+        }
+        let hash = tx.hash_or().hex();
+        let obs = handle_explorer_observations(hash.clone(), r.clone()).await?;
+        if stage == PendingStage::Accepted && obs.finalized {
+            continue;
+        }
+        res.push(PendingTransaction {
+            transaction_hash: hash,
+            stage,
+            time: tx.time().cloned().unwrap_or(0),
+            from: tx.first_input_address().and_then(|a| a.render_string().ok()).unwrap_or("".to_string()),
+            amount: tx.total_output_amount_float(),
+            acceptance_weight: obs.acceptance_weight,
+            acceptance_threshold: obs.acceptance_threshold,
+            raw_transaction: tx,
+        });
+    }
+    res.sort_by(|a, b| b.time.cmp(&a.time));
+    Ok(PendingTransactionsResponse { transactions: res })
+}
+
+pub async fn handle_explorer_stats(r: Relay) -> RgResult<NetworkStatsResponse> {
+    let observation_height = r.ds.observation.select_latest_observation(r.node_config.public_key())
+        .await?.and_then(|o| o.height().ok()).unwrap_or(0);
+    let volume = r.ds.config_store.get_typed(&AMM_VOLUME_SUMMARY).await?;
+    let v = volume.clone().unwrap_or_default();
+    Ok(NetworkStatsResponse {
+        total_transactions: r.ds.transaction_store.count_total_transactions().await?,
+        total_utxos: r.ds.transaction_store.count_total_utxos().await?,
+        total_observations: r.ds.observation.count_total_observations().await?,
+        observation_height,
+        // Includes self
+        num_active_peers: (r.ds.peer_store.active_nodes(None).await?.len() as i64) + 1,
+        num_known_peers: r.ds.peer_store.all_peers().await?.len() as i64,
+        pending_transactions: r.transaction_channels.len() as i64,
+        amm_btc_volume: rounded_balance(v.btc_deposit_volume + v.btc_fulfilled_volume),
+        amm_rdg_volume: rounded_balance(v.rdg_deposit_volume + v.rdg_fulfilled_volume),
+        amm_fulfillments: v.num_fulfillments as i64,
+        amm_volume_updated_time: volume.map(|v| v.updated_time),
+    })
 }
\ No newline at end of file
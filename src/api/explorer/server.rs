@@ -8,6 +8,7 @@ use warp::Filter;
 use redgold_keys::address_support::AddressSupport;
 use redgold_schema::structs::{Address, ErrorInfo, FaucetRequest, Request};
 use crate::api::{as_warp_json_response, explorer};
+use crate::api::access::{api_key_filter, EndpointClass, handle_rejection};
 use crate::api::explorer::{handle_explorer_faucet, handle_explorer_pool};
 use crate::api::public_api::{TokenParam, Pagination};
 use crate::core::relay::Relay;
@@ -65,12 +66,14 @@ pub async fn run_server(relay: Relay) -> Result<(), ErrorInfo>{
             async move {
                 as_warp_json_response( explorer::handle_explorer_hash(hash, relay3.clone(), pagination).await)
             }
-        }).with(warp::cors().allow_any_origin());  // add this line to enable CORS;
+        });
 
+    let access = relay.node_config.api_access.clone();
     let explorer_relay3 = relay.clone();
     let explorer_faucet = warp::get()
         .and(warp::path("explorer"))
         .and(warp::path("faucet"))
+        .and(api_key_filter(&access, EndpointClass::Faucet))
         .and(warp::path::param())
         .and(warp::query::<TokenParam>())
         .and(warp::addr::remote())
@@ -83,7 +86,7 @@ pub async fn run_server(relay: Relay) -> Result<(), ErrorInfo>{
                     handle_explorer_faucet(address, relay3, pagination, origin).await
                 )
             }
-        }).with(warp::cors().allow_any_origin());  // add this line to enable CORS;
+        });
 
     let explorer_relay4 = relay.clone();
     let explorer_pools = warp::get()
@@ -96,7 +99,7 @@ pub async fn run_server(relay: Relay) -> Result<(), ErrorInfo>{
                     handle_explorer_pool(relay3).await
                 )
             }
-        }).with(warp::cors().allow_any_origin());  // add this line to enable CORS;
+        });
 
     let explorer_relay2 = relay.clone();
     let explorer_recent = warp::get()
@@ -110,8 +113,7 @@ pub async fn run_server(relay: Relay) -> Result<(), ErrorInfo>{
                 let is_test = query_params.get("is_test").map(|value| value == "true");
                 as_warp_json_response(explorer::handle_explorer_recent(relay3.clone(), is_test).await)
             }
-        })
-        .with(warp::cors().allow_any_origin());
+        });
     //
     //
     // let explorer_relay2 = relay.clone();
@@ -125,6 +127,43 @@ pub async fn run_server(relay: Relay) -> Result<(), ErrorInfo>{
     //     })
     //     .with(warp::cors().allow_any_origin());  // add this line to enable CORS;
 
+    let explorer_relay5 = relay.clone();
+    let explorer_stats = warp::get()
+        .and(warp::path("explorer"))
+        .and(warp::path("stats"))
+        .and_then(move || {
+            let relay3 = explorer_relay5.clone();
+            async move {
+                as_warp_json_response(explorer::handle_explorer_stats(relay3).await)
+            }
+        });
+
+    let explorer_relay6 = relay.clone();
+    let explorer_balance_history = warp::get()
+        .and(warp::path("explorer"))
+        .and(warp::path("balance-history"))
+        .and(warp::path::param())
+        .and(warp::query::<HashMap<String, String>>())
+        .and_then(move |address: String, query_params: HashMap<String, String>| {
+            let relay3 = explorer_relay6.clone();
+            async move {
+                let days = query_params.get("days").and_then(|d| d.parse::<i64>().ok());
+                as_warp_json_response(explorer::handle_explorer_balance_history(address, days, relay3).await)
+            }
+        });
+
+    let explorer_relay7 = relay.clone();
+    let explorer_observations = warp::get()
+        .and(warp::path("explorer"))
+        .and(warp::path("observations"))
+        .and(warp::path::param())
+        .and_then(move |hash: String| {
+            let relay3 = explorer_relay7.clone();
+            async move {
+                as_warp_json_response(explorer::handle_explorer_observations(hash, relay3).await)
+            }
+        });
+
     let explorer_relay3 = relay.clone();
     let explorer_swap = warp::get()
         .and(warp::path("explorer"))
@@ -134,8 +173,7 @@ pub async fn run_server(relay: Relay) -> Result<(), ErrorInfo>{
             async move {
                 as_warp_json_response( explorer::handle_explorer_swap(relay3.clone()).await)
             }
-        })
-        .with(warp::cors().allow_any_origin());  // add this line to enable CORS;
+        });
 
     let port = relay2.node_config.explorer_port();
     info!("Running explorer API on port: {:?}", port.clone());
@@ -149,13 +187,20 @@ pub async fn run_server(relay: Relay) -> Result<(), ErrorInfo>{
         None
     };
 
-    let routes = explorer_hash
+    let read_routes = explorer_hash
+        .or(explorer_stats)
+        .or(explorer_balance_history)
+        .or(explorer_observations)
         .or(explorer_swap)
-        .or(explorer_faucet)
         .or(explorer_pools)
         .or(explorer_recent)
         .or(home);
 
+    let routes = explorer_faucet
+        .or(api_key_filter(&access, EndpointClass::PublicRead).and(read_routes))
+        .recover(handle_rejection)
+        .with(access.cors());
+
     // Create a warp Service using the filter
     // Create the server
     let addr = SocketAddr::from(([0, 0, 0, 0], port));
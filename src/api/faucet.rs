@@ -14,6 +14,7 @@ use crate::e2e::tx_gen::SpendableUTXO;
 use crate::core::relay::Relay;
 use redgold_schema::EasyJson;
 use crate::core::transact::tx_builder_supports::TransactionBuilderSupport;
+use crate::core::transact::fee_policy::fee_estimate;
 
 //
 // async fn faucet_request_old(address_input: String, relay: Relay) -> Result<FaucetResponse, ErrorInfo> {
@@ -61,9 +62,10 @@ use crate::core::transact::tx_builder_supports::TransactionBuilderSupport;
 // }
 
 pub async fn faucet_request(faucet_request: &FaucetRequest, relay: &Relay, origin: Option<&String>) -> Result<FaucetResponse, ErrorInfo> {
-    if relay.node_config.network.is_main() {
-        return Err(error_info("Faucet not supported on mainnet"))
+    if !relay.node_config.features.faucet {
+        return Err(error_info("Faucet not enabled on this network"))
     }
+    relay.check_disk_space()?;
     let option_token = faucet_request.token.clone();
     let faucet_addr = faucet_request.address.clone();
     let addr = faucet_addr.safe_get_msg("No address found")?;
@@ -109,13 +111,14 @@ pub async fn faucet_request(faucet_request: &FaucetRequest, relay: &Relay, origi
     } else {
 
         let mut amount = 5.0f64;
-        if relay.node_config.network.is_main_stage_network() {
+        if relay.node_config.features.faucet_captcha {
             let origin = *origin.safe_get_msg("No origin found")?;
             if !relay.check_rate_limit(origin)? {
                 return Err(error_info("Rate limit exceeded"));
             }
             let token = option_token.safe_get_msg("No recaptcha token found")?;
-            let captcha = recaptcha_verify(token.clone(), None, Some(origin.clone())).await?;
+            let secret = relay.node_config.secrets.get("RECAPTCHA_SECRET");
+            let captcha = recaptcha_verify(token.clone(), secret, Some(origin.clone())).await?;
             if !captcha {
                 return Err(error_info("Recaptcha verification failed"));
             }
@@ -130,6 +133,7 @@ pub async fn faucet_request(faucet_request: &FaucetRequest, relay: &Relay, origi
             .with_utxo(&utxo.utxo_entry)?
             .with_output(&addr, &CurrencyAmount::from_fractional(amount)?)
             .with_message("faucet")?
+            .with_auto_fee(&fee_estimate(relay))
             .build()?
             .sign(&utxo.key_pair)?;
 
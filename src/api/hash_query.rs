@@ -12,7 +12,8 @@ pub async fn hash_query(relay: Relay, hash_input: String, limit: Option<i64>, of
         address_info: None,
         observation: None,
         peer_node_info: None,
-        peer_id_info: None
+        peer_id_info: None,
+        conflicts: vec![],
     };
 
     let mut addr = None;
@@ -32,11 +33,15 @@ pub async fn hash_query(relay: Relay, hash_input: String, limit: Option<i64>, of
     } else {
         let h = from_hex(hash_input.clone())?;
         let hash = Hash::new(h.clone());
+        response.conflicts = relay.ds.transaction_store.query_conflicts(&hash).await?;
         let maybe_tx_info = relay.ds.resolve_transaction_hash(&hash).await?;
         if let Some(tx_info) = maybe_tx_info {
             response.transaction_info = Some(tx_info);
             return Ok(response)
         }
+        if !response.conflicts.is_empty() {
+            return Ok(response)
+        }
     }
 
     if let Some(pk) = PublicKey::from_hex(hash_input.clone()).ok() {
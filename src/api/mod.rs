@@ -13,11 +13,14 @@ use serde::__private::de::Borrowed;
 use tracing::info;
 use uuid::Uuid;
 use warp::reply::Json;
-use warp::{Filter, Rejection};
+use warp::{Filter, Rejection, Reply};
+use warp::http::header::CONTENT_TYPE;
+use warp::http::StatusCode;
 use redgold_keys::request_support::{RequestSupport, ResponseSupport};
 use redgold_schema::{EasyJson, error_info, ProtoHashable, ProtoSerde, RgResult, SafeOption, structs};
 use redgold_schema::errors::EnhanceErrorInfo;
-use redgold_schema::structs::{AboutNodeRequest, AboutNodeResponse, Address, UtxoId, GetPeersInfoRequest, GetPeersInfoResponse, Request, Response, HashSearchResponse, HashSearchRequest, Transaction, PublicKey};
+use redgold_schema::structs::{AboutNodeRequest, AboutNodeResponse, Address, UtxoId, GetPeersInfoRequest, GetPeersInfoResponse, Request, Response, HashSearchResponse, HashSearchRequest, Transaction, PublicKey, BatchBalanceRequest, BatchBalanceResponse};
+use crate::api::client_pool::ClientPool;
 use crate::core::relay::Relay;
 use crate::node_config::NodeConfig;
 use redgold_schema::util::lang_util::SameResult;
@@ -33,6 +36,9 @@ pub mod hash_query;
 pub mod udp_api;
 pub mod about;
 pub mod explorer;
+pub mod access;
+pub mod batch_balance;
+pub mod client_pool;
 
 
 #[derive(Clone)]
@@ -40,7 +46,8 @@ pub struct RgHttpClient {
     pub url: String,
     pub port: u16,
     pub timeout: Duration,
-    pub relay: Option<Relay>
+    pub relay: Option<Relay>,
+    pub api_key: Option<String>,
 }
 
 impl RgHttpClient {
@@ -50,6 +57,7 @@ impl RgHttpClient {
             port,
             timeout: Duration::from_secs(60),
             relay,
+            api_key: None,
         }
     }
     #[allow(dead_code)]
@@ -57,6 +65,22 @@ impl RgHttpClient {
         return "http://".to_owned() + &*self.url.clone() + ":" + &*self.port.to_string();
     }
 
+    fn host(&self) -> String {
+        format!("{}:{}", self.url, self.port)
+    }
+
+    // Pooled client for this destination, failing fast while its circuit breaker is open
+    fn pooled_client(&self) -> RgResult<reqwest::Client> {
+        let pool = ClientPool::global();
+        let host = self.host();
+        pool.check_breaker(&host)?;
+        pool.client(&host)
+    }
+
+    fn record_result<T>(&self, result: &RgResult<T>) {
+        ClientPool::global().record_result(&self.host(), result.is_ok());
+    }
+
     #[allow(dead_code)]
     pub async fn json_post_request<Req: Serialize + ?Sized, Resp: DeserializeOwned>(
         &self,
@@ -71,13 +95,18 @@ impl RgHttpClient {
         r: &Req,
         endpoint: String,
     ) -> Result<Resp, ErrorInfo> {
-        use reqwest::ClientBuilder;
-        let client = ClientBuilder::new().timeout(self.timeout).build().unwrap();
-        let sent = client
-            .post(format!("{}/{}", self.formatted_url(), endpoint))
+        let client = self.pooled_client()?;
+        let mut post = client.post(format!("{}/{}", self.formatted_url(), endpoint))
+            .timeout(self.timeout);
+        if let Some(k) = self.api_key.as_ref() {
+            post = post.header(access::API_KEY_HEADER, k);
+        }
+        let sent = post
             .json::<Req>(r)
             .send();
         let response = sent.await;
+        let status = response.as_ref().map(|_| ()).map_err(|e| error_info(e.to_string()));
+        self.record_result(&status);
         match response {
             Ok(r) => {
                 let text = r.text().await
@@ -95,14 +124,19 @@ impl RgHttpClient {
         r: &Req,
         endpoint: String,
     ) -> Result<Response, ErrorInfo> {
-        use reqwest::ClientBuilder;
-        let client = ClientBuilder::new().timeout(self.timeout).build().unwrap();
-        let sent = client
-            .post(format!("{}/{}", self.formatted_url(), endpoint))
+        let client = self.pooled_client()?;
+        let mut post = client.post(format!("{}/{}", self.formatted_url(), endpoint))
+            .timeout(self.timeout);
+        if let Some(k) = self.api_key.as_ref() {
+            post = post.header(access::API_KEY_HEADER, k);
+        }
+        let sent = post
             .body(r.encode_to_vec())
             .send();
         let response = sent.await.map_err(|e| ErrorInfo::error_info(
-            format!("Proto request failure: {}", e.to_string())))?;
+            format!("Proto request failure: {}", e.to_string())));
+        self.record_result(&response);
+        let response = response?;
         let bytes = response.bytes().await.map_err(|e| ErrorInfo::error_info(
             format!("Proto request bytes decode failure: {}", e.to_string())))?;
         let vec = bytes.to_vec();
@@ -175,6 +209,26 @@ impl RgHttpClient {
         Ok(response.resolve_code_response.ok_or(error_info("Missing resolve code response"))?)
     }
 
+    pub async fn batch_balance(&self, addresses: Vec<Address>) -> RgResult<BatchBalanceResponse> {
+        let mut req = Request::default();
+        let mut bbr = BatchBalanceRequest::default();
+        bbr.addresses = addresses;
+        req.batch_balance_request = Some(bbr);
+        let response = self.proto_post_request(req, None, None).await?;
+        response.batch_balance_response.ok_msg("Missing batch balance response")
+    }
+
+    pub async fn batch_balance_xpub(&self, xpub: String, start_index: i64, end_index: i64) -> RgResult<BatchBalanceResponse> {
+        let mut req = Request::default();
+        let mut bbr = BatchBalanceRequest::default();
+        bbr.xpub = Some(xpub);
+        bbr.start_index = Some(start_index);
+        bbr.end_index = Some(end_index);
+        req.batch_balance_request = Some(bbr);
+        let response = self.proto_post_request(req, None, None).await?;
+        response.batch_balance_response.ok_msg("Missing batch balance response")
+    }
+
     pub async fn genesis(&self) -> RgResult<Transaction> {
         let mut req = Request::default();
         req.genesis_request = Some(structs::GenesisRequest::default());
@@ -183,6 +237,35 @@ impl RgHttpClient {
     }
 
 
+    /// Sends a payment request through this node, which relays it to the recipient's nodes.
+    pub async fn send_payment_request(&self, payment_request: &structs::PaymentRequest) -> RgResult<()> {
+        let mut req = Request::default();
+        req.payment_request = Some(payment_request.clone());
+        self.proto_post_request(req, None, None).await?;
+        Ok(())
+    }
+
+    /// GET of a public endpoint negotiated as protobuf rather than JSON.
+    pub async fn proto_get<Resp: ProtoSerde>(&self, endpoint: impl Into<String>) -> RgResult<Resp> {
+        let client = self.pooled_client()?;
+        let mut get = client.get(format!("{}/{}", self.formatted_url(), endpoint.into()))
+            .header(reqwest::header::ACCEPT, PROTOBUF_CONTENT_TYPE)
+            .timeout(self.timeout);
+        if let Some(k) = self.api_key.as_ref() {
+            get = get.header(access::API_KEY_HEADER, k);
+        }
+        let response = get.send().await.map_err(|e| error_info(format!("Proto get failure: {}", e)));
+        self.record_result(&response);
+        let response = response?;
+        let success = response.status().is_success();
+        let bytes = response.bytes().await
+            .map_err(|e| error_info(format!("Proto get bytes failure: {}", e)))?.to_vec();
+        if !success {
+            return Err(ErrorInfo::proto_deserialize(bytes)?);
+        }
+        Resp::proto_deserialize(bytes)
+    }
+
     #[allow(dead_code)]
     pub async fn query_hash(
         &self,
@@ -323,6 +406,35 @@ ErrT: ?Sized + Serialize + Clone {
 }
 
 
+pub const PROTOBUF_CONTENT_TYPE: &str = "application/x-protobuf";
+
+/// Whether the Accept header asks for protobuf, anything else gets JSON.
+pub fn accepts_protobuf(accept: Option<&String>) -> bool {
+    accept.map(|a| a.split(',')
+        .filter_map(|t| t.split(';').next())
+        .any(|t| t.trim().eq_ignore_ascii_case(PROTOBUF_CONTENT_TYPE))
+    ).unwrap_or(false)
+}
+
+/// JSON by default, or the protobuf encoding of the same message when requested through the
+/// Accept header. Protobuf errors are an encoded ErrorInfo with an error status, since the body
+/// alone can't be told apart from the response message.
+pub fn negotiated_response<T: Serialize + ProtoSerde>(accept: Option<&String>, response: RgResult<T>) -> warp::reply::Response {
+    if !accepts_protobuf(accept) {
+        return response.map_err(|e| warp::reply::json(&e))
+            .map(|r| warp::reply::json(&r))
+            .combine()
+            .into_response();
+    }
+    let (status, body) = match response {
+        Ok(r) => (StatusCode::OK, r.proto_serialize()),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.proto_serialize()),
+    };
+    warp::reply::with_status(
+        warp::reply::with_header(body, CONTENT_TYPE, PROTOBUF_CONTENT_TYPE), status
+    ).into_response()
+}
+
 // TODO: implement as trait on result
 pub fn as_warp_json_response<T: Serialize, E: Serialize>(response: Result<T, E>) -> Result<Json, warp::reject::Rejection> {
     Ok(response.map_err(|e| warp::reply::json(&e))
@@ -331,3 +443,24 @@ pub fn as_warp_json_response<T: Serialize, E: Serialize>(response: Result<T, E>)
     )
 }
 
+#[tokio::test]
+async fn negotiated_response_round_trips_protobuf() {
+    let accept = Some(format!("{}, application/json;q=0.5", PROTOBUF_CONTENT_TYPE));
+    let mut about = AboutNodeResponse::default();
+    about.num_active_peers = 3;
+    let response = negotiated_response(accept.as_ref(), Ok(about.clone()));
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers().get(CONTENT_TYPE).expect("content type"), PROTOBUF_CONTENT_TYPE);
+    let body = warp::hyper::body::to_bytes(response.into_body()).await.expect("body").to_vec();
+    assert_eq!(AboutNodeResponse::proto_deserialize(body).expect("decode"), about);
+
+    let response = negotiated_response::<HashSearchResponse>(accept.as_ref(), Err(error_info("missing")));
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    let body = warp::hyper::body::to_bytes(response.into_body()).await.expect("body").to_vec();
+    assert_eq!(ErrorInfo::proto_deserialize(body).expect("decode").message, "missing");
+
+    let response = negotiated_response(None, Ok(about.clone()));
+    let body = warp::hyper::body::to_bytes(response.into_body()).await.expect("body");
+    assert_eq!(serde_json::from_slice::<AboutNodeResponse>(&body).expect("json"), about);
+    assert!(!accepts_protobuf(Some(&"application/json".to_string())));
+}
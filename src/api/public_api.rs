@@ -17,16 +17,16 @@ use tokio::time::sleep;
 use warp::reply::Json;
 use warp::{Filter, Server};
 use warp::http::Response;
-use redgold_schema::{empty_public_request, empty_public_response, from_hex, json, ProtoHashable, ProtoSerde, RgResult, SafeOption, structs};
-use redgold_schema::structs::{AboutNodeRequest, AboutNodeResponse, AddressInfo, FaucetRequest, FaucetResponse, HashSearchRequest, HashSearchResponse, NetworkEnvironment, Request, Response as RResponse, Seed};
-use redgold_schema::transaction::rounded_balance_i64;
+use redgold_schema::{EasyJson, empty_public_request, empty_public_response, from_hex, json, ProtoHashable, ProtoSerde, RgResult, SafeOption, structs};
+use redgold_schema::structs::{AboutNodeRequest, AboutNodeResponse, AddressInfo, FaucetRequest, FaucetResponse, HashSearchRequest, HashSearchResponse, NetworkEnvironment, PartyInfo, PaymentRequest, Request, Response as RResponse, Seed, UtxoId, UtxoStateProof, ValidateTransactionRequest, ValidateTransactionResponse};
+use redgold_sdk_client::RedgoldClient;
 
 use crate::core::internal_message::{new_channel, PeerMessage, RecvAsyncErrorInfo, SendErrorInfo, TransactionMessage};
 use crate::core::relay::Relay;
 use redgold_data::data_store::DataStore;
 use crate::genesis::create_test_genesis_transaction;
 use crate::schema::structs::{
-    Address, AddressType, ErrorInfo, QueryAddressesRequest, QueryTransactionResponse,
+    Address, AddressType, ErrorInfo, Hash, QueryAddressesRequest, QueryTransactionResponse,
 };
 use crate::schema::structs::{
     PublicRequest, PublicResponse, ResponseMetadata, SubmitTransactionRequest,
@@ -36,7 +36,17 @@ use crate::schema::structs::{QueryAddressesResponse, Transaction};
 use crate::schema::{bytes_data, error_info};
 use crate::schema::{response_metadata, SafeBytesAccess, WithMetadataHashable};
 use crate::{api, schema, util};
-use crate::api::{about, as_warp_json_response, explorer};
+use crate::api::{about, as_warp_json_response, explorer, negotiated_response};
+use crate::api::explorer::graph;
+use crate::multiparty::watcher_validation::WatcherStatusResponse;
+use crate::core::peer_scores::PeerScore;
+use crate::core::transact::fee_policy::FeeEstimate;
+use redgold_data::name_store::NameRecord;
+use redgold_keys::address_support::AddressSupport;
+use crate::api::explorer::graph::{TxGraphQuery, TxGraphResponse};
+use crate::api::explorer::{AddressPoolInfo, BalanceHistoryResponse, PendingQuery, PendingTransactionsResponse, TransactionObservationsResponse};
+use crate::api::access::{AccessDenied, API_KEY_HEADER, api_key_filter, EndpointClass, handle_rejection};
+use redgold_keys::request_support::RequestSupport;
 use crate::api::faucet::faucet_request;
 use crate::api::hash_query::hash_query;
 use crate::core::peer_rx_event_handler::PeerRxEventHandler;
@@ -52,7 +62,8 @@ pub struct PublicClient {
     pub url: String,
     pub port: u16,
     pub timeout: Duration,
-    pub relay: Option<Relay>
+    pub relay: Option<Relay>,
+    pub api_key: Option<String>,
 }
 
 impl PublicClient {
@@ -61,7 +72,21 @@ impl PublicClient {
     // }
 
     pub fn client_wrapper(&self) -> api::RgHttpClient {
-        api::RgHttpClient::new(self.url.clone(), self.port as u16, self.relay.clone())
+        let mut client = api::RgHttpClient::new(self.url.clone(), self.port as u16, self.relay.clone());
+        client.api_key = self.api_key.clone();
+        client
+    }
+
+    /// Lightweight typed client for the same node, requests sent through it are unsigned.
+    pub fn sdk_client(&self) -> RedgoldClient {
+        RedgoldClient::new(self.url.clone(), self.port)
+            .with_api_key(self.api_key.clone())
+            .with_timeout(self.timeout)
+    }
+
+    pub fn with_api_key(mut self, api_key: Option<String>) -> Self {
+        self.api_key = api_key;
+        self
     }
 
     pub fn local(port: u16, _relay: Option<Relay>) -> Self {
@@ -70,6 +95,7 @@ impl PublicClient {
             port,
             timeout: Duration::from_secs(30),
             relay: None,
+            api_key: None,
         }
     }
 
@@ -79,6 +105,7 @@ impl PublicClient {
             port,
             timeout: Duration::from_secs(30),
             relay,
+            api_key: None,
         }
     }
 
@@ -104,8 +131,11 @@ impl PublicClient {
         //     "Sending PublicRequest: {:?}",
         //     serde_json::to_string(&r.clone()).unwrap()
         // );
-        let sent = client
-            .post(self.formatted_url() + "/request")
+        let mut post = client.post(self.formatted_url() + "/request");
+        if let Some(k) = self.api_key.as_ref() {
+            post = post.header(API_KEY_HEADER, k);
+        }
+        let sent = post
             .json(r)
             .send();
         let response = sent.await;
@@ -134,7 +164,10 @@ impl PublicClient {
         t: &Transaction,
         sync: bool,
     ) -> Result<SubmitTransactionResponse, ErrorInfo> {
-
+        // Node internal submissions stay signed by the relay keypair
+        if self.relay.is_none() {
+            return self.sdk_client().submit(t, sync).await;
+        }
         let c = self.client_wrapper();
 
         let mut request = Request::default();
@@ -153,17 +186,10 @@ impl PublicClient {
         &self,
         t: &Address
     ) -> Result<FaucetResponse, ErrorInfo> {
-        let mut request = Request::default();
-        request.faucet_request = Some(FaucetRequest {
-            address: Some(t.clone()),
-            token: None
-        });
         info!("Sending faucet request: {}", t.clone().render_string().expect("r"));
-        let response = self.client_wrapper().proto_post_request(request, None, None).await?;
-        let fr = response.faucet_response.safe_get()?;
-        let res = json(&response)?;
-        info!("Faucet response: {}", res);
-        Ok(fr.clone())
+        let fr = self.sdk_client().faucet(t).await?;
+        info!("Faucet response: {}", fr.json_or());
+        Ok(fr)
     }
 
     #[allow(dead_code)]
@@ -202,28 +228,112 @@ impl PublicClient {
         &self,
         input: String,
     ) -> Result<HashSearchResponse, ErrorInfo> {
-        let mut request = Request::default();
-        request.hash_search_request = Some(HashSearchRequest {
-            search_string: input
-        });
-        Ok(self.client_wrapper().proto_post_request(request, None, None).await?.hash_search_response.safe_get()?.clone())
+        self.sdk_client().hash_search(input).await
     }
     pub async fn balance(
         &self,
         address: Address,
     ) -> Result<f64, ErrorInfo> {
-        let response = self.query_hash(address.render_string().expect("")).await?;
-        let ai = response.address_info.safe_get_msg("missing address_info")?;
-        Ok(rounded_balance_i64(ai.balance))
+        self.sdk_client().balance(&address).await
     }
 
     pub async fn address_info(
         &self,
         address: Address,
     ) -> Result<AddressInfo, ErrorInfo> {
-        let response = self.query_hash(address.render_string().expect("")).await?;
-        let ai = response.address_info.safe_get_msg("missing address_info")?;
-        Ok(ai.clone())
+        self.sdk_client().address_info(&address).await
+    }
+
+    pub async fn balance_history(&self, address: &Address) -> RgResult<BalanceHistoryResponse> {
+        let client = ClientBuilder::new().timeout(self.timeout).build().unwrap();
+        let mut get = client.get(format!("{}/balance-history/{}", self.formatted_url(), address.render_string()?));
+        if let Some(k) = self.api_key.as_ref() {
+            get = get.header(API_KEY_HEADER, k);
+        }
+        let response = get.send().await.map_err(|e| error_info(e.to_string()))?;
+        let text = response.text().await.map_err(|e| error_info(e.to_string()))?;
+        serde_json::from_str::<BalanceHistoryResponse>(&text)
+            .map_err(|e| error_info(format!("{} {}", e.to_string(), text)))
+    }
+
+    pub async fn transaction_observations(&self, hash: &Hash) -> RgResult<TransactionObservationsResponse> {
+        let client = ClientBuilder::new().timeout(self.timeout).build().unwrap();
+        let mut get = client.get(format!("{}/observations/{}", self.formatted_url(), hash.hex()));
+        if let Some(k) = self.api_key.as_ref() {
+            get = get.header(API_KEY_HEADER, k);
+        }
+        let response = get.send().await.map_err(|e| error_info(e.to_string()))?;
+        let text = response.text().await.map_err(|e| error_info(e.to_string()))?;
+        serde_json::from_str::<TransactionObservationsResponse>(&text)
+            .map_err(|e| error_info(format!("{} {}", e.to_string(), text)))
+    }
+
+    /// Spend graph up to `hops` around a transaction hash or an address's recent transactions.
+    pub async fn transaction_graph(&self, input: &String, hops: i64) -> RgResult<TxGraphResponse> {
+        self.sdk_client().get_json(format!("graph/{}?hops={}", input, hops)).await
+    }
+
+    /// Latency and reliability scores of the peers this node has called, best first.
+    pub async fn peer_scores(&self) -> RgResult<Vec<PeerScore>> {
+        self.sdk_client().get_json("peer-scores").await
+    }
+
+    /// Watcher startup validation and balance audit, showing whether fulfillments are held in safe mode.
+    pub async fn watcher_status(&self) -> RgResult<WatcherStatusResponse> {
+        self.sdk_client().get_json("watcher-status").await
+    }
+
+    /// Fee this node requires to accept a transaction and the rate it suggests, for use with
+    /// `TransactionBuilder::with_auto_fee`.
+    pub async fn fee_estimate(&self) -> RgResult<FeeEstimate> {
+        self.sdk_client().get_json("fee-estimate").await
+    }
+
+    /// Light client proof that the UTXO exists and is unspent as of the node's latest checkpoint,
+    /// check it with `StateProofSupport::verify`.
+    pub async fn state_proof(&self, utxo_id: &UtxoId) -> RgResult<UtxoStateProof> {
+        let hash = utxo_id.transaction_hash.safe_get_msg("Missing UTXO transaction hash")?;
+        self.sdk_client().get_json(format!("state-proof/{}/{}", hash.hex(), utxo_id.output_index)).await
+    }
+
+    /// Address currently bound to a registered name, failing if it is unregistered or expired.
+    pub async fn resolve_name(&self, name: impl Into<String>) -> RgResult<NameRecord> {
+        self.sdk_client().get_json(format!("name/{}", name.into())).await
+    }
+
+    /// Payment requests other users have sent to this node's peer id, newest first.
+    pub async fn payment_requests(&self) -> RgResult<Vec<PaymentRequest>> {
+        self.sdk_client().get_json("payment-requests").await
+    }
+
+    /// Transactions not yet finalized, restricted to those involving the address when given.
+    pub async fn pending_transactions(&self, address: Option<&Address>) -> RgResult<PendingTransactionsResponse> {
+        let path = match address {
+            Some(a) => format!("pending?address={}", a.render_string()?),
+            None => "pending".to_string(),
+        };
+        self.sdk_client().get_json(path).await
+    }
+
+    /// Full pool info including admission state, as shown in the GUI order book.
+    pub async fn order_book(&self) -> RgResult<Option<AddressPoolInfo>> {
+        self.sdk_client().get_json("order-book").await
+    }
+
+    pub async fn validate_transaction(&self, tx: &Transaction) -> RgResult<ValidateTransactionResponse> {
+        let mut request = Request::default();
+        request.validate_transaction_request = Some(ValidateTransactionRequest {
+            transaction: Some(tx.clone()),
+        });
+        let response = self.client_wrapper().proto_post_request(request, None, None).await?;
+        response.validate_transaction_response.ok_msg("Missing validate transaction response")
+    }
+
+    pub async fn parties_info(&self) -> RgResult<Vec<PartyInfo>> {
+        let mut request = Request::default();
+        request.get_parties_info_request = Some(Default::default());
+        let response = self.client_wrapper().proto_post_request(request, None, None).await?;
+        Ok(response.get_parties_info_response.safe_get_msg("Missing parties info response")?.party_info.clone())
     }
 
     pub async fn about(&self) -> Result<AboutNodeResponse, ErrorInfo> {
@@ -258,13 +368,13 @@ impl PublicClient {
 // TODO: wrapper function to handle errors and return as json
 // TODO: wrapper function to covnert result to warp json
 
-async fn process_request(request: PublicRequest, relay: Relay) -> Json {
+async fn process_request(request: PublicRequest, relay: Relay, accept: Option<String>) -> warp::reply::Response {
     let response = process_request_inner(request, relay).await.map_err(|e| {
         let mut response1 = empty_public_response();
         response1.response_metadata = Some(e.response_metadata());
         response1
     }).combine();
-    warp::reply::json(&response)
+    negotiated_response(accept.as_ref(), Ok(response))
 }
 
 async fn process_request_inner(request: PublicRequest, relay: Relay) -> Result<PublicResponse, ErrorInfo> {
@@ -365,12 +475,40 @@ pub async fn as_warp_proto_bytes<T: ProtoSerde>(response: Result<T, ErrorInfo>)
     Response::builder().body(vec).expect("a")
 }
 
-pub async fn handle_proto_post(reqb: Bytes, _address: Option<SocketAddr>, relay: Relay, origin: Option<String>) -> Result<RResponse, ErrorInfo> {
+/// Faucet requests are served by the shared request handler, so every route accepting a
+/// `Request` must check the faucet key itself.
+fn check_faucet_access(relay: &Relay, request: &Request, api_key: Option<&String>) -> Result<(), AccessDenied> {
+    if request.faucet_request.is_some() {
+        relay.node_config.api_access.check(&EndpointClass::Faucet, api_key)?;
+    }
+    Ok(())
+}
+
+fn check_proto_access(relay: &Relay, request: &Request, api_key: Option<&String>) -> Result<(), AccessDenied> {
+    check_faucet_access(relay, request, api_key)?;
+    // Peer requests are signed by the node and don't require an api key
+    match relay.node_config.api_access.check(&EndpointClass::PublicRead, api_key) {
+        Err(denied) if request.verify_auth().is_err() => Err(denied),
+        _ => Ok(())
+    }
+}
+
+pub async fn handle_proto_post(
+    reqb: Bytes, _address: Option<SocketAddr>, relay: Relay, origin: Option<String>, api_key: Option<String>
+) -> Response<Vec<u8>> {
     counter!("redgold.api.handle_proto_post").increment(1);
     let vec_b = reqb.to_vec();
-    let mut request = Request::proto_deserialize(vec_b)?;
+    let mut request = match Request::proto_deserialize(vec_b) {
+        Ok(request) => request,
+        Err(e) => return as_warp_proto_bytes::<RResponse>(Err(e)).await,
+    };
+    if let Err(denied) = check_proto_access(&relay, &request, api_key.as_ref()) {
+        let mut response = as_warp_proto_bytes::<RResponse>(Err(denied.error_info())).await;
+        *response.status_mut() = denied.status_code();
+        return response;
+    }
     request.origin = origin;
-    relay.receive_request_send_internal(request, None).await
+    as_warp_proto_bytes(relay.receive_request_send_internal(request, None).await).await
 }
 
 pub async fn run_server(relay: Relay) -> Result<(), ErrorInfo>{
@@ -395,11 +533,12 @@ pub async fn run_server(relay: Relay) -> Result<(), ErrorInfo>{
         // Only accept bodies smaller than 16kb...
         .and(warp::body::content_length_limit(1024 * 16))
         .and(warp::body::json::<PublicRequest>())
-        .and_then(move |request: PublicRequest| {
+        .and(warp::header::optional::<String>("accept"))
+        .and_then(move |request: PublicRequest, accept: Option<String>| {
             let relay3 = trelay.clone();
             async move {
-                let res: Result<Json, warp::reject::Rejection> =
-                    Ok(process_request(request, relay3.clone()).await);
+                let res: Result<warp::reply::Response, warp::reject::Rejection> =
+                    Ok(process_request(request, relay3.clone(), accept).await);
                 res
             }
         });
@@ -426,15 +565,12 @@ pub async fn run_server(relay: Relay) -> Result<(), ErrorInfo>{
     let query_hash = warp::get()
         .and(warp::path("query"))
         .and(warp::path::param())
-        .and_then(move |address: String| {
+        .and(warp::header::optional::<String>("accept"))
+        .and_then(move |address: String, accept: Option<String>| {
             let relay3 = qry_relay.clone();
             async move {
-                let res: Result<Json, warp::reject::Rejection> =
-                    Ok(hash_query(relay3.clone(), address, None, None).await
-                        .map_err(|e| warp::reply::json(&e))
-                        .map(|r| warp::reply::json(&r))
-                        .combine()
-                    );
+                let res: Result<warp::reply::Response, warp::reject::Rejection> =
+                    Ok(negotiated_response(accept.as_ref(), hash_query(relay3.clone(), address, None, None).await));
                 res
             }
         });
@@ -443,13 +579,16 @@ pub async fn run_server(relay: Relay) -> Result<(), ErrorInfo>{
 
     let about = warp::get()
         .and(warp::path("about"))
-        .and_then(move || {
+        .and(warp::header::optional::<String>("accept"))
+        .and_then(move |accept: Option<String>| {
             let relay3 = a_relay.clone();
             async move {
                 // TODO call about handler
                 // TODO: Should this be hitting the peer message channel?
                 let abr = about::handle_about_node(AboutNodeRequest::default(), relay3.clone()).await;
-                as_warp_json_response(abr)
+                let res: Result<warp::reply::Response, warp::reject::Rejection> =
+                    Ok(negotiated_response(accept.as_ref(), abr));
+                res
             }
         });
 
@@ -466,6 +605,13 @@ pub async fn run_server(relay: Relay) -> Result<(), ErrorInfo>{
             }
         });
 
+    let scores_relay = relay.clone();
+    let peer_scores = warp::get()
+        .and(warp::path("peer-scores"))
+        .map(move || {
+            warp::reply::json(&crate::core::peer_scores::peer_scores(&scores_relay))
+        });
+
     let p2_relay = relay.clone();
     let transaction_lookup = warp::get()
         .and(warp::path("transaction"))
@@ -487,11 +633,180 @@ pub async fn run_server(relay: Relay) -> Result<(), ErrorInfo>{
     let address_lookup = warp::get()
         .and(warp::path("address"))
         .and(warp::path::param())
-        .and_then(move |hash: String| {
+        .and(warp::header::optional::<String>("accept"))
+        .and_then(move |hash: String, accept: Option<String>| {
             let relay3 = address_relay.clone();
             async move {
                 let ps = relay3.ds.get_address_string_info(hash).await;
-                let res: Result<Json, warp::reject::Rejection> = Ok(ps
+                let res: Result<warp::reply::Response, warp::reject::Rejection> =
+                    Ok(negotiated_response(accept.as_ref(), ps));
+                res
+            }
+        });
+
+    let validate_relay = relay.clone();
+    let validate = warp::post()
+        .and(warp::path("validate"))
+        .and(warp::body::content_length_limit(1024 * 1024))
+        .and(warp::body::json::<Transaction>())
+        .and_then(move |tx: Transaction| {
+            let relay3 = validate_relay.clone();
+            async move {
+                let res = crate::core::transact::validate::dry_run_validate(&tx, &relay3).await;
+                let res: Result<Json, warp::reject::Rejection> = Ok(warp::reply::json(&res));
+                res
+            }
+        });
+
+    let balance_history_relay = relay.clone();
+    let balance_history = warp::get()
+        .and(warp::path("balance-history"))
+        .and(warp::path::param())
+        .and_then(move |address: String| {
+            let relay3 = balance_history_relay.clone();
+            async move {
+                let res = explorer::handle_explorer_balance_history(address, None, relay3).await;
+                let res: Result<Json, warp::reject::Rejection> = Ok(res
+                       .map_err(|e| warp::reply::json(&e))
+                       .map(|r| warp::reply::json(&r))
+                       .combine());
+                res
+            }
+        });
+
+    let observations_relay = relay.clone();
+    let observations = warp::get()
+        .and(warp::path("observations"))
+        .and(warp::path::param())
+        .and_then(move |hash: String| {
+            let relay3 = observations_relay.clone();
+            async move {
+                let res = explorer::handle_explorer_observations(hash, relay3).await;
+                let res: Result<Json, warp::reject::Rejection> = Ok(res
+                       .map_err(|e| warp::reply::json(&e))
+                       .map(|r| warp::reply::json(&r))
+                       .combine());
+                res
+            }
+        });
+
+    let graph_relay = relay.clone();
+    let graph = warp::get()
+        .and(warp::path("graph"))
+        .and(warp::path::param())
+        .and(warp::query::<TxGraphQuery>())
+        .and_then(move |input: String, query: TxGraphQuery| {
+            let relay3 = graph_relay.clone();
+            async move {
+                let res = graph::handle_transaction_graph(input, query, relay3).await;
+                let res: Result<Json, warp::reject::Rejection> = Ok(res
+                       .map_err(|e| warp::reply::json(&e))
+                       .map(|r| warp::reply::json(&r))
+                       .combine());
+                res
+            }
+        });
+
+    let pending_relay = relay.clone();
+    let pending = warp::get()
+        .and(warp::path("pending"))
+        .and(warp::query::<PendingQuery>())
+        .and_then(move |query: PendingQuery| {
+            let relay3 = pending_relay.clone();
+            async move {
+                let res = explorer::handle_pending_transactions(query, relay3).await;
+                let res: Result<Json, warp::reject::Rejection> = Ok(res
+                       .map_err(|e| warp::reply::json(&e))
+                       .map(|r| warp::reply::json(&r))
+                       .combine());
+                res
+            }
+        });
+
+    let order_book_relay = relay.clone();
+    let order_book = warp::get()
+        .and(warp::path("order-book"))
+        .and_then(move || {
+            let relay3 = order_book_relay.clone();
+            async move {
+                let res = explorer::get_address_pool_info(relay3).await;
+                let res: Result<Json, warp::reject::Rejection> = Ok(res
+                       .map_err(|e| warp::reply::json(&e))
+                       .map(|r| warp::reply::json(&r))
+                       .combine());
+                res
+            }
+        });
+
+    let watcher_status_relay = relay.clone();
+    let watcher_status = warp::get()
+        .and(warp::path("watcher-status"))
+        .and_then(move || {
+            let relay3 = watcher_status_relay.clone();
+            async move {
+                let res = crate::multiparty::watcher_validation::watcher_status(&relay3).await;
+                let res: Result<Json, warp::reject::Rejection> = Ok(res
+                       .map_err(|e| warp::reply::json(&e))
+                       .map(|r| warp::reply::json(&r))
+                       .combine());
+                res
+            }
+        });
+
+    let fee_estimate_relay = relay.clone();
+    let fee_estimate = warp::get()
+        .and(warp::path("fee-estimate"))
+        .map(move || warp::reply::json(&crate::core::transact::fee_policy::fee_estimate(&fee_estimate_relay)));
+
+    let state_proof_relay = relay.clone();
+    let state_proof = warp::get()
+        .and(warp::path("state-proof"))
+        .and(warp::path::param())
+        .and(warp::path::param())
+        .and_then(move |transaction_hash: String, output_index: i64| {
+            let relay3 = state_proof_relay.clone();
+            async move {
+                let res = async {
+                    let utxo_id = UtxoId::new(&Hash::from_hex(transaction_hash)?, output_index);
+                    crate::core::checkpoint::utxo_state_proof(&relay3, &utxo_id).await
+                }.await;
+                let res: Result<Json, warp::reject::Rejection> = Ok(res
+                       .map_err(|e| warp::reply::json(&e))
+                       .map(|r| warp::reply::json(&r))
+                       .combine());
+                res
+            }
+        });
+
+    let payment_requests_relay = relay.clone();
+    let payment_requests = warp::get()
+        .and(warp::path("payment-requests"))
+        .map(move || warp::reply::json(&crate::core::payment_requests::payment_requests(&payment_requests_relay)));
+
+    let name_relay = relay.clone();
+    let name_lookup = warp::get()
+        .and(warp::path("name"))
+        .and(warp::path::param())
+        .and_then(move |name: String| {
+            let relay3 = name_relay.clone();
+            async move {
+                let res = crate::core::transact::name_registry::resolve_name(&relay3.ds, &name).await;
+                let res: Result<Json, warp::reject::Rejection> = Ok(res
+                       .map_err(|e| warp::reply::json(&e))
+                       .map(|r| warp::reply::json(&r))
+                       .combine());
+                res
+            }
+        });
+
+    let seed_relay = relay.clone();
+    let seed_report = warp::get()
+        .and(warp::path("seed-report"))
+        .and_then(move || {
+            let relay3 = seed_relay.clone();
+            async move {
+                let res = crate::core::seed_mode::seed_quality_report(&relay3).await;
+                let res: Result<Json, warp::reject::Rejection> = Ok(res
                        .map_err(|e| warp::reply::json(&e))
                        .map(|r| warp::reply::json(&r))
                        .combine());
@@ -571,18 +886,24 @@ pub async fn run_server(relay: Relay) -> Result<(), ErrorInfo>{
         .and(warp::path("request_peer"))
         .and(warp::body::json::<Request>())
         .and(warp::addr::remote())
-        .and_then(move |request: Request, _address: Option<SocketAddr>| {
+        .and(warp::header::optional::<String>(API_KEY_HEADER))
+        .and_then(move |request: Request, _address: Option<SocketAddr>, api_key: Option<String>| {
             let relay3 = bin_relay.clone();
             async move {
                 // TODO: Isn't this supposed to go to peerRX event handler?
                 // info!{"Warp request from {:?}", address};
-                let res: Result<Json, warp::reject::Rejection> = {
+                let res: Result<warp::reply::WithStatus<Json>, warp::reject::Rejection> = {
+                    if let Err(denied) = check_faucet_access(&relay3, &request, api_key.as_ref()) {
+                        return Ok(warp::reply::with_status(
+                            warp::reply::json(&denied.error_info()), denied.status_code()
+                        ));
+                    }
                     let response = relay3.receive_request_send_internal(request, None).await;
                     //PeerRxEventHandler::request_response(relay3, request, )
-                    Ok(response
+                    Ok(warp::reply::with_status(response
                         .map_err(|e| warp::reply::json(&e))
                         .map(|r| warp::reply::json(&r))
-                        .combine()
+                        .combine(), warp::http::StatusCode::OK)
                     )
                 };
                 res
@@ -596,13 +917,14 @@ pub async fn run_server(relay: Relay) -> Result<(), ErrorInfo>{
         .and(warp::body::bytes())
         .and(warp::addr::remote())
         .and(extract_ip())
-        .and_then(move |reqb: Bytes, address: Option<SocketAddr>, remote: Option<String>| {
+        .and(warp::header::optional::<String>(API_KEY_HEADER))
+        .and_then(move |reqb: Bytes, address: Option<SocketAddr>, remote: Option<String>, api_key: Option<String>| {
             // TODO: verify auth and receive message sync from above
             let relay3 = bin_relay2.clone();
             let origin = process_origin(address, remote);
             let result = async move {
                 let res: Result<Response<Vec<u8>>, warp::Rejection> =
-                    Ok(as_warp_proto_bytes(handle_proto_post(reqb, address, relay3.clone(), origin).await).await);
+                    Ok(handle_proto_post(reqb, address, relay3.clone(), origin, api_key).await);
                 res
             };
             result
@@ -636,7 +958,8 @@ pub async fn run_server(relay: Relay) -> Result<(), ErrorInfo>{
     let port = relay2.node_config.public_port();
     info!("Running public API on port: {:?}", port.clone());
 
-    let routes = hello
+    let access = relay2.node_config.api_access.clone();
+    let read_routes = hello
         .or(seeds)
         .or(trust)
         .or(peer_tx)
@@ -648,14 +971,28 @@ pub async fn run_server(relay: Relay) -> Result<(), ErrorInfo>{
         .or(query_hash)
         .or(about)
         .or(request_normal)
-        .or(request_bin)
         .or(peers)
+        .or(peer_scores)
         .or(transaction_lookup)
         .or(address_lookup)
+        .or(validate)
+        .or(seed_report)
+        .or(balance_history)
+        .or(observations)
+        .or(order_book)
+        .or(watcher_status).or(state_proof).or(fee_estimate).or(name_lookup)
+        .or(payment_requests)
+        .or(pending)
+        .or(graph)
         // .or(explorer_hash)
         // .or(explorer_recent)
         .or(home);
 
+    let routes = request_bin
+        .or(api_key_filter(&access, EndpointClass::PublicRead).and(read_routes))
+        .recover(handle_rejection)
+        .with(access.cors());
+
     // Create a warp Service using the filter
     // Create the server
     let addr = SocketAddr::from(([0, 0, 0, 0], port));
@@ -893,4 +1230,26 @@ fn test_public_api_lb() {
     let c = config.api_client();
     let rt = build_runtime(1, "test");
     println!("{:?}", rt.block_on(c.about()));
-}
\ No newline at end of file
+}
+#[test]
+fn order_book_matches_pool_info() {
+    use redgold_schema::order_book::OrderBook;
+    use crate::multiparty::party_stream::RecentFill;
+    use crate::multiparty::watcher::{BidAsk, OrderLimits};
+    let info = AddressPoolInfo {
+        public_key: "pk".to_string(),
+        rdg_address: "rdg".to_string(),
+        rdg_balance: 1.5,
+        btc_address: "btc".to_string(),
+        btc_balance: 0.5,
+        bid_ask: BidAsk::generate_default(100_000, 100_000, 400.0, 400.0),
+        order_limits: Some(OrderLimits { min_btc: Some(10_000), ..Default::default() }),
+        recent_fills: vec![RecentFill { time: 1, is_ask: true, price_btc_per_rdg: 0.0025, rdg_amount: 400 }],
+        admission: None,
+    };
+    let book = serde_json::from_str::<OrderBook>(&info.json_or()).expect("order book");
+    assert_eq!(book.bid_ask.bids.len(), info.bid_ask.bids.len());
+    assert_eq!(book.bid_ask.center_price, info.bid_ask.center_price);
+    assert_eq!(book.order_limits.and_then(|l| l.min_btc), Some(10_000));
+    assert_eq!(book.recent_fills.len(), 1);
+}
@@ -0,0 +1,47 @@
+use async_trait::async_trait;
+use metrics::counter;
+use redgold_data::transaction_store::BalanceSnapshot;
+use redgold_schema::RgResult;
+use crate::core::relay::Relay;
+use crate::core::stream_handlers::IntervalFold;
+use crate::util::current_time_millis_i64;
+
+pub const DAY_MILLIS: i64 = 1000 * 60 * 60 * 24;
+// Bounds work done per interval, remaining addresses are picked up on the next run
+const SNAPSHOTS_PER_INTERVAL: i64 = 1000;
+pub const MAX_BALANCE_HISTORY_DAYS: i64 = 365;
+
+pub fn snapshot_day(time: i64) -> i64 {
+    time / DAY_MILLIS
+}
+
+/// Records a daily balance snapshot for every address that has appeared in a transaction
+pub struct BalanceSnapshots {
+    pub relay: Relay,
+}
+
+#[async_trait]
+impl IntervalFold for BalanceSnapshots {
+    async fn interval_fold(&mut self) -> RgResult<()> {
+        if self.relay.disk_space_low.load() {
+            return Ok(());
+        }
+        let time = current_time_millis_i64();
+        let day = snapshot_day(time);
+        let ts = &self.relay.ds.transaction_store;
+        let addresses = ts.addresses_missing_balance_snapshot(day, SNAPSHOTS_PER_INTERVAL).await?;
+        for address in addresses.iter() {
+            let balance = ts.get_balance(address).await?.unwrap_or(0);
+            ts.insert_balance_snapshot(address, &BalanceSnapshot { day, time, balance }).await?;
+            counter!("redgold.balance_snapshot.recorded").increment(1);
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn snapshot_day_boundaries() {
+    assert_eq!(snapshot_day(0), 0);
+    assert_eq!(snapshot_day(DAY_MILLIS - 1), 0);
+    assert_eq!(snapshot_day(DAY_MILLIS), 1);
+}
@@ -0,0 +1,262 @@
+use async_trait::async_trait;
+use itertools::Itertools;
+use log::{error, info};
+use metrics::{counter, gauge};
+use redgold_data::config::ConfigKey;
+use redgold_keys::proof_support::ProofSupport;
+use redgold_schema::{error_info, EasyJson, RgResult, SafeOption};
+use redgold_schema::state_proof::unspent_attestation_hash;
+use redgold_schema::structs::{Hash, Proof, Request, StateCheckpoint, StateCheckpointRequest, UtxoId, UtxoStateProof};
+use redgold_schema::util::merkle::{build_root, MerkleTree};
+use crate::core::relay::Relay;
+use crate::core::stream_handlers::IntervalFold;
+use crate::util::current_time_millis_i64;
+
+pub const STATE_CHECKPOINTS: ConfigKey<Vec<StateCheckpoint>> = ConfigKey::new("state_checkpoints");
+
+/// Latest interval aligned boundary old enough that its history has settled, so every node
+/// picks the same boundaries.
+pub fn checkpoint_boundary(now: i64, interval_ms: i64, lag_ms: i64) -> i64 {
+    let settled = now - lag_ms;
+    settled - settled.rem_euclid(interval_ms.max(1))
+}
+
+/// Rejects transactions timed too far before `now` to reach every node before the checkpoint
+/// segment covering their time is sealed, segments are cut on the transaction's own time.
+pub fn validate_checkpoint_time(tx_time: i64, now: i64, finalization_lag_ms: i64) -> RgResult<()> {
+    let earliest = now - finalization_lag_ms / 2;
+    if tx_time < earliest {
+        return Err(error_info(format!(
+            "Transaction time {} is before the earliest accepted time {}", tx_time, earliest
+        )));
+    }
+    Ok(())
+}
+
+/// Merkle root over the previous checkpoint root followed by the segment's transaction hashes.
+pub fn segment_root(previous_root: Option<&Hash>, hashes: &Vec<Hash>) -> RgResult<Hash> {
+    let mut leafs = previous_root.cloned().into_iter().collect_vec();
+    leafs.extend(hashes.iter().cloned());
+    if leafs.is_empty() {
+        return Ok(Hash::digest(vec![]));
+    }
+    Ok(build_root(leafs)?.root)
+}
+
+async fn compute_checkpoint(relay: &Relay, previous: Option<&StateCheckpoint>, time: i64) -> RgResult<StateCheckpoint> {
+    let start = previous.map(|p| p.time).unwrap_or(0);
+    let hashes = relay.ds.transaction_store.accepted_transaction_hashes_between(start, time).await?;
+    let previous_root = previous.and_then(|p| p.state_root.clone());
+    let mut cp = StateCheckpoint::default();
+    cp.time = time;
+    cp.transaction_count = previous.map(|p| p.transaction_count).unwrap_or(0) + hashes.len() as i64;
+    cp.state_root = Some(segment_root(previous_root.as_ref(), &hashes)?);
+    cp.previous_root = previous_root;
+    cp.previous_time = previous.map(|p| p.time);
+    Ok(cp)
+}
+
+/// Recomputes a checkpoint's segment from the local datastore and checks its own signature.
+async fn verify_checkpoint(relay: &Relay, cp: &StateCheckpoint, previous: Option<&StateCheckpoint>) -> RgResult<()> {
+    let proof = cp.proof.safe_get_msg("Missing checkpoint proof")?;
+    proof.verify(&cp.signing_hash()?)?;
+    let recomputed = compute_checkpoint(relay, previous, cp.time).await?;
+    if recomputed.state_root != cp.state_root || recomputed.transaction_count != cp.transaction_count {
+        return Err(error_info(format!(
+            "Checkpoint at {} does not match local history, expected {} transactions found {}",
+            cp.time, cp.transaction_count, recomputed.transaction_count
+        )));
+    }
+    Ok(())
+}
+
+pub async fn local_checkpoint(relay: &Relay, time: i64) -> RgResult<Option<StateCheckpoint>> {
+    let checkpoints = relay.ds.config_store.get_typed(&STATE_CHECKPOINTS).await?.unwrap_or(vec![]);
+    Ok(checkpoints.into_iter().find(|c| c.time == time))
+}
+
+/// Rebuilds the merkle tree of a checkpoint's segment, checking it still matches the stored root.
+async fn segment_tree(relay: &Relay, cp: &StateCheckpoint) -> RgResult<MerkleTree> {
+    let start = cp.previous_time.unwrap_or(0);
+    let mut leafs = cp.previous_root.clone().into_iter().collect_vec();
+    leafs.extend(relay.ds.transaction_store.accepted_transaction_hashes_between(start, cp.time).await?);
+    let tree = build_root(leafs)?;
+    if Some(&tree.root) != cp.state_root.as_ref() {
+        return Err(error_info(format!("Checkpoint at {} does not match local history", cp.time)));
+    }
+    Ok(tree)
+}
+
+/// Proof that the UTXO exists and is unspent as of the latest checkpoint, for light clients.
+/// Only UTXOs created within the retained checkpoints can be proven.
+pub async fn utxo_state_proof(relay: &Relay, utxo_id: &UtxoId) -> RgResult<UtxoStateProof> {
+    let tx_hash = utxo_id.transaction_hash.safe_get_msg("Missing UTXO transaction hash")?;
+    let checkpoints = relay.ds.config_store.get_typed(&STATE_CHECKPOINTS).await?.unwrap_or(vec![]);
+    let latest = checkpoints.last().safe_get_msg("No state checkpoints available")?;
+    let tx = relay.ds.transaction_store.query_accepted_transaction(tx_hash).await?
+        .safe_get_msg("Transaction not accepted")?.clone();
+    let tx_time = relay.ds.transaction_store.accepted_transaction_own_time(tx_hash).await?
+        .safe_get_msg("Missing transaction time")?;
+    let index = checkpoints.iter().position(|c| tx_time < c.time)
+        .safe_get_msg("Transaction is newer than the latest checkpoint")?;
+    if tx_time < checkpoints[index].previous_time.unwrap_or(0) {
+        return Err(error_info("Transaction predates the retained checkpoints"));
+    }
+    if let Some((child, _)) = relay.ds.transaction_store.utxo_used(utxo_id).await? {
+        let spent_time = relay.ds.transaction_store.accepted_transaction_own_time(&child).await?;
+        if spent_time.map(|t| t < latest.time).unwrap_or(false) {
+            return Err(error_info(format!("UTXO spent by {} before the latest checkpoint", child.hex())));
+        }
+    }
+
+    let mut proof = UtxoStateProof::default();
+    proof.transaction_proof = Some(segment_tree(relay, &checkpoints[index]).await?.proof(tx_hash.clone()));
+    for cp in checkpoints.iter().skip(index + 1) {
+        let previous_root = cp.previous_root.safe_get_msg("Missing checkpoint previous root")?;
+        proof.chain_proofs.push(segment_tree(relay, cp).await?.proof(previous_root.clone()));
+    }
+    proof.observation_proofs = relay.ds.observation.select_observation_edge(tx_hash).await?;
+    let observation_hashes = proof.observation_proofs.iter()
+        .filter_map(|o| o.observation_hash.clone())
+        .unique()
+        .collect_vec();
+    for h in observation_hashes {
+        if let Some(o) = relay.ds.observation.query_observation(&h).await? {
+            proof.observation_transactions.push(o);
+        }
+    }
+    proof.unspent_proof = Some(Proof::from_keypair_hash(
+        &unspent_attestation_hash(utxo_id, latest.time)?, &relay.node_config.keypair()
+    ));
+    proof.utxo_id = Some(utxo_id.clone());
+    proof.transaction = Some(tx);
+    proof.checkpoint = Some(latest.clone());
+    counter!("redgold.checkpoint.state_proof").increment(1);
+    Ok(proof)
+}
+
+/// Restart verification, either of only the latest checkpoint's segment or every retained one.
+pub async fn verify_on_startup(relay: &Relay) -> RgResult<()> {
+    let checkpoints = relay.ds.config_store.get_typed(&STATE_CHECKPOINTS).await?.unwrap_or(vec![]);
+    if checkpoints.is_empty() {
+        return Ok(());
+    }
+    let from_checkpoint = relay.node_config.checkpoint.from_checkpoint;
+    let start = if from_checkpoint { checkpoints.len() - 1 } else { 0 };
+    for i in start..checkpoints.len() {
+        let cp = &checkpoints[i];
+        let previous = if i > 0 { checkpoints.get(i - 1) } else { None };
+        // The oldest retained checkpoint chains from one that's no longer kept
+        if previous.is_none() && cp.previous_time.is_some() {
+            continue;
+        }
+        if let Err(e) = verify_checkpoint(relay, cp, previous).await {
+            counter!("redgold.checkpoint.verify_failed").increment(1);
+            error!("State checkpoint verification failed: {}", e.json_or());
+            if from_checkpoint {
+                return Err(e);
+            }
+        }
+    }
+    let latest = checkpoints.last().expect("latest");
+    info!("Verified state from checkpoint at {} with {} transactions", latest.time, latest.transaction_count);
+    Ok(())
+}
+
+/// Periodically checkpoints accepted history and collects peer signatures on the latest root.
+pub struct StateCheckpoints {
+    pub relay: Relay,
+}
+
+impl StateCheckpoints {
+
+    async fn create(&self, checkpoints: &mut Vec<StateCheckpoint>) -> RgResult<bool> {
+        let cfg = &self.relay.node_config.checkpoint;
+        let time = checkpoint_boundary(
+            current_time_millis_i64(), cfg.interval.as_millis() as i64, cfg.finalization_lag.as_millis() as i64
+        );
+        let previous = checkpoints.last();
+        if previous.map(|p| p.time >= time).unwrap_or(false) {
+            return Ok(false);
+        }
+        let mut cp = compute_checkpoint(&self.relay, previous, time).await?;
+        cp.observation_height = self.relay.ds.observation
+            .select_latest_observation(self.relay.node_config.public_key()).await?
+            .and_then(|o| o.height().ok())
+            .unwrap_or(0);
+        cp.proof = Some(Proof::from_keypair_hash(&cp.signing_hash()?, &self.relay.node_config.keypair()));
+        counter!("redgold.checkpoint.created").increment(1);
+        gauge!("redgold.checkpoint.transaction_count").set(cp.transaction_count as f64);
+        checkpoints.push(cp);
+        let excess = checkpoints.len().saturating_sub(cfg.keep.max(1));
+        checkpoints.drain(..excess);
+        Ok(true)
+    }
+
+    async fn cross_sign(&self, cp: &mut StateCheckpoint) -> RgResult<bool> {
+        let wanted = self.relay.node_config.checkpoint.peer_signatures;
+        if cp.peer_proofs.len() >= wanted {
+            return Ok(false);
+        }
+        let hash = cp.signing_hash()?;
+        let signed = cp.peer_proofs.iter().filter_map(|p| p.public_key.clone()).collect_vec();
+        let peers = self.relay.ds.peer_store.active_nodes(None).await?;
+        let mut added = false;
+        for pk in peers.into_iter().filter(|pk| !signed.contains(pk)) {
+            if cp.peer_proofs.len() >= wanted {
+                break;
+            }
+            let mut request = Request::default();
+            request.state_checkpoint_request = Some(StateCheckpointRequest { time: cp.time });
+            let response = self.relay.send_message_sync(request, pk.clone(), None).await;
+            let peer_cp = response.ok().and_then(|r| r.state_checkpoint_response);
+            if let Some(peer_cp) = peer_cp {
+                let proof = peer_cp.proof.filter(|p| p.public_key.as_ref() == Some(&pk));
+                let valid = proof.as_ref().map(|p| p.verify(&hash).is_ok()).unwrap_or(false);
+                if valid {
+                    cp.peer_proofs.push(proof.expect("proof"));
+                    counter!("redgold.checkpoint.peer_signed").increment(1);
+                    added = true;
+                } else {
+                    counter!("redgold.checkpoint.peer_mismatch").increment(1);
+                    info!("Peer {} checkpoint at {} differs from local root {}", pk.hex_or(), cp.time, hash.hex());
+                }
+            }
+        }
+        Ok(added)
+    }
+}
+
+#[async_trait]
+impl IntervalFold for StateCheckpoints {
+    async fn interval_fold(&mut self) -> RgResult<()> {
+        if self.relay.disk_space_low.load() {
+            return Ok(());
+        }
+        let mut checkpoints = self.relay.ds.config_store.get_typed(&STATE_CHECKPOINTS).await?.unwrap_or(vec![]);
+        let mut changed = self.create(&mut checkpoints).await?;
+        if let Some(latest) = checkpoints.last_mut() {
+            changed |= self.cross_sign(latest).await?;
+        }
+        if changed {
+            self.relay.ds.config_store.set_typed(&STATE_CHECKPOINTS, &checkpoints).await?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn checkpoint_boundaries_and_chaining() {
+    let hour = 1000 * 60 * 60;
+    assert_eq!(checkpoint_boundary(hour * 5 + 10, hour, 0), hour * 5);
+    assert_eq!(checkpoint_boundary(hour * 5 + 10, hour, 20), hour * 4);
+    let hashes = vec![Hash::from_string_calculate("a"), Hash::from_string_calculate("b")];
+    let first = segment_root(None, &hashes).expect("root");
+    assert_eq!(first, segment_root(None, &hashes).expect("root"));
+    let second = segment_root(Some(&first), &vec![]).expect("root");
+    assert_ne!(first, second);
+    assert_ne!(second, segment_root(Some(&first), &hashes).expect("root"));
+    assert!(validate_checkpoint_time(hour * 5, hour * 5 + 10, 20).is_ok());
+    assert!(validate_checkpoint_time(hour * 5 + 100, hour * 5 + 10, 20).is_ok());
+    assert!(validate_checkpoint_time(hour * 5 - 11, hour * 5 + 10, 40).is_err());
+}
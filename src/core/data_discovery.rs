@@ -14,6 +14,9 @@ pub struct DataDiscovery {
 #[async_trait]
 impl IntervalFold for DataDiscovery {
     async fn interval_fold(&mut self) -> RgResult<()> {
+        if self.relay.disk_space_low.load() {
+            return Ok(());
+        }
         let n = self.relay.ds.peer_store.active_nodes(None).await?;
         for node in n {
             let mut r = Request::default();
@@ -29,7 +32,7 @@ impl IntervalFold for DataDiscovery {
                         if self.relay.tx_hash_distance(&h).await? {
                             let t = self.relay.lookup_transaction_serial(&h).await?;
                             if let Some(tx) = t {
-                                self.relay.mempool.sender.send_err(
+                                self.relay.mempool.send_err(
                                      TransactionMessage{
                                          transaction: tx,
                                          response_channel: None,
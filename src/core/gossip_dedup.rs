@@ -0,0 +1,74 @@
+use std::collections::{HashMap, VecDeque};
+use metrics::counter;
+use redgold_schema::structs::Hash;
+use crate::core::relay::Relay;
+use crate::util::current_time_millis_i64;
+
+// Gossiped copies of a transaction arrive from peers within a few seconds of each other,
+// later copies are left to the usual already-processing checks.
+pub const GOSSIP_DEDUP_TTL_MS: i64 = 1000 * 60;
+const GOSSIP_DEDUP_MAX_ENTRIES: usize = 100_000;
+
+/// Transaction hashes recently received over gossip, in arrival order for expiry.
+#[derive(Clone, Debug, Default)]
+pub struct SeenTransactions {
+    seen: HashMap<Hash, i64>,
+    order: VecDeque<(Hash, i64)>,
+}
+
+impl SeenTransactions {
+    /// Records the hash, returning false if it was already seen within the ttl.
+    pub fn check_insert(&mut self, hash: &Hash, now: i64) -> bool {
+        while let Some((h, t)) = self.order.front().cloned() {
+            if now - t <= GOSSIP_DEDUP_TTL_MS && self.order.len() < GOSSIP_DEDUP_MAX_ENTRIES {
+                break;
+            }
+            self.order.pop_front();
+            if self.seen.get(&h) == Some(&t) {
+                self.seen.remove(&h);
+            }
+        }
+        if self.seen.contains_key(hash) {
+            return false;
+        }
+        self.seen.insert(hash.clone(), now);
+        self.order.push_back((hash.clone(), now));
+        true
+    }
+
+    pub fn remove(&mut self, hash: &Hash) {
+        self.seen.remove(hash);
+    }
+}
+
+/// True for a transaction already delivered by another peer, which is dropped before validation.
+pub fn is_duplicate_gossip(relay: &Relay, hash: &Hash) -> bool {
+    let first = relay.gossip_seen_transactions.lock()
+        .map(|mut s| s.check_insert(hash, current_time_millis_i64()))
+        .unwrap_or(true);
+    if !first {
+        counter!("redgold.gossip.transaction.dedup_hit").increment(1);
+    }
+    !first
+}
+
+/// Forgets a hash whose submission failed so a later copy is still processed.
+pub fn forget_gossip(relay: &Relay, hash: &Hash) {
+    if let Ok(mut s) = relay.gossip_seen_transactions.lock() {
+        s.remove(hash);
+    }
+}
+
+#[test]
+fn seen_transactions_drops_duplicates_until_expiry() {
+    let mut seen = SeenTransactions::default();
+    let a = Hash::from_string_calculate("a");
+    let b = Hash::from_string_calculate("b");
+    assert!(seen.check_insert(&a, 0));
+    assert!(!seen.check_insert(&a, 10));
+    assert!(seen.check_insert(&b, 10));
+    assert!(seen.check_insert(&a, GOSSIP_DEDUP_TTL_MS + 1));
+    assert!(!seen.check_insert(&b, GOSSIP_DEDUP_TTL_MS + 1));
+    seen.remove(&b);
+    assert!(seen.check_insert(&b, GOSSIP_DEDUP_TTL_MS + 2));
+}
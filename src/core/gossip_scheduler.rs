@@ -0,0 +1,243 @@
+use std::collections::{HashMap, VecDeque};
+use async_trait::async_trait;
+use metrics::{counter, gauge};
+use redgold_schema::RgResult;
+use redgold_schema::structs::{PublicKey, Request};
+use crate::core::internal_message::RecvAsyncErrorInfo;
+use crate::core::relay::Relay;
+use crate::core::stream_handlers::IntervalFold;
+use crate::node_config::GossipSchedulerConfig;
+use crate::util::current_time_millis_i64;
+
+/// Outbound gossip is drained per peer in this order, transactions first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GossipPriority {
+    Transaction = 0,
+    Observation = 1,
+    Maintenance = 2,
+}
+
+impl GossipPriority {
+    pub const ALL: [GossipPriority; 3] = [
+        GossipPriority::Transaction, GossipPriority::Observation, GossipPriority::Maintenance
+    ];
+
+    pub fn of(request: &Request) -> Self {
+        if request.gossip_transaction_request.is_some() {
+            GossipPriority::Transaction
+        } else if request.gossip_observation_request.is_some() {
+            GossipPriority::Observation
+        } else {
+            GossipPriority::Maintenance
+        }
+    }
+
+    fn queue_gauge(&self) -> &'static str {
+        match self {
+            GossipPriority::Transaction => "redgold.gossip.queue.transaction",
+            GossipPriority::Observation => "redgold.gossip.queue.observation",
+            GossipPriority::Maintenance => "redgold.gossip.queue.maintenance",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct QueuedGossip {
+    pub request: Request,
+    pub attempts: u32,
+    // Retries wait out their backoff before being sent again
+    pub not_before: i64,
+}
+
+impl QueuedGossip {
+    pub fn new(request: Request) -> Self {
+        Self { request, attempts: 0, not_before: 0 }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct PeerQueue {
+    queues: [VecDeque<QueuedGossip>; 3],
+    // Token bucket, one token per message sent
+    tokens: f64,
+    last_refill: i64,
+}
+
+impl PeerQueue {
+    fn len(&self) -> usize {
+        self.queues.iter().map(|q| q.len()).sum()
+    }
+
+    fn refill(&mut self, cfg: &GossipSchedulerConfig, now: i64) {
+        let elapsed = (now - self.last_refill).max(0) as f64 / 1000.0;
+        self.tokens = (self.tokens + elapsed * cfg.peer_rate_limit).min(cfg.peer_burst);
+        self.last_refill = now;
+    }
+}
+
+/// Per peer outbound gossip queues, drained by priority subject to each peer's rate limit.
+#[derive(Clone, Debug, Default)]
+pub struct GossipQueues {
+    peers: HashMap<PublicKey, PeerQueue>,
+}
+
+impl GossipQueues {
+
+    /// Queues a message for the peer. When the peer's queue is full the oldest message of a
+    /// lower priority is evicted, otherwise the new message is dropped and false returned.
+    pub fn push(&mut self, cfg: &GossipSchedulerConfig, peer: &PublicKey, item: QueuedGossip, now: i64) -> bool {
+        let q = self.peers.entry(peer.clone()).or_insert_with(|| PeerQueue {
+            queues: Default::default(),
+            tokens: cfg.peer_burst,
+            last_refill: now,
+        });
+        let priority = GossipPriority::of(&item.request) as usize;
+        if q.len() >= cfg.max_queue_per_peer {
+            let lowest = (0..3).rev().find(|i| !q.queues[*i].is_empty());
+            match lowest {
+                Some(l) if l > priority => {
+                    q.queues[l].pop_front();
+                }
+                _ => return false,
+            }
+        }
+        q.queues[priority].push_back(item);
+        true
+    }
+
+    /// Removes the messages ready to send this tick, at most one batch per peer and no more
+    /// than the peer's available tokens.
+    pub fn take_ready(&mut self, cfg: &GossipSchedulerConfig, now: i64) -> Vec<(PublicKey, QueuedGossip)> {
+        let mut res = vec![];
+        for (pk, q) in self.peers.iter_mut() {
+            q.refill(cfg, now);
+            let mut sent = 0;
+            for queue in q.queues.iter_mut() {
+                while sent < cfg.max_batch_per_peer && q.tokens >= 1.0 {
+                    // Retries still backing off don't hold up messages queued behind them
+                    let ready = queue.iter().position(|i| i.not_before <= now);
+                    match ready.and_then(|i| queue.remove(i)) {
+                        Some(item) => {
+                            q.tokens -= 1.0;
+                            sent += 1;
+                            res.push((pk.clone(), item));
+                        }
+                        None => break,
+                    }
+                }
+            }
+        }
+        // Idle peers with a full bucket carry no state worth keeping
+        self.peers.retain(|_, q| q.len() > 0 || q.tokens < cfg.peer_burst);
+        res
+    }
+
+    pub fn depths(&self) -> [usize; 3] {
+        let mut res = [0; 3];
+        for q in self.peers.values() {
+            for (i, queue) in q.queues.iter().enumerate() {
+                res[i] += queue.len();
+            }
+        }
+        res
+    }
+
+    pub fn num_peers(&self) -> usize {
+        self.peers.len()
+    }
+}
+
+/// Delay before a failed message is retried, doubling per attempt.
+pub fn retry_delay_millis(cfg: &GossipSchedulerConfig, attempts: u32) -> i64 {
+    let factor = 2u32.saturating_pow(attempts.saturating_sub(1).min(16));
+    cfg.retry_backoff.saturating_mul(factor).as_millis() as i64
+}
+
+/// Sends queued outbound gossip, requeueing failed sends until they run out of retries.
+pub struct GossipScheduler {
+    relay: Relay,
+}
+
+impl GossipScheduler {
+    pub fn new(relay: &Relay) -> Self {
+        Self { relay: relay.clone() }
+    }
+}
+
+async fn send_with_retry(relay: Relay, peer: PublicKey, mut item: QueuedGossip) {
+    let cfg = relay.node_config.gossip_scheduler.clone();
+    let result = match relay.send_message_async(&item.request, &peer, Some(cfg.send_timeout)).await {
+        Ok(r) => r.recv_async_err().await.and_then(|r| r.as_error_info()),
+        Err(e) => Err(e),
+    };
+    if result.is_ok() {
+        counter!("redgold.gossip.sent").increment(1);
+        return;
+    }
+    item.attempts += 1;
+    if item.attempts > cfg.max_retries {
+        counter!("redgold.gossip.dropped").increment(1);
+        return;
+    }
+    counter!("redgold.gossip.retry").increment(1);
+    let now = current_time_millis_i64();
+    item.not_before = now + retry_delay_millis(&cfg, item.attempts);
+    if let Ok(mut q) = relay.gossip_queues.lock() {
+        q.push(&cfg, &peer, item, now);
+    }
+}
+
+#[async_trait]
+impl IntervalFold for GossipScheduler {
+    async fn interval_fold(&mut self) -> RgResult<()> {
+        let cfg = self.relay.node_config.gossip_scheduler.clone();
+        let now = current_time_millis_i64();
+        let (ready, depths, peers) = match self.relay.gossip_queues.lock() {
+            Ok(mut q) => (q.take_ready(&cfg, now), q.depths(), q.num_peers()),
+            Err(_) => return Ok(()),
+        };
+        for (peer, item) in ready {
+            tokio::spawn(send_with_retry(self.relay.clone(), peer, item));
+        }
+        for p in GossipPriority::ALL.iter() {
+            gauge!(p.queue_gauge()).set(depths[*p as usize] as f64);
+        }
+        gauge!("redgold.gossip.queue.peers").set(peers as f64);
+        Ok(())
+    }
+}
+
+#[test]
+fn gossip_queues_prioritize_and_rate_limit() {
+    use redgold_schema::structs::{GossipObservationRequest, GossipTransactionRequest};
+    use redgold_keys::TestConstants;
+    use redgold_keys::util::keys::ToPublicKeyFromLib;
+    let mut cfg = GossipSchedulerConfig::default();
+    cfg.peer_burst = 2.0;
+    cfg.peer_rate_limit = 1.0;
+    cfg.max_queue_per_peer = 3;
+    let peer = TestConstants::new().public.to_struct_public_key();
+    let mut tx = Request::default();
+    tx.gossip_transaction_request = Some(GossipTransactionRequest::default());
+    let mut obs = Request::default();
+    obs.gossip_observation_request = Some(GossipObservationRequest::default());
+    let maintenance = Request::default();
+
+    let mut q = GossipQueues::default();
+    assert!(q.push(&cfg, &peer, QueuedGossip::new(maintenance.clone()), 0));
+    assert!(q.push(&cfg, &peer, QueuedGossip::new(obs.clone()), 0));
+    assert!(q.push(&cfg, &peer, QueuedGossip::new(tx.clone()), 0));
+    // Full, evicts the maintenance message for a transaction but drops another maintenance one
+    assert!(q.push(&cfg, &peer, QueuedGossip::new(tx.clone()), 0));
+    assert!(!q.push(&cfg, &peer, QueuedGossip::new(maintenance.clone()), 0));
+    assert_eq!(q.depths(), [2, 1, 0]);
+
+    let ready = q.take_ready(&cfg, 0);
+    assert_eq!(ready.len(), 2);
+    assert!(ready.iter().all(|(_, i)| GossipPriority::of(&i.request) == GossipPriority::Transaction));
+    // Out of tokens until one refills after a second
+    assert!(q.take_ready(&cfg, 500).is_empty());
+    let ready = q.take_ready(&cfg, 1000);
+    assert_eq!(ready.len(), 1);
+    assert_eq!(GossipPriority::of(&ready[0].1.request), GossipPriority::Observation);
+}
@@ -0,0 +1,68 @@
+use std::collections::VecDeque;
+use futures::stream::FuturesUnordered;
+use futures::StreamExt;
+use metrics::counter;
+use redgold_schema::{error_info, RgResult};
+use redgold_schema::structs::{ErrorInfo, PublicKey, Request, Response};
+use crate::core::relay::Relay;
+
+impl Relay {
+
+    /// Sends a request to the first of the given peers. Idempotent reads are retried against
+    /// the next peer on failure and hedged, also racing the next peer once the first hasn't
+    /// answered within the configured latency. Other requests are only ever sent once.
+    pub async fn send_hedged(&self, request: Request, peers: Vec<PublicKey>) -> RgResult<Response> {
+        let policy = self.node_config.peer_retry.clone();
+        let mut pending = peers.into_iter().collect::<VecDeque<PublicKey>>();
+        if !request.is_idempotent_read() {
+            let first = pending.pop_front().ok_or(error_info("No peers to send request to"))?;
+            let response = self.send_message_sync(request, first, Some(policy.attempt_timeout)).await?;
+            response.as_error_info()?;
+            return Ok(response);
+        }
+        pending.truncate(policy.max_attempts.max(1));
+
+        let attempt = |pk: PublicKey| {
+            let relay = self.clone();
+            let request = request.clone();
+            Box::pin(async move {
+                let response = relay.send_message_sync(request, pk, Some(policy.attempt_timeout)).await?;
+                response.as_error_info()?;
+                Ok::<Response, ErrorInfo>(response)
+            })
+        };
+
+        let mut in_flight = FuturesUnordered::new();
+        let first = pending.pop_front().ok_or(error_info("No peers to send request to"))?;
+        in_flight.push(attempt(first));
+        let mut last_error = None;
+
+        loop {
+            tokio::select! {
+                result = in_flight.next() => {
+                    match result {
+                        Some(Ok(response)) => return Ok(response),
+                        Some(Err(e)) => {
+                            last_error = Some(e);
+                            if let Some(pk) = pending.pop_front() {
+                                counter!("redgold.peer.request.retry").increment(1);
+                                in_flight.push(attempt(pk));
+                            } else if in_flight.is_empty() {
+                                break;
+                            }
+                        }
+                        None => break,
+                    }
+                }
+                _ = tokio::time::sleep(policy.hedge_after), if !pending.is_empty() => {
+                    if let Some(pk) = pending.pop_front() {
+                        counter!("redgold.peer.request.hedged").increment(1);
+                        in_flight.push(attempt(pk));
+                    }
+                }
+            }
+        }
+        counter!("redgold.peer.request.exhausted").increment(1);
+        Err(last_error.unwrap_or(error_info("All peer attempts failed")))
+    }
+}
@@ -77,7 +77,8 @@ pub struct TransactionMessage {
     pub response_channel: Option<flume::Sender<Response>>,
 }
 use async_trait::async_trait;
-use flume::TryRecvError;
+use flume::{TryRecvError, TrySendError};
+use metrics::counter;
 use futures::stream::{FuturesUnordered, StreamExt};
 use tokio::select;
 use tokio::task::JoinHandle;
@@ -126,17 +127,85 @@ where
     }
 }
 
+/// What a send does when a bounded channel is at capacity.
+#[derive(Clone, Debug, PartialEq)]
+pub enum OverflowPolicy {
+    // Wait for the receiver to make room
+    Block,
+    // Discard the oldest queued message to make room, counted in redgold.channel.<name>.dropped
+    DropOldest,
+    // Fail the send, counted in redgold.channel.<name>.rejected
+    Reject,
+}
+
+#[derive(Clone, Debug)]
+pub struct ChannelConfig {
+    pub capacity: usize,
+    pub overflow: OverflowPolicy,
+}
+
+impl ChannelConfig {
+    pub fn new(capacity: usize, overflow: OverflowPolicy) -> Self {
+        Self {
+            capacity,
+            overflow,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Channel<T> {
     pub sender: flume::Sender<T>,
     pub receiver: flume::Receiver<T>,
+    pub name: String,
+    pub overflow: OverflowPolicy,
 }
 
 impl<T> Channel<T> {
+    /// Sends applying the overflow policy, a blocking policy waits without holding the thread.
     pub async fn send(&self, t: T) -> Result<(), ErrorInfo> {
-        self.sender
-            .send(t)
-            .map_err(|e| error_message(Error::InternalChannelSendError, e.to_string()))
+        match self.overflow {
+            OverflowPolicy::Block => self.sender
+                .send_async(t)
+                .await
+                .map_err(|e| error_message(Error::InternalChannelSendError, e.to_string())),
+            _ => self.send_err(t)
+        }
+    }
+
+    /// Sends applying the overflow policy, a blocking policy blocks the calling thread so async
+    /// callers should use `send` instead.
+    pub fn send_err(&self, t: T) -> Result<(), ErrorInfo> {
+        match self.overflow {
+            OverflowPolicy::Block => self.sender
+                .send(t)
+                .map_err(|e| error_message(Error::InternalChannelSendError, e.to_string())),
+            OverflowPolicy::Reject => match self.sender.try_send(t) {
+                Ok(_) => Ok(()),
+                Err(TrySendError::Full(_)) => {
+                    counter!(format!("redgold.channel.{}.rejected", self.name)).increment(1);
+                    Err(error_message(Error::InternalChannelSendError, format!(
+                        "Channel {} at capacity {}", self.name, self.sender.capacity().unwrap_or(0)
+                    )))
+                }
+                Err(e) => Err(error_message(Error::InternalChannelSendError, e.to_string())),
+            },
+            OverflowPolicy::DropOldest => {
+                let mut t = t;
+                loop {
+                    match self.sender.try_send(t) {
+                        Ok(_) => return Ok(()),
+                        Err(TrySendError::Full(returned)) => {
+                            t = returned;
+                            if self.receiver.try_recv().is_ok() {
+                                counter!(format!("redgold.channel.{}.dropped", self.name)).increment(1);
+                            }
+                        }
+                        Err(e) => return Err(error_message(Error::InternalChannelSendError, e.to_string())),
+                    }
+                }
+            }
+        }
     }
     pub fn new() -> Channel<T> {
         new_channel()
@@ -173,6 +242,8 @@ pub fn new_channel<T>() -> Channel<T> {
     return Channel {
         sender: s,
         receiver: r,
+        name: "unbounded".to_string(),
+        overflow: OverflowPolicy::Block,
     };
 }
 
@@ -182,6 +253,18 @@ pub fn new_bounded_channel<T>(cap: usize) -> Channel<T> {
     return Channel {
         sender: s,
         receiver: r,
+        name: "bounded".to_string(),
+        overflow: OverflowPolicy::Block,
+    };
+}
+
+pub fn new_configured_channel<T>(name: impl Into<String>, config: &ChannelConfig) -> Channel<T> {
+    let (s, r) = flume::bounded::<T>(config.capacity);
+    return Channel {
+        sender: s,
+        receiver: r,
+        name: name.into(),
+        overflow: config.overflow.clone(),
     };
 }
 
@@ -198,4 +281,16 @@ pub fn map_fut(r: Option<Result<Result<(), ErrorInfo>, JoinError>>) -> Result<()
             Ok(())
         }
     }
-}
\ No newline at end of file
+}
+#[test]
+fn overflow_policies_at_capacity() {
+    let c = new_configured_channel::<i32>("test", &ChannelConfig::new(2, OverflowPolicy::DropOldest));
+    for i in 0..4 {
+        c.send_err(i).expect("drop oldest never fails");
+    }
+    assert_eq!(c.recv_while().expect("recv"), vec![2, 3]);
+    let c = new_configured_channel::<i32>("test", &ChannelConfig::new(1, OverflowPolicy::Reject));
+    c.send_err(0).expect("has capacity");
+    assert!(c.send_err(1).is_err());
+    assert_eq!(c.recv_while().expect("recv"), vec![0]);
+}
@@ -8,11 +8,23 @@ pub mod peer_rx_event_handler;
 pub mod process_observation;
 pub mod process_transaction;
 pub mod relay;
+pub mod hedged_request;
 pub mod transaction;
 pub mod resolver;
 pub mod run_main;
 pub mod status;
 pub mod seeds;
+pub mod seed_mode;
+pub mod balance_snapshots;
+pub mod checkpoint;
+pub mod retention;
+pub mod replay_protection;
+pub mod gossip_dedup;
+pub mod gossip_scheduler;
+pub mod peer_scores;
+pub mod payment_requests;
+pub mod seed_list;
+pub mod rebroadcast;
 pub mod transact;
 pub mod discovery;
 pub mod stream_handlers;
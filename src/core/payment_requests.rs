@@ -0,0 +1,111 @@
+use std::time::Duration;
+use itertools::Itertools;
+use metrics::counter;
+use redgold_schema::{error_info, RgResult, SafeOption};
+use redgold_schema::structs::{PaymentRequest, PeerId, Request};
+use crate::core::relay::Relay;
+use crate::observability::logging::Loggable;
+
+// Oldest requests are dropped past this many, so an unknown requester can't grow memory
+pub const MAX_PAYMENT_REQUESTS: usize = 200;
+pub const MAX_PAYMENT_REQUEST_MEMO_LENGTH: usize = 256;
+const FORWARD_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub fn validate_payment_request(r: &PaymentRequest) -> RgResult<()> {
+    if r.id.is_empty() || r.id.len() > 128 {
+        return Err(error_info("Invalid payment request id"));
+    }
+    r.address.safe_get_msg("Missing payment request address")?;
+    r.to_peer_id.as_ref().and_then(|p| p.peer_id.as_ref()).safe_get_msg("Missing payment request recipient")?;
+    let amount = r.amount.safe_get_msg("Missing payment request amount")?;
+    if amount.amount <= 0 {
+        return Err(error_info("Payment request amount must be positive"));
+    }
+    if r.memo.len() > MAX_PAYMENT_REQUEST_MEMO_LENGTH || r.from_name.len() > MAX_PAYMENT_REQUEST_MEMO_LENGTH {
+        return Err(error_info("Payment request memo too long"));
+    }
+    Ok(())
+}
+
+/// Stores a request addressed to this node's peer id, or relays one submitted by a wallet to the
+/// nodes of the recipient's peer id, succeeding once any of them has accepted it.
+pub async fn receive_payment_request(relay: &Relay, r: &PaymentRequest) -> RgResult<()> {
+    validate_payment_request(r)?;
+    let to = r.to_peer_id.safe_get_msg("Missing payment request recipient")?;
+    if &relay.peer_id().await? == to {
+        store_payment_request(relay, r.clone());
+        counter!("redgold.payment_request.received").increment(1);
+        return Ok(());
+    }
+    if r.forwarded {
+        return Err(error_info("Payment request is not addressed to this peer"));
+    }
+    forward_payment_request(relay, to, r).await
+}
+
+fn store_payment_request(relay: &Relay, r: PaymentRequest) {
+    relay.payment_requests.insert(r.id.clone(), r);
+    let excess = relay.payment_requests.len().saturating_sub(MAX_PAYMENT_REQUESTS);
+    if excess > 0 {
+        let oldest = relay.payment_requests.iter()
+            .map(|e| (e.time, e.key().clone()))
+            .sorted()
+            .take(excess)
+            .map(|(_, k)| k)
+            .collect_vec();
+        for k in oldest {
+            relay.payment_requests.remove(&k);
+        }
+    }
+}
+
+async fn forward_payment_request(relay: &Relay, to: &PeerId, r: &PaymentRequest) -> RgResult<()> {
+    let info = relay.ds.peer_store.query_peer_id_info(to).await?
+        .ok_or(error_info(format!("Unknown payment request recipient {}", to.hex_or())))?;
+    let nodes = info.peer_node_info.iter()
+        .flat_map(|n| n.latest_node_transaction.as_ref())
+        .flat_map(|t| t.node_metadata().ok())
+        .flat_map(|n| n.public_key)
+        .collect_vec();
+    let mut forwarded = r.clone();
+    forwarded.forwarded = true;
+    for node in nodes {
+        let mut req = Request::default();
+        req.payment_request = Some(forwarded.clone());
+        let res = relay.send_message_sync(req, node, Some(FORWARD_TIMEOUT)).await
+            .and_then(|res| res.as_error_info());
+        if res.log_error().is_ok() {
+            counter!("redgold.payment_request.forwarded").increment(1);
+            return Ok(());
+        }
+    }
+    Err(error_info(format!("No node of peer {} accepted the payment request", to.hex_or())))
+}
+
+/// Requests addressed to this node's peer id, newest first.
+pub fn payment_requests(relay: &Relay) -> Vec<PaymentRequest> {
+    relay.payment_requests.iter()
+        .map(|e| e.value().clone())
+        .sorted_by(|a, b| b.time.cmp(&a.time))
+        .collect_vec()
+}
+
+#[test]
+fn payment_request_validation() {
+    use redgold_schema::structs::{Address, CurrencyAmount};
+    let mut r = PaymentRequest::default();
+    r.id = "abc".to_string();
+    r.address = Some(Address::script_hash("payee").expect("address"));
+    r.to_peer_id = Some(PeerId::from_bytes(vec![1, 2, 3]));
+    r.amount = Some(CurrencyAmount::from(5));
+    assert!(validate_payment_request(&r).is_ok());
+    let mut zero = r.clone();
+    zero.amount = Some(CurrencyAmount::from(0));
+    assert!(validate_payment_request(&zero).is_err());
+    let mut long_memo = r.clone();
+    long_memo.memo = "m".repeat(MAX_PAYMENT_REQUEST_MEMO_LENGTH + 1);
+    assert!(validate_payment_request(&long_memo).is_err());
+    let mut no_recipient = r.clone();
+    no_recipient.to_peer_id = None;
+    assert!(validate_payment_request(&no_recipient).is_err());
+}
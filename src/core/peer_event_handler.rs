@@ -17,6 +17,7 @@ use redgold_schema::structs::{ErrorInfo, NetworkEnvironment, NodeMetadata, PeerM
 
 use crate::api::RgHttpClient;
 use crate::core::internal_message::{PeerMessage, SendErrorInfo};
+use crate::core::peer_scores::record_peer_call;
 use crate::core::relay::Relay;
 use crate::node_config::NodeConfig;
 use crate::schema::json;
@@ -84,6 +85,7 @@ impl PeerOutgoingEventHandler {
 
     pub async fn send_message_rest(mut message: PeerMessage, nmd: NodeMetadata, relay: &Relay) -> Result<(), ErrorInfo> {
         counter!("redgold.peer.rest.send").increment(1);
+        let start = util::current_time_millis_i64();
         let result = match tokio::time::timeout(
             message.send_timeout.clone(), Self::send_message_rest_ret_err(&mut message, nmd.clone(), relay)
         ).await
@@ -97,6 +99,10 @@ impl PeerOutgoingEventHandler {
                 Err(e)
             }
         };
+        if let Some(pk) = nmd.public_key.as_ref() {
+            let now = util::current_time_millis_i64();
+            record_peer_call(relay, pk, result.is_ok(), (now - start) as f64, now);
+        }
         let r = result.map_err(|e| {
             counter!("redgold.peer.rest.send.error").increment(1);
             let mut e2 = e.clone();
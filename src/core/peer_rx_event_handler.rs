@@ -14,11 +14,14 @@ use metrics::counter;
 use tokio::runtime::Runtime;
 use tokio::task::JoinHandle;
 
-use redgold_schema::{error_info, json_or, RgResult, SafeBytesAccess, SafeOption, structs, WithMetadataHashable};
+use redgold_schema::{error_info, error_message, json_or, RgResult, SafeBytesAccess, SafeOption, structs, WithMetadataHashable};
 use redgold_schema::EasyJson;
 use redgold_schema::errors::EnhanceErrorInfo;
-use redgold_schema::structs::{AboutNodeRequest, AboutNodeResponse, ErrorInfo, GetPartiesInfoResponse, GetPeersInfoRequest, GetPeersInfoResponse, PublicKey, QueryObservationProofResponse, RecentDiscoveryTransactionsResponse, Request, ResolveCodeResponse, SubmitTransactionRequest, UtxoId, UtxoValidResponse};
+use redgold_schema::structs::{AboutNodeRequest, AboutNodeResponse, Error, ErrorInfo, GetPartiesInfoResponse, GetPeersInfoRequest, GetPeersInfoResponse, PublicKey, QueryObservationProofResponse, RecentDiscoveryTransactionsResponse, Request, ResolveCodeResponse, SubmitTransactionRequest, UtxoId, UtxoValidResponse};
 
+use crate::core::gossip_dedup::{forget_gossip, is_duplicate_gossip};
+use crate::core::rebroadcast::track_local_submission;
+use crate::core::replay_protection::check_request_replay;
 use crate::api::about;
 use crate::core::discovery::DiscoveryMessage;
 // use crate::api::p2p_io::rgnetwork::{Client, Event, PeerResponse};
@@ -35,6 +38,10 @@ use crate::schema::structs::{Response, ResponseMetadata};
 use crate::util::keys::ToPublicKeyFromLib;
 use redgold_schema::util::lang_util::SameResult;
 use crate::api::faucet::faucet_request;
+use crate::core::transact::validate::dry_run_validate;
+use crate::api::batch_balance::batch_balance;
+use crate::core::seed_mode::check_seed_request;
+use crate::core::checkpoint;
 use crate::multiparty::watcher::DepositWatcher;
 use crate::observability::logging::Loggable;
 use crate::observability::metrics_help::WithMetrics;
@@ -46,6 +53,8 @@ pub struct PeerRxEventHandler {
 
 impl PeerRxEventHandler {
 
+    #[tracing::instrument(skip(relay, pm), fields(peer = %pm.request.proof.as_ref()
+        .and_then(|p| p.public_key.as_ref()).map(|pk| pk.short_id()).unwrap_or("unknown".to_string())))]
     pub async fn handle_incoming_message(
         relay: Relay, pm: PeerMessage
         // , rt: Arc<Runtime>
@@ -54,6 +63,9 @@ impl PeerRxEventHandler {
 
         // This is important for some requests but not others, use in case by case basis
         let verified = pm.request.verify_auth().add("Incoming request authorization failure in peer rx event handler");
+        let replay_check = verified.as_ref().ok()
+            .map(|pk| check_request_replay(&relay, pk, &pm.request))
+            .unwrap_or(Ok(()));
 
         // Check if we know the peer, if not, attempt discovery
         if let Some(pk) = pm.request.clone().proof.clone().and_then(|r| r.public_key) {
@@ -63,7 +75,7 @@ impl PeerRxEventHandler {
             } else {
                 if let Some(nmd) = &pm.request.node_metadata {
                     info!("Attempting immediate discovery on peer {}", pk.short_id());
-                    relay.discovery.sender.send_err(
+                    relay.discovery.send_err(
                         DiscoveryMessage::new(nmd.clone(), pm.dynamic_node_metadata.clone())
                     ).log_error().ok();
                 }
@@ -72,7 +84,10 @@ impl PeerRxEventHandler {
 
         // Handle the request
         // tracing::debug!("Peer Rx Event Handler received request {}", json(&pm.request)?);
-        let response = Self::request_response(relay.clone(), pm.request.clone(), verified.clone()).await
+        let response = match replay_check {
+            Ok(_) => Self::request_response_timeout(relay.clone(), pm.request.clone(), verified.clone()).await,
+            Err(e) => Err(e),
+        }
             .map_err(|e| Response::from_error_info(e)).combine()
             .with_metadata(relay.node_metadata().await?)
             .with_auth(&relay.node_config.keypair())
@@ -90,6 +105,21 @@ impl PeerRxEventHandler {
     }
 
 
+    // Dropping the handler future on timeout cancels any work still in progress
+    async fn request_response_timeout(relay: Relay, request: Request, verified: RgResult<PublicKey>) -> RgResult<Response> {
+        let timeout = relay.node_config.peer_rx.timeout_for(&request);
+        match tokio::time::timeout(timeout, Self::request_response(relay, request, verified)).await {
+            Ok(r) => r,
+            Err(_) => {
+                counter!("redgold.peer.message.timeout").increment(1);
+                Err(error_message(
+                    Error::RequestTimeout,
+                    format!("Peer request handling exceeded timeout of {} ms", timeout.as_millis())
+                ))
+            }
+        }
+    }
+
     pub async fn request_response(relay: Relay, request: Request, _verified: RgResult<PublicKey>
                                   // , arc: Arc<Runtime>
     ) -> RgResult<Response> {
@@ -102,6 +132,8 @@ impl PeerRxEventHandler {
 
         let auth_required = request.auth_required();
 
+        check_seed_request(&relay, &request)?;
+
         if let Some(fr) = &request.faucet_request {
             response.faucet_response = Some(faucet_request(fr, &relay, request.origin.as_ref()).await.log_error().with_err_count("redgold.faucet.error")?);
         }
@@ -120,6 +152,15 @@ impl PeerRxEventHandler {
             response.get_parties_info_response = Some(get_parties_info_response);
         }
 
+        if let Some(r) = &request.validate_transaction_request {
+            let tx = r.transaction.safe_get_msg("Missing transaction on validate request")?;
+            response.validate_transaction_response = Some(dry_run_validate(tx, &relay).await);
+        }
+
+        if let Some(r) = &request.batch_balance_request {
+            response.batch_balance_response = Some(batch_balance(r, &relay).await?);
+        }
+
         if let Some(r) = &request.lookup_transaction_request {
             let opt = relay.lookup_transaction(r).await?;
             response.lookup_transaction_response = opt;
@@ -129,6 +170,14 @@ impl PeerRxEventHandler {
             response.genesis_response = relay.ds.config_store.get_genesis().await?.clone();
         }
 
+        if let Some(r) = &request.payment_request {
+            crate::core::payment_requests::receive_payment_request(&relay, r).await?;
+        }
+
+        if let Some(r) = &request.state_checkpoint_request {
+            response.state_checkpoint_response = checkpoint::local_checkpoint(&relay, r.time).await?;
+        }
+
         if let Some(r) = &request.recent_transactions_request {
             let from_mempool = relay.mempool_entries.iter().map(|t| t.hash_or()).collect_vec();
             let in_process = relay.transaction_channels.iter().map(|c| c.transaction_hash.clone()).collect_vec();
@@ -204,7 +253,11 @@ impl PeerRxEventHandler {
 
         if let Some(s) = request.submit_transaction_request {
             // debug!("Received submit transaction request, sending to relay");
+            let submitted = s.transaction.clone();
             response.submit_transaction_response = Some(relay.submit_transaction(s).await?);
+            if let Some(t) = submitted.as_ref() {
+                track_local_submission(&relay, t);
+            }
         } // else
         // if let some(f) = request.fau
         if let Some(_) = request.get_peers_info_request {
@@ -219,20 +272,24 @@ impl PeerRxEventHandler {
 
         if let Some(t) = request.gossip_transaction_request {
             if let Some(t) = t.transaction {
-                trace!("Received gossip transaction request for {}", &t.hash_or().hex());
-                relay.submit_transaction(SubmitTransactionRequest {
-                    transaction: Some(t),
-                    sync_query_response: false,
-                }).await?;
+                let hash = t.hash_or();
+                trace!("Received gossip transaction request for {}", &hash.hex());
+                if !is_duplicate_gossip(&relay, &hash) {
+                    let result = relay.submit_transaction(SubmitTransactionRequest {
+                        transaction: Some(t),
+                        sync_query_response: false,
+                    }).await;
+                    if result.is_err() {
+                        forget_gossip(&relay, &hash);
+                    }
+                    result?;
+                }
             }
         }
         if let Some(o) = request.gossip_observation_request {
             // info!("Received gossip observation request");
-            relay
-                .observation
-                .sender
-                .send(o.observation.unwrap())
-                .expect("gossip send failure");
+            relay.observation
+                .send(o.observation.safe_get_msg("Missing observation on gossip request")?.clone()).await?;
         }
 
         if let Some(download_request) = request.download_request {
@@ -279,6 +336,9 @@ impl PeerRxEventHandler {
                         response.initiate_signing_response = Some(result1?);
                         // });
                     }
+                    if let Some(hb) = &request.watcher_heartbeat {
+                        crate::multiparty::election::handle_heartbeat(&relay, hb, &pk).await?;
+                    }
                 }
                 Err(e) => { return Err(e).add("Unable to process request, authorization required and failed").log_error(); }
             }
@@ -290,7 +350,9 @@ impl PeerRxEventHandler {
     async fn run(&mut self) -> Result<(), ErrorInfo> {
         let receiver = self.relay.peer_message_rx.receiver.clone();
         let relay = self.relay.clone();
-        receiver.into_stream().map(|r| Ok(r)).try_for_each_concurrent(10, |pm| {
+        // Seed nodes field requests from many bootstrapping peers at once
+        let concurrency = if relay.node_config.seed_mode { 50 } else { 10 };
+        receiver.into_stream().map(|r| Ok(r)).try_for_each_concurrent(concurrency, |pm| {
             // info!("Received peer message");
             Self::handle_incoming_message(relay.clone(), pm)
         }).await
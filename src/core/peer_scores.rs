@@ -0,0 +1,122 @@
+use std::cmp::Ordering;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use redgold_schema::structs::PublicKey;
+use crate::core::relay::Relay;
+use crate::node_config::PeerScoringConfig;
+
+/// Rolling round trip time and failure rate of REST calls made to a single peer.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PeerStats {
+    pub rtt_ms: Option<f64>,
+    // Exponentially weighted fraction of recent calls which failed
+    pub failure_rate: f64,
+    pub successes: u64,
+    pub failures: u64,
+    pub last_updated: i64,
+}
+
+impl PeerStats {
+    pub fn record(&mut self, cfg: &PeerScoringConfig, success: bool, rtt_ms: f64, now: i64) {
+        let alpha = cfg.ewma_alpha;
+        let failed = if success { 0.0 } else { 1.0 };
+        if self.successes + self.failures == 0 {
+            self.failure_rate = failed;
+        } else {
+            self.failure_rate = alpha * failed + (1.0 - alpha) * self.failure_rate;
+        }
+        // Failed calls are usually timeouts, which would only distort the latency estimate
+        if success {
+            self.rtt_ms = Some(match self.rtt_ms {
+                Some(r) => alpha * rtt_ms + (1.0 - alpha) * r,
+                None => rtt_ms,
+            });
+            self.successes += 1;
+        } else {
+            self.failures += 1;
+        }
+        self.last_updated = now;
+    }
+
+    /// Between 0 and 1, halved for a peer at the reference latency and scaled down by its
+    /// failure rate.
+    pub fn score(&self, cfg: &PeerScoringConfig) -> f64 {
+        let latency = self.rtt_ms.map(|r| cfg.reference_rtt_ms / (cfg.reference_rtt_ms + r))
+            .unwrap_or(cfg.unknown_score);
+        latency * (1.0 - self.failure_rate)
+    }
+}
+
+/// Scores served by the peers API and shown in the GUI peers view.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PeerScore {
+    pub public_key: String,
+    pub rtt_ms: Option<f64>,
+    pub failure_rate: f64,
+    pub successes: u64,
+    pub failures: u64,
+    pub score: f64,
+    pub last_updated: i64,
+}
+
+pub fn record_peer_call(relay: &Relay, peer: &PublicKey, success: bool, rtt_ms: f64, now: i64) {
+    relay.peer_stats.entry(peer.clone()).or_default()
+        .record(&relay.node_config.peer_scoring, success, rtt_ms, now);
+}
+
+pub fn peer_score(relay: &Relay, peer: &PublicKey) -> f64 {
+    let cfg = &relay.node_config.peer_scoring;
+    relay.peer_stats.get(peer).map(|s| s.score(cfg)).unwrap_or(cfg.unknown_score)
+}
+
+/// Orders peers best score first, keeping the original order between equal scores.
+pub fn rank_peers(relay: &Relay, peers: Vec<PublicKey>) -> Vec<PublicKey> {
+    peers.into_iter()
+        .map(|p| (peer_score(relay, &p), p))
+        .sorted_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal))
+        .map(|(_, p)| p)
+        .collect_vec()
+}
+
+/// Best ranked peers up to the configured gossip fan-out, or all of them when uncapped.
+pub fn select_fanout(relay: &Relay, peers: Vec<PublicKey>) -> Vec<PublicKey> {
+    let ranked = rank_peers(relay, peers);
+    match relay.node_config.peer_scoring.gossip_fanout {
+        Some(n) => ranked.into_iter().take(n).collect_vec(),
+        None => ranked,
+    }
+}
+
+pub fn peer_scores(relay: &Relay) -> Vec<PeerScore> {
+    let cfg = &relay.node_config.peer_scoring;
+    relay.peer_stats.iter()
+        .map(|e| PeerScore {
+            public_key: e.key().hex_or(),
+            rtt_ms: e.rtt_ms,
+            failure_rate: e.failure_rate,
+            successes: e.successes,
+            failures: e.failures,
+            score: e.score(cfg),
+            last_updated: e.last_updated,
+        })
+        .sorted_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal))
+        .collect_vec()
+}
+
+#[test]
+fn peer_stats_score_latency_and_failures() {
+    let cfg = PeerScoringConfig::default();
+    let mut fast = PeerStats::default();
+    let mut slow = PeerStats::default();
+    for i in 0..10 {
+        fast.record(&cfg, true, 20.0, i);
+        slow.record(&cfg, true, 2000.0, i);
+    }
+    assert!(fast.score(&cfg) > slow.score(&cfg));
+    assert!(PeerStats::default().score(&cfg) > slow.score(&cfg));
+    let before = fast.score(&cfg);
+    fast.record(&cfg, false, 10_000.0, 11);
+    assert_eq!(fast.failures, 1);
+    assert!(fast.score(&cfg) < before);
+    assert!((fast.rtt_ms.unwrap() - 20.0).abs() < 1e-6);
+}
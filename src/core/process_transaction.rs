@@ -14,8 +14,11 @@ use tokio::select;
 use tokio::task::{JoinError, JoinHandle};
 use uuid::Uuid;
 use redgold_schema::{json_or, ProtoHashable, ProtoSerde, RgResult, SafeOption, struct_metadata_new, structs, task_local, task_local_map, WithMetadataHashableFields};
-use redgold_schema::structs::{ContentionKey, ContractStateMarker, ExecutionInput, ExecutorBackend, UtxoId, GossipTransactionRequest, Hash, PublicResponse, QueryObservationProofRequest, Request, Response, ValidationType};
+use redgold_schema::structs::{ContentionKey, ContractStateMarker, ExecutionInput, ExecutorBackend, UtxoId, GossipTransactionRequest, Hash, PublicResponse, QueryObservationProofRequest, Request, Response, TransactionConflict, ValidationType};
 
+use crate::core::checkpoint::validate_checkpoint_time;
+use crate::core::transact::fee_policy::{accepted_fee_addresses, validate_fee};
+use crate::core::transact::name_registry::validate_name_registrations;
 use crate::core::internal_message::{Channel, new_bounded_channel, PeerMessage, RecvAsyncErrorInfo, SendErrorInfo, TransactionMessage};
 use crate::core::relay::Relay;
 use crate::core::transaction::{TransactionTestContext};
@@ -90,7 +93,8 @@ pub struct TransactionProcessContext {
     relay: Relay,
     request_processor: Option<RequestProcessor>,
     transaction_hash: Option<Hash>,
-    utxo_ids: Option<Vec<UtxoId>>
+    utxo_ids: Option<Vec<UtxoId>>,
+    name_keys: Vec<ContentionKey>,
 }
 
 
@@ -161,6 +165,7 @@ impl TransactionProcessContext {
             request_processor: None,
             transaction_hash: None,
             utxo_ids: None,
+            name_keys: vec![],
         };
 
         return tokio::spawn(async move { context.run().await });
@@ -300,6 +305,10 @@ impl TransactionProcessContext {
                 self.clean_utxo(&request_processor, utxo_ids, ii);
             }
         }
+        let hash = self.transaction_hash.safe_get()?;
+        for key in self.name_keys.drain(..) {
+            self.relay.name_contentions.remove_if(&key, |_, h| h == hash);
+        }
         Ok(())
     }
 
@@ -319,9 +328,24 @@ impl TransactionProcessContext {
         }
 
         // Validate obvious schema related errors / local errors requiring no other context information
-        transaction.prevalidate()?;
+        transaction.prevalidate_structure()?;
+
+        // Signature checks are CPU bound, so run them on the verifier's pool rather than a runtime thread
+        let verifier = self.relay.signature_verifier.clone();
+        let tx = transaction.clone();
+        let start = current_time_millis_i64();
+        tokio::task::spawn_blocking(move || verifier.verify_transaction(&tx)).await
+            .map_err(|e| error_info(format!("Signature verification task failure {}", e.to_string())))??;
+        histogram!("redgold.transaction.signature_verify_ms").record((current_time_millis_i64() - start) as f64);
 
         transaction.validate_network(&self.relay.node_config.network)?;
+        validate_checkpoint_time(
+            transaction.time()?.clone(),
+            current_time_millis_i64(),
+            self.relay.node_config.checkpoint.finalization_lag.as_millis() as i64
+        )?;
+        validate_fee(transaction, &self.relay.node_config.fee_policy, &accepted_fee_addresses(&self.relay).await?)?;
+        validate_name_registrations(transaction, &ds).await?;
 
         Ok(())
 
@@ -367,6 +391,25 @@ impl TransactionProcessContext {
         // TODO: Check for conflicts via peer query -- currently unimplemented
         check_utxo_conflicts(self.relay.clone(), &fixed_utxo_ids, &hash).await?;
 
+        // Registrations of one name from different owners don't share inputs, so they're claimed
+        // separately and the later one rejected while the first is in process.
+        for r in transaction.name_registrations() {
+            let key = ContentionKey::name_registration(&r.name);
+            match self.relay.name_contentions.entry(key.clone()) {
+                Entry::Occupied(e) if e.get() != &hash => {
+                    counter!("redgold.transaction.name_conflict").increment(1);
+                    return Err(error_info(format!(
+                        "Name {} has a registration in process by transaction {}", r.name, e.get().hex()
+                    )));
+                }
+                Entry::Occupied(_) => {}
+                Entry::Vacant(e) => {
+                    e.insert(hash.clone());
+                    self.name_keys.push(key);
+                }
+            }
+        }
+
         let mut conflict_detected = false;
         let self_conflict = Conflict {
             transaction_hash: hash.clone(),
@@ -375,6 +418,7 @@ impl TransactionProcessContext {
             request_processer: request_processor.clone(),
         };
         let mut conflicts: Vec<Conflict> = vec![];
+        let mut conflict_records: Vec<TransactionConflict> = vec![];
 
         // Change to UTXO stream processor sink message?
         // TODO: Change this so the UTXO pool is responsible for this, remove the request processor from here
@@ -397,6 +441,13 @@ impl TransactionProcessContext {
                             utxo_id.clone().transaction_hash.expect("h").hex(),
                             utxo_id.output_index.clone()
                         );
+                        counter!("redgold.transaction.conflict").increment(1);
+                        conflict_records.push(TransactionConflict {
+                            utxo_id: Some(utxo_id.clone()),
+                            transaction_hash: Some(active_request.transaction_hash.clone()),
+                            conflicting_transaction_hash: Some(hash.clone()),
+                            time: current_time_millis_i64(),
+                        });
                         // Need to capture this conflict LOCALLY here for use later.
                         active_request
                             .request_processer
@@ -415,6 +466,10 @@ impl TransactionProcessContext {
             };
         }
 
+        for record in &conflict_records {
+            self.relay.ds.transaction_store.insert_conflict(record).await.log_error().ok();
+        }
+
         // TODO: Don't remember the purpose of this duplicate validation here, we should really
         // seed this with existing data to reduce request size and just verify it's still valid
         let resolver_data = resolve_transaction(&transaction,
@@ -0,0 +1,148 @@
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use async_trait::async_trait;
+use itertools::Itertools;
+use log::info;
+use metrics::{counter, gauge};
+use redgold_schema::{RgResult, WithMetadataHashable};
+use redgold_schema::structs::{GossipTransactionRequest, Hash, PublicKey, Request, Transaction};
+use crate::api::explorer::handle_explorer_observations;
+use crate::core::peer_scores::rank_peers;
+use crate::core::relay::Relay;
+use crate::core::stream_handlers::IntervalFold;
+use crate::node_config::RebroadcastConfig;
+use crate::util::current_time_millis_i64;
+
+/// A transaction submitted to this node by a client, tracked until finalized.
+#[derive(Clone, Debug)]
+pub struct LocalSubmission {
+    pub transaction: Transaction,
+    pub first_submitted: i64,
+    pub attempts: u32,
+    pub next_attempt: i64,
+    // Peers already re-gossiped to, later attempts prefer peers not in here
+    pub sent_to: HashSet<PublicKey>,
+}
+
+/// Delay before the next re-broadcast, doubling per attempt up to the configured max.
+pub fn backoff(cfg: &RebroadcastConfig, attempts: u32) -> Duration {
+    let factor = 2u32.saturating_pow(attempts.min(16));
+    cfg.initial_backoff.saturating_mul(factor).min(cfg.max_backoff)
+}
+
+/// Tracks a client submission, a repeated submission restarts its backoff.
+pub fn track_local_submission(relay: &Relay, tx: &Transaction) {
+    let now = current_time_millis_i64();
+    let first_delay = backoff(&relay.node_config.rebroadcast, 0).as_millis() as i64;
+    if let Ok(mut s) = relay.local_submissions.lock() {
+        let entry = s.entry(tx.hash_or()).or_insert(LocalSubmission {
+            transaction: tx.clone(),
+            first_submitted: now,
+            attempts: 0,
+            next_attempt: now + first_delay,
+            sent_to: HashSet::new(),
+        });
+        entry.attempts = 0;
+        entry.next_attempt = now + first_delay;
+    }
+}
+
+// Prefers peers which haven't been sent the transaction yet, starting over once all have
+fn fresh_peers(active: Vec<PublicKey>, sent_to: &mut HashSet<PublicKey>, fanout: usize) -> Vec<PublicKey> {
+    let mut fresh = active.iter().filter(|p| !sent_to.contains(p)).cloned().collect_vec();
+    if fresh.is_empty() {
+        sent_to.clear();
+        fresh = active;
+    }
+    let selected = fresh.into_iter().take(fanout).collect_vec();
+    sent_to.extend(selected.iter().cloned());
+    selected
+}
+
+/// Re-gossips locally submitted transactions which haven't finalized, so a submission made
+/// during a peer outage still propagates once peers are reachable again.
+pub struct Rebroadcaster {
+    relay: Relay,
+}
+
+impl Rebroadcaster {
+    pub fn new(relay: &Relay) -> Self {
+        Self { relay: relay.clone() }
+    }
+
+    fn due(&self, now: i64) -> Vec<(Hash, LocalSubmission)> {
+        self.relay.local_submissions.lock()
+            .map(|s| s.iter().filter(|(_, l)| l.next_attempt <= now)
+                .map(|(h, l)| (h.clone(), l.clone())).collect_vec())
+            .unwrap_or_default()
+    }
+
+    fn update(&self, f: impl FnOnce(&mut HashMap<Hash, LocalSubmission>)) {
+        if let Ok(mut s) = self.relay.local_submissions.lock() {
+            f(&mut s);
+        }
+    }
+}
+
+#[async_trait]
+impl IntervalFold for Rebroadcaster {
+    async fn interval_fold(&mut self) -> RgResult<()> {
+        let cfg = self.relay.node_config.rebroadcast.clone();
+        let now = current_time_millis_i64();
+        for (hash, mut l) in self.due(now) {
+            let obs = handle_explorer_observations(hash.hex(), self.relay.clone()).await?;
+            if obs.finalized {
+                self.update(|s| { s.remove(&hash); });
+                continue;
+            }
+            if now - l.first_submitted > cfg.max_age.as_millis() as i64 {
+                info!("Giving up re-broadcasting unfinalized transaction {}", hash.hex());
+                counter!("redgold.transaction.rebroadcast.expired").increment(1);
+                self.update(|s| { s.remove(&hash); });
+                continue;
+            }
+            let active = rank_peers(&self.relay, self.relay.ds.peer_store.active_nodes(None).await?);
+            let peers = fresh_peers(active, &mut l.sent_to, cfg.fanout);
+            for p in peers.iter() {
+                let mut req = Request::default();
+                let mut gtr = GossipTransactionRequest::default();
+                gtr.transaction = Some(l.transaction.clone());
+                req.gossip_transaction_request = Some(gtr);
+                self.relay.schedule_gossip(req, p);
+            }
+            counter!("redgold.transaction.rebroadcast").increment(1);
+            l.attempts += 1;
+            l.next_attempt = now + backoff(&cfg, l.attempts).as_millis() as i64;
+            self.update(|s| {
+                // Skip if finalized and removed, or resubmitted, while peers were being messaged
+                if let Some(e) = s.get_mut(&hash) {
+                    if e.attempts + 1 == l.attempts {
+                        *e = l;
+                    }
+                }
+            });
+        }
+        let tracked = self.relay.local_submissions.lock().map(|s| s.len()).unwrap_or(0);
+        gauge!("redgold.transaction.rebroadcast.tracked").set(tracked as f64);
+        Ok(())
+    }
+}
+
+#[test]
+fn rebroadcast_backoff_doubles_until_capped() {
+    let cfg = RebroadcastConfig::default();
+    assert_eq!(backoff(&cfg, 0), cfg.initial_backoff);
+    assert_eq!(backoff(&cfg, 1), cfg.initial_backoff * 2);
+    assert_eq!(backoff(&cfg, 40), cfg.max_backoff);
+    use redgold_keys::TestConstants;
+    use redgold_keys::util::keys::ToPublicKeyFromLib;
+    let tc = TestConstants::new();
+    let peers = vec![tc.public.to_struct_public_key(), tc.public2.to_struct_public_key()];
+    let mut sent_to = HashSet::new();
+    assert_eq!(fresh_peers(peers.clone(), &mut sent_to, 1).len(), 1);
+    let second = fresh_peers(peers.clone(), &mut sent_to, 1);
+    assert_eq!(second.len(), 1);
+    assert_eq!(sent_to.len(), 2);
+    fresh_peers(peers, &mut sent_to, 1);
+    assert_eq!(sent_to.len(), 1);
+}
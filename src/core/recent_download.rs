@@ -80,7 +80,7 @@ impl RecentDownload {
                                 false
                             ).await;
                             if let Ok(resolved_input) = resolved_input {
-                                self.relay.unknown_resolved_inputs.sender.send_err(resolved_input)?;
+                                self.relay.unknown_resolved_inputs.send(resolved_input).await?;
                             } else {
                                 counter!("redgold.recent_download.resolve_input_error").increment(1);
                             }
@@ -97,6 +97,9 @@ impl RecentDownload {
 #[async_trait]
 impl IntervalFold for RecentDownload {
     async fn interval_fold(&mut self) -> RgResult<()> {
+        if self.relay.disk_space_low.load() {
+            return Ok(());
+        }
         let updates = self.relay.unknown_resolved_inputs.recv_while()?;
         for update in updates {
             self.process_resolved_input(update).await?;
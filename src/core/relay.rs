@@ -2,6 +2,7 @@ use std::cmp::Ordering;
 use std::collections::{HashMap, HashSet};
 use crossbeam::atomic::AtomicCell;
 use std::sync::{Arc, Mutex};
+use metrics::counter;
 use std::time::{Duration, Instant};
 
 use crate::core::internal_message;
@@ -15,11 +16,11 @@ use futures::{future, TryFutureExt};
 use futures::stream::FuturesUnordered;
 use futures::task::SpawnExt;
 use itertools::Itertools;
-use log::info;
+use log::{error, info};
 use tokio::runtime::Runtime;
-use redgold_schema::{error_info, ErrorInfoContext, RgResult, struct_metadata_new, structs};
+use redgold_schema::{EasyJson, error_info, ErrorInfoContext, RgResult, struct_metadata_new, structs};
 use redgold_schema::errors::EnhanceErrorInfo;
-use redgold_schema::structs::{AboutNodeRequest, Address, ContentionKey, ContractStateMarker, DynamicNodeMetadata, UtxoId, GossipTransactionRequest, Hash, HashType, InitiateMultipartyKeygenRequest, InitiateMultipartySigningRequest, MultipartyIdentifier, NodeMetadata, ObservationProof, Output, PeerId, PeerIdInfo, PeerNodeInfo, PublicKey, Request, Response, State, Transaction, TrustData, ValidationType, PartitionInfo, ResolveHashRequest, PartyId};
+use redgold_schema::structs::{AboutNodeRequest, Address, ContentionKey, ContractStateMarker, DynamicNodeMetadata, UtxoId, GossipTransactionRequest, Hash, HashType, InitiateMultipartyKeygenRequest, InitiateMultipartySigningRequest, MultipartyIdentifier, NodeMetadata, ObservationProof, Output, PeerId, PeerIdInfo, PeerNodeInfo, PublicKey, Request, Response, State, Transaction, TrustData, ValidationType, PartitionInfo, ResolveHashRequest, PartyId, PaymentRequest};
 use crate::core::transact::tx_builder_supports::TransactionBuilder;
 use crate::core::discovery::DiscoveryMessage;
 
@@ -30,13 +31,20 @@ use crate::core::process_transaction::{RequestProcessor, UTXOContentionPool};
 use redgold_data::data_store::DataStore;
 use redgold_data::peer::PeerTrustQueryResult;
 use redgold_keys::request_support::{RequestSupport, ResponseSupport};
+use redgold_keys::batch_verify::BatchVerifier;
 use crate::core::transact::tx_builder_supports::TransactionBuilderSupport;
 use redgold_schema::util::xor_distance::{xorf_conv_distance, xorfc_hash};
 use crate::core::contract::contract_state_manager::ContractStateMessage;
 use crate::node_config::NodeConfig;
+use crate::core::replay_protection::SeenNonces;
+use crate::core::gossip_dedup::SeenTransactions;
+use crate::core::rebroadcast::LocalSubmission;
+use crate::core::gossip_scheduler::{GossipQueues, QueuedGossip};
+use crate::core::peer_scores::{PeerStats, select_fanout};
 use crate::schema::structs::{Observation, ObservationMetadata};
 use crate::schema::{ProtoHashable, SafeOption, WithMetadataHashable};
 use crate::util;
+use crate::util::current_time_millis_i64;
 use crate::util::keys::ToPublicKey;
 
 #[derive(Clone)]
@@ -110,6 +118,8 @@ pub struct Relay {
     pub transaction_channels: Arc<DashMap<Hash, RequestProcessor>>,
     /// TODO: This really needs to incorporate some kind of UTXO stream handler?
     pub utxo_channels: Arc<DashMap<UtxoId, UTXOContentionPool>>,
+    /// Names with a registration currently in process, keyed to the registering transaction
+    pub name_contentions: Arc<DashMap<ContentionKey, Hash>>,
     /// Some update associated with the trust model or change in rating label
     pub trust: Channel<TrustUpdate>,
     /// This isn't really used anywhere, but might be useful for keeping track of some kind of
@@ -130,7 +140,26 @@ pub struct Relay {
     pub predicted_trust_overall_rating_score: Arc<Mutex<HashMap<PeerId, f64>>>,
     pub unknown_resolved_inputs: Channel<ResolvedInput>,
     pub mempool_entries: Arc<DashMap<Hash, Transaction>>,
-    pub faucet_rate_limiter: Arc<Mutex<HashMap<String, (Instant, i32)>>>
+    pub faucet_rate_limiter: Arc<Mutex<HashMap<String, (Instant, i32)>>>,
+    // Last heartbeat time from each watcher party member
+    pub watcher_heartbeats: Arc<Mutex<HashMap<PublicKey, i64>>>,
+    /// Set while free disk space is below the configured minimum, non-essential writers check
+    /// this and skip work to avoid corrupting the datastore on a full disk
+    pub disk_space_low: Arc<AtomicCell<bool>>,
+    /// Recently seen signed request nonces per peer, used to reject replayed requests
+    pub request_nonces: Arc<DashMap<PublicKey, SeenNonces>>,
+    /// Recently gossiped transaction hashes, copies from other peers are dropped before validation
+    pub gossip_seen_transactions: Arc<Mutex<SeenTransactions>>,
+    /// Shared thread pool for verifying transaction signatures in parallel
+    pub signature_verifier: BatchVerifier,
+    /// Client submitted transactions re-gossiped until finalized
+    pub local_submissions: Arc<Mutex<HashMap<Hash, LocalSubmission>>>,
+    /// Outbound gossip awaiting the scheduler, per peer and priority
+    pub gossip_queues: Arc<Mutex<GossipQueues>>,
+    /// Rolling latency and failure rate of calls to each peer, used to rank gossip targets
+    pub peer_stats: Arc<DashMap<PublicKey, PeerStats>>,
+    /// Payment requests addressed to this node's peer id, keyed by request id
+    pub payment_requests: Arc<DashMap<String, PaymentRequest>>,
 
 }
 
@@ -142,6 +171,13 @@ impl Relay {
 
 impl Relay {
 
+    pub fn check_disk_space(&self) -> RgResult<()> {
+        if self.disk_space_low.load() {
+            return Err(error_info("Node is low on disk space, non-essential writes are paused"));
+        }
+        Ok(())
+    }
+
     pub fn check_rate_limit(&self, ip: &String) -> RgResult<bool> {
         let mut l = self.faucet_rate_limiter.lock()
             .map_err(|e| error_info(format!("Failed to lock faucet_rate_limiter {}", e.to_string())))?;
@@ -518,7 +554,7 @@ impl Relay {
             observation_metadata: om,
             sender,
         };
-        self.observation_metadata.sender.send_err(omi)?;
+        self.observation_metadata.send(omi).await?;
         let res = tokio::time::timeout(
             Duration::from_secs(self.node_config.observation_formation_millis.as_secs() + 10),
             r.recv_async_err()
@@ -551,7 +587,7 @@ impl Relay {
         let (s, r) = flume::unbounded::<Response>();
         let mut pm = PeerMessage::from_pk(&request, &node.clone());
         pm.response = Some(s);
-        self.peer_message_tx.sender.send_err(pm)?;
+        self.peer_message_tx.send_err(pm)?;
         let res = tokio::time::timeout(timeout, r.recv_async_err()).await
             .map_err(|e| error_info(e.to_string()))??;
         // Is this necessary?? Or have we already handled this elsewhere?
@@ -567,7 +603,7 @@ impl Relay {
         if let Some(t) = timeout {
             pm.send_timeout = t;
         }
-        relay.peer_message_tx.sender.send_err(pm)?;
+        relay.peer_message_tx.send_err(pm)?;
         let res = r.recv_async_err().await?;
         res.as_error_info()?;
         Ok(res)
@@ -581,31 +617,45 @@ impl Relay {
         pm.request = request;
         pm.response = Some(s);
         pm.public_key = key;
-        self.peer_message_rx.sender.send_err(pm).add("receive_message_sync")?;
+        self.peer_message_rx.send_err(pm).add("receive_message_sync")?;
         let res = r.recv_async_err_timeout(timeout).await?;
         Ok(res)
     }
 
     pub async fn gossip(&self, tx: &Transaction) -> Result<(), ErrorInfo> {
-        let all = self.ds.peer_store.select_gossip_peers(tx).await?;
+        let all = select_fanout(self, self.ds.peer_store.select_gossip_peers(tx).await?);
         for p in all {
             let mut req = Request::default();
             let mut gtr = GossipTransactionRequest::default();
             gtr.transaction = Some(tx.clone());
             req.gossip_transaction_request = Some(gtr);
-            self.send_message(req, p).await?;
+            self.schedule_gossip(req, &p);
         }
         Ok(())
     }
 
     pub async fn gossip_req(&self, req: &Request, hash: &Hash) -> Result<(), ErrorInfo> {
-        let all = self.ds.peer_store.peers_near(hash, |p| p.transaction_hash).await?;
+        let all = select_fanout(self, self.ds.peer_store.peers_near(hash, |p| p.transaction_hash).await?);
         for p in all {
-            self.send_message(req.clone(), p).await?;
+            self.schedule_gossip(req.clone(), &p);
         }
         Ok(())
     }
 
+    /// Queues a fire and forget message for the gossip scheduler rather than sending it
+    /// immediately, returning false if the peer's queue is full.
+    pub fn schedule_gossip(&self, req: Request, peer: &PublicKey) -> bool {
+        let queued = self.gossip_queues.lock()
+            .map(|mut q| q.push(
+                &self.node_config.gossip_scheduler, peer, QueuedGossip::new(req), current_time_millis_i64()
+            ))
+            .unwrap_or(false);
+        if !queued {
+            counter!("redgold.gossip.queue_full").increment(1);
+        }
+        queued
+    }
+
     pub async fn utxo_id_valid_peers(&self, utxo_id: &UtxoId) -> RgResult<Option<Transaction>> {
         let peers = self.ds.peer_store
             .peers_near(&utxo_id.as_hash(), |p| p.utxo).await?;
@@ -729,7 +779,7 @@ impl Relay {
 
     pub async fn send_message(&self, request: Request, node: structs::PublicKey) -> Result<(), ErrorInfo> {
         let pm = PeerMessage::from_pk(&request, &node);
-        self.peer_message_tx.sender.send_err(pm)?;
+        self.peer_message_tx.send_err(pm)?;
         Ok(())
     }
 
@@ -746,7 +796,7 @@ impl Relay {
         if let Some(t) = timeout {
             pm.send_timeout = t;
         }
-        self.peer_message_tx.sender.send_err(pm)?;
+        self.peer_message_tx.send_err(pm)?;
         Ok(r)
     }
 
@@ -852,26 +902,54 @@ impl Relay {
             mempool: internal_message::new_bounded_channel::<TransactionMessage>(node_config.mempool.channel_bound),
             transaction_process: internal_message::new_bounded_channel(node_config.tx_config.channel_bound),
             // TODO: Remove and merge this into tx
-            observation: internal_message::new_channel::<Transaction>(),
+            observation: internal_message::new_configured_channel::<Transaction>(
+                "observation", &node_config.channels.observation
+            ),
             // multiparty: internal_message::new_channel::<MultipartyRequestResponse>(),
-            observation_metadata: internal_message::new_channel::<ObservationMetadataInternalSigning>(),
-            peer_message_tx: internal_message::new_channel::<PeerMessage>(),
-            peer_message_rx: internal_message::new_channel::<PeerMessage>(),
+            observation_metadata: internal_message::new_configured_channel::<ObservationMetadataInternalSigning>(
+                "observation_metadata", &node_config.channels.observation_metadata
+            ),
+            peer_message_tx: internal_message::new_configured_channel::<PeerMessage>(
+                "peer_message_tx", &node_config.channels.peer_message_tx
+            ),
+            peer_message_rx: internal_message::new_configured_channel::<PeerMessage>(
+                "peer_message_rx", &node_config.channels.peer_message_rx
+            ),
             ds,
             transaction_channels: Arc::new(DashMap::new()),
             utxo_channels: Arc::new(DashMap::new()),
-            trust: internal_message::new_channel::<TrustUpdate>(),
+            name_contentions: Arc::new(DashMap::new()),
+            trust: internal_message::new_configured_channel::<TrustUpdate>(
+                "trust", &node_config.channels.trust
+            ),
             node_state: Arc::new(AtomicCell::new(NodeState::Initializing)),
-            udp_outgoing_messages: internal_message::new_channel::<PeerMessage>(),
+            udp_outgoing_messages: internal_message::new_configured_channel::<PeerMessage>(
+                "udp_outgoing", &node_config.channels.udp_outgoing
+            ),
             discovery: internal_message::new_bounded_channel(100),
             mp_keygen_authorizations: Arc::new(Mutex::new(Default::default())),
             mp_signing_authorizations: Arc::new(Mutex::new(Default::default())),
             contract_state_manager_channels,
             contention,
             predicted_trust_overall_rating_score: Arc::new(Mutex::new(Default::default())),
-            unknown_resolved_inputs: internal_message::new_channel(),
+            unknown_resolved_inputs: internal_message::new_configured_channel(
+                "unknown_resolved_inputs", &node_config.channels.unknown_resolved_inputs
+            ),
             mempool_entries: Arc::new(Default::default()),
             faucet_rate_limiter: Arc::new(Mutex::new(Default::default())),
+            watcher_heartbeats: Arc::new(Mutex::new(Default::default())),
+            disk_space_low: Arc::new(AtomicCell::new(false)),
+            request_nonces: Arc::new(DashMap::new()),
+            gossip_seen_transactions: Arc::new(Mutex::new(Default::default())),
+            signature_verifier: BatchVerifier::new(node_config.tx_config.signature_verify_parallelism)
+                .unwrap_or_else(|e| {
+                    error!("Falling back to sequential signature verification: {}", e.json_or());
+                    BatchVerifier::sequential()
+                }),
+            local_submissions: Arc::new(Mutex::new(Default::default())),
+            gossip_queues: Arc::new(Mutex::new(Default::default())),
+            peer_stats: Arc::new(DashMap::new()),
+            payment_requests: Arc::new(DashMap::new()),
         }
     }
 }
@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+use metrics::counter;
+use redgold_schema::{error_message, RgResult};
+use redgold_schema::structs::{Error, PublicKey, Request};
+use crate::core::relay::Relay;
+use crate::util::current_time_millis_i64;
+
+// Signed requests older or further in the future than this are rejected, which also bounds
+// how long a nonce needs to be remembered.
+pub const REQUEST_MAX_AGE_MS: i64 = 1000 * 60 * 5;
+
+// Nonces can't be dropped early without allowing their replay, so a peer exceeding this
+// within one window is refused until older nonces expire.
+pub const MAX_NONCES_PER_PEER: usize = 10_000;
+
+// Upper bound on peers tracked at once, expired peers are swept before new ones are refused.
+pub const MAX_TRACKED_PEERS: usize = 10_000;
+
+/// Nonces recently seen from a single peer, keyed to the signed request time.
+#[derive(Clone, Debug, Default)]
+pub struct SeenNonces {
+    seen: HashMap<String, i64>,
+}
+
+impl SeenNonces {
+    /// Forgets nonces whose requests would now be rejected as stale anyway.
+    pub fn evict_expired(&mut self, now: i64) {
+        self.seen.retain(|_, t| now - *t <= REQUEST_MAX_AGE_MS);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.seen.is_empty()
+    }
+
+    pub fn check_insert(&mut self, nonce: &String, request_time: i64, now: i64) -> RgResult<()> {
+        if (now - request_time).abs() > REQUEST_MAX_AGE_MS {
+            return Err(error_message(Error::RequestReplay, format!(
+                "Stale request time {} outside of allowed window from {}", request_time, now
+            )));
+        }
+        self.evict_expired(now);
+        if self.seen.contains_key(nonce) {
+            return Err(error_message(Error::RequestReplay, format!("Duplicate request nonce {}", nonce)));
+        }
+        if self.seen.len() >= MAX_NONCES_PER_PEER {
+            return Err(error_message(Error::RequestReplay, "Too many requests within the replay window"));
+        }
+        self.seen.insert(nonce.clone(), request_time);
+        Ok(())
+    }
+}
+
+/// Rejects verified requests replaying a previously seen nonce or carrying a stale timestamp.
+/// Requests from peers predating replay protection are only let through when they don't
+/// require authentication.
+pub fn check_request_replay(relay: &Relay, pk: &PublicKey, request: &Request) -> RgResult<()> {
+    let (time, nonce) = match (request.auth_time, request.auth_nonce.as_ref()) {
+        (Some(t), Some(n)) => (t, n),
+        _ if request.auth_required() => {
+            return Err(error_message(Error::RequestReplay, "Missing auth time or nonce on authenticated request"));
+        }
+        _ => return Ok(()),
+    };
+    let now = current_time_millis_i64();
+    let nonces = &relay.request_nonces;
+    if nonces.len() >= MAX_TRACKED_PEERS && !nonces.contains_key(pk) {
+        nonces.retain(|_, seen| {
+            seen.evict_expired(now);
+            !seen.is_empty()
+        });
+        if nonces.len() >= MAX_TRACKED_PEERS {
+            counter!("redgold.peer.request_replay_rejected").increment(1);
+            return Err(error_message(Error::RequestReplay, "Too many peers tracked within the replay window"));
+        }
+    }
+    let result = nonces.entry(pk.clone()).or_default()
+        .check_insert(nonce, time, now);
+    if result.is_err() {
+        counter!("redgold.peer.request_replay_rejected").increment(1);
+    }
+    result
+}
+
+#[test]
+fn seen_nonces_rejects_duplicates_and_stale() {
+    let mut seen = SeenNonces::default();
+    let now = REQUEST_MAX_AGE_MS * 10;
+    let nonce = "a".to_string();
+    assert!(seen.check_insert(&nonce, now, now).is_ok());
+    assert!(seen.check_insert(&nonce, now, now + 1).is_err());
+    assert!(seen.check_insert(&"b".to_string(), now - REQUEST_MAX_AGE_MS - 1, now).is_err());
+    // Expired nonces are forgotten once their requests would be stale anyway
+    assert!(seen.check_insert(&"c".to_string(), now + REQUEST_MAX_AGE_MS + 1, now + REQUEST_MAX_AGE_MS + 1).is_ok());
+    assert_eq!(seen.seen.len(), 1);
+    let later = now + REQUEST_MAX_AGE_MS * 3;
+    for i in 0..MAX_NONCES_PER_PEER {
+        assert!(seen.check_insert(&i.to_string(), later, later).is_ok());
+    }
+    assert!(seen.check_insert(&"d".to_string(), later, later).is_err());
+    seen.evict_expired(later + REQUEST_MAX_AGE_MS + 1);
+    assert!(seen.is_empty());
+}
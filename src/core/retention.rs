@@ -0,0 +1,93 @@
+use async_trait::async_trait;
+use itertools::Itertools;
+use log::info;
+use metrics::counter;
+use redgold_data::data_store::DataStore;
+use redgold_data::retention::PruneSummary;
+use redgold_schema::RgResult;
+use redgold_schema::structs::Address;
+use crate::core::balance_snapshots::DAY_MILLIS;
+use crate::core::checkpoint::STATE_CHECKPOINTS;
+use crate::core::relay::Relay;
+use crate::core::stream_handlers::IntervalFold;
+use crate::multiparty::watcher::DepositWatcher;
+use crate::node_config::RetentionConfig;
+use crate::util::current_time_millis_i64;
+
+// Size based pruning never removes history newer than this
+const MIN_RETENTION_MS: i64 = DAY_MILLIS * 7;
+// Number of progressively later cutoffs tried while the datastore is over its size limit
+const SIZE_PRUNE_STEPS: i64 = 10;
+
+/// Cutoffs moving forward from the oldest record, stopping short of the minimum retention window.
+pub fn size_prune_cutoffs(oldest_time: i64, now: i64) -> Vec<i64> {
+    let newest_cutoff = now - MIN_RETENTION_MS;
+    if oldest_time >= newest_cutoff {
+        return vec![];
+    }
+    let step = ((newest_cutoff - oldest_time) / SIZE_PRUNE_STEPS).max(1);
+    (1..=SIZE_PRUNE_STEPS).map(|i| (oldest_time + step * i).min(newest_cutoff)).dedup().collect_vec()
+}
+
+/// Addresses whose full transaction history must be kept. The AMM watcher rebuilds its order
+/// state from the party address history, so a pruned fulfillment would make its deposit look
+/// unfulfilled and be paid out again.
+pub async fn protected_addresses(ds: &DataStore) -> RgResult<Vec<Address>> {
+    let cfg = DepositWatcher::get_deposit_config(ds).await?;
+    cfg.iter().flat_map(|c| c.deposit_allocations.iter()).map(|a| a.key.address()).collect()
+}
+
+/// Start of the oldest retained checkpoint's segment, transactions from then on are needed to
+/// verify checkpoints and build state proofs against them.
+pub async fn checkpointed_since(ds: &DataStore) -> RgResult<Option<i64>> {
+    let checkpoints = ds.config_store.get_typed(&STATE_CHECKPOINTS).await?.unwrap_or(vec![]);
+    Ok(checkpoints.first().map(|c| c.previous_time.unwrap_or(0)))
+}
+
+pub async fn prune_datastore(ds: &DataStore, config: &RetentionConfig, now: i64) -> RgResult<PruneSummary> {
+    let protected = protected_addresses(ds).await?;
+    let checkpointed = checkpointed_since(ds).await?;
+    let mut summary = PruneSummary::default();
+    summary.bytes_before = ds.file_size_bytes()?;
+    summary.bytes_after = summary.bytes_before;
+    if let Some(age) = config.max_age {
+        summary.add(&ds.prune_before(now - age.as_millis() as i64, &protected, checkpointed).await?);
+    }
+    if let Some(max_size) = config.max_size_bytes {
+        if let Some(oldest) = ds.oldest_transaction_time().await? {
+            for cutoff in size_prune_cutoffs(oldest, now) {
+                if summary.bytes_after <= max_size {
+                    break;
+                }
+                summary.add(&ds.prune_before(cutoff, &protected, checkpointed).await?);
+            }
+        }
+    }
+    counter!("redgold.datastore.pruned.rows").increment(summary.total_rows());
+    counter!("redgold.datastore.pruned.bytes").increment(summary.reclaimed_bytes());
+    info!("Pruned {} datastore rows, reclaimed {} bytes: {:?}", summary.total_rows(), summary.reclaimed_bytes(), summary);
+    Ok(summary)
+}
+
+/// Applies the node's retention policy on an interval
+pub struct DataRetention {
+    pub relay: Relay,
+}
+
+#[async_trait]
+impl IntervalFold for DataRetention {
+    async fn interval_fold(&mut self) -> RgResult<()> {
+        prune_datastore(&self.relay.ds, &self.relay.node_config.retention, current_time_millis_i64()).await?;
+        Ok(())
+    }
+}
+
+#[test]
+fn size_prune_cutoffs_respect_minimum_retention() {
+    let now = DAY_MILLIS * 100;
+    let cutoffs = size_prune_cutoffs(0, now);
+    assert_eq!(cutoffs.len(), SIZE_PRUNE_STEPS as usize);
+    assert_eq!(cutoffs.last().cloned(), Some(now - MIN_RETENTION_MS));
+    assert!(cutoffs.windows(2).all(|w| w[0] < w[1]));
+    assert!(size_prune_cutoffs(now - DAY_MILLIS, now).is_empty());
+}
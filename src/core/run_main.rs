@@ -8,12 +8,16 @@ use futures::StreamExt;
 use log::{error, info};
 use metrics::counter;
 
+use redgold_schema::EasyJson;
 use redgold_schema::structs::ErrorInfo;
 
 use crate::core::internal_message;
 use crate::core::relay::Relay;
+use crate::gui::deep_link::deep_link_args;
 use crate::node::Node;
 use crate::node_config::NodeConfig;
+use crate::observability::error_reports::{install_panic_hook, report_critical_error};
+use crate::observability::trace_setup::shutdown_tracing;
 use crate::util::cli::arg_parse_config;
 use crate::util::cli::arg_parse_config::ArgTranslate;
 use crate::util::cli::args::RgArgs;
@@ -36,11 +40,18 @@ pub async fn main_from_args(opts: RgArgs) {
         return;
     }
 
+    install_panic_hook(&node_config);
+
     if arg_translate.is_gui() {
         crate::gui::initialize::attempt_start(node_config.clone()).await.expect("GUI to start");
         return;
     }
 
+    if let Err(e) = node_config.env_data_folder().acquire_node_lock() {
+        error!("Node startup failure: {}", e.json_or());
+        return;
+    }
+
     let relay = Relay::new(node_config.clone()).await;
 
     Node::prelim_setup(relay.clone()).await.expect("prelim");
@@ -60,17 +71,20 @@ pub async fn main_from_args(opts: RgArgs) {
                 }
                 Err(e) => {
                     error!("Main service error: {}", crate::schema::json(&e).expect("json render of error failed?"));
+                    report_critical_error("Main service error", &e);
                     panic!("Error in sub-service in main thread");
                 }
             }
         }
         Err(e) => {
             error!("Node startup failure: {}", crate::schema::json(&e).expect("json render of error failed?"));
+            report_critical_error("Node startup failure", &e);
         }
     }
 }
 
 pub async fn main() {
-    let opts = RgArgs::parse();
+    let opts = RgArgs::parse_from(deep_link_args(env::args().collect()));
     main_from_args(opts).await;
+    shutdown_tracing();
 }
@@ -0,0 +1,212 @@
+use std::time::Duration;
+use async_trait::async_trait;
+use itertools::Itertools;
+use log::{error, info};
+use metrics::{counter, gauge};
+use serde::{Deserialize, Serialize};
+use redgold_data::config::ConfigKey;
+use redgold_data::data_store::DataStore;
+use redgold_keys::KeyPair;
+use redgold_keys::proof_support::ProofSupport;
+use redgold_schema::{error_info, ErrorInfoContext, from_hex, json_from, RgResult, SafeOption};
+use redgold_schema::structs::{Hash, NetworkEnvironment, Proof, PublicKey, Seed};
+use crate::core::relay::Relay;
+use crate::core::stream_handlers::IntervalFold;
+use crate::observability::logging::Loggable;
+use crate::util::current_time_millis_i64;
+
+// Keeps a seed list signature from doubling as a valid signature over anything else
+const SEED_LIST_PREFIX: &str = "Redgold Seed List:\n";
+// Public resolver used for dns: sources, which publish the hex encoded document as a TXT record
+const DNS_OVER_HTTPS_RESOLVER: &str = "https://dns.google/resolve";
+// Startup waits on the fetch, an unreachable source falls back to the compiled or stored seeds
+const SEED_LIST_FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Last verified seed list, used on startup when no source is reachable.
+pub const SIGNED_SEED_LIST: ConfigKey<SignedSeedList> = ConfigKey::new("signed_seed_list");
+
+/// Seed list released out of band of the binary, signed by a release key.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SignedSeedList {
+    pub network: String,
+    pub issued_time: i64,
+    pub seeds: Vec<Seed>,
+    pub proof: Option<Proof>,
+}
+
+impl SignedSeedList {
+
+    pub fn signing_hash(&self) -> RgResult<Hash> {
+        let mut unsigned = self.clone();
+        unsigned.proof = None;
+        let json = serde_json::to_string(&unsigned).error_info("Failed to serialize seed list")?;
+        Ok(Hash::from_string_calculate(&format!("{}{}", SEED_LIST_PREFIX, json)))
+    }
+
+    pub fn sign(network: &NetworkEnvironment, seeds: Vec<Seed>, issued_time: i64, keypair: &KeyPair) -> RgResult<Self> {
+        let mut list = Self {
+            network: network.to_std_string(),
+            issued_time,
+            seeds,
+            proof: None,
+        };
+        list.proof = Some(Proof::from_keypair_hash(&list.signing_hash()?, keypair));
+        Ok(list)
+    }
+
+    /// Checks the list is for this network and signed by one of the release keys.
+    pub fn verify(&self, network: &NetworkEnvironment, release_keys: &Vec<PublicKey>) -> RgResult<()> {
+        if self.network != network.to_std_string() {
+            return Err(error_info(format!("Seed list for network {} not {}", self.network, network.to_std_string())));
+        }
+        let proof = self.proof.safe_get_msg("Missing proof on seed list")?;
+        let signer = proof.public_key.safe_get_msg("Missing public key on seed list proof")?;
+        if !release_keys.contains(signer) {
+            return Err(error_info(format!("Seed list signed by unknown key {}", signer.hex_or())));
+        }
+        proof.verify(&self.signing_hash()?)
+    }
+}
+
+/// Compiled seeds followed by any fetched seeds not already present, matched by public key
+/// or, when missing, by address.
+pub fn merge_seeds(compiled: &Vec<Seed>, fetched: &Vec<Seed>) -> Vec<Seed> {
+    let mut merged = compiled.clone();
+    for s in fetched {
+        let known = merged.iter().any(|m| match (&m.public_key, &s.public_key) {
+            (Some(a), Some(b)) => a == b,
+            _ => m.external_address == s.external_address,
+        });
+        if !known {
+            merged.push(s.clone());
+        }
+    }
+    merged
+}
+
+#[derive(Deserialize)]
+struct DohAnswer {
+    data: String,
+}
+
+#[derive(Deserialize)]
+struct DohResponse {
+    #[serde(rename = "Answer", default)]
+    answer: Vec<DohAnswer>,
+}
+
+// TXT records are split into strings of at most 255 bytes, which resolvers may render quoted
+fn join_txt_strings(data: &str) -> String {
+    data.replace('"', "").split_whitespace().join("")
+}
+
+/// Fetches a seed list from an http(s) url or a `dns:<name>` TXT record.
+pub async fn fetch_seed_list(source: &String) -> RgResult<SignedSeedList> {
+    let client = reqwest::Client::builder().timeout(SEED_LIST_FETCH_TIMEOUT).build()
+        .error_info("Failed to build seed list client")?;
+    let text = if let Some(name) = source.strip_prefix("dns:") {
+        let res = client.get(DNS_OVER_HTTPS_RESOLVER)
+            .query(&[("name", name), ("type", "TXT")])
+            .send().await.error_info("Seed list DNS query failed")?
+            .json::<DohResponse>().await.error_info("Seed list DNS response parse failed")?;
+        let hex = res.answer.iter().map(|a| join_txt_strings(&a.data)).join("");
+        String::from_utf8(from_hex(hex)?).error_info("Seed list TXT record is not utf8")?
+    } else {
+        client.get(source).send().await.error_info("Seed list request failed")?
+            .text().await.error_info("Seed list response read failed")?
+    };
+    json_from(&text)
+}
+
+/// Newest valid list across the configured sources.
+pub async fn fetch_verified(
+    sources: &Vec<String>, network: &NetworkEnvironment, release_keys: &Vec<PublicKey>
+) -> Option<SignedSeedList> {
+    let mut best: Option<SignedSeedList> = None;
+    for source in sources {
+        let list = fetch_seed_list(source).await
+            .and_then(|l| l.verify(network, release_keys).map(|_| l));
+        match list {
+            Ok(l) => {
+                if best.as_ref().map(|b| l.issued_time > b.issued_time).unwrap_or(true) {
+                    best = Some(l);
+                }
+            }
+            Err(e) => {
+                counter!("redgold.seed_list.fetch_failed").increment(1);
+                error!("Seed list from {} rejected: {}", source, e.json_or());
+            }
+        }
+    }
+    best
+}
+
+/// Persists the list unless an equal or newer one is already stored.
+pub async fn store_if_newer(ds: &DataStore, list: &SignedSeedList) -> RgResult<bool> {
+    let existing = ds.config_store.get_typed(&SIGNED_SEED_LIST).await?;
+    if existing.map(|e| e.issued_time >= list.issued_time).unwrap_or(false) {
+        return Ok(false);
+    }
+    ds.config_store.set_typed(&SIGNED_SEED_LIST, list).await?;
+    Ok(true)
+}
+
+/// Latest seed list from the fetched sources or the last one persisted, verified either way.
+/// Store failures only lose the cache, the seeds compiled into the binary remain in use.
+pub async fn load_seed_list(
+    ds: &DataStore, sources: &Vec<String>, network: &NetworkEnvironment, release_keys: &Vec<PublicKey>
+) -> RgResult<Option<SignedSeedList>> {
+    let fetched = fetch_verified(sources, network, release_keys).await;
+    if let Some(l) = fetched.as_ref() {
+        store_if_newer(ds, l).await.log_error().ok();
+    }
+    let stored = ds.config_store.get_typed(&SIGNED_SEED_LIST).await.log_error().ok().flatten()
+        .filter(|l| l.verify(network, release_keys).log_error().is_ok());
+    let newest = fetched.into_iter().chain(stored.into_iter())
+        .max_by_key(|l| l.issued_time);
+    Ok(newest)
+}
+
+/// Periodically refreshes the persisted seed list so a restart finds current seeds.
+pub struct SeedListRefresh {
+    pub relay: Relay,
+}
+
+#[async_trait]
+impl IntervalFold for SeedListRefresh {
+    async fn interval_fold(&mut self) -> RgResult<()> {
+        let cfg = &self.relay.node_config.seed_list;
+        let network = &self.relay.node_config.network;
+        if let Some(l) = fetch_verified(&cfg.sources, network, &cfg.release_keys).await {
+            if store_if_newer(&self.relay.ds, &l).await? {
+                let new_seeds = merge_seeds(&self.relay.node_config.seeds, &l.seeds).len()
+                    - self.relay.node_config.seeds.len();
+                info!("Stored seed list issued at {} with {} seeds not yet in use", l.issued_time, new_seeds);
+                counter!("redgold.seed_list.updated").increment(1);
+            }
+            gauge!("redgold.seed_list.issued_time").set(l.issued_time as f64);
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn signed_seed_list_verifies_release_key() {
+    use redgold_keys::TestConstants;
+    use redgold_schema::seeds::seed;
+    let tc = TestConstants::new();
+    let kp = tc.key_pair();
+    let network = NetworkEnvironment::Dev;
+    let list = SignedSeedList::sign(&network, vec![seed("n9.example")], current_time_millis_i64(), &kp)
+        .expect("sign");
+    let release_keys = vec![kp.public_key()];
+    assert!(list.verify(&network, &release_keys).is_ok());
+    assert!(list.verify(&NetworkEnvironment::Main, &release_keys).is_err());
+    assert!(list.verify(&network, &vec![]).is_err());
+    let mut tampered = list.clone();
+    tampered.seeds.push(seed("n10.example"));
+    assert!(tampered.verify(&network, &release_keys).is_err());
+    let merged = merge_seeds(&vec![seed("n9.example")], &tampered.seeds);
+    assert_eq!(merged.len(), 2);
+    assert_eq!(join_txt_strings("\"7b22\" \"7d\""), "7b227d");
+}
@@ -0,0 +1,100 @@
+use std::time::Duration;
+use async_trait::async_trait;
+use itertools::Itertools;
+use log::info;
+use metrics::counter;
+use serde::{Deserialize, Serialize};
+use redgold_data::peer::PeerUptime;
+use redgold_schema::{error_info, RgResult};
+use redgold_schema::structs::{AboutNodeRequest, Request};
+use crate::core::relay::Relay;
+use crate::core::stream_handlers::IntervalFold;
+use crate::util::current_time_millis_i64;
+
+/// Peers not seen within this window and below the minimum uptime ratio are pruned
+const STALE_PEER_MS: i64 = 1000 * 60 * 60 * 6;
+const MIN_UPTIME_RATIO: f64 = 0.5;
+/// Minimum number of checks before a peer's uptime ratio is trusted for pruning
+const MIN_CHECKS_FOR_PRUNE: i64 = 5;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SeedQualityReport {
+    pub seed_mode: bool,
+    pub time: i64,
+    pub active_peers: i64,
+    pub average_uptime_ratio: f64,
+    pub peers: Vec<PeerUptime>,
+}
+
+/// Seed nodes only serve requests needed for peers to bootstrap, gossip and observe.
+pub fn check_seed_request(relay: &Relay, request: &Request) -> RgResult<()> {
+    if !relay.node_config.seed_mode {
+        return Ok(());
+    }
+    let non_essential = request.faucet_request.is_some()
+        || request.batch_balance_request.is_some()
+        || request.validate_transaction_request.is_some()
+        || request.recent_transaction_and_observation_request.is_some();
+    if non_essential {
+        counter!("redgold.seed.rejected_request").increment(1);
+        return Err(error_info("Request type not served by seed node"));
+    }
+    Ok(())
+}
+
+pub async fn seed_quality_report(relay: &Relay) -> RgResult<SeedQualityReport> {
+    let peers = relay.ds.peer_store.peer_uptime().await?;
+    let active_peers = relay.ds.peer_store.active_nodes(None).await?.len() as i64;
+    let average_uptime_ratio = if peers.is_empty() {
+        0.0
+    } else {
+        peers.iter().map(|p| p.uptime_ratio()).sum::<f64>() / peers.len() as f64
+    };
+    Ok(SeedQualityReport {
+        seed_mode: relay.node_config.seed_mode,
+        time: current_time_millis_i64(),
+        active_peers,
+        average_uptime_ratio,
+        peers,
+    })
+}
+
+pub struct SeedPeerScoring {
+    pub relay: Relay,
+}
+
+#[async_trait]
+impl IntervalFold for SeedPeerScoring {
+    async fn interval_fold(&mut self) -> RgResult<()> {
+        let nodes = self.relay.ds.peer_store.nodes_tx().await?
+            .iter()
+            .filter_map(|t| t.node_metadata().ok())
+            .filter_map(|n| n.public_key)
+            .collect_vec();
+        let mut req = Request::default();
+        req.about_node_request = Some(AboutNodeRequest::default());
+        let results = self.relay.broadcast_async(
+            nodes.clone(), req, Some(Duration::from_secs(10))
+        ).await?;
+        let time = current_time_millis_i64();
+        for (pk, r) in nodes.iter().zip(results.iter()) {
+            self.relay.ds.peer_store.record_peer_check(pk, r.is_ok(), time).await?;
+        }
+        let uptime = self.relay.ds.peer_store.peer_uptime().await?;
+        for pk in nodes.iter() {
+            let stats = uptime.iter().find(|u| u.public_key == pk.hex_or());
+            let last_seen = self.relay.ds.peer_store.node_last_seen(pk).await?.unwrap_or(0);
+            let stale = time - last_seen > STALE_PEER_MS;
+            let unreliable = stats
+                .map(|s| s.checks >= MIN_CHECKS_FOR_PRUNE && s.uptime_ratio() < MIN_UPTIME_RATIO)
+                .unwrap_or(false);
+            if stale && unreliable {
+                info!("Seed mode pruning stale peer {}", pk.short_id());
+                counter!("redgold.seed.pruned_peer").increment(1);
+                self.relay.ds.peer_store.remove_node(pk).await?;
+                self.relay.ds.peer_store.remove_peer_uptime(pk).await?;
+            }
+        }
+        Ok(())
+    }
+}
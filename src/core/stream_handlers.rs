@@ -10,7 +10,7 @@ use futures::future::Either;
 use futures::TryStreamExt;
 use tokio_stream::StreamExt;
 use redgold_schema::structs::{ErrorInfo, GetPeersInfoRequest};
-use tracing::error;
+use tracing::{error, info_span, Instrument};
 use redgold_schema::errors::EnhanceErrorInfo;
 use crate::observability::logging::Loggable;
 
@@ -24,18 +24,20 @@ pub async fn run_interval_fold(interval_f: impl IntervalFold + Send + 'static, i
     tokio::spawn(run_interval_inner(interval_f, interval_duration, run_at_start))
 }
 
-pub async fn run_interval_inner(
-    interval_f: impl IntervalFold, interval_duration: Duration, run_at_start: bool
+pub async fn run_interval_inner<T: IntervalFold>(
+    interval_f: T, interval_duration: Duration, run_at_start: bool
 ) -> RgResult<()> {
+    // Span per fold so exported traces are grouped by the interval implementation
+    let fold_name = std::any::type_name::<T>();
     let mut cs = interval_f;
     if run_at_start {
-        cs.interval_fold().await?;
+        cs.interval_fold().instrument(info_span!("interval_fold", fold = fold_name)).await?;
     }
     let interval1 = tokio::time::interval(interval_duration);
     IntervalStream::new(interval1)
         .map(|x| Ok(x))
         .try_fold(cs, |mut c, _| async {
-            c.interval_fold().await.map(|_| c)
+            c.interval_fold().instrument(info_span!("interval_fold", fold = fold_name)).await.map(|_| c)
         }).await.map(|_| ())
 }
 
@@ -0,0 +1,124 @@
+use metrics::counter;
+use serde::{Deserialize, Serialize};
+use redgold_schema::{error_message, ProtoSerde, RgResult};
+use redgold_schema::constants::DECIMAL_MULTIPLIER;
+use redgold_schema::structs::{Address, Error as RGError, Transaction};
+use crate::core::relay::Relay;
+use crate::node_config::FeePolicyConfig;
+
+// Size charged for the signature proof of each input in place of its actual size
+const INPUT_PROOF_SIZE_ESTIMATE: usize = 150;
+
+/// Fee requirements a node enforces, with a congestion adjusted suggestion for wallets. Amounts
+/// are in raw units and fees are paid to `fee_address` through a fee marked output.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FeeEstimate {
+    pub min_relay_fee: i64,
+    pub fee_per_kb: i64,
+    // Per kilobyte rate scaled up with the number of pending transactions
+    pub suggested_fee_per_kb: i64,
+    pub pending_transactions: usize,
+    pub fee_address: Address,
+}
+
+impl FeeEstimate {
+
+    /// Estimate without congestion from a local policy, for when the node can't provide one.
+    pub fn minimum(cfg: &FeePolicyConfig, fee_address: &Address) -> Self {
+        Self {
+            min_relay_fee: cfg.min_relay_fee,
+            fee_per_kb: cfg.fee_per_kb,
+            suggested_fee_per_kb: cfg.fee_per_kb,
+            pending_transactions: 0,
+            fee_address: fee_address.clone(),
+        }
+    }
+
+    pub fn required_fee(&self, size_bytes: usize) -> i64 {
+        required_fee(self.min_relay_fee, self.fee_per_kb, size_bytes)
+    }
+
+    pub fn suggested_fee(&self, size_bytes: usize) -> i64 {
+        required_fee(self.min_relay_fee, self.suggested_fee_per_kb, size_bytes)
+    }
+}
+
+/// The larger of the flat minimum and the size based fee, rounding partial bytes up.
+pub fn required_fee(min_relay_fee: i64, fee_per_kb: i64, size_bytes: usize) -> i64 {
+    let size_fee = (fee_per_kb.max(0) as i128 * size_bytes as i128 + 999) / 1000;
+    min_relay_fee.max(size_fee as i64)
+}
+
+/// Size fees are charged on, with every input proof counted at a fixed estimate so the builder
+/// and validation agree on the size whether or not the transaction is signed yet.
+pub fn estimated_signed_size(tx: &Transaction) -> usize {
+    let mut unsigned = tx.clone();
+    for i in unsigned.inputs.iter_mut() {
+        i.proof.clear();
+    }
+    unsigned.proto_serialize().len() + unsigned.inputs.len() * INPUT_PROOF_SIZE_ESTIMATE
+}
+
+/// Converts a fractional RDG amount from config or CLI options into raw units.
+pub fn raw_fee_amount(rdg: f64) -> i64 {
+    (rdg.max(0.0) * DECIMAL_MULTIPLIER as f64).round() as i64
+}
+
+pub fn fee_estimate(relay: &Relay) -> FeeEstimate {
+    let cfg = &relay.node_config.fee_policy;
+    let pending = relay.mempool_entries.len();
+    let congestion = 1 + pending / cfg.congestion_threshold.max(1);
+    let mut estimate = FeeEstimate::minimum(cfg, &relay.node_config.address());
+    estimate.suggested_fee_per_kb = cfg.fee_per_kb.saturating_mul(congestion as i64);
+    estimate.pending_transactions = pending;
+    estimate
+}
+
+/// Addresses a fee may be paid to, this node's, the seeds' and any recently active peer's, since
+/// wallets pay whichever node they took the fee estimate from.
+pub async fn accepted_fee_addresses(relay: &Relay) -> RgResult<Vec<Address>> {
+    let mut keys = relay.node_config.seeds_pk();
+    keys.extend(relay.node_config.seeds_now_pk().iter().flat_map(|s| s.public_key.clone()));
+    keys.extend(relay.ds.peer_store.active_nodes(None).await?);
+    let mut addresses = vec![relay.node_config.address()];
+    for pk in keys {
+        let a = pk.address()?;
+        if !addresses.contains(&a) {
+            addresses.push(a);
+        }
+    }
+    Ok(addresses)
+}
+
+/// Rejects currency transfers whose fee outputs to `fee_addresses` pay less than the node's
+/// minimum relay fee for their size. Observations and other data only transactions are exempt.
+pub fn validate_fee(tx: &Transaction, cfg: &FeePolicyConfig, fee_addresses: &[Address]) -> RgResult<()> {
+    if !tx.transfers_currency() {
+        return Ok(());
+    }
+    let size = estimated_signed_size(tx);
+    let required = required_fee(cfg.min_relay_fee, cfg.fee_per_kb, size);
+    let paid = tx.fee_amount_paid_to(fee_addresses);
+    if paid < required {
+        counter!("redgold.transaction.insufficient_fee").increment(1);
+        let mut e = error_message(
+            RGError::InsufficientFee,
+            format!("Fee of {} is below the required {} for {} bytes", paid, required, size)
+        );
+        e.with_detail("required_fee", required.to_string());
+        e.with_detail("paid_fee", paid.to_string());
+        return Err(e);
+    }
+    Ok(())
+}
+
+#[test]
+fn required_fee_uses_minimum_and_size() {
+    assert_eq!(required_fee(0, 0, 500), 0);
+    assert_eq!(required_fee(100, 0, 500), 100);
+    assert_eq!(required_fee(100, 1000, 500), 500);
+    assert_eq!(required_fee(0, 1000, 1), 1);
+    assert_eq!(required_fee(0, 1500, 1001), 1502);
+    assert_eq!(raw_fee_amount(0.01), DECIMAL_MULTIPLIER / 100);
+    assert_eq!(raw_fee_amount(-1.0), 0);
+}
@@ -0,0 +1,233 @@
+use itertools::Itertools;
+use redgold_schema::{RgResult, SafeOption, WithMetadataHashable};
+use redgold_schema::structs::{Address, AddressInfo, LiquidityRequest, LiquidityWithdrawal, NetworkEnvironment, Output, StandardContractType, StandardData, Transaction, UtxoEntry};
+use crate::core::transact::tx_builder_supports::{TransactionBuilder, TransactionBuilderSupport};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MarkedOutputKind {
+    // RDG sent to a party to be swapped for BTC
+    Swap,
+    // RDG sent to a party as stake
+    Stake,
+    // Liquidity marker left with the staker, spent to request a withdrawal
+    StakeMarker,
+    // RDG received from a party in exchange for a BTC deposit
+    SwapFulfillment,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MarkedOutputStatus {
+    // Swap not yet paid out or refunded as far as this address can see
+    AwaitingFulfillment,
+    // Swap returned in RDG, with the refund transaction hash
+    Refunded(String),
+    Staked,
+    // Marker unspent, a withdrawal can be requested with it
+    Withdrawable,
+    WithdrawalRequested,
+    // Fulfillment received, with the BTC deposit txid it pays for
+    Received(String),
+}
+
+/// A swap or stake marked output involving the wallet address, with what can be done with it next.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MarkedOutput {
+    pub kind: MarkedOutputKind,
+    pub status: MarkedOutputStatus,
+    pub tx_hash: String,
+    // Party address for swaps, stakes and stake markers, empty when not found in recent history
+    pub counterparty: String,
+    pub amount: i64,
+    pub time: i64,
+    // Set for stake markers which can still be spent
+    pub utxo: Option<UtxoEntry>,
+}
+
+fn contract_type(o: &Output) -> Option<i32> {
+    o.contract.as_ref().and_then(|c| c.standard_contract_type)
+}
+
+fn is_stake_marker(o: &Output) -> bool {
+    o.data.as_ref().and_then(|d| d.liquidity_request.as_ref())
+        .map(|l| l.deposit.is_some()).unwrap_or(false)
+}
+
+fn is_withdrawal_request(o: &Output) -> bool {
+    o.data.as_ref().and_then(|d| d.liquidity_request.as_ref())
+        .map(|l| l.withdrawal.is_some()).unwrap_or(false)
+}
+
+fn render(a: Option<&Address>) -> String {
+    a.and_then(|a| a.render_string().ok()).unwrap_or("".to_string())
+}
+
+/// Detects swap and stake outputs sent from, and swap fulfillments received by, the address in
+/// the recent transactions, plus any unspent stake markers among its UTXOs.
+pub fn marked_outputs(ai: &AddressInfo) -> Vec<MarkedOutput> {
+    let address = match ai.address.as_ref() {
+        Some(a) => a,
+        None => return vec![],
+    };
+    let txs = &ai.recent_transactions;
+    let refunds = txs.iter()
+        .filter(|t| !t.input_addresses().contains(address))
+        .flat_map(|t| t.output_external_txids().map(|id| (id.identifier.clone(), t.hash_or().hex())).collect_vec())
+        .collect_vec();
+    let withdrawal_requested = txs.iter()
+        .filter(|t| t.input_addresses().contains(address))
+        .any(|t| t.outputs.iter().any(is_withdrawal_request));
+
+    let mut res = vec![];
+    for t in txs {
+        let hash = t.hash_or().hex();
+        let time = t.time().cloned().unwrap_or(0);
+        let outgoing = t.input_addresses().contains(address);
+        for o in t.outputs.iter() {
+            let amount = o.opt_amount().unwrap_or(0);
+            let to_self = o.address.as_ref() == Some(address);
+            let kind = if outgoing && !to_self && o.is_swap() {
+                MarkedOutputKind::Swap
+            } else if outgoing && !to_self && contract_type(o) == Some(StandardContractType::Stake as i32)
+                && !is_withdrawal_request(o) {
+                MarkedOutputKind::Stake
+            } else if !outgoing && to_self && amount > 0 {
+                match o.data.as_ref().and_then(|d| d.external_transaction_id.as_ref()) {
+                    // RDG refunds reference the swap transaction rather than a BTC deposit
+                    Some(id) if !txs.iter().any(|s| s.hash_or().hex() == id.identifier) => {
+                        res.push(MarkedOutput {
+                            kind: MarkedOutputKind::SwapFulfillment,
+                            status: MarkedOutputStatus::Received(id.identifier.clone()),
+                            tx_hash: hash.clone(),
+                            counterparty: render(t.first_input_address().as_ref()),
+                            amount,
+                            time,
+                            utxo: None,
+                        });
+                    }
+                    _ => {}
+                }
+                continue;
+            } else {
+                continue;
+            };
+            let status = match kind {
+                MarkedOutputKind::Swap => refunds.iter()
+                    .find(|(swap, _)| swap == &hash)
+                    .map(|(_, refund)| MarkedOutputStatus::Refunded(refund.clone()))
+                    .unwrap_or(MarkedOutputStatus::AwaitingFulfillment),
+                _ => MarkedOutputStatus::Staked,
+            };
+            res.push(MarkedOutput {
+                kind,
+                status,
+                tx_hash: hash.clone(),
+                counterparty: render(o.address.as_ref()),
+                amount,
+                time,
+                utxo: None,
+            });
+        }
+    }
+    for u in ai.utxo_entries.iter() {
+        if u.output.as_ref().map(is_stake_marker).unwrap_or(false) {
+            let tx_hash = u.utxo_id.as_ref().and_then(|id| id.transaction_hash.as_ref())
+                .map(|h| h.hex()).unwrap_or("".to_string());
+            // The party holding the stake is the stake output alongside the marker
+            let party = txs.iter().filter(|t| t.hash_or().hex() == tx_hash)
+                .flat_map(|t| t.outputs.iter())
+                .filter(|o| contract_type(o) == Some(StandardContractType::Stake as i32))
+                .filter_map(|o| o.address.as_ref())
+                .next();
+            let status = if withdrawal_requested {
+                MarkedOutputStatus::WithdrawalRequested
+            } else {
+                MarkedOutputStatus::Withdrawable
+            };
+            res.push(MarkedOutput {
+                kind: MarkedOutputKind::StakeMarker,
+                status,
+                tx_hash,
+                counterparty: render(party),
+                amount: 0,
+                time: u.time,
+                utxo: Some(u.clone()),
+            });
+        }
+    }
+    res.into_iter().sorted_by_key(|m| -m.time).collect_vec()
+}
+
+impl TransactionBuilder {
+
+    /// Spends a stake marker into a withdrawal request to the party holding the stake, the
+    /// party returns the stake to `destination`. Fee is paid from the currency UTXOs.
+    pub fn with_stake_withdrawal(
+        &mut self, marker: &UtxoEntry, party_address: &Address, destination: &Address
+    ) -> RgResult<&mut Self> {
+        marker.output.as_ref().filter(|o| is_stake_marker(o))
+            .safe_get_msg("UTXO is not a stake marker")?;
+        self.with_unsigned_input(marker.clone())?;
+        let mut o = Output::default();
+        o.address = Some(party_address.clone());
+        let mut d = StandardData::default();
+        let mut lq = LiquidityRequest::default();
+        lq.withdrawal = Some(LiquidityWithdrawal { destination: Some(destination.clone()) });
+        d.liquidity_request = Some(lq);
+        o.data = Some(d);
+        self.transaction.outputs.push(o);
+        self.with_last_output_stake();
+        Ok(self)
+    }
+}
+
+/// Guided stake withdrawal for the wallet, no hand written contract JSON involved.
+pub fn prepare_stake_withdrawal(
+    ai: &AddressInfo, marked: &MarkedOutput, party_address: &Address, network: &NetworkEnvironment
+) -> RgResult<Transaction> {
+    let address = ai.address.safe_get_msg("Missing address on address info")?;
+    let marker = marked.utxo.safe_get_msg("Marked output has no stake marker to spend")?;
+    let currency = ai.utxo_entries.iter()
+        .filter(|u| u.opt_amount().is_some())
+        .cloned()
+        .collect_vec();
+    TransactionBuilder::new(network)
+        .with_utxos(&currency)?
+        .with_stake_withdrawal(marker, party_address, address)?
+        .build()
+}
+
+#[test]
+fn marked_outputs_detects_swaps_and_refunds() {
+    use redgold_keys::TestConstants;
+    use redgold_schema::structs::{CurrencyAmount, ExternalTransactionId, Hash, Input, Proof};
+    use redgold_keys::proof_support::ProofSupport;
+    let tc = TestConstants::new();
+    let party = tc.address_1.clone();
+    let mut input = Input::default();
+    input.proof = vec![Proof::from_keypair_hash(&Hash::from_string_calculate("swap"), &tc.key_pair())];
+    let address = input.address().expect("address");
+    let mut swap = Transaction::default();
+    swap.inputs.push(input);
+    let mut tb = TransactionBuilder::new(&NetworkEnvironment::Dev);
+    tb.with_output(&party, &CurrencyAmount::from(5000));
+    tb.with_last_output_withdrawal_swap();
+    swap.outputs = tb.transaction.outputs.clone();
+    let mut ai = AddressInfo::default();
+    ai.address = Some(address.clone());
+    ai.recent_transactions = vec![swap.clone()];
+    let found = marked_outputs(&ai);
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].kind, MarkedOutputKind::Swap);
+    assert_eq!(found[0].status, MarkedOutputStatus::AwaitingFulfillment);
+
+    let mut refund = Transaction::default();
+    let mut o = Output::new(&address, 5000);
+    o.data.as_mut().expect("data").external_transaction_id = Some(ExternalTransactionId {
+        identifier: swap.hash_or().hex()
+    });
+    refund.outputs.push(o);
+    ai.recent_transactions.push(refund.clone());
+    let found = marked_outputs(&ai);
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].status, MarkedOutputStatus::Refunded(refund.hash_or().hex()));
+}
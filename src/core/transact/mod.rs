@@ -1,3 +1,8 @@
 pub mod utxo_conflict_resolver;
 pub mod contention_conflicts;
 pub mod tx_builder_supports;
+pub mod validate;
+pub mod tx_preview;
+pub mod marked_outputs;
+pub mod fee_policy;
+pub mod name_registry;
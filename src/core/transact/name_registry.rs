@@ -0,0 +1,38 @@
+use redgold_data::data_store::DataStore;
+use redgold_data::name_store::NameRecord;
+use redgold_schema::{error_info, error_message, RgResult, WithMetadataHashable};
+use redgold_schema::name_registry::{validate_name, validate_registration, validate_registration_time};
+use redgold_schema::structs::{Error as RGError, Transaction};
+use crate::util::current_time_millis_i64;
+
+/// Checks any name registrations in the transaction against the currently stored binding of the
+/// name, so a held name can only be renewed by its owner. Expiry is judged at the node's time,
+/// the client chosen transaction time only has to be close to it.
+pub async fn validate_name_registrations(tx: &Transaction, ds: &DataStore) -> RgResult<()> {
+    let registrations = tx.name_registrations();
+    if registrations.is_empty() {
+        return Ok(());
+    }
+    if registrations.len() > 1 {
+        return Err(error_info("Only one name registration is allowed per transaction"));
+    }
+    let owner = tx.name_owner()?;
+    let now = current_time_millis_i64();
+    validate_registration_time(*tx.time()?, now)?;
+    for r in registrations {
+        let current = ds.name_store.query_name(&r.name).await?;
+        validate_registration(r, &owner, now, current.as_ref().map(|c| (&c.owner, c.expiry_time)))?;
+    }
+    Ok(())
+}
+
+/// Current binding of an unexpired name.
+pub async fn resolve_name(ds: &DataStore, name: &str) -> RgResult<NameRecord> {
+    validate_name(name)?;
+    let record = ds.name_store.query_name(name).await?;
+    match record {
+        Some(r) if r.active(current_time_millis_i64()) => Ok(r),
+        Some(_) => Err(error_message(RGError::NameUnavailable, format!("Name {} has expired", name))),
+        None => Err(error_message(RGError::NameUnavailable, format!("Name {} is not registered", name))),
+    }
+}
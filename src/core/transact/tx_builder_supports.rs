@@ -6,7 +6,9 @@ use redgold_schema::constants::{DECIMAL_MULTIPLIER, MAX_COIN_SUPPLY};
 use redgold_schema::{bytes_data, error_info, RgResult, SafeOption, structs, WithMetadataHashable};
 use redgold_schema::structs::{Address, AddressInfo, CodeExecutionContract, CurrencyAmount, ErrorInfo, ExecutorBackend, Input, LiquidityDeposit, LiquidityRange, LiquidityRequest, NetworkEnvironment, NodeMetadata, Observation, Output, OutputContract, OutputType, PeerMetadata, StandardContractType, StandardData, Transaction, TransactionData, TransactionOptions, UtxoEntry};
 use redgold_schema::transaction::amount_data;
+use strum_macros::{EnumIter, EnumString};
 use crate::api::public_api::PublicClient;
+use crate::core::transact::fee_policy::{estimated_signed_size, FeeEstimate};
 
 // Really just move the transaction builder to the main thing??
 
@@ -73,6 +75,9 @@ impl TransactionBuilderSupport for TransactionBuilder {
             ds: None,
             client: None,
             network: Some(network.clone()),
+            coin_selection: CoinSelectionStrategy::default(),
+            change_address: None,
+            fee_estimate: None,
         };
         s.with_network(&network);
         s
@@ -86,7 +91,128 @@ pub struct TransactionBuilder {
     // TODO: These can be injected as traits to get utxos.
     pub ds: Option<DataStore>,
     pub client: Option<PublicClient>,
-    pub network: Option<NetworkEnvironment>
+    pub network: Option<NetworkEnvironment>,
+    pub coin_selection: CoinSelectionStrategy,
+    // Destination for the remainder, defaults to the address of the first input
+    pub change_address: Option<Address>,
+    // When set, build adds a fee output sized from the estimate
+    pub fee_estimate: Option<FeeEstimate>,
+}
+
+/// Rebuild attempts while the fee output grows the transaction past the size it was estimated at.
+const AUTO_FEE_MAX_PASSES: usize = 4;
+
+/// Order in which loaded UTXOs are consumed as inputs when building a transaction.
+#[derive(Clone, Debug, PartialEq, EnumString, EnumIter, Default)]
+pub enum CoinSelectionStrategy {
+    /// Spend the smallest UTXOs first, compacting dust (original behavior)
+    #[default]
+    SmallestFirst,
+    /// Spend the largest UTXOs first, minimizing the number of inputs
+    LargestFirst,
+    /// Spend the oldest UTXOs first by creation time
+    OldestFirst,
+    /// Search for the input set producing the least change, falling back to largest first
+    BranchAndBound,
+}
+
+/// Upper bound on search steps for branch and bound before accepting the best found so far.
+const BRANCH_AND_BOUND_MAX_TRIES: usize = 100_000;
+
+impl CoinSelectionStrategy {
+
+    /// Select UTXOs covering at least `target`, returns None if total funds are insufficient.
+    pub fn select(&self, utxos: &Vec<UtxoEntry>, target: i64) -> Option<Vec<UtxoEntry>> {
+        let mut sorted = utxos.clone();
+        match self {
+            CoinSelectionStrategy::SmallestFirst => sorted.sort_by(|a, b| a.amount().cmp(&b.amount())),
+            CoinSelectionStrategy::LargestFirst => sorted.sort_by(|a, b| b.amount().cmp(&a.amount())),
+            CoinSelectionStrategy::OldestFirst => sorted.sort_by(|a, b| a.time.cmp(&b.time)),
+            CoinSelectionStrategy::BranchAndBound => {
+                return Self::branch_and_bound(utxos, target)
+                    .or_else(|| CoinSelectionStrategy::LargestFirst.select(utxos, target));
+            }
+        }
+        let mut selected = vec![];
+        let mut total: i64 = 0;
+        for u in sorted {
+            if total >= target && !selected.is_empty() {
+                break
+            }
+            total += u.amount() as i64;
+            selected.push(u);
+        }
+        if total < target {
+            return None;
+        }
+        Some(selected)
+    }
+
+    /// Depth first search over UTXOs sorted descending, minimizing excess above the target.
+    fn branch_and_bound(utxos: &Vec<UtxoEntry>, target: i64) -> Option<Vec<UtxoEntry>> {
+        let mut sorted = utxos.clone();
+        sorted.sort_by(|a, b| b.amount().cmp(&a.amount()));
+        let amounts = sorted.iter().map(|u| u.amount() as i64).collect::<Vec<i64>>();
+        // remaining[i] is the sum of all amounts from index i onwards
+        let mut remaining = vec![0i64; amounts.len() + 1];
+        for i in (0..amounts.len()).rev() {
+            remaining[i] = remaining[i + 1] + amounts[i];
+        }
+        if remaining[0] < target {
+            return None;
+        }
+        let mut search = BranchAndBoundSearch {
+            amounts,
+            remaining,
+            target,
+            current: vec![],
+            best: None,
+            tries: 0,
+        };
+        search.step(0, 0);
+        search.best.map(|(_, idx)| idx.iter().map(|i| sorted[*i].clone()).collect())
+    }
+}
+
+struct BranchAndBoundSearch {
+    amounts: Vec<i64>,
+    remaining: Vec<i64>,
+    target: i64,
+    current: Vec<usize>,
+    best: Option<(i64, Vec<usize>)>,
+    tries: usize,
+}
+
+impl BranchAndBoundSearch {
+    fn step(&mut self, index: usize, total: i64) {
+        self.tries += 1;
+        if self.tries > BRANCH_AND_BOUND_MAX_TRIES {
+            return;
+        }
+        let best_excess = self.best.as_ref().map(|(b, _)| *b);
+        if best_excess == Some(0) {
+            return;
+        }
+        if total >= self.target && !self.current.is_empty() {
+            let excess = total - self.target;
+            if best_excess.map(|b| excess < b).unwrap_or(true) {
+                self.best = Some((excess, self.current.clone()));
+            }
+            return;
+        }
+        if index >= self.amounts.len() || total + self.remaining[index] < self.target {
+            return;
+        }
+        let with_amount = total + self.amounts[index];
+        // Including this UTXO can't beat the best excess found, smaller ones still might
+        let prune = with_amount >= self.target && best_excess.map(|b| with_amount - self.target >= b).unwrap_or(false);
+        if !prune {
+            self.current.push(index);
+            self.step(index + 1, with_amount);
+            self.current.pop();
+        }
+        self.step(index + 1, total);
+    }
 }
 
 
@@ -102,6 +228,23 @@ impl TransactionBuilder {
         self
     }
 
+    pub fn with_coin_selection(&mut self, strategy: &CoinSelectionStrategy) -> &mut Self {
+        self.coin_selection = strategy.clone();
+        self
+    }
+
+    pub fn with_change_address(&mut self, address: &Address) -> &mut Self {
+        self.change_address = Some(address.clone());
+        self
+    }
+
+    /// Calculates the fee on build from the node's estimate, paying it to the node's fee address.
+    /// Use `with_fee` instead to set the fee explicitly.
+    pub fn with_auto_fee(&mut self, estimate: &FeeEstimate) -> &mut Self {
+        self.fee_estimate = Some(estimate.clone());
+        self
+    }
+
     pub fn with_network(&mut self, network: &NetworkEnvironment) -> &mut Self {
         self.network = Some(network.clone());
         let mut options = self.transaction.options.clone().unwrap_or(TransactionOptions::default());
@@ -222,6 +365,15 @@ impl TransactionBuilder {
 
 
 
+    /// Binds `name` to `address` until `expiry_time`, owned by the signer of the first input.
+    pub fn with_name_registration(&mut self, name: impl Into<String>, address: &Address, expiry_time: i64) -> &mut Self {
+        let mut o = Output::default();
+        o.address = Some(address.clone());
+        o.data = Some(StandardData::name_registration(name, address, expiry_time));
+        self.transaction.outputs.push(o);
+        self
+    }
+
     pub fn with_contract_request_output(&mut self,
                                         destination: &Address,
                                         serialized_request: &Vec<u8>
@@ -331,16 +483,39 @@ impl TransactionBuilder {
     }
 
     pub fn build(&mut self) -> Result<Transaction, ErrorInfo> {
+        let estimate = match self.fee_estimate.clone() {
+            None => return self.select_inputs_and_change(),
+            Some(e) => e,
+        };
+        let base = self.transaction.clone();
+        let base_used = self.used_utxos.clone();
+        let mut fee = estimate.min_relay_fee;
+        for _ in 0..AUTO_FEE_MAX_PASSES {
+            self.transaction = base.clone();
+            self.used_utxos = base_used.clone();
+            if fee > 0 {
+                self.with_fee(&estimate.fee_address, &CurrencyAmount::from(fee))?;
+            }
+            let tx = self.select_inputs_and_change()?;
+            let required = estimate.suggested_fee(estimated_signed_size(&tx));
+            if required <= fee {
+                return Ok(tx);
+            }
+            fee = required;
+        }
+        Err(error_info("Fee estimate did not converge"))
+    }
 
-        // TODO: Should we sort the other way instead? To compress as many UTXOs as possible?
-        // In order for that we'd need to raise transaction size limit
-        self.utxos.sort_by(|a, b| a.amount().cmp(&b.amount()));
+    fn select_inputs_and_change(&mut self) -> Result<Transaction, ErrorInfo> {
 
-        for u in self.utxos.clone() {
+        // Transaction size limit constrains how many UTXOs can be compressed at once, the
+        // strategy decides which subset is consumed.
+        let target = -self.balance();
+        let selected = self.coin_selection.select(&self.utxos, target)
+            .ok_or(ErrorInfo::error_info("Insufficient funds"))?;
+
+        for u in selected {
             self.with_unsigned_input(u.clone())?;
-            if self.balance() > 0 {
-                break
-            }
         }
 
         if self.balance() < 0 {
@@ -355,6 +530,44 @@ impl TransactionBuilder {
         // self.balance
     }
 
+    /// Consumes every loaded UTXO as an input and sends the entire balance to one destination,
+    /// used for sweeping all funds controlled by a key.
+    pub fn build_sweep(&mut self, destination: &Address) -> Result<Transaction, ErrorInfo> {
+        for u in self.utxos.clone() {
+            self.with_unsigned_input(u.clone())?;
+        }
+        let total = self.balance();
+        if total <= 0 {
+            return Err(ErrorInfo::error_info("No funds available to sweep"));
+        }
+        let estimate = match self.fee_estimate.clone() {
+            None => {
+                self.with_output(destination, &CurrencyAmount::from(total));
+                return Ok(self.transaction.clone());
+            }
+            Some(e) => e,
+        };
+        // The fee comes out of the swept amount rather than from additional inputs
+        let base = self.transaction.clone();
+        let mut fee = estimate.min_relay_fee;
+        for _ in 0..AUTO_FEE_MAX_PASSES {
+            if fee >= total {
+                return Err(ErrorInfo::error_info("Swept amount does not cover the fee"));
+            }
+            self.transaction = base.clone();
+            self.with_output(destination, &CurrencyAmount::from(total - fee));
+            if fee > 0 {
+                self.with_fee(&estimate.fee_address, &CurrencyAmount::from(fee))?;
+            }
+            let required = estimate.suggested_fee(estimated_signed_size(&self.transaction));
+            if required <= fee {
+                return Ok(self.transaction.clone());
+            }
+            fee = required;
+        }
+        Err(error_info("Fee estimate did not converge"))
+    }
+
     pub fn with_output_peer_data(&mut self, destination: &Address, pd: PeerMetadata, height: i64) -> &mut Self {
         let mut option = StandardData::peer_data(pd).expect("o");
         option.height = Some(height);
@@ -402,7 +615,7 @@ impl TransactionBuilder {
     }
 
     pub fn with_remainder(&mut self) -> &mut Self {
-        let address = self
+        let address = self.change_address.clone().unwrap_or_else(|| self
             .transaction.inputs.get(0)
             .expect("missing head")
             .output
@@ -411,7 +624,7 @@ impl TransactionBuilder {
             .address
             .as_ref()
             .expect("address")
-            .clone();
+            .clone());
 
         let output = Output::new(&address, self.balance());
         self.transaction.outputs.push(output);
@@ -419,3 +632,95 @@ impl TransactionBuilder {
     }
 
 }
+
+
+#[cfg(test)]
+mod tests {
+    use redgold_keys::TestConstants;
+    use redgold_schema::structs::{Hash, NetworkEnvironment, UtxoEntry};
+    use redgold_schema::structs::{CurrencyAmount, Output};
+    use crate::core::transact::tx_builder_supports::{CoinSelectionStrategy, TransactionBuilder, TransactionBuilderSupport};
+    use crate::core::transact::fee_policy::{estimated_signed_size, FeeEstimate};
+    use redgold_schema::structs::OutputType;
+
+    fn fixture_utxos(amounts_times: Vec<(i64, i64)>) -> Vec<UtxoEntry> {
+        let address = TestConstants::new().address_1;
+        amounts_times.iter().enumerate().map(|(i, (amount, time))| {
+            Output::new(&address, *amount)
+                .utxo_entry(&Hash::from_string_calculate(&i.to_string()), 0, *time)
+        }).collect()
+    }
+
+    fn amounts(utxos: &Vec<UtxoEntry>) -> Vec<u64> {
+        let mut a = utxos.iter().map(|u| u.amount()).collect::<Vec<u64>>();
+        a.sort();
+        a
+    }
+
+    #[test]
+    fn coin_selection_strategies() {
+        let utxos = fixture_utxos(vec![(10, 5), (50, 1), (30, 3), (20, 4), (70, 2)]);
+        let smallest = CoinSelectionStrategy::SmallestFirst.select(&utxos, 35).expect("select");
+        assert_eq!(amounts(&smallest), vec![10, 20, 30]);
+        let largest = CoinSelectionStrategy::LargestFirst.select(&utxos, 35).expect("select");
+        assert_eq!(amounts(&largest), vec![70]);
+        let oldest = CoinSelectionStrategy::OldestFirst.select(&utxos, 35).expect("select");
+        assert_eq!(amounts(&oldest), vec![50]);
+        // Exact match with no change available
+        let bnb = CoinSelectionStrategy::BranchAndBound.select(&utxos, 60).expect("select");
+        assert_eq!(amounts(&bnb).iter().sum::<u64>(), 60);
+        let bnb = CoinSelectionStrategy::BranchAndBound.select(&utxos, 75).expect("select");
+        assert_eq!(amounts(&bnb).iter().sum::<u64>(), 80);
+        for s in vec![
+            CoinSelectionStrategy::SmallestFirst, CoinSelectionStrategy::LargestFirst,
+            CoinSelectionStrategy::OldestFirst, CoinSelectionStrategy::BranchAndBound
+        ] {
+            assert!(s.select(&utxos, 181).is_none());
+            assert_eq!(amounts(&s.select(&utxos, 180).expect("all")).len(), 5);
+        }
+    }
+
+    #[test]
+    fn build_with_coin_selection() {
+        let tc = TestConstants::new();
+        let utxos = fixture_utxos(vec![(10, 5), (50, 1), (30, 3), (20, 4), (70, 2)]);
+        let tx = TransactionBuilder::new(&NetworkEnvironment::Debug)
+            .with_utxos(&utxos).expect("utxos")
+            .with_coin_selection(&CoinSelectionStrategy::BranchAndBound)
+            .with_output(&tc.address_1, &CurrencyAmount::from(60))
+            .build().expect("build");
+        assert_eq!(tx.total_input_amount(), tx.total_output_amount());
+        // Exact match produces no change output
+        assert_eq!(tx.outputs.len(), 1);
+    }
+
+    #[test]
+    fn build_with_auto_fee() {
+        let tc = TestConstants::new();
+        let utxos = fixture_utxos(vec![(1_000_000, 1), (2_000_000, 2)]);
+        let estimate = FeeEstimate {
+            min_relay_fee: 100,
+            fee_per_kb: 1000,
+            suggested_fee_per_kb: 2000,
+            pending_transactions: 0,
+            fee_address: tc.addr2.clone().into(),
+        };
+        let tx = TransactionBuilder::new(&NetworkEnvironment::Debug)
+            .with_utxos(&utxos).expect("utxos")
+            .with_output(&tc.address_1, &CurrencyAmount::from(1_500_000))
+            .with_auto_fee(&estimate)
+            .build().expect("build");
+        assert_eq!(tx.total_input_amount(), tx.total_output_amount());
+        assert!(tx.fee_amount() >= estimate.suggested_fee(estimated_signed_size(&tx)));
+        assert_eq!(tx.outputs.iter().filter(|o| o.output_type == Some(OutputType::Fee as i32)).count(), 1);
+        assert_eq!(tx.fee_amount_paid_to(&[estimate.fee_address.clone()]), tx.fee_amount());
+        assert_eq!(tx.fee_amount_paid_to(&[tc.address_1.clone()]), 0);
+
+        let sweep = TransactionBuilder::new(&NetworkEnvironment::Debug)
+            .with_utxos(&utxos).expect("utxos")
+            .with_auto_fee(&estimate)
+            .build_sweep(&tc.address_1).expect("sweep");
+        assert_eq!(sweep.total_input_amount(), sweep.total_output_amount());
+        assert!(sweep.fee_amount() >= estimate.suggested_fee(estimated_signed_size(&sweep)));
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+use itertools::Itertools;
+use redgold_schema::structs::{OutputType, StandardContractType, SupportedCurrency, Transaction};
+use redgold_schema::transaction::rounded_balance_i64;
+
+// Outputs below this many satoshis cost more to spend than they're worth.
+const DUST_OUTPUT_SATS: i64 = 546;
+// Fees above this fraction of the amount sent to other addresses are flagged.
+const LARGE_FEE_FRACTION: f64 = 0.05;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PreviewEntry {
+    pub address: String,
+    // Satoshis for both currencies
+    pub amount: i64,
+    pub marker: Option<String>,
+}
+
+/// Human readable breakdown of a prepared transaction, shown before signing.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TransactionPreview {
+    pub currency: SupportedCurrency,
+    pub inputs: Vec<PreviewEntry>,
+    pub outputs: Vec<PreviewEntry>,
+    pub fee: i64,
+    // Net change in balance of every address involved, sorted by address
+    pub net_changes: Vec<(String, i64)>,
+    pub warnings: Vec<String>,
+}
+
+impl TransactionPreview {
+
+    pub fn from_transaction(tx: &Transaction) -> Self {
+        let inputs = tx.inputs.iter().filter_map(|i| i.output.as_ref()).map(|o| PreviewEntry {
+            address: o.address.as_ref().and_then(|a| a.render_string().ok()).unwrap_or("".to_string()),
+            amount: o.opt_amount().unwrap_or(0),
+            marker: None,
+        }).collect_vec();
+        let mut explicit_fee = 0;
+        let outputs = tx.outputs.iter().filter_map(|o| {
+            let amount = o.opt_amount()?;
+            let is_fee = o.output_type == Some(OutputType::Fee as i32);
+            if is_fee {
+                explicit_fee += amount;
+            }
+            let contract_type = o.contract.as_ref().and_then(|c| c.standard_contract_type);
+            let marker = if is_fee {
+                Some("fee".to_string())
+            } else if o.is_swap() {
+                Some("swap".to_string())
+            } else if contract_type == Some(StandardContractType::Stake as i32) {
+                Some("stake".to_string())
+            } else {
+                None
+            };
+            Some(PreviewEntry {
+                address: o.address.as_ref().and_then(|a| a.render_string().ok()).unwrap_or("".to_string()),
+                amount,
+                marker,
+            })
+        }).collect_vec();
+        // Anything unaccounted for by outputs is implicitly paid as a fee
+        let implicit_fee = (tx.total_input_amount() - tx.total_output_amount()).max(0);
+        Self::new(SupportedCurrency::Redgold, inputs, outputs, explicit_fee + implicit_fee)
+    }
+
+    pub fn from_btc(inputs: Vec<(String, u64)>, outputs: Vec<(String, u64)>, fee: u64) -> Self {
+        let entries = |v: Vec<(String, u64)>| v.into_iter()
+            .map(|(address, amount)| PreviewEntry { address, amount: amount as i64, marker: None })
+            .collect_vec();
+        Self::new(SupportedCurrency::Bitcoin, entries(inputs), entries(outputs), fee as i64)
+    }
+
+    fn new(currency: SupportedCurrency, inputs: Vec<PreviewEntry>, outputs: Vec<PreviewEntry>, fee: i64) -> Self {
+        let mut net: HashMap<String, i64> = HashMap::new();
+        for i in inputs.iter() {
+            *net.entry(i.address.clone()).or_insert(0) -= i.amount;
+        }
+        for o in outputs.iter().filter(|o| o.marker.as_deref() != Some("fee")) {
+            *net.entry(o.address.clone()).or_insert(0) += o.amount;
+        }
+        let net_changes = net.into_iter().filter(|(_, v)| *v != 0).sorted().collect_vec();
+
+        let mut warnings = vec![];
+        let input_addresses = inputs.iter().map(|i| i.address.clone()).collect_vec();
+        let sent: i64 = outputs.iter()
+            .filter(|o| o.marker.as_deref() != Some("fee"))
+            .filter(|o| !input_addresses.contains(&o.address))
+            .map(|o| o.amount)
+            .sum();
+        if sent > 0 && (fee as f64) > (sent as f64) * LARGE_FEE_FRACTION {
+            warnings.push(format!(
+                "Fee of {} is over {}% of the amount sent", rounded_balance_i64(fee), (LARGE_FEE_FRACTION * 100.0) as i64
+            ));
+        }
+        for o in outputs.iter().filter(|o| o.amount < DUST_OUTPUT_SATS && o.marker.is_none()) {
+            warnings.push(format!("Dust output of {} satoshis to {}", o.amount, o.address));
+        }
+        if inputs.is_empty() {
+            warnings.push("Transaction has no inputs".to_string());
+        }
+        Self { currency, inputs, outputs, fee, net_changes, warnings }
+    }
+}
+
+#[test]
+fn preview_btc_fee_and_dust_warnings() {
+    let preview = TransactionPreview::from_btc(
+        vec![("a".to_string(), 100_000)],
+        vec![("b".to_string(), 10_000), ("b2".to_string(), 100), ("a".to_string(), 80_000)],
+        9_900
+    );
+    assert_eq!(preview.net_changes, vec![
+        ("a".to_string(), -20_000), ("b".to_string(), 10_000), ("b2".to_string(), 100)
+    ]);
+    assert_eq!(preview.warnings.len(), 2);
+    let clean = TransactionPreview::from_btc(
+        vec![("a".to_string(), 100_000)], vec![("b".to_string(), 99_000)], 1_000
+    );
+    assert!(clean.warnings.is_empty());
+}
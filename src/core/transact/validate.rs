@@ -0,0 +1,88 @@
+use redgold_keys::transaction_support::TransactionSupport;
+use redgold_schema::{error_info, RgResult, WithMetadataHashable};
+use redgold_schema::structs::{ErrorInfo, Transaction, TransactionValidationIssue, ValidateTransactionResponse};
+use crate::core::relay::Relay;
+use crate::core::resolver::resolve_transaction;
+use crate::core::transact::fee_policy::{accepted_fee_addresses, validate_fee};
+use crate::core::transact::name_registry::validate_name_registrations;
+
+fn issue(stage: &str, error: ErrorInfo) -> TransactionValidationIssue {
+    TransactionValidationIssue {
+        stage: stage.to_string(),
+        error: Some(error),
+    }
+}
+
+fn validate_structure(tx: &Transaction, relay: &Relay) -> RgResult<()> {
+    tx.prevalidate_structure()?;
+    tx.validate_network(&relay.node_config.network)
+}
+
+fn validate_amounts(tx: &Transaction) -> RgResult<()> {
+    for (i, o) in tx.outputs.iter().enumerate() {
+        if o.opt_amount().map(|a| a < 0).unwrap_or(false) {
+            return Err(error_info(format!("Negative amount on output {}", i)));
+        }
+    }
+    Ok(())
+}
+
+/// Runs the same validation as transaction processing without submitting, collecting an issue
+/// per failed stage rather than stopping at the first failure where stages are independent.
+pub async fn dry_run_validate(tx: &Transaction, relay: &Relay) -> ValidateTransactionResponse {
+    let mut issues = vec![];
+    let hash = tx.hash_or();
+
+    match relay.ds.transaction_store.query_maybe_transaction(&hash).await {
+        Ok(Some((_, Some(rejection)))) => issues.push(issue("previously_rejected", rejection)),
+        Ok(Some((_, None))) => issues.push(issue("duplicate", error_info("Transaction already accepted"))),
+        Ok(None) => {}
+        Err(e) => issues.push(issue("lookup", e)),
+    }
+
+    let structure_valid = validate_structure(tx, relay).map_err(|e| issues.push(issue("structure", e))).is_ok();
+
+    let signature_results = relay.signature_verifier.verify_inputs(tx);
+    for (i, result) in signature_results.into_iter().enumerate() {
+        if let Err(e) = result {
+            let mut e = e;
+            e.with_detail("input_index", i.to_string());
+            issues.push(issue("signatures", e));
+        }
+    }
+
+    if let Err(e) = validate_amounts(tx) {
+        issues.push(issue("amounts", e));
+    }
+
+    match accepted_fee_addresses(relay).await {
+        Ok(fee_addresses) => {
+            if let Err(e) = validate_fee(tx, &relay.node_config.fee_policy, &fee_addresses) {
+                issues.push(issue("fee", e));
+            }
+        }
+        Err(e) => issues.push(issue("fee", e)),
+    }
+
+    if let Err(e) = validate_name_registrations(tx, &relay.ds).await {
+        issues.push(issue("name", e));
+    }
+
+    // Resolution queries peers and the local store, so only attempt it on well formed transactions
+    if structure_valid {
+        match resolve_transaction(tx, relay.clone()).await {
+            Ok(resolved) => {
+                if let Err(e) = resolved.validate() {
+                    issues.push(issue("amounts", e));
+                }
+            }
+            Err(e) => issues.push(issue("utxo", e)),
+        }
+    }
+
+    ValidateTransactionResponse {
+        valid: issues.is_empty(),
+        transaction_hash: Some(hash),
+        issues,
+    }
+}
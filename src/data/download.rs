@@ -34,7 +34,7 @@ pub async fn download_msg(
     start_time: i64,
     end_time: i64,
     data_type: DownloadDataType,
-    key: structs::PublicKey,
+    peers: &Vec<structs::PublicKey>,
 ) -> RgResult<DownloadResponse> {
 
     let mut request = Request::empty();
@@ -44,9 +44,8 @@ pub async fn download_msg(
             data_type: data_type as i32,
             offset: None,
     });
-    // TODO: Handle retries to other peers
-    let response = relay.send_message_sync(request, key, None).await?;
-    response.as_error_info()?;
+    // Retried and hedged across peers in order, the first being the preferred source
+    let response = relay.send_hedged(request, peers.clone()).await?;
     response.download_response.ok_msg("Missing download response")
 }
 
@@ -54,7 +53,7 @@ pub async fn download_all(
     relay: &Relay,
     start_time: i64,
     end_time: i64,
-    key: &structs::PublicKey,
+    peers: &Vec<structs::PublicKey>,
 ) -> Result<bool, ErrorInfo> {
 
     let mut got_data = false;
@@ -64,7 +63,7 @@ pub async fn download_all(
         start_time,
         end_time,
         DownloadDataType::UtxoEntry,
-        key.clone(),
+        peers,
     ).await.log_error().with_err_count("redgold.download.utxo_error") {
         // TODO: Change this to include peer observations as well to determine if it's sufficient to accept.
         let utxo_entries = dr.utxo_entries;
@@ -84,7 +83,7 @@ pub async fn download_all(
         start_time,
         end_time,
         DownloadDataType::TransactionEntry,
-        key.clone(),
+        peers,
     ).await.log_error().with_err_count("redgold.download.transaction_error") {
         // TODO: Change this to include peer observations as well to determine if it's sufficient to accept.
         let txs = dr.transactions;
@@ -108,7 +107,7 @@ pub async fn download_all(
         start_time,
         end_time,
         DownloadDataType::ObservationEntry,
-        key.clone(),
+        peers,
     ).await.log_error().with_err_count("redgold.download.observation_error") {
         // TODO: Change this to include peer observations as well to determine if it's sufficient to accept.
         let obes = dr.observations;
@@ -127,7 +126,7 @@ pub async fn download_all(
         start_time,
         end_time,
         DownloadDataType::ObservationEdgeEntry,
-        key.clone(),
+        peers,
     ).await.log_error().with_err_count("redgold.download.observation_edge_error") {
         // TODO: Change this to include peer observations as well to determine if it's sufficient to accept.
         let obes = dr.observation_edges;
@@ -191,12 +190,10 @@ pub async fn download(relay: Relay, bootstrap_pks: Vec<structs::PublicKey>) -> R
     // Time slice by days backwards.
     let mut no_data_count = 0;
 
-    // TODO: Not this, also a maximum earliest lookback period.
-    let bootstrap = bootstrap_pks.get(0).expect("bootstrap").clone();
-
-    if let Some(g_time) = download_genesis(&relay, bootstrap_pks).await? {
+    // TODO: Maximum earliest lookback period.
+    if let Some(g_time) = download_genesis(&relay, bootstrap_pks.clone()).await? {
         // Workaround to get genesis currently valid UTXOs
-        download_all(&relay, g_time - 1, g_time + 1, &bootstrap).await?;
+        download_all(&relay, g_time - 1, g_time + 1, &bootstrap_pks).await?;
     }
 
     let mut cur_end = start_time;
@@ -206,7 +203,7 @@ pub async fn download(relay: Relay, bootstrap_pks: Vec<structs::PublicKey>) -> R
     while no_data_count < 3 && cur_end > min_time{
         let prev_day = cur_end - 1000 * 60 * 60 * 24;
 
-        let got_data = download_all(&relay, prev_day, cur_end, &bootstrap).await?;
+        let got_data = download_all(&relay, prev_day, cur_end, &bootstrap_pks).await?;
 
         if got_data {
             no_data_count = 0;
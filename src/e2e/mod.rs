@@ -18,6 +18,7 @@ use redgold_keys::transaction_support::TransactionSupport;
 pub mod tx_gen;
 pub mod tx_submit;
 pub mod alert;
+pub mod swap_round_trip;
 use redgold_schema::EasyJson;
 use redgold_schema::transaction::amount_to_raw_amount;
 use crate::core::transact::tx_builder_supports::TransactionBuilder;
@@ -138,7 +139,7 @@ impl LiveE2E {
     pub async fn build_tx(&self) -> RgResult<Option<Transaction>> {
 
         let mut map: HashMap<Address, KeyPair> = HashMap::new();
-        if !self.relay.node_config.network.is_main() {
+        if self.relay.node_config.features.e2e_test_keys {
             let min_offset = 20;
             let max_offset = 30;
             for i in min_offset..max_offset {
@@ -0,0 +1,204 @@
+use std::time::Duration;
+use log::info;
+use redgold_keys::address_external::ToBitcoinAddress;
+use redgold_keys::KeyPair;
+use redgold_keys::transaction_support::TransactionSupport;
+use redgold_keys::util::btc_wallet::SingleKeyBitcoinWallet;
+use redgold_schema::{error_info, RgResult, SafeOption, WithMetadataHashable};
+use redgold_schema::structs::{CurrencyAmount, NetworkEnvironment, PartyInfo, PublicKey, SupportedCurrency, Transaction};
+use crate::api::public_api::PublicClient;
+use crate::core::transact::tx_builder_supports::{TransactionBuilder, TransactionBuilderSupport};
+use crate::multiparty::party_stream::RecentFill;
+use crate::util;
+
+// End to end scenario exercising both directions of the bridge against a live party,
+// BTC deposit -> RDG fulfillment followed by RDG withdrawal -> BTC fulfillment.
+
+#[derive(Clone, Debug)]
+pub struct SwapRoundTripConfig {
+    pub network: NetworkEnvironment,
+    pub party_public_key: PublicKey,
+    pub btc_deposit_sats: u64,
+    pub rdg_withdrawal_amount: f64,
+    // Optional RDG sent to the party address up front so it can fulfill the deposit
+    pub party_funding_amount: Option<f64>,
+    pub timeout: Duration,
+    pub poll_interval: Duration,
+}
+
+impl SwapRoundTripConfig {
+    pub fn new(network: NetworkEnvironment, party_public_key: PublicKey) -> Self {
+        Self {
+            network,
+            party_public_key,
+            btc_deposit_sats: 40000,
+            rdg_withdrawal_amount: 1.0,
+            party_funding_amount: None,
+            timeout: Duration::from_secs(60 * 30),
+            poll_interval: Duration::from_secs(30),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SwapRoundTripResult {
+    pub btc_deposit_txid: String,
+    pub rdg_fulfillment: Transaction,
+    pub rdg_withdrawal: Transaction,
+    pub btc_fulfillment_txid: String,
+    pub btc_fulfillment_amount: u64,
+    pub starting_rdg_balance: i64,
+    pub ending_rdg_balance: i64,
+    pub starting_btc_balance: u64,
+    pub ending_btc_balance: u64,
+    pub party_info: PartyInfo,
+    // Fills the party recorded during the round trip, read back from its stored order book state
+    pub new_fills: Vec<RecentFill>,
+}
+
+async fn poll_until<T, F, Fut>(config: &SwapRoundTripConfig, description: &str, f: F) -> RgResult<T>
+    where F: Fn() -> Fut, Fut: std::future::Future<Output = RgResult<Option<T>>> {
+    let start = util::current_time_millis_i64();
+    loop {
+        if let Some(t) = f().await? {
+            return Ok(t);
+        }
+        if util::current_time_millis_i64() - start > config.timeout.as_millis() as i64 {
+            return Err(error_info(format!("Timed out waiting for {}", description)));
+        }
+        info!("Waiting for {}", description);
+        tokio::time::sleep(config.poll_interval).await;
+    }
+}
+
+async fn recent_fills(client: &PublicClient) -> RgResult<Vec<RecentFill>> {
+    Ok(client.order_book().await?.map(|b| b.recent_fills).unwrap_or_default())
+}
+
+fn recorded_both_legs(fills: &Vec<RecentFill>, withdrawal_amount: i64) -> bool {
+    fills.iter().any(|f| f.is_ask) &&
+        fills.iter().any(|f| !f.is_ask && f.rdg_amount as i64 == withdrawal_amount)
+}
+
+async fn send_rdg(client: &PublicClient, kp: &KeyPair, network: &NetworkEnvironment,
+                  destination: &redgold_schema::structs::Address, amount: f64, swap: bool) -> RgResult<Transaction> {
+    let address = kp.address_typed();
+    let ai = client.address_info(address).await?;
+    let mut tb = TransactionBuilder::new(network);
+    tb.with_utxos(&ai.utxo_entries)?
+        .with_output(destination, &CurrencyAmount::from_fractional(amount)?);
+    if swap {
+        tb.with_last_output_withdrawal_swap();
+    }
+    let tx = tb.build()?.sign(kp)?;
+    client.send_transaction(&tx, true).await?;
+    Ok(tx)
+}
+
+pub async fn run_swap_round_trip(
+    client: &PublicClient,
+    kp: &KeyPair,
+    private_key_hex: String,
+    config: &SwapRoundTripConfig
+) -> RgResult<SwapRoundTripResult> {
+    let network = config.network.clone();
+    let rdg_address = kp.address_typed();
+    let party_address = config.party_public_key.address()?;
+    let party_btc_address = config.party_public_key.to_bitcoin_address(&network)?;
+    let user_btc_address = kp.public_key().to_bitcoin_address(&network)?;
+
+    let party_info = client.parties_info().await?.into_iter()
+        .find(|p| p.party_id.as_ref().and_then(|p| p.public_key.as_ref()) == Some(&config.party_public_key))
+        .ok_or(error_info("Party not found in parties info response"))?;
+
+    client.faucet(&rdg_address).await?;
+    if let Some(amount) = config.party_funding_amount {
+        send_rdg(client, kp, &network, &party_address, amount, false).await?;
+    }
+
+    let starting_rdg_balance = client.address_info(rdg_address.clone()).await?.balance;
+    let starting_fills = recent_fills(client).await?;
+    let mut w = SingleKeyBitcoinWallet::new_wallet(kp.public_key(), network.clone(), true)?;
+    let starting_btc_balance = w.get_wallet_balance()?.confirmed;
+
+    // BTC -> RDG, the party fulfills by tagging its RDG output with the deposit txid
+    let btc_deposit_txid = w.send_local(party_btc_address.clone(), config.btc_deposit_sats, private_key_hex)?;
+    info!("Sent BTC deposit {} to party address {}", btc_deposit_txid, party_btc_address);
+    let (address_ref, deposit_ref) = (&rdg_address, &btc_deposit_txid);
+    let rdg_fulfillment = poll_until(config, "RDG deposit fulfillment", move || async move {
+        let ai = client.address_info(address_ref.clone()).await?;
+        Ok(ai.recent_transactions.into_iter().find(|t| {
+            t.output_external_txids().any(|id| &id.identifier == deposit_ref)
+        }))
+    }).await?;
+    if rdg_fulfillment.output_amount_of(&rdg_address) <= 0 {
+        return Err(error_info("RDG fulfillment does not pay the depositing address"));
+    }
+
+    // RDG -> BTC, the party pays out to the bitcoin address of the signing key
+    let rdg_withdrawal = send_rdg(
+        client, kp, &network, &party_address, config.rdg_withdrawal_amount, true
+    ).await?;
+    info!("Sent RDG withdrawal {}", rdg_withdrawal.hash_hex_or_missing());
+    let withdrawal_time = rdg_withdrawal.time()?.clone() as u64 / 1000;
+    let (network_ref, party_btc_ref) = (&network, &party_btc_address);
+    let btc_fulfillment = poll_until(config, "BTC withdrawal fulfillment", move || async move {
+        let w = SingleKeyBitcoinWallet::new_wallet(kp.public_key(), network_ref.clone(), true)?;
+        Ok(w.get_all_tx()?.into_iter().find(|t| {
+            t.incoming && &t.other_address == party_btc_ref &&
+                t.timestamp.map(|ts| ts >= withdrawal_time).unwrap_or(true)
+        }))
+    }).await?;
+    let btc_fulfillment_txid = btc_fulfillment.tx_id.clone();
+    info!("Received BTC withdrawal fulfillment {} to {}", btc_fulfillment_txid, user_btc_address);
+
+    // The party persists its fills after processing, so wait for both legs to show up there too
+    let withdrawal_amount = CurrencyAmount::from_fractional(config.rdg_withdrawal_amount)?.amount;
+    let starting_ref = &starting_fills;
+    let new_fills = poll_until(config, "party to record both swap legs", move || async move {
+        let fills = recent_fills(client).await?.into_iter()
+            .filter(|f| !starting_ref.contains(f))
+            .collect::<Vec<RecentFill>>();
+        Ok(Some(fills).filter(|f| recorded_both_legs(f, withdrawal_amount)))
+    }).await?;
+
+    let ending_rdg_balance = client.address_info(rdg_address.clone()).await?.balance;
+    let ending_btc_balance = SingleKeyBitcoinWallet::new_wallet(kp.public_key(), network.clone(), true)?
+        .get_wallet_balance()?.confirmed;
+
+    Ok(SwapRoundTripResult {
+        btc_deposit_txid,
+        rdg_fulfillment,
+        rdg_withdrawal,
+        btc_fulfillment_txid,
+        btc_fulfillment_amount: btc_fulfillment.amount,
+        starting_rdg_balance,
+        ending_rdg_balance,
+        starting_btc_balance,
+        ending_btc_balance,
+        party_info,
+        new_fills,
+    })
+}
+
+/// Checks the receipts of a completed round trip are consistent with each other.
+pub fn verify_round_trip(result: &SwapRoundTripResult) -> RgResult<()> {
+    let party_rdg = result.party_info.balances.iter()
+        .find(|b| b.currency == Some(SupportedCurrency::Redgold as i32));
+    party_rdg.safe_get_msg("Party missing RDG balance")?;
+    if result.btc_deposit_txid == result.btc_fulfillment_txid {
+        return Err(error_info("BTC fulfillment reuses deposit txid"));
+    }
+    let party_address = result.party_info.party_id.safe_get()?.public_key.safe_get()?.address()?;
+    let withdrawn = result.rdg_withdrawal.output_swap_amount_of(&party_address);
+    if withdrawn <= 0 {
+        return Err(error_info("RDG withdrawal missing swap output"));
+    }
+    if result.btc_fulfillment_amount == 0 {
+        return Err(error_info("BTC fulfillment pays nothing to the withdrawing address"));
+    }
+    if !recorded_both_legs(&result.new_fills, withdrawn) {
+        return Err(error_info("Party state missing a fulfilled leg of the round trip"));
+    }
+    Ok(())
+}
@@ -1,5 +1,7 @@
 use std::collections::HashSet;
+use std::path::PathBuf;
 use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 use crate::core::transaction;
 use crate::schema::output::output_data;
 use crate::schema::structs::{Block, Output, Transaction, UtxoEntry};
@@ -8,16 +10,105 @@ use redgold_keys::TestConstants;
 use crate::core::transact::tx_builder_supports::TransactionBuilderSupport;
 use redgold_keys::util::mnemonic_support::WordsPass;
 use crate::schema::{struct_metadata, WithMetadataHashable};
-use redgold_schema::{constants, ProtoHashable};
+use redgold_schema::{constants, error_info, json_from, ErrorInfoContext, ProtoHashable, RgResult};
 use redgold_schema::constants::{DECIMAL_MULTIPLIER, EARLIEST_TIME, MAX_COIN_SUPPLY, REDGOLD_PURPOSE, REWARD_AMOUNT};
+use redgold_schema::errors::EnhanceErrorInfo;
 use redgold_schema::output::tx_output_data;
-use redgold_schema::structs::{Address, BlockMetadata, CurrencyAmount, NetworkEnvironment, PublicKey, Seed};
+use redgold_schema::structs::{Address, BlockMetadata, CurrencyAmount, Hash, NetworkEnvironment, PublicKey, Seed};
 use crate::core::transact::tx_builder_supports::TransactionBuilder;
 use crate::node_config::NodeConfig;
 
 pub struct GenesisDistribution{
     pub address: Address,
     pub amount: CurrencyAmount,
+    pub stake: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct GenesisManifestEntry {
+    pub address: String,
+    // Fractional RDG amount
+    pub amount: f64,
+    #[serde(default)]
+    pub stake: bool,
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+/// Custom genesis allocations for a non-main network, so test networks can bootstrap
+/// without code edits. Seeds may pin the manifest hash every genesis node must agree on.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct GenesisManifest {
+    pub network: String,
+    pub entries: Vec<GenesisManifestEntry>,
+    // Address of a node owned output used to fund the multiparty deposit address
+    #[serde(default)]
+    pub watcher_funding_address: Option<String>,
+}
+
+impl GenesisManifest {
+
+    pub fn read(path: &PathBuf) -> RgResult<Self> {
+        let contents = std::fs::read_to_string(path)
+            .error_info("Failed to read genesis manifest")
+            .add(path.to_string_lossy().to_string())?;
+        json_from(&contents)
+    }
+
+    /// Hash of a canonical encoding, with network and addresses normalized and amounts as
+    /// integer units, so it doesn't depend on how the file was written or floats are formatted.
+    pub fn hash(&self) -> RgResult<Hash> {
+        let network = NetworkEnvironment::parse_safe(self.network.clone())?.to_std_string();
+        let funding = self.watcher_funding_address.as_ref()
+            .map(|a| Address::parse(a.clone()).and_then(|a| a.render_string()))
+            .transpose()?;
+        let entries = self.distribution()?.iter().zip(self.entries.iter())
+            .map(|(d, e)| Ok((d.address.render_string()?, d.amount.amount, d.stake, e.label.clone())))
+            .collect::<RgResult<Vec<(String, i64, bool, Option<String>)>>>()?;
+        let canonical = serde_json::to_vec(&(network, funding, entries))
+            .error_info("Failed to encode genesis manifest")?;
+        Ok(Hash::digest(canonical))
+    }
+
+    pub fn validate(&self, network: &NetworkEnvironment) -> RgResult<()> {
+        if network.is_main() {
+            return Err(error_info("Genesis manifests are not supported on main network"));
+        }
+        let manifest_network = NetworkEnvironment::parse_safe(self.network.clone())?;
+        if &manifest_network != network {
+            return Err(error_info(format!(
+                "Genesis manifest is for network {} but node is running {}",
+                self.network, network.to_std_string()
+            )));
+        }
+        if self.entries.is_empty() {
+            return Err(error_info("Genesis manifest has no entries"));
+        }
+        let distribution = self.distribution()?;
+        let total = distribution.iter().map(|d| d.amount.amount).sum::<i64>();
+        if total > MAX_COIN_SUPPLY * DECIMAL_MULTIPLIER {
+            return Err(error_info(format!("Genesis manifest total {} exceeds max coin supply", total)));
+        }
+        if let Some(a) = &self.watcher_funding_address {
+            let a = Address::parse(a.clone())?;
+            if !distribution.iter().any(|d| d.address == a && !d.stake) {
+                return Err(error_info("Watcher funding address is not a non-stake manifest entry"));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn distribution(&self) -> RgResult<Vec<GenesisDistribution>> {
+        self.entries.iter().map(|e| {
+            Ok(GenesisDistribution {
+                address: Address::parse(e.address.clone())
+                    .add(e.label.clone().unwrap_or(e.address.clone()))?,
+                amount: CurrencyAmount::from_fractional(e.amount)
+                    .add(e.label.clone().unwrap_or(e.address.clone()))?,
+                stake: e.stake,
+            })
+        }).collect()
+    }
 }
 
 
@@ -25,6 +116,7 @@ fn main_entry(address: impl Into<String>, fraction_pct: impl Into<f64>) -> Genes
     GenesisDistribution {
         address: Address::parse(&address.into()).expect("works"),
         amount: CurrencyAmount::from_fractional((fraction_pct.into() / 100.0) * (MAX_COIN_SUPPLY as f64)).expect("works"),
+        stake: false,
     }
 }
 fn main_distribution(test_address: &Address) -> Vec<GenesisDistribution> {
@@ -52,7 +144,7 @@ fn main_distribution(test_address: &Address) -> Vec<GenesisDistribution> {
         // 9 - Origin DAO
         main_entry("8965cf0387275d2ac5100b9a3d0e46d9d5cf6e6066db9d5779b1f1649f159068", 65),
         // Node testing address
-        GenesisDistribution { address: test_address.clone(), amount: CurrencyAmount::from_fractional(10.0).expect("a") }
+        GenesisDistribution { address: test_address.clone(), amount: CurrencyAmount::from_fractional(10.0).expect("a"), stake: false }
     ];
 
     let total = entries.iter().map(|e| e.amount.to_rounded_int()).sum::<i64>();
@@ -66,6 +158,34 @@ pub fn verify_genesis_distribution_main() {
     main_distribution(&tc.address_1);
 }
 
+#[test]
+pub fn genesis_manifest_validation() {
+    let tc = TestConstants::new();
+    let staker = tc.words_pass.keypair_at_change(1).expect("kp").address_typed().render_string().expect("a");
+    let mut manifest = GenesisManifest {
+        network: "dev".to_string(),
+        entries: vec![
+            GenesisManifestEntry { address: tc.address_1.render_string().expect("a"), amount: 1000.0, stake: false, label: None },
+            GenesisManifestEntry { address: staker.clone(), amount: 500.0, stake: true, label: Some("stake".to_string()) },
+        ],
+        watcher_funding_address: None,
+    };
+    assert!(manifest.validate(&NetworkEnvironment::Dev).is_ok());
+    assert!(manifest.validate(&NetworkEnvironment::Test).is_err());
+    assert!(manifest.validate(&NetworkEnvironment::Main).is_err());
+    let tx = genesis_tx_from(manifest.distribution().expect("d"), &NetworkEnvironment::Dev);
+    assert_eq!(tx.outputs.len(), 2);
+    assert!(tx.outputs[1].contract.is_some());
+    let h = manifest.hash().expect("hash");
+    // Equal amounts written differently hash the same
+    manifest.entries[0].amount = 1000.000000000001;
+    assert_eq!(h, manifest.hash().expect("hash"));
+    manifest.entries[0].amount = 1001.0;
+    assert_ne!(h, manifest.hash().expect("hash"));
+    manifest.watcher_funding_address = Some(staker);
+    assert!(manifest.validate(&NetworkEnvironment::Dev).is_err());
+}
+
 fn lower_distribution(_network: &NetworkEnvironment, words_pass: &WordsPass, seeds: &Vec<Seed>) -> Vec<GenesisDistribution> {
     let mut pks = vec![];
 
@@ -86,6 +206,7 @@ fn lower_distribution(_network: &NetworkEnvironment, words_pass: &WordsPass, see
         GenesisDistribution {
             address: Address::from_struct_public(o).expect("works"),
             amount: CurrencyAmount::from_fractional((1.0 / pks.len() as f64) * (MAX_COIN_SUPPLY as f64)).expect("works"),
+            stake: false,
         }
     }).collect_vec();
     res
@@ -94,21 +215,36 @@ fn lower_distribution(_network: &NetworkEnvironment, words_pass: &WordsPass, see
 pub fn genesis_transaction(
     network: &NetworkEnvironment,
     words: &WordsPass,
-    seeds: &Vec<Seed>
+    seeds: &Vec<Seed>,
+    test_distribution: bool
 ) -> Transaction {
-    let distribution = if network.is_main() {
-        main_distribution(&words.default_public_key().expect("default_kp").address().expect("address"))
-    } else {
+    let distribution = if test_distribution {
         lower_distribution(network, words, seeds)
+    } else {
+        main_distribution(&words.default_public_key().expect("default_kp").address().expect("address"))
     };
     genesis_tx_from(distribution, network)
 }
 
+/// Genesis transaction for this node, built from the loaded manifest when one is configured.
+pub fn genesis_transaction_config(node_config: &NodeConfig) -> RgResult<Transaction> {
+    match &node_config.genesis_manifest {
+        Some(m) => Ok(genesis_tx_from(m.distribution()?, &node_config.network)),
+        None => Ok(genesis_transaction(
+            &node_config.network, &node_config.words(), &node_config.seeds,
+            node_config.features.genesis_test_distribution
+        ))
+    }
+}
+
 
 pub fn genesis_tx_from(distribution: Vec<GenesisDistribution>, network: &NetworkEnvironment) -> Transaction {
     let mut txb = TransactionBuilder::new(network);
     for d in distribution {
         txb.with_output(&d.address, &d.amount);
+        if d.stake {
+            txb.with_last_output_stake();
+        }
     }
     let x = txb.with_no_salt().with_time(Some(EARLIEST_TIME))
         .transaction.with_hashes();
@@ -8,6 +8,8 @@ use eframe::egui;
 use itertools::Itertools;
 use log::{error, info};
 use redgold_schema::{EasyJson, error_info, RgResult};
+use redgold_schema::payment_uri::PaymentUri;
+use crate::util::cli::args::RgTopLevelSubcommand;
 
 use crate::util::sym_crypt;
 // 0.8
@@ -62,6 +64,8 @@ pub struct LocalState {
     // pub runtime: Arc<Runtime>,
     pub home_state: HomeState,
     pub server_state: ServersState,
+    pub peers_state: PeersState,
+    pub contacts_state: ContactsState,
     pub current_time: i64,
     pub keygen_state: KeygenState,
     pub wallet_state: WalletState,
@@ -71,32 +75,50 @@ pub struct LocalState {
     pub settings_state: SettingsState,
     pub address_state: AddressState,
     pub otp_state: OtpState,
+    pub portfolio_state: PortfolioState,
     pub ds_env: DataStore,
     pub ds_env_secure: Option<DataStore>,
     pub local_stored_state: LocalStoredState,
-    pub updates: Channel<StateUpdate>
+    pub updates: Channel<StateUpdate>,
+    // Font size last applied to the egui style, to only restyle on change
+    applied_font_size: Option<f32>,
+    pub gui_layout: GuiLayoutState,
+    pub gui_layout_saved_time: Option<i64>,
 }
 
 impl LocalState {
 
     pub fn add_mnemonic(&mut self, name: String, mnemonic: String, persist_disk: bool) {
-        self.updates.sender.send(StateUpdate {
+        self.updates.send_err(StateUpdate {
             update: Box::new(
                 move |lss: &mut LocalState| {
                     lss.upsert_mnemonic(StoredMnemonic {
                         name: name.clone(),
                         mnemonic: mnemonic.clone(),
                         persist_disk: Some(persist_disk),
+                        backup_verified: None,
+                        backup_verified_time: None,
+                        os_keystore: None,
                     });
                 })
-        }).unwrap();
+        }).log_error().ok();
+    }
+
+    /// Selections persisted across restarts, window geometry is filled in from the viewport.
+    pub fn current_layout(&self) -> GuiLayoutState {
+        let mut l = self.gui_layout.clone();
+        l.active_tab = Some(format!("{:?}", self.active_tab));
+        l.selected_account = self.wallet_state.selected_account.clone();
+        l.selected_key_name = Some(self.wallet_state.selected_key_name.clone());
+        l.active_xpub = Some(self.wallet_state.active_xpub.clone()).filter(|x| !x.is_empty());
+        l
     }
 
     pub fn secure_or(&self) -> DataStore {
         self.ds_env_secure.clone().unwrap_or(self.ds_env.clone())
     }
     pub fn send_update<F: FnMut(&mut LocalState) + Send + 'static>(updates: &Channel<StateUpdate>, p0: F) {
-        updates.sender.send(StateUpdate{update: Box::new(p0)}).unwrap();
+        updates.send_err(StateUpdate{update: Box::new(p0)}).log_error().ok();
     }
 
     pub fn persist_local_state_store(&self) {
@@ -142,6 +164,12 @@ impl LocalState {
 
 
     pub fn upsert_mnemonic(&mut self, new_named: StoredMnemonic) -> () {
+        let mut new_named = new_named;
+        if self.local_stored_state.os_keystore_enabled() {
+            // Falls back to the local stored state if the keychain rejects the write
+            new_named.os_keystore = store_secret(&keystore_namespace(&self.node_config), &mnemonic_account(&new_named.name), &new_named.mnemonic)
+                .log_error().ok().map(|_| true);
+        }
         let mut updated = self.local_stored_state.mnemonics.as_ref().unwrap_or(&vec![]).iter().filter(|x| {
             x.name != new_named.name
         }).map(|x| x.clone()).collect_vec();
@@ -150,7 +178,29 @@ impl LocalState {
         self.persist_local_state_store();
     }
 
+    pub fn upsert_watched_address(&mut self, new_named: WatchedAddress) -> () {
+        let mut updated = self.local_stored_state.watched_addresses().into_iter().filter(|x| {
+            x.name != new_named.name
+        }).collect_vec();
+        updated.push(new_named);
+        self.local_stored_state.watched_addresses = Some(updated);
+        self.persist_local_state_store();
+    }
+
+    pub fn remove_watched_address(&mut self, name: &String) -> () {
+        let updated = self.local_stored_state.watched_addresses().into_iter().filter(|x| {
+            &x.name != name
+        }).collect_vec();
+        self.local_stored_state.watched_addresses = Some(updated);
+        self.persist_local_state_store();
+    }
+
     pub fn upsert_private_key(&mut self, new_named: StoredPrivateKey) -> () {
+        let mut new_named = new_named;
+        if self.local_stored_state.os_keystore_enabled() {
+            new_named.os_keystore = store_secret(&keystore_namespace(&self.node_config), &private_key_account(&new_named.name), &new_named.key_hex)
+                .log_error().ok().map(|_| true);
+        }
         let mut updated = self.local_stored_state.private_keys.as_ref().unwrap_or(&vec![]).iter().filter(|x| {
             x.name != new_named.name
         }).map(|x| x.clone()).collect_vec();
@@ -190,14 +240,37 @@ impl LocalState {
         ).await.expect("migrations");
         // DataStore::run_migrations(&ds_or).await.expect("");
         let hot_mnemonic = node_config.secure_or().all().mnemonic().await.unwrap_or(node_config.mnemonic_words.clone());
-        let local_stored_state = ds_or.config_store.get_stored_state().await?;
+        let mut local_stored_state = ds_or.config_store.get_stored_state().await?;
+        let xpubs_migrated = local_stored_state.migrate_xpubs();
+        if let Some(missing) = load_os_keystore_secrets(&keystore_namespace(&node_config), &mut local_stored_state).log_error().ok() {
+            if !missing.is_empty() {
+                error!("Keys missing from the OS keystore: {}", missing.join(", "));
+            }
+        }
+        if let Some(l) = local_stored_state.locale.as_ref().and_then(|c| Locale::from_code(c)) {
+            set_locale(l);
+        }
         let mut ss = crate::gui::tabs::server_tab::ServersState::default();
 
         ss.csv_edit_path = node_config.clone().secure_data_folder.unwrap_or(node_config.data_folder.clone())
             .all().servers_path().to_str().expect("").to_string();
         ss.genesis = node_config.opts.development_mode;
-        let ls = LocalState {
-            active_tab: Tab::Home,
+        let updates = new_configured_channel("gui_updates", &node_config.channels.gui_updates);
+        let gui_layout = ds_or.config_store.get_typed(&GUI_LAYOUT_STATE).await
+            .log_error().ok().flatten().unwrap_or_default();
+        let active_tab = gui_layout.active_tab.as_ref()
+            .and_then(|t| Tab::iter().find(|i| &format!("{:?}", i) == t))
+            .unwrap_or(Tab::Home);
+        let mut wallet_state = WalletState::new(hot_mnemonic);
+        wallet_state.selected_account = gui_layout.selected_account.clone();
+        if let Some(k) = &gui_layout.selected_key_name {
+            wallet_state.selected_key_name = k.clone();
+        }
+        if let Some(x) = &gui_layout.active_xpub {
+            wallet_state.active_xpub = x.clone();
+        }
+        let mut ls = LocalState {
+            active_tab,
             session_salt: random_bytes(),
             session_password_hashed: None,
             session_locked: false,
@@ -218,11 +291,13 @@ impl LocalState {
             // runtime,
             home_state: HomeState::from(),
             server_state: ss,
+            peers_state: Default::default(),
+            contacts_state: Default::default(),
             current_time: util::current_time_millis_i64(),
             keygen_state: KeygenState::new(
                 node_config.clone().executable_checksum.clone().unwrap_or("".to_string())
             ),
-            wallet_state: WalletState::new(hot_mnemonic),
+            wallet_state,
             qr_state: Default::default(),
             qr_show_state: Default::default(),
             identity_state: IdentityState::new(),
@@ -233,14 +308,51 @@ impl LocalState {
             ),
             address_state: Default::default(),
             otp_state: Default::default(),
+            portfolio_state: Default::default(),
             ds_env,
             ds_env_secure,
             local_stored_state,
-            updates: new_channel(),
+            updates,
+            applied_font_size: None,
+            gui_layout,
+            gui_layout_saved_time: None,
         };
+        if xpubs_migrated {
+            info!("Migrated stored xpub metadata");
+            ls.persist_local_state_store();
+        }
+        ls.open_payment_uri();
         Ok(ls)
     }
 
+    /// Opens the send view pre-filled from a payment link passed on the command line.
+    fn open_payment_uri(&mut self) {
+        let uri = match &self.node_config.opts.subcmd {
+            Some(RgTopLevelSubcommand::GUI(g)) => g.open_uri.clone(),
+            _ => None,
+        };
+        if let Some(uri) = uri {
+            match PaymentUri::parse(uri) {
+                Ok(parsed) => {
+                    self.wallet_state.apply_payment_uri(&parsed);
+                    self.wallet_state.opened_payment_uri = Some(parsed);
+                    self.active_tab = Tab::Transact;
+                }
+                Err(e) => error!("Unable to open payment link: {}", e.json_or()),
+            }
+        }
+    }
+
+    /// Opens the send view pre-filled with an accepted payment request's address and amount.
+    pub fn open_payment_request(&mut self, r: &PaymentRequest) {
+        let address = r.address.as_ref().and_then(|a| a.render_string().ok()).unwrap_or("".to_string());
+        let amount = r.amount.as_ref().map(|a| a.to_fractional());
+        let uri = PaymentUri::new(payment_request_currency(r), address, amount);
+        self.wallet_state.apply_payment_uri(&uri);
+        self.wallet_state.opened_payment_uri = Some(uri);
+        self.active_tab = Tab::Transact;
+    }
+
     fn encrypt(&self, str: String) -> Vec<u8> {
         return sym_crypt::encrypt(
             str.as_bytes(),
@@ -264,6 +376,17 @@ impl LocalState {
         vec.extend(self.session_salt.to_vec());
         return dhash_vec(&vec);
     }
+    /// Whether the entry matches the session password, false if no password was set.
+    pub fn check_session_password(&self, entry: &String) -> bool {
+        let mut vec = entry.as_bytes().to_vec();
+        vec.extend(self.session_salt.to_vec());
+        self.session_password_hashed.map(|h| h == dhash_vec(&vec)).unwrap_or(false)
+    }
+
+    pub fn has_session_password(&self) -> bool {
+        self.session_password_hashed.is_some()
+    }
+
     fn store_password(&mut self) {
         self.session_password_hashed = Some(self.hash_password());
     }
@@ -275,12 +398,58 @@ fn random_bytes() -> [u8; 32] {
 
 use strum::IntoEnumIterator; // 0.17.1
 use strum_macros::EnumIter;
-use redgold_schema::structs::{ErrorInfo, PublicKey};
+use redgold_schema::structs::{ErrorInfo, PaymentRequest, PublicKey};
 use crate::node_config::NodeConfig; // 0.17.1
 
 
 
-#[derive(Debug, EnumIter, Clone)]
+pub const DEFAULT_UI_SCALE: f32 = 2.5;
+pub const MIN_UI_SCALE: f32 = 1.0;
+pub const MAX_UI_SCALE: f32 = 4.0;
+// Body text size of the default egui style
+pub const DEFAULT_FONT_SIZE: f32 = 12.5;
+
+/// Scales every text style so the body text is the configured font size.
+fn apply_font_size(ctx: &egui::Context, local_state: &mut LocalState) {
+    let size = local_state.local_stored_state.font_size.unwrap_or(DEFAULT_FONT_SIZE);
+    if local_state.applied_font_size == Some(size) {
+        return;
+    }
+    local_state.applied_font_size = Some(size);
+    let factor = size / DEFAULT_FONT_SIZE;
+    let mut style = (*ctx.style()).clone();
+    style.text_styles = egui::Style::default().text_styles.into_iter()
+        .map(|(ts, mut font)| {
+            font.size *= factor;
+            (ts, font)
+        }).collect();
+    ctx.set_style(style);
+}
+
+/// Ctrl+Tab and Ctrl+Shift+Tab cycle tabs, Alt+1 through Alt+9 jump to the first nine.
+fn handle_tab_shortcuts(ctx: &egui::Context, local_state: &mut LocalState) {
+    let number_keys = [
+        egui::Key::Num1, egui::Key::Num2, egui::Key::Num3, egui::Key::Num4, egui::Key::Num5,
+        egui::Key::Num6, egui::Key::Num7, egui::Key::Num8, egui::Key::Num9,
+    ];
+    let current = local_state.active_tab.clone();
+    let next = ctx.input_mut(|i| {
+        if i.consume_key(egui::Modifiers::CTRL | egui::Modifiers::SHIFT, egui::Key::Tab) {
+            return Some(current.cycle(false));
+        }
+        if i.consume_key(egui::Modifiers::CTRL, egui::Key::Tab) {
+            return Some(current.cycle(true));
+        }
+        number_keys.iter().enumerate()
+            .find(|(_, k)| i.consume_key(egui::Modifiers::ALT, **k))
+            .and_then(|(n, _)| Tab::iter().nth(n))
+    });
+    if let Some(t) = next {
+        local_state.active_tab = t;
+    }
+}
+
+#[derive(Debug, EnumIter, Clone, PartialEq)]
 #[repr(i32)]
 pub enum Tab {
     Home,
@@ -291,11 +460,21 @@ pub enum Tab {
     Contacts,
     Address,
     Servers,
+    Peers,
     Ratings,
     Settings,
     OTP,
 }
 
+impl Tab {
+    pub fn cycle(&self, forward: bool) -> Tab {
+        let tabs = Tab::iter().collect_vec();
+        let i = tabs.iter().position(|t| t == self).unwrap_or(0);
+        let n = if forward { (i + 1) % tabs.len() } else { (i + tabs.len() - 1) % tabs.len() };
+        tabs[n].clone()
+    }
+}
+
 fn update_lock_screen(app: &mut ClientApp, ctx: &egui::Context) {
     let ClientApp { local_state, .. } = app;
     egui::CentralPanel::default().show(ctx, |ui| {
@@ -330,14 +509,21 @@ fn update_lock_screen(app: &mut ClientApp, ctx: &egui::Context) {
 use redgold_data::data_store::DataStore;
 use redgold_keys::util::dhash_vec;
 use redgold_keys::xpub_wrapper::XpubWrapper;
-use crate::core::internal_message::{Channel, new_channel};
+use crate::core::internal_message::{Channel, new_configured_channel};
 use crate::gui::home::HomeState;
+use crate::gui::os_keystore::{keystore_namespace, load_os_keystore_secrets, mnemonic_account, private_key_account, store_secret};
+use crate::gui::layout_state::{GUI_LAYOUT_STATE, GuiLayoutState, save_layout_if_changed};
+use crate::observability::logging::Loggable;
+use crate::gui::i18n::{Locale, set_locale, tr};
 use crate::gui::tabs::keys_tab::KeygenState;
-use redgold_schema::local_stored_state::{Identity, LocalStoredState, NamedXpub, StoredMnemonic, StoredPrivateKey};
+use redgold_schema::local_stored_state::{Identity, LocalStoredState, NamedXpub, StoredMnemonic, StoredPrivateKey, WatchedAddress};
 use crate::gui::tabs::address_tab::AddressState;
+use crate::gui::tabs::portfolio_tab::PortfolioState;
 use crate::gui::tabs::identity_tab::IdentityState;
 use crate::gui::tabs::otp_tab::{otp_tab, OtpState};
-use crate::gui::tabs::{keys_tab, server_tab};
+use crate::gui::tabs::{keys_tab, peers_tab, server_tab};
+use crate::gui::tabs::peers_tab::PeersState;
+use crate::gui::tabs::contacts_tab::{contacts_tab, payment_request_currency, ContactsState};
 use crate::gui::tabs::server_tab::{ServersState, ServerStatus};
 use crate::gui::tabs::settings_tab::{settings_tab, SettingsState};
 use crate::gui::wallet_tab::{StateUpdate, wallet_screen, WalletState};
@@ -358,10 +544,13 @@ pub fn app_update(app: &mut ClientApp, ctx: &egui::Context, _frame: &mut eframe:
         local_state,
     } = app;
 
-    // TODO: Replace with config query and check.
     INIT.call_once(|| {
-        ctx.set_pixels_per_point(2.5);
+        ctx.set_pixels_per_point(local_state.local_stored_state.ui_scale.unwrap_or(DEFAULT_UI_SCALE));
     });
+    apply_font_size(ctx, local_state);
+    handle_tab_shortcuts(ctx, local_state);
+    let layout = local_state.current_layout();
+    save_layout_if_changed(ctx, local_state, layout);
 
     local_state.current_time = util::current_time_millis_i64();
     // Continuous mode
@@ -429,7 +618,7 @@ pub fn app_update(app: &mut ClientApp, ctx: &egui::Context, _frame: &mut eframe:
                     //     *tab = Tab::Home;
                     // }
                     for tab_i in Tab::iter() {
-                        let tab_str = format!("{:?}", tab_i);
+                        let tab_str = tr(&format!("{:?}", tab_i));
                         if ui.button(tab_str).clicked() {
                             local_state.active_tab = tab_i;
                         }
@@ -472,6 +661,12 @@ pub fn app_update(app: &mut ClientApp, ctx: &egui::Context, _frame: &mut eframe:
             Tab::Servers => {
                 server_tab::servers_tab(ui, ctx, local_state);
             }
+            Tab::Peers => {
+                peers_tab::peers_tab(ui, ctx, local_state);
+            }
+            Tab::Contacts => {
+                contacts_tab(ui, ctx, local_state);
+            }
             Tab::Transact => {
                 wallet_screen(ui, ctx, local_state);
             }
@@ -484,6 +679,9 @@ pub fn app_update(app: &mut ClientApp, ctx: &egui::Context, _frame: &mut eframe:
             Tab::OTP => {
                 otp_tab(ui, ctx, local_state);
             }
+            Tab::Portfolio => {
+                crate::gui::tabs::portfolio_tab::portfolio_tab(ui, ctx, local_state);
+            }
             _ => {}
         }
         // ui.hyperlink("https://github.com/emilk/egui_template");
@@ -502,3 +700,10 @@ pub fn app_update(app: &mut ClientApp, ctx: &egui::Context, _frame: &mut eframe:
     // sync local data to RDS -- apart from data associated with phrases
     // discuss extra features around confirmation process. p2p negotation, contacts table.
 }
+
+#[test]
+fn tab_cycle_wraps() {
+    assert_eq!(Tab::Home.cycle(false), Tab::iter().last().expect("tab"));
+    assert_eq!(Tab::iter().last().expect("tab").cycle(true), Tab::Home);
+    assert_eq!(Tab::Home.cycle(true), Tab::Keys);
+}
@@ -1,5 +1,6 @@
 use eframe::egui;
-use eframe::egui::{Color32, RichText, TextEdit, TextStyle, Ui, Widget};
+use eframe::egui::{Color32, ComboBox, RichText, TextEdit, TextStyle, Ui, Widget};
+use redgold_schema::local_stored_state::Account;
 
 pub fn valid_label(ui: &mut Ui, bool: bool) {
     if bool {
@@ -150,3 +151,43 @@ pub fn password_single(
         }
     });
 }
+
+pub fn account_color(account: &Account) -> Color32 {
+    account.color.as_ref().and_then(|c| parse_hex_color(c)).unwrap_or(Color32::GRAY)
+}
+
+pub fn parse_hex_color(hex: &String) -> Option<Color32> {
+    let hex = hex.trim().trim_start_matches('#');
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some(Color32::from_rgb(channel(0)?, channel(2)?, channel(4)?))
+}
+
+pub fn hex_color(color: Color32) -> String {
+    format!("#{:02X}{:02X}{:02X}", color.r(), color.g(), color.b())
+}
+
+pub fn account_text(account: &Account) -> RichText {
+    RichText::new(format!("\u{25CF} {}", account.name)).color(account_color(account))
+}
+
+pub fn account_label(ui: &mut Ui, account: &Account) {
+    ui.label(account_text(account));
+}
+
+/// Combo box over accounts, storing the selected account name.
+pub fn account_selector(ui: &mut Ui, label: impl Into<String>, accounts: &Vec<Account>, selected: &mut Option<String>) {
+    let selected_text = selected.as_ref()
+        .and_then(|n| accounts.iter().find(|a| &a.name == n))
+        .map(|a| account_text(a))
+        .unwrap_or(RichText::new("Select Account"));
+    ComboBox::from_label(label.into())
+        .selected_text(selected_text)
+        .show_ui(ui, |ui| {
+            for a in accounts.iter() {
+                ui.selectable_value(selected, Some(a.name.clone()), account_text(a));
+            }
+        });
+}
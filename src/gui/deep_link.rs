@@ -0,0 +1,66 @@
+use std::path::PathBuf;
+use redgold_schema::{error_info, ErrorInfoContext, RgResult};
+use redgold_schema::payment_uri::PaymentUri;
+use crate::util::cmd::run_cmd_safe;
+
+/// Operating systems launch the registered handler with the bare link as the only argument,
+/// rewrite that into the gui subcommand so clap accepts it.
+pub fn deep_link_args(args: Vec<String>) -> Vec<String> {
+    if args.len() == 2 && PaymentUri::parse(args[1].clone()).is_ok() {
+        return vec![args[0].clone(), "gui".to_string(), "--open-uri".to_string(), args[1].clone()];
+    }
+    args
+}
+
+/// Registers the current executable as the handler for redgold: links, returning a
+/// description of what was registered.
+pub fn register_uri_handler() -> RgResult<String> {
+    let exe = std::env::current_exe().error_info("Unable to find current executable")?;
+    let exe = exe.to_str().ok_or(error_info("Executable path is not valid utf8"))?.to_string();
+    if cfg!(target_os = "linux") {
+        register_linux(&exe)
+    } else if cfg!(target_os = "windows") {
+        register_windows(&exe)
+    } else {
+        Err(error_info("Automatic redgold: link registration is only supported on Linux and Windows"))
+    }
+}
+
+fn register_linux(exe: &String) -> RgResult<String> {
+    let applications = dirs::data_dir()
+        .ok_or(error_info("Unable to find user data directory"))?
+        .join("applications");
+    std::fs::create_dir_all(&applications).error_info("Failed to create applications directory")?;
+    let desktop: PathBuf = applications.join("redgold-uri.desktop");
+    let contents = format!(
+        "[Desktop Entry]\nType=Application\nName=Redgold\nExec=\"{}\" gui --open-uri %u\nTerminal=false\nNoDisplay=true\nMimeType=x-scheme-handler/redgold;\n",
+        exe
+    );
+    std::fs::write(&desktop, contents).error_info("Failed to write desktop entry")?;
+    run_cmd_safe("xdg-mime", vec!["default", "redgold-uri.desktop", "x-scheme-handler/redgold"])?;
+    Ok(format!("Registered redgold: links with {}", desktop.to_string_lossy()))
+}
+
+fn register_windows(exe: &String) -> RgResult<String> {
+    let key = "HKCU\\Software\\Classes\\redgold";
+    let command = format!("\"{}\" gui --open-uri \"%1\"", exe);
+    run_cmd_safe("reg", vec!["add", key, "/ve", "/d", "URL:Redgold Protocol", "/f"])?;
+    run_cmd_safe("reg", vec!["add", key, "/v", "URL Protocol", "/d", "", "/f"])?;
+    run_cmd_safe("reg", vec!["add", &format!("{}\\shell\\open\\command", key), "/ve", "/d", &command, "/f"])?;
+    Ok(format!("Registered redgold: links in {}", key))
+}
+
+/// Reads the system clipboard and returns any payment link or address found in it.
+pub fn clipboard_payment_uri() -> Option<PaymentUri> {
+    let text = arboard::Clipboard::new().ok()?.get_text().ok()?;
+    PaymentUri::detect(&text)
+}
+
+#[test]
+fn deep_link_args_rewrite() {
+    let args = vec!["redgold".to_string(), "redgold:abc".to_string()];
+    // Not a valid redgold address, left for clap to reject
+    assert_eq!(deep_link_args(args.clone()), args);
+    let args = vec!["redgold".to_string(), "bitcoin:tb1qxyz?amount=1".to_string()];
+    assert_eq!(deep_link_args(args)[1..3], ["gui".to_string(), "--open-uri".to_string()]);
+}
@@ -0,0 +1,154 @@
+use std::net::{TcpListener, UdpSocket};
+use std::sync::{Arc, Mutex};
+use eframe::egui::{Color32, Ui};
+use itertools::Itertools;
+use log::{error, info};
+use tokio::task::JoinHandle;
+use redgold_schema::{error_info, EasyJson, RgResult};
+use crate::core::relay::Relay;
+use crate::gui::app_loop::LocalState;
+use crate::node::Node;
+use crate::node_config::NodeConfig;
+use crate::observability::error_reports::report_critical_error;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum EmbeddedNodeStatus {
+    Stopped,
+    Starting,
+    Running,
+    Failed(String),
+}
+
+/// A full node run inside the GUI process, sharing its runtime.
+#[derive(Clone)]
+pub struct EmbeddedNodeState {
+    pub status: EmbeddedNodeStatus,
+    pub started_time: Option<i64>,
+    handles: Arc<Mutex<Vec<JoinHandle<RgResult<()>>>>>,
+}
+
+impl Default for EmbeddedNodeState {
+    fn default() -> Self {
+        Self {
+            status: EmbeddedNodeStatus::Stopped,
+            started_time: None,
+            handles: Arc::new(Mutex::new(vec![])),
+        }
+    }
+}
+
+impl EmbeddedNodeState {
+    fn abort_services(&self) {
+        if let Ok(mut handles) = self.handles.lock() {
+            for h in handles.drain(..) {
+                h.abort();
+            }
+        }
+    }
+
+    fn any_service_finished(&self) -> bool {
+        self.handles.lock().map(|h| h.iter().any(|j| j.is_finished())).unwrap_or(false)
+    }
+}
+
+/// Errors naming every port the node needs that's already bound by another process.
+pub fn check_ports_available(nc: &NodeConfig) -> RgResult<()> {
+    let mut tcp = vec![nc.p2p_port(), nc.public_port(), nc.rosetta_port(), nc.explorer_port()];
+    if !nc.disable_control_api {
+        tcp.push(nc.control_port());
+    }
+    let mut in_use = tcp.into_iter().filter(|p| TcpListener::bind(("0.0.0.0", *p)).is_err()).collect_vec();
+    if UdpSocket::bind(("0.0.0.0", nc.udp_port())).is_err() {
+        in_use.push(nc.udp_port());
+    }
+    if !in_use.is_empty() {
+        return Err(error_info(format!(
+            "Ports already in use: {}, is another node running?", in_use.iter().map(|p| p.to_string()).join(", ")
+        )));
+    }
+    Ok(())
+}
+
+async fn run_node(nc: NodeConfig, handles: Arc<Mutex<Vec<JoinHandle<RgResult<()>>>>>) -> RgResult<()> {
+    let relay = Relay::new(nc.clone()).await;
+    Node::prelim_setup(relay.clone()).await?;
+    let started = Node::start_services(relay.clone()).await;
+    handles.lock().map_err(|e| error_info(format!("Failed to lock node handles {}", e.to_string())))?
+        .extend(started);
+    Node::from_config(relay).await?;
+    Ok(())
+}
+
+pub fn start_embedded_node(ls: &mut LocalState) {
+    let nc = ls.node_config.clone();
+    let result = check_ports_available(&nc).and_then(|_| nc.env_data_folder().acquire_node_lock());
+    if let Err(e) = result {
+        ls.home_state.embedded_node.status = EmbeddedNodeStatus::Failed(e.json_or());
+        return;
+    }
+    info!("Starting embedded node on network {}", nc.network.to_std_string());
+    ls.home_state.embedded_node.status = EmbeddedNodeStatus::Starting;
+    ls.home_state.embedded_node.started_time = Some(ls.current_time);
+    let handles = ls.home_state.embedded_node.handles.clone();
+    let updates = ls.updates.clone();
+    tokio::spawn(async move {
+        let res = run_node(nc, handles).await;
+        LocalState::send_update(&updates, move |ls| {
+            match &res {
+                Ok(_) => ls.home_state.embedded_node.status = EmbeddedNodeStatus::Running,
+                Err(e) => {
+                    error!("Embedded node startup failure: {}", e.json_or());
+                    report_critical_error("Embedded node startup failure", e);
+                    stop_embedded_node(ls);
+                    ls.home_state.embedded_node.status = EmbeddedNodeStatus::Failed(e.json_or());
+                }
+            }
+        });
+    });
+}
+
+pub fn stop_embedded_node(ls: &mut LocalState) {
+    let state = &mut ls.home_state.embedded_node;
+    state.abort_services();
+    state.status = EmbeddedNodeStatus::Stopped;
+    state.started_time = None;
+    if let Err(e) = ls.node_config.env_data_folder().release_node_lock() {
+        error!("Failed to release node lock: {}", e.json_or());
+    }
+}
+
+pub fn embedded_node_view(ui: &mut Ui, ls: &mut LocalState) {
+    if ls.home_state.embedded_node.status == EmbeddedNodeStatus::Running &&
+        ls.home_state.embedded_node.any_service_finished() {
+        stop_embedded_node(ls);
+        ls.home_state.embedded_node.status = EmbeddedNodeStatus::Failed("A node service terminated".to_string());
+    }
+    let state = &ls.home_state.embedded_node;
+    let (color, label) = match &state.status {
+        EmbeddedNodeStatus::Stopped => (Color32::GRAY, "Stopped".to_string()),
+        EmbeddedNodeStatus::Starting => (Color32::YELLOW, "Starting".to_string()),
+        EmbeddedNodeStatus::Running => (Color32::GREEN, format!(
+            "Running for {}s", state.started_time.map(|t| (ls.current_time - t) / 1000).unwrap_or(0)
+        )),
+        EmbeddedNodeStatus::Failed(e) => (Color32::RED, format!("Failed: {}", e)),
+    };
+    let active = matches!(state.status, EmbeddedNodeStatus::Starting | EmbeddedNodeStatus::Running);
+    ui.heading("Local Node");
+    ui.horizontal(|ui| {
+        ui.colored_label(color, "\u{25CF}");
+        ui.label(label);
+    });
+    ui.label(format!(
+        "Network {} with public port {} and data folder {}",
+        ls.node_config.network.to_std_string(),
+        ls.node_config.public_port(),
+        ls.node_config.env_data_folder().path.to_string_lossy()
+    ));
+    if active {
+        if ui.button("Stop Node").clicked() {
+            stop_embedded_node(ls);
+        }
+    } else if ui.button("Start Node").clicked() {
+        start_embedded_node(ls);
+    }
+}
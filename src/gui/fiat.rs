@@ -0,0 +1,76 @@
+use log::error;
+use redgold_schema::EasyJson;
+use redgold_schema::structs::SupportedCurrency;
+use crate::core::internal_message::Channel;
+use crate::gui::app_loop::LocalState;
+use crate::gui::wallet_tab::StateUpdate;
+use crate::multiparty::watcher::get_btc_per_rdg_starting_min_ask;
+use crate::scrape::coinbase_btc_spot_latest;
+
+const PRICE_QUERY_INTERVAL_MS: i64 = 1000 * 60;
+
+/// USD prices used for fiat entry and display. BTC is quoted live from the spot oracle, RDG is
+/// derived from it using the AMM's reference BTC/RDG price.
+#[derive(Clone, Default)]
+pub struct FiatPrices {
+    pub usd_btc: Option<f64>,
+    pub last_query_time: Option<i64>,
+}
+
+impl FiatPrices {
+    pub fn usd_per_unit(&self, currency: &SupportedCurrency, time: i64) -> Option<f64> {
+        let usd_btc = self.usd_btc?;
+        match currency {
+            SupportedCurrency::Bitcoin => Some(usd_btc),
+            SupportedCurrency::Redgold => Some(usd_btc * get_btc_per_rdg_starting_min_ask(time)),
+            _ => None
+        }
+    }
+
+    pub fn to_usd(&self, amount: f64, currency: &SupportedCurrency, time: i64) -> Option<f64> {
+        self.usd_per_unit(currency, time).map(|p| amount * p)
+    }
+
+    pub fn from_usd(&self, usd: f64, currency: &SupportedCurrency, time: i64) -> Option<f64> {
+        self.usd_per_unit(currency, time).filter(|p| *p > 0.0).map(|p| usd / p)
+    }
+
+    /// Rendered USD equivalent suffix, empty until a price is available.
+    pub fn usd_label(&self, amount: f64, currency: &SupportedCurrency, time: i64) -> String {
+        self.to_usd(amount, currency, time).map(|u| format!("(~${:.2})", u)).unwrap_or("".to_string())
+    }
+
+    pub fn needs_refresh(&self, now: i64) -> bool {
+        self.last_query_time.map(|q| (now - q) > PRICE_QUERY_INTERVAL_MS).unwrap_or(true)
+    }
+}
+
+pub async fn query_prices(updates: Channel<StateUpdate>) {
+    match coinbase_btc_spot_latest().await.and_then(|s| s.usd_btc()) {
+        Ok(usd_btc) => {
+            LocalState::send_update(&updates, move |ls| {
+                ls.wallet_state.fiat_prices.usd_btc = Some(usd_btc);
+            });
+        }
+        Err(e) => error!("Fiat price query failed: {}", e.json_or())
+    }
+}
+
+pub fn refresh_prices(ls: &mut LocalState) {
+    let now = ls.current_time;
+    if ls.wallet_state.fiat_prices.needs_refresh(now) {
+        ls.wallet_state.fiat_prices.last_query_time = Some(now);
+        tokio::spawn(query_prices(ls.updates.clone()));
+    }
+}
+
+#[test]
+fn usd_round_trip() {
+    let prices = FiatPrices { usd_btc: Some(50_000.0), last_query_time: None };
+    let btc = prices.from_usd(100.0, &SupportedCurrency::Bitcoin, 0).expect("price");
+    assert!((btc - 0.002).abs() < 1e-12);
+    let rdg = prices.from_usd(100.0, &SupportedCurrency::Redgold, 0).expect("price");
+    let usd = prices.to_usd(rdg, &SupportedCurrency::Redgold, 0).expect("price");
+    assert!((usd - 100.0).abs() < 1e-9);
+    assert!(FiatPrices::default().from_usd(1.0, &SupportedCurrency::Bitcoin, 0).is_none());
+}
@@ -1,6 +1,7 @@
 use std::sync::Arc;
 use crossbeam::atomic::AtomicCell;
-use eframe::egui::Ui;
+use eframe::egui::{Color32, Ui};
+use redgold_schema::EasyJson;
 use redgold_schema::structs::{ErrorInfo, NetworkEnvironment, Transaction};
 use std::collections::HashMap;
 use eframe::egui;
@@ -10,7 +11,14 @@ use std::time::Duration;
 use log::{error, info};
 use crate::gui::app_loop;
 use crate::gui::app_loop::LocalState;
+use crate::core::internal_message::Channel;
+use crate::gui::wallet_tab::StateUpdate;
 use crate::gui::tables::text_table;
+use crate::gui::watch_list::{watch_list, WatchListState};
+use crate::gui::order_book::{cold_sweep_view, order_book_chart, OrderBookState};
+use crate::gui::embedded_node::{embedded_node_view, EmbeddedNodeState};
+use crate::observability::metrics_registry::metrics_snapshot;
+use crate::observability::error_reports::{dismiss_last_crash, ErrorReport, last_crash};
 use crate::node_config::NodeConfig;
 use crate::util;
 
@@ -20,10 +28,27 @@ pub fn gui_status_networks() -> Vec<NetworkEnvironment> {
     // vec
 }
 
+const DASHBOARD_QUERY_INTERVAL_MS: i64 = 1000 * 15;
+const MAX_RECENT_ERRORS: usize = 10;
+const METRICS_REFRESH_INTERVAL_MS: i64 = 1000 * 2;
+
 #[derive(Clone)]
 pub struct HomeState {
     pub network_status_info: Arc<AtomicCell<Vec<NetworkStatusInfo>>>,
-    pub last_query_started_time: Option<i64>
+    pub last_query_started_time: Option<i64>,
+    pub dashboard: HealthDashboard,
+    pub last_dashboard_query_time: Option<i64>,
+    pub btc_wallet_synced_time: Option<i64>,
+    pub recent_errors: Vec<(i64, String)>,
+    pub watch_list: WatchListState,
+    pub embedded_node: EmbeddedNodeState,
+    // Crash report from the previous run, loaded once when the home screen is first shown
+    pub last_crash: Option<ErrorReport>,
+    pub last_crash_loaded: bool,
+    pub order_book: OrderBookState,
+    pub metrics: Vec<(String, f64)>,
+    pub metrics_filter: String,
+    pub metrics_updated_time: Option<i64>,
 }
 
 impl HomeState {
@@ -31,13 +56,295 @@ impl HomeState {
         Self {
             network_status_info: Arc::new(AtomicCell::new(vec![])),
             last_query_started_time: None,
+            dashboard: Default::default(),
+            last_dashboard_query_time: None,
+            btc_wallet_synced_time: None,
+            recent_errors: vec![],
+            watch_list: Default::default(),
+            embedded_node: Default::default(),
+            last_crash: None,
+            last_crash_loaded: false,
+            order_book: Default::default(),
+            metrics: vec![],
+            metrics_filter: "".to_string(),
+            metrics_updated_time: None,
+        }
+    }
+
+    pub fn record_error(&mut self, time: i64, error: String) {
+        self.recent_errors.push((time, error));
+        if self.recent_errors.len() > MAX_RECENT_ERRORS {
+            self.recent_errors.remove(0);
         }
     }
+
+    fn apply_dashboard(&mut self, time: i64, mut update: HealthDashboard, errors: Vec<String>) {
+        update.obs_height_changed_time = if update.obs_height.is_some() && update.obs_height == self.dashboard.obs_height {
+            self.dashboard.obs_height_changed_time
+        } else {
+            Some(time)
+        };
+        update.updated_time = Some(time);
+        self.dashboard = update;
+        for e in errors {
+            self.record_error(time, e);
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum HealthIndicator {
+    Green,
+    Yellow,
+    Red
+}
+
+impl HealthIndicator {
+    pub fn color(&self) -> Color32 {
+        match self {
+            HealthIndicator::Green => Color32::GREEN,
+            HealthIndicator::Yellow => Color32::YELLOW,
+            HealthIndicator::Red => Color32::RED,
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct HealthDashboard {
+    pub updated_time: Option<i64>,
+    pub reachable: bool,
+    pub peer_count: Option<i64>,
+    pub obs_height: Option<i64>,
+    pub obs_height_changed_time: Option<i64>,
+    pub pending: Option<i64>,
+    pub party_count: Option<usize>,
+    pub party_members: Option<usize>,
+    pub disk_usage_bytes: Option<u64>,
+}
+
+impl HealthDashboard {
+
+    pub fn peers_indicator(&self) -> HealthIndicator {
+        match self.peer_count {
+            Some(p) if p >= 3 => HealthIndicator::Green,
+            Some(p) if p > 0 => HealthIndicator::Yellow,
+            _ => HealthIndicator::Red
+        }
+    }
+
+    pub fn sync_indicator(&self, current_time: i64) -> HealthIndicator {
+        if !self.reachable {
+            return HealthIndicator::Red;
+        }
+        let stalled = self.obs_height_changed_time
+            .map(|t| current_time - t > 1000 * 60 * 5)
+            .unwrap_or(true);
+        if stalled { HealthIndicator::Yellow } else { HealthIndicator::Green }
+    }
+
+    pub fn party_indicator(&self) -> HealthIndicator {
+        match self.party_count {
+            Some(c) if c > 0 => HealthIndicator::Green,
+            Some(_) => HealthIndicator::Yellow,
+            None => HealthIndicator::Red
+        }
+    }
+
+    pub fn disk_indicator(&self) -> HealthIndicator {
+        let gb = 1024 * 1024 * 1024;
+        match self.disk_usage_bytes {
+            Some(b) if b < 10 * gb => HealthIndicator::Green,
+            Some(b) if b < 50 * gb => HealthIndicator::Yellow,
+            _ => HealthIndicator::Red
+        }
+    }
+}
+
+fn btc_sync_indicator(synced_time: Option<i64>, current_time: i64) -> HealthIndicator {
+    match synced_time.map(|t| current_time - t) {
+        Some(age) if age < 1000 * 60 * 10 => HealthIndicator::Green,
+        Some(age) if age < 1000 * 60 * 60 => HealthIndicator::Yellow,
+        _ => HealthIndicator::Red
+    }
+}
+
+fn errors_indicator(errors: &Vec<(i64, String)>, current_time: i64) -> HealthIndicator {
+    let recent = errors.iter().filter(|(t, _)| current_time - t < 1000 * 60 * 10).count();
+    match recent {
+        0 => HealthIndicator::Green,
+        1..=4 => HealthIndicator::Yellow,
+        _ => HealthIndicator::Red
+    }
+}
+
+fn ago(time: Option<i64>, current_time: i64) -> String {
+    time.map(|t| format!("{}s ago", (current_time - t) / 1000)).unwrap_or("never".to_string())
+}
+
+fn dashboard_row(ui: &mut Ui, indicator: HealthIndicator, name: &str, value: String) {
+    ui.colored_label(indicator.color(), "\u{25CF}");
+    ui.label(name);
+    ui.label(value);
+    ui.end_row();
+}
+
+fn health_dashboard(ui: &mut Ui, local_state: &LocalState) {
+    let home_state = &local_state.home_state;
+    let d = &home_state.dashboard;
+    let now = local_state.current_time;
+    ui.heading("Health");
+    ui.label(format!("Updated: {}", ago(d.updated_time, now)));
+    egui::Grid::new("health_dashboard").striped(true).show(ui, |ui| {
+        dashboard_row(ui, d.peers_indicator(), "Peers", d.peer_count.map(|p| p.to_string()).unwrap_or("unknown".to_string()));
+        dashboard_row(ui, d.sync_indicator(now), "Sync Status", if d.reachable {
+            format!("Online, height changed {}", ago(d.obs_height_changed_time, now))
+        } else {
+            "Offline".to_string()
+        });
+        dashboard_row(ui, d.sync_indicator(now), "Observation Height", format!(
+            "{} ({} pending)",
+            d.obs_height.map(|h| h.to_string()).unwrap_or("unknown".to_string()),
+            d.pending.unwrap_or(0)
+        ));
+        dashboard_row(ui, d.party_indicator(), "AMM Parties", match (d.party_count, d.party_members) {
+            (Some(c), Some(m)) => format!("{} parties, {} members", c, m),
+            _ => "unavailable".to_string()
+        });
+        dashboard_row(ui, btc_sync_indicator(home_state.btc_wallet_synced_time, now),
+                      "BTC Wallet Sync", ago(home_state.btc_wallet_synced_time, now));
+        dashboard_row(ui, d.disk_indicator(), "Data Store Disk", d.disk_usage_bytes
+            .map(|b| format!("{:.2} MB", b as f64 / (1024.0 * 1024.0)))
+            .unwrap_or("unknown".to_string()));
+        dashboard_row(ui, errors_indicator(&home_state.recent_errors, now), "Recent Errors",
+                      home_state.recent_errors.len().to_string());
+    });
+    for (t, e) in home_state.recent_errors.iter().rev() {
+        ui.colored_label(Color32::LIGHT_RED, format!("{} {}", ago(Some(*t), now), e));
+    }
+}
+
+pub async fn query_dashboard(node_config: NodeConfig, updates: Channel<StateUpdate>) {
+    let mut client = node_config.api_client();
+    client.timeout = Duration::from_secs(5);
+    let mut dashboard = HealthDashboard::default();
+    let mut errors = vec![];
+    match client.about().await {
+        Ok(a) => {
+            dashboard.reachable = true;
+            dashboard.peer_count = Some(a.num_active_peers);
+            dashboard.obs_height = Some(a.observation_height);
+            dashboard.pending = Some(a.pending_transactions);
+        }
+        Err(e) => errors.push(format!("Node status query failed: {}", e.json_or()))
+    }
+    match client.parties_info().await {
+        Ok(parties) => {
+            dashboard.party_members = Some(parties.iter().map(|p| p.members.len()).sum());
+            dashboard.party_count = Some(parties.len());
+        }
+        Err(e) => errors.push(format!("Party info query failed: {}", e.json_or()))
+    }
+    let order_book = client.order_book().await;
+    if let Err(e) = &order_book {
+        errors.push(format!("Order book query failed: {}", e.json_or()));
+    }
+    match node_config.data_folder.all().data_store_size_bytes() {
+        Ok(b) => dashboard.disk_usage_bytes = Some(b),
+        Err(e) => errors.push(format!("Data store size failed: {}", e.json_or()))
+    }
+    LocalState::send_update(&updates, move |ls| {
+        ls.home_state.apply_dashboard(ls.current_time, dashboard.clone(), errors.clone());
+        if let Ok(pool) = &order_book {
+            ls.home_state.order_book.pool = pool.clone();
+            ls.home_state.order_book.updated_time = Some(ls.current_time);
+        }
+    });
+}
+
+// Metrics recorded in this process, which includes the embedded node when it's running
+fn metrics_view(ui: &mut Ui, local_state: &mut LocalState) {
+    let home_state = &mut local_state.home_state;
+    if home_state.metrics_updated_time
+        .map(|t| local_state.current_time - t > METRICS_REFRESH_INTERVAL_MS)
+        .unwrap_or(true) {
+        home_state.metrics_updated_time = Some(local_state.current_time);
+        home_state.metrics = metrics_snapshot().unwrap_or_default().into_iter()
+            .sorted_by(|a, b| a.0.cmp(&b.0))
+            .collect_vec();
+    }
+    egui::CollapsingHeader::new("Metrics").show(ui, |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Filter");
+            ui.text_edit_singleline(&mut home_state.metrics_filter);
+        });
+        let filter = home_state.metrics_filter.to_lowercase();
+        let mut data = vec![vec!["Metric".to_string(), "Value".to_string()]];
+        for (name, value) in home_state.metrics.iter().filter(|(n, _)| n.contains(&filter)) {
+            data.push(vec![name.clone(), value.to_string()]);
+        }
+        if data.len() == 1 {
+            ui.label("No metrics recorded");
+        } else {
+            egui::ScrollArea::vertical().id_source("metrics_scroll").max_height(300.0).show(ui, |ui| {
+                text_table(ui, data);
+            });
+        }
+    });
+}
+
+fn last_crash_view(ui: &mut Ui, local_state: &mut LocalState) {
+    if !local_state.home_state.last_crash_loaded {
+        local_state.home_state.last_crash_loaded = true;
+        local_state.home_state.last_crash = last_crash(&local_state.node_config);
+    }
+    let crash = match local_state.home_state.last_crash.as_ref() {
+        Some(c) => c.clone(),
+        None => return,
+    };
+    ui.colored_label(Color32::LIGHT_RED, format!(
+        "Crashed last run {} on version {}: {}", ago(Some(crash.time), local_state.current_time), crash.version, crash.message
+    ));
+    if let Some(l) = crash.location.as_ref() {
+        ui.label(format!("At {}", l));
+    }
+    egui::CollapsingHeader::new("Logs before crash").show(ui, |ui| {
+        for line in crash.log_tail.iter() {
+            ui.label(line);
+        }
+    });
+    if ui.button("Dismiss").clicked() {
+        match dismiss_last_crash(&local_state.node_config) {
+            Ok(_) => local_state.home_state.last_crash = None,
+            Err(e) => local_state.home_state.record_error(local_state.current_time, e.json_or()),
+        }
+    }
+    ui.separator();
 }
 
 pub fn home_screen(ui: &mut Ui, _ctx: &egui::Context, local_state: &mut LocalState) {
     ui.heading("Home");
     ui.separator();
+    last_crash_view(ui, local_state);
+    if local_state.home_state.last_dashboard_query_time
+        .map(|q| (local_state.current_time - q) > DASHBOARD_QUERY_INTERVAL_MS)
+        .unwrap_or(true) {
+        local_state.home_state.last_dashboard_query_time = Some(local_state.current_time);
+        let nc = local_state.node_config.clone();
+        let updates = local_state.updates.clone();
+        tokio::spawn(async move {
+            query_dashboard(nc, updates).await
+        });
+    }
+    embedded_node_view(ui, local_state);
+    ui.separator();
+    health_dashboard(ui, local_state);
+    metrics_view(ui, local_state);
+    ui.separator();
+    order_book_chart(ui, local_state);
+    cold_sweep_view(ui, local_state);
+    ui.separator();
+    watch_list(ui, local_state);
+    ui.separator();
     let home_state = &mut local_state.home_state;
     let nc2 = local_state.node_config.clone();
     let arc = home_state.network_status_info.clone();
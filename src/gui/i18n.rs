@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::OnceLock;
+use strum_macros::EnumIter;
+
+/// GUI display language. English strings double as the translation keys, so any string without
+/// a translation falls back to English.
+#[derive(Clone, Copy, Debug, PartialEq, EnumIter)]
+pub enum Locale {
+    English,
+    Spanish,
+}
+
+impl Locale {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Locale::English => "en",
+            Locale::Spanish => "es",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "en" => Some(Locale::English),
+            "es" => Some(Locale::Spanish),
+            _ => None,
+        }
+    }
+
+    // Shown in its own language so it can be found regardless of the current locale
+    pub fn native_name(&self) -> &'static str {
+        match self {
+            Locale::English => "English",
+            Locale::Spanish => "Español",
+        }
+    }
+
+    fn table(&self) -> Option<&'static HashMap<&'static str, &'static str>> {
+        static SPANISH_TABLE: OnceLock<HashMap<&'static str, &'static str>> = OnceLock::new();
+        match self {
+            Locale::English => None,
+            Locale::Spanish => Some(SPANISH_TABLE.get_or_init(|| SPANISH.iter().cloned().collect())),
+        }
+    }
+}
+
+static CURRENT: AtomicU8 = AtomicU8::new(0);
+
+pub fn set_locale(locale: Locale) {
+    CURRENT.store(locale as u8, Ordering::Relaxed);
+}
+
+pub fn locale() -> Locale {
+    match CURRENT.load(Ordering::Relaxed) {
+        1 => Locale::Spanish,
+        _ => Locale::English,
+    }
+}
+
+/// Translates an English GUI string into the current locale.
+pub fn tr(text: &str) -> String {
+    tr_in(locale(), text)
+}
+
+pub fn tr_in(locale: Locale, text: &str) -> String {
+    locale.table()
+        .and_then(|t| t.get(text))
+        .map(|t| t.to_string())
+        .unwrap_or(text.to_string())
+}
+
+const SPANISH: &[(&str, &str)] = &[
+    // Navigation
+    ("Home", "Inicio"),
+    ("Keys", "Claves"),
+    ("Transact", "Transacciones"),
+    ("Portfolio", "Cartera"),
+    ("Identity", "Identidad"),
+    ("Contacts", "Contactos"),
+    ("Address", "Direcciones"),
+    ("Servers", "Servidores"),
+    ("Peers", "Pares"),
+    ("Ratings", "Valoraciones"),
+    ("Settings", "Ajustes"),
+    ("OTP", "OTP"),
+    ("Network: ", "Red: "),
+    // Settings
+    ("Language", "Idioma"),
+    ("UI scale", "Escala de la interfaz"),
+    ("Font size", "Tamaño de letra"),
+    ("Shortcuts: Ctrl+Tab / Ctrl+Shift+Tab switch tabs, Alt+1 to Alt+9 open a tab", "Atajos: Ctrl+Tab / Ctrl+Mayús+Tab cambian de pestaña, Alt+1 a Alt+9 abren una pestaña"),
+    ("Local stored state json", "JSON del estado local almacenado"),
+    ("Save Json", "Guardar JSON"),
+    ("Register redgold: links", "Registrar enlaces redgold:"),
+    ("Open payment links in this wallet", "Abrir enlaces de pago en esta cartera"),
+    ("Hot Wallet Spending Limits", "Límites de gasto de la cartera caliente"),
+    ("Confirm hot wallet sends above a per-session limit", "Confirmar envíos por encima de un límite por sesión"),
+    ("Session limit (USD)", "Límite por sesión (USD)"),
+    ("Confirmation countdown (seconds)", "Cuenta atrás de confirmación (segundos)"),
+    ("Require session password instead of countdown", "Pedir la contraseña de sesión en lugar de la cuenta atrás"),
+    ("Store hot keys in the OS keychain", "Guardar las claves calientes en el llavero del sistema"),
+    ("Keeps mnemonics and private keys out of the local database file", "Mantiene las mnemónicas y claves privadas fuera del archivo de base de datos local"),
+    ("Keys moved:", "Claves movidas:"),
+    ("Keychain migration failed:", "Falló la migración al llavero:"),
+    // Wallet
+    ("Show BTC Info / Enable BTC", "Mostrar información BTC / Activar BTC"),
+    ("Show UTXOs / Coin Control", "Mostrar UTXOs / Control de monedas"),
+    ("Show Swaps / Stakes", "Mostrar intercambios / participaciones"),
+    ("Show Transaction History", "Mostrar historial de transacciones"),
+    ("Destination Address", "Dirección de destino"),
+    ("Amount", "Cantidad"),
+    ("Currency", "Moneda"),
+    ("Valid", "Válido"),
+    ("Invalid", "Inválido"),
+    ("Mark as Stake", "Marcar como participación"),
+    ("Mark as Swap", "Marcar como intercambio"),
+    ("Refresh balance to load UTXOs", "Actualiza el saldo para cargar los UTXOs"),
+    ("Refresh balance to load swaps and stakes", "Actualiza el saldo para cargar intercambios y participaciones"),
+    ("Refresh balance to load transaction history", "Actualiza el saldo para cargar el historial"),
+    ("Click a transaction to copy its hash", "Pulsa una transacción para copiar su hash"),
+    ("Pending", "Pendiente"),
+    ("Rebroadcast", "Retransmitir"),
+    ("Clear Selection", "Borrar selección"),
+    ("Consolidate", "Consolidar"),
+    ("Name", "Nombre"),
+    ("Resolving name...", "Resolviendo nombre..."),
+    ("Resolved Address", "Dirección resuelta"),
+    ("Owner", "Propietario"),
+    ("Expires", "Caduca"),
+    ("Verified", "Verificado"),
+    ("Expired", "Caducado"),
+    ("Name lookup failed", "Error al resolver el nombre"),
+    // Tables
+    ("Filter", "Filtrar"),
+    // Peers
+    ("Refresh", "Actualizar"),
+    ("Ping", "Ping"),
+    ("Remove", "Eliminar"),
+    ("Select a peer to ping or remove it", "Selecciona un par para hacer ping o eliminarlo"),
+    // Contacts
+    ("Peer Id", "Id de par"),
+    ("Add Contact", "Añadir contacto"),
+    ("Request Payment From", "Solicitar pago a"),
+    ("Memo", "Nota"),
+    ("From Name", "Nombre del remitente"),
+    ("Send Payment Request", "Enviar solicitud de pago"),
+    ("Incoming Payment Requests", "Solicitudes de pago recibidas"),
+    ("Accept", "Aceptar"),
+    ("Decline", "Rechazar"),
+    ("Select a request to accept or decline it", "Selecciona una solicitud para aceptarla o rechazarla"),
+    ("Contact has no peer id to send requests to", "El contacto no tiene id de par para enviar solicitudes"),
+    ("Select a contact to request a payment", "Selecciona un contacto para solicitar un pago"),
+];
+
+#[test]
+fn spanish_translations_are_complete_entries() {
+    use itertools::Itertools;
+    assert!(SPANISH.iter().all(|(k, v)| !k.is_empty() && !v.is_empty()));
+    assert_eq!(SPANISH.iter().map(|(k, _)| k).unique().count(), SPANISH.len());
+    assert_eq!(tr_in(Locale::Spanish, "Settings"), "Ajustes");
+    assert_eq!(tr_in(Locale::Spanish, "Untranslated"), "Untranslated");
+    assert_eq!(tr_in(Locale::English, "Settings"), "Settings");
+    assert_eq!(Locale::from_code(Locale::Spanish.code()), Some(Locale::Spanish));
+}
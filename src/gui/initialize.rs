@@ -37,9 +37,7 @@ pub async fn attempt_start(nc: NodeConfig
     let app = gui::ClientApp::from(ri, nc).await?;
 
     let native_options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default()
-            .with_inner_size([800.0, 600.0])
-            .with_min_inner_size([800.0, 600.0])
+        viewport: app.local_state.gui_layout.viewport()
             .with_icon(
                 // NOE: Adding an icon is optional
                 eframe::icon_data::from_png_bytes(&include_bytes!("../resources/svg_rg_2_crop.png")[..])
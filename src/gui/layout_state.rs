@@ -0,0 +1,65 @@
+use eframe::egui;
+use log::error;
+use serde::{Deserialize, Serialize};
+use redgold_data::config::ConfigKey;
+use redgold_schema::EasyJson;
+use crate::gui::app_loop::LocalState;
+
+pub const GUI_LAYOUT_STATE: ConfigKey<GuiLayoutState> = ConfigKey::new("gui_layout_state");
+
+// Window changes are written at most this often while dragging or resizing
+const SAVE_INTERVAL_MS: i64 = 2000;
+pub const DEFAULT_WINDOW_SIZE: [f32; 2] = [800.0, 600.0];
+
+/// Window geometry and last selections, restored on the next GUI start.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct GuiLayoutState {
+    // Inner window size and outer position, in points
+    pub window_size: Option<[f32; 2]>,
+    pub window_position: Option<[f32; 2]>,
+    pub active_tab: Option<String>,
+    pub selected_account: Option<String>,
+    pub selected_key_name: Option<String>,
+    pub active_xpub: Option<String>,
+}
+
+impl GuiLayoutState {
+    pub fn viewport(&self) -> egui::ViewportBuilder {
+        let size = self.window_size.unwrap_or(DEFAULT_WINDOW_SIZE);
+        let mut vp = egui::ViewportBuilder::default()
+            .with_inner_size([size[0].max(DEFAULT_WINDOW_SIZE[0]), size[1].max(DEFAULT_WINDOW_SIZE[1])])
+            .with_min_inner_size(DEFAULT_WINDOW_SIZE);
+        if let Some(p) = self.window_position {
+            vp = vp.with_position(p);
+        }
+        vp
+    }
+}
+
+/// Persists the layout when it changed since the last save, throttled while the window moves.
+pub fn save_layout_if_changed(ctx: &egui::Context, ls: &mut LocalState, current: GuiLayoutState) {
+    let mut current = current;
+    ctx.input(|i| {
+        let vp = i.viewport();
+        if let Some(r) = vp.inner_rect {
+            current.window_size = Some([r.width(), r.height()]);
+        }
+        if let Some(r) = vp.outer_rect {
+            current.window_position = Some([r.min.x, r.min.y]);
+        }
+    });
+    if current == ls.gui_layout {
+        return;
+    }
+    if ls.gui_layout_saved_time.map(|t| ls.current_time - t < SAVE_INTERVAL_MS).unwrap_or(false) {
+        return;
+    }
+    ls.gui_layout = current.clone();
+    ls.gui_layout_saved_time = Some(ls.current_time);
+    let ds = ls.secure_or();
+    tokio::spawn(async move {
+        if let Err(e) = ds.config_store.set_typed(&GUI_LAYOUT_STATE, &current).await {
+            error!("Failed to save GUI layout: {}", e.json_or());
+        }
+    });
+}
@@ -19,6 +19,15 @@ pub mod top_panel;
 pub mod webcam;
 pub mod image_capture;
 pub mod qr_render;
+pub mod watch_list;
+pub mod fiat;
+pub mod observation_view;
+pub mod embedded_node;
+pub mod deep_link;
+pub mod order_book;
+pub mod i18n;
+pub mod layout_state;
+pub mod os_keystore;
 
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
 #[cfg_attr(feature = "persistence", derive(serde::Deserialize, serde::Serialize))]
@@ -0,0 +1,88 @@
+use std::time::Duration;
+use eframe::egui::{Color32, Ui};
+use redgold_schema::{EasyJson, RgResult};
+use redgold_schema::structs::Hash;
+use crate::api::explorer::TransactionObservationsResponse;
+use crate::core::internal_message::Channel;
+use crate::gui::app_loop::LocalState;
+use crate::gui::tables::text_table;
+use crate::gui::wallet_tab::StateUpdate;
+use crate::node_config::NodeConfig;
+
+#[derive(Clone, Default)]
+pub struct ObservationViewState {
+    pub hash_input: String,
+    pub loading: bool,
+    pub result: Option<RgResult<TransactionObservationsResponse>>,
+}
+
+pub async fn query_observations(node_config: NodeConfig, hash: String, updates: Channel<StateUpdate>) {
+    let mut client = node_config.api_client();
+    client.timeout = Duration::from_secs(10);
+    let res = match Hash::from_hex(hash) {
+        Ok(h) => client.transaction_observations(&h).await,
+        Err(e) => Err(e)
+    };
+    LocalState::send_update(&updates, move |ls| {
+        ls.wallet_state.observation_view.loading = false;
+        ls.wallet_state.observation_view.result = Some(res.clone());
+    });
+}
+
+/// Lists which peers attested to a transaction, when and at what observation height.
+pub fn observation_view(ui: &mut Ui, ls: &mut LocalState) {
+    let state = &mut ls.wallet_state.observation_view;
+    let mut lookup = false;
+    ui.horizontal(|ui| {
+        ui.label("Transaction Hash");
+        ui.text_edit_singleline(&mut state.hash_input);
+        lookup = ui.button("Lookup Attestations").clicked();
+    });
+    if lookup && !state.loading {
+        state.loading = true;
+        state.result = None;
+        let nc = ls.node_config.clone();
+        let hash = state.hash_input.trim().to_string();
+        tokio::spawn(query_observations(nc, hash, ls.updates.clone()));
+    }
+    let state = &ls.wallet_state.observation_view;
+    if state.loading {
+        ui.label("Loading...");
+    }
+    match &state.result {
+        Some(Ok(r)) => {
+            ui.label(format!("Observed by {} nodes, {} accepted", r.num_peers, r.num_accepted));
+            let (status, color) = if r.finalized {
+                ("Finalized", Color32::GREEN)
+            } else {
+                ("Awaiting acceptance", Color32::YELLOW)
+            };
+            ui.horizontal(|ui| {
+                ui.label(format!("Acceptance weight {:.1} / {:.1}", r.acceptance_weight, r.acceptance_threshold));
+                ui.colored_label(color, status);
+            });
+            let mut rows = vec![vec![
+                "Node".to_string(), "Peer".to_string(), "Trust".to_string(), "State".to_string(),
+                "Type".to_string(), "Time".to_string(), "Height".to_string()
+            ]];
+            for a in r.attestations.iter() {
+                rows.push(vec![
+                    a.node_id.chars().take(16).collect(),
+                    a.peer_id.as_ref().map(|p| p.chars().take(16).collect()).unwrap_or("".to_string()),
+                    a.trust.map(|t| format!("{:.1}", t)).unwrap_or("".to_string()),
+                    a.state.clone(),
+                    a.observation_type.clone(),
+                    a.time.to_string(),
+                    a.height.map(|h| h.to_string()).unwrap_or("".to_string()),
+                ]);
+            }
+            if !r.attestations.is_empty() {
+                text_table(ui, rows);
+            }
+        }
+        Some(Err(e)) => {
+            ui.colored_label(Color32::LIGHT_RED, format!("Lookup failed: {}", e.json_or()));
+        }
+        None => {}
+    }
+}
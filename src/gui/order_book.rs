@@ -0,0 +1,127 @@
+use eframe::egui;
+use eframe::egui::{Color32, Ui};
+use egui_plot::{Legend, Line, Plot, PlotPoints, Points};
+use itertools::Itertools;
+use redgold_schema::EasyJson;
+use redgold_schema::structs::ControlColdSweepRequest;
+use crate::api::control_api::ControlClient;
+use crate::api::explorer::AddressPoolInfo;
+use crate::gui::app_loop::LocalState;
+use crate::multiparty::watcher::BidAsk;
+
+#[derive(Clone, Default)]
+pub struct OrderBookState {
+    pub pool: Option<AddressPoolInfo>,
+    pub updated_time: Option<i64>,
+    pub sweep_confirm: bool,
+    pub sweep_pending: bool,
+    pub sweep_result: Option<String>,
+}
+
+/// Cumulative RDG depth against price in BTC per RDG, outward from the center for each side.
+/// Bids are quoted in RDG per BTC with BTC volume, so they're inverted onto the same axes.
+pub fn depth_points(bid_ask: &BidAsk) -> (Vec<[f64; 2]>, Vec<[f64; 2]>) {
+    let mut cumulative = 0.0;
+    let bids = bid_ask.bids.iter()
+        .filter(|pv| pv.price > 0.0)
+        .map(|pv| {
+            cumulative += pv.volume as f64 * pv.price / 1e8;
+            [1.0 / pv.price, cumulative]
+        }).collect_vec();
+    let mut cumulative = 0.0;
+    let asks = bid_ask.asks.iter()
+        .map(|pv| {
+            cumulative += pv.volume as f64 / 1e8;
+            [pv.price, cumulative]
+        }).collect_vec();
+    (bids, asks)
+}
+
+pub fn order_book_chart(ui: &mut Ui, ls: &LocalState) {
+    ui.heading("AMM Order Book");
+    let state = &ls.home_state.order_book;
+    let pool = match &state.pool {
+        Some(p) => p,
+        None => {
+            ui.label("No AMM party order book available");
+            return;
+        }
+    };
+    let (bids, asks) = depth_points(&pool.bid_ask);
+    ui.label(format!(
+        "Center {:.2} RDG/BTC, pool {:.4} RDG / {:.8} BTC",
+        pool.bid_ask.center_price, pool.rdg_balance, pool.btc_balance
+    ));
+    if let Some(a) = &pool.admission {
+        ui.colored_label(Color32::YELLOW, format!(
+            "Deposits paused, {} unfulfilled orders, BTC fee rate {:.1} sat/vB",
+            a.unfulfilled_orders, a.btc_fee_rate.unwrap_or(0.0)
+        ));
+    }
+    let fills = pool.recent_fills.iter()
+        .map(|f| [f.price_btc_per_rdg, f.rdg_amount as f64 / 1e8])
+        .collect_vec();
+    Plot::new("amm_order_book")
+        .height(250.0)
+        .legend(Legend::default())
+        .x_axis_label("BTC per RDG")
+        .y_axis_label("Cumulative RDG")
+        .show(ui, |plot_ui| {
+            plot_ui.line(Line::new(PlotPoints::from(bids)).color(Color32::GREEN).name("Bids"));
+            plot_ui.line(Line::new(PlotPoints::from(asks)).color(Color32::RED).name("Asks"));
+            plot_ui.points(Points::new(PlotPoints::from(fills)).radius(3.0).color(Color32::YELLOW).name("Recent fills"));
+        });
+}
+
+/// Operator preview and execution of the cold storage sweep against the local node.
+pub fn cold_sweep_view(ui: &mut Ui, ls: &mut LocalState) {
+    egui::CollapsingHeader::new("Cold Storage Sweep").show(ui, |ui| {
+        ui.label("Moves party funds above the configured hot balance caps to the cold addresses");
+        let pending = ls.home_state.order_book.sweep_pending;
+        ui.horizontal(|ui| {
+            if ui.add_enabled(!pending, egui::Button::new("Preview")).clicked() {
+                start_cold_sweep(ls, false);
+            }
+            ui.checkbox(&mut ls.home_state.order_book.sweep_confirm, "Confirm");
+            let confirmed = ls.home_state.order_book.sweep_confirm;
+            if ui.add_enabled(!pending && confirmed, egui::Button::new("Execute Sweep")).clicked() {
+                ls.home_state.order_book.sweep_confirm = false;
+                start_cold_sweep(ls, true);
+            }
+        });
+        if pending {
+            ui.spinner();
+        }
+        if let Some(r) = &ls.home_state.order_book.sweep_result {
+            ui.label(r);
+        }
+    });
+}
+
+fn start_cold_sweep(ls: &mut LocalState, execute: bool) {
+    ls.home_state.order_book.sweep_pending = true;
+    let client = ControlClient::local(ls.node_config.control_port())
+        .with_api_key(ls.node_config.api_access.control_api_key.clone());
+    let updates = ls.updates.clone();
+    tokio::spawn(async move {
+        let mut req = ControlColdSweepRequest::default();
+        req.execute = execute;
+        let result = match client.cold_sweep(req).await {
+            Ok(r) => r.json_pretty_or(),
+            Err(e) => format!("Cold sweep failed: {}", e.json_or()),
+        };
+        LocalState::send_update(&updates, move |ls| {
+            ls.home_state.order_book.sweep_pending = false;
+            ls.home_state.order_book.sweep_result = Some(result.clone());
+        });
+    });
+}
+
+#[test]
+fn depth_points_accumulate_outward() {
+    let ba = BidAsk::generate_default(1000 * 100_000_000, 100_000_000, 450.0, 0.002);
+    let (bids, asks) = depth_points(&ba);
+    assert!(!bids.is_empty() && !asks.is_empty());
+    assert!(bids.windows(2).all(|w| w[1][1] >= w[0][1] && w[1][0] <= w[0][0]));
+    assert!(asks.windows(2).all(|w| w[1][1] >= w[0][1] && w[1][0] >= w[0][0]));
+}
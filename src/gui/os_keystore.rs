@@ -0,0 +1,116 @@
+use itertools::Itertools;
+use redgold_schema::{error_info, RgResult};
+use redgold_schema::local_stored_state::LocalStoredState;
+use crate::node_config::NodeConfig;
+
+// Prefix of the keychain service hot key entries are stored under
+const KEYSTORE_SERVICE: &str = "redgold";
+
+/// Keychain namespace for a node config, so wallets on different networks sharing a key name
+/// never overwrite each other's entries.
+pub fn keystore_namespace(node_config: &NodeConfig) -> String {
+    format!("{}:{}", KEYSTORE_SERVICE, node_config.network.to_std_string())
+}
+
+pub fn mnemonic_account(name: &str) -> String {
+    format!("mnemonic:{}", name)
+}
+
+pub fn private_key_account(name: &str) -> String {
+    format!("private_key:{}", name)
+}
+
+fn entry(namespace: &str, account: &str) -> RgResult<keyring::Entry> {
+    keyring::Entry::new(namespace, account)
+        .map_err(|e| error_info(format!("OS keystore unavailable: {}", e.to_string())))
+}
+
+pub fn store_secret(namespace: &str, account: &str, secret: &str) -> RgResult<()> {
+    entry(namespace, account)?.set_password(secret)
+        .map_err(|e| error_info(format!("Failed to store {} in OS keystore: {}", account, e.to_string())))
+}
+
+pub fn load_secret(namespace: &str, account: &str) -> RgResult<Option<String>> {
+    match entry(namespace, account)?.get_password() {
+        Ok(s) => Ok(Some(s)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(error_info(format!("Failed to load {} from OS keystore: {}", account, e.to_string()))),
+    }
+}
+
+pub fn delete_secret(namespace: &str, account: &str) -> RgResult<()> {
+    match entry(namespace, account)?.delete_password() {
+        Ok(_) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(error_info(format!("Failed to delete {} from OS keystore: {}", account, e.to_string()))),
+    }
+}
+
+/// Moves every hot secret not yet in the keychain into it. Plaintext is only dropped from disk
+/// on the next persist, after all writes succeeded. Returns the number of secrets moved.
+pub fn migrate_to_os_keystore(namespace: &str, state: &mut LocalStoredState) -> RgResult<usize> {
+    let mut moved = 0;
+    for m in state.mnemonics.iter_mut().flatten().filter(|m| !m.os_keystore.unwrap_or(false)) {
+        store_secret(namespace, &mnemonic_account(&m.name), &m.mnemonic)?;
+        m.os_keystore = Some(true);
+        moved += 1;
+    }
+    for k in state.private_keys.iter_mut().flatten().filter(|k| !k.os_keystore.unwrap_or(false)) {
+        store_secret(namespace, &private_key_account(&k.name), &k.key_hex)?;
+        k.os_keystore = Some(true);
+        moved += 1;
+    }
+    state.use_os_keystore = Some(true);
+    Ok(moved)
+}
+
+/// Returns keychain held secrets to the local stored state and removes them from the keychain.
+pub fn migrate_from_os_keystore(namespace: &str, state: &mut LocalStoredState) -> RgResult<usize> {
+    load_os_keystore_secrets(namespace, state)?;
+    let mut moved = 0;
+    for m in state.mnemonics.iter_mut().flatten().filter(|m| m.os_keystore.unwrap_or(false)) {
+        delete_secret(namespace, &mnemonic_account(&m.name))?;
+        m.os_keystore = None;
+        moved += 1;
+    }
+    for k in state.private_keys.iter_mut().flatten().filter(|k| k.os_keystore.unwrap_or(false)) {
+        delete_secret(namespace, &private_key_account(&k.name))?;
+        k.os_keystore = None;
+        moved += 1;
+    }
+    state.use_os_keystore = Some(false);
+    Ok(moved)
+}
+
+/// Fills in the keychain held secrets after loading the state from disk, returning the names
+/// of any entries missing from the keychain.
+pub fn load_os_keystore_secrets(namespace: &str, state: &mut LocalStoredState) -> RgResult<Vec<String>> {
+    let mut missing = vec![];
+    for m in state.mnemonics.iter_mut().flatten().filter(|m| m.os_keystore.unwrap_or(false)) {
+        match load_secret(namespace, &mnemonic_account(&m.name))? {
+            Some(s) => m.mnemonic = s,
+            None => missing.push(m.name.clone()),
+        }
+    }
+    for k in state.private_keys.iter_mut().flatten().filter(|k| k.os_keystore.unwrap_or(false)) {
+        match load_secret(namespace, &private_key_account(&k.name))? {
+            Some(s) => k.key_hex = s,
+            None => missing.push(k.name.clone()),
+        }
+    }
+    Ok(missing.into_iter().unique().collect_vec())
+}
+
+#[test]
+fn keystore_accounts_are_namespaced_by_kind() {
+    use redgold_schema::structs::NetworkEnvironment;
+    let mut main = NodeConfig::default();
+    main.network = NetworkEnvironment::Main;
+    let mut test = NodeConfig::default();
+    test.network = NetworkEnvironment::Test;
+    assert_ne!(keystore_namespace(&main), keystore_namespace(&test));
+    assert_ne!(mnemonic_account("hot"), private_key_account("hot"));
+    let mut state = LocalStoredState::default();
+    // Nothing to move should never touch the keychain
+    assert_eq!(migrate_from_os_keystore("redgold:test", &mut state).ok(), Some(0));
+    assert_eq!(state.use_os_keystore, Some(false));
+}
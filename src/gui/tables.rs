@@ -1,6 +1,8 @@
 use eframe::egui;
-use eframe::egui::Ui;
+use eframe::egui::{RichText, Ui};
+use itertools::Itertools;
 use egui_extras::{Column, TableBuilder};
+use crate::gui::i18n::tr;
 
 pub fn text_table(ui: &mut Ui, data: Vec<Vec<String>>) {
 
@@ -43,3 +45,179 @@ pub fn text_table(ui: &mut Ui, data: Vec<Vec<String>>) {
     });
 
 }
+
+const DEFAULT_PAGE_SIZE: usize = 25;
+
+/// Sort, filter, page and selection state of a `data_table`, kept by the owning view.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DataTableState {
+    pub sort_column: Option<usize>,
+    pub sort_ascending: bool,
+    pub filter: String,
+    pub page: usize,
+    pub page_size: usize,
+    // Index into the unfiltered rows, excluding the header
+    pub selected: Option<usize>,
+}
+
+impl Default for DataTableState {
+    fn default() -> Self {
+        Self {
+            sort_column: None,
+            sort_ascending: true,
+            filter: "".to_string(),
+            page: 0,
+            page_size: DEFAULT_PAGE_SIZE,
+            selected: None,
+        }
+    }
+}
+
+impl DataTableState {
+
+    pub fn toggle_sort(&mut self, column: usize) {
+        if self.sort_column == Some(column) {
+            self.sort_ascending = !self.sort_ascending;
+        } else {
+            self.sort_column = Some(column);
+            self.sort_ascending = true;
+        }
+    }
+
+    fn num_pages(&self, rows: usize) -> usize {
+        ((rows + self.page_size.max(1) - 1) / self.page_size.max(1)).max(1)
+    }
+
+    /// Indexes of the rows matching the filter in sorted order, numbers compared numerically.
+    pub fn filtered_sorted(&self, rows: &[Vec<String>]) -> Vec<usize> {
+        let filter = self.filter.to_lowercase();
+        let mut indexes = (0..rows.len())
+            .filter(|i| filter.is_empty() || rows[*i].iter().any(|c| c.to_lowercase().contains(&filter)))
+            .collect_vec();
+        if let Some(c) = self.sort_column {
+            let cell = |i: &usize| rows[*i].get(c).cloned().unwrap_or("".to_string());
+            indexes.sort_by(|a, b| {
+                let (a, b) = (cell(a), cell(b));
+                let ord = match (a.parse::<f64>(), b.parse::<f64>()) {
+                    (Ok(x), Ok(y)) => x.total_cmp(&y),
+                    _ => a.cmp(&b),
+                };
+                if self.sort_ascending { ord } else { ord.reverse() }
+            });
+        }
+        indexes
+    }
+
+    /// Indexes of the rows shown on the current page, clamping the page to the last one.
+    pub fn page_rows(&mut self, rows: &[Vec<String>]) -> Vec<usize> {
+        let indexes = self.filtered_sorted(rows);
+        self.page = self.page.min(self.num_pages(indexes.len()) - 1);
+        indexes.into_iter().skip(self.page * self.page_size).take(self.page_size).collect_vec()
+    }
+}
+
+/// Table with a header row followed by value rows like `text_table`, with sortable headers, a
+/// filter box, pagination and row selection. `on_select` receives the index of a clicked row
+/// among the value rows along with its cells.
+pub fn data_table(
+    ui: &mut Ui,
+    id: impl Into<String>,
+    state: &mut DataTableState,
+    data: Vec<Vec<String>>,
+    mut on_select: impl FnMut(usize, &Vec<String>),
+) {
+    if data.len() == 0 {
+        return;
+    }
+    let id = id.into();
+    let headers = data.get(0).expect("").clone();
+    let rows = &data[1..];
+
+    ui.horizontal(|ui| {
+        ui.label(tr("Filter"));
+        if ui.text_edit_singleline(&mut state.filter).changed() {
+            state.page = 0;
+        }
+    });
+    let visible = state.page_rows(rows);
+    let matching = state.filtered_sorted(rows).len();
+
+    let text_height = 25.0;
+    let mut sort_clicked = None;
+    let mut clicked = None;
+    // Scoped so several tables in one view keep separate scroll state
+    ui.push_id(id, |ui| {
+        let mut table = TableBuilder::new(ui)
+            .striped(true)
+            .resizable(false)
+            .sense(egui::Sense::click())
+            .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+            .min_scrolled_height(0.0);
+        for _ in 0..headers.len() {
+            table = table.column(Column::auto());
+        };
+
+        table
+            .header(text_height, |mut header| {
+                for (i, h) in headers.iter().enumerate() {
+                    header.col(|ui| {
+                        let arrow = match (state.sort_column == Some(i), state.sort_ascending) {
+                            (true, true) => " ^",
+                            (true, false) => " v",
+                            _ => "",
+                        };
+                        if ui.button(RichText::new(format!("{}{}", h, arrow)).strong()).clicked() {
+                            sort_clicked = Some(i);
+                        }
+                    });
+                }
+            }).body(|body| {
+            body.rows(text_height, visible.len(), |mut row| {
+                let index = visible[row.index()];
+                row.set_selected(state.selected == Some(index));
+                for cell in rows[index].iter() {
+                    row.col(|ui| {
+                        ui.label(cell);
+                    });
+                }
+                if row.response().clicked() {
+                    clicked = Some(index);
+                }
+            });
+        });
+    });
+    if let Some(c) = sort_clicked {
+        state.toggle_sort(c);
+    }
+    if let Some(i) = clicked {
+        state.selected = Some(i);
+        on_select(i, &rows[i]);
+    }
+
+    let pages = state.num_pages(matching);
+    ui.horizontal(|ui| {
+        if ui.add_enabled(state.page > 0, egui::Button::new("<")).clicked() {
+            state.page -= 1;
+        }
+        ui.label(format!("Page {} of {} ({} rows)", state.page + 1, pages, matching));
+        if ui.add_enabled(state.page + 1 < pages, egui::Button::new(">")).clicked() {
+            state.page += 1;
+        }
+    });
+}
+
+#[test]
+fn data_table_filters_sorts_and_pages() {
+    let rows = (0..30).map(|i| vec![format!("host{}", i), (i * 7 % 30).to_string()]).collect_vec();
+    let mut state = DataTableState::default();
+    state.page_size = 10;
+    state.toggle_sort(1);
+    state.toggle_sort(1);
+    let sorted = state.filtered_sorted(&rows);
+    assert_eq!(rows[sorted[0]][1], "29");
+    state.page = 9;
+    assert_eq!(state.page_rows(&rows).len(), 10);
+    assert_eq!(state.page, 2);
+    state.filter = "HOST2".to_string();
+    assert_eq!(state.filtered_sorted(&rows).len(), 11);
+}
@@ -1,11 +1,15 @@
 use eframe::egui;
 use eframe::egui::{Context, Ui};
 use egui_extras::{Column, TableBuilder};
+use redgold_keys::address_external::ToBitcoinAddress;
+use redgold_keys::xpub_wrapper::XpubWrapper;
 use redgold_schema::structs::SupportedCurrency;
 use crate::gui::app_loop::LocalState;
-use crate::gui::common::editable_text_input_copy;
+use crate::gui::common::{account_label, account_selector, editable_text_input_copy};
 use crate::gui::tables::text_table;
 
+// Receive addresses listed for the selected account
+const ACCOUNT_ADDRESS_ROWS: usize = 5;
 
 pub fn data_table(ui: &mut Ui, data: Vec<Vec<String>>) {
 
@@ -55,7 +59,7 @@ pub struct AddressState {
     pub new_address: String,
     pub address_name: String,
     pub contact_name: String,
-    pub xpub_name: String,
+    pub account_name: Option<String>,
     pub address_network: SupportedCurrency
 }
 
@@ -66,7 +70,7 @@ impl Default for AddressState {
             new_address: "".to_string(),
             address_name: "".to_string(),
             contact_name: "".to_string(),
-            xpub_name: "".to_string(),
+            account_name: None,
             address_network: SupportedCurrency::Redgold,
         }
     }
@@ -88,5 +92,32 @@ pub(crate) fn address_tab(ui: &mut Ui, _ctx: &Context, ls: &mut LocalState) {
         // editable_text_input_copy(ui, "Xpub Name", &mut ls.new_address, 400.);
     }
 
+    let accounts = ls.local_stored_state.accounts();
+    account_selector(ui, "Account", &accounts, &mut ls.address_state.account_name);
+    let account = ls.address_state.account_name.as_ref().and_then(|n| ls.local_stored_state.account(n));
+    if let Some(account) = account {
+        account_label(ui, &account);
+        if let Some(x) = ls.local_stored_state.account_xpub(&account) {
+            let w = XpubWrapper::new(x.xpub.clone());
+            let network = ls.node_config.network.clone();
+            let mut rows = vec![vec!["Index".to_string(), "Currency".to_string(), "Address".to_string()]];
+            for i in 0..ACCOUNT_ADDRESS_ROWS {
+                if let Ok(pk) = w.public_at(i, 0) {
+                    if account.shows_currency(&SupportedCurrency::Redgold) {
+                        if let Ok(a) = pk.address().and_then(|a| a.render_string()) {
+                            rows.push(vec![i.to_string(), "Redgold".to_string(), a]);
+                        }
+                    }
+                    if account.shows_currency(&SupportedCurrency::Bitcoin) {
+                        if let Ok(a) = pk.to_bitcoin_address(&network) {
+                            rows.push(vec![i.to_string(), "Bitcoin".to_string(), a]);
+                        }
+                    }
+                }
+            }
+            text_table(ui, rows);
+        }
+    }
+
 
 }
\ No newline at end of file
@@ -0,0 +1,247 @@
+use std::sync::{Arc, Mutex};
+use eframe::egui;
+use eframe::egui::{Color32, RichText, Ui};
+use log::error;
+use redgold_schema::{EasyJson, RgResult};
+use redgold_schema::local_stored_state::Contact;
+use redgold_schema::structs::{Address, CurrencyAmount, Hash, PaymentRequest, PeerId, SupportedCurrency};
+use crate::gui::app_loop::LocalState;
+use crate::gui::common::{editable_text_input_copy, valid_label};
+use crate::gui::i18n::tr;
+use crate::gui::tables;
+use crate::gui::tables::DataTableState;
+use crate::node_config::NodeConfig;
+
+#[derive(Clone)]
+pub struct ContactsState {
+    needs_update: bool,
+    incoming: Arc<Mutex<Vec<PaymentRequest>>>,
+    load_error: Arc<Mutex<Option<String>>>,
+    send_status: Arc<Mutex<Option<String>>>,
+    contacts_table: DataTableState,
+    requests_table: DataTableState,
+    new_contact_name: String,
+    new_contact_peer_id: String,
+    request_address: String,
+    request_amount: String,
+    request_memo: String,
+    request_from_name: String,
+}
+
+impl Default for ContactsState {
+    fn default() -> Self {
+        Self {
+            needs_update: true,
+            incoming: Arc::new(Mutex::new(vec![])),
+            load_error: Arc::new(Mutex::new(None)),
+            send_status: Arc::new(Mutex::new(None)),
+            contacts_table: Default::default(),
+            requests_table: Default::default(),
+            new_contact_name: "".to_string(),
+            new_contact_peer_id: "".to_string(),
+            request_address: "".to_string(),
+            request_amount: "".to_string(),
+            request_memo: "".to_string(),
+            request_from_name: "".to_string(),
+        }
+    }
+}
+
+pub async fn update_payment_requests(nc: NodeConfig, incoming: Arc<Mutex<Vec<PaymentRequest>>>, load_error: Arc<Mutex<Option<String>>>) {
+    match nc.api_client().payment_requests().await {
+        Ok(r) => {
+            *incoming.lock().expect("lock") = r;
+            load_error.lock().expect("lock").take();
+        }
+        Err(e) => {
+            error!("Payment request load failure: {}", e.json_or());
+            load_error.lock().expect("lock").replace(e.json_or());
+        }
+    }
+}
+
+fn new_payment_request(contact: &Contact, state: &ContactsState, time: i64) -> RgResult<PaymentRequest> {
+    let address = Address::parse(state.request_address.clone())?;
+    let amount = CurrencyAmount::from_float_string(&state.request_amount)?;
+    let to_peer_id = contact.peer_id.clone();
+    let id = Hash::from_string_calculate(&format!(
+        "{}:{}:{}:{}", state.request_address, amount.amount, contact.name, time
+    )).hex();
+    let r = PaymentRequest {
+        id,
+        address: Some(address),
+        amount: Some(amount),
+        memo: state.request_memo.clone(),
+        to_peer_id,
+        from_name: state.request_from_name.clone(),
+        time,
+        forwarded: false,
+    };
+    crate::core::payment_requests::validate_payment_request(&r)?;
+    Ok(r)
+}
+
+fn payment_request_form(ui: &mut Ui, ls: &mut LocalState, contact: &Contact) {
+    ui.heading(format!("{} {}", tr("Request Payment From"), contact.name));
+    let state = &mut ls.contacts_state;
+    ui.horizontal(|ui| {
+        editable_text_input_copy(ui, tr("Address"), &mut state.request_address, 400.0);
+        valid_label(ui, Address::parse(state.request_address.clone()).is_ok());
+    });
+    editable_text_input_copy(ui, tr("Amount"), &mut state.request_amount, 200.0);
+    editable_text_input_copy(ui, tr("Memo"), &mut state.request_memo, 400.0);
+    editable_text_input_copy(ui, tr("From Name"), &mut state.request_from_name, 200.0);
+    if ui.button(tr("Send Payment Request")).clicked() {
+        match new_payment_request(contact, state, ls.current_time) {
+            Ok(r) => {
+                let status = state.send_status.clone();
+                status.lock().expect("lock").replace("Sending".to_string());
+                let nc = ls.node_config.clone();
+                tokio::spawn(async move {
+                    let res = nc.api_client().client_wrapper().send_payment_request(&r).await;
+                    let msg = match res {
+                        Ok(_) => "Payment request sent".to_string(),
+                        Err(e) => {
+                            error!("Payment request send failure: {}", e.json_or());
+                            format!("Payment request failed: {}", e.message)
+                        }
+                    };
+                    status.lock().expect("lock").replace(msg);
+                });
+            }
+            Err(e) => {
+                state.send_status.lock().expect("lock").replace(e.message);
+            }
+        }
+    }
+    if let Some(s) = state.send_status.lock().expect("lock").as_ref() {
+        ui.label(s);
+    }
+}
+
+fn add_contact_form(ui: &mut Ui, ls: &mut LocalState) {
+    let state = &mut ls.contacts_state;
+    let peer_id = PeerId::from_hex(state.new_contact_peer_id.clone()).ok()
+        .filter(|p| p.peer_id.as_ref().map(|pk| pk.validate().is_ok()).unwrap_or(false));
+    ui.horizontal(|ui| {
+        ui.label(tr("Name"));
+        ui.add(egui::TextEdit::singleline(&mut state.new_contact_name).desired_width(150.0));
+        ui.label(tr("Peer Id"));
+        ui.add(egui::TextEdit::singleline(&mut state.new_contact_peer_id).desired_width(400.0));
+        valid_label(ui, peer_id.is_some());
+    });
+    let can_add = peer_id.is_some() && !state.new_contact_name.is_empty();
+    if ui.add_enabled(can_add, egui::Button::new(tr("Add Contact"))).clicked() {
+        let contact = Contact { name: state.new_contact_name.clone(), peer_id };
+        state.new_contact_name = "".to_string();
+        state.new_contact_peer_id = "".to_string();
+        ls.local_stored_state.contacts.retain(|c| c.name != contact.name);
+        ls.local_stored_state.contacts.push(contact);
+        ls.persist_local_state_store();
+    }
+}
+
+fn incoming_requests_view(ui: &mut Ui, ls: &mut LocalState) {
+    let handled = ls.local_stored_state.handled_payment_requests.clone().unwrap_or_default();
+    let requests = ls.contacts_state.incoming.lock().expect("lock").iter()
+        .filter(|r| !handled.contains(&r.id))
+        .cloned()
+        .collect::<Vec<_>>();
+    ui.horizontal(|ui| {
+        ui.heading(tr("Incoming Payment Requests"));
+        if ui.button(tr("Refresh")).clicked() {
+            ls.contacts_state.needs_update = true;
+        }
+    });
+    if let Some(e) = ls.contacts_state.load_error.lock().expect("lock").as_ref() {
+        ui.label(RichText::new(format!("Error loading payment requests: {}", e)).color(Color32::RED));
+    }
+    let format_time = |time: i64| chrono::NaiveDateTime::from_timestamp_opt(time / 1000, 0)
+        .map(|d| d.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or(time.to_string());
+    let mut rows = vec![vec![
+        "Time".to_string(), "From".to_string(), "Amount".to_string(), "Address".to_string(), "Memo".to_string()
+    ]];
+    for r in requests.iter() {
+        rows.push(vec![
+            format_time(r.time),
+            r.from_name.clone(),
+            r.amount.as_ref().map(|a| a.to_fractional().to_string()).unwrap_or("".to_string()),
+            r.address.as_ref().and_then(|a| a.render_string().ok()).unwrap_or("".to_string()),
+            r.memo.clone(),
+        ]);
+    }
+    tables::data_table(ui, "payment_requests_table", &mut ls.contacts_state.requests_table, rows, |_, _| {});
+    let selected = ls.contacts_state.requests_table.selected.and_then(|i| requests.get(i)).cloned();
+    if let Some(r) = selected {
+        let mut handled_now = false;
+        ui.horizontal(|ui| {
+            if ui.button(tr("Accept")).clicked() {
+                ls.open_payment_request(&r);
+                handled_now = true;
+            }
+            if ui.button(tr("Decline")).clicked() {
+                handled_now = true;
+            }
+        });
+        if handled_now {
+            let mut handled = handled;
+            handled.push(r.id.clone());
+            ls.local_stored_state.handled_payment_requests = Some(handled);
+            ls.persist_local_state_store();
+            ls.contacts_state.requests_table.selected = None;
+        }
+    } else {
+        ui.label(tr("Select a request to accept or decline it"));
+    }
+}
+
+pub fn contacts_tab(ui: &mut Ui, _ctx: &egui::Context, ls: &mut LocalState) {
+    if ls.contacts_state.needs_update {
+        ls.contacts_state.needs_update = false;
+        tokio::spawn(update_payment_requests(
+            ls.node_config.clone(),
+            ls.contacts_state.incoming.clone(),
+            ls.contacts_state.load_error.clone(),
+        ));
+    }
+    ui.heading(tr("Contacts"));
+    ui.separator();
+    let contacts = ls.local_stored_state.contacts.clone();
+    let mut rows = vec![vec!["Name".to_string(), "Peer Id".to_string()]];
+    for c in contacts.iter() {
+        rows.push(vec![c.name.clone(), c.peer_id.as_ref().map(|p| p.hex_or()).unwrap_or("".to_string())]);
+    }
+    tables::data_table(ui, "contacts_table", &mut ls.contacts_state.contacts_table, rows, |_, _| {});
+    add_contact_form(ui, ls);
+    ui.separator();
+    let selected = ls.contacts_state.contacts_table.selected.and_then(|i| contacts.get(i)).cloned();
+    match selected {
+        Some(c) if c.peer_id.is_some() => payment_request_form(ui, ls, &c),
+        Some(_) => { ui.label(tr("Contact has no peer id to send requests to")); }
+        None => { ui.label(tr("Select a contact to request a payment")); }
+    }
+    ui.separator();
+    incoming_requests_view(ui, ls);
+}
+
+/// Currency of the requested amount, RDG when unset.
+pub fn payment_request_currency(r: &PaymentRequest) -> SupportedCurrency {
+    r.amount.as_ref()
+        .and_then(|a| a.currency)
+        .and_then(SupportedCurrency::from_i32)
+        .unwrap_or(SupportedCurrency::Redgold)
+}
+
+#[test]
+fn payment_request_from_form() {
+    use crate::util::current_time_millis_i64;
+    let contact = Contact { name: "bob".to_string(), peer_id: Some(PeerId::from_bytes(vec![1, 2, 3])) };
+    let mut state = ContactsState::default();
+    state.request_address = Address::script_hash("payee").expect("address").render_string().expect("render");
+    state.request_amount = "1.5".to_string();
+    let r = new_payment_request(&contact, &state, current_time_millis_i64()).expect("request");
+    assert_eq!(r.amount.expect("amount").to_fractional(), 1.5);
+    state.request_amount = "".to_string();
+    assert!(new_payment_request(&contact, &state, 0).is_err());
+}
@@ -8,6 +8,7 @@ use redgold_schema::local_stored_state::{StoredMnemonic, StoredPrivateKey};
 use crate::gui::app_loop::LocalState;
 use crate::gui::common::{data_item, editable_text_input_copy, valid_label};
 use crate::gui::wallet_tab::{StateUpdate, WalletState};
+use crate::observability::logging::Loggable;
 
 
 fn save_key_window(
@@ -40,7 +41,7 @@ fn save_key_window(
                     ls.wallet_state.is_mnemonic_or_kp = is_mnemonic.clone();
 
                     if let Some(is_m) = is_mnemonic {
-                        ls.updates.sender.send(StateUpdate {
+                        ls.updates.send_err(StateUpdate {
                             update: Box::new(
                                 move |lss: &mut LocalState| {
                                     if is_m {
@@ -48,15 +49,19 @@ fn save_key_window(
                                             name: name.clone(),
                                             mnemonic: data.clone(),
                                             persist_disk: None,
+                                            backup_verified: None,
+                                            backup_verified_time: None,
+                                            os_keystore: None,
                                         });
                                     } else {
                                         lss.upsert_private_key(StoredPrivateKey {
                                             name: name.clone(),
                                             key_hex: data.clone(),
+                                            os_keystore: None,
                                         })
                                     }
                                 })
-                        }).unwrap();
+                        }).log_error().ok();
                         ls.wallet_state.mnemonic_save_name = "".to_string();
                         ls.wallet_state.mnemonic_save_data = "".to_string();
                         LocalState::send_update(&ls.updates, |lss| {
@@ -1,19 +1,23 @@
 use eframe::egui;
-use eframe::egui::{Context, ScrollArea, TextEdit, Ui, Widget};
+use eframe::egui::{Color32, ComboBox, Context, RichText, ScrollArea, TextEdit, Ui, Widget};
 use itertools::Itertools;
 
 use redgold_keys::address_external::{ToBitcoinAddress, ToEthereumAddress};
 use redgold_keys::util::mnemonic_builder;
 use redgold_keys::util::mnemonic_support::WordsPass;
-use redgold_schema::EasyJson;
+use redgold_keys::util::shamir_backup::{slip39_recover, slip39_shares, slip39_verify_shares};
+use redgold_schema::{EasyJson, EasyJsonDeser, ErrorInfoContext};
+use redgold_schema::local_stored_state::LocalStoredState;
 use redgold_schema::structs::NetworkEnvironment;
 
 use crate::gui::app_loop::LocalState;
 use crate::gui::common::{copy_to_clipboard, editable_text_input_copy, medium_data_item, valid_label};
 use crate::gui::tables::text_table;
 use crate::util;
+use crate::observability::logging::Loggable;
 use crate::util::argon_kdf::argon2d_hash;
 use crate::util::cli::commands::generate_random_mnemonic;
+use crate::util::key_bundle::{EncryptedKeyBundle, KeyBundleContents};
 use crate::util::keys::ToPublicKeyFromLib;
 
 #[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize, Clone)]
@@ -57,7 +61,11 @@ pub struct MnemonicWindowState {
     exe_checksum: String,
     save_name: String,
     persist_disk: bool,
-    set_hot_mnemonic: bool
+    set_hot_mnemonic: bool,
+    bip85_word_count: usize,
+    bip85_index_input: String,
+    bip85_child: Option<Result<String, String>>,
+    bip85_open_child: bool,
 }
 
 impl MnemonicWindowState {
@@ -133,10 +141,138 @@ impl GenerateMnemonicState {
         self.concat_password = format!("{}{}", mod_join, metadata_join);
     }
 }
+#[derive(Clone)]
+pub struct BackupVerificationState {
+    selected_mnemonic_name: String,
+    quiz_positions: Vec<usize>,
+    quiz_answers: Vec<String>,
+    result_message: Option<String>,
+}
+
+impl Default for BackupVerificationState {
+    fn default() -> Self {
+        Self {
+            selected_mnemonic_name: "Select Mnemonic".to_string(),
+            quiz_positions: vec![],
+            quiz_answers: vec![],
+            result_message: None,
+        }
+    }
+}
+
+impl BackupVerificationState {
+    pub fn reset_quiz(&mut self) {
+        self.quiz_positions = vec![];
+        self.quiz_answers = vec![];
+        self.result_message = None;
+    }
+}
+
+#[derive(Clone)]
+pub struct ShamirBackupState {
+    threshold_input: String,
+    share_count_input: String,
+    shares: Vec<String>,
+    recovery_input: String,
+    message: Option<Result<String, String>>,
+}
+
+impl Default for ShamirBackupState {
+    fn default() -> Self {
+        Self {
+            threshold_input: "2".to_string(),
+            share_count_input: "3".to_string(),
+            shares: vec![],
+            recovery_input: "".to_string(),
+            message: None,
+        }
+    }
+}
+
+impl ShamirBackupState {
+    fn threshold_and_count(&self) -> Result<(u8, u8), String> {
+        let threshold = self.threshold_input.trim().parse::<u8>().map_err(|_| "Invalid threshold".to_string())?;
+        let count = self.share_count_input.trim().parse::<u8>().map_err(|_| "Invalid share count".to_string())?;
+        Ok((threshold, count))
+    }
+
+    fn printable(&self, threshold: u8) -> String {
+        let mut lines = vec![format!(
+            "SLIP-39 mnemonic backup, any {} of these {} shares recover the mnemonic", threshold, self.shares.len()
+        )];
+        for (i, s) in self.shares.iter().enumerate() {
+            lines.push("".to_string());
+            lines.push(format!("Share {}", i + 1));
+            lines.push(s.clone());
+        }
+        lines.join("\n")
+    }
+}
+
+#[derive(Clone)]
+pub struct KeyTransferState {
+    // Entries chosen for export, keyed by kind and name i.e. mnemonic:default
+    selected: Vec<String>,
+    path: String,
+    password: String,
+    password_confirm: String,
+    import_password: String,
+    overwrite: bool,
+    message: Option<Result<String, String>>,
+}
+
+impl Default for KeyTransferState {
+    fn default() -> Self {
+        Self {
+            selected: vec![],
+            path: "redgold_keys.json".to_string(),
+            password: "".to_string(),
+            password_confirm: "".to_string(),
+            import_password: "".to_string(),
+            overwrite: false,
+            message: None,
+        }
+    }
+}
+
+impl KeyTransferState {
+    fn is_selected(&self, key: &String) -> bool {
+        self.selected.contains(key)
+    }
+
+    fn toggle(&mut self, key: String, selected: bool) {
+        self.selected.retain(|k| k != &key);
+        if selected {
+            self.selected.push(key);
+        }
+    }
+
+    fn selected_contents(&self, lss: &LocalStoredState) -> KeyBundleContents {
+        KeyBundleContents {
+            mnemonics: lss.mnemonics.clone().unwrap_or(vec![]).into_iter()
+                .filter(|m| self.is_selected(&format!("mnemonic:{}", m.name)))
+                // Keychain storage is per machine, the importing side decides its own
+                .map(|mut m| { m.os_keystore = None; m })
+                .collect_vec(),
+            private_keys: lss.private_keys.clone().unwrap_or(vec![]).into_iter()
+                .filter(|k| self.is_selected(&format!("private_key:{}", k.name)))
+                .map(|mut k| { k.os_keystore = None; k })
+                .collect_vec(),
+            xpubs: lss.xpubs.iter()
+                .filter(|x| self.is_selected(&format!("xpub:{}", x.name)))
+                .cloned()
+                .collect_vec(),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct KeygenState {
     mnemonic_window_state: MnemonicWindowState,
     generate_mnemonic_state: GenerateMnemonicState,
+    backup_verification_state: BackupVerificationState,
+    shamir_backup_state: ShamirBackupState,
+    key_transfer_state: KeyTransferState,
 }
 
 impl KeygenState {
@@ -165,7 +301,11 @@ impl KeygenState {
                 exe_checksum,
                 save_name: "keygen".to_string(),
                 persist_disk: false,
-                set_hot_mnemonic: false
+                set_hot_mnemonic: false,
+                bip85_word_count: 24,
+                bip85_index_input: "0".to_string(),
+                bip85_child: None,
+                bip85_open_child: false,
             },
             generate_mnemonic_state: GenerateMnemonicState {
                 random_input_mnemonic: "".to_string(),
@@ -190,6 +330,9 @@ impl KeygenState {
                 p_cost: Some(2),
                 t_cost: Some(10),
             },
+            backup_verification_state: Default::default(),
+            shamir_backup_state: Default::default(),
+            key_transfer_state: Default::default(),
         }
     }
 }
@@ -231,6 +374,273 @@ pub fn keys_screen_scroll(ui: &mut Ui, ctx: &egui::Context, local_state: &mut Lo
         ctx, local_state
     );
 
+    backup_verification(ui, local_state);
+
+    shamir_backup(ui, local_state);
+
+    key_transfer(ui, local_state);
+
+}
+
+fn key_transfer(ui: &mut Ui, ls: &mut LocalState) {
+    ui.separator();
+    ui.spacing();
+    ui.label("Export or import keys and xpubs with a password encrypted file");
+
+    let stored = ls.local_stored_state.clone();
+    let state = &mut ls.keygen_state.key_transfer_state;
+    let entries = stored.mnemonics.clone().unwrap_or(vec![]).iter()
+        .map(|m| (format!("mnemonic:{}", m.name), format!("Mnemonic {}", m.name)))
+        .chain(stored.private_keys.clone().unwrap_or(vec![]).iter()
+            .map(|k| (format!("private_key:{}", k.name), format!("Private Key {}", k.name))))
+        .chain(stored.xpubs.iter()
+            .map(|x| (format!("xpub:{}", x.name), format!("Xpub {}", x.name))))
+        .collect_vec();
+    for (key, label) in entries {
+        let mut selected = state.is_selected(&key);
+        if ui.checkbox(&mut selected, label).changed() {
+            state.toggle(key, selected);
+        }
+    }
+
+    ui.horizontal(|ui| {
+        ui.label("File");
+        TextEdit::singleline(&mut state.path).desired_width(300f32).show(ui);
+    });
+    ui.horizontal(|ui| {
+        ui.label("Password");
+        TextEdit::singleline(&mut state.password).password(true).desired_width(150f32).show(ui);
+        ui.label("Confirm");
+        TextEdit::singleline(&mut state.password_confirm).password(true).desired_width(150f32).show(ui);
+    });
+    if ui.add_enabled(!state.selected.is_empty(), egui::Button::new("Export Selected")).clicked() {
+        let contents = state.selected_contents(&stored);
+        state.message = Some(if state.password != state.password_confirm {
+            Err("Passwords do not match".to_string())
+        } else {
+            EncryptedKeyBundle::encrypt(&contents, &state.password)
+                .and_then(|b| std::fs::write(&state.path, b.json_pretty_or())
+                    .error_info("Failed to write key bundle"))
+                .map(|_| format!("Exported {} to {}", contents.summary(), state.path))
+                .map_err(|e| e.json_or())
+        });
+    }
+
+    ui.horizontal(|ui| {
+        ui.label("Import Password");
+        TextEdit::singleline(&mut state.import_password).password(true).desired_width(150f32).show(ui);
+        ui.checkbox(&mut state.overwrite, "Overwrite existing names");
+    });
+    let mut imported = None;
+    if ui.button("Import").clicked() {
+        let result = std::fs::read_to_string(&state.path)
+            .error_info("Failed to read key bundle")
+            .and_then(|s| s.json_from::<EncryptedKeyBundle>())
+            .and_then(|b| b.decrypt(&state.import_password));
+        match result {
+            Ok(mut contents) => {
+                if !state.overwrite {
+                    let key_names = stored.key_names();
+                    contents.mnemonics.retain(|m| !key_names.contains(&m.name));
+                    contents.private_keys.retain(|k| !key_names.contains(&k.name));
+                    contents.xpubs.retain(|x| !stored.xpubs.iter().any(|e| e.name == x.name));
+                }
+                state.message = Some(Ok(format!("Imported {}", contents.summary())));
+                state.import_password = "".to_string();
+                imported = Some(contents);
+            }
+            Err(e) => state.message = Some(Err(e.json_or())),
+        }
+    }
+    match &state.message {
+        Some(Ok(m)) => { ui.label(RichText::new(m.clone()).color(Color32::GREEN)); }
+        Some(Err(e)) => { ui.label(RichText::new(e.clone()).color(Color32::RED)); }
+        None => {}
+    }
+
+    if let Some(contents) = imported.filter(|c| !c.is_empty()) {
+        LocalState::send_update(&ls.updates, move |lss| {
+            for m in contents.mnemonics.iter() {
+                lss.upsert_mnemonic(m.clone());
+            }
+            for k in contents.private_keys.iter() {
+                lss.upsert_private_key(k.clone());
+            }
+            for x in contents.xpubs.iter() {
+                lss.add_named_xpub(true, x.clone()).log_error().ok();
+            }
+        });
+    }
+}
+
+fn shamir_backup(ui: &mut Ui, ls: &mut LocalState) {
+    ui.separator();
+    ui.spacing();
+    ui.label("Shamir secret sharing (SLIP-39) backup of the open mnemonic");
+
+    let words = ls.keygen_state.mnemonic_window_state.words_pass();
+    let has_words = words.mnemonic().is_ok();
+    let state = &mut ls.keygen_state.shamir_backup_state;
+    ui.horizontal(|ui| {
+        ui.label("Threshold");
+        TextEdit::singleline(&mut state.threshold_input).desired_width(30f32).show(ui);
+        ui.label("of Shares");
+        TextEdit::singleline(&mut state.share_count_input).desired_width(30f32).show(ui);
+        if ui.add_enabled(has_words, egui::Button::new("Generate Shares")).clicked() {
+            state.shares = vec![];
+            state.message = Some(state.threshold_and_count().and_then(|(t, n)| {
+                let shares = slip39_shares(&words, t, n).map_err(|e| e.json_or())?;
+                slip39_verify_shares(&words, &shares, t as usize).map_err(|e| e.json_or())?;
+                state.shares = shares;
+                Ok(format!("Generated and verified {}-of-{} shares", t, n))
+            }));
+        }
+    });
+    if !has_words {
+        ui.label("Generate or open a mnemonic above to split it into shares");
+    }
+    if words.passphrase.is_some() {
+        ui.label(RichText::new("The passphrase is not included in the shares, back it up separately")
+            .color(Color32::YELLOW));
+    }
+
+    let mut show_qr = None;
+    for (i, share) in state.shares.iter().enumerate() {
+        ui.horizontal(|ui| {
+            medium_data_item(ui, format!("Share {}", i + 1), share.clone());
+            copy_to_clipboard(ui, share.clone());
+            if ui.button("QR").clicked() {
+                show_qr = Some(share.clone());
+            }
+        });
+    }
+    if !state.shares.is_empty() {
+        if ui.button("Save Printable ./slip39_shares.txt").clicked() {
+            let threshold = state.threshold_and_count().map(|(t, _)| t).unwrap_or(0);
+            state.message = Some(std::fs::write("slip39_shares.txt", state.printable(threshold))
+                .map(|_| "Saved ./slip39_shares.txt".to_string())
+                .map_err(|e| e.to_string()));
+        }
+    }
+
+    ui.label("Recover from shares, one per line");
+    TextEdit::multiline(&mut state.recovery_input)
+        .desired_width(500f32)
+        .desired_rows(3)
+        .show(ui);
+    let mut recovered = None;
+    if ui.button("Recover Mnemonic").clicked() {
+        let shares = state.recovery_input.lines().map(|l| l.to_string()).filter(|l| !l.trim().is_empty()).collect_vec();
+        match slip39_recover(&shares) {
+            Ok(w) => {
+                state.message = Some(Ok("Recovered mnemonic".to_string()));
+                recovered = Some(w.words);
+            }
+            Err(e) => state.message = Some(Err(e.json_or())),
+        }
+    }
+    match &state.message {
+        Some(Ok(m)) => { ui.label(RichText::new(m.clone()).color(Color32::GREEN)); }
+        Some(Err(e)) => { ui.label(RichText::new(e.clone()).color(Color32::RED)); }
+        None => {}
+    }
+
+    if let Some(s) = show_qr {
+        ls.qr_show_state.enable(s);
+    }
+    if let Some(w) = recovered {
+        ls.keygen_state.mnemonic_window_state.set_words(w, "Recovered from SLIP-39 shares");
+    }
+}
+
+const BACKUP_QUIZ_WORDS: usize = 3;
+
+fn backup_verification(ui: &mut Ui, ls: &mut LocalState) {
+    ui.separator();
+    ui.spacing();
+    ui.label("Verify stored mnemonic backup");
+
+    let mnemonics = ls.local_stored_state.mnemonics.clone().unwrap_or(vec![]);
+    let state = &mut ls.keygen_state.backup_verification_state;
+    let previous = state.selected_mnemonic_name.clone();
+    ComboBox::from_label("Stored Mnemonic")
+        .selected_text(state.selected_mnemonic_name.clone())
+        .show_ui(ui, |ui| {
+            for m in mnemonics.iter() {
+                ui.selectable_value(&mut state.selected_mnemonic_name, m.name.clone(), m.name.clone());
+            }
+        });
+    if previous != state.selected_mnemonic_name {
+        state.reset_quiz();
+    }
+
+    let stored = match mnemonics.iter().find(|m| m.name == state.selected_mnemonic_name) {
+        Some(m) => m,
+        None => return,
+    };
+    let wp = WordsPass::words(stored.mnemonic.clone());
+    let health = wp.health();
+
+    ui.horizontal(|ui| {
+        medium_data_item(ui, "Word Count", health.word_count.to_string());
+        ui.label("BIP-39");
+        valid_label(ui, health.valid_bip39);
+        if let Some(bits) = health.entropy_bits {
+            medium_data_item(ui, "Entropy Bits", bits.to_string());
+        }
+    });
+    if let Some(c) = &health.checksum_words {
+        medium_data_item(ui, "Words Checksum", c.clone());
+    }
+    if let Some(e) = &health.error {
+        ui.label(RichText::new(e.clone()).color(Color32::RED));
+    }
+    let status = match stored.backup_verified {
+        None => RichText::new("Not yet verified").color(Color32::YELLOW),
+        Some(true) => RichText::new("Backup verified").color(Color32::GREEN),
+        Some(false) => RichText::new("Last verification failed").color(Color32::RED),
+    };
+    ui.label(status);
+
+    if !health.valid_bip39 {
+        return;
+    }
+
+    if ui.button("Start Verification Quiz").clicked() {
+        state.reset_quiz();
+        state.quiz_positions = wp.random_word_positions(BACKUP_QUIZ_WORDS);
+        state.quiz_answers = state.quiz_positions.iter().map(|_| "".to_string()).collect_vec();
+    }
+
+    for (i, position) in state.quiz_positions.iter().enumerate() {
+        ui.horizontal(|ui| {
+            ui.label(format!("Word #{}", position + 1));
+            TextEdit::singleline(&mut state.quiz_answers[i])
+                .desired_width(120f32)
+                .lock_focus(false)
+                .show(ui);
+        });
+    }
+
+    if !state.quiz_positions.is_empty() && ui.button("Check Answers").clicked() {
+        let answers = state.quiz_positions.iter().cloned()
+            .zip(state.quiz_answers.iter().cloned())
+            .collect_vec();
+        let passed = wp.verify_word_positions(&answers);
+        state.result_message = Some(if passed { "Passed" } else { "Failed" }.to_string());
+        state.quiz_positions = vec![];
+        state.quiz_answers = vec![];
+        let mut updated = stored.clone();
+        updated.backup_verified = Some(passed);
+        updated.backup_verified_time = Some(util::current_time_millis_i64());
+        LocalState::send_update(&ls.updates, move |lss| {
+            lss.upsert_mnemonic(updated.clone());
+        });
+    }
+
+    if let Some(m) = &ls.keygen_state.backup_verification_state.result_message {
+        ui.label(format!("Quiz result: {}", m));
+    }
 }
 
 pub fn keys_screen(ui: &mut Ui, ctx: &egui::Context, local_state: &mut LocalState) {
@@ -505,13 +915,26 @@ fn mnemonic_window(
         state.set_words_from_passphrase();
         state.requires_reset = false;
     }
+    if state.bip85_open_child {
+        state.bip85_open_child = false;
+        if let Some(Ok(child)) = state.bip85_child.take() {
+            let label = format!(
+                "BIP-85 child {} words index {} of parent {}",
+                state.bip85_word_count, state.bip85_index_input, state.words_checksum
+            );
+            state.passphrase = None;
+            state.passphrase_input = "".to_string();
+            state.set_words(child, label);
+        }
+    }
     if state.calc_private_key_hex {
         state.private_key_hex = state.get_private_key_hex();
         state.calc_private_key_hex = false;
     }
 
+    let mut open = state.open;
     egui::Window::new("Mnemonic")
-        .open(&mut state.open)
+        .open(&mut open)
         .resizable(false)
         .collapsible(false)
         .min_width(500.0)
@@ -586,6 +1009,8 @@ fn mnemonic_window(
                         medium_data_item(ui, "Private Key Hex", state.private_key_hex.clone());
                     });
 
+                    bip85_child_derivation(ui, state);
+
                     ui.horizontal(|ui| {
                         if ui.button("Save ./metadata.json").clicked() {
                             let words = state.words.clone();
@@ -605,8 +1030,50 @@ fn mnemonic_window(
                     });
             });
         });
+    state.open = open;
 }
 
+fn bip85_child_derivation(ui: &mut Ui, state: &mut MnemonicWindowState) {
+    ui.horizontal(|ui| {
+        ui.label("BIP-85 Child Mnemonic");
+        ComboBox::from_id_source("bip85_word_count")
+            .selected_text(format!("{} words", state.bip85_word_count))
+            .width(90.0)
+            .show_ui(ui, |ui| {
+                for c in [12, 18, 24] {
+                    ui.selectable_value(&mut state.bip85_word_count, c, format!("{} words", c));
+                }
+            });
+        ui.label("Index");
+        TextEdit::singleline(&mut state.bip85_index_input)
+            .desired_width(50f32)
+            .ui(ui);
+        if ui.button("Derive").clicked() {
+            state.bip85_child = Some(
+                state.bip85_index_input.trim().parse::<u32>()
+                    .map_err(|_| "Invalid index".to_string())
+                    .and_then(|i| state.words_pass().bip85_derive_words(state.bip85_word_count, i)
+                        .map(|w| w.words)
+                        .map_err(|e| e.json_or()))
+            );
+        }
+    });
+    match &state.bip85_child {
+        Some(Ok(child)) => {
+            ui.horizontal(|ui| {
+                medium_data_item(ui, "Child Words", child.clone());
+                copy_to_clipboard(ui, child.clone());
+                if ui.button("Open").clicked() {
+                    state.bip85_open_child = true;
+                }
+            });
+        }
+        Some(Err(e)) => {
+            ui.label(RichText::new(e.clone()).color(Color32::RED));
+        }
+        None => {}
+    }
+}
 
 /*
           egui::Grid::new("my_grid")
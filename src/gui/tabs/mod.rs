@@ -7,4 +7,7 @@ pub mod address_tab;
 pub mod otp_tab;
 pub mod recovery_tab;
 pub mod keys_tab;
+pub mod peers_tab;
+pub mod portfolio_tab;
 
+pub mod contacts_tab;
@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use eframe::egui;
+use eframe::egui::Ui;
+use log::error;
+use redgold_schema::{EasyJson, RgResult, SafeOption};
+use redgold_schema::structs::{PeerId, PublicKey};
+use crate::api::public_api::PublicClient;
+use crate::gui::app_loop::LocalState;
+use crate::gui::common;
+use crate::gui::tables;
+use crate::gui::tables::DataTableState;
+use crate::node_config::NodeConfig;
+use crate::gui::i18n::tr;
+use crate::core::peer_scores::PeerScore;
+use crate::observability::logging::Loggable;
+
+#[derive(Clone)]
+pub struct PeerRow {
+    pub public_key: PublicKey,
+    pub peer_id: Option<PeerId>,
+    pub address: String,
+    pub port: u16,
+    pub last_seen: Option<i64>,
+    pub trust: Option<f64>,
+    pub checksum: String,
+    // From the connected node's peer scores, None if it hasn't called this peer
+    pub score: Option<PeerScore>,
+}
+
+#[derive(Clone)]
+pub struct PeersState {
+    needs_update: bool,
+    peers: Arc<Mutex<Vec<PeerRow>>>,
+    ping_results: Arc<Mutex<HashMap<String, String>>>,
+    load_error: Arc<Mutex<Option<String>>>,
+    table: DataTableState,
+}
+
+impl Default for PeersState {
+    fn default() -> Self {
+        Self {
+            needs_update: true,
+            peers: Arc::new(Mutex::new(vec![])),
+            ping_results: Arc::new(Mutex::new(HashMap::new())),
+            load_error: Arc::new(Mutex::new(None)),
+            table: Default::default(),
+        }
+    }
+}
+
+async fn load_peers(nc: &NodeConfig) -> RgResult<Vec<PeerRow>> {
+    let ds = nc.data_store().await;
+    let scores = nc.api_client().peer_scores().await.log_error().unwrap_or_default()
+        .into_iter().map(|s| (s.public_key.clone(), s)).collect::<HashMap<String, PeerScore>>();
+    let infos = ds.peer_store.all_peers_info().await?;
+    let peers = infos.iter()
+        .flat_map(|i| i.latest_peer_transaction.as_ref())
+        .flat_map(|t| t.peer_data().ok())
+        .collect::<Vec<_>>();
+    let mut rows = vec![];
+    let node_metadata = infos.iter()
+        .flat_map(|i| i.latest_node_transaction.as_ref())
+        .map(|t| t.node_metadata())
+        .collect::<RgResult<Vec<_>>>()?;
+    for nmd in node_metadata {
+        let public_key = nmd.public_key.safe_get_msg("Missing node public key")?.clone();
+        let peer_id = nmd.peer_id.clone();
+        // Average of all labels other peers have assigned to this peer, normalized from the 1e3 scale
+        let ratings = peers.iter()
+            .flat_map(|p| p.labels.iter())
+            .filter(|l| l.peer_id.is_some() && l.peer_id == peer_id)
+            .flat_map(|l| l.trust_data.iter())
+            .flat_map(|d| d.label_rating)
+            .collect::<Vec<_>>();
+        let trust = if ratings.is_empty() {
+            None
+        } else {
+            Some(ratings.iter().sum::<i64>() as f64 / (ratings.len() as f64 * 1000.0))
+        };
+        let checksum = nmd.version_info.as_ref()
+            .map(|v| v.executable_checksum.clone())
+            .unwrap_or("".to_string());
+        rows.push(PeerRow {
+            last_seen: ds.peer_store.node_last_seen(&public_key).await?,
+            score: scores.get(&public_key.hex_or()).cloned(),
+            public_key,
+            peer_id,
+            address: nmd.external_address().unwrap_or("".to_string()),
+            port: nmd.port_or(nc.network.clone()) + 1,
+            trust,
+            checksum,
+        });
+    }
+    Ok(rows)
+}
+
+pub async fn update_peers(nc: NodeConfig, peers: Arc<Mutex<Vec<PeerRow>>>, load_error: Arc<Mutex<Option<String>>>) {
+    let res = load_peers(&nc).await;
+    match res {
+        Ok(rows) => {
+            let mut guard = peers.lock().expect("lock");
+            guard.clear();
+            guard.extend(rows);
+            load_error.lock().expect("lock").take();
+        }
+        Err(e) => {
+            error!("Peer load failure: {}", e.json_or());
+            load_error.lock().expect("lock").replace(e.json_or());
+        }
+    }
+}
+
+pub async fn ping_peer(peer: PeerRow, results: Arc<Mutex<HashMap<String, String>>>) {
+    let mut client = PublicClient::from(peer.address.clone(), peer.port, None);
+    client.timeout = Duration::from_secs(5);
+    let start = crate::util::current_time_millis_i64();
+    let result = match client.about().await {
+        Ok(a) => format!("ok {}ms, {} peers", crate::util::current_time_millis_i64() - start, a.num_active_peers),
+        Err(e) => {
+            error!("Peer ping failure: {}", e.json_or());
+            "failed".to_string()
+        }
+    };
+    results.lock().expect("lock").insert(peer.public_key.hex_or(), result);
+}
+
+pub async fn remove_peer(nc: NodeConfig, peer: PeerRow) -> RgResult<()> {
+    let ds = nc.data_store().await;
+    ds.peer_store.remove_node(&peer.public_key).await?;
+    if let Some(pid) = peer.peer_id.as_ref() {
+        ds.peer_store.remove_peer_id(pid).await?;
+    }
+    Ok(())
+}
+
+fn last_seen_str(last_seen: Option<i64>, current_time: i64) -> String {
+    match last_seen {
+        Some(t) if t > 0 => format!("{}s ago", (current_time - t) / 1000),
+        _ => "never".to_string()
+    }
+}
+
+pub fn peers_tab(ui: &mut Ui, _ctx: &egui::Context, local_state: &mut LocalState) {
+
+    if local_state.peers_state.needs_update {
+        local_state.peers_state.needs_update = false;
+        tokio::spawn(update_peers(
+            local_state.node_config.clone(),
+            local_state.peers_state.peers.clone(),
+            local_state.peers_state.load_error.clone(),
+        ));
+    }
+    let peers = local_state.peers_state.peers.lock().expect("lock").to_vec();
+    let pings = local_state.peers_state.ping_results.lock().expect("lock").clone();
+
+    ui.horizontal(|ui| {
+        ui.heading(tr("Peers"));
+        ui.separator();
+        if ui.button(tr("Refresh")).clicked() {
+            local_state.peers_state.needs_update = true;
+        }
+    });
+    ui.separator();
+
+    if let Some(e) = local_state.peers_state.load_error.lock().expect("lock").as_ref() {
+        ui.label(format!("Error loading peers: {}", e));
+    }
+
+    let mut table_rows: Vec<Vec<String>> = vec![];
+    table_rows.push(vec![
+        "Public Key".to_string(),
+        "Address".to_string(),
+        "Last Seen".to_string(),
+        "Trust".to_string(),
+        "Checksum".to_string(),
+        "RTT".to_string(),
+        "Score".to_string(),
+        "Ping".to_string(),
+    ]);
+    for p in peers.iter() {
+        let pk = p.public_key.hex_or();
+        let checksum_len = p.checksum.len();
+        table_rows.push(vec![
+            p.public_key.short_id(),
+            format!("{}:{}", p.address, p.port),
+            last_seen_str(p.last_seen, local_state.current_time),
+            p.trust.map(|t| format!("{:.3}", t)).unwrap_or("".to_string()),
+            p.checksum[checksum_len.saturating_sub(9)..].to_string(),
+            p.score.as_ref().and_then(|s| s.rtt_ms).map(|r| format!("{:.0}ms", r)).unwrap_or("".to_string()),
+            p.score.as_ref().map(|s| format!("{:.3}", s.score)).unwrap_or("".to_string()),
+            pings.get(&pk).cloned().unwrap_or("".to_string()),
+        ]);
+    }
+    tables::data_table(ui, "peers_table", &mut local_state.peers_state.table, table_rows, |_, _| {});
+
+    ui.separator();
+    // Actions apply to the peer selected in the table
+    let selected = local_state.peers_state.table.selected.and_then(|i| peers.get(i));
+    if let Some(p) = selected {
+        ui.horizontal(|ui| {
+            ui.label(p.public_key.short_id());
+            common::copy_to_clipboard(ui, p.public_key.hex_or());
+            if ui.button(tr("Ping")).clicked() {
+                let results = local_state.peers_state.ping_results.clone();
+                results.lock().expect("lock").insert(p.public_key.hex_or(), "pinging".to_string());
+                tokio::spawn(ping_peer(p.clone(), results));
+            }
+            if ui.button(tr("Remove")).clicked() {
+                let nc = local_state.node_config.clone();
+                let peer = p.clone();
+                let peers_arc = local_state.peers_state.peers.clone();
+                let load_error = local_state.peers_state.load_error.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = remove_peer(nc.clone(), peer).await {
+                        error!("Peer removal failure: {}", e.json_or());
+                    }
+                    update_peers(nc, peers_arc, load_error).await;
+                });
+            }
+        });
+    } else {
+        ui.label(tr("Select a peer to ping or remove it"));
+    }
+}
@@ -0,0 +1,157 @@
+use std::time::Duration;
+use chrono::NaiveDateTime;
+use eframe::egui;
+use eframe::egui::{Color32, Context, Pos2, Sense, Stroke, Ui, Vec2};
+use redgold_keys::address_support::AddressSupport;
+use redgold_keys::xpub_wrapper::XpubWrapper;
+use redgold_schema::EasyJson;
+use redgold_schema::local_stored_state::Account;
+use crate::api::explorer::BalanceHistoryResponse;
+use crate::gui::app_loop::LocalState;
+use crate::gui::common::{account_color, account_label, account_text};
+use crate::gui::tables::{data_table, DataTableState};
+
+#[derive(Clone, Default)]
+pub struct PortfolioState {
+    pub address_entry: String,
+    pub history: Option<BalanceHistoryResponse>,
+    pub error: Option<String>,
+    pub loading: bool,
+    // Account the loaded address belongs to, None for manually entered or watched addresses
+    pub account: Option<Account>,
+    pub history_table: DataTableState,
+}
+
+fn load_history(ls: &mut LocalState, address: String) {
+    let state = &mut ls.portfolio_state;
+    state.loading = true;
+    state.error = None;
+    let nc = ls.node_config.clone();
+    let updates = ls.updates.clone();
+    tokio::spawn(async move {
+        let res = match address.parse_address() {
+            Ok(a) => {
+                let mut client = nc.api_client();
+                client.timeout = Duration::from_secs(10);
+                client.balance_history(&a).await
+            }
+            Err(e) => Err(e)
+        };
+        LocalState::send_update(&updates, move |ls| {
+            let state = &mut ls.portfolio_state;
+            state.loading = false;
+            match res.clone() {
+                Ok(h) => state.history = Some(h),
+                Err(e) => state.error = Some(e.json_or()),
+            }
+        });
+    });
+}
+
+fn snapshot_date(time: i64) -> String {
+    NaiveDateTime::from_timestamp_opt(time / 1000, 0)
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .unwrap_or(time.to_string())
+}
+
+fn balance_chart(ui: &mut Ui, history: &BalanceHistoryResponse, color: Color32) {
+    let mut points = history.history.iter().map(|p| (p.time, p.balance)).collect::<Vec<(i64, f64)>>();
+    if points.is_empty() {
+        ui.label("No balance snapshots recorded yet");
+        return;
+    }
+    let (response, painter) = ui.allocate_painter(Vec2::new(600.0, 200.0), Sense::hover());
+    let rect = response.rect;
+    painter.rect_stroke(rect, 0.0, Stroke::new(1.0, Color32::GRAY));
+    if points.len() == 1 {
+        points.push(points[0]);
+    }
+    let min_t = points.iter().map(|p| p.0).min().unwrap_or(0);
+    let max_t = points.iter().map(|p| p.0).max().unwrap_or(0);
+    let max_b = points.iter().map(|p| p.1).fold(0.0, f64::max);
+    let t_range = ((max_t - min_t) as f32).max(1.0);
+    let b_range = (max_b as f32).max(f32::EPSILON);
+    let line = points.iter().enumerate().map(|(i, (t, b))| {
+        let x = if max_t == min_t {
+            rect.left() + rect.width() * (i as f32) / ((points.len() - 1) as f32)
+        } else {
+            rect.left() + rect.width() * ((t - min_t) as f32) / t_range
+        };
+        let y = rect.bottom() - rect.height() * (*b as f32) / b_range;
+        Pos2::new(x, y)
+    }).collect::<Vec<Pos2>>();
+    painter.add(egui::Shape::line(line, Stroke::new(2.0, color)));
+    ui.label(format!("Max balance: {}", max_b));
+}
+
+pub fn portfolio_tab(ui: &mut Ui, _ctx: &Context, ls: &mut LocalState) {
+    ui.heading("Portfolio");
+
+    let watched = ls.local_stored_state.watched_addresses();
+    let mut load = None;
+    ui.horizontal(|ui| {
+        ui.label("Address");
+        ui.text_edit_singleline(&mut ls.portfolio_state.address_entry);
+        if ui.button("Load History").clicked() {
+            ls.portfolio_state.account = None;
+            load = Some(ls.portfolio_state.address_entry.clone());
+        }
+    });
+    let accounts = ls.local_stored_state.accounts();
+    if !accounts.is_empty() {
+        ui.horizontal(|ui| {
+            ui.label("Accounts");
+            for account in accounts.iter() {
+                if ui.button(account_text(account)).clicked() {
+                    let address = ls.local_stored_state.account_xpub(account)
+                        .and_then(|x| XpubWrapper::new(x.xpub.clone()).public_at(0, 0).ok())
+                        .and_then(|pk| pk.address().ok())
+                        .and_then(|a| a.render_string().ok());
+                    if let Some(a) = address {
+                        ls.portfolio_state.address_entry = a.clone();
+                        ls.portfolio_state.account = Some(account.clone());
+                        load = Some(a);
+                    }
+                }
+            }
+        });
+    }
+    if !watched.is_empty() {
+        ui.horizontal(|ui| {
+            ui.label("Watched");
+            for w in watched.iter() {
+                if ui.button(w.name.clone()).clicked() {
+                    if let Ok(a) = w.address.render_string() {
+                        ls.portfolio_state.address_entry = a.clone();
+                        ls.portfolio_state.account = None;
+                        load = Some(a);
+                    }
+                }
+            }
+        });
+    }
+    if let Some(a) = load {
+        load_history(ls, a);
+    }
+
+    let state = &mut ls.portfolio_state;
+    if state.loading {
+        ui.spinner();
+    }
+    if let Some(e) = &state.error {
+        ui.colored_label(Color32::LIGHT_RED, format!("Balance history query failed: {}", e));
+    }
+    if let Some(h) = &state.history {
+        if let Some(a) = &state.account {
+            account_label(ui, a);
+        }
+        ui.label(format!("Current balance: {}", h.current_balance));
+        let color = state.account.as_ref().map(|a| account_color(a)).unwrap_or(Color32::LIGHT_GREEN);
+        balance_chart(ui, h, color);
+        let mut rows = vec![vec!["Time".to_string(), "Balance".to_string()]];
+        for p in h.history.iter().rev() {
+            rows.push(vec![snapshot_date(p.time), p.balance.to_string()]);
+        }
+        data_table(ui, "balance_history_table", &mut state.history_table, rows, |_, _| {});
+    }
+}
@@ -1,18 +1,24 @@
 use redgold_schema::servers::Server;
+use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use eframe::egui::{Color32, RichText, TextEdit, Ui};
 use std::path::PathBuf;
 use eframe::egui;
 use log::{error, info};
 use redgold_schema::structs::{ErrorInfo, NetworkEnvironment};
+use flume::Sender;
 use tokio::task::JoinHandle;
 use redgold_schema::{EasyJson, RgResult};
 use crate::core::internal_message::{Channel, RecvAsyncErrorInfo};
+use crate::api::public_api::PublicClient;
 use crate::gui::app_loop::LocalState;
 use crate::gui::common::{bounded_text_area_size_focus, editable_text_input_copy, password_single, valid_label};
 use crate::gui::tables;
-use crate::infra::deploy::{default_deploy, DeployMachine};
+use crate::gui::tables::DataTableState;
+use crate::infra::deploy::{default_deploy, DeployMachine, run_server_action, ServerAction};
 use crate::infra::{deploy};
+use crate::observability::metrics_help::parse_prometheus_values;
 use crate::util::cli::args::Deploy;
 
 pub async fn update_server_status(servers: Vec<Server>, status: Arc<Mutex<Vec<ServerStatus>>>) {
@@ -28,6 +34,335 @@ pub async fn update_server_status(servers: Vec<Server>, status: Arc<Mutex<Vec<Se
     guard.extend(results);
 }
 
+/// Appends each line sent on the returned sender to the shared output text, abort the handle when done
+fn output_collector(arc: Arc<Mutex<String>>) -> (Sender<String>, JoinHandle<()>) {
+    let c: Channel::<String> = Channel::new();
+    let r = c.receiver.clone();
+    let handle = tokio::spawn(async move {
+        loop {
+            let s = match r.recv_async_err().await {
+                Ok(x) => {
+                    x
+                }
+                Err(e) => {
+                    error!("Channel receive error: {}", e.json_or());
+                    break;
+                }
+            };
+            let mut inner = arc.lock().expect("lock poisoned");
+            let s = s.trim();
+            if s.is_empty() {
+                continue;
+            }
+            *inner = format!("{}\n{}", &*inner, s);
+            info!("Server output: {}", s);
+        }
+        ()
+    });
+    (c.sender.clone(), handle)
+}
+
+const METRICS_QUERY_INTERVAL_MS: i64 = 1000 * 30;
+
+#[derive(Clone, Default)]
+pub struct ServerMetrics {
+    pub uptime_seconds: Option<f64>,
+    pub peers: Option<f64>,
+    pub tx_total: Option<f64>,
+    pub obs_height: Option<f64>,
+    pub error: Option<String>,
+}
+
+pub async fn update_server_metrics(
+    servers: Vec<Server>, network: NetworkEnvironment, metrics: Arc<Mutex<HashMap<i64, ServerMetrics>>>
+) {
+    let public_port = network.default_port_offset() + 1;
+    for server in servers {
+        let host = server.external_host.clone().unwrap_or(server.host.clone());
+        let mut client = PublicClient::from(host, public_port, None);
+        client.timeout = Duration::from_secs(5);
+        let result = match client.metrics().await {
+            Ok(text) => {
+                let values = parse_prometheus_values(&text);
+                ServerMetrics {
+                    uptime_seconds: values.get("redgold_node_uptime_seconds").cloned(),
+                    peers: values.get("redgold_peer_active").cloned(),
+                    tx_total: values.get("redgold_transaction_accepted_total").cloned(),
+                    obs_height: values.get("redgold_observation_height").cloned(),
+                    error: None,
+                }
+            }
+            Err(e) => ServerMetrics {
+                error: Some(e.json_or()),
+                ..Default::default()
+            }
+        };
+        metrics.lock().expect("lock").insert(server.index, result);
+    }
+}
+
+fn metric_str(v: Option<f64>) -> String {
+    v.map(|v| format!("{}", v as i64)).unwrap_or("".to_string())
+}
+
+fn server_metrics(ui: &mut Ui, local_state: &mut LocalState, servers: &Vec<Server>) {
+    let now = local_state.current_time;
+    if local_state.server_state.last_metrics_query
+        .map(|q| (now - q) > METRICS_QUERY_INTERVAL_MS)
+        .unwrap_or(true) {
+        local_state.server_state.last_metrics_query = Some(now);
+        tokio::spawn(update_server_metrics(
+            servers.clone(),
+            local_state.node_config.network.clone(),
+            local_state.server_state.metrics.clone()
+        ));
+    }
+    let metrics = local_state.server_state.metrics.lock().expect("lock").clone();
+    let mut table_rows: Vec<Vec<String>> = vec![];
+    table_rows.push(vec![
+        "Hostname".to_string(),
+        "Uptime".to_string(),
+        "Peers".to_string(),
+        "Total TX".to_string(),
+        "Obs Height".to_string(),
+        "Status".to_string(),
+    ]);
+    for server in servers {
+        let m = metrics.get(&server.index).cloned();
+        let status = match &m {
+            None => "querying".to_string(),
+            Some(m) => m.error.as_ref().map(|_| "Unreachable".to_string()).unwrap_or("Online".to_string()),
+        };
+        let m = m.unwrap_or_default();
+        table_rows.push(vec![
+            server.host.clone(),
+            m.uptime_seconds.map(|u| format!("{:.1}h", u / 3600.0)).unwrap_or("".to_string()),
+            metric_str(m.peers),
+            metric_str(m.tx_total),
+            metric_str(m.obs_height),
+            status,
+        ]);
+    }
+    ui.horizontal(|ui| {
+        ui.label("Node Metrics");
+        if ui.button("Refresh Metrics").clicked() {
+            local_state.server_state.last_metrics_query = None;
+        }
+    });
+    tables::data_table(ui, "server_metrics_table", &mut local_state.server_state.metrics_table, table_rows, |_, _| {});
+}
+
+#[derive(Clone)]
+pub struct ServerWizardState {
+    // Index of the server being edited, None when adding a new one
+    editing: Option<i64>,
+    host: String,
+    username: String,
+    index: String,
+    peer_id_index: String,
+    node_name: String,
+    external_host: String,
+    // Host of the last connectivity check and its result, None while still running
+    ssh_check: Arc<Mutex<Option<(String, Option<bool>)>>>,
+    message: Option<Result<String, String>>,
+}
+
+impl Default for ServerWizardState {
+    fn default() -> Self {
+        Self {
+            editing: None,
+            host: "".to_string(),
+            username: "root".to_string(),
+            index: "0".to_string(),
+            peer_id_index: "0".to_string(),
+            node_name: "".to_string(),
+            external_host: "".to_string(),
+            ssh_check: Arc::new(Mutex::new(None)),
+            message: None,
+        }
+    }
+}
+
+fn non_empty(s: &String) -> Option<String> {
+    Some(s.trim().to_string()).filter(|s| !s.is_empty())
+}
+
+impl ServerWizardState {
+    fn new_server(&mut self, servers: &Vec<Server>) {
+        let next_index = servers.iter().map(|s| s.index + 1).max().unwrap_or(0);
+        *self = Self::default();
+        self.index = next_index.to_string();
+    }
+
+    fn load(&mut self, s: &Server) {
+        *self = Self::default();
+        self.editing = Some(s.index);
+        self.host = s.host.clone();
+        self.username = s.username.clone().unwrap_or("".to_string());
+        self.index = s.index.to_string();
+        self.peer_id_index = s.peer_id_index.to_string();
+        self.node_name = s.node_name.clone().unwrap_or("".to_string());
+        self.external_host = s.external_host.clone().unwrap_or("".to_string());
+    }
+
+    fn server(&self, existing: Option<&Server>) -> Result<Server, String> {
+        let host = non_empty(&self.host).ok_or("Host is required".to_string())?;
+        let mut s = existing.cloned().unwrap_or(Server::new(host.clone()));
+        s.host = host;
+        s.index = self.index.trim().parse::<i64>().map_err(|_| "Invalid index".to_string())?;
+        s.peer_id_index = self.peer_id_index.trim().parse::<i64>().map_err(|_| "Invalid peer id index".to_string())?;
+        s.username = non_empty(&self.username);
+        s.node_name = non_empty(&self.node_name);
+        s.external_host = non_empty(&self.external_host);
+        Ok(s)
+    }
+
+    fn check_ssh(&self, server: &Server) {
+        let check = self.ssh_check.clone();
+        check.lock().expect("lock").replace((server.host.clone(), None));
+        let server = server.clone();
+        tokio::spawn(async move {
+            let reachable = DeployMachine::new(&server, None).verify().await.is_ok();
+            check.lock().expect("lock").replace((server.host.clone(), Some(reachable)));
+        });
+    }
+}
+
+/// Servers with the edited entry replaced or added, ordered by index.
+fn upsert_server(servers: &Vec<Server>, editing: Option<i64>, server: Server) -> Vec<Server> {
+    let mut updated = servers.iter()
+        .filter(|s| Some(s.index) != editing)
+        .cloned()
+        .collect::<Vec<Server>>();
+    updated.push(server);
+    updated.sort_by_key(|s| s.index);
+    updated
+}
+
+fn server_wizard(ui: &mut Ui, local_state: &mut LocalState, servers: &Vec<Server>) {
+    egui::CollapsingHeader::new("Server Manifest Wizard").show(ui, |ui| {
+        let state = &mut local_state.server_state.wizard;
+        ui.horizontal(|ui| {
+            if ui.button("New Server").clicked() {
+                state.new_server(servers);
+            }
+            for s in servers {
+                if ui.button(format!("Edit {}", s.index)).on_hover_text(s.host.clone()).clicked() {
+                    state.load(s);
+                }
+            }
+        });
+        match state.editing {
+            Some(i) => ui.label(format!("Editing server {}", i)),
+            None => ui.label("Adding a new server"),
+        };
+        editable_text_input_copy(ui, "Host", &mut state.host, 200.0);
+        editable_text_input_copy(ui, "SSH User", &mut state.username, 100.0);
+        ui.horizontal(|ui| {
+            ui.label("Index");
+            TextEdit::singleline(&mut state.index).desired_width(50.0).show(ui);
+            ui.label("PeerId Index");
+            TextEdit::singleline(&mut state.peer_id_index).desired_width(50.0).show(ui);
+        });
+        editable_text_input_copy(ui, "Node Name", &mut state.node_name, 200.0);
+        editable_text_input_copy(ui, "External Host", &mut state.external_host, 200.0);
+
+        let existing = state.editing.and_then(|i| servers.iter().find(|s| s.index == i));
+        let server = state.server(existing);
+        ui.horizontal(|ui| {
+            if ui.add_enabled(server.is_ok(), egui::Button::new("Check SSH")).clicked() {
+                if let Ok(s) = &server {
+                    state.check_ssh(s);
+                }
+            }
+            let check = state.ssh_check.lock().expect("lock").clone();
+            match check.filter(|(h, _)| h == state.host.trim()) {
+                Some((_, None)) => { ui.label("Checking"); }
+                Some((_, Some(reachable))) => {
+                    ui.label("SSH");
+                    valid_label(ui, reachable);
+                }
+                None => {}
+            }
+        });
+        if let Err(e) = &server {
+            ui.label(RichText::new(e.clone()).color(Color32::YELLOW));
+        }
+
+        let mut saved = None;
+        if ui.add_enabled(server.is_ok(), egui::Button::new("Save Server")).clicked() {
+            if let Ok(s) = server {
+                let updated = upsert_server(servers, state.editing, s.clone());
+                let path = PathBuf::from(local_state.server_state.csv_edit_path.clone());
+                let state = &mut local_state.server_state.wizard;
+                match Server::write_to_file(path.clone(), &updated) {
+                    Ok(_) => {
+                        state.message = Some(Ok(format!("Saved {} servers to {}", updated.len(), path.display())));
+                        state.editing = Some(s.index);
+                        state.check_ssh(&s);
+                        saved = Some(updated);
+                    }
+                    Err(e) => state.message = Some(Err(e.json_or())),
+                }
+            }
+        }
+        match &local_state.server_state.wizard.message {
+            Some(Ok(m)) => { ui.label(RichText::new(m.clone()).color(Color32::GREEN)); }
+            Some(Err(e)) => { ui.label(RichText::new(e.clone()).color(Color32::RED)); }
+            None => {}
+        }
+        if let Some(updated) = saved {
+            local_state.node_config.servers = updated.clone();
+            local_state.local_stored_state.servers = updated;
+            local_state.persist_local_state_store();
+            local_state.server_state.needs_update = true;
+        }
+    });
+}
+
+fn server_actions(ui: &mut Ui, local_state: &mut LocalState, servers: &Vec<Server>) {
+    ui.label("Server Actions");
+    ui.horizontal(|ui| {
+        ui.label("Log Lines:");
+        TextEdit::singleline(&mut local_state.server_state.log_lines_edit).desired_width(50.0).show(ui);
+    });
+    let running = local_state.server_state.action_running.lock().expect("lock").clone();
+    for server in servers {
+        ui.horizontal(|ui| {
+            ui.label(format!("{} ({})", server.host, server.index));
+            for action in ServerAction::all() {
+                let clicked = ui.add_enabled(running.is_none(), egui::Button::new(action.label())).clicked();
+                if clicked {
+                    let output = Arc::new(Mutex::new("".to_string()));
+                    local_state.server_state.action_output = output.clone();
+                    let running_arc = local_state.server_state.action_running.clone();
+                    running_arc.lock().expect("lock").replace(format!("{} {}", action.label(), server.host));
+                    let log_lines = local_state.server_state.log_lines_edit.parse::<usize>().unwrap_or(200);
+                    let nc = local_state.node_config.clone();
+                    let server = server.clone();
+                    tokio::spawn(async move {
+                        let (sender, collector) = output_collector(output.clone());
+                        let res = run_server_action(nc, server, action, log_lines, &Some(sender)).await;
+                        if let Err(e) = res {
+                            let mut inner = output.lock().expect("lock");
+                            *inner = format!("{}\nAction failed: {}", &*inner, e.json_or());
+                        }
+                        // Give the collector a moment to drain the remaining output
+                        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+                        collector.abort();
+                        running_arc.lock().expect("lock").take();
+                    });
+                }
+            }
+        });
+    }
+    if let Some(r) = running {
+        ui.label(RichText::new(format!("Running: {}", r)).color(Color32::WHITE));
+    }
+    let mut output = local_state.server_state.action_output.lock().expect("lock").clone();
+    bounded_text_area_size_focus(ui, &mut output, 600., 10);
+}
+
 pub fn servers_tab(ui: &mut Ui, _ctx: &egui::Context, local_state: &mut LocalState) {
 
     let servers = local_state.node_config.servers.clone();
@@ -77,7 +412,21 @@ pub fn servers_tab(ui: &mut Ui, _ctx: &egui::Context, local_state: &mut LocalSta
         ui.spacing();
     });
     ui.separator();
-    tables::text_table(ui, table_rows);
+    // Selecting a server targets it for deploys and actions
+    let mut selected_index = None;
+    tables::data_table(ui, "servers_table", &mut local_state.server_state.servers_table, table_rows, |_, row| {
+        selected_index = row.get(2).cloned();
+    });
+    if let Some(i) = selected_index {
+        local_state.server_state.server_index_edit = i;
+    }
+    ui.separator();
+    server_wizard(ui, local_state, &servers);
+    ui.separator();
+    server_metrics(ui, local_state, &servers);
+    ui.separator();
+    server_actions(ui, local_state, &servers);
+    ui.separator();
 
     editable_text_input_copy(
         ui,"Server CSV Load Path", &mut local_state.server_state.csv_edit_path, 400.0
@@ -106,6 +455,8 @@ pub fn servers_tab(ui: &mut Ui, _ctx: &egui::Context, local_state: &mut LocalSta
     ui.horizontal(|ui| {
         ui.checkbox(&mut local_state.server_state.words_and_id, "Words/Id");
         ui.checkbox(&mut local_state.server_state.cold, "Cold");
+        ui.checkbox(&mut local_state.server_state.bip85, "BIP-85")
+            .on_hover_text("Derive server mnemonics with standard BIP-85 instead of the legacy hash derivation");
         ui.checkbox(&mut local_state.server_state.purge, "Purge");
         ui.checkbox(&mut local_state.server_state.ops, "Ops");
         ui.checkbox(&mut local_state.server_state.purge_ops, "Purge Ops");
@@ -148,6 +499,7 @@ pub fn servers_tab(ui: &mut Ui, _ctx: &egui::Context, local_state: &mut LocalSta
         d.mixing_password = Some(local_state.server_state.mixing_password.clone()).filter(|s| !s.is_empty());
         d.words_and_id = local_state.server_state.words_and_id;
         d.cold = local_state.server_state.cold;
+        d.bip85 = local_state.server_state.bip85;
 
         let hard = local_state.server_state.hard_coord_reset.clone();
         if hard {
@@ -158,31 +510,8 @@ pub fn servers_tab(ui: &mut Ui, _ctx: &egui::Context, local_state: &mut LocalSta
         let config = local_state.node_config.clone();
         let arc = local_state.server_state.deployment_result_info_box.clone();
 
-        let c: Channel::<String> = Channel::new();
-        let r = c.receiver.clone();
-        let default_fun = tokio::spawn(async move {
-            loop {
-                let s = match r.recv_async_err().await {
-                    Ok(x) => {
-                        x
-                    }
-                    Err(e) => {
-                        error!("Channel receive error: {}", e.json_or());
-                        break;
-                    }
-                };
-                let mut inner = arc.lock().expect("lock poisoned");
-                let s = s.trim();
-                if s.is_empty() {
-                    continue;
-                }
-                *inner = format!("{}\n{}", &*inner, s);
-                info!("Deploy result: {}", s);
-            }
-            ()
-        });
-
-        let output_handler = Some(c.sender.clone());
+        let (sender, default_fun) = output_collector(arc);
+        let output_handler = Some(sender);
         let arc = local_state.server_state.deployment_result.clone();
         let deploy_join = tokio::spawn(async move {
             let f = output_handler.clone();
@@ -245,6 +574,7 @@ pub fn servers_tab(ui: &mut Ui, _ctx: &egui::Context, local_state: &mut LocalSta
                 PathBuf::from(local_state.server_state.generate_offline_path.clone()),
                 local_state.wallet_state.hot_mnemonic().words.clone(),
                 local_state.wallet_state.hot_mnemonic().passphrase.clone(),
+                local_state.server_state.bip85,
             ));
         }
     });
@@ -279,6 +609,7 @@ pub struct ServersState {
     hard_coord_reset: bool,
     words_and_id: bool,
     cold: bool,
+    bip85: bool,
     deployment_result: Arc<Mutex<Option<RgResult<()>>>>,
     deploy_process: Option<Arc<JoinHandle<()>>>,
     mixing_password: String,
@@ -286,7 +617,15 @@ pub struct ServersState {
     load_offline_path: String,
     load_offline_deploy: bool,
     show_mixing_password: bool,
-    last_env: NetworkEnvironment
+    last_env: NetworkEnvironment,
+    action_output: Arc<Mutex<String>>,
+    action_running: Arc<Mutex<Option<String>>>,
+    log_lines_edit: String,
+    metrics: Arc<Mutex<HashMap<i64, ServerMetrics>>>,
+    last_metrics_query: Option<i64>,
+    servers_table: DataTableState,
+    metrics_table: DataTableState,
+    wizard: ServerWizardState,
 }
 
 impl Default for ServersState {
@@ -306,6 +645,7 @@ impl Default for ServersState {
             hard_coord_reset: false,
             words_and_id: false,
             cold: false,
+            bip85: false,
             deployment_result: Arc::new(Mutex::new(None)),
             deploy_process: None,
             mixing_password: "".to_string(),
@@ -314,6 +654,14 @@ impl Default for ServersState {
             load_offline_deploy: false,
             show_mixing_password: false,
             last_env: NetworkEnvironment::Dev,
+            action_output: Arc::new(Mutex::new("".to_string())),
+            action_running: Arc::new(Mutex::new(None)),
+            log_lines_edit: "200".to_string(),
+            metrics: Arc::new(Mutex::new(HashMap::new())),
+            last_metrics_query: None,
+            servers_table: Default::default(),
+            metrics_table: Default::default(),
+            wizard: Default::default(),
         }
     }
 }
\ No newline at end of file
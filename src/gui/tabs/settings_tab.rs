@@ -1,9 +1,15 @@
-use eframe::egui::{Context, Ui};
+use eframe::egui;
+use eframe::egui::{ComboBox, Context, Ui};
+use strum::IntoEnumIterator;
 use serde::Serialize;
-use redgold_schema::EasyJsonDeser;
-use redgold_schema::local_stored_state::LocalStoredState;
-use crate::gui::app_loop::LocalState;
+use redgold_schema::{EasyJson, EasyJsonDeser};
+use redgold_schema::local_stored_state::{LocalStoredState, SpendingLimits};
+use crate::gui::app_loop::{DEFAULT_FONT_SIZE, LocalState, MAX_UI_SCALE, MIN_UI_SCALE};
+use crate::gui::top_panel::set_ui_scale;
+use crate::gui::deep_link::register_uri_handler;
+use crate::gui::os_keystore::{keystore_namespace, migrate_from_os_keystore, migrate_to_os_keystore};
 use crate::gui::common::{bounded_text_area, editable_text_input_copy, valid_label};
+use crate::gui::i18n::{Locale, locale, set_locale, tr};
 
 
 #[derive(Clone)]
@@ -14,6 +20,10 @@ pub struct SettingsState {
     valid_json: bool,
     data_folder: String,
     secure_data_folder: String,
+    uri_handler_status: Option<String>,
+    spending_limit_input: String,
+    countdown_input: String,
+    os_keystore_status: Option<String>,
 }
 
 impl SettingsState {
@@ -28,16 +38,23 @@ impl SettingsState {
             new_lss: None,
             valid_json: true,
             data_folder,
-            secure_data_folder
+            secure_data_folder,
+            uri_handler_status: None,
+            spending_limit_input: "".to_string(),
+            countdown_input: "".to_string(),
+            os_keystore_status: None,
         }
     }
 }
 
 
 pub fn settings_tab(ui: &mut Ui, _ctx: &Context, ls: &mut LocalState) {
-    ui.heading("Settings");
+    ui.heading(tr("Settings"));
+    language_settings(ui, ls);
+    display_settings(ui, _ctx, ls);
+    ui.separator();
 
-    ui.label("Local stored state json");
+    ui.label(tr("Local stored state json"));
     bounded_text_area(ui, &mut ls.settings_state.lss_serialized);
 
     valid_label(ui,ls.settings_state.valid_json);
@@ -53,12 +70,27 @@ pub fn settings_tab(ui: &mut Ui, _ctx: &Context, ls: &mut LocalState) {
         }
     }
 
-    if ui.button("Save Json").clicked() {
+    if ui.button(tr("Save Json")).clicked() {
         if let Some(lss) = &ls.settings_state.new_lss {
             ls.local_stored_state = lss.clone();
             ls.persist_local_state_store();
         }
     }
+    ui.separator();
+    spending_limits_settings(ui, ls);
+    ui.separator();
+    os_keystore_settings(ui, ls);
+    ui.separator();
+    ui.horizontal(|ui| {
+        if ui.button(tr("Register redgold: links")).on_hover_text(tr("Open payment links in this wallet")).clicked() {
+            ls.settings_state.uri_handler_status = Some(
+                register_uri_handler().unwrap_or_else(|e| format!("Registration failed: {}", e.json_or()))
+            );
+        }
+        if let Some(status) = &ls.settings_state.uri_handler_status {
+            ui.label(status);
+        }
+    });
     //
     // editable_text_input_copy(ui,"Data Directory for .rg folder", &mut ls.settings_state.lss_serialized);
     //
@@ -70,4 +102,104 @@ pub fn settings_tab(ui: &mut Ui, _ctx: &Context, ls: &mut LocalState) {
     // }
 
 
-}
\ No newline at end of file
+}
+fn language_settings(ui: &mut Ui, ls: &mut LocalState) {
+    let current = locale();
+    let mut selected = current;
+    ComboBox::from_label(tr("Language"))
+        .selected_text(current.native_name())
+        .show_ui(ui, |ui| {
+            for l in Locale::iter() {
+                ui.selectable_value(&mut selected, l, l.native_name());
+            }
+        });
+    if selected != current {
+        set_locale(selected);
+        ls.local_stored_state.locale = Some(selected.code().to_string());
+        ls.persist_local_state_store();
+    }
+}
+
+fn display_settings(ui: &mut Ui, ctx: &Context, ls: &mut LocalState) {
+    let mut scale = ctx.pixels_per_point();
+    let scale_changed = ui.add(egui::Slider::new(&mut scale, MIN_UI_SCALE..=MAX_UI_SCALE).step_by(0.25).text(tr("UI scale")))
+        .drag_released();
+    if scale_changed {
+        set_ui_scale(ctx, ls, scale);
+    }
+    let mut font_size = ls.local_stored_state.font_size.unwrap_or(DEFAULT_FONT_SIZE);
+    if ui.add(egui::Slider::new(&mut font_size, 8.0..=24.0).step_by(0.5).text(tr("Font size"))).changed() {
+        ls.local_stored_state.font_size = Some(font_size);
+        ls.persist_local_state_store();
+    }
+    ui.label(tr("Shortcuts: Ctrl+Tab / Ctrl+Shift+Tab switch tabs, Alt+1 to Alt+9 open a tab"));
+}
+
+fn os_keystore_settings(ui: &mut Ui, ls: &mut LocalState) {
+    let enabled = ls.local_stored_state.os_keystore_enabled();
+    let mut toggled = enabled;
+    ui.checkbox(&mut toggled, tr("Store hot keys in the OS keychain"))
+        .on_hover_text(tr("Keeps mnemonics and private keys out of the local database file"));
+    if toggled != enabled {
+        let mut state = ls.local_stored_state.clone();
+        let namespace = keystore_namespace(&ls.node_config);
+        let result = if toggled {
+            migrate_to_os_keystore(&namespace, &mut state)
+        } else {
+            migrate_from_os_keystore(&namespace, &mut state)
+        };
+        ls.settings_state.os_keystore_status = Some(match result {
+            Ok(moved) => {
+                ls.local_stored_state = state;
+                ls.persist_local_state_store();
+                format!("{} {}", tr("Keys moved:"), moved)
+            }
+            // State is left untouched so no secret is dropped from disk on a partial migration
+            Err(e) => format!("{} {}", tr("Keychain migration failed:"), e.json_or()),
+        });
+    }
+    if let Some(s) = &ls.settings_state.os_keystore_status {
+        ui.label(s);
+    }
+}
+
+fn spending_limits_settings(ui: &mut Ui, ls: &mut LocalState) {
+    ui.heading(tr("Hot Wallet Spending Limits"));
+    let mut enabled = ls.local_stored_state.spending_limits.is_some();
+    if ui.checkbox(&mut enabled, tr("Confirm hot wallet sends above a per-session limit")).changed() {
+        ls.local_stored_state.spending_limits = if enabled { Some(SpendingLimits::default()) } else { None };
+        ls.settings_state.spending_limit_input = "".to_string();
+        ls.settings_state.countdown_input = "".to_string();
+        ls.persist_local_state_store();
+    }
+    if ls.local_stored_state.spending_limits.is_none() {
+        return;
+    }
+    let limits = ls.local_stored_state.spending_limits.clone().expect("limits");
+    let ss = &mut ls.settings_state;
+    if ss.spending_limit_input.is_empty() && ss.countdown_input.is_empty() {
+        ss.spending_limit_input = limits.session_limit_usd.to_string();
+        ss.countdown_input = limits.confirm_countdown_secs.to_string();
+    }
+    let mut updated = limits.clone();
+    ui.horizontal(|ui| {
+        ui.label(tr("Session limit (USD)"));
+        ui.add(egui::TextEdit::singleline(&mut ss.spending_limit_input).desired_width(100.0));
+        ui.label(tr("Confirmation countdown (seconds)"));
+        ui.add(egui::TextEdit::singleline(&mut ss.countdown_input).desired_width(50.0));
+    });
+    let parsed_limit = ss.spending_limit_input.trim().parse::<f64>().ok().filter(|l| *l >= 0.0);
+    let parsed_countdown = ss.countdown_input.trim().parse::<u64>().ok();
+    valid_label(ui, parsed_limit.is_some() && parsed_countdown.is_some());
+    ui.checkbox(&mut updated.require_password, tr("Require session password instead of countdown"));
+    if let Some(l) = parsed_limit {
+        updated.session_limit_usd = l;
+    }
+    if let Some(c) = parsed_countdown {
+        updated.confirm_countdown_secs = c;
+    }
+    if updated != limits {
+        ls.local_stored_state.spending_limits = Some(updated);
+        ls.persist_local_state_store();
+    }
+}
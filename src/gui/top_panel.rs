@@ -1,7 +1,16 @@
 use eframe::egui;
 use eframe::egui::{ComboBox, Context};
 use redgold_schema::structs::NetworkEnvironment;
-use crate::gui::app_loop::LocalState;
+use crate::gui::app_loop::{LocalState, MAX_UI_SCALE, MIN_UI_SCALE};
+use crate::gui::i18n::tr;
+
+/// Applies and persists the UI scale, clamped to the supported range.
+pub fn set_ui_scale(ctx: &Context, local_state: &mut LocalState, scale: f32) {
+    let scale = scale.clamp(MIN_UI_SCALE, MAX_UI_SCALE);
+    ctx.set_pixels_per_point(scale);
+    local_state.local_stored_state.ui_scale = Some(scale);
+    local_state.persist_local_state_store();
+}
 
 pub fn render_top(ctx: &Context, local_state: &mut LocalState) {
     egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
@@ -12,15 +21,15 @@ pub fn render_top(ctx: &Context, local_state: &mut LocalState) {
             // TODO: Make button smaller
             if ui.small_button("+Text")
                 .on_hover_text(string.clone()).clicked() {
-                ctx.set_pixels_per_point(cur + 0.25);
+                set_ui_scale(ctx, local_state, cur + 0.25);
             }
 
             if ui.small_button("-Text")
                 .on_hover_text(string).clicked() {
-                ctx.set_pixels_per_point(cur - 0.25);
+                set_ui_scale(ctx, local_state, cur - 0.25);
             }
 
-            ui.label("Network: ");
+            ui.label(tr("Network: "));
             ComboBox::from_label("")
                 .width(80.0)
                 .selected_text(local_state.node_config.network.to_std_string())
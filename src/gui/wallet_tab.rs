@@ -1,3 +1,4 @@
+use std::collections::{HashMap, HashSet};
 use std::future::Future;
 use std::time::Instant;
 use eframe::egui;
@@ -11,12 +12,11 @@ use strum::IntoEnumIterator;
 // 0.17.1
 use strum_macros::{EnumIter, EnumString};
 use tracing::{error, info};
-use redgold_keys::address_external::ToBitcoinAddress;
 use redgold_keys::TestConstants;
 use redgold_keys::transaction_support::TransactionSupport;
-use redgold_keys::util::btc_wallet::SingleKeyBitcoinWallet;
-use redgold_schema::{EasyJsonDeser, error_info, ErrorInfoContext, RgResult, WithMetadataHashable};
-use redgold_schema::structs::{Address, AddressInfo, CurrencyAmount, ErrorInfo, NetworkEnvironment, PublicKey, SubmitTransactionResponse, SupportedCurrency, Transaction};
+use redgold_keys::util::btc_wallet::{bitcoin_network, BtcAddressType, SingleKeyBitcoinWallet};
+use redgold_schema::{EasyJsonDeser, error_info, ErrorInfoContext, RgResult, SafeOption, WithMetadataHashable};
+use redgold_schema::structs::{Address, AddressInfo, CurrencyAmount, ErrorInfo, NetworkEnvironment, PublicKey, SubmitTransactionResponse, SupportedCurrency, Transaction, UtxoEntry, UtxoId};
 use crate::hardware::trezor;
 use crate::hardware::trezor::trezor_list_devices;
 use redgold_schema::EasyJson;
@@ -25,14 +25,28 @@ use crate::core::transact::tx_builder_supports::TransactionBuilder;
 use redgold_keys::util::mnemonic_support::WordsPass;
 use redgold_keys::xpub_wrapper::XpubWrapper;
 use crate::core::internal_message::{Channel, new_channel, SendErrorInfo};
-use crate::gui::common;
-use crate::gui::common::{bounded_text_area, data_item, data_item_multiline_fixed, editable_text_input_copy, medium_data_item, valid_label};
+use crate::gui::{common, deep_link};
+use crate::gui::fiat::{FiatPrices, refresh_prices};
+use crate::gui::observation_view::{observation_view, ObservationViewState};
+use crate::core::transact::tx_preview::TransactionPreview;
+use crate::gui::tables::{data_table, DataTableState};
+use chrono::NaiveDateTime;
+use crate::core::transact::marked_outputs::{marked_outputs, MarkedOutput, MarkedOutputKind, MarkedOutputStatus, prepare_stake_withdrawal};
+use redgold_keys::message_signing::{message_hash, SignedMessage};
+use crate::gui::common::{account_color, account_label, account_selector, bounded_text_area, hex_color, data_item, data_item_multiline_fixed, editable_text_input_copy, medium_data_item, valid_label};
 use crate::node_config::NodeConfig;
 use redgold_schema::util::lang_util::JsonCombineResult;
 use crate::observability::logging::Loggable;
-use redgold_schema::local_stored_state::NamedXpub;
+use crate::api::explorer::{PendingStage, PendingTransaction};
+use redgold_schema::local_stored_state::{Account, NamedXpub};
 use crate::core::transact::tx_builder_supports::TransactionBuilderSupport;
 use crate::gui::tabs::{cold_wallet, hot_wallet};
+use crate::gui::qr_render::qr_encode;
+use egui_extras::RetainedImage;
+use redgold_schema::payment_uri::PaymentUri;
+use crate::gui::i18n::tr;
+use redgold_data::name_store::NameRecord;
+use redgold_schema::name_registry::validate_name;
 
 
 #[derive(Debug, EnumIter, EnumString, PartialEq)]
@@ -67,6 +81,7 @@ enum SendReceiveTabs {
     Send,
     Receive,
     CustomTx,
+    SignMessage,
     // Swap
 }
 
@@ -97,6 +112,7 @@ pub struct WalletState {
     signing_flow_transaction_box_msg: Option<String>,
     broadcast_transaction_response: Option<Result<SubmitTransactionResponse, ErrorInfo>>,
     pub show_btc_info: bool,
+    pub btc_address_type: BtcAddressType,
     pub hot_mnemonic_default: String,
     pub send_currency_type: SupportedCurrency,
     pub active_hot_mnemonic: Option<String>,
@@ -116,7 +132,9 @@ pub struct WalletState {
     pub is_mnemonic_or_kp: Option<bool>,
     pub valid_save_mnemonic: String,
     pub show_xpub_loader_window: bool,
-    pub selected_xpub_name: String,
+    pub selected_account: Option<String>,
+    pub account_color_edit: Color32,
+    pub account_currencies_edit: Vec<SupportedCurrency>,
     pub selected_key_name: String,
     pub last_selected_key_name: String,
     pub add_new_key_window: bool,
@@ -125,12 +143,56 @@ pub struct WalletState {
     pub allow_xpub_name_overwrite: bool,
     pub xpub_loader_rows: String,
     pub xpub_loader_error_message: String,
+    // Seed checksum of the hot mnemonic the active xpub was derived from, None for hardware
+    pub active_xpub_seed_checksum: Option<String>,
+    pub xpub_load_warning: Option<String>,
     pub hot_passphrase: String,
     pub hot_offset: String,
     pub custom_tx_json: String,
     pub mnemonic_save_persist: bool,
     pub mark_output_as_stake: bool,
-    pub mark_output_as_swap: bool
+    pub mark_output_as_swap: bool,
+    pub show_utxo_panel: bool,
+    pub show_marked_outputs: bool,
+    pub show_transaction_history: bool,
+    // Set to move keyboard focus to the amount input on the next frame
+    pub focus_amount: bool,
+    pub utxo_table: DataTableState,
+    pub history_table: DataTableState,
+    // Not yet finalized transactions of the active address, including ones just broadcast
+    pub pending_transactions: Vec<PendingTransaction>,
+    pub rebroadcast_status: Option<String>,
+    pub selected_utxos: HashSet<UtxoId>,
+    pub fiat_prices: FiatPrices,
+    // Amount input is denominated in USD and converted to the send currency on prepare
+    pub amount_in_usd: bool,
+    pub prepared_preview: Option<TransactionPreview>,
+    pub sign_message_input: String,
+    pub signed_message_output: Option<String>,
+    pub verify_message_input: String,
+    pub verify_message_result: Option<RgResult<String>>,
+    pub observation_view: ObservationViewState,
+    pub receive_amount_input: String,
+    // Rendered QR codes keyed by the payment uri they encode
+    pub receive_qr: HashMap<String, RetainedImage>,
+    // Payment link the gui was opened with, applied again once a wallet is selected
+    pub opened_payment_uri: Option<PaymentUri>,
+    pub clipboard_uri: Option<PaymentUri>,
+    pub clipboard_last_checked: i64,
+    // USD value signed by hot keys this session, counted against the spending limit
+    pub session_spent_usd: f64,
+    // Destination name and its registry lookup, None while the lookup is in flight
+    pub name_lookup: Option<(String, Option<RgResult<NameRecord>>)>,
+    pub limit_confirmation: Option<LimitConfirmation>,
+}
+
+/// Pending confirmation of a hot wallet send over the session spending limit.
+pub struct LimitConfirmation {
+    pub tx_hash: String,
+    pub usd: Option<f64>,
+    pub started: i64,
+    pub password_entry: String,
+    pub error: Option<String>,
 }
 
 impl WalletState {
@@ -162,6 +224,10 @@ impl WalletState {
         self.address_info = None;
         self.public_key = None;
         self.send_receive = None;
+        self.selected_utxos.clear();
+        if let Some(uri) = self.opened_payment_uri.take() {
+            self.apply_payment_uri(&uri);
+        }
     }
 
     pub fn update_signed_tx(&mut self, tx_o: Option<RgResult<Transaction>>) {
@@ -177,9 +243,11 @@ impl WalletState {
     pub fn update_unsigned_tx(&mut self, tx_o: Option<RgResult<Transaction>>) {
         if let Some(tx) = tx_o.as_ref().and_then(|tx| tx.as_ref().ok()) {
             self.unsigned_transaction_hash = Some(tx.hash_hex_or_missing());
+            self.prepared_preview = Some(TransactionPreview::from_transaction(tx));
             self.prepared_transaction = tx_o.clone()
         } else {
             self.signed_transaction_hash = None;
+            self.prepared_preview = None;
             self.prepared_transaction = None;
         }
     }
@@ -223,6 +291,7 @@ impl WalletState {
             signing_flow_transaction_box_msg: None,
             broadcast_transaction_response: None,
             show_btc_info: false,
+            btc_address_type: BtcAddressType::NativeSegwit,
             hot_mnemonic_default: hot_mnemonic,
             send_currency_type: SupportedCurrency::Redgold,
             active_hot_mnemonic: None,
@@ -243,12 +312,16 @@ impl WalletState {
             show_save_xpub_window: false,
             purge_existing_xpubs_on_save: false,
             allow_xpub_name_overwrite: true,
-            selected_xpub_name: "Select Xpub".to_string(),
+            selected_account: None,
+            account_color_edit: Color32::GRAY,
+            account_currencies_edit: vec![],
             selected_key_name: "default".to_string(),
             last_selected_key_name: "default".to_string(),
             show_xpub_loader_window: false,
             xpub_loader_rows: "".to_string(),
             xpub_loader_error_message: "".to_string(),
+            active_xpub_seed_checksum: None,
+            xpub_load_warning: None,
             hot_passphrase: "".to_string(),
             hot_offset: "".to_string(),
             custom_tx_json: "".to_string(),
@@ -257,6 +330,65 @@ impl WalletState {
             mnemonic_save_persist: true,
             mark_output_as_stake: false,
             mark_output_as_swap: false,
+            show_utxo_panel: false,
+            show_marked_outputs: false,
+            show_transaction_history: false,
+            focus_amount: false,
+            utxo_table: Default::default(),
+            history_table: Default::default(),
+            pending_transactions: vec![],
+            rebroadcast_status: None,
+            selected_utxos: HashSet::new(),
+            fiat_prices: FiatPrices::default(),
+            amount_in_usd: false,
+            prepared_preview: None,
+            sign_message_input: "".to_string(),
+            signed_message_output: None,
+            verify_message_input: "".to_string(),
+            verify_message_result: None,
+            observation_view: ObservationViewState::default(),
+            receive_amount_input: "".to_string(),
+            receive_qr: HashMap::new(),
+            opened_payment_uri: None,
+            clipboard_uri: None,
+            clipboard_last_checked: 0,
+            session_spent_usd: 0.0,
+            limit_confirmation: None,
+            name_lookup: None,
+        }
+    }
+
+    /// Destination input, replaced by the bound address when it is a successfully resolved name.
+    pub fn resolved_destination(&self) -> String {
+        self.name_lookup.as_ref()
+            .filter(|(name, _)| name == &self.destination_address)
+            .and_then(|(_, r)| r.as_ref())
+            .and_then(|r| r.as_ref().ok())
+            .and_then(|r| r.address.render_string().ok())
+            .unwrap_or(self.destination_address.clone())
+    }
+
+    /// Pre-fills the send view with a payment link's destination and amount.
+    pub fn apply_payment_uri(&mut self, uri: &PaymentUri) {
+        self.send_currency_type = uri.currency.clone();
+        if uri.currency == SupportedCurrency::Bitcoin {
+            self.show_btc_info = true;
+        }
+        self.destination_address = uri.address.clone();
+        if let Some(a) = uri.amount {
+            self.amount_input = a.to_string();
+            self.amount_in_usd = false;
+        }
+        self.send_receive = Some(SendReceiveTabs::Send);
+    }
+
+    /// Amount input in units of the send currency, converting from USD when fiat entry is enabled.
+    pub fn send_amount(&self, time: i64) -> Option<f64> {
+        let amount = self.amount_input.parse::<f64>().ok()?;
+        if self.amount_in_usd {
+            self.fiat_prices.from_usd(amount, &self.send_currency_type, time)
+        } else {
+            Some(amount)
         }
     }
     pub fn update_hardware(&mut self) {
@@ -369,15 +501,46 @@ fn proceed_from_pk(ui: &mut Ui, ls: &mut LocalState, pk: &PublicKey) {
     ui.separator();
 
 
-    ui.heading(RichText::new(format!("Balance: RDG: {} {}",
+    refresh_prices(ls);
+    let now = ls.current_time;
+    let prices = &ls.wallet_state.fiat_prices;
+    let rdg_usd = ls.wallet_state.balance_f64
+        .map(|b| prices.usd_label(b, &SupportedCurrency::Redgold, now)).unwrap_or("".to_string());
+    let btc_usd = ls.wallet_state.balance_btc_f64
+        .map(|b| prices.usd_label(b, &SupportedCurrency::Bitcoin, now)).unwrap_or("".to_string());
+    ui.heading(RichText::new(format!("Balance: RDG: {} {} {}",
                                      ls.wallet_state.balance.clone(),
-        ls.wallet_state.balance_btc.clone().map(|b| format!("BTC: {}", b)).unwrap_or("".to_string())
+                                     rdg_usd,
+        ls.wallet_state.balance_btc.clone().map(|b| format!("BTC: {} {}", b, btc_usd)).unwrap_or("".to_string())
     ))
         .color(Color32::LIGHT_GREEN));
 
-    ui.checkbox(&mut ls.wallet_state.show_btc_info, "Show BTC Info / Enable BTC");
+    ui.checkbox(&mut ls.wallet_state.show_btc_info, tr("Show BTC Info / Enable BTC"));
     if ls.wallet_state.show_btc_info {
-        data_item(ui, "BTC Address", pk.to_bitcoin_address(&ls.node_config.network).unwrap_or("".to_string()));
+        ComboBox::from_label("BTC Address Type")
+            .selected_text(format!("{:?}", ls.wallet_state.btc_address_type))
+            .show_ui(ui, |ui| {
+                for t in BtcAddressType::all() {
+                    ui.selectable_value(&mut ls.wallet_state.btc_address_type, t, format!("{:?}", t));
+                }
+            });
+        let network = bitcoin_network(&ls.node_config.network);
+        let btc_address = ls.wallet_state.btc_address_type.address(pk, network)
+            .map(|a| a.to_string()).unwrap_or("".to_string());
+        data_item(ui, "BTC Address", btc_address);
+    }
+
+    ui.checkbox(&mut ls.wallet_state.show_utxo_panel, tr("Show UTXOs / Coin Control"));
+    if ls.wallet_state.show_utxo_panel {
+        utxo_panel(ui, ls);
+    }
+    ui.checkbox(&mut ls.wallet_state.show_marked_outputs, tr("Show Swaps / Stakes"));
+    if ls.wallet_state.show_marked_outputs {
+        marked_outputs_panel(ui, ls);
+    }
+    ui.checkbox(&mut ls.wallet_state.show_transaction_history, tr("Show Transaction History"));
+    if ls.wallet_state.show_transaction_history {
+        transaction_history_panel(ui, ls);
     }
 
     send_receive_bar(ui, ls, pk);
@@ -393,11 +556,16 @@ fn proceed_from_pk(ui: &mut Ui, ls: &mut LocalState, pk: &PublicKey) {
             }
             SendReceiveTabs::Receive => {
                 show_prepared = false;
+                receive_view(ui, ls, pk);
             }
             SendReceiveTabs::CustomTx => {
                 ui.label("Enter custom transaction JSON:");
                 ui.horizontal(|ui| bounded_text_area(ui, &mut ls.wallet_state.custom_tx_json));
             }
+            SendReceiveTabs::SignMessage => {
+                show_prepared = false;
+                sign_message_view(ui, ls, pk);
+            }
             // SendReceiveTabs::Swap => {
             //     // show_prepared = false;
             //     // swap_view(ui, ls, pk);
@@ -407,11 +575,269 @@ fn proceed_from_pk(ui: &mut Ui, ls: &mut LocalState, pk: &PublicKey) {
             prepared_view(ui, ls, pk);
         }
     }
+    ui.separator();
+    egui::CollapsingHeader::new("Transaction Attestations").show(ui, |ui| {
+        observation_view(ui, ls);
+    });
+}
+
+fn utxo_age(current_time: i64, utxo_time: i64) -> String {
+    let seconds = (current_time - utxo_time).max(0) / 1000;
+    if seconds < 3600 {
+        format!("{}m", seconds / 60)
+    } else if seconds < 86400 {
+        format!("{}h", seconds / 3600)
+    } else {
+        format!("{}d", seconds / 86400)
+    }
+}
+
+fn utxo_id_string(id: &UtxoId) -> String {
+    let hash = id.transaction_hash.as_ref().map(|h| h.hex()).unwrap_or("".to_string());
+    format!("{}:{}", hash, id.output_index)
+}
+
+/// Currency UTXOs of the active address which should be spent, either the manually selected
+/// subset or all of them when nothing is selected.
+pub fn selected_or_all_utxos(ai: &AddressInfo, selected: &HashSet<UtxoId>) -> Vec<UtxoEntry> {
+    let currency = ai.utxo_entries.iter()
+        .filter(|u| u.opt_amount().is_some())
+        .cloned()
+        .collect_vec();
+    if selected.is_empty() {
+        currency
+    } else {
+        currency.into_iter()
+            .filter(|u| u.utxo_id.as_ref().map(|id| selected.contains(id)).unwrap_or(false))
+            .collect_vec()
+    }
+}
+
+fn utxo_panel(ui: &mut Ui, ls: &mut LocalState) {
+    let ai = match &ls.wallet_state.address_info {
+        None => {
+            ui.label(tr("Refresh balance to load UTXOs"));
+            return;
+        }
+        Some(ai) => ai.clone()
+    };
+    let current_time = ls.current_time;
+    let utxos = ai.utxo_entries.iter()
+        .filter(|u| u.opt_amount().is_some())
+        .filter_map(|u| u.utxo_id.clone().map(|id| (id, u.clone())))
+        .collect_vec();
+    ui.label(format!("UTXOs: {} Selected: {}", utxos.len(), ls.wallet_state.selected_utxos.len()));
+    let mut rows = vec![vec!["Use".to_string(), "Amount".to_string(), "Age (s)".to_string(), "Id".to_string()]];
+    for (id, u) in utxos.iter() {
+        let used = if ls.wallet_state.selected_utxos.contains(id) { "x" } else { "" };
+        rows.push(vec![
+            used.to_string(),
+            rounded_balance_i64(u.amount() as i64).to_string(),
+            ((current_time - u.time).max(0) / 1000).to_string(),
+            utxo_id_string(id),
+        ]);
+    }
+    // Clicking a row toggles it in the manual coin selection
+    let selected = &mut ls.wallet_state.selected_utxos;
+    data_table(ui, "utxo_table", &mut ls.wallet_state.utxo_table, rows, |i, _| {
+        if let Some((id, _)) = utxos.get(i) {
+            if !selected.remove(id) {
+                selected.insert(id.clone());
+            }
+        }
+    });
+    ui.horizontal(|ui| {
+        if ui.button(tr("Clear Selection")).clicked() {
+            ls.wallet_state.selected_utxos.clear();
+        }
+        if ui.button(tr("Consolidate")).on_hover_text("Merge selected (or all) UTXOs into one output to this address").clicked() {
+            let result = ai.address.safe_get_msg("Missing address on address info").cloned()
+                .and_then(|a| {
+                    let entries = selected_or_all_utxos(&ai, &ls.wallet_state.selected_utxos);
+                    TransactionBuilder::new(&ls.node_config.network)
+                        .with_utxos(&entries)?
+                        .build_sweep(&a)
+                });
+            ls.wallet_state.send_currency_type = SupportedCurrency::Redgold;
+            ls.wallet_state.send_receive = Some(SendReceiveTabs::Send);
+            ls.wallet_state.update_unsigned_tx(Some(result.clone()));
+            ls.wallet_state.signing_flow_transaction_box_msg = Some(result.clone().json_or_combine());
+            let status = result.map(|_x| "Consolidation Prepared".to_string())
+                .unwrap_or("Consolidation Failed".to_string());
+            ls.wallet_state.signing_flow_status = Some(status);
+        }
+    });
+}
+
+/// Recent transactions of the active address with the net amount moved by each.
+fn transaction_history_panel(ui: &mut Ui, ls: &mut LocalState) {
+    let ai = match &ls.wallet_state.address_info {
+        None => {
+            ui.label(tr("Refresh balance to load transaction history"));
+            return;
+        }
+        Some(ai) => ai.clone()
+    };
+    let address = match ai.address.as_ref() {
+        None => return,
+        Some(a) => a.clone()
+    };
+    let format_time = |time: i64| NaiveDateTime::from_timestamp_opt(time / 1000, 0)
+        .map(|d| d.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or(time.to_string());
+    let mut rows = vec![vec!["Time".to_string(), "Direction".to_string(), "Amount".to_string(), "Hash".to_string()]];
+    let pending = ls.wallet_state.pending_transactions.clone();
+    let pending_label = |p: &PendingTransaction| format!(
+        "{} ({:.1} / {:.1})", tr("Pending"), p.acceptance_weight, p.acceptance_threshold
+    );
+    let known = ai.recent_transactions.iter().map(|t| t.hash_or().hex()).collect::<HashSet<String>>();
+    for p in pending.iter().filter(|p| !known.contains(&p.transaction_hash)) {
+        rows.push(vec![
+            format_time(p.time),
+            pending_label(p),
+            p.amount.to_string(),
+            p.transaction_hash.clone(),
+        ]);
+    }
+    for t in ai.recent_transactions.iter() {
+        let outgoing = t.input_addresses().contains(&address);
+        let received: i64 = t.output_of(&address).iter().filter_map(|o| o.opt_amount()).sum();
+        let net = if outgoing {
+            received - t.inputs.iter().filter_map(|i| i.output.as_ref())
+                .filter(|o| o.address.as_ref() == Some(&address))
+                .filter_map(|o| o.opt_amount())
+                .sum::<i64>()
+        } else {
+            received
+        };
+        let time = t.time().cloned().unwrap_or(0);
+        let hash = t.hash_or().hex();
+        let direction = match pending.iter().find(|p| p.transaction_hash == hash) {
+            Some(p) => pending_label(p),
+            None => if outgoing { "Sent" } else { "Received" }.to_string(),
+        };
+        rows.push(vec![
+            format_time(time),
+            direction,
+            rounded_balance_i64(net).to_string(),
+            hash,
+        ]);
+    }
+    ui.label(tr("Click a transaction to copy its hash"));
+    let mut copied = None;
+    data_table(ui, "transaction_history_table", &mut ls.wallet_state.history_table, rows, |_, row| {
+        copied = row.get(3).cloned();
+    });
+    if let Some(h) = copied {
+        ui.ctx().output_mut(|o| o.copied_text = h);
+    }
+    for p in pending.iter() {
+        ui.horizontal(|ui| {
+            ui.label(format!("{} {}", tr("Pending"), p.transaction_hash));
+            if ui.button(tr("Rebroadcast")).clicked() {
+                rebroadcast_transaction(ls, p.raw_transaction.clone());
+            }
+        });
+    }
+    if let Some(s) = ls.wallet_state.rebroadcast_status.as_ref() {
+        ui.label(s);
+    }
+}
+
+// Resubmits to the node, which also restarts its own re-broadcast backoff for the transaction
+fn rebroadcast_transaction(ls: &mut LocalState, tx: Transaction) {
+    let client = ls.node_config.api_client();
+    let updates = ls.updates.clone();
+    tokio::spawn(async move {
+        let status = match client.send_transaction(&tx, false).await {
+            Ok(_) => format!("Rebroadcast {}", tx.hash_or().hex()),
+            Err(e) => format!("Rebroadcast failed: {}", e.json_or()),
+        };
+        LocalState::send_update(&updates, move |lss| {
+            lss.wallet_state.rebroadcast_status = Some(status.clone());
+        });
+    });
+}
+
+fn marked_status_label(status: &MarkedOutputStatus) -> String {
+    match status {
+        MarkedOutputStatus::AwaitingFulfillment => "Awaiting BTC payout".to_string(),
+        MarkedOutputStatus::Refunded(h) => format!("Refunded in {}", h),
+        MarkedOutputStatus::Staked => "Staked".to_string(),
+        MarkedOutputStatus::Withdrawable => "Withdrawable".to_string(),
+        MarkedOutputStatus::WithdrawalRequested => "Withdrawal requested".to_string(),
+        MarkedOutputStatus::Received(txid) => format!("Received for BTC tx {}", txid),
+    }
+}
+
+/// Swap and stake marked outputs of the active address, with guided redemption in place of
+/// hand written custom transactions.
+fn marked_outputs_panel(ui: &mut Ui, ls: &mut LocalState) {
+    let ai = match &ls.wallet_state.address_info {
+        None => {
+            ui.label(tr("Refresh balance to load swaps and stakes"));
+            return;
+        }
+        Some(ai) => ai.clone()
+    };
+    let marked = marked_outputs(&ai);
+    if marked.is_empty() {
+        ui.label("No swap or stake outputs in recent transactions");
+        return;
+    }
+    let current_time = ls.current_time;
+    let mut withdraw: Option<MarkedOutput> = None;
+    ScrollArea::vertical().id_source("marked_outputs_panel").max_height(200.0).show(ui, |ui| {
+        egui::Grid::new("marked_outputs_grid")
+            .num_columns(6)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("Type");
+                ui.strong("Amount");
+                ui.strong("Age");
+                ui.strong("Status");
+                ui.strong("Transaction");
+                ui.strong("Action");
+                ui.end_row();
+                for m in marked.iter() {
+                    ui.label(format!("{:?}", m.kind));
+                    ui.label(rounded_balance_i64(m.amount).to_string());
+                    ui.label(utxo_age(current_time, m.time));
+                    ui.label(marked_status_label(&m.status));
+                    ui.horizontal(|ui| {
+                        ui.label(m.tx_hash.chars().take(16).collect::<String>());
+                        common::copy_to_clipboard(ui, m.tx_hash.clone());
+                    });
+                    let can_withdraw = m.status == MarkedOutputStatus::Withdrawable && !m.counterparty.is_empty();
+                    if m.kind == MarkedOutputKind::StakeMarker {
+                        if ui.add_enabled(can_withdraw, egui::Button::new("Request Withdrawal"))
+                            .on_hover_text("Spend the stake marker to ask the party to return the stake to this address")
+                            .clicked() {
+                            withdraw = Some(m.clone());
+                        }
+                    } else {
+                        ui.label("");
+                    }
+                    ui.end_row();
+                }
+            });
+    });
+    if let Some(m) = withdraw {
+        let result = Address::parse(m.counterparty.clone())
+            .and_then(|party| prepare_stake_withdrawal(&ai, &m, &party, &ls.node_config.network));
+        ls.wallet_state.send_currency_type = SupportedCurrency::Redgold;
+        ls.wallet_state.send_receive = Some(SendReceiveTabs::Send);
+        ls.wallet_state.update_unsigned_tx(Some(result.clone()));
+        ls.wallet_state.signing_flow_transaction_box_msg = Some(result.clone().json_or_combine());
+        let status = result.map(|_x| "Stake Withdrawal Prepared".to_string())
+            .unwrap_or("Stake Withdrawal Failed".to_string());
+        ls.wallet_state.signing_flow_status = Some(status);
+    }
 }
 
 fn send_view(ui: &mut Ui, ls: &mut LocalState, _pk: &PublicKey) {
 
-    ComboBox::from_label("Currency")
+    ComboBox::from_label(tr("Currency"))
         .selected_text(format!("{:?}", ls.wallet_state.send_currency_type))
         .show_ui(ui, |ui| {
             let styles = vec![SupportedCurrency::Bitcoin, SupportedCurrency::Redgold];
@@ -419,28 +845,216 @@ fn send_view(ui: &mut Ui, ls: &mut LocalState, _pk: &PublicKey) {
                 ui.selectable_value(&mut ls.wallet_state.send_currency_type, style.clone(), format!("{:?}", style));
             }
         });
+    clipboard_detect(ui, ls);
     ui.horizontal(|ui| {
-        ui.label("Destination Address");
+        ui.label(tr("Destination Address"));
         let string = &mut ls.wallet_state.destination_address;
-        ui.add(egui::TextEdit::singleline(string).desired_width(460.0));
+        let response = ui.add(egui::TextEdit::singleline(string).desired_width(460.0));
+        // Enter moves on to the amount rather than the copy button next in tab order
+        if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+            ls.wallet_state.focus_amount = true;
+        }
+        let string = &ls.wallet_state.destination_address;
         common::copy_to_clipboard(ui, string.clone());
         let valid_addr = Address::parse(string.clone()).is_ok();
+        let is_name = !valid_addr && validate_name(string).is_ok();
         if valid_addr {
-            ui.label(RichText::new("Valid").color(Color32::GREEN));
+            ui.label(RichText::new(tr("Valid")).color(Color32::GREEN));
+        } else if is_name {
+            ui.label(RichText::new(tr("Name")).color(Color32::LIGHT_BLUE));
         } else {
-            ui.label(RichText::new("Invalid").color(Color32::RED));
+            ui.label(RichText::new(tr("Invalid")).color(Color32::RED));
         }
     });
-    // TODO: Amount USD and conversions etc.
+    let now = ls.current_time;
+    if ls.wallet_state.send_currency_type == SupportedCurrency::Redgold {
+        name_lookup_view(ui, ls, now);
+    }
     ui.horizontal(|ui| {
-        ui.label("Amount");
+        ui.label(tr("Amount"));
         let string = &mut ls.wallet_state.amount_input;
-        ui.add(egui::TextEdit::singleline(string).desired_width(200.0));
-        ui.checkbox(&mut ls.wallet_state.mark_output_as_stake, "Mark as Stake");
-        ui.checkbox(&mut ls.wallet_state.mark_output_as_swap, "Mark as Swap");
+        let response = ui.add(egui::TextEdit::singleline(string).desired_width(200.0));
+        if ls.wallet_state.focus_amount {
+            ls.wallet_state.focus_amount = false;
+            response.request_focus();
+        }
+        ui.checkbox(&mut ls.wallet_state.amount_in_usd, "USD");
+        let ws = &ls.wallet_state;
+        let currency = &ws.send_currency_type;
+        if ws.amount_in_usd {
+            let converted = ws.send_amount(now)
+                .map(|a| format!("= {:.8} {:?}", a, currency))
+                .unwrap_or("Price unavailable".to_string());
+            ui.label(converted);
+        } else if let Some(a) = ws.send_amount(now) {
+            ui.label(ws.fiat_prices.usd_label(a, currency, now));
+        }
+        ui.checkbox(&mut ls.wallet_state.mark_output_as_stake, tr("Mark as Stake"));
+        ui.checkbox(&mut ls.wallet_state.mark_output_as_swap, tr("Mark as Swap"));
+    });
+
+}
 
+/// Resolves a destination entered as a registered name and shows what it is bound to, so the
+/// user can check the address before preparing the transaction.
+fn name_lookup_view(ui: &mut Ui, ls: &mut LocalState, now: i64) {
+    let name = ls.wallet_state.destination_address.clone();
+    if Address::parse(name.clone()).is_ok() || validate_name(&name).is_err() {
+        return;
+    }
+    let stale = ls.wallet_state.name_lookup.as_ref().map(|(n, _)| n != &name).unwrap_or(true);
+    if stale {
+        ls.wallet_state.name_lookup = Some((name.clone(), None));
+        lookup_name(&ls.node_config, name.clone(), ls.wallet_state.updates.sender.clone());
+    }
+    let format_time = |time: i64| NaiveDateTime::from_timestamp_opt(time / 1000, 0)
+        .map(|d| d.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or(time.to_string());
+    match ls.wallet_state.name_lookup.as_ref().and_then(|(_, r)| r.as_ref()) {
+        None => {
+            ui.label(tr("Resolving name..."));
+        }
+        Some(Ok(r)) => {
+            let address = r.address.render_string().unwrap_or("".to_string());
+            let owner = r.owner.render_string().unwrap_or("".to_string());
+            ui.horizontal(|ui| {
+                ui.label(tr("Resolved Address"));
+                ui.label(RichText::new(address.clone()).monospace());
+                common::copy_to_clipboard(ui, address);
+            });
+            ui.horizontal(|ui| {
+                ui.label(tr("Owner"));
+                ui.label(RichText::new(owner).monospace());
+            });
+            ui.horizontal(|ui| {
+                ui.label(tr("Expires"));
+                ui.label(format_time(r.expiry_time));
+                if r.active(now) {
+                    ui.label(RichText::new(tr("Verified")).color(Color32::GREEN));
+                } else {
+                    ui.label(RichText::new(tr("Expired")).color(Color32::RED));
+                }
+            });
+        }
+        Some(Err(e)) => {
+            ui.label(RichText::new(format!("{}: {}", tr("Name lookup failed"), e.message)).color(Color32::RED));
+        }
+    }
+}
+
+fn lookup_name(node_config: &NodeConfig, name: String, update_channel: flume::Sender<StateUpdate>) {
+    let node_config = node_config.clone();
+    let _ = tokio::spawn(async move {
+        let result = node_config.api_client().resolve_name(name.clone()).await;
+        let up = StateUpdate {
+            update: Box::new(move |ls: &mut LocalState| {
+                // Ignore lookups for a name the user has since edited away from
+                if ls.wallet_state.name_lookup.as_ref().map(|(n, _)| n == &name).unwrap_or(false) {
+                    ls.wallet_state.name_lookup = Some((name.clone(), Some(result.clone())));
+                }
+            }),
+        };
+        update_channel.send_err(up).log_error().ok();
     });
+}
 
+fn sign_hot(ls: &mut LocalState, t: &Transaction, usd: Option<f64>) {
+    let kp = ls.wallet_state.hot_mnemonic().keypair_at(ls.wallet_state.derivation_path.clone()).expect("kp");
+    let mut t2 = t.clone();
+    let signed = t2.sign(&kp);
+    if signed.is_ok() {
+        ls.wallet_state.session_spent_usd += usd.unwrap_or(0.0);
+    }
+    ls.wallet_state.update_signed_tx(Some(signed));
+}
+
+/// Holds back signing a send over the spending limit until the session password is re-entered
+/// or the countdown has elapsed and the user confirms.
+fn limit_confirmation_view(ui: &mut Ui, ls: &mut LocalState) {
+    let prepared = ls.wallet_state.prepared_transaction.as_ref().and_then(|r| r.as_ref().ok()).cloned();
+    let pending = ls.wallet_state.limit_confirmation.as_ref().map(|c| c.tx_hash.clone());
+    if pending.is_none() {
+        return;
+    }
+    // Preparing a different transaction discards the confirmation
+    if prepared.as_ref().map(|t| t.hash_hex_or_missing()) != pending {
+        ls.wallet_state.limit_confirmation = None;
+        return;
+    }
+    let t = prepared.expect("prepared");
+    let limits = ls.local_stored_state.spending_limits.clone().unwrap_or_default();
+    let use_password = limits.require_password && ls.has_session_password();
+    let now = ls.current_time;
+    let spent = ls.wallet_state.session_spent_usd;
+    let mut confirmed = false;
+    let mut cancelled = false;
+    let mut password_ok = None;
+    let c = ls.wallet_state.limit_confirmation.as_mut().expect("confirmation");
+    let value = c.usd.map(|u| format!("${:.2}", u)).unwrap_or("an unpriced amount".to_string());
+    ui.label(RichText::new(format!(
+        "This send of {} exceeds the session spending limit of ${:.2} (${:.2} already sent)",
+        value, limits.session_limit_usd, spent
+    )).color(Color32::RED));
+    ui.horizontal(|ui| {
+        if use_password {
+            ui.label("Session Password");
+            ui.add(egui::TextEdit::singleline(&mut c.password_entry).password(true).desired_width(200.0));
+            if ui.button("Confirm Send").clicked() {
+                password_ok = Some(c.password_entry.clone());
+            }
+        } else {
+            let remaining = limits.confirm_countdown_secs as i64 - (now - c.started) / 1000;
+            if remaining > 0 {
+                ui.ctx().request_repaint_after(std::time::Duration::from_millis(500));
+            }
+            let label = if remaining > 0 { format!("Confirm Send ({})", remaining) } else { "Confirm Send".to_string() };
+            if ui.add_enabled(remaining <= 0, egui::Button::new(label)).clicked() {
+                confirmed = true;
+            }
+        }
+        if ui.button("Cancel").clicked() {
+            cancelled = true;
+        }
+    });
+    if let Some(e) = &c.error {
+        ui.label(RichText::new(e).color(Color32::RED));
+    }
+    if let Some(entry) = password_ok {
+        confirmed = ls.check_session_password(&entry);
+        if !confirmed {
+            let c = ls.wallet_state.limit_confirmation.as_mut().expect("confirmation");
+            c.error = Some("Incorrect session password".to_string());
+            c.password_entry = "".to_string();
+        }
+    }
+    if confirmed {
+        let usd = ls.wallet_state.limit_confirmation.take().and_then(|c| c.usd);
+        sign_hot(ls, &t, usd);
+    } else if cancelled {
+        ls.wallet_state.limit_confirmation = None;
+    }
+}
+
+// Polling the system clipboard every frame is expensive on some platforms
+const CLIPBOARD_POLL_MS: i64 = 1000;
+
+fn clipboard_detect(ui: &mut Ui, ls: &mut LocalState) {
+    let ws = &mut ls.wallet_state;
+    if ls.current_time - ws.clipboard_last_checked > CLIPBOARD_POLL_MS {
+        ws.clipboard_last_checked = ls.current_time;
+        ws.clipboard_uri = deep_link::clipboard_payment_uri()
+            .filter(|u| u.address != ws.destination_address);
+    }
+    if let Some(uri) = ws.clipboard_uri.clone() {
+        ui.horizontal(|ui| {
+            if ui.button("Paste detected address").clicked() {
+                ws.apply_payment_uri(&uri);
+                ws.clipboard_uri = None;
+            }
+            let amount = uri.amount.map(|a| format!(" for {} {:?}", a, uri.currency)).unwrap_or("".to_string());
+            ui.label(format!("{:?} {}{}", uri.currency, uri.address, amount));
+        });
+    }
 }
 
 fn swap_view(_ui: &mut Ui, _ls: &mut LocalState, _pk: &PublicKey) {
@@ -477,15 +1091,19 @@ pub fn prepared_view(ui: &mut Ui, ls: &mut LocalState, pk: &PublicKey) {
 
 
     if ui.button("Prepare Transaction").clicked() {
+        let send_amount = ls.wallet_state.send_amount(ls.current_time);
         if ls.wallet_state.send_currency_type == SupportedCurrency::Bitcoin {
-            if let Ok(amount) = ls.wallet_state.amount_input.parse::<f64>() {
-                let mut w = SingleKeyBitcoinWallet::new_wallet(
-                    pk.clone(), ls.node_config.network, true
+            if let Some(amount) = send_amount {
+                let mut w = SingleKeyBitcoinWallet::new_wallet_typed(
+                    pk.clone(), ls.node_config.network, true, &ls.node_config.btc_backend,
+                    ls.wallet_state.btc_address_type
                 ).expect("w");
                 let result = w.prepare_single(
                     ls.wallet_state.destination_address.clone(),
                     amount
                 );
+                ls.wallet_state.prepared_preview = w.prepared_amounts().ok()
+                    .map(|(i, o, fee)| TransactionPreview::from_btc(i, o, fee));
                 ls.wallet_state.signing_flow_transaction_box_msg = Some(
                     result.clone().json_or_combine()
                 );
@@ -497,10 +1115,12 @@ pub fn prepared_view(ui: &mut Ui, ls: &mut LocalState, pk: &PublicKey) {
             match &ls.wallet_state.address_info {
                 None => {}
                 Some(ai) => {
+                    let amount = send_amount.map(|a| a.to_string()).unwrap_or(ls.wallet_state.amount_input.clone());
+                    let destination = ls.wallet_state.resolved_destination();
                     let result = prepare_transaction(
                         ai,
-                        &ls.wallet_state.amount_input,
-                        &ls.wallet_state.destination_address,
+                        &amount,
+                        &destination,
                         &ls.wallet_state,
                         &ls.node_config
                     );
@@ -520,6 +1140,9 @@ pub fn prepared_view(ui: &mut Ui, ls: &mut LocalState, pk: &PublicKey) {
             )
         }
     }
+    if let Some(p) = &ls.wallet_state.prepared_preview {
+        preview_view(ui, p, &ls.wallet_state.fiat_prices, ls.current_time);
+    }
     if let Some(p) = &ls.wallet_state.signing_flow_transaction_box_msg {
         // ui.with_layout(
         //     Layout::centered_and_justified(Direction::TopDown)
@@ -548,10 +1171,24 @@ pub fn prepared_view(ui: &mut Ui, ls: &mut LocalState, pk: &PublicKey) {
                             ls.wallet_state.signing_flow_status = Some("Awaiting hardware response...".to_string());
                         }
                         WalletTab::Software => {
-                            let kp = ls.wallet_state.hot_mnemonic().keypair_at(ls.wallet_state.derivation_path.clone()).expect("kp");
-                            let mut t2 = t.clone();
-                            let signed = t2.sign(&kp);
-                            ls.wallet_state.update_signed_tx(Some(signed));
+                            let t = t.clone();
+                            let ws = &ls.wallet_state;
+                            let usd = ws.send_amount(ls.current_time)
+                                .and_then(|a| ws.fiat_prices.to_usd(a, &ws.send_currency_type, ls.current_time));
+                            let over_limit = ls.local_stored_state.spending_limits.as_ref()
+                                .map(|l| l.requires_confirmation(ws.session_spent_usd, usd))
+                                .unwrap_or(false);
+                            if over_limit {
+                                ls.wallet_state.limit_confirmation = Some(LimitConfirmation {
+                                    tx_hash: t.hash_hex_or_missing(),
+                                    usd,
+                                    started: ls.current_time,
+                                    password_entry: "".to_string(),
+                                    error: None,
+                                });
+                            } else {
+                                sign_hot(ls, &t, usd);
+                            }
                         }
                     }
                 } else if ls.wallet_state.send_currency_type == SupportedCurrency::Bitcoin {
@@ -577,9 +1214,17 @@ pub fn prepared_view(ui: &mut Ui, ls: &mut LocalState, pk: &PublicKey) {
             }
         }
     }
+    limit_confirmation_view(ui, ls);
     if let Some(m) = &ls.wallet_state.signing_flow_status {
         ui.label(m);
     }
+    if ls.wallet_state.prepared_transaction.as_ref().map(|r| r.is_ok()).unwrap_or(false) {
+        let ws = &ls.wallet_state;
+        if let Some(a) = ws.send_amount(ls.current_time) {
+            ui.label(format!("Sending {} {:?} {}", a, ws.send_currency_type,
+                             ws.fiat_prices.usd_label(a, &ws.send_currency_type, ls.current_time)));
+        }
+    }
     if let Some(t) = &ls.wallet_state.signed_transaction {
         if let Some(t) = t.as_ref().ok() {
             medium_data_item(ui, "Signed TX Hash:", ls.wallet_state.signed_transaction_hash.clone().unwrap_or("error".to_string()));
@@ -596,6 +1241,144 @@ pub fn prepared_view(ui: &mut Ui, ls: &mut LocalState, pk: &PublicKey) {
     }
 }
 
+fn preview_view(ui: &mut Ui, p: &TransactionPreview, prices: &FiatPrices, time: i64) {
+    ui.label("Transaction Preview");
+    let amount = |a: i64| format!("{} {:?} {}", rounded_balance_i64(a), p.currency,
+                                  prices.usd_label(rounded_balance_i64(a), &p.currency, time));
+    egui::Grid::new("tx_preview_grid")
+        .num_columns(3)
+        .striped(true)
+        .show(ui, |ui| {
+            for i in p.inputs.iter() {
+                ui.label("Input");
+                ui.label(i.address.clone());
+                ui.label(amount(i.amount));
+                ui.end_row();
+            }
+            for o in p.outputs.iter() {
+                ui.label(o.marker.as_ref().map(|m| format!("Output ({})", m)).unwrap_or("Output".to_string()));
+                ui.label(o.address.clone());
+                ui.label(amount(o.amount));
+                ui.end_row();
+            }
+            ui.label("Fee");
+            ui.label("");
+            ui.label(amount(p.fee));
+            ui.end_row();
+            for (address, change) in p.net_changes.iter() {
+                ui.label("Net Change");
+                ui.label(address.clone());
+                ui.label(amount(*change));
+                ui.end_row();
+            }
+        });
+    for w in p.warnings.iter() {
+        ui.colored_label(Color32::YELLOW, format!("Warning: {}", w));
+    }
+}
+
+fn receive_view(ui: &mut Ui, ls: &mut LocalState, pk: &PublicKey) {
+    ui.horizontal(|ui| {
+        ui.label("Requested Amount:");
+        egui::TextEdit::singleline(&mut ls.wallet_state.receive_amount_input)
+            .desired_width(100f32)
+            .show(ui);
+    });
+    let amount = ls.wallet_state.receive_amount_input.trim().parse::<f64>().ok();
+    if !ls.wallet_state.receive_amount_input.trim().is_empty() && amount.is_none() {
+        ui.label(RichText::new("Invalid amount").color(Color32::RED));
+    }
+    let mut uris = vec![];
+    if let Ok(a) = pk.address().and_then(|a| a.render_string()) {
+        uris.push(PaymentUri::new(SupportedCurrency::Redgold, a, amount));
+    }
+    if ls.wallet_state.show_btc_info {
+        let network = bitcoin_network(&ls.node_config.network);
+        if let Ok(a) = ls.wallet_state.btc_address_type.address(pk, network) {
+            uris.push(PaymentUri::new(SupportedCurrency::Bitcoin, a.to_string(), amount));
+        }
+    }
+    let path = ls.wallet_state.derivation_path.clone();
+    ui.horizontal(|ui| {
+        for uri in uris {
+            let uri_str = uri.to_uri();
+            let cache = &mut ls.wallet_state.receive_qr;
+            if !cache.contains_key(&uri_str) {
+                if cache.len() > 8 {
+                    cache.clear();
+                }
+                cache.insert(uri_str.clone(), qr_encode(uri_str.clone()));
+            }
+            ui.vertical(|ui| {
+                ui.heading(format!("{:?}", uri.currency));
+                if let Some(img) = ls.wallet_state.receive_qr.get(&uri_str) {
+                    img.show_max_size(ui, egui::vec2(200.0, 200.0));
+                }
+                editable_text_input_copy(ui, "Address", &mut uri.address.clone(), 200.0);
+                editable_text_input_copy(ui, "URI", &mut uri_str.clone(), 200.0);
+                ui.label(RichText::new(format!("Derivation path: {}", path)).small());
+            });
+        }
+    });
+}
+
+fn sign_message_view(ui: &mut Ui, ls: &mut LocalState, pk: &PublicKey) {
+    ui.label("Message to sign:");
+    ui.horizontal(|ui| bounded_text_area(ui, &mut ls.wallet_state.sign_message_input));
+    if ui.button("Sign").clicked() {
+        let message = ls.wallet_state.sign_message_input.clone();
+        match ls.wallet_state.tab {
+            WalletTab::Hardware => {
+                ls.wallet_state.signed_message_output = Some("Awaiting hardware response...".to_string());
+                let public = pk.clone();
+                let updates = ls.wallet_state.updates.clone();
+                tokio::spawn(async move {
+                    let res = trezor::trezor_proof(&message_hash(&message), public, trezor::default_pubkey_path())
+                        .and_then(|proof| SignedMessage::from_proof(message, proof))
+                        .log_error()
+                        .map(|m| m.json_or())
+                        .unwrap_or_else(|e| e.json_or());
+                    LocalState::send_update(&updates, move |ls| {
+                        ls.wallet_state.signed_message_output = Some(res.clone());
+                    });
+                });
+            }
+            WalletTab::Software => {
+                let res = ls.wallet_state.hot_mnemonic().keypair_at(ls.wallet_state.derivation_path.clone())
+                    .and_then(|kp| SignedMessage::sign(message, &kp))
+                    .map(|m| m.json_or())
+                    .unwrap_or_else(|e| e.json_or());
+                ls.wallet_state.signed_message_output = Some(res);
+            }
+        }
+    }
+    if let Some(out) = &ls.wallet_state.signed_message_output {
+        ui.label("Signed message proof:");
+        let string1 = &mut out.clone();
+        ui.horizontal(|ui| {
+            bounded_text_area(ui, string1);
+            common::copy_to_clipboard(ui, out.clone());
+        });
+    }
+    ui.separator();
+    ui.label("Signed message proof to verify:");
+    ui.horizontal(|ui| bounded_text_area(ui, &mut ls.wallet_state.verify_message_input));
+    if ui.button("Verify").clicked() {
+        let res = SignedMessage::parse(&ls.wallet_state.verify_message_input)
+            .and_then(|m| m.verify().map(|_| m.address.clone()));
+        ls.wallet_state.verify_message_result = Some(res);
+    }
+    match &ls.wallet_state.verify_message_result {
+        Some(Ok(address)) => {
+            ui.label(RichText::new(format!("Valid signature from {}", address)).color(Color32::GREEN));
+        }
+        Some(Err(e)) => {
+            ui.label(RichText::new(format!("Invalid: {}", e.json_or())).color(Color32::RED));
+        }
+        None => {}
+    }
+}
+
 fn send_receive_bar(ui: &mut Ui, ls: &mut LocalState, pk: &PublicKey) {
     ui.horizontal(|ui| {
         let style = ui.style_mut();
@@ -624,6 +1407,14 @@ fn send_receive_bar(ui: &mut Ui, ls: &mut LocalState, pk: &PublicKey) {
                 ls.wallet_state.send_receive = some;
             }
         }
+        if ui.button("Sign Message").clicked() {
+            let some = Some(SendReceiveTabs::SignMessage);
+            if ls.wallet_state.send_receive == some.clone() {
+                ls.wallet_state.send_receive = None;
+            } else {
+                ls.wallet_state.send_receive = some;
+            }
+        }
         // if ui.button("Swap").clicked() {
         //     let some = Some(SendReceiveTabs::Swap);
         //     if ls.wallet_state.send_receive == some.clone() {
@@ -637,18 +1428,21 @@ fn send_receive_bar(ui: &mut Ui, ls: &mut LocalState, pk: &PublicKey) {
 
         ui.with_layout(layout, |ui| {
 
-            let url_env = if ls.node_config.network.is_main() {
-                "".to_string()
-            } else {
-                format!("{}.",ls.node_config.network.to_std_string())
-            };
-            // TODO: Format the address of some xpub.
-            let env_formatted_faucet = format!("https://{}explorer.redgold.io/faucet", url_env, );
-            ui.hyperlink_to("Faucet", env_formatted_faucet);
-            ui.label(ls.wallet_state.faucet_success.clone());
+            if ls.node_config.features.faucet {
+                let url_env = if ls.node_config.network.is_main() {
+                    "".to_string()
+                } else {
+                    format!("{}.",ls.node_config.network.to_std_string())
+                };
+                // TODO: Format the address of some xpub.
+                let env_formatted_faucet = format!("https://{}explorer.redgold.io/faucet", url_env, );
+                ui.hyperlink_to("Faucet", env_formatted_faucet);
+                ui.label(ls.wallet_state.faucet_success.clone());
+            }
             if ui.button("Refresh Balance").clicked() {
                 get_address_info(&ls.node_config, ls.wallet_state.public_key.clone().expect("pk"),
                                  ls.wallet_state.show_btc_info.clone(),
+                                 ls.wallet_state.btc_address_type,
                                  ls.wallet_state.updates.sender.clone(),
                 );
             };
@@ -681,6 +1475,7 @@ pub fn derivation_path_section(ui: &mut Ui, ls: &mut LocalState) {
                                 &ls.node_config,
                                 pk.clone(),
                                 ls.wallet_state.show_btc_info,
+                                ls.wallet_state.btc_address_type,
                                 ls.wallet_state.updates.sender.clone(),
                             );
                         }
@@ -718,32 +1513,35 @@ fn window_xpub(
                 editable_text_input_copy(ui, "Name", &mut ls.wallet_state.xpub_save_name, 150.0);
                 if ui.button("Save Internal").clicked() {
                     let xpub = ls.wallet_state.active_xpub.clone();
+                    let software = ls.wallet_state.tab == WalletTab::Software;
                     let named_xpub = NamedXpub {
                         name: ls.wallet_state.xpub_save_name.clone(),
                         derivation_path: ls.wallet_state.xpub_derivation_path.clone(),
                         xpub,
-                        hot_offset: if ls.wallet_state.tab == WalletTab::Software {
-                            if ls.wallet_state.hot_offset != "" {
-                                Some(ls.wallet_state.hot_offset.clone())
-                            } else {
-                                None
-                            }
-                        } else {
-                            None
-                        },
+                        hot_offset: Some(ls.wallet_state.hot_offset.clone()).filter(|o| software && !o.is_empty()),
+                        uses_passphrase: Some(!ls.wallet_state.hot_passphrase.is_empty()).filter(|_| software),
+                        seed_checksum: ls.wallet_state.active_xpub_seed_checksum.clone().filter(|_| software),
+                        account_index: NamedXpub::account_from_path(&ls.wallet_state.xpub_derivation_path),
                     };
-                    ls.updates.sender.send(StateUpdate {
+                    let key_name = Some(ls.wallet_state.selected_key_name.clone()).filter(|_| software);
+                    ls.updates.send_err(StateUpdate {
                         update: Box::new(
                             move |lss: &mut LocalState| {
                                 let new_named = named_xpub.clone();
                                 let mut new_xpubs = lss.local_stored_state.xpubs.iter().filter(|x| {
                                     x.name != new_named.name
                                 }).map(|x| x.clone()).collect_vec();
-                                new_xpubs.push(new_named);
+                                new_xpubs.push(new_named.clone());
                                 lss.local_stored_state.xpubs = new_xpubs;
+                                if let Some(k) = key_name.clone() {
+                                    let mut account = lss.local_stored_state.account(&new_named.name)
+                                        .unwrap_or(Account::for_xpub(new_named.name.clone()));
+                                    account.key_name = Some(k);
+                                    lss.local_stored_state.upsert_account(account);
+                                }
                                 lss.persist_local_state_store();
                             })
-                    }).unwrap();
+                    }).log_error().ok();
                     ;
                     ls.wallet_state.xpub_save_name = "".to_string();
                     LocalState::send_update(&ls.updates, |lss| {
@@ -824,6 +1622,25 @@ fn window_xpub_loader(
 }
 
 
+/// Explains why a stored software xpub doesn't derive from the active mnemonic, passphrase and offset.
+fn software_xpub_mismatch(ws: &WalletState, named_xpub: &NamedXpub) -> Option<String> {
+    let hot = ws.hot_mnemonic();
+    let matches = hot.xpub(named_xpub.derivation_path.clone())
+        .map(|x| x.to_string() == named_xpub.xpub)
+        .unwrap_or(false);
+    if matches {
+        return None;
+    }
+    let message = if named_xpub.uses_passphrase == Some(true) && ws.hot_passphrase.is_empty() {
+        "Xpub was saved with a passphrase, enter it and press Update"
+    } else if named_xpub.seed_checksum.is_some() && named_xpub.seed_checksum != hot.checksum().ok() {
+        "Active mnemonic or passphrase differs from the one this xpub was saved with"
+    } else {
+        "Xpub does not derive from the active mnemonic"
+    };
+    Some(message.to_string())
+}
+
 pub fn xpub_path_section(ui: &mut Ui, ls: &mut LocalState, ctx: &Context) {
     window_xpub(ui, ls, ctx);
     window_xpub_loader(ui, ls, ctx);
@@ -852,6 +1669,7 @@ pub fn xpub_path_section(ui: &mut Ui, ls: &mut LocalState, ctx: &Context) {
                         Ok(xpub) => {
                             ls.wallet_state.show_save_xpub_window = true;
                             ls.wallet_state.active_xpub = xpub.clone();
+                            ls.wallet_state.active_xpub_seed_checksum = None;
                             let pk = XpubWrapper::new(xpub).public_at(0, 0).expect("xpub failure");
                             ls.wallet_state.public_key = Some(pk.clone());
                             ls.wallet_state.public_key_msg = Some("Got public key".to_string());
@@ -859,6 +1677,7 @@ pub fn xpub_path_section(ui: &mut Ui, ls: &mut LocalState, ctx: &Context) {
                                 &ls.node_config,
                                 pk,
                                              ls.wallet_state.show_btc_info.clone(),
+                                             ls.wallet_state.btc_address_type,
                                              ls.wallet_state.updates.sender.clone(),
                             );
                         }
@@ -874,40 +1693,59 @@ pub fn xpub_path_section(ui: &mut Ui, ls: &mut LocalState, ctx: &Context) {
 
 
     ui.horizontal(|ui| {
-        ComboBox::from_label("Set Xpub Source")
-            .selected_text(ls.wallet_state.selected_xpub_name.clone())
-            .show_ui(ui, |ui| {
-                for style in ls.local_stored_state.xpubs.iter().map(|x| x.name.clone()) {
-                    ui.selectable_value(&mut ls.wallet_state.selected_xpub_name, style.clone(), style.to_string());
+        let accounts = ls.local_stored_state.accounts();
+        let previous = ls.wallet_state.selected_account.clone();
+        account_selector(ui, "Set Account", &accounts, &mut ls.wallet_state.selected_account);
+        if previous != ls.wallet_state.selected_account {
+            if let Some(a) = ls.wallet_state.selected_account.as_ref().and_then(|n| ls.local_stored_state.account(n)) {
+                ls.wallet_state.account_color_edit = account_color(&a);
+                ls.wallet_state.account_currencies_edit = a.currencies.clone();
+            }
+        }
+        if ui.button("Load Account").clicked() {
+            let account = ls.wallet_state.selected_account.as_ref()
+                .and_then(|n| ls.local_stored_state.account(n));
+            if let Some(account) = account {
+                if !account.currencies.is_empty() {
+                    ls.wallet_state.show_btc_info = account.currencies.contains(&SupportedCurrency::Bitcoin);
                 }
-                ui.selectable_value(&mut ls.wallet_state.selected_xpub_name,
-                                    "Select Xpub".to_string(), "Select Xpub".to_string());
-            });
-        if ui.button("Load Xpub").clicked() {
-            let xpub = ls.local_stored_state.xpubs.iter().find(|x|
-                x.name == ls.wallet_state.selected_xpub_name);
-            if let Some(named_xpub) = xpub {
-                let xpub = named_xpub.clone().xpub.clone();
-                ls.wallet_state.active_xpub = xpub.clone();
-                let pk = XpubWrapper::new(xpub).public_at(0, 0).expect("xpub failure");
-                ls.wallet_state.public_key = Some(pk.clone());
-                let dp = format!("{}/0/0", named_xpub.derivation_path.clone());
-                if ls.wallet_state.tab == WalletTab::Hardware {
-                    ls.wallet_state.active_derivation_path = dp;
-                } else {
-                    ls.wallet_state.derivation_path = dp;
-                    ls.wallet_state.xpub_derivation_path = named_xpub.derivation_path.clone();
-                    ls.wallet_state.active_derivation_path = named_xpub.derivation_path.clone();
+                if let Some(k) = &account.key_name {
+                    if ls.wallet_state.tab == WalletTab::Software {
+                        ls.wallet_state.selected_key_name = k.clone();
+                    }
+                }
+                if let Some(named_xpub) = ls.local_stored_state.account_xpub(&account).cloned() {
+                    let xpub = named_xpub.xpub.clone();
+                    ls.wallet_state.active_xpub = xpub.clone();
+                    let pk = XpubWrapper::new(xpub).public_at(0, 0).expect("xpub failure");
+                    ls.wallet_state.public_key = Some(pk.clone());
+                    let dp = format!("{}/0/0", named_xpub.derivation_path.clone());
+                    if ls.wallet_state.tab == WalletTab::Hardware {
+                        ls.wallet_state.active_derivation_path = dp;
+                        ls.wallet_state.xpub_load_warning = None;
+                    } else {
+                        ls.wallet_state.hot_offset = named_xpub.hot_offset.clone().unwrap_or("".to_string());
+                        ls.wallet_state.xpub_load_warning = software_xpub_mismatch(&ls.wallet_state, &named_xpub);
+                        ls.wallet_state.derivation_path = dp;
+                        ls.wallet_state.xpub_derivation_path = named_xpub.derivation_path.clone();
+                        ls.wallet_state.active_derivation_path = named_xpub.derivation_path.clone();
+                    }
                 }
             }
         }
     });
+    account_settings(ui, ls);
+    if let Some(w) = &ls.wallet_state.xpub_load_warning {
+        ui.label(RichText::new(w.clone()).color(Color32::RED));
+    }
     medium_data_item(ui, "Active Derivation Path:", ls.wallet_state.active_derivation_path.clone());
 
     if ls.wallet_state.tab == WalletTab::Software {
         if ui.button("Save Xpub").clicked() {
-            let xpub = ls.wallet_state.hot_mnemonic().xpub(ls.wallet_state.xpub_derivation_path.clone()).expect("xpub failure");
+            let hot = ls.wallet_state.hot_mnemonic();
+            let xpub = hot.xpub(ls.wallet_state.xpub_derivation_path.clone()).expect("xpub failure");
             ls.wallet_state.active_xpub = xpub.to_string();
+            ls.wallet_state.active_xpub_seed_checksum = hot.checksum().ok();
             ls.wallet_state.show_save_xpub_window = true;
         }
     }
@@ -918,6 +1756,37 @@ pub fn xpub_path_section(ui: &mut Ui, ls: &mut LocalState, ctx: &Context) {
 }
 
 
+fn account_settings(ui: &mut Ui, ls: &mut LocalState) {
+    let account = ls.wallet_state.selected_account.as_ref()
+        .and_then(|n| ls.local_stored_state.account(n));
+    if let Some(mut account) = account {
+        ui.horizontal(|ui| {
+            account_label(ui, &account);
+            ui.label("Color");
+            ui.color_edit_button_srgba(&mut ls.wallet_state.account_color_edit);
+            ui.label("Currencies");
+            for c in [SupportedCurrency::Redgold, SupportedCurrency::Bitcoin, SupportedCurrency::Ethereum] {
+                let mut shown = ls.wallet_state.account_currencies_edit.contains(&c);
+                if ui.checkbox(&mut shown, format!("{:?}", c)).changed() {
+                    if shown {
+                        ls.wallet_state.account_currencies_edit.push(c);
+                    } else {
+                        ls.wallet_state.account_currencies_edit.retain(|x| x != &c);
+                    }
+                }
+            }
+            if ui.button("Save Account").clicked() {
+                account.color = Some(hex_color(ls.wallet_state.account_color_edit));
+                account.currencies = ls.wallet_state.account_currencies_edit.clone();
+                LocalState::send_update(&ls.updates, move |lss| {
+                    lss.local_stored_state.upsert_account(account.clone());
+                    lss.persist_local_state_store();
+                });
+            }
+        });
+    }
+}
+
 //
 //
 // fn spawn_update(fun: impl Future<Output = Box<dyn FnMut(&mut LocalState) + Send>> + std::marker::Send, update_channel: Sender<StateUpdate>) {
@@ -944,6 +1813,23 @@ fn broadcast_transaction(nc: NodeConfig, tx: Transaction, ne: NetworkEnvironment
             .unwrap_or("Rejected Transaction".to_string()));
 
         let fun = move |ls: &mut LocalState| {
+            let accepted = st.as_ref().map(|r| r.is_ok()).unwrap_or(false);
+            if accepted {
+                if let Some(h) = ls.wallet_state.signed_transaction_hash.clone() {
+                    ls.wallet_state.observation_view.hash_input = h;
+                }
+                // Listed until the next balance refresh picks up the node's pending view
+                ls.wallet_state.pending_transactions.insert(0, PendingTransaction {
+                    transaction_hash: tx.hash_or().hex(),
+                    stage: PendingStage::Queued,
+                    time: tx.time().cloned().unwrap_or(ls.current_time),
+                    from: tx.first_input_address().and_then(|a| a.render_string().ok()).unwrap_or("".to_string()),
+                    amount: tx.total_output_amount_float(),
+                    acceptance_weight: 0.0,
+                    acceptance_threshold: ls.node_config.observation.acceptance_weight_threshold,
+                    raw_transaction: tx.clone(),
+                });
+            }
             ls.wallet_state.broadcast_transaction_response = st.clone();
             ls.wallet_state.signing_flow_transaction_box_msg = st_msg.clone();
             ls.wallet_state.signing_flow_status = ss.clone();
@@ -989,7 +1875,9 @@ pub fn prepare_transaction(ai: &AddressInfo, amount: &String, destination: &Stri
     let amount = CurrencyAmount::from_float_string(amount)?;
     let mut tb = TransactionBuilder::new(&nc.network);
     let a = ai.address.as_ref().expect("a");
-    tb.with_address_info(ai.clone());
+    // Manual coin control restricts inputs to the selected UTXOs
+    tb.with_utxos(&selected_or_all_utxos(ai, &x.selected_utxos))?;
+    tb.with_coin_selection(&nc.coin_selection);
     tb.with_output(&destination, &amount);
     if x.mark_output_as_swap {
         tb.with_last_output_withdrawal_swap();
@@ -1010,6 +1898,7 @@ pub fn get_address_info(
     node_config: &NodeConfig,
     public_key: PublicKey,
     show_btc_info: bool,
+    btc_address_type: BtcAddressType,
     update_channel: flume::Sender<StateUpdate>,
 ) {
     let node_config = node_config.clone();
@@ -1017,8 +1906,8 @@ pub fn get_address_info(
     let _ = tokio::spawn(async move {
 
         let btc_bal = if show_btc_info {
-            let w = SingleKeyBitcoinWallet::new_wallet(
-                public_key.clone(), node_config.network.clone(), true)
+            let w = SingleKeyBitcoinWallet::new_wallet_typed(
+                public_key.clone(), node_config.network.clone(), true, &node_config.btc_backend, btc_address_type)
                 .expect("worx");
             let fb = w.get_wallet_balance().expect("b").confirmed as f64 / 100_000_000.0;
             Some(fb)
@@ -1027,6 +1916,7 @@ pub fn get_address_info(
         };
 
         let client = node_config.api_client();
+        let pending = client.pending_transactions(Some(&address)).await.log_error().ok();
         let response = client
             .address_info(address).await;
         let fun: Box<dyn FnMut(&mut LocalState) + Send> = match response {
@@ -1038,14 +1928,21 @@ pub fn get_address_info(
                     ls.wallet_state.balance = o.to_string();
                     ls.wallet_state.balance_f64 = Some(o.clone());
                     ls.wallet_state.address_info = Some(ai.clone());
+                    if let Some(p) = pending.as_ref() {
+                        ls.wallet_state.pending_transactions = p.transactions.clone();
+                    }
                     ls.wallet_state.balance_btc_f64 = btc_bal.clone();
                     ls.wallet_state.balance_btc = btc_bal.clone().map(|b| b.to_string());
+                    if btc_bal.is_some() {
+                        ls.home_state.btc_wallet_synced_time = Some(ls.current_time);
+                    }
                 })
             }
             Err(e) => {
                 error!("balance error: {}", e.json_or());
                 Box::new(move |ls: &mut LocalState| {
                     ls.wallet_state.balance = "error".to_string();
+                    ls.home_state.record_error(ls.current_time, format!("Balance query failed: {}", e.json_or()));
                 })
             }
         };
@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use eframe::egui::{Color32, Ui};
+use log::error;
+use redgold_schema::EasyJson;
+use redgold_schema::local_stored_state::WatchedAddress;
+use redgold_schema::transaction::rounded_balance_i64;
+use redgold_keys::address_support::AddressSupport;
+use crate::core::internal_message::Channel;
+use crate::gui::app_loop::LocalState;
+use crate::gui::tables::text_table;
+use crate::gui::wallet_tab::StateUpdate;
+use crate::node_config::NodeConfig;
+
+const WATCH_QUERY_INTERVAL_MS: i64 = 1000 * 30;
+const MAX_NOTIFICATIONS: usize = 20;
+
+#[derive(Clone)]
+pub struct WatchNotification {
+    pub time: i64,
+    pub name: String,
+    pub address: String,
+    pub previous_balance: i64,
+    pub balance: i64,
+}
+
+#[derive(Clone, Default)]
+pub struct WatchListState {
+    // Last observed balance keyed by rendered address
+    pub balances: HashMap<String, i64>,
+    pub notifications: Vec<WatchNotification>,
+    pub last_query_time: Option<i64>,
+    pub name_entry: String,
+    pub address_entry: String,
+    pub alert_outgoing_only: bool,
+    pub add_error: Option<String>,
+}
+
+impl WatchListState {
+    fn apply_balances(&mut self, time: i64, watched: Vec<WatchedAddress>, results: Vec<(String, i64)>) {
+        for (address, balance) in results {
+            let previous = self.balances.insert(address.clone(), balance);
+            let w = watched.iter().find(|w| w.address.render_string().ok() == Some(address.clone()));
+            if let (Some(previous), Some(w)) = (previous, w) {
+                if w.should_alert(previous, balance) {
+                    self.notifications.push(WatchNotification {
+                        time,
+                        name: w.name.clone(),
+                        address: address.clone(),
+                        previous_balance: previous,
+                        balance,
+                    });
+                }
+            }
+        }
+        if self.notifications.len() > MAX_NOTIFICATIONS {
+            let excess = self.notifications.len() - MAX_NOTIFICATIONS;
+            self.notifications.drain(0..excess);
+        }
+    }
+}
+
+pub async fn query_watched(node_config: NodeConfig, watched: Vec<WatchedAddress>, updates: Channel<StateUpdate>) {
+    let mut client = node_config.api_client();
+    client.timeout = Duration::from_secs(5);
+    let mut results = vec![];
+    for w in watched.iter() {
+        match client.address_info(w.address.clone()).await {
+            Ok(ai) => {
+                if let Ok(a) = w.address.render_string() {
+                    results.push((a, ai.balance));
+                }
+            }
+            Err(e) => error!("Watched address query failed for {}: {}", w.name, e.json_or())
+        }
+    }
+    LocalState::send_update(&updates, move |ls| {
+        ls.home_state.watch_list.apply_balances(ls.current_time, watched.clone(), results.clone());
+    });
+}
+
+pub fn watch_list(ui: &mut Ui, local_state: &mut LocalState) {
+    let watched = local_state.local_stored_state.watched_addresses();
+    let now = local_state.current_time;
+    if !watched.is_empty() && local_state.home_state.watch_list.last_query_time
+        .map(|q| (now - q) > WATCH_QUERY_INTERVAL_MS)
+        .unwrap_or(true) {
+        local_state.home_state.watch_list.last_query_time = Some(now);
+        let nc = local_state.node_config.clone();
+        let updates = local_state.updates.clone();
+        tokio::spawn(query_watched(nc, watched.clone(), updates));
+    }
+
+    ui.heading("Watched Addresses");
+    let state = &mut local_state.home_state.watch_list;
+
+    let mut dismiss = false;
+    if !state.notifications.is_empty() {
+        for n in state.notifications.iter().rev() {
+            ui.colored_label(Color32::YELLOW, format!(
+                "{}s ago {} ({}) balance changed {} -> {}",
+                (now - n.time) / 1000,
+                n.name,
+                n.address,
+                rounded_balance_i64(n.previous_balance),
+                rounded_balance_i64(n.balance)
+            ));
+        }
+        dismiss = ui.button("Dismiss Notifications").clicked();
+    }
+    if dismiss {
+        state.notifications.clear();
+    }
+
+    let mut table_rows: Vec<Vec<String>> = vec![];
+    table_rows.push(vec!["Name".to_string(), "Address".to_string(), "Balance".to_string(), "Alerts".to_string()]);
+    for w in watched.iter() {
+        let address = w.address.render_string().unwrap_or("".to_string());
+        table_rows.push(vec![
+            w.name.clone(),
+            address.clone(),
+            state.balances.get(&address).map(|b| rounded_balance_i64(*b).to_string()).unwrap_or("".to_string()),
+            if w.alert_all { "all" } else { "outgoing" }.to_string(),
+        ]);
+    }
+    if !watched.is_empty() {
+        text_table(ui, table_rows);
+    }
+
+    let mut added = None;
+    let mut removed = None;
+    ui.horizontal(|ui| {
+        ui.label("Name");
+        ui.text_edit_singleline(&mut state.name_entry);
+        ui.label("Address");
+        ui.text_edit_singleline(&mut state.address_entry);
+        ui.checkbox(&mut state.alert_outgoing_only, "Outgoing Only");
+        if ui.button("Watch").clicked() {
+            match state.address_entry.parse_address() {
+                Ok(address) => {
+                    added = Some(WatchedAddress {
+                        name: state.name_entry.clone(),
+                        address,
+                        alert_all: !state.alert_outgoing_only,
+                        alert_outgoing: true,
+                    });
+                    state.add_error = None;
+                    state.name_entry.clear();
+                    state.address_entry.clear();
+                }
+                Err(e) => state.add_error = Some(e.json_or()),
+            }
+        }
+    });
+    if let Some(e) = &state.add_error {
+        ui.colored_label(Color32::LIGHT_RED, format!("Invalid address: {}", e));
+    }
+    for w in watched.iter() {
+        ui.horizontal(|ui| {
+            ui.label(w.name.clone());
+            if ui.button("Unwatch").clicked() {
+                removed = Some(w.name.clone());
+            }
+        });
+    }
+    if let Some(w) = added {
+        local_state.home_state.watch_list.last_query_time = None;
+        local_state.upsert_watched_address(w);
+    }
+    if let Some(name) = removed {
+        local_state.remove_watched_address(&name);
+    }
+}
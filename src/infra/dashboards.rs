@@ -0,0 +1,138 @@
+use serde_json::{json, Value};
+use redgold_schema::structs::NetworkEnvironment;
+
+const PANEL_WIDTH: i64 = 12;
+const PANEL_HEIGHT: i64 = 8;
+
+struct AmmPanel {
+    title: &'static str,
+    unit: &'static str,
+    // Prometheus queries with the legend for each, {env} is replaced by the environment selector
+    queries: Vec<(&'static str, &'static str)>,
+}
+
+fn amm_panels() -> Vec<AmmPanel> {
+    vec![
+        AmmPanel {
+            title: "Pool Balances",
+            unit: "short",
+            queries: vec![
+                ("redgold_amm_balance_rdg{env} / 1e8", "RDG"),
+                ("redgold_amm_balance_btc{env} / 1e8", "BTC"),
+            ],
+        },
+        AmmPanel {
+            title: "Center Price (RDG/BTC)",
+            unit: "short",
+            queries: vec![("redgold_amm_center_price{env}", "Center price")],
+        },
+        AmmPanel {
+            title: "Order Volume",
+            unit: "short",
+            queries: vec![
+                ("redgold_amm_volume_rdg{env} / 1e8", "RDG"),
+                ("redgold_amm_volume_btc{env} / 1e8", "BTC"),
+            ],
+        },
+        AmmPanel {
+            title: "Fulfillments",
+            unit: "short",
+            queries: vec![
+                ("redgold_amm_fulfillments{env}", "Total"),
+                ("redgold_amm_unfulfilled_orders{env}", "Unfulfilled"),
+            ],
+        },
+        AmmPanel {
+            title: "Fulfillment Latency",
+            unit: "s",
+            queries: vec![
+                ("redgold_amm_fulfillment_latency_seconds{env_quantile=\"0.5\"}", "p50"),
+                ("redgold_amm_fulfillment_latency_seconds{env_quantile=\"0.99\"}", "p99"),
+            ],
+        },
+        AmmPanel {
+            title: "Safety",
+            unit: "short",
+            queries: vec![
+                ("redgold_amm_admission_paused{env}", "Deposits paused"),
+                ("redgold_amm_btc_fee_rate{env}", "BTC fee rate (sat/vB)"),
+                ("redgold_amm_drift_rdg{env}", "RDG drift"),
+                ("redgold_amm_drift_btc{env}", "BTC drift"),
+            ],
+        },
+    ]
+}
+
+fn expr(query: &str, network: &NetworkEnvironment) -> String {
+    let selector = format!("environment=\"{}\"", network.to_std_string());
+    query
+        .replace("{env_", &format!("{{{}, ", selector))
+        .replace("{env}", &format!("{{{}}}", selector))
+}
+
+pub fn amm_dashboard_file_name(network: &NetworkEnvironment) -> String {
+    format!("redgold-amm-{}.json", network.to_std_string())
+}
+
+/// Grafana dashboard of the AMM watcher metrics, scoped to one network environment through
+/// the environment label added by the prometheus scrape config.
+pub fn amm_dashboard(network: &NetworkEnvironment) -> Value {
+    let datasource = json!({"type": "prometheus", "uid": "${datasource}"});
+    let panels = amm_panels().into_iter().enumerate().map(|(i, p)| {
+        let targets = p.queries.iter().enumerate().map(|(j, (q, legend))| json!({
+            "datasource": datasource.clone(),
+            "expr": expr(q, network),
+            "legendFormat": legend,
+            "range": true,
+            "refId": ((b'A' + j as u8) as char).to_string(),
+        })).collect::<Vec<Value>>();
+        json!({
+            "id": i + 1,
+            "type": "timeseries",
+            "title": p.title,
+            "datasource": datasource.clone(),
+            "gridPos": {
+                "h": PANEL_HEIGHT,
+                "w": PANEL_WIDTH,
+                "x": (i as i64 % 2) * PANEL_WIDTH,
+                "y": (i as i64 / 2) * PANEL_HEIGHT
+            },
+            "fieldConfig": {"defaults": {"unit": p.unit}, "overrides": []},
+            "options": {
+                "legend": {"displayMode": "list", "placement": "bottom", "showLegend": true},
+                "tooltip": {"mode": "multi", "sort": "none"}
+            },
+            "targets": targets,
+        })
+    }).collect::<Vec<Value>>();
+    let net = network.to_std_string();
+    json!({
+        "uid": format!("redgold-amm-{}", net),
+        "title": format!("Redgold AMM ({})", net),
+        "tags": ["redgold", "amm", net],
+        "editable": true,
+        "refresh": "1m",
+        "schemaVersion": 37,
+        "time": {"from": "now-24h", "to": "now"},
+        "templating": {"list": [{
+            "name": "datasource",
+            "label": "Datasource",
+            "type": "datasource",
+            "query": "prometheus",
+            "hide": 0
+        }]},
+        "panels": panels,
+    })
+}
+
+#[test]
+fn amm_dashboard_scoped_to_network() {
+    let d = amm_dashboard(&NetworkEnvironment::Test);
+    assert_eq!(d["uid"], "redgold-amm-test");
+    let exprs = d["panels"].as_array().expect("panels").iter()
+        .flat_map(|p| p["targets"].as_array().expect("targets").clone())
+        .map(|t| t["expr"].as_str().expect("expr").to_string())
+        .collect::<Vec<String>>();
+    assert!(exprs.iter().all(|e| e.contains("environment=\"test\"") && !e.contains("{env")));
+    assert!(exprs.contains(&"redgold_amm_fulfillment_latency_seconds{environment=\"test\", quantile=\"0.99\"}".to_string()));
+}
@@ -12,7 +12,7 @@ use itertools::Itertools;
 
 use redgold_keys::transaction_support::TransactionSupport;
 use redgold_keys::util::mnemonic_support::WordsPass;
-use redgold_schema::{EasyJson, EasyJsonDeser, ErrorInfoContext, RgResult, structs, WithMetadataHashable};
+use redgold_schema::{error_info, EasyJson, EasyJsonDeser, ErrorInfoContext, RgResult, structs, WithMetadataHashable};
 use redgold_schema::constants::default_node_internal_derivation_path;
 use redgold_schema::servers::Server;
 use redgold_schema::structs::{ErrorInfo, NetworkEnvironment, PeerId, PeerMetadata, Transaction, TrustRatingLabel};
@@ -28,13 +28,25 @@ use crate::util::cli::arg_parse_config::ArgTranslate;
 use crate::util::cli::args::Deploy;
 use crate::util::cli::data_folder::DataFolder;
 use crate::util::cmd::{run_bash, run_bash_async, run_powershell, run_powershell_async};
+use crate::util::secrets::{deploy_secret_env, parse_secret_args};
+use crate::infra::dashboards::{amm_dashboard, amm_dashboard_file_name};
 
 
 #[async_trait]
-pub trait SSHLike {
+pub trait SSHLike: Send + Sync {
     async fn execute(&self, command: impl Into<String> + Send, output_handler: Option<Sender<String>>) -> RgResult<String>;
     async fn scp(&self, from: impl Into<String> + Send, to: impl Into<String> + Send, to_dest: bool, output_handler: Option<Sender<String>>) -> RgResult<String>;
 
+    /// Writes the contents to a remote file, by default through a local temporary file and scp.
+    async fn copy_contents(&self, contents: String, remote_path: String) -> RgResult<()> {
+        let path = "tmpfile";
+        fs::remove_file("tmpfile").ok();
+        let mut file = File::create(path).expect("create failed");
+        file.write_all(contents.as_bytes()).expect("write temp file");
+        self.scp("./tmpfile", remote_path, true, None).await?;
+        fs::remove_file("tmpfile").unwrap();
+        Ok(())
+    }
 }
 
 pub struct SSHProcessInvoke {
@@ -128,6 +140,7 @@ async fn debug_ssh_invoke() {
         ipv4: None,
         node_name: None,
         external_host: None,
+        image_tag: None,
     };
 
     let mut dm = DeployMachine::new(&s, None);
@@ -189,27 +202,72 @@ impl<S: SSHLike> DeployMachine<S> {
     }
     pub async fn copy(&mut self, contents: impl Into<String> + Send, remote_path: String) -> RgResult<()> {
         // println!("Copying to: {}", remote_path);
-        let contents = contents.into();
-        let path = "tmpfile";
-        fs::remove_file("tmpfile").ok();
-        let mut file = File::create(path).expect("create failed");
-        file.write_all(contents.as_bytes()).expect("write temp file");
-        self.ssh.scp("./tmpfile", &*remote_path, true, None).await?;
-        fs::remove_file("tmpfile").unwrap();
-        Ok(())
+        self.ssh.copy_contents(contents.into(), remote_path).await
     }
 
 
 }
 
+pub const DOCKER_IMAGE: &str = "redgoldio/redgold";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ServerArch {
+    Amd64,
+    Arm64,
+}
+
+impl ServerArch {
+    pub fn from_uname(uname: &str) -> RgResult<Self> {
+        match uname.trim() {
+            "x86_64" | "amd64" => Ok(Self::Amd64),
+            "aarch64" | "arm64" => Ok(Self::Arm64),
+            other => Err(error_info(format!("Unsupported server architecture {}", other))),
+        }
+    }
+
+    pub fn docker_arch(&self) -> &'static str {
+        match self {
+            Self::Amd64 => "amd64",
+            Self::Arm64 => "arm64",
+        }
+    }
+}
+
+/// Whether verbose `docker manifest inspect` output, for a single image or a manifest list,
+/// includes an image for the architecture.
+pub fn manifest_has_arch(manifest: &str, arch: ServerArch) -> bool {
+    let compact = manifest.chars().filter(|c| !c.is_whitespace()).collect::<String>();
+    compact.contains(&format!("\"architecture\":\"{}\"", arch.docker_arch()))
+}
+
+/// Picks the image tag runnable on the server, failing before anything is stopped when none
+/// exists. Multi-arch tags are used as is, otherwise arm64 builds are published with an -arm64
+/// suffix on the tag.
+pub async fn resolve_image_tag<S: SSHLike>(
+    ssh: &mut DeployMachine<S>, tag: &str, p: &Option<Sender<String>>
+) -> RgResult<String> {
+    let arch = ServerArch::from_uname(ssh.exes("uname -m", p).await?.lines().next().unwrap_or(""))?;
+    let mut candidates = vec![tag.to_string()];
+    if arch == ServerArch::Arm64 && !tag.ends_with("-arm64") {
+        candidates.push(format!("{}-arm64", tag));
+    }
+    for c in candidates {
+        let manifest = ssh.exes(format!("docker manifest inspect -v {}:{}", DOCKER_IMAGE, c), p).await?;
+        if manifest_has_arch(&manifest, arch) {
+            return Ok(c);
+        }
+    }
+    Err(error_info(format!("No {} image found for {}:{}", arch.docker_arch(), DOCKER_IMAGE, tag)))
+}
+
 /**
 Updates to this cannot be explicitly watched through docker watchtower for automatic updates
 They must be manually deployed.
 
  This whole thing should really have a streaming output for the lines and stuff.
  */
-pub async fn setup_server_redgold(
-     mut ssh: DeployMachine<SSHProcessInvoke>,
+pub async fn setup_server_redgold<S: SSHLike>(
+     mut ssh: DeployMachine<S>,
      network: NetworkEnvironment,
      is_genesis: bool,
      additional_env: Option<HashMap<String, String>>,
@@ -219,8 +277,9 @@ pub async fn setup_server_redgold(
      start_node: bool,
      alias: Option<String>,
      ser_pid_tx: Option<String>,
+     genesis_manifest: Option<String>,
      p: &Option<Sender<String>>
- ) -> Result<(), ErrorInfo> {
+ ) -> RgResult<String> {
 
     ssh.verify().await?;
 
@@ -237,6 +296,11 @@ pub async fn setup_server_redgold(
         ssh.exes("curl -fsSL https://get.docker.com -o get-docker.sh; sh ./get-docker.sh", p).await?;
         ssh.exes("sudo apt install -y docker-compose", p).await?;
     }
+    configure_docker_log_rotation(&mut ssh, p).await?;
+    let mut env = additional_env.unwrap_or(Default::default());
+    let requested_tag = env.get("REDGOLD_IMAGE_TAG").cloned().unwrap_or(network.to_std_string());
+    let image_tag = resolve_image_tag(&mut ssh, &requested_tag, p).await?;
+    env.insert("REDGOLD_IMAGE_TAG".to_string(), image_tag.clone());
     let r = Resources::default();
 
     let path = format!("/root/.rg/{}", network.to_std_string());
@@ -266,6 +330,10 @@ pub async fn setup_server_redgold(
          let remote = format!("{}/peer_tx", path);
          ssh.copy_p(tx, remote, p).await?;
      }
+     if let Some(manifest) = genesis_manifest {
+         let remote = format!("{}/genesis.json", path);
+         ssh.copy_p(manifest, remote, p).await?;
+     }
 
 
     // TODO: Investigate issue with tmpfile, not working
@@ -277,7 +345,6 @@ pub async fn setup_server_redgold(
     ssh.copy_p(r.redgold_docker_compose, format!("{}/redgold-only.yml", path), p).await?;
 
     let port = network.default_port_offset();
-    let mut env = additional_env.unwrap_or(Default::default());
     env.insert("REDGOLD_NETWORK".to_string(), network.to_std_string());
     env.insert("REDGOLD_GENESIS".to_string(), is_genesis.to_string());
     env.insert("REDGOLD_METRICS_PORT".to_string(), format!("{}", port - 1));
@@ -288,12 +355,7 @@ pub async fn setup_server_redgold(
      if let Some(a) = alias {
          env.insert("REDGOLD_ALIAS".to_string(), a);
      }
-    let copy_env = vec!["AWS_ACCESS_KEY_ID", "AWS_SECRET_ACCESS_KEY", "ETHERSCAN_API_KEY", "RECAPTCHA_SECRET"];
-    for e in copy_env {
-        for i in std::env::var(e).ok() {
-            env.insert(e.to_string(), i);
-        }
-    }
+    deploy_secret_env(&mut env, &HashMap::new())?;
 
      // TODO: Lol not this
      let port_range: Vec<i64> = vec![-1, 0, 1, 4, 5, 6];
@@ -335,15 +397,176 @@ pub async fn setup_server_redgold(
         }
     }
 
+    Ok(image_tag)
+}
+
+#[tokio::test]
+async fn setup_server_redgold_command_sequence() {
+    use crate::infra::mock_ssh::MockSSH;
+    let server = Server {
+        name: "n0".to_string(),
+        host: "mock".to_string(),
+        index: 0,
+        peer_id_index: 0,
+        network_environment: "dev".to_string(),
+        username: None,
+        ipv4: None,
+        node_name: None,
+        external_host: None,
+        image_tag: None,
+    };
+    let path = "/root/.rg/dev";
+    let down = format!("cd {}; docker-compose -f redgold-only.yml down", path);
+    let pull = format!("cd {}; docker-compose -f redgold-only.yml pull", path);
+    let up = format!("cd {}; docker-compose -f redgold-only.yml up -d", path);
+    let purge_cmd = format!("rm -rf {}/data_store.sqlite", path);
+    let var_env = format!("{}/var.env", path);
+
+    for (is_genesis, purge) in vec![(true, false), (true, true), (false, true), (false, false)] {
+        // Genesis servers are treated as fresh machines without docker-compose installed
+        let mut mock = MockSSH::new()
+            .with_response("uname -m", "x86_64")
+            .with_response("docker manifest inspect", "{\"platform\": {\"architecture\": \"amd64\"}}");
+        if !is_genesis {
+            mock = mock.with_response("docker-compose", "Define and run multi-container applications");
+        }
+        let tag = setup_server_redgold(
+            mock.machine(&server), NetworkEnvironment::Dev, is_genesis, None, purge,
+            None, None, true, None, None, None, &None
+        ).await.expect("setup");
+        assert_eq!(tag, "dev");
+
+        let commands = mock.commands();
+        assert_eq!(commands[..6], [
+            "df", "docker system prune -a -f", "apt install -y ufw", "sudo ufw allow ssh",
+            "sudo ufw allow in on tailscale0", "echo 'y' | sudo ufw enable"
+        ]);
+        assert_eq!(commands.iter().any(|c| c.contains("get-docker.sh")), is_genesis);
+        let position = |cmd: &String| commands.iter().position(|c| c == cmd).expect(cmd);
+        assert!(position(&down) < position(&pull));
+        assert!(position(&pull) < position(&up));
+        assert_eq!(commands.contains(&purge_cmd), purge);
+        if purge {
+            assert!(position(&down) < position(&purge_cmd));
+            assert!(position(&purge_cmd) < position(&pull));
+        }
+
+        let copies = mock.copies();
+        assert!(copies.iter().any(|(p, _)| p == &format!("{}/redgold-only.yml", path)));
+        let env_files = copies.iter().filter(|(p, _)| p == &var_env).map(|(_, c)| c.clone()).collect_vec();
+        if is_genesis {
+            // Rewritten without the genesis flag once the node has started
+            assert_eq!(env_files.len(), 2);
+            assert!(env_files[0].contains("REDGOLD_GENESIS=true"));
+            assert!(!env_files[1].contains("REDGOLD_GENESIS"));
+        } else {
+            assert_eq!(env_files.len(), 1);
+            assert!(env_files[0].contains("REDGOLD_GENESIS=false"));
+            assert_eq!(commands.last(), Some(&up));
+        }
+    }
+}
+
+#[tokio::test]
+async fn resolve_image_tag_by_architecture() {
+    use crate::infra::mock_ssh::MockSSH;
+    assert_eq!(ServerArch::from_uname("aarch64\n").expect("arch"), ServerArch::Arm64);
+    assert!(ServerArch::from_uname("riscv64").is_err());
+    let amd64 = r#"[{"Descriptor": {"platform": {"architecture": "amd64", "os": "linux"}}}]"#;
+    let arm64 = r#"{"Descriptor": {"platform": {"architecture": "arm64", "os": "linux"}}}"#;
+    assert!(manifest_has_arch(amd64, ServerArch::Amd64));
+    assert!(!manifest_has_arch(amd64, ServerArch::Arm64));
+
+    let server = Server::new("mock".to_string());
+    let mock = MockSSH::new()
+        .with_response("uname -m", "aarch64")
+        .with_response("docker manifest inspect -v redgoldio/redgold:dev", amd64)
+        .with_response("docker manifest inspect -v redgoldio/redgold:dev-arm64", arm64);
+    let tag = resolve_image_tag(&mut mock.machine(&server), "dev", &None).await.expect("tag");
+    assert_eq!(tag, "dev-arm64");
+    // No arm64 build published for this tag
+    let tag = resolve_image_tag(&mut mock.machine(&server), "main", &None).await;
+    assert!(tag.is_err());
+    let amd = MockSSH::new().with_response("uname -m", "x86_64").with_response("docker manifest inspect", amd64);
+    assert_eq!(resolve_image_tag(&mut amd.machine(&server), "main", &None).await.expect("tag"), "main");
+}
+
+pub const DOCKER_DAEMON_CONFIG: &str = include_str!("../resources/infra/docker-daemon.json");
+
+/// Caps the size of every container's json-file logs so they can't fill the disk. Replaces any
+/// existing daemon.json, docker is only restarted when the config changes.
+pub async fn configure_docker_log_rotation<S: SSHLike>(
+    ssh: &mut DeployMachine<S>, p: &Option<Sender<String>>
+) -> RgResult<()> {
+    let current = ssh.exes("cat /etc/docker/daemon.json", p).await?;
+    if current.trim() == DOCKER_DAEMON_CONFIG.trim() {
+        return Ok(());
+    }
+    ssh.exes("mkdir -p /etc/docker", p).await?;
+    ssh.copy_p(DOCKER_DAEMON_CONFIG, "/etc/docker/daemon.json", p).await?;
+    ssh.exes("sudo systemctl restart docker", p).await?;
     Ok(())
 }
 
+/// Log collection stack of the ops services, metrics and grafana are deployed with either.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum OpsProfile {
+    /// Filebeat, elasticsearch and kibana
+    #[default]
+    Elastic,
+    /// Promtail and Loki, much lighter on memory and disk
+    Loki,
+}
+
+impl OpsProfile {
+    pub fn parse(s: &str) -> RgResult<Self> {
+        match s.to_lowercase().as_str() {
+            "elastic" => Ok(Self::Elastic),
+            "loki" => Ok(Self::Loki),
+            other => Err(error_info(format!("Unknown ops profile {}, expected elastic or loki", other))),
+        }
+    }
+
+    /// Profile of an existing ops deployment, so redeploys keep it.
+    pub async fn detect<S: SSHLike>(
+        ssh: &mut DeployMachine<S>, remote_path: &str, p: &Option<Sender<String>>
+    ) -> RgResult<Self> {
+        let files = ssh.exes(format!("ls {}", remote_path), p).await?;
+        Ok(if files.contains("loki-config.yml") { Self::Loki } else { Self::Elastic })
+    }
+
+    fn data_dirs(&self) -> Vec<&'static str> {
+        match self {
+            Self::Elastic => vec!["grafana", "prometheus", "esdata"],
+            Self::Loki => vec!["grafana", "prometheus", "loki"],
+        }
+    }
+}
+
+#[tokio::test]
+async fn docker_log_rotation_only_restarts_on_change() {
+    use crate::infra::mock_ssh::MockSSH;
+    let server = Server::new("mock".to_string());
+    let fresh = MockSSH::new();
+    configure_docker_log_rotation(&mut fresh.machine(&server), &None).await.expect("rotation");
+    assert_eq!(fresh.copies(), vec![("/etc/docker/daemon.json".to_string(), DOCKER_DAEMON_CONFIG.to_string())]);
+    assert!(fresh.commands().contains(&"sudo systemctl restart docker".to_string()));
+
+    let configured = MockSSH::new().with_response("cat /etc/docker/daemon.json", DOCKER_DAEMON_CONFIG);
+    configure_docker_log_rotation(&mut configured.machine(&server), &None).await.expect("rotation");
+    assert!(configured.copies().is_empty());
+    assert_eq!(configured.commands(), vec!["cat /etc/docker/daemon.json"]);
+    assert_eq!(OpsProfile::parse("Loki").expect("profile"), OpsProfile::Loki);
+    assert!(OpsProfile::parse("splunk").is_err());
+}
+
 pub async fn deploy_ops_services(
     mut ssh: DeployMachine<SSHProcessInvoke>,
     _additional_env: Option<HashMap<String, String>>,
     remote_path_prefix: Option<String>,
     grafana_pass: Option<String>,
     purge_data: bool,
+    profile: OpsProfile,
     p: &Option<Sender<String>>
 ) -> Result<(), ErrorInfo> {
     let remote_path = remote_path_prefix.unwrap_or("/root/.rg/all".to_string());
@@ -355,14 +578,39 @@ pub async fn deploy_ops_services(
     // });
 
     ssh.exes("docker ps", p).await?;
-    ssh.copy(
-        include_str!("../resources/infra/ops_services/services-all.yml"),
-        format!("{}/services-all.yml", remote_path)
-    );
-    ssh.copy(
-        include_str!("../resources/infra/ops_services/filebeat.docker.yml"),
-        format!("{}/filebeat.docker.yml", remote_path)
-    );
+    configure_docker_log_rotation(&mut ssh, p).await?;
+    // Either profile is written as services-all.yml, orphans of the other are removed on down
+    match profile {
+        OpsProfile::Elastic => {
+            ssh.copy(
+                include_str!("../resources/infra/ops_services/services-all.yml"),
+                format!("{}/services-all.yml", remote_path)
+            ).await?;
+            ssh.copy(
+                include_str!("../resources/infra/ops_services/filebeat.docker.yml"),
+                format!("{}/filebeat.docker.yml", remote_path)
+            ).await?;
+            ssh.exes(format!("rm -f {}/loki-config.yml", remote_path), p).await?;
+        }
+        OpsProfile::Loki => {
+            ssh.copy(
+                include_str!("../resources/infra/ops_services/services-loki.yml"),
+                format!("{}/services-all.yml", remote_path)
+            ).await?;
+            ssh.copy(
+                include_str!("../resources/infra/ops_services/loki-config.yml"),
+                format!("{}/loki-config.yml", remote_path)
+            ).await?;
+            ssh.copy(
+                include_str!("../resources/infra/ops_services/promtail.yml"),
+                format!("{}/promtail.yml", remote_path)
+            ).await?;
+            ssh.copy(
+                include_str!("../resources/infra/ops_services/loki-datasource.yaml"),
+                format!("{}/loki-datasource.yaml", remote_path)
+            ).await?;
+        }
+    }
 
     let prometheus_yml = include_str!("../resources/infra/ops_services/prometheus.yml").to_string();
 //     match std::env::var("GRAFANA_CLOUD_USER") {
@@ -382,16 +630,16 @@ pub async fn deploy_ops_services(
     ssh.copy(
         prometheus_yml,
         format!("{}/prometheus.yml", remote_path)
-    );
+    ).await?;
     ssh.copy(
         include_str!("../resources/infra/ops_services/prometheus-datasource.yaml"),
         format!("{}/prometheus-datasource.yaml", remote_path)
-    );
+    ).await?;
 
     ssh.copy(
         grafana_pass.unwrap_or("debug".to_string()),
         format!("{}/grafana_password", remote_path)
-    );
+    ).await?;
 
     ssh.exes(format!("rm -r {}/dashboards", remote_path), p).await?;
     ssh.exes(format!("mkdir {}/dashboards", remote_path), p).await?;
@@ -400,25 +648,32 @@ pub async fn deploy_ops_services(
     ssh.copy(
         x,
         format!("{}/dashboards/node-exporter.json", remote_path)
-    );
+    ).await?;
 
     let x = include_str!("../resources/infra/ops_services/dashboards/redgold_rev0.json");
     ssh.copy(
         x,
         format!("{}/dashboards/redgold.json", remote_path)
-    );
+    ).await?;
+
+    for net in NetworkEnvironment::status_networks() {
+        ssh.copy(
+            amm_dashboard(&net).json_or(),
+            format!("{}/dashboards/{}", remote_path, amm_dashboard_file_name(&net))
+        ).await?;
+    }
 
     // println!("Copying node exporter dashboard: {}", x);
 
     ssh.copy(
         include_str!("../resources/infra/ops_services/dashboards/dashboard_config.yaml"),
         format!("{}/dashboards/dashboard_config.yaml", remote_path)
-    );
+    ).await?;
 
     ssh.copy(
         include_str!("../resources/infra/ops_services/grafana/grafana.ini"),
         format!("{}/grafana.ini", remote_path)
-    );
+    ).await?;
 
     // Environment
     let mut env = _additional_env.unwrap_or(Default::default());
@@ -432,31 +687,34 @@ pub async fn deploy_ops_services(
     let env_contents = env.iter().map(|(k, v)| {
         format!("{}={}", k, format!("{}", v))
     }).join("\n");
-    ssh.copy(env_contents.clone(), format!("{}/ops_var.env", remote_path));
+    ssh.copy(env_contents.clone(), format!("{}/ops_var.env", remote_path)).await?;
 
-    ssh.exes(format!("cd {}; docker-compose -f services-all.yml down", remote_path), p).await?;
+    ssh.exes(format!("cd {}; docker-compose -f services-all.yml down --remove-orphans", remote_path), p).await?;
 
-    for s in vec!["grafana", "prometheus", "esdata"] {
+    for s in profile.data_dirs() {
         if purge_data {
             ssh.exes(format!("rm -r {}/data/{}", remote_path, s), p).await?;
         }
         ssh.exes(format!("mkdir -p {}/data/{}", remote_path, s), p).await?;
     };
 
-    ssh.exes(format!("chmod -R 777 {}/data/esdata", remote_path), p).await?;
+    if profile == OpsProfile::Elastic {
+        ssh.exes(format!("chmod -R 777 {}/data/esdata", remote_path), p).await?;
+    }
 
     ssh.exes(format!("cd {}; docker-compose -f services-all.yml up -d", remote_path), p).await?;
 
-    tokio::time::sleep(Duration::from_secs(15)).await;
-
-    let kibana_setup_path = format!("{}/kibana_setup.sh", remote_path);
-    ssh.copy(
-        include_str!("../resources/infra/ops_services/kibana_setup.sh"),
-        kibana_setup_path
-    );
+    if profile == OpsProfile::Elastic {
+        tokio::time::sleep(Duration::from_secs(15)).await;
 
-    ssh.exes(format!("chmod +x {}; {}", remote_path, remote_path), p).await?;
+        let kibana_setup_path = format!("{}/kibana_setup.sh", remote_path);
+        ssh.copy(
+            include_str!("../resources/infra/ops_services/kibana_setup.sh"),
+            kibana_setup_path
+        ).await?;
 
+        ssh.exes(format!("chmod +x {}; {}", remote_path, remote_path), p).await?;
+    }
 
     Ok(())
 }
@@ -473,13 +731,18 @@ pub async fn derive_mnemonic_and_peer_id(
     servers: Vec<Server>,
     trust: Vec<TrustRatingLabel>,
     peer_id_tx: &mut HashMap<String, structs::Transaction>,
-    net: &NetworkEnvironment
+    net: &NetworkEnvironment,
+    bip85: bool
 )
     -> RgResult<(String, String)> {
 
     // TODO: Make peer id transaction here using details.
     let w = WordsPass::new(mnemonic, passphrase);
-    let new = w.hash_derive_words(server_id_index.to_string())?;
+    let new = if bip85 {
+        w.bip85_derive_words(24, server_id_index as u32)?
+    } else {
+        w.hash_derive_words(server_id_index.to_string())?
+    };
     let server_mnemonic = new.words.clone();
     let account = (99 - peer_id_index) as u32;
     let mut pid_hex = "".to_string();
@@ -536,14 +799,22 @@ pub async fn derive_mnemonic_and_peer_id(
 }
 
 
+pub struct OfflineServerKeys {
+    pub server: Server,
+    pub words: String,
+    pub peer_id_hex: String,
+}
+
 /// Allow offline (airgapped) generation of peer TX / node TX from servers manifest
 pub async fn offline_generate_keys_servers(
     node_config: NodeConfig,
     servers: Vec<Server>,
     save_path: PathBuf,
     salt_mnemonic: String,
-    passphrase: Option<String>
-) -> RgResult<()> {
+    passphrase: Option<String>,
+    bip85: bool
+) -> RgResult<Vec<OfflineServerKeys>> {
+    let mut generated = vec![];
     let mut pid_tx: HashMap<String, structs::Transaction> = HashMap::default();
     for ss in &servers {
         let (words, peer_id_hex) = derive_mnemonic_and_peer_id(
@@ -557,7 +828,8 @@ pub async fn offline_generate_keys_servers(
             servers.clone(),
             vec![],
             &mut pid_tx,
-            &node_config.network
+            &node_config.network,
+            bip85
         ).await?;
         let peer_tx = pid_tx.get(&peer_id_hex).expect("").clone();
         let peer_tx_ser = peer_tx.json_or();
@@ -567,9 +839,14 @@ pub async fn offline_generate_keys_servers(
         let peer_tx_path = server_index_path.join("peer_tx");
         let words_path = server_index_path.join("mnemonic");
         std::fs::write(peer_tx_path, peer_tx_ser).expect("");
-        std::fs::write(words_path, words).expect("");
+        std::fs::write(words_path, words.clone()).expect("");
+        generated.push(OfflineServerKeys {
+            server: ss.clone(),
+            words,
+            peer_id_hex,
+        });
     }
-    Ok(())
+    Ok(generated)
 }
 
 
@@ -627,6 +904,8 @@ pub async fn default_deploy(
     // }
     let mut hm = HashMap::new();
     hm.insert("RUST_BACKTRACE".to_string(), "1".to_string());
+    deploy_secret_env(&mut hm, &parse_secret_args(&deploy.secret)?)?;
+    let ops_profile = deploy.ops_profile.as_ref().map(|o| OpsProfile::parse(o)).transpose()?.unwrap_or_default();
 
     let mut servers = s.to_vec();
     if let Some(i) = deploy.server_index {
@@ -657,7 +936,8 @@ pub async fn default_deploy(
             servers.clone(),
             vec![],
             &mut pid_tx,
-            &net
+            &net,
+            deploy.bip85
         ).await?;
 
         let mut peer_tx_opt: Option<structs::Transaction> = None;
@@ -678,9 +958,20 @@ pub async fn default_deploy(
             None
         };
         peer_id_index.insert(ss.peer_id_index, peer_id_hex.clone());
-        let hm = hm.clone();
+        let mut hm = hm.clone();
+        let server_key = ss.deploy_key(&net);
+        let image_tag = if deploy.rollback && !deploy.ops {
+            df.all().rollback_deploy_tag(&server_key)?
+        } else {
+            deploy.image_tag.clone()
+                .or(ss.image_tag.clone())
+                .unwrap_or(net.to_std_string())
+        };
+        hm.insert("REDGOLD_IMAGE_TAG".to_string(), image_tag.clone());
+        println!("Using image tag: {}", image_tag);
         println!("Setting up server: {}", ss.host.clone());
 
+        let mut genesis_manifest_opt = None;
         if let Some(o) = &deploy.server_offline_info {
             let p = PathBuf::from(o);
             let pi = p.join(format!("{}", ss.index));
@@ -692,25 +983,32 @@ pub async fn default_deploy(
             let words_path = pi.join("mnemonic");
             let words_read = std::fs::read_to_string(words_path).expect("offline info");
             words_opt = Some(words_read);
+            // Written by network-init for private networks with custom genesis allocations
+            genesis_manifest_opt = std::fs::read_to_string(p.join("genesis.json")).ok();
         }
 
         // let ssh = SSH::new_ssh(ss.host.clone(), None);
         let ssh = DeployMachine::new(ss, None);
         if !deploy.ops {
-            let _t = tokio::time::timeout(Duration::from_secs(120), setup_server_redgold(
+            let image_tag = tokio::time::timeout(Duration::from_secs(120), setup_server_redgold(
                 ssh, net, gen, Some(hm), purge,
                 words_opt,
                 peer_id_hex_opt,
                 !deploy.debug_skip_start,
                 ss.node_name.clone(),
                 peer_tx_opt.map(|p| p.json_or()),
+                genesis_manifest_opt,
                 &output_handler
             )).await.error_info("Timeout")??;
+            println!("Deployed image tag: {}", image_tag);
+            if !deploy.rollback {
+                df.all().record_deploy_tag(&server_key, &image_tag)?;
+            }
         }
         gen = false;
         if !deploy.skip_ops || deploy.ops {
             let ssh = DeployMachine::new(ss, None);
-            deploy_ops_services(ssh, None, None, None, deploy.purge_ops, &output_handler).await.expect("")
+            deploy_ops_services(ssh, None, None, None, deploy.purge_ops, ops_profile, &output_handler).await.expect("")
         }
     }
     Ok(())
@@ -758,5 +1056,74 @@ pub(crate) async fn backup_multiparty_local_shares(p0: NodeConfig, p1: Vec<Serve
         let contents = std::fs::read_to_string(fnm_export).expect("");
         std::fs::remove_file(fnm_export).ok();
         std::fs::write(server_dir.join(fnm_export), contents).expect("");
+        // BTC wallet checkpoints let a redeployed node skip most of its first sync
+        let checkpoints = util::cmd::run_bash_async(
+            format!(
+                "scp -r {}@{}:~/.rg/{}/btc_checkpoints {}",
+                user, s.host.clone(), net_str, server_dir.to_string_lossy())
+        ).await;
+        println!("Checkpoint backup result: {:?}", checkpoints);
+    }
+}
+/// Single server operations exposed alongside the bulk deploy
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ServerAction {
+    Start,
+    Stop,
+    Logs,
+    RedeployOps,
+    Backup,
+}
+
+impl ServerAction {
+    pub fn all() -> Vec<ServerAction> {
+        vec![ServerAction::Start, ServerAction::Stop, ServerAction::Logs, ServerAction::RedeployOps, ServerAction::Backup]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ServerAction::Start => "Start",
+            ServerAction::Stop => "Stop",
+            ServerAction::Logs => "Logs",
+            ServerAction::RedeployOps => "Redeploy Ops",
+            ServerAction::Backup => "Backup",
+        }
+    }
+}
+
+pub async fn run_server_action(
+    node_config: NodeConfig,
+    server: Server,
+    action: ServerAction,
+    log_lines: usize,
+    p: &Option<Sender<String>>
+) -> RgResult<()> {
+    let net = node_config.network.to_std_string();
+    let path = format!("/root/.rg/{}", net);
+    let mut ssh = DeployMachine::new(&server, None);
+    if let Some(s) = p {
+        s.send(format!("Running {} on {}", action.label(), server.host)).ok();
+    }
+    match action {
+        ServerAction::Start => {
+            ssh.exes(format!("cd {}; docker-compose -f redgold-only.yml up -d", path), p).await?;
+        }
+        ServerAction::Stop => {
+            ssh.exes(format!("cd {}; docker-compose -f redgold-only.yml stop", path), p).await?;
+        }
+        ServerAction::Logs => {
+            ssh.exes(format!("docker logs --tail {} redgold-{}", log_lines, net), p).await?;
+        }
+        ServerAction::RedeployOps => {
+            let profile = OpsProfile::detect(&mut ssh, "/root/.rg/all", p).await?;
+            deploy_ops_services(ssh, None, None, None, false, profile, p).await?;
+        }
+        ServerAction::Backup => {
+            backup_multiparty_local_shares(node_config, vec![server.clone()]).await;
+            if let Some(s) = p {
+                s.send(format!("Backup complete for {}", server.host)).ok();
+            }
+        }
     }
-}
\ No newline at end of file
+    Ok(())
+}
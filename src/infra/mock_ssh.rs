@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use async_trait::async_trait;
+use flume::Sender;
+use itertools::Itertools;
+use redgold_schema::{error_info, RgResult};
+use redgold_schema::servers::Server;
+use crate::infra::deploy::{DeployMachine, SSHLike};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MockSSHCall {
+    Execute(String),
+    Scp { from: String, to: String, to_dest: bool },
+    Copy { remote_path: String, contents: String },
+}
+
+/// In memory SSHLike recording every call in order, for testing deploy steps without a server.
+/// Commands return the output registered for the longest matching prefix, or nothing.
+#[derive(Clone, Default)]
+pub struct MockSSH {
+    pub calls: Arc<Mutex<Vec<MockSSHCall>>>,
+    pub responses: HashMap<String, String>,
+}
+
+impl MockSSH {
+
+    /// Responds to the connection check in DeployMachine::verify.
+    pub fn new() -> Self {
+        Self::default().with_response("df", "Filesystem     1K-blocks")
+    }
+
+    pub fn with_response(mut self, command_prefix: impl Into<String>, output: impl Into<String>) -> Self {
+        self.responses.insert(command_prefix.into(), output.into());
+        self
+    }
+
+    pub fn machine(&self, server: &Server) -> DeployMachine<MockSSH> {
+        DeployMachine {
+            server: server.clone(),
+            ssh: self.clone(),
+        }
+    }
+
+    fn record(&self, call: MockSSHCall) -> RgResult<()> {
+        self.calls.lock()
+            .map_err(|e| error_info(format!("Failed to lock mock ssh calls: {}", e)))?
+            .push(call);
+        Ok(())
+    }
+
+    pub fn calls(&self) -> Vec<MockSSHCall> {
+        self.calls.lock().map(|c| c.clone()).unwrap_or_default()
+    }
+
+    pub fn commands(&self) -> Vec<String> {
+        self.calls().into_iter().filter_map(|c| match c {
+            MockSSHCall::Execute(cmd) => Some(cmd),
+            _ => None
+        }).collect_vec()
+    }
+
+    /// Remote paths and contents of copied files, in order.
+    pub fn copies(&self) -> Vec<(String, String)> {
+        self.calls().into_iter().filter_map(|c| match c {
+            MockSSHCall::Copy { remote_path, contents } => Some((remote_path, contents)),
+            _ => None
+        }).collect_vec()
+    }
+}
+
+#[async_trait]
+impl SSHLike for MockSSH {
+
+    async fn execute(&self, command: impl Into<String> + Send, output_handler: Option<Sender<String>>) -> RgResult<String> {
+        let command = command.into();
+        if let Some(s) = output_handler {
+            s.send(format!("mock: {}", command)).ok();
+        }
+        self.record(MockSSHCall::Execute(command.clone()))?;
+        let output = self.responses.iter()
+            .filter(|(prefix, _)| command.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, output)| output.clone())
+            .unwrap_or_default();
+        Ok(output)
+    }
+
+    async fn scp(&self, from: impl Into<String> + Send, to: impl Into<String> + Send, to_dest: bool, _output_handler: Option<Sender<String>>) -> RgResult<String> {
+        self.record(MockSSHCall::Scp { from: from.into(), to: to.into(), to_dest })?;
+        Ok("".to_string())
+    }
+
+    async fn copy_contents(&self, contents: String, remote_path: String) -> RgResult<()> {
+        self.record(MockSSHCall::Copy { remote_path, contents })
+    }
+}
+
+#[tokio::test]
+async fn mock_ssh_records_calls_and_responses() {
+    let ssh = MockSSH::new().with_response("docker", "a").with_response("docker ps", "b");
+    assert_eq!(ssh.execute("docker ps -a", None).await.expect("exec"), "b");
+    assert_eq!(ssh.execute("docker-compose", None).await.expect("exec"), "a");
+    assert_eq!(ssh.execute("ls", None).await.expect("exec"), "");
+    ssh.copy_contents("x".to_string(), "/root/f".to_string()).await.expect("copy");
+    assert_eq!(ssh.commands(), vec!["docker ps -a", "docker-compose", "ls"]);
+    assert_eq!(ssh.copies(), vec![("/root/f".to_string(), "x".to_string())]);
+}
@@ -5,6 +5,8 @@ pub mod bitcoind;
 pub mod matrix;
 pub mod netmaker;
 pub mod deploy;
+pub mod dashboards;
+pub mod mock_ssh;
 //
 // use bdk::bitcoin::util::bip32::ExtendedPrivKey;
 // use bdk::bitcoin::Network;
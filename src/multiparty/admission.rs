@@ -0,0 +1,165 @@
+use log::{error, info};
+use metrics::{counter, gauge};
+use serde::{Deserialize, Serialize};
+use redgold_data::config::ConfigKey;
+use redgold_schema::RgResult;
+use crate::core::relay::Relay;
+use crate::multiparty::watcher::{BidAsk, BTC_FULFILLMENTS};
+
+pub const ADMISSION_STATE: ConfigKey<AdmissionState> = ConfigKey::new("watcher_admission");
+// A paused condition has to drop below this fraction of its threshold before the order book is
+// published again, so it doesn't flap around the threshold
+pub const DEFAULT_RESUME_FRACTION: f64 = 0.8;
+// Fee rates come from fulfillment previews, older ones no longer reflect the mempool
+const FEE_RATE_MAX_AGE_MS: i64 = 1000 * 60 * 60;
+
+/// Limits above which the watcher stops advertising prices, so new deposits aren't taken while
+/// existing orders are stuck. Unset limits are never checked.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct AdmissionControlConfig {
+    #[serde(default)]
+    pub max_unfulfilled_orders: Option<usize>,
+    // sat/vB of the most recent BTC fulfillment preview
+    #[serde(default)]
+    pub max_btc_fee_rate: Option<f64>,
+    // DEFAULT_RESUME_FRACTION when unset
+    #[serde(default)]
+    pub resume_fraction: Option<f64>,
+}
+
+/// Result of the latest admission check, published along with the order book.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct AdmissionState {
+    pub time: i64,
+    pub unfulfilled_orders: usize,
+    pub btc_fee_rate: Option<f64>,
+    // A backlog withdraws both sides, high BTC fees only the bids which are paid out in BTC
+    pub backlog_paused: bool,
+    pub fee_paused: bool,
+    pub paused_since: Option<i64>,
+}
+
+fn over_limit(value: f64, limit: Option<f64>, was_paused: bool, resume_fraction: f64) -> bool {
+    match limit {
+        None => false,
+        Some(l) if was_paused => value > l * resume_fraction,
+        Some(l) => value > l,
+    }
+}
+
+impl AdmissionState {
+
+    pub fn paused(&self) -> bool {
+        self.backlog_paused || self.fee_paused
+    }
+
+    pub fn next(
+        previous: Option<&AdmissionState>,
+        time: i64,
+        cfg: &AdmissionControlConfig,
+        unfulfilled_orders: usize,
+        btc_fee_rate: Option<f64>,
+    ) -> AdmissionState {
+        let resume_fraction = cfg.resume_fraction.unwrap_or(DEFAULT_RESUME_FRACTION);
+        let backlog_paused = over_limit(
+            unfulfilled_orders as f64,
+            cfg.max_unfulfilled_orders.map(|m| m as f64),
+            previous.map(|p| p.backlog_paused).unwrap_or(false),
+            resume_fraction
+        );
+        // Without a recent preview there are no BTC payouts pending, so nothing to hold back
+        let fee_paused = btc_fee_rate.map(|r| over_limit(
+            r, cfg.max_btc_fee_rate, previous.map(|p| p.fee_paused).unwrap_or(false), resume_fraction
+        )).unwrap_or(false);
+        let mut state = AdmissionState {
+            time,
+            unfulfilled_orders,
+            btc_fee_rate,
+            backlog_paused,
+            fee_paused,
+            paused_since: None,
+        };
+        if state.paused() {
+            state.paused_since = previous.and_then(|p| p.paused_since).or(Some(time));
+        }
+        state
+    }
+
+    /// Order book as advertised to users, with paused sides withdrawn.
+    pub fn apply(&self, bid_ask: &BidAsk) -> BidAsk {
+        let mut advertised = bid_ask.clone();
+        if self.backlog_paused {
+            advertised.asks = vec![];
+        }
+        if self.paused() {
+            advertised.bids = vec![];
+        }
+        advertised
+    }
+}
+
+pub async fn latest_btc_fee_rate(relay: &Relay, now: i64) -> RgResult<Option<f64>> {
+    let records = relay.ds.config_store.get_typed(&BTC_FULFILLMENTS).await?.unwrap_or(vec![]);
+    Ok(records.last()
+        .filter(|r| now - r.time < FEE_RATE_MAX_AGE_MS)
+        .map(|r| r.preview.fee_rate))
+}
+
+/// Evaluates the limits for this watcher pass and stores the result for the order book API.
+/// With no config the state is still stored, clearing any earlier pause.
+pub async fn update_admission(
+    relay: &Relay,
+    cfg: Option<&AdmissionControlConfig>,
+    unfulfilled_orders: usize,
+    now: i64,
+) -> RgResult<AdmissionState> {
+    let cfg = cfg.cloned().unwrap_or_default();
+    let previous = relay.ds.config_store.get_typed(&ADMISSION_STATE).await?;
+    let fee_rate = latest_btc_fee_rate(relay, now).await?;
+    let state = AdmissionState::next(previous.as_ref(), now, &cfg, unfulfilled_orders, fee_rate);
+    relay.ds.config_store.set_typed(&ADMISSION_STATE, &state).await?;
+    gauge!("redgold.amm.admission_paused").set(if state.paused() { 1.0 } else { 0.0 });
+    let was_paused = previous.map(|p| p.paused()).unwrap_or(false);
+    if state.paused() && !was_paused {
+        error!("Withdrawing order book, unfulfilled orders: {} BTC fee rate: {:?}",
+            unfulfilled_orders, fee_rate);
+        counter!("redgold.amm.admission_paused_entered").increment(1);
+    } else if !state.paused() && was_paused {
+        info!("Resuming order book, unfulfilled orders: {} BTC fee rate: {:?}", unfulfilled_orders, fee_rate);
+    }
+    Ok(state)
+}
+
+#[test]
+fn admission_pauses_and_resumes_with_hysteresis() {
+    use crate::multiparty::watcher::PriceVolume;
+    let cfg = AdmissionControlConfig {
+        max_unfulfilled_orders: Some(10),
+        max_btc_fee_rate: Some(50.0),
+        resume_fraction: None,
+    };
+    let normal = AdmissionState::next(None, 0, &cfg, 5, Some(10.0));
+    assert!(!normal.paused());
+    let backlog = AdmissionState::next(Some(&normal), 1, &cfg, 11, Some(10.0));
+    assert!(backlog.backlog_paused && !backlog.fee_paused);
+    assert_eq!(backlog.paused_since, Some(1));
+    // Stays paused until below the resume fraction of the limit
+    let draining = AdmissionState::next(Some(&backlog), 2, &cfg, 9, Some(10.0));
+    assert!(draining.backlog_paused);
+    assert_eq!(draining.paused_since, Some(1));
+    let drained = AdmissionState::next(Some(&draining), 3, &cfg, 8, Some(10.0));
+    assert!(!drained.paused());
+    assert_eq!(drained.paused_since, None);
+
+    let fees = AdmissionState::next(Some(&drained), 4, &cfg, 0, Some(60.0));
+    assert!(fees.fee_paused && !fees.backlog_paused);
+    let pv = PriceVolume { price: 1.0, volume: 1 };
+    let ba = BidAsk { bids: vec![pv.clone()], asks: vec![pv], center_price: 1.0 };
+    let advertised = fees.apply(&ba);
+    assert!(advertised.bids.is_empty());
+    assert_eq!(advertised.asks.len(), 1);
+    assert!(backlog.apply(&ba).asks.is_empty());
+    // No recent preview means no BTC payouts are pending
+    assert!(!AdmissionState::next(Some(&fees), 5, &cfg, 0, None).paused());
+    assert!(!AdmissionState::next(None, 6, &AdmissionControlConfig::default(), 1000, Some(500.0)).paused());
+}
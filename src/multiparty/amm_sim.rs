@@ -0,0 +1,185 @@
+use std::path::PathBuf;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use redgold_schema::{error_info, ErrorInfoContext, RgResult};
+use redgold_schema::structs::Address;
+use crate::multiparty::watcher::{BidAsk, curve_price_after, CurveConfig, get_btc_per_rdg_starting_min_ask};
+
+/// Market price of RDG in BTC at a point in time, one row of the simulation input.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PricePoint {
+    pub time: i64,
+    // BTC per RDG
+    pub price: f64,
+}
+
+/// Reads `time,price` rows with a header, price in BTC per RDG.
+pub fn read_price_csv(path: &PathBuf) -> RgResult<Vec<PricePoint>> {
+    let mut rdr = csv::Reader::from_path(path).error_info("Failed to open price csv")?;
+    let mut res = vec![];
+    for result in rdr.deserialize() {
+        let record: PricePoint = result.error_info("price csv line parse failure")?;
+        if record.price <= 0.0 {
+            return Err(error_info(format!("Non positive price {} at time {}", record.price, record.time)));
+        }
+        res.push(record);
+    }
+    Ok(res)
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct SlippageSummary {
+    pub mean: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+    pub max: f64,
+}
+
+impl SlippageSummary {
+    fn from(values: &Vec<f64>) -> Self {
+        if values.is_empty() {
+            return Self::default();
+        }
+        let sorted = values.iter().cloned().sorted_by(|a, b| a.total_cmp(b)).collect_vec();
+        let pct = |p: f64| sorted[((sorted.len() - 1) as f64 * p).round() as usize];
+        Self {
+            mean: sorted.iter().sum::<f64>() / sorted.len() as f64,
+            p50: pct(0.5),
+            p90: pct(0.9),
+            p99: pct(0.99),
+            max: *sorted.last().expect("max"),
+        }
+    }
+}
+
+/// Outcome of replaying a price series against the curve, values in BTC at market prices.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct AmmSimReport {
+    pub curve: CurveConfig,
+    pub points: usize,
+    // BTC deposits filled from the ask curve
+    pub ask_fills: usize,
+    // RDG deposits filled from the bid curve
+    pub bid_fills: usize,
+    // Orders too small to fill or against an empty curve
+    pub unfilled: usize,
+    pub initial_value_btc: f64,
+    pub final_value_btc: f64,
+    // Value of the starting inventory left untouched, at the final price
+    pub hold_value_btc: f64,
+    pub pnl_vs_hold_btc: f64,
+    // Fraction of the market implied amount a taker didn't receive
+    pub slippage: SlippageSummary,
+    // Share of inventory value held in RDG
+    pub rdg_share_initial: f64,
+    pub rdg_share_final: f64,
+    pub rdg_share_min: f64,
+    pub rdg_share_max: f64,
+    pub final_rdg: u64,
+    pub final_btc: u64,
+    pub final_center_price: f64,
+}
+
+const SATS: f64 = 1e8;
+
+fn value_btc(rdg: u64, btc: u64, btc_per_rdg: f64) -> f64 {
+    (rdg as f64 * btc_per_rdg + btc as f64) / SATS
+}
+
+/// Replays arbitrage flow over the price series through the production curve code. Each point
+/// a taker trades `order_sats` worth against whichever side of the curve is better than market,
+/// after which the curve is regenerated from the new balances as the watcher does.
+pub fn simulate(
+    points: &Vec<PricePoint>,
+    curve: &CurveConfig,
+    initial_rdg: u64,
+    initial_btc: u64,
+    order_sats: u64,
+) -> RgResult<AmmSimReport> {
+    let first = points.first().ok_or(error_info("No price points to simulate"))?;
+    let last = points.last().expect("last");
+    let destination = Address::default();
+    let (mut rdg, mut btc) = (initial_rdg, initial_btc);
+    // Curve center price is RDG per BTC
+    let mut price = 1.0 / first.price;
+    let mut bid_ask = BidAsk::generate_with_config(
+        rdg as i64, btc, price, get_btc_per_rdg_starting_min_ask(first.time), curve
+    );
+    let mut report = AmmSimReport::default();
+    report.curve = curve.clone();
+    report.points = points.len();
+    report.initial_value_btc = value_btc(rdg, btc, first.price);
+    report.hold_value_btc = value_btc(initial_rdg, initial_btc, last.price);
+    let mut slippage = vec![];
+
+    let rdg_share = |rdg: u64, btc: u64, price: f64| {
+        rdg as f64 * price / SATS / value_btc(rdg, btc, price).max(f64::MIN_POSITIVE)
+    };
+    report.rdg_share_initial = rdg_share(rdg, btc, first.price);
+    report.rdg_share_min = report.rdg_share_initial;
+    report.rdg_share_max = report.rdg_share_initial;
+    for p in points {
+        let share = rdg_share(rdg, btc, p.price);
+        report.rdg_share_min = report.rdg_share_min.min(share);
+        report.rdg_share_max = report.rdg_share_max.max(share);
+        let market_rdg_per_btc = 1.0 / p.price;
+        let ask_cheap = bid_ask.asks.first().map(|a| a.price < p.price).unwrap_or(false);
+        let bid_rich = bid_ask.bids.first().map(|b| b.price < market_rdg_per_btc).unwrap_or(false);
+        let (is_ask, order_amount) = if ask_cheap {
+            (true, order_sats)
+        } else if bid_rich {
+            (false, (order_sats as f64 * market_rdg_per_btc) as u64)
+        } else {
+            continue;
+        };
+        let fulfillment = bid_ask.fulfill_taker_order(order_amount, is_ask, p.time, None, &destination);
+        if fulfillment.is_none() {
+            report.unfilled += 1;
+            continue;
+        }
+        let f = fulfillment.expect("fulfillment");
+        let market_amount = if is_ask {
+            report.ask_fills += 1;
+            btc += f.order_amount;
+            rdg = rdg.saturating_sub(f.fulfilled_amount);
+            f.order_amount as f64 * market_rdg_per_btc
+        } else {
+            report.bid_fills += 1;
+            rdg += f.order_amount;
+            btc = btc.saturating_sub(f.fulfilled_amount);
+            f.order_amount as f64 * p.price
+        };
+        slippage.push(((market_amount - f.fulfilled_amount as f64) / market_amount).max(0.0));
+        price = curve_price_after(price, Some(&f));
+        bid_ask = BidAsk::generate_with_config(
+            rdg as i64, btc, price, get_btc_per_rdg_starting_min_ask(p.time), curve
+        );
+    }
+
+    report.final_value_btc = value_btc(rdg, btc, last.price);
+    report.pnl_vs_hold_btc = report.final_value_btc - report.hold_value_btc;
+    report.rdg_share_final = rdg_share(rdg, btc, last.price);
+    report.rdg_share_min = report.rdg_share_min.min(report.rdg_share_final);
+    report.rdg_share_max = report.rdg_share_max.max(report.rdg_share_final);
+    report.slippage = SlippageSummary::from(&slippage);
+    report.final_rdg = rdg;
+    report.final_btc = btc;
+    report.final_center_price = price;
+    Ok(report)
+}
+
+#[test]
+fn amm_sim_reports_fills() {
+    let points = (0..50).map(|i| PricePoint {
+        time: i * 60_000,
+        price: 0.0025 + (i % 10) as f64 * 0.0001,
+    }).collect_vec();
+    let report = simulate(&points, &CurveConfig::default(), 100_000 * 100_000_000, 10 * 100_000_000, 1_000_000)
+        .expect("sim");
+    assert_eq!(report.points, 50);
+    assert!(report.ask_fills + report.bid_fills > 0);
+    assert!(report.slippage.max >= report.slippage.p50);
+    assert!(report.rdg_share_min <= report.rdg_share_final && report.rdg_share_final <= report.rdg_share_max);
+    assert!(simulate(&vec![], &CurveConfig::default(), 1, 1, 1).is_err());
+}
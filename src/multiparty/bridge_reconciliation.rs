@@ -0,0 +1,108 @@
+use std::collections::HashSet;
+use itertools::Itertools;
+use redgold_data::data_store::DataStore;
+use redgold_schema::{from_hex_ref, RgResult, SafeOption};
+use redgold_schema::structs::{Address, SupportedCurrency};
+use crate::multiparty::watcher::{BtcFulfillmentRecord, BTC_FULFILLMENTS};
+
+/// Outcome of comparing stored bridge rows against the recorded BTC fulfillments.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BridgeReconciliation {
+    pub duplicate_rows: u64,
+    pub missing: Vec<BtcFulfillmentRecord>,
+    // Missing records which can't be repaired since they predate recording the source address
+    pub unrepairable: Vec<String>,
+    pub removed: u64,
+    pub inserted: u64,
+}
+
+/// Broadcast fulfillments whose BTC txid doesn't appear in any bridge row, either as the
+/// primary txid or as the secondary txid of a withdrawal.
+pub fn missing_bridge_fulfillments(
+    records: &Vec<BtcFulfillmentRecord>,
+    bridge_txids: &HashSet<Vec<u8>>
+) -> Vec<BtcFulfillmentRecord> {
+    records.iter()
+        .filter(|r| !r.aborted)
+        .filter(|r| r.txid.as_ref()
+            .and_then(|t| from_hex_ref(t).ok())
+            .map(|t| !bridge_txids.contains(&t))
+            .unwrap_or(false))
+        .cloned()
+        .collect_vec()
+}
+
+/// Writes the outgoing bridge row for a broadcast BTC fulfillment, keyed by the BTC txid. Returns
+/// false if the row already exists.
+pub async fn insert_fulfillment_bridge_row(ds: &DataStore, record: &BtcFulfillmentRecord) -> RgResult<bool> {
+    let txid = from_hex_ref(record.txid.safe_get_msg("Fulfillment not broadcast")?)?;
+    let source = Address::from_bitcoin(record.source_address.safe_get_msg("Missing fulfillment source address")?);
+    let (destination, _) = record.outputs.first().safe_get_msg("Fulfillment has no outputs")?;
+    let destination = Address::from_bitcoin(destination);
+    let amount = record.outputs.iter().map(|(_, a)| *a as i64).sum::<i64>();
+    ds.multiparty_store.insert_bridge_tx(
+        &txid,
+        &vec![],
+        true,
+        SupportedCurrency::Bitcoin,
+        &source,
+        &destination,
+        record.time,
+        amount
+    ).await
+}
+
+/// Detects duplicate bridge rows and broadcast fulfillments without a bridge row, removing the
+/// duplicates and inserting the missing rows when `repair` is set.
+pub async fn reconcile_bridge(ds: &DataStore, repair: bool) -> RgResult<BridgeReconciliation> {
+    let mut res = BridgeReconciliation::default();
+    res.duplicate_rows = ds.multiparty_store.count_duplicate_bridge_rows().await?;
+    let bridge_txids = ds.multiparty_store.bridge_row_keys().await?
+        .into_iter()
+        .flat_map(|k| vec![k.txid, k.secondary_txid])
+        .flatten()
+        .collect::<HashSet<Vec<u8>>>();
+    let records = ds.config_store.get_typed(&BTC_FULFILLMENTS).await?.unwrap_or(vec![]);
+    res.missing = missing_bridge_fulfillments(&records, &bridge_txids);
+    res.unrepairable = res.missing.iter()
+        .filter(|r| r.source_address.is_none())
+        .flat_map(|r| r.txid.clone())
+        .collect_vec();
+    if repair {
+        if res.duplicate_rows > 0 {
+            res.removed = ds.multiparty_store.remove_duplicate_bridge_rows().await?;
+        }
+        for r in res.missing.iter().filter(|r| r.source_address.is_some()) {
+            if insert_fulfillment_bridge_row(ds, r).await? {
+                res.inserted += 1;
+            }
+        }
+    }
+    Ok(res)
+}
+
+#[test]
+fn missing_fulfillments_ignore_aborted_and_recorded() {
+    use redgold_keys::util::btc_wallet::BtcFeePreview;
+    let record = |txid: Option<&str>, aborted: bool| BtcFulfillmentRecord {
+        time: 0,
+        outputs: vec![],
+        preview: BtcFeePreview { vsize: 0, fee: 0, fee_rate: 0.0 },
+        max_fee_rate: 0.0,
+        txid: txid.map(|t| t.to_string()),
+        aborted,
+        source_address: None,
+    };
+    let recorded = "aa".repeat(32);
+    let missing = "bb".repeat(32);
+    let records = vec![
+        record(Some(&recorded), false),
+        record(Some(&missing), false),
+        record(None, true),
+    ];
+    let mut txids = HashSet::new();
+    txids.insert(from_hex_ref(&recorded).unwrap());
+    let res = missing_bridge_fulfillments(&records, &txids);
+    assert_eq!(res.len(), 1);
+    assert_eq!(res[0].txid, Some(missing));
+}
@@ -0,0 +1,75 @@
+use std::path::PathBuf;
+use metrics::counter;
+use redgold_keys::util::btc_checkpoint::BtcWalletCheckpoint;
+use redgold_keys::util::btc_wallet::{BtcAddressType, SingleKeyBitcoinWallet};
+use redgold_schema::{json_from, json_pretty, ErrorInfoContext, RgResult};
+use redgold_schema::structs::{Hash, PublicKey};
+use crate::node_config::NodeConfig;
+use crate::observability::logging::Loggable;
+use crate::util::cli::data_folder::EnvDataFolder;
+
+// Checkpoints are rewritten at most this often, each is a full copy of the wallet history
+pub const BTC_CHECKPOINT_INTERVAL_MS: i64 = 1000 * 60 * 10;
+
+fn checkpoint_path(folder: &EnvDataFolder, descriptor: &str) -> PathBuf {
+    let name = Hash::from_string_calculate(descriptor).hex();
+    folder.btc_checkpoints_path().join(format!("{}.json", name))
+}
+
+pub fn read_checkpoint(folder: &EnvDataFolder, descriptor: &str) -> RgResult<Option<BtcWalletCheckpoint>> {
+    let path = checkpoint_path(folder, descriptor);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let contents = std::fs::read_to_string(&path).error_info("Bad btc checkpoint read")?;
+    Ok(Some(json_from(&contents)?))
+}
+
+/// Written to a temporary file first so a crash mid write leaves the previous checkpoint intact.
+pub fn write_checkpoint(folder: &EnvDataFolder, checkpoint: &BtcWalletCheckpoint) -> RgResult<()> {
+    std::fs::create_dir_all(folder.btc_checkpoints_path()).error_info("Bad btc checkpoint folder create")?;
+    let path = checkpoint_path(folder, &checkpoint.descriptor);
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, json_pretty(checkpoint)?).error_info("Bad btc checkpoint write")?;
+    std::fs::rename(&tmp, &path).error_info("Bad btc checkpoint rename")
+}
+
+pub fn all_checkpoints(folder: &EnvDataFolder) -> RgResult<Vec<BtcWalletCheckpoint>> {
+    let dir = folder.btc_checkpoints_path();
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+    let mut res = vec![];
+    for entry in std::fs::read_dir(&dir).error_info("Bad btc checkpoint folder read")? {
+        let path = entry.error_info("Bad btc checkpoint entry")?.path();
+        if path.extension().map(|e| e == "json").unwrap_or(false) {
+            let contents = std::fs::read_to_string(&path).error_info("Bad btc checkpoint read")?;
+            res.push(json_from(&contents)?);
+        }
+    }
+    Ok(res)
+}
+
+/// Wallet for a multiparty key, resuming from the node's stored checkpoint when there is a
+/// usable one and falling back to a full sync otherwise.
+pub fn checkpointed_wallet(nc: &NodeConfig, key: &PublicKey) -> RgResult<SingleKeyBitcoinWallet> {
+    let address_type = BtcAddressType::NativeSegwit;
+    let descriptor = address_type.descriptor(key)?;
+    let checkpoint = read_checkpoint(&nc.env_data_folder(), &descriptor).log_error().ok().flatten();
+    if let Some(cp) = checkpoint.as_ref() {
+        let res = SingleKeyBitcoinWallet::new_wallet_checkpointed(
+            key.clone(), nc.network, true, &nc.btc_backend, address_type, Some(cp)
+        ).log_error();
+        if res.is_ok() {
+            counter!("redgold.btc.checkpoint.imported").increment(1);
+            return res;
+        }
+    }
+    SingleKeyBitcoinWallet::new_wallet_backend(key.clone(), nc.network, true, &nc.btc_backend)
+}
+
+pub fn save_wallet_checkpoint(nc: &NodeConfig, w: &SingleKeyBitcoinWallet) -> RgResult<()> {
+    write_checkpoint(&nc.env_data_folder(), &w.export_checkpoint()?)?;
+    counter!("redgold.btc.checkpoint.saved").increment(1);
+    Ok(())
+}
@@ -0,0 +1,154 @@
+use std::sync::{Arc, Mutex, RwLock};
+use async_trait::async_trait;
+use log::info;
+use metrics::{counter, gauge, histogram};
+use redgold_keys::util::btc_wallet::{ExternalTimedTransaction, SingleKeyBitcoinWallet};
+use redgold_schema::{error_info, ErrorInfoContext, RgResult};
+use redgold_schema::structs::PublicKey;
+use crate::core::relay::Relay;
+use crate::core::stream_handlers::IntervalFold;
+use crate::multiparty::btc_checkpoint;
+use crate::observability::logging::Loggable;
+use crate::util::current_time_millis_i64;
+
+// Fulfillment is skipped when the last completed sync started longer ago than this
+pub const BTC_SNAPSHOT_MAX_AGE_MS: i64 = 1000 * 60 * 10;
+
+/// BTC wallet state of the party key as of the start of the sync that produced it, so anything
+/// broadcast before that time is reflected.
+#[derive(Clone, Debug)]
+pub struct BtcWalletSnapshot {
+    pub time: i64,
+    pub public_key: PublicKey,
+    pub confirmed_balance: u64,
+    pub transactions: Vec<ExternalTimedTransaction>,
+}
+
+impl BtcWalletSnapshot {
+
+    pub fn check_fresh(&self, now: i64, max_age_ms: i64) -> RgResult<()> {
+        let age = now - self.time;
+        if age > max_age_ms {
+            return Err(error_info(format!(
+                "BTC wallet snapshot is {}s old, exceeding max age of {}s", age / 1000, max_age_ms / 1000
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Latest snapshot shared between the sync task and its readers, the lock is only held to swap
+/// or clone the pointer so readers never wait on a sync.
+#[derive(Clone, Default)]
+pub struct SharedBtcSnapshot {
+    inner: Arc<RwLock<Option<Arc<BtcWalletSnapshot>>>>,
+}
+
+impl SharedBtcSnapshot {
+
+    pub fn latest(&self) -> Option<Arc<BtcWalletSnapshot>> {
+        self.inner.read().ok().and_then(|s| s.clone())
+    }
+
+    pub fn set(&self, snapshot: BtcWalletSnapshot) -> RgResult<()> {
+        let mut guard = self.inner.write()
+            .map_err(|e| error_info(format!("Failed to lock BTC snapshot: {}", e).as_str()))?;
+        *guard = Some(Arc::new(snapshot));
+        Ok(())
+    }
+}
+
+/// Runs `f` with the wallet locked on the blocking pool. The sync task holds the lock across
+/// electrum calls, so it must never be taken on a runtime thread.
+pub async fn with_wallet<T, F>(wallet: &Arc<Mutex<SingleKeyBitcoinWallet>>, f: F) -> RgResult<T>
+where
+    T: Send + 'static,
+    F: FnOnce(&mut SingleKeyBitcoinWallet) -> RgResult<T> + Send + 'static,
+{
+    let w = wallet.clone();
+    tokio::task::spawn_blocking(move || {
+        let mut w = w.lock()
+            .map_err(|e| error_info(format!("Failed to lock wallet: {}", e).as_str()))?;
+        f(&mut w)
+    }).await.error_info("BTC wallet task failed")?
+}
+
+/// Background task syncing the party BTC wallet off the watcher's fulfillment path, which waits
+/// for the wallet through `with_wallet` rather than blocking a runtime thread.
+pub struct BtcWalletSyncer {
+    relay: Relay,
+    wallet: Arc<Mutex<SingleKeyBitcoinWallet>>,
+    snapshot: SharedBtcSnapshot,
+    last_checkpoint: i64,
+}
+
+impl BtcWalletSyncer {
+
+    pub fn new(relay: &Relay, wallet: &Arc<Mutex<SingleKeyBitcoinWallet>>, snapshot: &SharedBtcSnapshot) -> Self {
+        Self {
+            relay: relay.clone(),
+            wallet: wallet.clone(),
+            snapshot: snapshot.clone(),
+            last_checkpoint: 0,
+        }
+    }
+
+    pub async fn sync(&mut self) -> RgResult<()> {
+        let start = current_time_millis_i64();
+        let (public_key, confirmed_balance, transactions) = with_wallet(&self.wallet, |w| {
+            let balance = w.get_wallet_balance()?.confirmed;
+            Ok((w.public_key.clone(), balance, w.get_all_tx()?))
+        }).await?;
+
+        let end = current_time_millis_i64();
+        histogram!("redgold.btc.sync.duration_seconds").record((end - start) as f64 / 1000.0);
+        gauge!("redgold.btc.sync.last_success").set(end as f64);
+        if end - start > BTC_SNAPSHOT_MAX_AGE_MS {
+            info!("BTC wallet sync took {}ms, snapshot will already be stale", end - start);
+        }
+
+        if end - self.last_checkpoint > btc_checkpoint::BTC_CHECKPOINT_INTERVAL_MS {
+            self.last_checkpoint = end;
+            let node_config = self.relay.node_config.clone();
+            with_wallet(&self.wallet, move |w| btc_checkpoint::save_wallet_checkpoint(&node_config, w))
+                .await.log_error().ok();
+        }
+
+        self.snapshot.set(BtcWalletSnapshot {
+            time: start,
+            public_key,
+            confirmed_balance,
+            transactions,
+        })
+    }
+}
+
+#[async_trait]
+impl IntervalFold for BtcWalletSyncer {
+
+    #[tracing::instrument(skip(self))]
+    async fn interval_fold(&mut self) -> RgResult<()> {
+        // Errors are only logged, returning one would end the sync task
+        if self.sync().await.log_error().is_err() {
+            counter!("redgold.btc.sync.failed").increment(1);
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn btc_snapshot_staleness() {
+    use redgold_keys::TestConstants;
+    let shared = SharedBtcSnapshot::default();
+    assert!(shared.latest().is_none());
+    shared.set(BtcWalletSnapshot {
+        time: 1000,
+        public_key: TestConstants::new().key_pair().public_key(),
+        confirmed_balance: 10,
+        transactions: vec![],
+    }).expect("set");
+    let s = shared.latest().expect("snapshot");
+    assert_eq!(s.confirmed_balance, 10);
+    assert!(s.check_fresh(1000 + BTC_SNAPSHOT_MAX_AGE_MS, BTC_SNAPSHOT_MAX_AGE_MS).is_ok());
+    assert!(s.check_fresh(1001 + BTC_SNAPSHOT_MAX_AGE_MS, BTC_SNAPSHOT_MAX_AGE_MS).is_err());
+}
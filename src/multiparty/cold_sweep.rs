@@ -0,0 +1,181 @@
+use std::sync::{Arc, Mutex};
+use log::info;
+use metrics::counter;
+use serde::{Deserialize, Serialize};
+use redgold_keys::address_external::ToBitcoinAddress;
+use redgold_keys::util::btc_wallet::{bitcoin_network, SingleKeyBitcoinWallet};
+use redgold_schema::{EasyJson, error_info, RgResult, SafeOption, WithMetadataHashable};
+use redgold_schema::errors::EnhanceErrorInfo;
+use redgold_schema::structs::{Address, ControlColdSweepRequest, ControlColdSweepResponse, CurrencyAmount, NetworkEnvironment, SupportedCurrency};
+use crate::core::transact::fee_policy::fee_estimate;
+use crate::core::transact::tx_builder_supports::TransactionBuilder;
+use crate::core::transact::tx_builder_supports::TransactionBuilderSupport;
+use crate::multiparty::btc_sync::{BtcWalletSnapshot, with_wallet};
+use crate::multiparty::party_stream::PartyEvents;
+use crate::multiparty::watcher::{DEPOSIT_WATCHER_CONFIG, DepositWatcher};
+use crate::util::current_time_millis_i64;
+
+/// Balances held by the party above these caps are moved to cold addresses by the operator
+/// sweep, amounts in satoshis for both currencies.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ColdStorageConfig {
+    pub rdg_address: Option<String>,
+    pub btc_address: Option<String>,
+    pub hot_cap_rdg: u64,
+    pub hot_cap_btc: u64,
+}
+
+impl ColdStorageConfig {
+    pub fn rdg_address_typed(&self) -> Option<Address> {
+        self.rdg_address.as_ref().and_then(|a| Address::parse(a.clone()).ok())
+    }
+}
+
+/// Outgoing party transfer to a configured cold address, kept apart from fulfillments.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SweepEvent {
+    pub time: i64,
+    pub currency: SupportedCurrency,
+    pub amount: u64,
+    pub destination: String,
+    pub tx_id: String,
+}
+
+/// Amounts above the hot caps once `owed` (RDG, BTC) to pending orders is set aside, zero for a
+/// currency without a cold address.
+pub fn sweep_amounts(cfg: &ColdStorageConfig, rdg_balance: u64, btc_balance: u64, owed: (u64, u64)) -> (u64, u64) {
+    let (rdg_owed, btc_owed) = owed;
+    let rdg = if cfg.rdg_address.is_some() {
+        rdg_balance.saturating_sub(rdg_owed).saturating_sub(cfg.hot_cap_rdg)
+    } else { 0 };
+    let btc = if cfg.btc_address.is_some() {
+        btc_balance.saturating_sub(btc_owed).saturating_sub(cfg.hot_cap_btc)
+    } else { 0 };
+    (rdg, btc)
+}
+
+fn validate_cold_btc_address(address: &String, network: &NetworkEnvironment) -> RgResult<()> {
+    let parsed = SingleKeyBitcoinWallet::parse_address(address).add("Invalid cold BTC address")?;
+    if parsed.network != bitcoin_network(network) {
+        return Err(error_info(format!("Cold BTC address {} is not for network {}", address, network.to_std_string())));
+    }
+    Ok(())
+}
+
+impl DepositWatcher {
+
+    /// Applies any cold storage fields set on the request to the stored watcher config.
+    pub async fn update_cold_storage(&self, req: &ControlColdSweepRequest) -> RgResult<()> {
+        if req.rdg_address.is_none() && req.btc_address.is_none() && req.hot_cap_rdg.is_none() && req.hot_cap_btc.is_none() {
+            return Ok(());
+        }
+        if let Some(a) = &req.rdg_address {
+            Address::parse(a.clone()).add("Invalid cold RDG address")?;
+        }
+        if let Some(a) = &req.btc_address {
+            validate_cold_btc_address(a, &self.relay.node_config.network)?;
+        }
+        let mut cfg = Self::get_deposit_config(&self.relay.ds).await?
+            .ok_or(error_info("No deposit watcher config, party not yet formed"))?;
+        let mut cold = cfg.cold_storage.clone().unwrap_or_default();
+        if let Some(a) = &req.rdg_address {
+            cold.rdg_address = Some(a.clone());
+        }
+        if let Some(a) = &req.btc_address {
+            cold.btc_address = Some(a.clone());
+        }
+        if let Some(c) = req.hot_cap_rdg {
+            cold.hot_cap_rdg = c;
+        }
+        if let Some(c) = req.hot_cap_btc {
+            cold.hot_cap_btc = c;
+        }
+        info!("Updated cold storage config {}", cold.json_or());
+        cfg.cold_storage = Some(cold);
+        self.relay.ds.config_store.set_typed(&DEPOSIT_WATCHER_CONFIG, &cfg).await?;
+        Ok(())
+    }
+
+    /// Plans, and when executing keysigns and broadcasts, transfers of party balances above the
+    /// hot caps to the cold addresses.
+    pub async fn cold_sweep(&self, execute: bool) -> RgResult<ControlColdSweepResponse> {
+        let cfg = Self::get_deposit_config(&self.relay.ds).await?
+            .ok_or(error_info("No deposit watcher config, party not yet formed"))?;
+        let cold = cfg.cold_storage.clone()
+            .ok_or(error_info("No cold storage configured in the deposit watcher config"))?;
+        let alloc = cfg.deposit_allocations.get(0).safe_get_msg("Missing deposit allocation")?.clone();
+        let identifier = alloc.initiate.identifier.safe_get_msg("Missing party identifier")?.clone();
+        let network = self.relay.node_config.network;
+        let key_address = alloc.key.address()?;
+
+        let rdg_balance = self.relay.ds.transaction_store.get_balance(&key_address).await?.unwrap_or(0).max(0) as u64;
+        let w = SingleKeyBitcoinWallet::new_wallet_backend(
+            alloc.key.clone(), network, true, &self.relay.node_config.btc_backend
+        )?;
+        let w_arc = Arc::new(Mutex::new(w));
+        let (btc_balance, transactions) = with_wallet(&w_arc, |w| {
+            Ok((w.get_wallet_balance()?.confirmed, w.get_all_tx()?))
+        }).await?;
+        let btc = BtcWalletSnapshot {
+            time: current_time_millis_i64(),
+            public_key: alloc.key.clone(),
+            confirmed_balance: btc_balance,
+            transactions,
+        };
+        let ps = PartyEvents::historical_initialize(&alloc.key, &self.relay, &btc).await?;
+        let owed = ps.outstanding_obligations();
+        let (rdg_amount, btc_amount) = sweep_amounts(&cold, rdg_balance, btc_balance, owed);
+        info!("Cold sweep setting aside {} RDG sats and {} BTC sats owed to pending orders", owed.0, owed.1);
+
+        let mut res = ControlColdSweepResponse::default();
+        res.rdg_balance = rdg_balance as i64;
+        res.btc_balance = btc_balance as i64;
+        res.rdg_amount = rdg_amount as i64;
+        res.btc_amount = btc_amount as i64;
+        res.party_btc_address = alloc.key.to_bitcoin_address(&network)?;
+        res.rdg_destination = cold.rdg_address.clone();
+        res.btc_destination = cold.btc_address.clone();
+        if !execute {
+            return Ok(res);
+        }
+        // Batched orders aren't priced until the window settles, so what they're owed isn't known
+        if !ps.pending_batch.is_empty() {
+            return Err(error_info("Orders are pending in the batch window, retry the sweep once it settles"));
+        }
+
+        if rdg_amount > 0 {
+            let destination = cold.rdg_address_typed().ok_or(error_info("Invalid cold RDG address"))?;
+            let utxos = self.relay.ds.transaction_store.query_utxo_address(&key_address).await?;
+            let mut tb = TransactionBuilder::new(&network);
+            tb.with_utxos(&utxos)?;
+            tb.with_output(&destination, &CurrencyAmount::from_rdg(rdg_amount as i64))
+                .with_auto_fee(&fee_estimate(&self.relay));
+            let mut tx = tb.build()?;
+            self.send_ask_fulfillment_transaction(&mut tx, identifier.clone()).await?;
+            counter!("redgold.amm.cold_sweep.rdg").increment(1);
+            info!("Swept {} RDG sats to cold address {}", rdg_amount, destination.render_string()?);
+            res.rdg_tx_hash = Some(tx.hash_or().hex());
+        }
+        if btc_amount > 0 {
+            let destination = cold.btc_address.clone().ok_or(error_info("Missing cold BTC address"))?;
+            let txid = self.fulfill_btc_bids(&w_arc, identifier, vec![(destination.clone(), btc_amount)], cfg.max_btc_fee_rate).await?;
+            counter!("redgold.amm.cold_sweep.btc").increment(1);
+            info!("Swept {} sats to cold address {} in txid {}", btc_amount, destination, txid);
+            res.btc_txid = Some(txid);
+        }
+        Ok(res)
+    }
+}
+
+#[test]
+fn sweep_amounts_above_caps() {
+    let mut cfg = ColdStorageConfig::default();
+    cfg.hot_cap_rdg = 100;
+    cfg.hot_cap_btc = 50;
+    assert_eq!(sweep_amounts(&cfg, 500, 500, (0, 0)), (0, 0));
+    cfg.rdg_address = Some("a".to_string());
+    cfg.btc_address = Some("b".to_string());
+    assert_eq!(sweep_amounts(&cfg, 500, 20, (0, 0)), (400, 0));
+    // Pending orders are paid from the hot balance before anything is swept
+    assert_eq!(sweep_amounts(&cfg, 500, 500, (150, 500)), (250, 0));
+}
@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+use redgold_data::config::ConfigKey;
+use redgold_keys::deposit_address::derive_deposit_btc_address;
+use redgold_schema::{error_info, RgResult, SafeOption};
+use redgold_schema::structs::{Address, PublicKey};
+use crate::core::relay::Relay;
+use crate::multiparty::watcher::DepositWatcher;
+use crate::util::current_time_millis_i64;
+
+// Each address would be synced by the BTC sync task every interval, so registrations are bounded
+pub const MAX_DEPOSIT_ADDRESSES: usize = 1000;
+
+pub const DEPOSIT_ADDRESSES: ConfigKey<Vec<DepositAddressEntry>> = ConfigKey::new("amm_deposit_addresses");
+
+/// A BTC deposit address for one user, the child at `index` of the party xpub's deposit chain.
+/// Entries are never removed, an address once handed out may receive funds at any later time.
+///
+/// Nothing hands these out or credits deposits to them yet, the party can't sign for derived
+/// keys, so BTC sent to them couldn't be swept or refunded.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DepositAddressEntry {
+    pub user_address: Address,
+    pub btc_address: String,
+    pub party_public_key: PublicKey,
+    pub index: u32,
+    pub created_time: i64,
+}
+
+pub async fn deposit_addresses(relay: &Relay) -> RgResult<Vec<DepositAddressEntry>> {
+    Ok(relay.ds.config_store.get_typed(&DEPOSIT_ADDRESSES).await?.unwrap_or_default())
+}
+
+/// Returns the user's deposit address for the active party key, assigning the next index of the
+/// deposit chain on first request.
+pub async fn register_deposit_address(relay: &Relay, user_address: &Address) -> RgResult<DepositAddressEntry> {
+    let cfg = DepositWatcher::get_deposit_config(&relay.ds).await?
+        .safe_get_msg("No AMM party formed on this node")?.clone();
+    let party_key = cfg.deposit_allocations.get(0).map(|a| a.key.clone())
+        .safe_get_msg("Missing deposit allocation")?.clone();
+    let mut entries = deposit_addresses(relay).await?;
+    if let Some(e) = entries.iter().find(|e| &e.user_address == user_address && e.party_public_key == party_key) {
+        return Ok(e.clone());
+    }
+    let index = entries.iter().filter(|e| e.party_public_key == party_key).count();
+    if index >= MAX_DEPOSIT_ADDRESSES {
+        return Err(error_info("Deposit address limit reached, send from the BTC key of the RDG address instead"));
+    }
+    let entry = DepositAddressEntry {
+        user_address: user_address.clone(),
+        btc_address: derive_deposit_btc_address(&party_key, index as u32, &relay.node_config.network)?,
+        party_public_key: party_key,
+        index: index as u32,
+        created_time: current_time_millis_i64(),
+    };
+    entries.push(entry.clone());
+    relay.ds.config_store.set_typed(&DEPOSIT_ADDRESSES, &entries).await?;
+    Ok(entry)
+}
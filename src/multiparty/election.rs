@@ -0,0 +1,214 @@
+use std::collections::HashSet;
+use std::time::Duration;
+use itertools::Itertools;
+use log::info;
+use redgold_schema::{error_info, RgResult, SafeOption};
+use redgold_schema::structs::{MultipartyIdentifier, PublicKey, Request, WatcherHeartbeat};
+use crate::core::relay::Relay;
+use crate::multiparty::gg20_signing::local_share_public_key;
+use crate::multiparty::watcher::{BidAsk, DepositKeyAllocation, DepositWatcher, DepositWatcherConfig, DEPOSIT_WATCHER_CONFIG};
+use crate::util::current_time_millis_i64;
+
+// Slots span several watcher intervals so leadership stays stable between handovers
+const SLOT_INTERVALS: u32 = 6;
+const HEARTBEAT_TIMEOUT_INTERVALS: u32 = 3;
+
+/// Timing for watcher leader election, derived from the watcher interval.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ElectionTiming {
+    pub slot_ms: i64,
+    pub heartbeat_timeout_ms: i64,
+    // No member fulfills at the start of a slot, giving the previous leader's
+    // in flight transactions time to be observed before anyone else builds on them.
+    pub handover_grace_ms: i64,
+}
+
+impl ElectionTiming {
+    pub fn from_interval(interval: Duration) -> Self {
+        let interval_ms = interval.as_millis() as i64;
+        Self {
+            slot_ms: interval_ms * SLOT_INTERVALS as i64,
+            heartbeat_timeout_ms: interval_ms * HEARTBEAT_TIMEOUT_INTERVALS as i64,
+            handover_grace_ms: interval_ms,
+        }
+    }
+
+    pub fn slot(&self, time: i64) -> i64 {
+        time / self.slot_ms
+    }
+
+    pub fn in_handover_grace(&self, time: i64) -> bool {
+        time % self.slot_ms < self.handover_grace_ms
+    }
+}
+
+/// Rotates the member list by slot index and picks the first healthy member, so every
+/// member computing this with the same view of liveness arrives at the same leader.
+pub fn elect_leader(members: &Vec<PublicKey>, healthy: &HashSet<PublicKey>, slot: i64) -> Option<PublicKey> {
+    let n = members.len();
+    if n == 0 {
+        return None;
+    }
+    let start = slot.rem_euclid(n as i64) as usize;
+    (0..n).map(|i| &members[(start + i) % n])
+        .find(|m| healthy.contains(*m))
+        .cloned()
+}
+
+fn timing(relay: &Relay) -> ElectionTiming {
+    ElectionTiming::from_interval(relay.node_config.watcher_interval)
+}
+
+pub fn record_heartbeat(relay: &Relay, member: &PublicKey, time: i64) -> RgResult<()> {
+    let mut hb = relay.watcher_heartbeats.lock()
+        .map_err(|e| error_info(format!("Failed to lock watcher_heartbeats {}", e.to_string())))?;
+    hb.insert(member.clone(), time);
+    Ok(())
+}
+
+pub fn healthy_members(relay: &Relay, members: &Vec<PublicKey>, now: i64) -> RgResult<HashSet<PublicKey>> {
+    let self_key = relay.node_config.public_key();
+    let timeout = timing(relay).heartbeat_timeout_ms;
+    let hb = relay.watcher_heartbeats.lock()
+        .map_err(|e| error_info(format!("Failed to lock watcher_heartbeats {}", e.to_string())))?;
+    Ok(members.iter().filter(|m| {
+        **m == self_key || hb.get(*m).map(|t| now - *t < timeout).unwrap_or(false)
+    }).cloned().collect())
+}
+
+pub fn current_leader(relay: &Relay, ident: &MultipartyIdentifier, now: i64) -> RgResult<Option<PublicKey>> {
+    let healthy = healthy_members(relay, &ident.party_keys, now)?;
+    Ok(elect_leader(&ident.party_keys, &healthy, timing(relay).slot(now)))
+}
+
+/// Whether this node should drive fulfillment for the allocation right now.
+pub fn is_fulfillment_leader(relay: &Relay, alloc: &DepositKeyAllocation, now: i64) -> RgResult<bool> {
+    let ident = alloc.initiate.identifier.safe_get_msg("Missing identifier")?;
+    let leader = current_leader(relay, ident, now)?;
+    let is_leader = leader.as_ref() == Some(&relay.node_config.public_key());
+    Ok(is_leader && !timing(relay).in_handover_grace(now))
+}
+
+pub async fn broadcast_heartbeat(relay: &Relay, alloc: &DepositKeyAllocation) -> RgResult<()> {
+    let ident = alloc.initiate.identifier.safe_get_msg("Missing identifier")?;
+    let now = current_time_millis_i64();
+    let self_key = relay.node_config.public_key();
+    let peers = ident.party_keys.iter().filter(|p| **p != self_key).cloned().collect_vec();
+    let mut hb = WatcherHeartbeat::default();
+    hb.party_id = Some(alloc.party_id()?);
+    hb.initiate = Some(alloc.initiate.clone());
+    hb.time = now;
+    hb.slot = timing(relay).slot(now);
+    hb.leader = current_leader(relay, ident, now)?;
+    let mut req = Request::default();
+    req.watcher_heartbeat = Some(hb);
+    let results = relay.broadcast_async(peers.clone(), req, Some(Duration::from_secs(10))).await?;
+    for (peer, result) in peers.iter().zip(results.iter()) {
+        // A successful response is as good as a heartbeat from that member
+        if result.is_ok() {
+            record_heartbeat(relay, peer, now)?;
+        }
+    }
+    Ok(())
+}
+
+pub async fn handle_heartbeat(relay: &Relay, hb: &WatcherHeartbeat, sender: &PublicKey) -> RgResult<()> {
+    let initiate = hb.initiate.safe_get_msg("Missing initiate request on heartbeat")?;
+    let claimed = initiate.identifier.safe_get_msg("Missing identifier on heartbeat")?;
+    // The heartbeat contents are sender controlled, so membership and the party key are taken
+    // from this node's own record of the keygen round.
+    let (local_share, local_initiate) = relay.ds.multiparty_store
+        .local_share_and_initiate(claimed.uuid.clone()).await?
+        .ok_msg("Heartbeat for a keygen room this node did not take part in")?;
+    let ident = local_initiate.identifier.safe_get_msg("Missing identifier on local keygen")?;
+    if !ident.party_keys.contains(sender) || !ident.party_keys.contains(&relay.node_config.public_key()) {
+        return Err(error_info("Heartbeat sender or receiver is not a member of the watcher party"));
+    }
+    record_heartbeat(relay, sender, current_time_millis_i64())?;
+
+    // Members other than the initiator learn the allocation here so they're able to take over
+    if DepositWatcher::get_deposit_config(&relay.ds).await?.is_none() {
+        let key = local_share_public_key(&local_share)?;
+        let claimed_key = hb.party_id.as_ref().and_then(|p| p.public_key.as_ref());
+        if claimed_key != Some(&key) {
+            return Err(error_info("Heartbeat party key does not match the local keygen share"));
+        }
+        info!("Storing watcher allocation learned from party heartbeat");
+        let cfg = DepositWatcherConfig {
+            deposit_allocations: vec![DepositKeyAllocation {
+                key: key.clone(),
+                allocation: 1.0,
+                initiate: local_initiate.clone(),
+                balance_btc: 0,
+                balance_rdg: 0,
+            }],
+            bid_ask: BidAsk { bids: vec![], asks: vec![], center_price: DepositWatcher::get_starting_center_price_rdg_btc_fallback().await },
+            last_btc_timestamp: 0,
+            ask_bid_code_reset: None,
+            order_limits: None,
+            batching: None,
+            safe_mode_drift_threshold: None,
+            max_btc_fee_rate: None,
+            cold_storage: None,
+            startup_max_balance_drop: None,
+            admission: None,
+        };
+        relay.ds.config_store.set_typed(&DEPOSIT_WATCHER_CONFIG, &cfg).await?;
+    }
+    Ok(())
+}
+
+/// Whether a member may lead the slot. The initiator is treated as healthy since it evidently
+/// is, and the previous slot's leader is accepted so signing rounds started just before a slot
+/// boundary, or seen with slightly different heartbeat views, aren't rejected.
+pub fn accepted_leader(members: &Vec<PublicKey>, healthy: &HashSet<PublicKey>, slot: i64, pk: &PublicKey) -> bool {
+    let mut healthy = healthy.clone();
+    healthy.insert(pk.clone());
+    [slot, slot - 1].iter().any(|s| elect_leader(members, &healthy, *s).as_ref() == Some(pk))
+}
+
+/// Signing requests against the watcher party key are only honored from the elected leader,
+/// which prevents two members from concurrently building fulfillments over the same funds.
+pub async fn check_signing_leader(relay: &Relay, ident: &MultipartyIdentifier, initiating_pk: &PublicKey) -> RgResult<()> {
+    let cfg = DepositWatcher::get_deposit_config(&relay.ds).await?;
+    let watcher_ident = cfg.as_ref()
+        .and_then(|c| c.deposit_allocations.get(0))
+        .and_then(|a| a.initiate.identifier.as_ref());
+    if watcher_ident.map(|i| i.uuid == ident.uuid).unwrap_or(false) {
+        let now = current_time_millis_i64();
+        let healthy = healthy_members(relay, &ident.party_keys, now)?;
+        if !accepted_leader(&ident.party_keys, &healthy, timing(relay).slot(now), initiating_pk) {
+            return Err(error_info("Signing request for watcher party not from elected leader"));
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn elect_leader_rotates_and_skips_unhealthy() {
+    use redgold_keys::TestConstants;
+    use redgold_keys::util::keys::ToPublicKeyFromLib;
+    let tc = TestConstants::new();
+    let members = vec![tc.public.to_struct_public_key(), tc.public2.to_struct_public_key()];
+    let all: HashSet<PublicKey> = members.iter().cloned().collect();
+    assert_eq!(elect_leader(&members, &all, 0), Some(members[0].clone()));
+    assert_eq!(elect_leader(&members, &all, 1), Some(members[1].clone()));
+    let only_first: HashSet<PublicKey> = vec![members[0].clone()].into_iter().collect();
+    assert_eq!(elect_leader(&members, &only_first, 1), Some(members[0].clone()));
+    assert_eq!(elect_leader(&members, &HashSet::new(), 1), None);
+}
+
+#[test]
+fn accepted_leader_allows_previous_slot() {
+    use redgold_keys::TestConstants;
+    use redgold_keys::util::keys::ToPublicKeyFromLib;
+    let tc = TestConstants::new();
+    let third = tc.words_pass.keypair_at_change(2).expect("keypair").public_key();
+    let members = vec![tc.public.to_struct_public_key(), tc.public2.to_struct_public_key(), third];
+    let all: HashSet<PublicKey> = members.iter().cloned().collect();
+    assert!(accepted_leader(&members, &all, 2, &members[2]));
+    assert!(accepted_leader(&members, &all, 2, &members[1]));
+    assert!(!accepted_leader(&members, &all, 2, &members[0]));
+    // Missing heartbeats from the initiator don't cause its own slot to be rejected
+    assert!(accepted_leader(&members, &HashSet::new(), 0, &members[0]));
+}
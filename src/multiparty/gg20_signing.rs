@@ -49,6 +49,13 @@ use curv::elliptic::curves::Point;
 //
 // }
 
+/// Party public key of a stored keygen share, compressed as used for the signing proofs.
+pub fn local_share_public_key(local_share: &str) -> Result<structs::PublicKey, ErrorInfo> {
+    let share: LocalKey<curv::elliptic::curves::secp256_k1::Secp256k1> = serde_json::from_str(local_share)
+        .map_err(|e| error_info(format!("Failed to parse local share: {}", e)))?;
+    Ok(structs::PublicKey::from_bytes(share.public_key().to_bytes(true).to_vec()))
+}
+
 // #[tokio::main]
 async fn signing_original(
     address: surf::Url, room: &str, local_share: String, parties: Vec<u16>, data_to_sign: Vec<u8>,
@@ -445,6 +445,7 @@ pub async fn initiate_mp_keysign_follower(
 
     let ident = mp_req.identifier.safe_get_msg("Missing room id for keygen on signing follower")?;
     let keygen_room_id = ident.uuid.clone();
+    crate::multiparty::election::check_signing_leader(&relay, ident, initiating_pk).await?;
 
     // TODO: Duplicated, put on the identifier class
     let index = ident.party_keys.iter().enumerate().filter_map(|(idx, pk)| {
@@ -4,5 +4,17 @@ pub mod initiate_mp;
 pub mod gg20_keygen;
 pub mod gg20_signing;
 pub mod watcher;
-mod party_stream;
+pub mod party_stream;
+pub mod party_snapshot;
+pub mod election;
+pub mod party_audit;
+pub mod test_liquidity;
+pub mod amm_sim;
+pub mod cold_sweep;
+pub mod deposit_addresses;
+pub mod bridge_reconciliation;
+pub mod watcher_validation;
+pub mod btc_checkpoint;
+pub mod btc_sync;
+pub mod admission;
 mod offline_debug;
\ No newline at end of file
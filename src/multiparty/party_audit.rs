@@ -0,0 +1,122 @@
+use log::{error, info};
+use metrics::{counter, gauge};
+use serde::{Deserialize, Serialize};
+use redgold_data::config::ConfigKey;
+use redgold_schema::structs::SupportedCurrency;
+use crate::multiparty::party_stream::PartyEvents;
+
+pub const PARTY_AUDIT: ConfigKey<PartyAudit> = ConfigKey::new("watcher_party_audit");
+// Drift above this is logged even when no safe mode threshold is configured
+const ALERT_DRIFT: f64 = 0.01;
+// Transactions not yet observed by seeds cause transient drift, so the threshold must be
+// exceeded on several consecutive audits before fulfillments are paused.
+const DRIFT_AUDITS_BEFORE_SAFE_MODE: u32 = 3;
+
+/// Comparison of balances expected from the party event history against actual chain balances.
+/// BTC fees aren't tracked by the event stream, so a small BTC drift is expected over time.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct PartyAudit {
+    pub time: i64,
+    pub expected_rdg: i64,
+    pub actual_rdg: i64,
+    pub expected_btc: i64,
+    pub actual_btc: i64,
+    pub consecutive_drift: u32,
+    pub safe_mode: bool,
+}
+
+fn drift_fraction(expected: i64, actual: i64) -> f64 {
+    let denominator = expected.abs().max(actual.abs());
+    if denominator == 0 {
+        0.0
+    } else {
+        (expected - actual).abs() as f64 / denominator as f64
+    }
+}
+
+impl PartyAudit {
+
+    pub fn rdg_drift(&self) -> f64 {
+        drift_fraction(self.expected_rdg, self.actual_rdg)
+    }
+
+    pub fn btc_drift(&self) -> f64 {
+        drift_fraction(self.expected_btc, self.actual_btc)
+    }
+
+    pub fn max_drift(&self) -> f64 {
+        self.rdg_drift().max(self.btc_drift())
+    }
+
+    /// Builds the next audit, entering safe mode after repeated drift over the threshold and
+    /// leaving it again as soon as an audit comes back within bounds.
+    pub fn next(
+        previous: Option<&PartyAudit>,
+        time: i64,
+        expected: (i64, i64),
+        actual: (i64, i64),
+        safe_mode_threshold: Option<f64>
+    ) -> PartyAudit {
+        let mut audit = PartyAudit {
+            time,
+            expected_rdg: expected.0,
+            actual_rdg: actual.0,
+            expected_btc: expected.1,
+            actual_btc: actual.1,
+            consecutive_drift: 0,
+            safe_mode: false,
+        };
+        let exceeded = safe_mode_threshold.map(|t| audit.max_drift() > t).unwrap_or(false);
+        if exceeded {
+            audit.consecutive_drift = previous.map(|p| p.consecutive_drift).unwrap_or(0) + 1;
+        }
+        audit.safe_mode = audit.consecutive_drift >= DRIFT_AUDITS_BEFORE_SAFE_MODE;
+        audit
+    }
+}
+
+pub fn audit_party_events(
+    ps: &PartyEvents,
+    previous: Option<&PartyAudit>,
+    time: i64,
+    actual_rdg: i64,
+    actual_btc: i64,
+    safe_mode_threshold: Option<f64>
+) -> PartyAudit {
+    let expected_rdg = ps.balance_map.get(&SupportedCurrency::Redgold).cloned().unwrap_or(0);
+    let expected_btc = ps.balance_map.get(&SupportedCurrency::Bitcoin).cloned().unwrap_or(0);
+    let audit = PartyAudit::next(
+        previous, time, (expected_rdg, expected_btc), (actual_rdg, actual_btc), safe_mode_threshold
+    );
+    gauge!("redgold.amm.drift.rdg").set(audit.rdg_drift());
+    gauge!("redgold.amm.drift.btc").set(audit.btc_drift());
+    if audit.max_drift() > ALERT_DRIFT {
+        error!("Party balance drift detected expected RDG:{} actual RDG:{} expected BTC:{} actual BTC:{} consecutive: {}",
+            expected_rdg, actual_rdg, expected_btc, actual_btc, audit.consecutive_drift);
+    }
+    let was_safe_mode = previous.map(|p| p.safe_mode).unwrap_or(false);
+    if audit.safe_mode && !was_safe_mode {
+        error!("Watcher entering safe mode, fulfillments paused until party balances reconcile");
+        counter!("redgold.amm.safe_mode_entered").increment(1);
+    } else if !audit.safe_mode && was_safe_mode {
+        info!("Watcher leaving safe mode, party balances reconciled");
+    }
+    audit
+}
+
+#[test]
+fn safe_mode_after_consecutive_drift() {
+    let threshold = Some(0.05);
+    let mut prev: Option<PartyAudit> = None;
+    for i in 0..DRIFT_AUDITS_BEFORE_SAFE_MODE {
+        let a = PartyAudit::next(prev.as_ref(), i as i64, (100, 100), (80, 100), threshold);
+        assert_eq!(a.safe_mode, i + 1 >= DRIFT_AUDITS_BEFORE_SAFE_MODE);
+        prev = Some(a);
+    }
+    // Reconciles immediately once drift is within bounds
+    let healed = PartyAudit::next(prev.as_ref(), 10, (100, 100), (99, 100), threshold);
+    assert!(!healed.safe_mode);
+    // No threshold configured never pauses
+    let unconfigured = PartyAudit::next(prev.as_ref(), 11, (100, 100), (0, 0), None);
+    assert!(!unconfigured.safe_mode);
+}
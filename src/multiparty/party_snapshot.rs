@@ -0,0 +1,171 @@
+use itertools::Itertools;
+use log::info;
+use metrics::counter;
+use serde::{Deserialize, Serialize};
+use redgold_data::config::ConfigKey;
+use redgold_keys::proof_support::ProofSupport;
+use redgold_schema::{error_info, RgResult, SafeBytesAccess, SafeOption};
+use redgold_schema::structs::{Hash, MultipartyIdentifier, Proof, PublicKey, SupportedCurrency};
+use crate::core::relay::Relay;
+use crate::multiparty::cold_sweep::{ColdStorageConfig, SweepEvent};
+use crate::multiparty::initiate_mp::initiate_mp_keysign;
+use crate::multiparty::party_stream::{AddressEvent, BatchedOrder, PartyEvents};
+use crate::multiparty::watcher::{BatchingConfig, BidAsk, OrderFulfillment, OrderLimits};
+use crate::util::current_time_millis_i64;
+
+pub const PARTY_EVENTS_SNAPSHOT: ConfigKey<PartyEventsSnapshot> = ConfigKey::new("party_events_snapshot");
+// Events newer than this may still be re-timed or reordered by late observations, so they're
+// always replayed rather than compacted into a snapshot.
+pub const SNAPSHOT_LAG_MS: i64 = 1000 * 60 * 60 * 6;
+// Signing requires every party member online, so it's only attempted this often
+pub const SNAPSHOT_INTERVAL_MS: i64 = 1000 * 60 * 60 * 24;
+
+/// Replayed state of the party event stream, excluding unconfirmed events and deposit address
+/// destinations which are rebuilt on every initialize.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PartyEventsState {
+    // Sorted by currency so the serialized form, and so the signing hash, is deterministic
+    pub balances: Vec<(SupportedCurrency, i64)>,
+    pub unfulfilled_deposits: Vec<(OrderFulfillment, AddressEvent)>,
+    pub unfulfilled_withdrawals: Vec<(OrderFulfillment, AddressEvent)>,
+    pub unfulfilled_refunds: Vec<(OrderFulfillment, AddressEvent)>,
+    pub pending_batch: Vec<BatchedOrder>,
+    pub batch_window_start: Option<i64>,
+    pub price: f64,
+    pub bid_ask: BidAsk,
+    pub fulfillment_history: Vec<(OrderFulfillment, AddressEvent, AddressEvent)>,
+    pub refund_history: Vec<(OrderFulfillment, AddressEvent, AddressEvent)>,
+    pub sweep_history: Vec<SweepEvent>,
+}
+
+/// Party event state as of a cutoff time, signed by the party key so historical initialize can
+/// resume from it and only replay the events that came after.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PartyEventsSnapshot {
+    pub party_public_key: PublicKey,
+    pub cutoff_time: i64,
+    // Confirmed events at or before the cutoff, used to check the history hasn't changed under it
+    pub event_count: usize,
+    pub events_hash: Hash,
+    // Processing depends on these, so a config change invalidates the snapshot
+    pub config_hash: Hash,
+    pub state: PartyEventsState,
+    pub created_time: i64,
+    pub proof: Option<Proof>,
+}
+
+/// Digest of event identifiers, sorted since events with equal times have no stable order.
+pub fn events_digest(ids: Vec<String>) -> Hash {
+    Hash::from_string_calculate(&ids.into_iter().sorted().join(","))
+}
+
+pub fn config_digest(
+    order_limits: &Option<OrderLimits>,
+    batching: &Option<BatchingConfig>,
+    cold_storage: &Option<ColdStorageConfig>,
+) -> Hash {
+    let json = serde_json::to_string(&(order_limits, batching, cold_storage)).unwrap_or_default();
+    Hash::from_string_calculate(&json)
+}
+
+impl PartyEventsSnapshot {
+
+    pub fn signing_hash(&self) -> RgResult<Hash> {
+        let mut unsigned = self.clone();
+        unsigned.proof = None;
+        let json = serde_json::to_vec(&unsigned)
+            .map_err(|e| error_info(format!("Failed to serialize party snapshot: {}", e)))?;
+        Ok(Hash::digest(json))
+    }
+
+    pub fn verify(&self, party_public_key: &PublicKey) -> RgResult<()> {
+        if &self.party_public_key != party_public_key {
+            return Err(error_info("Party snapshot is for a different party key"));
+        }
+        let proof = self.proof.safe_get_msg("Party snapshot missing proof")?;
+        if proof.public_key.as_ref() != Some(party_public_key) {
+            return Err(error_info("Party snapshot not signed by the party key"));
+        }
+        proof.verify(&self.signing_hash()?)
+    }
+
+    /// Whether the snapshot covers exactly the confirmed events at or before its cutoff.
+    pub fn matches_events(&self, events: &Vec<AddressEvent>, seeds: &Vec<PublicKey>, config_hash: &Hash) -> bool {
+        let ids = events.iter()
+            .filter(|e| e.time(seeds).map(|t| t <= self.cutoff_time).unwrap_or(false))
+            .map(|e| e.identifier())
+            .collect_vec();
+        &self.config_hash == config_hash && ids.len() == self.event_count && events_digest(ids) == self.events_hash
+    }
+}
+
+/// Signs and stores the candidate captured during historical initialize once the stored
+/// snapshot is older than the snapshot interval.
+pub async fn sign_snapshot_if_due(
+    relay: &Relay,
+    ps: &PartyEvents,
+    identifier: &MultipartyIdentifier,
+) -> RgResult<()> {
+    let candidate = match ps.snapshot_candidate.as_ref() {
+        Some(c) => c,
+        None => return Ok(()),
+    };
+    let existing = relay.ds.config_store.get_typed(&PARTY_EVENTS_SNAPSHOT).await?;
+    let due = existing.as_ref()
+        .filter(|s| &s.party_public_key == &candidate.party_public_key)
+        .map(|s| candidate.cutoff_time - s.cutoff_time >= SNAPSHOT_INTERVAL_MS)
+        .unwrap_or(true);
+    if !due {
+        return Ok(());
+    }
+    let mut snapshot = candidate.clone();
+    snapshot.created_time = current_time_millis_i64();
+    let hash = snapshot.signing_hash()?;
+    let result = initiate_mp_keysign(
+        relay.clone(), identifier.clone(), hash.bytes.safe_get()?.clone(), identifier.party_keys.clone(), None
+    ).await?;
+    snapshot.proof = Some(result.proof);
+    snapshot.verify(&candidate.party_public_key)?;
+    relay.ds.config_store.set_typed(&PARTY_EVENTS_SNAPSHOT, &snapshot).await?;
+    counter!("redgold.amm.snapshot.saved").increment(1);
+    info!("Stored party events snapshot at cutoff {} covering {} events", snapshot.cutoff_time, snapshot.event_count);
+    Ok(())
+}
+
+#[test]
+fn party_snapshot_signature_covers_state() {
+    use redgold_keys::TestConstants;
+    use redgold_keys::util::ToPublicKey;
+    let tc = TestConstants::new();
+    let kp = tc.key_pair();
+    let pk = kp.public_key();
+    let mut snapshot = PartyEventsSnapshot {
+        party_public_key: pk.clone(),
+        cutoff_time: 1000,
+        event_count: 2,
+        events_hash: events_digest(vec!["b".to_string(), "a".to_string()]),
+        config_hash: config_digest(&None, &None, &None),
+        state: PartyEventsState {
+            balances: vec![(SupportedCurrency::Redgold, 100)],
+            unfulfilled_deposits: vec![],
+            unfulfilled_withdrawals: vec![],
+            unfulfilled_refunds: vec![],
+            pending_batch: vec![],
+            batch_window_start: None,
+            price: 1.0,
+            bid_ask: BidAsk::generate_default(0, 0, 1.0, 1.0),
+            fulfillment_history: vec![],
+            refund_history: vec![],
+            sweep_history: vec![],
+        },
+        created_time: 2000,
+        proof: None,
+    };
+    assert_eq!(snapshot.events_hash, events_digest(vec!["a".to_string(), "b".to_string()]));
+    assert!(snapshot.verify(&pk).is_err());
+    snapshot.proof = Some(Proof::from_keypair_hash(&snapshot.signing_hash().expect("hash"), &kp));
+    assert!(snapshot.verify(&pk).is_ok());
+    assert!(snapshot.verify(&tc.public2.to_struct_public_key()).is_err());
+    snapshot.state.balances = vec![(SupportedCurrency::Redgold, 200)];
+    assert!(snapshot.verify(&pk).is_err());
+}
@@ -4,17 +4,24 @@ use std::sync::{Arc, Mutex};
 use async_trait::async_trait;
 use itertools::Itertools;
 use log::{error, info};
+use metrics::counter;
 use rocket::serde::{Deserialize, Serialize};
+use redgold_data::config::ConfigKey;
 use redgold_keys::address_external::ToBitcoinAddress;
 use redgold_keys::transaction_support::TransactionSupport;
 use redgold_keys::util::btc_wallet::{ExternalTimedTransaction, SingleKeyBitcoinWallet};
 use redgold_schema::{EasyJson, error_info, RgResult, structs, WithMetadataHashable};
-use redgold_schema::structs::{Address, CurrencyAmount, ErrorInfo, ExternalTransactionId, NetworkEnvironment, ObservationProof, PublicKey, State, SupportedCurrency, Transaction, ValidationLiveness};
+use redgold_schema::structs::{Address, CurrencyAmount, ErrorInfo, ExternalTransactionId, Hash, NetworkEnvironment, ObservationProof, PublicKey, State, SupportedCurrency, Transaction, ValidationLiveness};
 use crate::api::public_api::PublicClient;
 use crate::api::RgHttpClient;
 use crate::core::relay::Relay;
-use crate::multiparty::watcher::{BidAsk, DepositWatcher, get_btc_per_rdg_starting_min_ask, OrderFulfillment};
+use crate::multiparty::cold_sweep::{ColdStorageConfig, SweepEvent};
+use crate::multiparty::btc_sync::{BtcWalletSnapshot, BtcWalletSyncer, SharedBtcSnapshot};
+use crate::multiparty::party_snapshot::{config_digest, events_digest, PARTY_EVENTS_SNAPSHOT, PartyEventsSnapshot, PartyEventsState, SNAPSHOT_LAG_MS};
+use crate::multiparty::watcher::{BatchingConfig, BidAsk, curve_price_after, DepositWatcher, get_btc_per_rdg_starting_min_ask, MIN_BTC_REFUND, OrderFulfillment, OrderLimits};
+use crate::util::current_time_millis_i64;
 use crate::node_config::NodeConfig;
+use crate::observability::logging::Loggable;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct TransactionWithObservations {
@@ -58,6 +65,170 @@ impl Balance {
 }
 
 
+pub const AMM_VOLUME_SUMMARY: ConfigKey<AmmVolumeSummary> = ConfigKey::new("amm_volume_summary");
+
+pub const AMM_RECENT_FILLS: ConfigKey<Vec<RecentFill>> = ConfigKey::new("amm_recent_fills");
+// Number of most recent fills published with the order book
+const RECENT_FILLS: usize = 20;
+
+/// A fulfilled order reduced to what's needed to mark it against the curve.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RecentFill {
+    pub time: i64,
+    // BTC deposit filled from the asks, otherwise an RDG deposit filled from the bids
+    pub is_ask: bool,
+    pub price_btc_per_rdg: f64,
+    pub rdg_amount: u64,
+}
+
+impl RecentFill {
+    pub fn from_fulfillment(of: &OrderFulfillment) -> Option<Self> {
+        let (btc, rdg) = if of.is_ask_fulfillment_from_external_deposit {
+            (of.order_amount, of.fulfilled_amount)
+        } else {
+            (of.fulfilled_amount, of.order_amount)
+        };
+        if rdg == 0 || of.is_refund {
+            return None;
+        }
+        Some(Self {
+            time: of.event_time,
+            is_ask: of.is_ask_fulfillment_from_external_deposit,
+            price_btc_per_rdg: btc as f64 / rdg as f64,
+            rdg_amount: rdg,
+        })
+    }
+}
+
+/// Cumulative swap volumes over fulfilled orders, amounts in satoshis for both currencies.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct AmmVolumeSummary {
+    pub btc_deposit_volume: u64,
+    pub rdg_fulfilled_volume: u64,
+    pub rdg_deposit_volume: u64,
+    pub btc_fulfilled_volume: u64,
+    pub num_fulfillments: u64,
+    pub updated_time: i64,
+    // Volume paid out through batch netting rather than against the curve
+    #[serde(default)]
+    pub rdg_netted_volume: u64,
+    #[serde(default)]
+    pub btc_netted_volume: u64,
+    // Moved to cold storage by operator sweeps, not counted as fulfillments
+    #[serde(default)]
+    pub rdg_swept_volume: u64,
+    #[serde(default)]
+    pub btc_swept_volume: u64,
+}
+
+/// Matches opposing flows at the given RDG/BTC price without moving along the curve,
+/// returning the matched (BTC, RDG) amounts.
+pub fn net_flows(btc_in: u64, rdg_in: u64, price: f64) -> (u64, u64) {
+    let matched_btc = btc_in.min((rdg_in as f64 / price) as u64);
+    let matched_rdg = ((matched_btc as f64 * price) as u64).min(rdg_in);
+    (matched_btc, matched_rdg)
+}
+
+#[test]
+fn net_flows_matches_smaller_side() {
+    // 400 RDG per BTC
+    assert_eq!(net_flows(1_000, 800_000, 400.0), (1_000, 400_000));
+    assert_eq!(net_flows(10_000, 800_000, 400.0), (2_000, 800_000));
+    assert_eq!(net_flows(10_000, 0, 400.0), (0, 0));
+}
+
+/// Indexes of the pending BTC refunds paid by an outgoing transaction. Each refund output pays its
+/// deposit less the same share of the fee, so refunds match on destination and on their amount
+/// net of one share common to the transaction, with every output paying at most one refund.
+pub fn paid_btc_refunds(
+    refunds: &Vec<(OrderFulfillment, AddressEvent)>,
+    t: &ExternalTimedTransaction,
+    network: &NetworkEnvironment
+) -> Vec<usize> {
+    let destinations = refunds.iter()
+        .map(|(of, _)| Some(of).filter(|of| !of.pays_rdg()).and_then(|of| of.destination.to_bitcoin_address(network).ok()))
+        .collect_vec();
+    let fee = t.fee.unwrap_or(0);
+    let mut shares = vec![];
+    for (addr, amount) in t.other_output_amounts.iter() {
+        for ((of, _), d) in refunds.iter().zip(destinations.iter()) {
+            if let Some(share) = of.fulfilled_amount.checked_sub(*amount) {
+                if d.as_ref() == Some(addr) && share <= fee {
+                    shares.push(share);
+                }
+            }
+        }
+    }
+    shares.sort();
+    shares.dedup();
+    let mut best = vec![];
+    for share in shares {
+        let mut paid = vec![];
+        for (addr, amount) in t.other_output_amounts.iter() {
+            let matched = (0..refunds.len()).find(|i| {
+                !paid.contains(i) && destinations[*i].as_ref() == Some(addr) &&
+                    refunds[*i].0.fulfilled_amount == amount + share
+            });
+            if let Some(i) = matched {
+                paid.push(i);
+            }
+        }
+        if paid.len() > best.len() {
+            best = paid;
+        }
+    }
+    best
+}
+
+#[test]
+fn paid_btc_refunds_matches_each_deposit_once() {
+    let network = NetworkEnvironment::Debug;
+    let addr = "bc1qrefundaddress".to_string();
+    let deposit = |tx_id: &str, amount: u64| {
+        let ett = ExternalTimedTransaction {
+            tx_id: tx_id.to_string(),
+            timestamp: Some(1),
+            other_address: addr.clone(),
+            other_output_addresses: vec![],
+            amount,
+            incoming: true,
+            currency: SupportedCurrency::Bitcoin,
+            other_output_amounts: vec![],
+            fee: None,
+        };
+        let refund = OrderFulfillment::refund(amount, true, 1, Some(tx_id.to_string()), &Address::from_bitcoin(&addr));
+        (refund, AddressEvent::External(ett))
+    };
+    let outgoing = |outputs: Vec<u64>, fee: u64| ExternalTimedTransaction {
+        tx_id: "refund".to_string(),
+        timestamp: Some(2),
+        other_address: addr.clone(),
+        other_output_addresses: vec![addr.clone()],
+        amount: outputs[0],
+        incoming: false,
+        currency: SupportedCurrency::Bitcoin,
+        other_output_amounts: outputs.iter().map(|a| (addr.clone(), *a)).collect_vec(),
+        fee: Some(fee),
+    };
+    let refunds = vec![deposit("d1", 50_000), deposit("d2", 30_000)];
+    // Only the second deposit refunded, the first stays pending
+    assert_eq!(paid_btc_refunds(&refunds, &outgoing(vec![29_500], 500), &network), vec![1]);
+    // Both refunded in one transaction with the fee split between them
+    assert_eq!(paid_btc_refunds(&refunds, &outgoing(vec![49_750, 29_750], 500), &network), vec![0, 1]);
+    // Unrelated payment to the same address
+    assert!(paid_btc_refunds(&refunds, &outgoing(vec![20_000], 300), &network).is_empty());
+}
+
+/// Swap order held until its batching window closes.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BatchedOrder {
+    pub amount: u64,
+    pub is_ask: bool,
+    pub tx_id: Option<String>,
+    pub destination: Address,
+    pub event: AddressEvent,
+}
+
 pub struct PartyEvents {
     key_address: Address,
     party_public_key: structs::PublicKey,
@@ -66,11 +237,22 @@ pub struct PartyEvents {
     pub balance_map: HashMap<SupportedCurrency, i64>,
     pub unfulfilled_deposits: Vec<(OrderFulfillment, AddressEvent)>,
     pub unfulfilled_withdrawals: Vec<(OrderFulfillment, AddressEvent)>,
+    // Deposits outside of the configured order limits, pending return to the sender
+    pub unfulfilled_refunds: Vec<(OrderFulfillment, AddressEvent)>,
+    pub order_limits: Option<OrderLimits>,
+    pub batching: Option<BatchingConfig>,
+    pub cold_storage: Option<ColdStorageConfig>,
+    pub pending_batch: Vec<BatchedOrder>,
+    pub batch_window_start: Option<i64>,
     pub price: f64,
     pub(crate) bid_ask: BidAsk,
     pub unconfirmed_events: Vec<AddressEvent>,
     // TODO: populate
-    pub fulfillment_history: Vec<(OrderFulfillment, AddressEvent, AddressEvent)>
+    pub fulfillment_history: Vec<(OrderFulfillment, AddressEvent, AddressEvent)>,
+    pub refund_history: Vec<(OrderFulfillment, AddressEvent, AddressEvent)>,
+    pub sweep_history: Vec<SweepEvent>,
+    // Unsigned snapshot of the state as of the snapshot lag, captured during historical initialize
+    pub snapshot_candidate: Option<PartyEventsSnapshot>,
 }
 
 impl PartyEvents {
@@ -143,10 +325,169 @@ impl PartyEvents {
             }
         }
 
+        let network = &self.relay.node_config.network;
+        for (of, _) in &self.unfulfilled_refunds {
+            let pending = if of.pays_rdg() {
+                of.tx_id_ref.as_ref().map(|id| rdg_extern_txids.contains(&id.identifier)).unwrap_or(false)
+            } else {
+                of.destination.to_bitcoin_address(network).ok()
+                    .map(|a| self.unconfirmed_btc_output_other_addresses().contains(&a))
+                    .unwrap_or(false)
+            };
+            if !pending {
+                orders.push(of.clone());
+            }
+        }
+
         orders.sort_by(|a, b| a.event_time.cmp(&b.event_time));
         orders
     }
 
+    // Refund amounts still held by the party, excluded from the liquidity offered on the curve.
+    pub fn pending_refund_amount(&self, pays_rdg: bool) -> i64 {
+        self.unfulfilled_refunds.iter()
+            .filter(|(of, _)| of.pays_rdg() == pays_rdg)
+            .map(|(of, _)| of.fulfilled_amount as i64)
+            .sum()
+    }
+
+    fn accepts_order(&self, amount: u64, is_btc: bool, time: i64) -> bool {
+        self.order_limits.as_ref().map(|l| l.accepts(amount, is_btc, time)).unwrap_or(true)
+    }
+
+    fn batching_active(&self, time: i64) -> bool {
+        self.batching.as_ref().map(|b| b.active(time)).unwrap_or(false)
+    }
+
+    fn add_to_batch(&mut self, order: BatchedOrder, time: i64) {
+        if self.batch_window_start.is_none() {
+            self.batch_window_start = Some(time);
+        }
+        self.pending_batch.push(order);
+    }
+
+    /// Settles the pending batch once its window has closed. Must be called before processing
+    /// any event past the window end so replays settle at the same point.
+    pub fn settle_batch_if_due(&mut self, time: i64) {
+        let window_end = self.batch_window_start.and_then(|start| {
+            self.batching.as_ref().map(|b| start + b.window_ms)
+        });
+        if let Some(end) = window_end {
+            if time >= end {
+                self.settle_batch(end);
+            }
+        }
+    }
+
+    fn settle_batch(&mut self, settle_time: i64) {
+        let orders = std::mem::take(&mut self.pending_batch);
+        self.batch_window_start = None;
+        // RDG per BTC, both denominated in satoshis
+        let price = self.price;
+        let btc_in: u64 = orders.iter().filter(|o| o.is_ask).map(|o| o.amount).sum();
+        let rdg_in: u64 = orders.iter().filter(|o| !o.is_ask).map(|o| o.amount).sum();
+
+        let (matched_btc, matched_rdg) = net_flows(btc_in, rdg_in, price);
+        let (residual, residual_is_ask) = if btc_in > matched_btc {
+            (btc_in - matched_btc, true)
+        } else {
+            (rdg_in.saturating_sub(matched_rdg), false)
+        };
+        let residual_fill = if residual > 0 {
+            self.bid_ask.fulfill_taker_order(residual, residual_is_ask, settle_time, None, &self.key_address)
+        } else {
+            None
+        };
+        let residual_filled = residual_fill.as_ref().map(|f| f.fulfilled_amount).unwrap_or(0);
+        let (ask_fill, bid_fill) = if residual_is_ask {
+            (matched_rdg + residual_filled, matched_btc)
+        } else {
+            (matched_rdg, matched_btc + residual_filled)
+        };
+        let curve = residual_fill.as_ref().map(|f| f.updated_curve.clone())
+            .unwrap_or(if residual_is_ask { self.bid_ask.asks.clone() } else { self.bid_ask.bids.clone() });
+
+        for o in orders {
+            // Each order receives its pro rata share of both the netted and curve filled totals
+            let (side_total, fill_total, netted_total) = if o.is_ask {
+                (btc_in, ask_fill, matched_rdg)
+            } else {
+                (rdg_in, bid_fill, matched_btc)
+            };
+            let share = o.amount as f64 / side_total as f64;
+            let fulfilled_amount = (fill_total as f64 * share) as u64;
+            if fulfilled_amount == 0 {
+                continue;
+            }
+            let of = OrderFulfillment {
+                order_amount: o.amount,
+                fulfilled_amount,
+                updated_curve: curve.clone(),
+                is_ask_fulfillment_from_external_deposit: o.is_ask,
+                event_time: settle_time,
+                tx_id_ref: o.tx_id.map(|id| ExternalTransactionId { identifier: id }),
+                destination: o.destination.clone(),
+                is_refund: false,
+                netted_amount: (netted_total as f64 * share) as u64,
+            };
+            if o.is_ask {
+                self.unfulfilled_deposits.push((of, o.event));
+            } else {
+                self.unfulfilled_withdrawals.push((of, o.event));
+            }
+        }
+        self.update_curve(residual_fill, settle_time);
+    }
+
+    fn update_curve(&mut self, event_fulfillment: Option<OrderFulfillment>, time: i64) {
+        let new_price = curve_price_after(self.price, event_fulfillment.as_ref());
+        let min_ask = get_btc_per_rdg_starting_min_ask(time);
+        let balance = self.balance_map.get(&SupportedCurrency::Redgold).unwrap_or(&(0i64)).clone() as i64
+            - self.pending_refund_amount(true);
+        let pair_balance = (self.balance_map.get(&SupportedCurrency::Bitcoin).unwrap_or(&(0i64)).clone()
+            - self.pending_refund_amount(false)).max(0) as u64;
+        self.bid_ask = BidAsk::generate_default(
+            balance, pair_balance, new_price, min_ask
+        );
+
+        // info!("New bid ask: {}", self.bid_ask.json_or());
+        // info!("New balances: {}", self.balance_map.json_or());
+        self.price = new_price;
+    }
+
+    pub fn volume_summary(&self, time: i64) -> AmmVolumeSummary {
+        let mut summary = AmmVolumeSummary::default();
+        for (of, _, _) in &self.fulfillment_history {
+            if of.is_ask_fulfillment_from_external_deposit {
+                summary.btc_deposit_volume += of.order_amount;
+                summary.rdg_fulfilled_volume += of.fulfilled_amount;
+                summary.rdg_netted_volume += of.netted_amount;
+            } else {
+                summary.rdg_deposit_volume += of.order_amount;
+                summary.btc_fulfilled_volume += of.fulfilled_amount;
+                summary.btc_netted_volume += of.netted_amount;
+            }
+            summary.num_fulfillments += 1;
+        }
+        for s in &self.sweep_history {
+            if s.currency == SupportedCurrency::Redgold {
+                summary.rdg_swept_volume += s.amount;
+            } else {
+                summary.btc_swept_volume += s.amount;
+            }
+        }
+        summary.updated_time = time;
+        summary
+    }
+
+    pub fn recent_fills(&self) -> Vec<RecentFill> {
+        let fills = self.fulfillment_history.iter()
+            .filter_map(|(of, _, _)| RecentFill::from_fulfillment(of))
+            .collect_vec();
+        let skip = fills.len().saturating_sub(RECENT_FILLS);
+        fills.into_iter().skip(skip).collect_vec()
+    }
+
     pub fn unconfirmed_identifiers(&self) -> HashSet<String> {
         let ids = self.unconfirmed_events.iter().map(|d| d.identifier())
             .collect::<HashSet<String>>();
@@ -164,12 +505,80 @@ impl PartyEvents {
             balance_map: Default::default(),
             unfulfilled_deposits: vec![],
             unfulfilled_withdrawals: vec![],
+            unfulfilled_refunds: vec![],
+            order_limits: None,
+            batching: None,
+            cold_storage: None,
+            pending_batch: vec![],
+            batch_window_start: None,
             price: price,
             bid_ask: BidAsk::generate_default(
                 0, 0, price, min_ask
             ),
             unconfirmed_events: vec![],
             fulfillment_history: vec![],
+            refund_history: vec![],
+            sweep_history: vec![],
+            snapshot_candidate: None,
+        }
+    }
+
+    /// RDG and BTC owed to unfulfilled deposits, withdrawals and refunds.
+    pub fn outstanding_obligations(&self) -> (u64, u64) {
+        let mut rdg = 0;
+        let mut btc = 0;
+        for (of, _) in self.unfulfilled_deposits.iter()
+            .chain(self.unfulfilled_withdrawals.iter())
+            .chain(self.unfulfilled_refunds.iter()) {
+            if of.pays_rdg() {
+                rdg += of.fulfilled_amount;
+            } else {
+                btc += of.fulfilled_amount;
+            }
+        }
+        (rdg, btc)
+    }
+
+    pub fn snapshot_state(&self) -> PartyEventsState {
+        PartyEventsState {
+            balances: self.balance_map.iter().map(|(c, b)| (c.clone(), *b)).sorted_by(|a, b| a.0.cmp(&b.0)).collect_vec(),
+            unfulfilled_deposits: self.unfulfilled_deposits.clone(),
+            unfulfilled_withdrawals: self.unfulfilled_withdrawals.clone(),
+            unfulfilled_refunds: self.unfulfilled_refunds.clone(),
+            pending_batch: self.pending_batch.clone(),
+            batch_window_start: self.batch_window_start,
+            price: self.price,
+            bid_ask: self.bid_ask.clone(),
+            fulfillment_history: self.fulfillment_history.clone(),
+            refund_history: self.refund_history.clone(),
+            sweep_history: self.sweep_history.clone(),
+        }
+    }
+
+    pub fn restore_state(&mut self, state: PartyEventsState) {
+        self.balance_map = state.balances.into_iter().collect();
+        self.unfulfilled_deposits = state.unfulfilled_deposits;
+        self.unfulfilled_withdrawals = state.unfulfilled_withdrawals;
+        self.unfulfilled_refunds = state.unfulfilled_refunds;
+        self.pending_batch = state.pending_batch;
+        self.batch_window_start = state.batch_window_start;
+        self.price = state.price;
+        self.bid_ask = state.bid_ask;
+        self.fulfillment_history = state.fulfillment_history;
+        self.refund_history = state.refund_history;
+        self.sweep_history = state.sweep_history;
+    }
+
+    fn capture_snapshot(&self, cutoff_time: i64, event_ids: &Vec<String>, config_hash: &Hash) -> PartyEventsSnapshot {
+        PartyEventsSnapshot {
+            party_public_key: self.party_public_key.clone(),
+            cutoff_time,
+            event_count: event_ids.len(),
+            events_hash: events_digest(event_ids.clone()),
+            config_hash: config_hash.clone(),
+            state: self.snapshot_state(),
+            created_time: current_time_millis_i64(),
+            proof: None,
         }
     }
 
@@ -185,6 +594,7 @@ impl PartyEvents {
     }
 
     async fn process_confirmed_event(&mut self, e: &AddressEvent, time: i64) -> Result<(), ErrorInfo> {
+        self.settle_batch_if_due(time);
         let ec = e.clone().clone();
         let mut event_fulfillment: Option<OrderFulfillment> = None;
         match e {
@@ -202,14 +612,31 @@ impl PartyEvents {
 
                     // Expect BTC here
                     let other_addr = t.other_address_typed().expect("addr");
-                    let fulfillment = self.bid_ask.fulfill_taker_order(
-                        t.amount, true, time, Some(t.tx_id.clone()), &other_addr
-                    );
-                    // info!("Incoming BTC tx {} Fulfillment: {}", t.json_or(), fulfillment.json_or());
-                    if let Some(fulfillment) = fulfillment {
-                        event_fulfillment = Some(fulfillment.clone());
-                        let pair = (fulfillment, ec.clone());
-                        self.unfulfilled_deposits.push(pair);
+                    if !self.accepts_order(t.amount, true, time) && t.amount < MIN_BTC_REFUND {
+                        // Too small to return after fees, absorbed into the pool balance
+                    } else if !self.accepts_order(t.amount, true, time) {
+                        let refund = OrderFulfillment::refund(
+                            t.amount, true, time, Some(t.tx_id.clone()), &other_addr
+                        );
+                        self.unfulfilled_refunds.push((refund, ec.clone()));
+                    } else if self.batching_active(time) {
+                        self.add_to_batch(BatchedOrder {
+                            amount: t.amount,
+                            is_ask: true,
+                            tx_id: Some(t.tx_id.clone()),
+                            destination: other_addr.clone(),
+                            event: ec.clone(),
+                        }, time);
+                    } else {
+                        let fulfillment = self.bid_ask.fulfill_taker_order(
+                            t.amount, true, time, Some(t.tx_id.clone()), &other_addr
+                        );
+                        // info!("Incoming BTC tx {} Fulfillment: {}", t.json_or(), fulfillment.json_or());
+                        if let Some(fulfillment) = fulfillment {
+                            event_fulfillment = Some(fulfillment.clone());
+                            let pair = (fulfillment, ec.clone());
+                            self.unfulfilled_deposits.push(pair);
+                        }
                     }
                 } else {
                     balance_sign = -1;
@@ -225,7 +652,29 @@ impl PartyEvents {
                         };
                         res
                     });
+                    let paid = paid_btc_refunds(&self.unfulfilled_refunds, t, &self.relay.node_config.network);
+                    let mut index = 0;
+                    self.unfulfilled_refunds.retain(|(of, d)| {
+                        let res = !paid.contains(&index);
+                        index += 1;
+                        if !res {
+                            self.refund_history.push((of.clone(), d.clone(), ec.clone()));
+                        }
+                        res
+                    });
                     self.remove_unconfirmed_event(&e);
+                    let cold_btc = self.cold_storage.as_ref().and_then(|c| c.btc_address.clone());
+                    if let Some(cold) = cold_btc {
+                        if t.other_address == cold || t.other_output_addresses.contains(&cold) {
+                            self.sweep_history.push(SweepEvent {
+                                time,
+                                currency: SupportedCurrency::Bitcoin,
+                                amount: t.amount,
+                                destination: cold,
+                                tx_id: t.tx_id.clone(),
+                            });
+                        }
+                    }
                     // info!("Outgoing BTC tx {}", t.json_or());
 
                 }
@@ -246,16 +695,33 @@ impl PartyEvents {
                     let is_swap = t.tx.has_swap_to_multi(&self.party_public_key, &self.relay.node_config.network);
                     if is_swap {
                         // Represents a withdrawal initiation event
-                        if let Some(addr) = t.tx.first_input_address_to_btc_address(&self.relay.node_config.network) {
-                            let addr = Address::from_bitcoin(&addr);
-                            let fulfillment = self.bid_ask.fulfill_taker_order(
-                                amount as u64, false, time, None, &addr
+                        let refund_address = t.tx.first_input_address()
+                            .filter(|_| !self.accepts_order(amount as u64, false, time));
+                        if let Some(refund_address) = refund_address {
+                            let refund = OrderFulfillment::refund(
+                                amount as u64, false, time, Some(t.tx.hash_or().hex()), &refund_address
                             );
-                            if let Some(fulfillment) = fulfillment {
-                                event_fulfillment = Some(fulfillment.clone());
-                                let pair = (fulfillment.clone(), ec.clone());
-                                self.unfulfilled_withdrawals.push(pair);
-                                // info!("Withdrawal fulfillment request for incoming RDG tx_hash: {} fulfillment {}", t.tx.hash_or(), fulfillment.json_or());
+                            self.unfulfilled_refunds.push((refund, ec.clone()));
+                        } else if let Some(addr) = t.tx.first_input_address_to_btc_address(&self.relay.node_config.network) {
+                            let addr = Address::from_bitcoin(&addr);
+                            if self.batching_active(time) {
+                                self.add_to_batch(BatchedOrder {
+                                    amount: amount as u64,
+                                    is_ask: false,
+                                    tx_id: None,
+                                    destination: addr.clone(),
+                                    event: ec.clone(),
+                                }, time);
+                            } else {
+                                let fulfillment = self.bid_ask.fulfill_taker_order(
+                                    amount as u64, false, time, None, &addr
+                                );
+                                if let Some(fulfillment) = fulfillment {
+                                    event_fulfillment = Some(fulfillment.clone());
+                                    let pair = (fulfillment.clone(), ec.clone());
+                                    self.unfulfilled_withdrawals.push(pair);
+                                    // info!("Withdrawal fulfillment request for incoming RDG tx_hash: {} fulfillment {}", t.tx.hash_or(), fulfillment.json_or());
+                                }
                             }
                         };
                     } else {
@@ -276,8 +742,28 @@ impl PartyEvents {
                             }
                             res
                         });
+                        self.unfulfilled_refunds.retain(|(of, d)| {
+                            let res = !(of.pays_rdg() && of.tx_id_ref.as_ref() == Some(tx_id));
+                            if !res {
+                                self.refund_history.push((of.clone(), d.clone(), ec.clone()));
+                            }
+                            res
+                        });
                         // info!("Outgoing RDG tx fulfillment for BTC tx_id: {} {}", tx_id.identifier.clone(), t.tx.json_or());
                     }
+                    let cold_rdg = self.cold_storage.as_ref().and_then(|c| c.rdg_address_typed());
+                    if let Some(cold) = cold_rdg {
+                        let swept = t.tx.output_amount_of(&cold);
+                        if swept > 0 {
+                            self.sweep_history.push(SweepEvent {
+                                time,
+                                currency: SupportedCurrency::Redgold,
+                                amount: swept as u64,
+                                destination: cold.render_string()?,
+                                tx_id: t.tx.hash_or().hex(),
+                            });
+                        }
+                    }
 
                 }
                 let delta = amount as i64 * balance_sign;
@@ -286,22 +772,7 @@ impl PartyEvents {
             }
         }
 
-        let new_price = if let Some(f) = event_fulfillment {
-            let p_delta = f.fulfillment_fraction();
-            self.price * (1.0 + p_delta)
-        } else {
-            self.price
-        };
-        let min_ask = get_btc_per_rdg_starting_min_ask(time);
-        let balance = self.balance_map.get(&SupportedCurrency::Redgold).unwrap_or(&(0i64)).clone() as i64;
-        let pair_balance = self.balance_map.get(&SupportedCurrency::Bitcoin).unwrap_or(&(0i64)).clone() as u64;
-        self.bid_ask = BidAsk::generate_default(
-            balance, pair_balance, new_price, min_ask
-        );
-
-        // info!("New bid ask: {}", self.bid_ask.json_or());
-        // info!("New balances: {}", self.balance_map.json_or());
-        self.price = new_price;
+        self.update_curve(event_fulfillment, time);
         Ok(())
     }
 
@@ -332,11 +803,15 @@ impl PartyEvents {
     pub async fn historical_initialize(
         pk_address: &PublicKey,
         relay: &Relay,
-        btc_wallet: &Arc<Mutex<SingleKeyBitcoinWallet>>,
+        btc: &BtcWalletSnapshot,
     ) -> RgResult<Self> {
 
 
         let mut n = Self::new(pk_address, relay);
+        let cfg = DepositWatcher::get_deposit_config(&relay.ds).await?;
+        n.order_limits = cfg.as_ref().and_then(|c| c.order_limits.clone());
+        n.batching = cfg.as_ref().and_then(|c| c.batching.clone());
+        n.cold_storage = cfg.as_ref().and_then(|c| c.cold_storage.clone());
         // transactions
 
         let seeds = relay.node_config.seeds.iter().flat_map(|s| s.public_key.clone()).collect_vec();
@@ -358,8 +833,7 @@ impl PartyEvents {
             res.push(ae);
         }
 
-        btc_wallet.lock().map_err(|e| error_info(format!("Failed to lock wallet: {}", e).as_str()))?
-            .get_all_tx()?.iter().for_each(|t| {
+        btc.transactions.iter().for_each(|t| {
             let ae = AddressEvent::External(t.clone());
             res.push(ae);
         });
@@ -371,9 +845,39 @@ impl PartyEvents {
         // info!("Watcher Processing {} events", res.len());
         // info!("Watcher Processing events {}", res.json_or());
 
+        // Resume from the stored snapshot when it's signed by the party and still covers exactly
+        // the confirmed history up to its cutoff, otherwise fall back to a full replay.
+        let config_hash = config_digest(&n.order_limits, &n.batching, &n.cold_storage);
+        let restored = relay.ds.config_store.get_typed(&PARTY_EVENTS_SNAPSHOT).await.log_error().ok().flatten()
+            .filter(|s| s.verify(pk_address).is_ok())
+            .filter(|s| s.matches_events(&res, &seeds, &config_hash));
+        let restored_cutoff = restored.as_ref().map(|s| s.cutoff_time);
+        if let Some(s) = restored {
+            counter!("redgold.amm.snapshot.restored").increment(1);
+            n.restore_state(s.state);
+        }
+
+        let snapshot_cutoff = current_time_millis_i64() - SNAPSHOT_LAG_MS;
+        let mut compacted_ids = vec![];
         for e in &res {
-            n.process_event(e).await?;
+            let time = e.time(&seeds);
+            if let Some(t) = time {
+                if t > snapshot_cutoff && n.snapshot_candidate.is_none() && !compacted_ids.is_empty() {
+                    n.snapshot_candidate = Some(n.capture_snapshot(snapshot_cutoff, &compacted_ids, &config_hash));
+                }
+                if t <= snapshot_cutoff {
+                    compacted_ids.push(e.identifier());
+                }
+            }
+            let in_snapshot = time.zip(restored_cutoff).map(|(t, c)| t <= c).unwrap_or(false);
+            if !in_snapshot {
+                n.process_event(e).await?;
+            }
         }
+        if n.snapshot_candidate.is_none() && !compacted_ids.is_empty() {
+            n.snapshot_candidate = Some(n.capture_snapshot(snapshot_cutoff, &compacted_ids, &config_hash));
+        }
+        n.settle_batch_if_due(current_time_millis_i64());
 
         Ok(n)
 
@@ -512,7 +1016,11 @@ async fn debug_events() -> RgResult<()> {
         SingleKeyBitcoinWallet::new_wallet(pk_address.clone(), NetworkEnvironment::Dev, true)
             .expect("w")));
 
-    let n = PartyEvents::historical_initialize(&pk_address, &relay, &btc_wallet).await?;
+    let snapshot = SharedBtcSnapshot::default();
+    BtcWalletSyncer::new(&relay, &btc_wallet, &snapshot).sync().await?;
+    let btc = snapshot.latest().expect("synced");
+
+    let n = PartyEvents::historical_initialize(&pk_address, &relay, &btc).await?;
 
 
     let mut txids = HashSet::new();
@@ -0,0 +1,135 @@
+use async_trait::async_trait;
+use log::info;
+use metrics::counter;
+use redgold_keys::KeyPair;
+use redgold_keys::address_external::ToBitcoinAddress;
+use redgold_keys::util::btc_wallet::SingleKeyBitcoinWallet;
+use redgold_keys::util::mnemonic_support::WordsPass;
+use redgold_schema::{error_info, RgResult};
+use redgold_schema::structs::{Address, CurrencyAmount, PublicKey};
+use crate::core::relay::Relay;
+use crate::core::stream_handlers::IntervalFold;
+use crate::core::transact::tx_builder_supports::TransactionBuilder;
+use crate::multiparty::watcher::DepositWatcher;
+use crate::node_config::NodeConfig;
+use redgold_keys::transaction_support::TransactionSupport;
+
+// Change key index holding the genesis allocation used for AMM funding in the default distribution
+const DEFAULT_FUNDING_KEY_INDEX: i64 = 14;
+
+/// Key funding the AMM with RDG, the manifest watcher funding address if one is configured.
+pub fn rdg_faucet_key(node_config: &NodeConfig) -> RgResult<Option<KeyPair>> {
+    let words = node_config.words();
+    match &node_config.genesis_manifest {
+        None => Ok(Some(words.keypair_at_change(DEFAULT_FUNDING_KEY_INDEX)?)),
+        Some(m) => {
+            if m.watcher_funding_address.is_none() {
+                return Ok(None);
+            }
+            let funding = Address::parse(m.watcher_funding_address.clone().expect("address"))?;
+            for i in 0..50 {
+                let kp = words.keypair_at_change(i)?;
+                if kp.address_typed() == funding {
+                    return Ok(Some(kp));
+                }
+            }
+            Ok(None)
+        }
+    }
+}
+
+/// Tops up the AMM party's RDG and testnet BTC whenever they fall below the configured
+/// thresholds, replacing one-off genesis funding on dev networks.
+pub struct TestLiquidityProvisioner {
+    relay: Relay,
+}
+
+impl TestLiquidityProvisioner {
+    pub fn new(relay: &Relay) -> Self {
+        Self {
+            relay: relay.clone(),
+        }
+    }
+
+    async fn top_up_rdg(&self, amm: &PublicKey) -> RgResult<()> {
+        let cfg = &self.relay.node_config.test_liquidity;
+        let amm_address = amm.address()?;
+        let balance = self.relay.ds.transaction_store.get_balance(&amm_address).await?.unwrap_or(0);
+        if balance >= CurrencyAmount::from_fractional(cfg.min_rdg)?.amount {
+            return Ok(());
+        }
+        let kp = rdg_faucet_key(&self.relay.node_config)?;
+        if kp.is_none() {
+            info!("No RDG faucet key configured for test liquidity");
+            return Ok(());
+        }
+        let kp = kp.expect("kp");
+        let source = kp.address_typed();
+        let utxos = self.relay.ds.transaction_store.utxo_for_addresses(&vec![source.clone()]).await?;
+        if utxos.is_empty() {
+            info!("RDG faucet address {} has no funds for test liquidity", source.render_string()?);
+            return Ok(());
+        }
+        let mut tb = TransactionBuilder::new(&self.relay.node_config.network);
+        tb.with_utxos(&utxos)?;
+        tb.with_output(&amm_address, &CurrencyAmount::from_fractional(cfg.rdg_top_up)?);
+        if balance == 0 {
+            // Register the faucet as the liquidity depositor on first funding
+            tb.with_stake_usd_bounds(Some(100f64), Some(1000f64), &source);
+        }
+        let mut tx = tb.build()?;
+        tx.sign(&kp)?;
+        self.relay.submit_transaction_sync(&tx).await?;
+        counter!("redgold.test_liquidity.rdg_top_up").increment(1);
+        info!("Topped up AMM {} with {} RDG", amm_address.render_string()?, cfg.rdg_top_up);
+        Ok(())
+    }
+
+    fn top_up_btc(&self, amm: &PublicKey) -> RgResult<()> {
+        let nc = &self.relay.node_config;
+        let cfg = &nc.test_liquidity;
+        if cfg.btc_faucet_words.is_none() {
+            return Ok(());
+        }
+        let amm_wallet = SingleKeyBitcoinWallet::new_wallet_backend(amm.clone(), nc.network, true, &nc.btc_backend)?;
+        if amm_wallet.get_wallet_balance()?.confirmed >= cfg.min_btc_sats {
+            return Ok(());
+        }
+        let faucet = WordsPass::new(cfg.btc_faucet_words.clone().expect("words"), None).default_kp()?;
+        let mut faucet_wallet = SingleKeyBitcoinWallet::new_wallet_backend(
+            faucet.public_key(), nc.network, true, &nc.btc_backend
+        )?;
+        let available = faucet_wallet.get_wallet_balance()?.confirmed;
+        if available < cfg.btc_top_up_sats {
+            return Err(error_info(format!(
+                "BTC faucet has {} sats, not enough for a {} sat top up", available, cfg.btc_top_up_sats
+            )));
+        }
+        let destination = amm.to_bitcoin_address(&nc.network)?;
+        let txid = faucet_wallet.send_local(destination.clone(), cfg.btc_top_up_sats, faucet.private_hex())?;
+        counter!("redgold.test_liquidity.btc_top_up").increment(1);
+        info!("Topped up AMM {} with {} sats in txid {}", destination, cfg.btc_top_up_sats, txid);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl IntervalFold for TestLiquidityProvisioner {
+    async fn interval_fold(&mut self) -> RgResult<()> {
+        if !self.relay.node_config.test_liquidity.active(&self.relay.node_config.network) {
+            return Ok(());
+        }
+        let cfg = DepositWatcher::get_deposit_config(&self.relay.ds).await?;
+        let amm = cfg.and_then(|c| c.deposit_allocations.get(0).map(|d| d.key.clone()));
+        if amm.is_none() {
+            return Ok(());
+        }
+        let amm = amm.expect("amm");
+        self.top_up_rdg(&amm).await?;
+        let relay = self.relay.clone();
+        tokio::task::spawn_blocking(move || TestLiquidityProvisioner { relay }.top_up_btc(&amm))
+            .await
+            .map_err(|e| error_info(format!("BTC top up task failed: {}", e)))??;
+        Ok(())
+    }
+}
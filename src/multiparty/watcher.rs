@@ -3,18 +3,22 @@ use async_trait::async_trait;
 use futures::TryFutureExt;
 use itertools::Itertools;
 use log::{error, info};
+use metrics::{counter, gauge, histogram};
 
 use redgold_schema::{EasyJsonDeser, error_info, ErrorInfoContext, from_hex, from_hex_ref, RgResult, SafeBytesAccess, SafeOption, structs, WithMetadataHashable};
 use redgold_schema::structs::{PartyId, Address, BytesData, CurrencyAmount, ErrorInfo, ExternalTransactionId, Hash, InitiateMultipartyKeygenRequest, LiquidityDeposit, MultipartyIdentifier, NetworkEnvironment, PublicKey, SubmitTransactionResponse, SupportedCurrency, Transaction, UtxoEntry, PartyInfo, Weighting};
 use crate::core::relay::Relay;
+use crate::core::stream_handlers;
 use crate::core::stream_handlers::IntervalFold;
-use crate::multiparty::initiate_mp;
+use crate::e2e::alert;
+use crate::multiparty::{btc_checkpoint, election, initiate_mp, party_snapshot};
 
 use serde::{Deserialize, Serialize};
+use redgold_data::config::ConfigKey;
 use redgold_data::data_store::DataStore;
 use redgold_keys::transaction_support::TransactionSupport;
 use crate::core::transact::tx_builder_supports::TransactionBuilder;
-use redgold_keys::util::btc_wallet::{ExternalTimedTransaction, SingleKeyBitcoinWallet};
+use redgold_keys::util::btc_wallet::{BtcFeePreview, ExternalTimedTransaction, SingleKeyBitcoinWallet};
 use crate::multiparty::initiate_mp::initiate_mp_keysign;
 use crate::node::Node;
 use redgold_keys::address_external::ToBitcoinAddress;
@@ -22,13 +26,21 @@ use crate::observability::logging::Loggable;
 use redgold_schema::EasyJson;
 use redgold_schema::errors::EnhanceErrorInfo;
 use crate::core::transact::tx_builder_supports::TransactionBuilderSupport;
-use crate::multiparty::party_stream::PartyEvents;
+use crate::core::transact::fee_policy::fee_estimate;
+use crate::multiparty::admission;
+use crate::multiparty::admission::AdmissionControlConfig;
+use crate::multiparty::party_stream::{AMM_RECENT_FILLS, AMM_VOLUME_SUMMARY, PartyEvents};
+use crate::multiparty::cold_sweep::ColdStorageConfig;
+use crate::multiparty::party_audit::{audit_party_events, PARTY_AUDIT};
 use crate::node_config::NodeConfig;
 use crate::scrape::coinbase_btc_spot_latest;
 use crate::util;
 use crate::util::cli::arg_parse_config::ArgTranslate;
 use crate::util::cli::args::RgArgs;
 use crate::util::current_time_millis_i64;
+use crate::multiparty::btc_sync::{BTC_SNAPSHOT_MAX_AGE_MS, BtcWalletSnapshot, BtcWalletSyncer, SharedBtcSnapshot, with_wallet};
+use crate::multiparty::bridge_reconciliation::insert_fulfillment_bridge_row;
+use crate::multiparty::watcher_validation::{record_validation, validate_watcher_state, WatcherValidation};
 
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -242,6 +254,32 @@ impl PriceVolume {
 //     }
 // }
 
+/// Shape of the generated bid / ask curves, the defaults are what the watcher runs with.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CurveConfig {
+    pub divisions: i32,
+    // Ratio between the volume of the first and last price level
+    pub scale: f64,
+}
+
+impl Default for CurveConfig {
+    fn default() -> Self {
+        Self {
+            divisions: 40,
+            scale: 20.0,
+        }
+    }
+}
+
+/// Center price after a curve fill, moved up by the fraction of the curve the fill consumed.
+pub fn curve_price_after(price: f64, fulfillment: Option<&OrderFulfillment>) -> f64 {
+    if let Some(f) = fulfillment {
+        price * (1.0 + f.fulfillment_fraction())
+    } else {
+        price
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct BidAsk{
     pub bids: Vec<PriceVolume>,
@@ -287,13 +325,29 @@ impl BidAsk {
         pair_balance: u64,
         last_exchange_price: f64,
         min_ask: f64,
+    ) -> BidAsk {
+        BidAsk::generate_with_config(
+            available_balance,
+            pair_balance,
+            last_exchange_price,
+            min_ask,
+            &CurveConfig::default()
+        )
+    }
+
+    pub fn generate_with_config(
+        available_balance: i64,
+        pair_balance: u64,
+        last_exchange_price: f64,
+        min_ask: f64,
+        curve: &CurveConfig,
     ) -> BidAsk {
         BidAsk::generate(
             available_balance,
             pair_balance,
             last_exchange_price,
-            40,
-            20.0f64,
+            curve.divisions,
+            curve.scale,
             min_ask
         )
     }
@@ -360,10 +414,42 @@ pub struct OrderFulfillment {
     pub is_ask_fulfillment_from_external_deposit: bool,
     pub event_time: i64,
     pub tx_id_ref: Option<ExternalTransactionId>,
-    pub destination: Address
+    pub destination: Address,
+    #[serde(default)]
+    pub is_refund: bool,
+    // Portion of the fulfilled amount matched against opposing orders in the same batch
+    // rather than against the curve
+    #[serde(default)]
+    pub netted_amount: u64,
 }
 
 impl OrderFulfillment {
+
+    /// Returns the deposit in full to the sender, in the same currency it arrived in.
+    pub fn refund(
+        order_amount: u64,
+        is_ask: bool,
+        event_time: i64,
+        tx_id: Option<String>,
+        destination: &Address
+    ) -> Self {
+        Self {
+            order_amount,
+            fulfilled_amount: order_amount,
+            updated_curve: vec![],
+            is_ask_fulfillment_from_external_deposit: is_ask,
+            event_time,
+            tx_id_ref: tx_id.map(|id| ExternalTransactionId{ identifier: id }),
+            destination: destination.clone(),
+            is_refund: true,
+            netted_amount: 0,
+        }
+    }
+
+    // Asks are paid out in RDG unless refunded, in which case the BTC deposit is returned
+    pub fn pays_rdg(&self) -> bool {
+        self.is_ask_fulfillment_from_external_deposit != self.is_refund
+    }
     pub fn fulfillment_price(&self) -> f64 {
         self.fulfilled_amount as f64 / self.order_amount as f64
     }
@@ -470,11 +556,72 @@ impl BidAsk {
                 event_time,
                 tx_id_ref: tx_id.map(|id| ExternalTransactionId{ identifier: id }),
                 destination: destination.clone(),
+                is_refund: false,
+                netted_amount: 0,
             })
         }
     }
 }
 
+/// Bounds on accepted order sizes, amounts in satoshis for both currencies.
+/// Deposits outside these bounds are refunded rather than absorbed into the pool.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct OrderLimits {
+    pub min_btc: Option<u64>,
+    pub max_btc: Option<u64>,
+    pub min_rdg: Option<u64>,
+    pub max_rdg: Option<u64>,
+    // Events before this time are exempt, so replaying the event stream after a limit
+    // change doesn't reclassify deposits which were already fulfilled.
+    pub active_since: i64,
+}
+
+impl OrderLimits {
+    pub fn accepts(&self, amount: u64, is_btc: bool, event_time: i64) -> bool {
+        if event_time < self.active_since {
+            return true;
+        }
+        let (min, max) = if is_btc {
+            (self.min_btc, self.max_btc)
+        } else {
+            (self.min_rdg, self.max_rdg)
+        };
+        min.map(|m| amount >= m).unwrap_or(true) && max.map(|m| amount <= m).unwrap_or(true)
+    }
+}
+
+// BTC deposits below this are absorbed into the pool rather than refunded, since the refund
+// would be mostly or entirely fee.
+pub const MIN_BTC_REFUND: u64 = 10_000;
+
+/// Splits a refund transaction's fee evenly across its outputs so depositors rather than the
+/// party pay it, dropping outputs left below dust which stay pending until fees fall.
+pub fn deduct_refund_fee(outputs: &Vec<(String, u64)>, fee: u64) -> Vec<(String, u64)> {
+    if outputs.is_empty() {
+        return vec![];
+    }
+    let share = (fee + outputs.len() as u64 - 1) / outputs.len() as u64;
+    outputs.iter()
+        .filter(|(_, amount)| *amount >= share + DUST_LIMIT)
+        .map(|(addr, amount)| (addr.clone(), amount - share))
+        .collect_vec()
+}
+
+/// Collects swap orders over a window and nets opposing flows against each other
+/// before filling any remainder against the curve.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BatchingConfig {
+    pub window_ms: i64,
+    // As with order limits, events before this time are replayed with immediate fulfillment
+    pub active_since: i64,
+}
+
+impl BatchingConfig {
+    pub fn active(&self, event_time: i64) -> bool {
+        self.window_ms > 0 && event_time >= self.active_since
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct DepositWatcherConfig {
     pub deposit_allocations: Vec<DepositKeyAllocation>,
@@ -482,10 +629,64 @@ pub struct DepositWatcherConfig {
     pub bid_ask: BidAsk,
     pub last_btc_timestamp: u64,
     pub ask_bid_code_reset: Option<bool>,
+    #[serde(default)]
+    pub order_limits: Option<OrderLimits>,
+    #[serde(default)]
+    pub batching: Option<BatchingConfig>,
+    // Fractional balance drift between the event history and chain which pauses fulfillments
+    #[serde(default)]
+    pub safe_mode_drift_threshold: Option<f64>,
+    // BTC fulfillments paying more than this many sat/vB are aborted, DEFAULT_MAX_BTC_FEE_RATE when unset
+    #[serde(default)]
+    pub max_btc_fee_rate: Option<f64>,
+    // Destination and hot balance caps for operator sweeps of party funds
+    #[serde(default)]
+    pub cold_storage: Option<ColdStorageConfig>,
+    // Fractional balance drop since the last pass tolerated by startup validation,
+    // DEFAULT_MAX_BALANCE_DROP when unset
+    #[serde(default)]
+    pub startup_max_balance_drop: Option<f64>,
+    // Backlog and fee limits above which the published order book is withdrawn
+    #[serde(default)]
+    pub admission: Option<AdmissionControlConfig>,
+}
+
+pub const DEFAULT_MAX_BTC_FEE_RATE: f64 = 50.0;
+// Number of most recent BTC fulfillments kept in the config store
+const BTC_FULFILLMENT_RECORDS: usize = 100;
+
+pub const BTC_FULFILLMENTS: ConfigKey<Vec<BtcFulfillmentRecord>> = ConfigKey::new("watcher_btc_fulfillments");
+
+/// Fee preview of a BTC fulfillment transaction taken before keysign, along with the outcome.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BtcFulfillmentRecord {
+    pub time: i64,
+    pub outputs: Vec<(String, u64)>,
+    pub preview: BtcFeePreview,
+    pub max_fee_rate: f64,
+    // Set once broadcast, None when aborted by the fee guard
+    pub txid: Option<String>,
+    pub aborted: bool,
+    // Party BTC address paying the outputs, used to write or repair the bridge row
+    #[serde(default)]
+    pub source_address: Option<String>,
 }
 
+// Number of most recent dry run passes kept in the config store
+const WATCHER_DRY_RUN_RECORDS: usize = 100;
 
+pub const WATCHER_DRY_RUNS: ConfigKey<Vec<WatcherDryRunRecord>> = ConfigKey::new("watcher_dry_runs");
 
+/// Fulfillments a dry run watcher pass would have sent, along with the prices it used.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct WatcherDryRunRecord {
+    pub time: i64,
+    pub rdg_transaction: Option<Transaction>,
+    pub btc_outputs: Vec<(String, u64)>,
+    pub btc_fee_preview: Option<BtcFeePreview>,
+    pub center_price: f64,
+    pub orders: Vec<OrderFulfillment>,
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct PriceVolumeBroken {
@@ -510,42 +711,70 @@ pub struct DepositWatcherConfigBroken {
     pub ask_bid_code_reset: Option<bool>
 }
 
+/// Seconds from each deposit event to its fulfillment being sent.
+fn record_fulfillment_latency<'a>(orders: impl Iterator<Item=&'a &'a OrderFulfillment>) {
+    let now = current_time_millis_i64();
+    for o in orders {
+        histogram!("redgold.amm.fulfillment_latency_seconds").record((now - o.event_time).max(0) as f64 / 1000.0);
+    }
+}
+
+fn price_volumes(broken: &Vec<PriceVolumeBroken>) -> Vec<PriceVolume> {
+    broken.iter().filter_map(|v| {
+        match (v.price, v.volume) {
+            (Some(price), Some(volume)) => Some(PriceVolume { price, volume }),
+            _ => None
+        }
+    }).collect()
+}
+
+// Early configs were written with optional price / volume fields, entries missing either are dropped.
+fn migrate_broken_deposit_watcher_config(value: serde_json::Value) -> RgResult<serde_json::Value> {
+    if serde_json::from_value::<DepositWatcherConfig>(value.clone()).is_ok() {
+        return Ok(value);
+    }
+    let bcfg = serde_json::from_value::<DepositWatcherConfigBroken>(value)
+        .error_info("Unrecognized deposit watcher config")?;
+    let cfg = DepositWatcherConfig {
+        deposit_allocations: bcfg.deposit_allocations,
+        bid_ask: BidAsk {
+            bids: price_volumes(&bcfg.bid_ask.bids),
+            asks: price_volumes(&bcfg.bid_ask.asks),
+            center_price: bcfg.bid_ask.center_price,
+        },
+        last_btc_timestamp: 0,
+        ask_bid_code_reset: None,
+        order_limits: None,
+        batching: None,
+        safe_mode_drift_threshold: None,
+        max_btc_fee_rate: None,
+        cold_storage: None,
+        startup_max_balance_drop: None,
+        admission: None,
+    };
+    info!("Migrated broken deposit watcher config");
+    serde_json::to_value(cfg).error_info("Failed to serialize deposit watcher config")
+}
+
+pub const DEPOSIT_WATCHER_CONFIG: ConfigKey<DepositWatcherConfig> = ConfigKey::versioned(
+    "deposit_watcher_config", &[migrate_broken_deposit_watcher_config]
+);
+
 #[derive(Clone)]
 pub struct DepositWatcher {
-    relay: Relay,
-    wallet: Vec<Arc<Mutex<SingleKeyBitcoinWallet>>>
+    pub(crate) relay: Relay,
+    wallet: Vec<Arc<Mutex<SingleKeyBitcoinWallet>>>,
+    // Set once startup validation passes, nothing is signed before then
+    validation: Option<WatcherValidation>,
+    btc_snapshot: SharedBtcSnapshot,
+    // Snapshots from syncs started before this don't include the last BTC fulfillment yet
+    last_btc_broadcast: i64,
 }
 
 impl DepositWatcher {
 
-    // Need to update this to the current test address?
-    pub async fn genesis_funding(&self, destination: &Address) -> RgResult<()> {
-        let (_, utxos) = Node::genesis_from(self.relay.node_config.clone());
-        let u = utxos.get(14).safe_get_msg("Missing utxo")?.clone();
-        let a = u.key_pair.address_typed();
-        let a_str = a.render_string()?;
-        let res = self.relay.ds.transaction_store.query_utxo_id_valid(
-            &u.utxo_entry.utxo_id()?.transaction_hash.clone().expect("hash"),
-            u.utxo_entry.utxo_id()?.output_index.clone()
-        ).await?;
-        let uu = u.utxo_entry.clone().json_or();
-        if res {
-            info!("Sending genesis funding to multiparty address from origin {a_str} using utxo {uu}");
-            let mut tb = TransactionBuilder::new(&self.relay.node_config.network);
-            tb.with_utxo(&u.utxo_entry)?;
-            tb.with_output(&destination, &CurrencyAmount::from(u.utxo_entry.amount() as i64));
-            tb.with_stake_usd_bounds(Some(100f64), Some(1000f64), &a);
-            let mut tx = tb.build()?;
-            tx.sign(&u.key_pair)?;
-            self.relay.submit_transaction_sync(&tx).await?;
-        } else {
-            info!("No genesis funding possible to send");
-        }
-        Ok(())
-    }
-
     pub async fn get_deposit_config(ds: &DataStore) -> Result<Option<DepositWatcherConfig>, ErrorInfo> {
-        ds.config_store.get_json::<DepositWatcherConfig>("deposit_watcher_config").await
+        ds.config_store.get_typed(&DEPOSIT_WATCHER_CONFIG).await
     }
 }
 
@@ -580,6 +809,9 @@ impl DepositWatcher {
         Self {
             relay,
             wallet: vec![],
+            validation: None,
+            btc_snapshot: SharedBtcSnapshot::default(),
+            last_btc_broadcast: 0,
         }
     }
     // pub fn establish_first_allocation(&self) -> RgResult<()> {
@@ -686,27 +918,134 @@ impl DepositWatcher {
     }
 
     pub async fn fulfill_btc_bids(&self, w_arc: &Arc<Mutex<SingleKeyBitcoinWallet>>,
-                                  identifier: MultipartyIdentifier, outputs: Vec<(String, u64)>) -> RgResult<String> {
-        w_arc.lock()
-            .map_err(|e| error_info(format!("Failed to lock wallet: {}", e).as_str()))?
-            .create_transaction_output_batch(outputs)?;
-        let hashes = w_arc.lock()
-            .map_err(|e| error_info(format!("Failed to lock wallet: {}", e).as_str()))?
-            .signable_hashes()?.clone();
-        for (i, (hash, hash_type)) in hashes.iter().enumerate() {
+                                  identifier: MultipartyIdentifier, outputs: Vec<(String, u64)>,
+                                  max_fee_rate: Option<f64>) -> RgResult<String> {
+        let preview = Self::btc_fee_preview(w_arc, &outputs).await?;
+        let max_fee_rate = max_fee_rate.unwrap_or(DEFAULT_MAX_BTC_FEE_RATE);
+        info!("BTC fulfillment preview vsize: {} fee: {} fee rate: {:.2} sat/vB max: {}",
+            preview.vsize, preview.fee, preview.fee_rate, max_fee_rate);
+        gauge!("redgold.amm.btc_fee_rate").set(preview.fee_rate);
+        let mut record = BtcFulfillmentRecord {
+            time: current_time_millis_i64(),
+            outputs,
+            preview: preview.clone(),
+            max_fee_rate,
+            txid: None,
+            aborted: false,
+            source_address: with_wallet(w_arc, |w| Ok(w.public_key.clone())).await?
+                .to_bitcoin_address(&self.relay.node_config.network).ok(),
+        };
+        if preview.fee_rate > max_fee_rate {
+            record.aborted = true;
+            self.record_btc_fulfillment(record).await?;
+            counter!("redgold.amm.btc_fee_guard_aborted").increment(1);
+            let msg = format!(
+                "Aborted BTC fulfillment before keysign, fee rate {:.2} sat/vB exceeds max {} with fee {} for vsize {}",
+                preview.fee_rate, max_fee_rate, preview.fee, preview.vsize
+            );
+            error!("{}", msg);
+            alert::email(format!("{} BTC fee guard", self.relay.node_config.network.to_std_string()), &msg)
+                .await.log_error().ok();
+            return Err(error_info(msg));
+        }
+        let hashes = with_wallet(w_arc, |w| w.signable_hashes()).await?;
+        for (i, (hash, hash_type)) in hashes.into_iter().enumerate() {
             let result = initiate_mp_keysign(self.relay.clone(), identifier.clone(),
-                                             BytesData::from(hash.clone()),
+                                             BytesData::from(hash),
                                              identifier.party_keys.clone(), None
             ).await?;
-            w_arc.lock()
-                .map_err(|e| error_info(format!("Failed to lock wallet: {}", e).as_str()))?
-                .affix_input_signature(i, &result.proof, hash_type);
+            with_wallet(w_arc, move |w| Ok(w.affix_input_signature(i, &result.proof, &hash_type))).await?;
+        }
+        let txid = with_wallet(w_arc, |w| {
+            w.sign()?;
+            w.broadcast_tx()?;
+            w.txid()
+        }).await?;
+        record.txid = Some(txid.clone());
+        self.record_btc_fulfillment(record.clone()).await?;
+        // A failure here leaves a broadcast fulfillment without a bridge row, which
+        // bridge reconciliation repairs from the record above.
+        insert_fulfillment_bridge_row(&self.relay.ds, &record).await.log_error().ok();
+        Ok(txid)
+    }
+
+    async fn send_btc_refunds(
+        &mut self,
+        w_arc: &Arc<Mutex<SingleKeyBitcoinWallet>>,
+        identifier: MultipartyIdentifier,
+        refunds: &Vec<(String, u64)>,
+        max_fee_rate: Option<f64>,
+    ) -> RgResult<()> {
+        let fee = Self::btc_fee_preview(w_arc, refunds).await?.fee;
+        let mut outputs = deduct_refund_fee(refunds, fee);
+        // Smaller outputs may select fewer inputs, the party stream only matches refunds whose
+        // share is within the fee the broadcast transaction actually pays
+        if !outputs.is_empty() {
+            let final_fee = Self::btc_fee_preview(w_arc, &outputs).await?.fee;
+            if final_fee < fee {
+                outputs = deduct_refund_fee(refunds, final_fee);
+            }
         }
-        let mut w = w_arc.lock()
-            .map_err(|e| error_info(format!("Failed to lock wallet: {}", e).as_str()))?;
-        w.sign()?;
-        w.broadcast_tx()?;
-        Ok(w.txid()?)
+        let skipped = refunds.len() - outputs.len();
+        if skipped > 0 {
+            counter!("redgold.amm.btc_refunds_below_dust").increment(skipped as u64);
+            info!("Skipping {} BTC refunds which would be below dust after a fee of {}", skipped, fee);
+        }
+        if outputs.is_empty() {
+            return Ok(());
+        }
+        let txid = self.fulfill_btc_bids(w_arc, identifier, outputs.clone(), max_fee_rate).await?;
+        self.last_btc_broadcast = current_time_millis_i64();
+        counter!("redgold.amm.btc_refunds").increment(outputs.len() as u64);
+        info!("Sent BTC refund transaction id {}: {:?}", txid, outputs);
+        Ok(())
+    }
+
+    async fn btc_fee_preview(w_arc: &Arc<Mutex<SingleKeyBitcoinWallet>>, outputs: &Vec<(String, u64)>) -> RgResult<BtcFeePreview> {
+        let outputs = outputs.clone();
+        with_wallet(w_arc, move |w| {
+            w.create_transaction_output_batch(outputs)?;
+            w.fee_preview()
+        }).await
+    }
+
+    /// Records the fulfillments a dry run would have sent, nothing is signed or broadcast.
+    async fn record_dry_run(
+        &self,
+        rdg_transaction: Option<Transaction>,
+        btc_outputs: Vec<(String, u64)>,
+        orders: Vec<OrderFulfillment>,
+        bid_ask: &BidAsk,
+        w_arc: &Arc<Mutex<SingleKeyBitcoinWallet>>,
+    ) -> RgResult<()> {
+        let btc_fee_preview = if btc_outputs.is_empty() {
+            None
+        } else {
+            Self::btc_fee_preview(w_arc, &btc_outputs).await.log_error().ok()
+        };
+        let record = WatcherDryRunRecord {
+            time: current_time_millis_i64(),
+            rdg_transaction,
+            btc_outputs,
+            btc_fee_preview,
+            center_price: bid_ask.center_price,
+            orders,
+        };
+        counter!("redgold.amm.dry_run_fulfillments").increment(record.orders.len() as u64);
+        info!("Dry run watcher fulfillment, not signing or broadcasting: {}", record.json_or());
+        let mut records = self.relay.ds.config_store.get_typed(&WATCHER_DRY_RUNS).await?.unwrap_or(vec![]);
+        records.push(record);
+        let excess = records.len().saturating_sub(WATCHER_DRY_RUN_RECORDS);
+        records.drain(..excess);
+        self.relay.ds.config_store.set_typed(&WATCHER_DRY_RUNS, &records).await
+    }
+
+    async fn record_btc_fulfillment(&self, record: BtcFulfillmentRecord) -> RgResult<()> {
+        let mut records = self.relay.ds.config_store.get_typed(&BTC_FULFILLMENTS).await?.unwrap_or(vec![]);
+        records.push(record);
+        let excess = records.len().saturating_sub(BTC_FULFILLMENT_RECORDS);
+        records.drain(..excess);
+        self.relay.ds.config_store.set_typed(&BTC_FULFILLMENTS, &records).await
     }
 
     pub async fn update_withdrawal_datastore(&self, withdrawals: WithdrawalBitcoin, txid: String, key_address: &structs::Address) -> RgResult<()> {
@@ -803,28 +1142,57 @@ impl DepositWatcher {
         _bid_ask_original: BidAsk,
         last_timestamp: u64,
         w: &Arc<Mutex<SingleKeyBitcoinWallet>>,
+        btc: &BtcWalletSnapshot,
+        safe_mode_drift_threshold: Option<f64>,
+        max_btc_fee_rate: Option<f64>,
+        admission: Option<&AdmissionControlConfig>,
     ) -> Result<CurveUpdateResult, ErrorInfo> {
 
         let key = &alloc.key;
         let key_address = key.address()?;
 
-        let btc_starting_balance = w.lock()
-            .map_err(|e| error_info(format!("Failed to lock wallet: {}", e).as_str()))?
-            .get_wallet_balance()?.confirmed;
+        // Orders are derived from the snapshot, so a snapshot missing recent BTC transactions could
+        // fulfill the same order twice.
+        if let Err(e) = btc.check_fresh(current_time_millis_i64(), BTC_SNAPSHOT_MAX_AGE_MS) {
+            counter!("redgold.amm.btc_snapshot_stale").increment(1);
+            return Err(e);
+        }
+        if btc.time <= self.last_btc_broadcast {
+            return Err(error_info("BTC wallet snapshot predates the last BTC fulfillment, waiting for sync"));
+        }
+        let btc_starting_balance = btc.confirmed_balance;
 
-        let ps = PartyEvents::historical_initialize(&key, &self.relay, w).await?;
+        let ps = PartyEvents::historical_initialize(&key, &self.relay, btc).await?;
         let orders = ps.orders();
+        let volume = ps.volume_summary(current_time_millis_i64());
+        gauge!("redgold.amm.volume.btc").set((volume.btc_deposit_volume + volume.btc_fulfilled_volume) as f64);
+        gauge!("redgold.amm.volume.rdg").set((volume.rdg_deposit_volume + volume.rdg_fulfilled_volume) as f64);
+        gauge!("redgold.amm.fulfillments").set(volume.num_fulfillments as f64);
+        self.relay.ds.config_store.set_typed(&AMM_VOLUME_SUMMARY, &volume).await?;
+        self.relay.ds.config_store.set_typed(&AMM_RECENT_FILLS, &ps.recent_fills()).await?;
         let cutoff_time = current_time_millis_i64() - 30_000; //
         let identifier = alloc.initiate.identifier.safe_get().cloned()?;
+        party_snapshot::sign_snapshot_if_due(&self.relay, &ps, &identifier).await.log_error().ok();
 
 
         let environment = self.relay.node_config.network.clone();
-        let btc_address = w.lock()
-            .map_err(|e| error_info(format!("Failed to lock wallet: {}", e).as_str()))?
-            .public_key.to_bitcoin_address(&environment)?;
+        let btc_address = btc.public_key.to_bitcoin_address(&environment)?;
 
         let balance = self.relay.ds.transaction_store.get_balance(&key_address).await?;
         let rdg_starting_balance: i64 = balance.safe_get_msg("Missing balance")?.clone();
+        gauge!("redgold.amm.balance.rdg").set(rdg_starting_balance as f64);
+        gauge!("redgold.amm.balance.btc").set(btc_starting_balance as f64);
+        gauge!("redgold.amm.center_price").set(ps.bid_ask.center_price);
+        gauge!("redgold.amm.unfulfilled_orders").set(orders.len() as f64);
+
+        let previous_audit = self.relay.ds.config_store.get_typed(&PARTY_AUDIT).await?;
+        let audit = audit_party_events(
+            &ps, previous_audit.as_ref(), current_time_millis_i64(),
+            rdg_starting_balance, btc_starting_balance as i64, safe_mode_drift_threshold
+        );
+        self.relay.ds.config_store.set_typed(&PARTY_AUDIT, &audit).await?;
+        admission::update_admission(&self.relay, admission, orders.len(), current_time_millis_i64())
+            .await.log_error().ok();
 
 
         let num_events = ps.events.len();
@@ -852,10 +1220,17 @@ impl DepositWatcher {
         // TODO: Change this to support batches -- might need some consideration around ids and utxos later
         // when calculating the receipts?
 
+        // Safe mode holds all orders until balances reconcile, they remain pending in the event stream
         let with_cutoff = orders.iter()
             .filter(|o| o.event_time < cutoff_time)
+            .filter(|_| !audit.safe_mode)
             .collect_vec();
 
+        let netted = with_cutoff.iter().filter(|o| o.netted_amount > 0).count();
+        if netted > 0 {
+            info!("Watcher batch contains {} netted orders of {} pending", netted, with_cutoff.len());
+        }
+
         /*
 
         tb.with_utxos(&utxos)?;
@@ -867,23 +1242,24 @@ impl DepositWatcher {
          */
         let mut tb = TransactionBuilder::new(&self.relay.node_config.network);
         tb.with_utxos(&utxos)?;
+        tb.with_auto_fee(&fee_estimate(&self.relay));
 
         let rdg_fulfillment_txb = with_cutoff.iter()
-            .filter(|e| e.is_ask_fulfillment_from_external_deposit && e.tx_id_ref.is_some())
+            .filter(|e| e.pays_rdg() && e.tx_id_ref.is_some())
             .fold(&mut tb, |tb, o| {
             tb.with_output(&o.destination, &o.fulfilled_currency_amount())
             .with_last_output_deposit_swap_fulfillment(o.tx_id_ref.clone().expect("Missing tx_id").identifier)
         });
 
-        if rdg_fulfillment_txb.transaction.outputs.len() > 0 {
-            let tx = rdg_fulfillment_txb.build()?;
-            info!("Sending RDG fulfillment transaction: {}", tx.json_or());
-            self.send_ask_fulfillment_transaction(&mut tx.clone(), identifier.clone()).await.log_error().ok();
-        }
+        let rdg_fulfillment_tx = if rdg_fulfillment_txb.transaction.outputs.len() > 0 {
+            Some(rdg_fulfillment_txb.build()?)
+        } else {
+            None
+        };
 
         let mut outputs = vec![];
         let btc_outputs = with_cutoff.iter()
-            .filter(|e| !e.is_ask_fulfillment_from_external_deposit &&
+            .filter(|e| !e.pays_rdg() && !e.is_refund &&
             e.destination.to_bitcoin_address(&self.relay.node_config.network).is_ok())
             .fold(&mut outputs, |vec, o| {
                 let btc = o.destination.to_bitcoin_address(&self.relay.node_config.network).expect("works");
@@ -893,9 +1269,50 @@ impl DepositWatcher {
                 vec
             });
 
-        if btc_outputs.len() > 0 {
-            let txid = self.fulfill_btc_bids(w, identifier.clone(), btc_outputs.clone()).await.log_error().ok();
-            info!("Sending BTC fulfillment transaction id {}: {:?}", txid.json_or(), btc_outputs);
+        // Refunds go in their own transaction so a problem with one can't hold up fills
+        let btc_refunds = with_cutoff.iter()
+            .filter(|e| !e.pays_rdg() && e.is_refund)
+            .filter_map(|o| o.destination.to_bitcoin_address(&self.relay.node_config.network).ok()
+                .map(|btc| (btc, o.fulfilled_amount)))
+            .collect_vec();
+
+        if self.relay.node_config.features.amm_dry_run {
+            if rdg_fulfillment_tx.is_some() || btc_outputs.len() > 0 || btc_refunds.len() > 0 {
+                let orders = with_cutoff.iter().map(|o| (*o).clone()).collect_vec();
+                let mut all_btc = btc_outputs.clone();
+                all_btc.extend(btc_refunds.clone());
+                self.record_dry_run(rdg_fulfillment_tx, all_btc, orders, &ps.bid_ask, w)
+                    .await.log_error().ok();
+            }
+        } else {
+            if let Some(tx) = rdg_fulfillment_tx {
+                info!("Sending RDG fulfillment transaction: {}", tx.json_or());
+                let sent = self.send_ask_fulfillment_transaction(&mut tx.clone(), identifier.clone()).await.log_error().is_ok();
+                if sent {
+                    record_fulfillment_latency(with_cutoff.iter().filter(|o| o.pays_rdg() && o.tx_id_ref.is_some()));
+                }
+            }
+            // RDG fulfillment keysigning may have taken long enough for the snapshot to go stale
+            let btc_fresh = btc.check_fresh(current_time_millis_i64(), BTC_SNAPSHOT_MAX_AGE_MS)
+                .log_error().is_ok();
+            if btc_outputs.len() > 0 && !btc_fresh {
+                counter!("redgold.amm.btc_snapshot_stale").increment(1);
+            }
+            if btc_outputs.len() > 0 && btc_fresh {
+                let txid = self.fulfill_btc_bids(w, identifier.clone(), btc_outputs.clone(), max_btc_fee_rate)
+                    .await.log_error().ok();
+                info!("Sending BTC fulfillment transaction id {}: {:?}", txid.json_or(), btc_outputs);
+                if txid.is_some() {
+                    self.last_btc_broadcast = current_time_millis_i64();
+                    record_fulfillment_latency(with_cutoff.iter().filter(|o| !o.pays_rdg() && !o.is_refund &&
+                        o.destination.to_bitcoin_address(&self.relay.node_config.network).is_ok()));
+                }
+            }
+            // Only sent once the wallet has synced past any fill broadcast, so both can't select the
+            // same inputs.
+            if btc_refunds.len() > 0 && btc_fresh && btc.time > self.last_btc_broadcast {
+                self.send_btc_refunds(w, identifier.clone(), &btc_refunds, max_btc_fee_rate).await.log_error().ok();
+            }
         }
         let mut alloc2 = alloc.clone();
         alloc2.balance_btc = btc_starting_balance;
@@ -1065,54 +1482,6 @@ impl DepositWatcher {
 
     }
 
-    pub async fn fix_historical_errors(&self) -> RgResult<()> {
-        let ds = self.relay.ds.clone();
-
-        let test_load = ds.config_store.get_json::<DepositWatcherConfig>("deposit_watcher_config").await;
-
-        // First broken json error
-        if test_load.is_err() {
-            let broken_cfg = ds.config_store.get_json::<DepositWatcherConfigBroken>("deposit_watcher_config").await;
-            if let Ok(Some(bcfg)) = broken_cfg {
-                let ba = bcfg.bid_ask;
-                let new_bid_ask = BidAsk {
-                    bids: ba.bids.iter().filter_map(|v| {
-                        if let Some(p) = v.price {
-                            if let Some(v) = v.volume {
-                                Some(PriceVolume { price: p, volume: v })
-                            } else {
-                                None
-                            }
-                        } else {
-                            None
-                        }
-                    }).collect::<Vec<PriceVolume>>(),
-                    asks: ba.asks.iter().filter_map(|v| {
-                        if let Some(p) = v.price {
-                            if let Some(v) = v.volume {
-                                Some(PriceVolume { price: p, volume: v })
-                            } else {
-                                None
-                            }
-                        } else {
-                            None
-                        }
-                    }).collect::<Vec<PriceVolume>>(),
-                    center_price: Self::get_starting_center_price_rdg_btc_fallback().await,
-                };
-                let new_cfg = DepositWatcherConfig {
-                    deposit_allocations: bcfg.deposit_allocations,
-                    bid_ask: new_bid_ask,
-                    last_btc_timestamp: 0,
-                    ask_bid_code_reset: None,
-                };
-                ds.config_store.insert_update_json("deposit_watcher_config", new_cfg).await?;
-                info!("Updated broken deposit watcher config");
-            };
-        }
-        Ok(())
-    }
-
 }
 
 
@@ -1124,7 +1493,7 @@ impl IntervalFold for DepositWatcher {
 
         // info!("Deposit watcher interval fold complete");
 
-        if self.relay.node_config.is_local_debug() {
+        if !self.relay.node_config.features.amm_watcher {
             return Ok(())
         }
 
@@ -1132,10 +1501,6 @@ impl IntervalFold for DepositWatcher {
         // TODO: Change to query to include trust information re: deposit score
         // How best to represent this to user? As trustData?
         let _nodes = ds.peer_store.active_nodes(None).await?;
-        self.fix_historical_errors().await.log_error().ok();
-
-        // Fund from genesis for test purposes
-        // self.genesis_funding().await?;
 
         // let kp = initiate_mp::find_multiparty_key_pairs(self.relay.clone()).await;
         // match kp {
@@ -1152,7 +1517,7 @@ impl IntervalFold for DepositWatcher {
             //     info!("Regenerating starting price due to code reset");
             //     cfg.bid_ask = cfg.bid_ask.regenerate(self.get_starting_center_price_rdg_btc().await);
             //     cfg.ask_bid_code_reset = Some(true);
-            //     ds.config_store.insert_update_json("deposit_watcher_config", cfg.clone()).await?;
+            //     ds.config_store.set_typed(&DEPOSIT_WATCHER_CONFIG, &cfg).await?;
             // }
 
             // Check to see if other nodes are dead / not responding, if so, move the thing.
@@ -1160,17 +1525,35 @@ impl IntervalFold for DepositWatcher {
             // we'll need a guide saying to send from a single account
             if let Some(d) = cfg.deposit_allocations.get(0) {
                 self.relay.add_party_id(&d.party_id()?).await?;
+                election::broadcast_heartbeat(&self.relay, d).await.log_error().ok();
                 // info!("Watcher checking deposit allocation pubkey hex: {}", d.key.hex()?);
                 if self.wallet.get(0).is_none() {
-                    let key = &d.key;
-                    let w = SingleKeyBitcoinWallet::new_wallet(key.clone(), self.relay.node_config.network, true)?;
-                    self.wallet.push(Arc::new(Mutex::new(w)));
+                    let w = Arc::new(Mutex::new(btc_checkpoint::checkpointed_wallet(&self.relay.node_config, &d.key)?));
+                    self.wallet.push(w.clone());
+                    let syncer = BtcWalletSyncer::new(&self.relay, &w, &self.btc_snapshot);
+                    stream_handlers::run_interval_fold(syncer, self.relay.node_config.btc_sync_interval, true).await;
                 }
+                let btc = match self.btc_snapshot.latest() {
+                    Some(btc) => btc,
+                    None => {
+                        info!("Waiting for initial BTC wallet sync");
+                        return Ok(())
+                    }
+                };
                 let w = self.wallet.get(0).cloned();
                 if let Some(w) = w {
-                    let btc_starting_balance = w.lock()
-                        .map_err(|e| error_info(format!("Failed to lock wallet: {}", e).as_str()))?
-                        .get_wallet_balance()?.confirmed;
+                    if !self.validation.as_ref().map(|v| !v.safe_mode).unwrap_or(false) {
+                        let validation = validate_watcher_state(
+                            &self.relay, d, &btc, cfg.startup_max_balance_drop
+                        ).await?;
+                        record_validation(&self.relay, &validation, self.validation.as_ref()).await?;
+                        let safe_mode = validation.safe_mode;
+                        self.validation = Some(validation);
+                        if safe_mode {
+                            return Ok(())
+                        }
+                    }
+                    let btc_starting_balance = btc.confirmed_balance;
 
                     let balance = self.relay.ds.transaction_store.get_balance(&d.key.address()?).await?;
                     if balance.map(|x| x > 0).unwrap_or(false) { // && btc_starting_balance > 3500 {
@@ -1181,17 +1564,22 @@ impl IntervalFold for DepositWatcher {
                             let min_ask = 1f64 / center_price;
                             cfg.bid_ask = cfg.bid_ask.regenerate(center_price, min_ask);
                             cfg.ask_bid_code_reset = Some(!reset_condition);
-                            ds.config_store.insert_update_json("deposit_watcher_config", cfg.clone()).await?;
+                            ds.config_store.set_typed(&DEPOSIT_WATCHER_CONFIG, &cfg).await?;
+                        }
+                        if !election::is_fulfillment_leader(&self.relay, d, current_time_millis_i64())? {
+                            info!("Not elected watcher leader for this slot, skipping fulfillment");
+                            return Ok(())
                         }
                         let update_result = self.process_requests_new(
-                            d, cfg.bid_ask.clone(), cfg.last_btc_timestamp, &w
+                            d, cfg.bid_ask.clone(), cfg.last_btc_timestamp, &w, &btc, cfg.safe_mode_drift_threshold,
+                            cfg.max_btc_fee_rate, cfg.admission.as_ref()
                         ).await;
                         if let Ok(update_result) = &update_result {
                             let mut cfg2 = cfg.clone();
                             cfg2.last_btc_timestamp = update_result.updated_btc_timestamp;
                             cfg2.bid_ask = update_result.updated_bid_ask.clone();
                             cfg2.deposit_allocations = vec![update_result.updated_allocation.clone()];
-                            ds.config_store.insert_update_json("deposit_watcher_config", cfg2).await?;
+                            ds.config_store.set_typed(&DEPOSIT_WATCHER_CONFIG, &cfg2).await?;
                         } else if let Err(e) = update_result {
                             error!("Error processing requests: {}", e.json_or());
                         }
@@ -1250,10 +1638,15 @@ impl IntervalFold for DepositWatcher {
                         bid_ask: BidAsk { bids: vec![], asks: vec![], center_price: Self::get_starting_center_price_rdg_btc_fallback().await },
                         last_btc_timestamp: 0,
                         ask_bid_code_reset: None,
+                        order_limits: None,
+                        batching: None,
+                        safe_mode_drift_threshold: None,
+                        max_btc_fee_rate: None,
+                        cold_storage: None,
+                        startup_max_balance_drop: None,
+                        admission: None,
                     };
-                    self.genesis_funding(&pk.address()?)
-                        .await.add("Genesis watcher funding error").log_error().ok();
-                    ds.config_store.insert_update_json("deposit_watcher_config", cfg).await?;
+                    ds.config_store.set_typed(&DEPOSIT_WATCHER_CONFIG, &cfg).await?;
                 }
             }
             // self.relay.broadcast_async(nodes, req)
@@ -1315,6 +1708,46 @@ fn test_json() {
     assert_eq!(t2.some, "yo".to_string());
     assert_eq!(t2.other, None);
 }
+
+#[test]
+fn order_limits_bounds() {
+    let limits = OrderLimits {
+        min_btc: Some(10_000),
+        max_btc: Some(1_000_000),
+        min_rdg: None,
+        max_rdg: Some(5_000),
+        active_since: 100,
+    };
+    assert!(limits.accepts(10_000, true, 200));
+    assert!(!limits.accepts(9_999, true, 200));
+    assert!(!limits.accepts(1_000_001, true, 200));
+    assert!(limits.accepts(1, false, 200));
+    assert!(!limits.accepts(5_001, false, 200));
+    // Historical events are unaffected
+    assert!(limits.accepts(1, true, 50));
+}
+
+#[test]
+fn refund_fee_is_split_across_outputs() {
+    let outputs = vec![("a".to_string(), 20_000), ("b".to_string(), 3_000)];
+    assert_eq!(deduct_refund_fee(&outputs, 1_001), vec![("a".to_string(), 19_499)]);
+    assert_eq!(deduct_refund_fee(&outputs, 0), outputs);
+    assert!(deduct_refund_fee(&vec![], 1_000).is_empty());
+}
+
+#[test]
+fn broken_deposit_watcher_config_fixture_migrates() {
+    let raw = include_str!("../resources/fixtures/deposit_watcher_config_broken.json");
+    let (cfg, version) = DEPOSIT_WATCHER_CONFIG.decode(raw).expect("migrates");
+    assert_eq!(version, 0);
+    assert_eq!(cfg.bid_ask.bids.len(), 1);
+    assert!(cfg.bid_ask.asks.is_empty());
+    assert_eq!(cfg.bid_ask.center_price, 0.0023);
+    let (reloaded, version) = DEPOSIT_WATCHER_CONFIG.decode(&DEPOSIT_WATCHER_CONFIG.encode(&cfg).expect("encode"))
+        .expect("reload");
+    assert_eq!(version, DEPOSIT_WATCHER_CONFIG.version);
+    assert_eq!(reloaded.bid_ask.bids.len(), 1);
+}
 //
 // #[ignore]
 // #[tokio::test]
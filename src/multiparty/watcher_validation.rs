@@ -0,0 +1,140 @@
+use log::{error, info};
+use metrics::counter;
+use serde::{Deserialize, Serialize};
+use redgold_data::config::ConfigKey;
+use redgold_keys::address_external::ToBitcoinAddress;
+use redgold_schema::{EasyJson, RgResult, SafeOption};
+use crate::core::relay::Relay;
+use crate::e2e::alert;
+use crate::multiparty::btc_sync::BtcWalletSnapshot;
+use crate::multiparty::party_audit::PartyAudit;
+use crate::multiparty::party_audit::PARTY_AUDIT;
+use crate::multiparty::watcher::DepositKeyAllocation;
+use crate::observability::logging::Loggable;
+use crate::util::current_time_millis_i64;
+
+pub const WATCHER_VALIDATION: ConfigKey<WatcherValidation> = ConfigKey::new("watcher_startup_validation");
+// Balances recorded on the allocation are from the start of the last pass, so a fulfillment
+// batch sent after that legitimately lowers the actual balance. Larger drops mean funds moved
+// outside the watcher.
+pub const DEFAULT_MAX_BALANCE_DROP: f64 = 0.25;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ValidationCheck {
+    pub name: String,
+    pub passed: bool,
+    pub detail: String,
+}
+
+impl ValidationCheck {
+    fn new(name: &str, passed: bool, detail: impl Into<String>) -> Self {
+        Self { name: name.to_string(), passed, detail: detail.into() }
+    }
+}
+
+/// Checks of the stored watcher config against the local key shares, wallet and chain balances.
+/// While any check fails the watcher stays in a read only safe mode and signs nothing.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct WatcherValidation {
+    pub time: i64,
+    pub checks: Vec<ValidationCheck>,
+    pub safe_mode: bool,
+}
+
+impl WatcherValidation {
+    pub fn new(time: i64, checks: Vec<ValidationCheck>) -> Self {
+        let safe_mode = checks.iter().any(|c| !c.passed);
+        Self { time, checks, safe_mode }
+    }
+
+    pub fn failures(&self) -> Vec<&ValidationCheck> {
+        self.checks.iter().filter(|c| !c.passed).collect()
+    }
+}
+
+/// Startup validation and the latest balance audit, served to operators and monitoring.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct WatcherStatusResponse {
+    pub validation: Option<WatcherValidation>,
+    pub audit: Option<PartyAudit>,
+}
+
+pub fn check_balance_range(name: &str, recorded: u64, actual: u64, max_drop: f64) -> ValidationCheck {
+    // Nothing recorded yet on a newly formed party
+    if recorded == 0 {
+        return ValidationCheck::new(name, true, format!("actual {} with no recorded balance", actual));
+    }
+    let floor = (recorded as f64) * (1.0 - max_drop);
+    let passed = (actual as f64) >= floor;
+    ValidationCheck::new(name, passed, format!("recorded {} actual {} minimum {:.0}", recorded, actual, floor))
+}
+
+pub async fn validate_watcher_state(
+    relay: &Relay,
+    alloc: &DepositKeyAllocation,
+    btc: &BtcWalletSnapshot,
+    max_balance_drop: Option<f64>,
+) -> RgResult<WatcherValidation> {
+    let max_drop = max_balance_drop.unwrap_or(DEFAULT_MAX_BALANCE_DROP);
+    let network = relay.node_config.network.clone();
+    let mut checks = vec![];
+
+    let identifier = alloc.initiate.identifier.safe_get_msg("Missing multiparty identifier")?;
+    let share = relay.ds.multiparty_store.local_share_and_initiate(identifier.uuid.clone()).await?;
+    checks.push(ValidationCheck::new(
+        "party_key_share", share.is_some(), format!("local share for party {}", identifier.uuid)
+    ));
+    let member = identifier.party_keys.contains(&relay.node_config.public_key());
+    checks.push(ValidationCheck::new("party_member", member, "node key is a party member"));
+
+    let expected_btc_address = alloc.key.to_bitcoin_address(&network)?;
+    let wallet_btc_address = btc.public_key.to_bitcoin_address(&network)?;
+    checks.push(ValidationCheck::new(
+        "btc_wallet_address",
+        expected_btc_address == wallet_btc_address,
+        format!("expected {} wallet {}", expected_btc_address, wallet_btc_address)
+    ));
+
+    let actual_btc = btc.confirmed_balance;
+    checks.push(check_balance_range("btc_balance", alloc.balance_btc, actual_btc, max_drop));
+
+    let actual_rdg = relay.ds.transaction_store.get_balance(&alloc.key.address()?).await?.unwrap_or(0);
+    checks.push(check_balance_range("rdg_balance", alloc.balance_rdg, actual_rdg.max(0) as u64, max_drop));
+
+    Ok(WatcherValidation::new(current_time_millis_i64(), checks))
+}
+
+/// Stores the validation for the status endpoint, alerting on entering safe mode.
+pub async fn record_validation(relay: &Relay, validation: &WatcherValidation, previous: Option<&WatcherValidation>) -> RgResult<()> {
+    relay.ds.config_store.set_typed(&WATCHER_VALIDATION, validation).await?;
+    if !validation.safe_mode {
+        info!("Watcher startup validation passed");
+        return Ok(());
+    }
+    let msg = format!("Watcher startup validation failed, holding in safe mode: {}", validation.failures().json_or());
+    error!("{}", msg);
+    if !previous.map(|p| p.safe_mode).unwrap_or(false) {
+        counter!("redgold.amm.startup_validation_failed").increment(1);
+        alert::email(format!("{} watcher safe mode", relay.node_config.network.to_std_string()), &msg)
+            .await.log_error().ok();
+    }
+    Ok(())
+}
+
+pub async fn watcher_status(relay: &Relay) -> RgResult<WatcherStatusResponse> {
+    Ok(WatcherStatusResponse {
+        validation: relay.ds.config_store.get_typed(&WATCHER_VALIDATION).await?,
+        audit: relay.ds.config_store.get_typed(&PARTY_AUDIT).await?,
+    })
+}
+
+#[test]
+fn balance_range_allows_growth_and_small_drops() {
+    assert!(check_balance_range("b", 0, 10, 0.25).passed);
+    assert!(check_balance_range("b", 100, 500, 0.25).passed);
+    assert!(check_balance_range("b", 100, 80, 0.25).passed);
+    assert!(!check_balance_range("b", 100, 70, 0.25).passed);
+    let v = WatcherValidation::new(0, vec![check_balance_range("b", 100, 70, 0.25)]);
+    assert!(v.safe_mode);
+    assert_eq!(v.failures().len(), 1);
+}
@@ -32,7 +32,7 @@ use crate::core::process_transaction::TransactionProcessContext;
 use crate::core::relay::Relay;
 use redgold_data::data_store::DataStore;
 use crate::data::download;
-use crate::genesis::{create_test_genesis_transaction, genesis_transaction, genesis_tx_from, GenesisDistribution};
+use crate::genesis::{create_test_genesis_transaction, genesis_transaction_config, genesis_tx_from, GenesisDistribution};
 use crate::node_config::NodeConfig;
 use crate::schema::structs::{ ControlRequest, ErrorInfo, NodeState};
 use crate::schema::{ProtoHashable, WithMetadataHashable};
@@ -58,6 +58,16 @@ use crate::core::internal_message::SendErrorInfo;
 use crate::core::recent_download::RecentDownload;
 use crate::core::stream_handlers::IntervalFold;
 use crate::core::transact::contention_conflicts::ContentionConflictManager;
+use crate::observability::node_gauges::NodeStatusGauges;
+use crate::core::seed_mode::SeedPeerScoring;
+use crate::core::seed_list::SeedListRefresh;
+use crate::core::rebroadcast::Rebroadcaster;
+use crate::core::gossip_scheduler::GossipScheduler;
+use crate::core::balance_snapshots::BalanceSnapshots;
+use crate::core::retention::DataRetention;
+use crate::core::checkpoint;
+use crate::core::checkpoint::StateCheckpoints;
+use crate::multiparty::test_liquidity::TestLiquidityProvisioner;
 use crate::multiparty::initiate_mp::default_room_id_signing;
 use crate::multiparty::watcher::DepositWatcher;
 use crate::observability::dynamic_prometheus::update_prometheus_configs;
@@ -159,7 +169,7 @@ impl Node {
         // });
         // join_handles.push(amh);
         let c_config = relay.clone();
-        if node_config.e2e_enabled {
+        if node_config.features.live_e2e {
             // TODO: Distinguish errors here
             let _cwh = tokio::spawn(e2e::run(c_config));
             // join_handles.push(cwh);
@@ -192,6 +202,14 @@ impl Node {
             crate::core::mempool::Mempool::new(&relay), relay.node_config.mempool.interval.clone(), false
         ).await);
 
+        join_handles.push(stream_handlers::run_interval_fold(
+            Rebroadcaster::new(&relay), relay.node_config.rebroadcast.interval.clone(), false
+        ).await);
+
+        join_handles.push(stream_handlers::run_interval_fold(
+            GossipScheduler::new(&relay), relay.node_config.gossip_scheduler.interval.clone(), false
+        ).await);
+
         for i in 0..relay.node_config.contract.bucket_parallelism {
             let opt_c = relay.contract_state_manager_channels.get(i);
             let c = opt_c.expect("bucket partition creation error");
@@ -230,6 +248,54 @@ impl Node {
             }, Duration::from_secs(60), false
         ).await);
 
+        join_handles.push(stream_handlers::run_interval_fold(
+            NodeStatusGauges::new(&relay), Duration::from_secs(30), false
+        ).await);
+
+        join_handles.push(stream_handlers::run_interval_fold(
+            BalanceSnapshots {
+                relay: relay.clone(),
+            }, Duration::from_secs(3600), false
+        ).await);
+
+        if relay.node_config.checkpoint.enabled {
+            join_handles.push(stream_handlers::run_interval_fold(
+                StateCheckpoints {
+                    relay: relay.clone(),
+                }, Duration::from_secs(300), false
+            ).await);
+        }
+
+        if relay.node_config.retention.enabled() {
+            join_handles.push(stream_handlers::run_interval_fold(
+                DataRetention {
+                    relay: relay.clone(),
+                }, relay.node_config.retention.interval.clone(), false
+            ).await);
+        }
+
+        if relay.node_config.test_liquidity.active(&relay.node_config.network) {
+            join_handles.push(stream_handlers::run_interval_fold(
+                TestLiquidityProvisioner::new(&relay), relay.node_config.test_liquidity.interval.clone(), false
+            ).await);
+        }
+
+        if relay.node_config.seed_list.enabled() {
+            join_handles.push(stream_handlers::run_interval_fold(
+                SeedListRefresh {
+                    relay: relay.clone(),
+                }, relay.node_config.seed_list.refresh_interval.clone(), false
+            ).await);
+        }
+
+        if relay.node_config.seed_mode {
+            join_handles.push(stream_handlers::run_interval_fold(
+                SeedPeerScoring {
+                    relay: relay.clone(),
+                }, Duration::from_secs(300), false
+            ).await);
+        }
+
 
         join_handles
     }
@@ -248,6 +314,7 @@ impl Node {
         relay.ds.count_gauges().await?;
 
         relay.ds.check_consistency_apply_fixes().await?;
+        checkpoint::verify_on_startup(&relay).await?;
 
         Ok(())
     }
@@ -264,20 +331,23 @@ impl Node {
     }
 
     pub fn genesis_from(node_config: NodeConfig) -> (Transaction, Vec<SpendableUTXO>) {
-        let tx = genesis_transaction(&node_config.network, &node_config.words(), &node_config.seeds);
+        let tx = genesis_transaction_config(&node_config).expect("genesis transaction");
         let outputs = tx.utxo_outputs().expect("utxos");
         let mut res = vec![];
+        // Manifest distributions may allocate to only some (or none) of the node's change keys
         for i in 0..50 {
             let kp = node_config.words().keypair_at_change(i).expect("works");
             let address = kp.address_typed();
             let o = outputs.iter().find(|o| {
                 address == o.address().as_ref().expect("a").clone().clone()
-            }).expect("found");
-            let s = SpendableUTXO {
-                utxo_entry: o.clone(),
-                key_pair: kp,
-            };
-            res.push(s);
+            });
+            if let Some(o) = o {
+                let s = SpendableUTXO {
+                    utxo_entry: o.clone(),
+                    key_pair: kp,
+                };
+                res.push(s);
+            }
         }
         (tx, res)
     }
@@ -372,7 +442,7 @@ impl Node {
         if existing.is_none() {
             counter!("redgold.node.genesis_created").increment(1);
             info!("No genesis transaction found, generating new one");
-            let tx = genesis_transaction(&node_config.network, &node_config.words(), &node_config.seeds);
+            let tx = genesis_transaction_config(node_config)?;
             // let tx = Node::genesis_from(node_config.clone()).0;
             // runtimes.auxiliary.block_on(
             relay.ds.config_store.store_proto("genesis", tx.clone()).await?;
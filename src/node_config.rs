@@ -1,5 +1,7 @@
+use std::collections::HashMap;
 use std::fs;
 use std::hash::Hash;
+use redgold_data::config::ConfigKey;
 use redgold_data::data_store::DataStore;
 use crate::{genesis, util};
 use crate::schema::structs::{Block, NetworkEnvironment, Transaction};
@@ -11,14 +13,19 @@ use log::info;
 use redgold_keys::KeyPair;
 use redgold_keys::transaction_support::TransactionSupport;
 use redgold_schema::servers::Server;
-use redgold_schema::{ErrorInfoContext, RgResult, ShortString, structs};
+use redgold_schema::{error_info, ErrorInfoContext, RgResult, ShortString, structs};
 use redgold_schema::structs::{Address, DynamicNodeMetadata, ErrorInfo, NodeMetadata, NodeType, PeerId, PeerMetadata, PublicKey, Seed, TransportInfo, TrustData, VersionInfo};
-use crate::core::transact::tx_builder_supports::TransactionBuilder;
+use crate::api::access::ApiAccessConfig;
+use crate::util::secrets::SecretsConfig;
+use crate::core::internal_message::{ChannelConfig, OverflowPolicy};
+use crate::core::transact::tx_builder_supports::{CoinSelectionStrategy, TransactionBuilder};
 use redgold_schema::util::merkle;
 use redgold_schema::util::merkle::MerkleTree;
+use redgold_keys::util::btc_backend::BitcoinBackendConfig;
 use redgold_keys::util::mnemonic_support::WordsPass;
 use redgold_schema::seeds::{get_seeds_by_env, get_seeds_by_env_time};
 use crate::api::public_api::PublicClient;
+use redgold_sdk_client::RedgoldClient;
 use crate::util::cli::args::RgArgs;
 use crate::util::cli::commands;
 use crate::util::cli::data_folder::{DataFolder, EnvDataFolder};
@@ -26,6 +33,7 @@ use redgold_schema::util::lang_util::JsonCombineResult;
 use crate::core::transact::tx_builder_supports::TransactionBuilderSupport;
 use crate::util::cli::arg_parse_config::ArgTranslate;
 use crate::observability::logging::Loggable;
+use crate::genesis::GenesisManifest;
 
 pub struct CanaryConfig {}
 
@@ -69,6 +77,7 @@ impl Default for TransactionProcessingConfig {
         Self {
             channel_bound: 1000,
             concurrency: 100,
+            signature_verify_parallelism: 0,
         }
     }
 }
@@ -76,12 +85,15 @@ impl Default for TransactionProcessingConfig {
 pub struct TransactionProcessingConfig {
     pub channel_bound: usize,
     pub concurrency: usize,
+    // Threads used to verify transaction signatures, 0 for one per core and 1 for sequential
+    pub signature_verify_parallelism: usize,
 }
 
 impl Default for ObservationConfig {
     fn default() -> Self {
         Self {
-            channel_bound: 1000
+            channel_bound: 1000,
+            acceptance_weight_threshold: 10.0,
         }
     }
 }
@@ -89,6 +101,20 @@ impl Default for ObservationConfig {
 #[derive(Clone, Debug)]
 pub struct ObservationConfig {
     pub channel_bound: usize,
+    // Summed trust of distinct nodes that accepted a transaction, on the explorer's 0-10 trust
+    // scale, required before it's reported as finalized. Defaults to one fully trusted node.
+    pub acceptance_weight_threshold: f64,
+}
+
+impl ObservationConfig {
+    /// Total weight of accepting nodes, counting each node once.
+    pub fn acceptance_weight(accepted: &Vec<(String, f64)>) -> f64 {
+        accepted.iter().unique_by(|(node, _)| node.clone()).map(|(_, trust)| *trust).sum()
+    }
+
+    pub fn is_finalized(&self, weight: f64) -> bool {
+        weight >= self.acceptance_weight_threshold
+    }
 }
 
 impl Default for ContractConfig {
@@ -127,6 +153,397 @@ pub struct ContentionConfig {
     pub interval: Duration
 }
 
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            max_age: None,
+            max_size_bytes: None,
+            interval: Duration::from_secs(60 * 60 * 6),
+        }
+    }
+}
+
+/// Pruning of historical datastore records, disabled unless an age or size limit is set.
+#[derive(Clone, Debug)]
+pub struct RetentionConfig {
+    pub max_age: Option<Duration>,
+    pub max_size_bytes: Option<u64>,
+    pub interval: Duration,
+}
+
+impl RetentionConfig {
+    pub fn enabled(&self) -> bool {
+        self.max_age.is_some() || self.max_size_bytes.is_some()
+    }
+}
+
+impl Default for DiskSpaceConfig {
+    fn default() -> Self {
+        Self {
+            min_free_bytes: 512 * 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct DiskSpaceConfig {
+    // Below this much free space on the datastore's disk, non-essential writes are paused
+    pub min_free_bytes: u64,
+}
+
+impl Default for CheckpointConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval: Duration::from_secs(3600),
+            finalization_lag: Duration::from_secs(600),
+            keep: 168,
+            peer_signatures: 3,
+            from_checkpoint: false,
+        }
+    }
+}
+
+/// Periodic signed state checkpoints, boundaries are aligned to the interval so peers
+/// checkpoint the same history and can cross-sign each other's roots.
+#[derive(Clone, Debug)]
+pub struct CheckpointConfig {
+    pub enabled: bool,
+    pub interval: Duration,
+    // Transactions newer than this may still be settling and are left to the next checkpoint
+    pub finalization_lag: Duration,
+    // Number of most recent checkpoints retained
+    pub keep: usize,
+    // Peer signatures collected per checkpoint, 0 disables cross-signing
+    pub peer_signatures: usize,
+    // Only verify history after the latest checkpoint on restart
+    pub from_checkpoint: bool,
+}
+
+impl Default for TestLiquidityConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval: Duration::from_secs(600),
+            min_rdg: 100.0,
+            rdg_top_up: 1000.0,
+            min_btc_sats: 10_000,
+            btc_top_up_sats: 50_000,
+            btc_faucet_words: None,
+        }
+    }
+}
+
+/// Keeps the AMM party funded on dev networks, never active outside Dev / Staging.
+#[derive(Clone, Debug)]
+pub struct TestLiquidityConfig {
+    pub enabled: bool,
+    pub interval: Duration,
+    // RDG balance of the AMM address below which it's topped up from the genesis funding key
+    pub min_rdg: f64,
+    pub rdg_top_up: f64,
+    pub min_btc_sats: u64,
+    pub btc_top_up_sats: u64,
+    // Mnemonic holding testnet BTC used to top up the AMM, BTC top ups are skipped without it
+    pub btc_faucet_words: Option<String>,
+}
+
+impl TestLiquidityConfig {
+    pub fn active(&self, network: &NetworkEnvironment) -> bool {
+        self.enabled && (network == &NetworkEnvironment::Dev || network == &NetworkEnvironment::Staging)
+    }
+}
+
+impl Default for PeerRetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            hedge_after: Duration::from_secs(2),
+            attempt_timeout: Duration::from_secs(20),
+        }
+    }
+}
+
+/// Retry and hedging of idempotent peer reads, mutating requests are always sent once.
+#[derive(Clone, Debug)]
+pub struct PeerRetryConfig {
+    // Total number of peers a single read may be sent to
+    pub max_attempts: usize,
+    // Without a response by then the read is also sent to the next peer, racing both
+    pub hedge_after: Duration,
+    pub attempt_timeout: Duration,
+}
+
+// Operator overrides of individual feature flags, applied over the network defaults on node start
+pub const FEATURE_FLAG_OVERRIDES: ConfigKey<HashMap<String, bool>> = ConfigKey::new("feature_flags");
+
+pub const FEATURE_FLAG_NAMES: [&str; 8] = [
+    "faucet", "faucet_captcha", "live_e2e", "e2e_test_keys", "genesis_test_distribution",
+    "amm_watcher", "amm_dry_run", "debug_multiparty_control"
+];
+
+/// Behavior which differs between environments. Defaults come from the network and can be
+/// overridden by the config store, then REDGOLD_FEATURE_FLAGS, then --feature-flags.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FeatureFlags {
+    // Serve faucet requests
+    pub faucet: bool,
+    // Rate limit and require a captcha on faucet requests, paying out a smaller amount
+    pub faucet_captcha: bool,
+    // Periodically send live end to end test transactions
+    pub live_e2e: bool,
+    // Live e2e spends from dedicated test keys rather than the node's default key
+    pub e2e_test_keys: bool,
+    // Form genesis from the seed test distribution rather than the main distribution
+    pub genesis_test_distribution: bool,
+    // Run the AMM deposit watcher
+    pub amm_watcher: bool,
+    // Compute and record AMM fulfillments without signing or broadcasting them
+    pub amm_dry_run: bool,
+    // Accept multiparty keygen / signing requests over the control API
+    pub debug_multiparty_control: bool,
+}
+
+impl FeatureFlags {
+    pub fn for_network(network: &NetworkEnvironment) -> Self {
+        Self {
+            faucet: !network.is_main(),
+            faucet_captcha: network.is_main_stage_network(),
+            live_e2e: true,
+            e2e_test_keys: !network.is_main(),
+            genesis_test_distribution: !network.is_main(),
+            amm_watcher: !network.is_local_debug(),
+            amm_dry_run: false,
+            debug_multiparty_control: network.is_local_debug(),
+        }
+    }
+
+    pub fn set(&mut self, name: &str, value: bool) -> RgResult<()> {
+        let flag = match name {
+            "faucet" => &mut self.faucet,
+            "faucet_captcha" => &mut self.faucet_captcha,
+            "live_e2e" => &mut self.live_e2e,
+            "e2e_test_keys" => &mut self.e2e_test_keys,
+            "genesis_test_distribution" => &mut self.genesis_test_distribution,
+            "amm_watcher" => &mut self.amm_watcher,
+            "amm_dry_run" => &mut self.amm_dry_run,
+            "debug_multiparty_control" => &mut self.debug_multiparty_control,
+            _ => return Err(error_info(format!(
+                "Unknown feature flag {}, expected one of {}", name, FEATURE_FLAG_NAMES.join(", ")
+            ))),
+        };
+        *flag = value;
+        Ok(())
+    }
+
+    pub fn apply(&mut self, overrides: &HashMap<String, bool>) -> RgResult<()> {
+        for (name, value) in overrides.iter() {
+            self.set(name, *value)?;
+        }
+        Ok(())
+    }
+
+    /// Parses comma separated overrides such as `faucet=false,amm_dry_run`, a bare name enables
+    /// the flag.
+    pub fn parse_overrides(input: &str) -> RgResult<HashMap<String, bool>> {
+        let mut res = HashMap::new();
+        for entry in input.split(",").map(|e| e.trim()).filter(|e| !e.is_empty()) {
+            let (name, value) = match entry.split_once("=") {
+                Some((n, v)) => (n.trim(), v.trim().parse::<bool>()
+                    .error_info(format!("Invalid value for feature flag {}", n))?),
+                None => (entry, true),
+            };
+            res.insert(name.to_string(), value);
+        }
+        Ok(res)
+    }
+}
+
+impl Default for PeerRxConfig {
+    fn default() -> Self {
+        Self {
+            request_timeout: Duration::from_secs(30),
+            multiparty_request_timeout: Duration::from_secs(300),
+        }
+    }
+}
+
+impl Default for RebroadcastConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(15),
+            initial_backoff: Duration::from_secs(30),
+            max_backoff: Duration::from_secs(60 * 30),
+            max_age: Duration::from_secs(60 * 60 * 24),
+            fanout: 8,
+        }
+    }
+}
+
+impl Default for GossipSchedulerConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_millis(200),
+            peer_rate_limit: 50.0,
+            peer_burst: 100.0,
+            max_batch_per_peer: 50,
+            max_queue_per_peer: 10_000,
+            max_retries: 3,
+            retry_backoff: Duration::from_secs(2),
+            send_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Outbound gossip queueing, drained per peer in priority order under a token bucket limit.
+#[derive(Clone, Debug)]
+pub struct GossipSchedulerConfig {
+    pub interval: Duration,
+    // Messages per second sent to a single peer once its burst is spent
+    pub peer_rate_limit: f64,
+    pub peer_burst: f64,
+    // Messages sent to a single peer per interval
+    pub max_batch_per_peer: usize,
+    // Lower priority messages are evicted, then new messages dropped, past this depth
+    pub max_queue_per_peer: usize,
+    pub max_retries: u32,
+    // Doubles per failed attempt
+    pub retry_backoff: Duration,
+    pub send_timeout: Duration,
+}
+
+impl Default for PeerScoringConfig {
+    fn default() -> Self {
+        Self {
+            reference_rtt_ms: 250.0,
+            ewma_alpha: 0.2,
+            unknown_score: 0.5,
+            gossip_fanout: None,
+        }
+    }
+}
+
+/// Scoring of peers from the latency and failures of REST calls made to them.
+#[derive(Clone, Debug)]
+pub struct PeerScoringConfig {
+    // A peer responding in this long scores half that of an instant one
+    pub reference_rtt_ms: f64,
+    // Weight of the latest call in the rolling averages
+    pub ewma_alpha: f64,
+    // Score of peers with no calls recorded yet, so new peers still get selected
+    pub unknown_score: f64,
+    // Gossip goes to at most this many of the best scored peers, all active peers when unset
+    pub gossip_fanout: Option<usize>,
+}
+
+/// Fees this node requires before accepting and relaying a currency transaction, in raw units.
+/// Both default to zero so fees stay optional unless an operator sets them.
+#[derive(Clone, Debug)]
+pub struct FeePolicyConfig {
+    pub min_relay_fee: i64,
+    pub fee_per_kb: i64,
+    // The suggested per kilobyte rate increases by the base rate per this many pending transactions
+    pub congestion_threshold: usize,
+}
+
+impl Default for FeePolicyConfig {
+    fn default() -> Self {
+        Self {
+            min_relay_fee: 0,
+            fee_per_kb: 0,
+            congestion_threshold: 1000,
+        }
+    }
+}
+
+/// Re-gossip of client submitted transactions until they finalize.
+#[derive(Clone, Debug)]
+pub struct RebroadcastConfig {
+    pub interval: Duration,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    // Submissions still unfinalized after this long are dropped
+    pub max_age: Duration,
+    // Peers sent the transaction per attempt
+    pub fanout: usize,
+}
+
+/// Bounds on handling a single incoming peer request, which is cancelled when exceeded so
+/// a hung dependency can't hold a concurrency slot.
+#[derive(Clone, Debug)]
+pub struct PeerRxConfig {
+    pub request_timeout: Duration,
+    // Multiparty keygen and signing followers run the full protocol before responding
+    pub multiparty_request_timeout: Duration,
+}
+
+impl Default for SeedListConfig {
+    fn default() -> Self {
+        Self {
+            sources: vec![],
+            release_keys: vec![],
+            refresh_interval: Duration::from_secs(60 * 60),
+        }
+    }
+}
+
+/// Signed seed lists fetched at startup and periodically, merged with the compiled seeds.
+#[derive(Clone, Debug)]
+pub struct SeedListConfig {
+    // http(s) urls or dns:<name> TXT records serving a signed seed list
+    pub sources: Vec<String>,
+    // Keys trusted to sign seed lists, none disables seed list updates
+    pub release_keys: Vec<PublicKey>,
+    pub refresh_interval: Duration,
+}
+
+impl SeedListConfig {
+    pub fn enabled(&self) -> bool {
+        !self.release_keys.is_empty()
+    }
+}
+
+impl Default for ChannelsConfig {
+    fn default() -> Self {
+        Self {
+            observation: ChannelConfig::new(10_000, OverflowPolicy::Block),
+            observation_metadata: ChannelConfig::new(10_000, OverflowPolicy::Block),
+            peer_message_tx: ChannelConfig::new(10_000, OverflowPolicy::Reject),
+            peer_message_rx: ChannelConfig::new(10_000, OverflowPolicy::Reject),
+            udp_outgoing: ChannelConfig::new(10_000, OverflowPolicy::DropOldest),
+            trust: ChannelConfig::new(1000, OverflowPolicy::DropOldest),
+            unknown_resolved_inputs: ChannelConfig::new(10_000, OverflowPolicy::Block),
+            // Updates change GUI state, a dropped one would leave it wrong so overflow fails loudly
+            gui_updates: ChannelConfig::new(10_000, OverflowPolicy::Reject),
+        }
+    }
+}
+
+/// Capacities and overflow behavior of the relay's internal channels without a
+/// dedicated bound elsewhere, along with the GUI state update channel.
+#[derive(Clone, Debug)]
+pub struct ChannelsConfig {
+    pub observation: ChannelConfig,
+    pub observation_metadata: ChannelConfig,
+    // Rejected sends surface as errors to the requester rather than queueing unbounded
+    pub peer_message_tx: ChannelConfig,
+    pub peer_message_rx: ChannelConfig,
+    pub udp_outgoing: ChannelConfig,
+    pub trust: ChannelConfig,
+    pub unknown_resolved_inputs: ChannelConfig,
+    pub gui_updates: ChannelConfig,
+}
+
+impl PeerRxConfig {
+    pub fn timeout_for(&self, request: &structs::Request) -> Duration {
+        if request.initiate_keygen.is_some() || request.initiate_signing.is_some() {
+            self.multiparty_request_timeout
+        } else {
+            self.request_timeout
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct NodeInfoConfig {
     pub alias: Option<String>,
@@ -176,8 +593,6 @@ pub struct NodeConfig {
     pub auto_update_poll_interval: Duration,
     pub block_formation_interval: Duration,
     pub genesis_config: GenesisConfig,
-    pub faucet_enabled: bool,
-    pub e2e_enabled: bool,
     pub load_balancer_url: String,
     pub external_ip: String,
     pub external_host: String,
@@ -188,17 +603,40 @@ pub struct NodeConfig {
     pub enable_logging: bool,
     pub discovery_interval: Duration,
     pub watcher_interval: Duration,
+    // How often the AMM party BTC wallet is synced in the background for the watcher
+    pub btc_sync_interval: Duration,
     pub shuffle_interval: Duration,
     pub live_e2e_interval: Duration,
     pub genesis: bool,
+    pub genesis_manifest: Option<GenesisManifest>,
     pub opts: RgArgs,
     pub mempool: MempoolConfig,
     pub tx_config: TransactionProcessingConfig,
     pub observation: ObservationConfig,
     pub contract: ContractConfig,
     pub contention: ContentionConfig,
+    pub retention: RetentionConfig,
+    pub disk_space: DiskSpaceConfig,
+    pub peer_retry: PeerRetryConfig,
+    pub test_liquidity: TestLiquidityConfig,
+    pub checkpoint: CheckpointConfig,
+    pub features: FeatureFlags,
+    pub peer_rx: PeerRxConfig,
+    pub channels: ChannelsConfig,
+    pub seed_list: SeedListConfig,
+    pub rebroadcast: RebroadcastConfig,
+    pub gossip_scheduler: GossipSchedulerConfig,
+    pub peer_scoring: PeerScoringConfig,
+    pub fee_policy: FeePolicyConfig,
     pub node_info: NodeInfoConfig,
     pub(crate) default_timeout: Duration,
+    pub coin_selection: CoinSelectionStrategy,
+    // Opt in endpoint receiving crash and critical error reports
+    pub telemetry_endpoint: Option<String>,
+    pub btc_backend: BitcoinBackendConfig,
+    pub api_access: ApiAccessConfig,
+    pub secrets: SecretsConfig,
+    pub seed_mode: bool,
 }
 
 impl NodeConfig {
@@ -256,6 +694,7 @@ impl NodeConfig {
             trust: vec![TrustData::from_label(1.0)],
             peer_id: Some(self.peer_id.clone()),
             public_key: Some(self.public_key()),
+            genesis_manifest_hash: self.genesis_manifest.as_ref().and_then(|m| m.hash().ok()),
         }
     }
 
@@ -374,7 +813,11 @@ impl NodeConfig {
             }
         };
         info!("Load balancer host: {} port: {:?}", host, port);
-        PublicClient::from(host, port, None)
+        PublicClient::from(host, port, None).with_api_key(self.api_access.client_api_key.clone())
+    }
+
+    pub fn sdk_client(&self) -> RedgoldClient {
+        self.api_client().sdk_client()
     }
 
     pub fn is_local_debug(&self) -> bool {
@@ -489,8 +932,6 @@ impl NodeConfig {
             auto_update_poll_interval: Duration::from_secs(60),
             block_formation_interval: Duration::from_secs(10),
             genesis_config: Default::default(),
-            faucet_enabled: true,
-            e2e_enabled: true,
             load_balancer_url: "lb.redgold.io".to_string(),
             external_ip: "127.0.0.1".to_string(),
             external_host: "localhost".to_string(),
@@ -501,9 +942,11 @@ impl NodeConfig {
             enable_logging: true,
             discovery_interval: Duration::from_secs(5),
             watcher_interval: Duration::from_secs(200),
+            btc_sync_interval: Duration::from_secs(60),
             shuffle_interval: Duration::from_secs(600),
             live_e2e_interval: Duration::from_secs(60*10), // every 10 minutes
             genesis: false,
+            genesis_manifest: None,
             opts: RgArgs::default(),
             mempool: Default::default(),
             tx_config: Default::default(),
@@ -511,7 +954,26 @@ impl NodeConfig {
             node_info: NodeInfoConfig::default(),
             contract: Default::default(),
             contention: Default::default(),
+            retention: Default::default(),
+            disk_space: Default::default(),
+            peer_retry: Default::default(),
+            test_liquidity: Default::default(),
+            checkpoint: Default::default(),
+            features: FeatureFlags::for_network(&NetworkEnvironment::Debug),
+            peer_rx: Default::default(),
+            channels: Default::default(),
+            seed_list: Default::default(),
+            rebroadcast: Default::default(),
+            gossip_scheduler: Default::default(),
+            peer_scoring: Default::default(),
+            fee_policy: Default::default(),
             default_timeout: Duration::from_secs(60),
+            coin_selection: CoinSelectionStrategy::default(),
+            telemetry_endpoint: None,
+            btc_backend: BitcoinBackendConfig::default(),
+            api_access: ApiAccessConfig::default(),
+            secrets: SecretsConfig::default(),
+            seed_mode: false,
         }
     }
 
@@ -536,8 +998,10 @@ impl NodeConfig {
         node_config.network = NetworkEnvironment::Debug;
         node_config.check_observations_done_poll_interval = Duration::from_secs(1);
         node_config.check_observations_done_poll_attempts = 5;
-        node_config.e2e_enabled = false;
+        node_config.features = FeatureFlags::for_network(&node_config.network);
+        node_config.features.live_e2e = false;
         node_config.watcher_interval = Duration::from_secs(5);
+        node_config.btc_sync_interval = Duration::from_secs(2);
         node_config
     }
     pub async fn data_store(&self) -> DataStore {
@@ -588,4 +1052,33 @@ impl NodeConfig {
 #[test]
 fn debug(){
 
-}
\ No newline at end of file
+}
+#[test]
+fn acceptance_weight_counts_each_node_once() {
+    let config = ObservationConfig::default();
+    let accepted = vec![("a".to_string(), 6.0), ("a".to_string(), 6.0), ("b".to_string(), 3.0)];
+    let weight = ObservationConfig::acceptance_weight(&accepted);
+    assert_eq!(weight, 9.0);
+    assert!(!config.is_finalized(weight));
+    assert!(config.is_finalized(weight + 1.0));
+}
+
+#[test]
+fn peer_rx_timeout_extends_for_multiparty() {
+    let config = PeerRxConfig::default();
+    let mut request = structs::Request::default();
+    assert_eq!(config.timeout_for(&request), config.request_timeout);
+    request.initiate_signing = Some(Default::default());
+    assert_eq!(config.timeout_for(&request), config.multiparty_request_timeout);
+}
+
+#[test]
+fn feature_flag_overrides() {
+    let mut flags = FeatureFlags::for_network(&NetworkEnvironment::Main);
+    assert!(!flags.faucet);
+    let overrides = FeatureFlags::parse_overrides("faucet, amm_dry_run=true,live_e2e=false").unwrap();
+    flags.apply(&overrides).unwrap();
+    assert!(flags.faucet && flags.amm_dry_run && !flags.live_e2e);
+    assert!(FeatureFlags::parse_overrides("faucet=maybe").is_err());
+    assert!(flags.set("unknown", true).is_err());
+}
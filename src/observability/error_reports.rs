@@ -0,0 +1,252 @@
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use log::error;
+use serde::{Deserialize, Serialize};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::Layer;
+use redgold_schema::{json_from, json_or, RgResult, ErrorInfoContext};
+use redgold_schema::structs::ErrorInfo;
+use crate::node_config::NodeConfig;
+use crate::util::current_time_millis_i64;
+
+const MAX_LOG_TAIL: usize = 200;
+const LAST_CRASH_FILE: &str = "last_crash.json";
+const ERROR_REPORTS_FILE: &str = "error_reports.jsonl";
+const MAX_ERROR_REPORTS_BYTES: u64 = 5 * 1024 * 1024;
+// Lines mentioning any of these are dropped from reports leaving the machine
+const SENSITIVE_MARKERS: [&str; 10] = [
+    "mnemonic", "words", "private", "secret", "password", "passphrase", "seed", "api_key", "x-api-key", "token",
+];
+
+static LOG_TAIL: OnceLock<Mutex<VecDeque<String>>> = OnceLock::new();
+static REPORT_CONTEXT: OnceLock<ReportContext> = OnceLock::new();
+
+/// Where reports are written and what's attached to them, fixed once config is loaded.
+#[derive(Clone, Debug)]
+pub struct ReportContext {
+    pub report_dir: PathBuf,
+    pub network: String,
+    pub version: String,
+    pub telemetry_endpoint: Option<String>,
+}
+
+impl ReportContext {
+    pub fn from_node_config(nc: &NodeConfig) -> Self {
+        Self {
+            report_dir: nc.env_data_folder().path.join("reports"),
+            network: nc.network.to_std_string(),
+            version: format!("{} {}", env!("CARGO_PKG_VERSION"), nc.executable_checksum.clone().unwrap_or("".to_string())),
+            telemetry_endpoint: nc.telemetry_endpoint.clone(),
+        }
+    }
+
+    pub fn last_crash_path(&self) -> PathBuf {
+        self.report_dir.join(LAST_CRASH_FILE)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ErrorReport {
+    pub time: i64,
+    pub kind: String,
+    pub message: String,
+    pub location: Option<String>,
+    pub version: String,
+    pub network: String,
+    pub log_tail: Vec<String>,
+    #[serde(default)]
+    pub sent: bool,
+}
+
+impl ErrorReport {
+    fn new(ctx: &ReportContext, kind: &str, message: String, location: Option<String>) -> Self {
+        Self {
+            time: current_time_millis_i64(),
+            kind: kind.to_string(),
+            message,
+            location,
+            version: ctx.version.clone(),
+            network: ctx.network.clone(),
+            log_tail: log_tail(),
+            sent: false,
+        }
+    }
+
+    /// Copy safe to send to the telemetry endpoint, with any line that may carry secrets removed.
+    pub fn redacted(&self) -> Self {
+        let mut report = self.clone();
+        report.message = redact_line(&report.message);
+        report.log_tail = report.log_tail.iter().map(|l| redact_line(l)).collect();
+        report
+    }
+}
+
+fn redact_line(line: &String) -> String {
+    let lower = line.to_lowercase();
+    if SENSITIVE_MARKERS.iter().any(|m| lower.contains(m)) {
+        "[redacted]".to_string()
+    } else {
+        line.clone()
+    }
+}
+
+pub fn log_tail() -> Vec<String> {
+    LOG_TAIL.get()
+        .and_then(|t| t.lock().ok())
+        .map(|t| t.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+fn push_log_line(line: String) {
+    if let Ok(mut tail) = LOG_TAIL.get_or_init(|| Mutex::new(VecDeque::new())).lock() {
+        tail.push_back(line);
+        if tail.len() > MAX_LOG_TAIL {
+            tail.pop_front();
+        }
+    }
+}
+
+struct MessageVisitor(String);
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            self.0.insert_str(0, &format!("{:?}", value));
+        } else {
+            self.0.push_str(&format!(" {}={:?}", field.name(), value));
+        }
+    }
+}
+
+/// Keeps the most recent log lines in memory so they can be attached to error reports.
+pub struct LogTailLayer;
+
+impl<S: Subscriber> Layer<S> for LogTailLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = MessageVisitor(String::new());
+        event.record(&mut visitor);
+        let meta = event.metadata();
+        push_log_line(format!("{} {} {}: {}", current_time_millis_i64(), meta.level(), meta.target(), visitor.0));
+    }
+}
+
+fn write_report(ctx: &ReportContext, report: &ErrorReport) -> RgResult<()> {
+    std::fs::create_dir_all(&ctx.report_dir).error_info("Failed to create report folder")?;
+    let path = ctx.report_dir.join(ERROR_REPORTS_FILE);
+    // Keep a single previous file so repeated errors can't fill the disk
+    if std::fs::metadata(&path).map(|m| m.len() >= MAX_ERROR_REPORTS_BYTES).unwrap_or(false) {
+        std::fs::rename(&path, ctx.report_dir.join(format!("{}.1", ERROR_REPORTS_FILE)))
+            .error_info("Failed to rotate error reports file")?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true).append(true)
+        .open(&path)
+        .error_info("Failed to open error reports file")?;
+    writeln!(file, "{}", json_or(report)).error_info("Failed to write error report")?;
+    if report.kind == "panic" {
+        std::fs::write(ctx.last_crash_path(), json_or(report)).error_info("Failed to write crash report")?;
+    }
+    Ok(())
+}
+
+async fn send_report(endpoint: String, report: ErrorReport) -> RgResult<()> {
+    reqwest::Client::new().post(endpoint).json(&report.redacted()).send().await
+        .error_info("Failed to send error report")?
+        .error_for_status()
+        .error_info("Error report rejected by telemetry endpoint")?;
+    Ok(())
+}
+
+/// Records a critical error that didn't crash the process, forwarding it to the telemetry
+/// endpoint if one was opted into.
+pub fn report_critical_error(context: &str, e: &ErrorInfo) {
+    let ctx = match REPORT_CONTEXT.get() {
+        Some(c) => c,
+        None => return,
+    };
+    let report = ErrorReport::new(ctx, "error", format!("{}: {}", context, json_or(e)), None);
+    if let Err(e) = write_report(ctx, &report) {
+        error!("Failed to record error report: {}", json_or(&e));
+    }
+    if let (Some(endpoint), Ok(handle)) = (ctx.telemetry_endpoint.clone(), tokio::runtime::Handle::try_current()) {
+        handle.spawn(send_report(endpoint, report));
+    }
+}
+
+/// Crash report left behind by the previous run, if it hasn't been dismissed.
+pub fn last_crash(nc: &NodeConfig) -> Option<ErrorReport> {
+    let path = ReportContext::from_node_config(nc).last_crash_path();
+    std::fs::read_to_string(path).ok().and_then(|c| json_from(&c).ok())
+}
+
+pub fn dismiss_last_crash(nc: &NodeConfig) -> RgResult<()> {
+    let path = ReportContext::from_node_config(nc).last_crash_path();
+    if path.exists() {
+        std::fs::remove_file(path).error_info("Failed to remove crash report")?;
+    }
+    Ok(())
+}
+
+/// Installs a process wide panic hook writing a crash report before the default handler runs.
+/// Panics can't wait on network requests, so an unsent crash report is forwarded on next start.
+pub fn install_panic_hook(nc: &NodeConfig) {
+    let ctx = ReportContext::from_node_config(nc);
+    if REPORT_CONTEXT.set(ctx.clone()).is_err() {
+        return;
+    }
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let message = info.payload().downcast_ref::<&str>().map(|s| s.to_string())
+            .or(info.payload().downcast_ref::<String>().cloned())
+            .unwrap_or("Unknown panic".to_string());
+        let location = info.location().map(|l| format!("{}:{}", l.file(), l.line()));
+        let report = ErrorReport::new(&ctx, "panic", message, location);
+        if let Err(e) = write_report(&ctx, &report) {
+            eprintln!("Failed to write crash report: {}", json_or(&e));
+        }
+        default_hook(info);
+    }));
+    if let (Some(endpoint), Some(mut report)) = (ctx.telemetry_endpoint.clone(), last_crash(nc)) {
+        if !report.sent {
+            if let Ok(handle) = tokio::runtime::Handle::try_current() {
+                let path = ctx.last_crash_path();
+                handle.spawn(async move {
+                    if send_report(endpoint, report.clone()).await.is_ok() {
+                        report.sent = true;
+                        std::fs::write(path, json_or(&report)).ok();
+                    }
+                });
+            }
+        }
+    }
+}
+
+#[test]
+fn log_tail_is_bounded() {
+    for i in 0..(MAX_LOG_TAIL + 5) {
+        push_log_line(i.to_string());
+    }
+    let tail = log_tail();
+    assert_eq!(tail.len(), MAX_LOG_TAIL);
+    assert_eq!(tail.last().cloned(), Some((MAX_LOG_TAIL + 4).to_string()));
+}
+
+#[test]
+fn redacted_report_drops_sensitive_lines() {
+    let ctx = ReportContext {
+        report_dir: PathBuf::from("."),
+        network: "debug".to_string(),
+        version: "".to_string(),
+        telemetry_endpoint: None,
+    };
+    let mut report = ErrorReport::new(&ctx, "error", "Failed to parse mnemonic abandon abandon".to_string(), None);
+    report.log_tail = vec!["INFO peer connected".to_string(), "DEBUG X-Api-Key=abc".to_string()];
+    let redacted = report.redacted();
+    assert_eq!(redacted.message, "[redacted]");
+    assert_eq!(redacted.log_tail, vec!["INFO peer connected".to_string(), "[redacted]".to_string()]);
+}
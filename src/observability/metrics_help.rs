@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use metrics::counter;
 use redgold_schema::RgResult;
 
@@ -10,4 +11,29 @@ impl<T> WithMetrics for RgResult<T> {
         counter!(counter.into()).increment(1);
         self
     }
-}
\ No newline at end of file
+}
+/// Parses prometheus text exposition into metric name to value, ignoring labels and comments
+pub fn parse_prometheus_values(text: &str) -> HashMap<String, f64> {
+    let mut values = HashMap::new();
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let name_end = line.find(|c: char| c == '{' || c == ' ').unwrap_or(line.len());
+        let name = &line[..name_end];
+        let value = line.rsplit(' ').next().and_then(|v| v.parse::<f64>().ok());
+        if let Some(v) = value {
+            values.insert(name.to_string(), v);
+        }
+    }
+    values
+}
+
+#[test]
+fn parse_prometheus_example() {
+    let text = "# TYPE redgold_observation_height gauge\nredgold_observation_height 42\nredgold_peer_active{env=\"dev\"} 3\n";
+    let values = parse_prometheus_values(text);
+    assert_eq!(values.get("redgold_observation_height"), Some(&42.0));
+    assert_eq!(values.get("redgold_peer_active"), Some(&3.0));
+}
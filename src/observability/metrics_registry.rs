@@ -9,11 +9,23 @@
 use log::info;
 use metrics::{counter, describe_counter, describe_gauge, describe_histogram, KeyName, SharedString};
 use metrics::{Counter, CounterFn, Gauge, GaugeFn, Histogram, HistogramFn, Key, Recorder, Unit};
-use metrics_exporter_prometheus::PrometheusBuilder;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+use crate::observability::metrics_help::parse_prometheus_values;
 use std::sync::Arc;
 
 pub fn register_metric_names() {
     describe_counter!("redgold.p2p.request_peer_info", "");
+    describe_counter!("redgold.http.pool.hit", "");
+    describe_counter!("redgold.http.pool.miss", "");
+    describe_gauge!("redgold.http.pool.hosts", "");
+    describe_counter!("redgold.http.breaker.rejected", "");
+    describe_counter!("redgold.http.breaker.opened", "");
+    describe_gauge!("redgold.http.breaker.open", "");
+    describe_counter!("redgold.peer.request.retry", "");
+    describe_counter!("redgold.peer.request.hedged", "");
+    describe_counter!("redgold.peer.request.exhausted", "");
 
     describe_counter!("redgold.node.main_started", "");
     describe_counter!("redgold.node.node_started", "");
@@ -28,13 +40,82 @@ pub fn register_metric_names() {
     describe_counter!("redgold.observation.buffer.added", "");
     describe_counter!("redgold.observation.failed_to_send_to_transaction_processor", "");
     describe_gauge!("redgold.observation.height", "");
+    describe_gauge!("redgold.node.uptime_seconds", "");
+    describe_gauge!("redgold.peer.active", "");
+    describe_counter!("redgold.seed.rejected_request", "");
+    describe_counter!("redgold.peer.request_replay_rejected", "");
+    describe_counter!("redgold.gossip.transaction.dedup_hit", "");
+    describe_counter!("redgold.seed_list.fetch_failed", "");
+    describe_counter!("redgold.seed_list.updated", "");
+    describe_gauge!("redgold.seed_list.issued_time", "");
+    describe_counter!("redgold.transaction.rebroadcast", "");
+    describe_counter!("redgold.transaction.rebroadcast.expired", "");
+    describe_gauge!("redgold.transaction.rebroadcast.tracked", "");
+    describe_counter!("redgold.gossip.sent", "");
+    describe_counter!("redgold.gossip.retry", "");
+    describe_counter!("redgold.gossip.dropped", "");
+    describe_counter!("redgold.gossip.queue_full", "");
+    describe_gauge!("redgold.gossip.queue.transaction", "");
+    describe_gauge!("redgold.gossip.queue.observation", "");
+    describe_gauge!("redgold.gossip.queue.maintenance", "");
+    describe_gauge!("redgold.gossip.queue.peers", "");
+    describe_counter!("redgold.seed.pruned_peer", "");
+    describe_counter!("redgold.balance_snapshot.recorded", "");
+    describe_counter!("redgold.datastore.pruned.rows", "");
+    describe_counter!("redgold.datastore.pruned.bytes", "");
+    describe_gauge!("redgold.datastore.size_bytes", "");
+    describe_gauge!("redgold.disk.free_bytes", "");
+    describe_gauge!("redgold.disk.space_low", "");
+    describe_counter!("redgold.disk.low_space_alerts", "");
     describe_gauge!("redgold.observation.total", "");
     describe_gauge!("redgold.observation.last.size", "");
     describe_gauge!("redgold.utxo.total", "");
+    describe_gauge!("redgold.amm.volume.btc", "");
+    describe_gauge!("redgold.amm.volume.rdg", "");
+    describe_gauge!("redgold.amm.fulfillments", "");
+    describe_gauge!("redgold.amm.unfulfilled_orders", "");
+    describe_gauge!("redgold.amm.balance.rdg", "");
+    describe_gauge!("redgold.amm.balance.btc", "");
+    describe_gauge!("redgold.amm.center_price", "");
+    describe_histogram!("redgold.amm.fulfillment_latency_seconds", "");
+    describe_counter!("redgold.amm.snapshot.saved", "");
+    describe_counter!("redgold.amm.snapshot.restored", "");
+    describe_gauge!("redgold.amm.drift.rdg", "");
+    describe_gauge!("redgold.amm.drift.btc", "");
+    describe_counter!("redgold.amm.safe_mode_entered", "");
+    describe_counter!("redgold.amm.startup_validation_failed", "");
+    describe_gauge!("redgold.amm.btc_fee_rate", "");
+    describe_counter!("redgold.amm.btc_fee_guard_aborted", "");
+    describe_counter!("redgold.amm.dry_run_fulfillments", "");
+    describe_counter!("redgold.amm.btc_refunds", "");
+    describe_counter!("redgold.amm.btc_refunds_below_dust", "");
+    describe_gauge!("redgold.amm.admission_paused", "");
+    describe_counter!("redgold.amm.admission_paused_entered", "");
+    describe_counter!("redgold.amm.cold_sweep.rdg", "");
+    describe_counter!("redgold.amm.cold_sweep.btc", "");
+    describe_counter!("redgold.test_liquidity.rdg_top_up", "");
+    describe_counter!("redgold.test_liquidity.btc_top_up", "");
+    describe_counter!("redgold.checkpoint.created", "");
+    describe_gauge!("redgold.checkpoint.transaction_count", "");
+    describe_counter!("redgold.checkpoint.peer_signed", "");
+    describe_counter!("redgold.checkpoint.peer_mismatch", "");
+    describe_counter!("redgold.checkpoint.verify_failed", "");
+    describe_counter!("redgold.checkpoint.state_proof", "");
+    describe_counter!("redgold.transaction.insufficient_fee", "");
+    describe_counter!("redgold.payment_request.received", "");
+    describe_counter!("redgold.payment_request.forwarded", "");
+    describe_counter!("redgold.btc.checkpoint.saved", "");
+    describe_counter!("redgold.btc.checkpoint.imported", "");
+    describe_histogram!("redgold.btc.sync.duration_seconds", "");
+    describe_gauge!("redgold.btc.sync.last_success", "");
+    describe_counter!("redgold.btc.sync.failed", "");
+    describe_counter!("redgold.amm.btc_snapshot_stale", "");
 
     describe_counter!("redgold.transaction.accepted", "");
     describe_gauge!("redgold.transaction.accepted.total", "");
     describe_counter!("redgold.transaction.received", "");
+    describe_counter!("redgold.transaction.conflict", "");
+    describe_counter!("redgold.transaction.name_conflict", "");
     describe_counter!("redgold.transaction.missing_response_channel", "");
     describe_counter!("redgold.transaction.resolve.input", "");
     describe_counter!("redgold.transaction.resolve.output", "");
@@ -44,6 +125,7 @@ pub fn register_metric_names() {
     describe_histogram!("redgold.transaction.size_bytes", "");
     describe_histogram!("redgold.transaction.floating_inputs", "");
     describe_histogram!("redgold.transaction.total_output_amount", "");
+    describe_histogram!("redgold.transaction.signature_verify_ms", "Time spent verifying all input signatures of a transaction");
     describe_histogram!("redgold.transaction.num_inputs", "");
     describe_histogram!("redgold.transaction.num_outputs", "");
 
@@ -60,6 +142,7 @@ pub fn register_metric_names() {
     describe_counter!("redgold.e2e.failure", "");
     describe_counter!("redgold.e2e.success", "");
     describe_counter!("redgold.peer.message.received", "");
+    describe_counter!("redgold.peer.message.timeout", "");
     describe_counter!("redgold.peer.rest.send.error", "");
     describe_counter!("redgold.peer.rest.send", "");
     describe_counter!("redgold.peer.send", "");
@@ -190,15 +273,42 @@ enum MetricType {
     Histogram,
 }
 
-pub fn register_metrics(port_offset: u16) {
+/// Whether metrics are served to scrapers over HTTP or only kept for reads within the process.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MetricsMode {
+    Exporter,
+    InProcess,
+}
+
+static METRICS_HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+
+pub fn register_metrics(port_offset: u16, mode: MetricsMode) {
     if std::env::var("REDGOLD_LOCAL_DEBUG").is_ok() {
         // init_print_logger();
     } else {
-        init_prometheus(port_offset);
+        match mode {
+            MetricsMode::Exporter => init_prometheus(port_offset),
+            MetricsMode::InProcess => init_in_process(),
+        }
     }
     register_metric_names();
 }
 
+// Installs the recorder without binding a listener, i.e. for the GUI and its embedded node
+fn init_in_process() {
+    match PrometheusBuilder::new().install_recorder() {
+        Ok(handle) => {
+            METRICS_HANDLE.set(handle).ok();
+        }
+        Err(e) => info!("Failed to install in process metrics recorder: {}", e),
+    }
+}
+
+/// Current values of all metrics recorded in this process, only available in process mode.
+pub fn metrics_snapshot() -> Option<HashMap<String, f64>> {
+    METRICS_HANDLE.get().map(|h| parse_prometheus_values(&h.render()))
+}
+
 // let _common_labels = &[("listener", "frontend")];
 
 // // Go through describing the metrics:
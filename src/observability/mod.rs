@@ -4,3 +4,5 @@ pub mod metrics_registry;
 pub mod logging;
 pub mod trace_setup;
 pub mod metrics_help;
+pub mod node_gauges;
+pub mod error_reports;
@@ -0,0 +1,68 @@
+use std::path::Path;
+use async_trait::async_trait;
+use log::{error, info};
+use metrics::{counter, gauge};
+use redgold_schema::RgResult;
+use crate::core::relay::Relay;
+use crate::core::stream_handlers::IntervalFold;
+use crate::util::current_time_millis_i64;
+
+/// Periodically exported node level gauges used for quick remote status checks
+pub struct NodeStatusGauges {
+    pub relay: Relay,
+    pub start_time: i64,
+}
+
+impl NodeStatusGauges {
+    pub fn new(relay: &Relay) -> Self {
+        Self {
+            relay: relay.clone(),
+            start_time: current_time_millis_i64(),
+        }
+    }
+
+    fn disk_gauges(&self) -> RgResult<()> {
+        let ds = &self.relay.ds;
+        gauge!("redgold.datastore.size_bytes").set(ds.file_size_bytes()? as f64);
+        let free = match ds.file_path().and_then(|p| free_disk_bytes(&p)) {
+            Some(f) => f,
+            None => return Ok(()),
+        };
+        gauge!("redgold.disk.free_bytes").set(free as f64);
+        let min_free = self.relay.node_config.disk_space.min_free_bytes;
+        let low = free < min_free;
+        let was_low = self.relay.disk_space_low.swap(low);
+        gauge!("redgold.disk.space_low").set(if low { 1.0 } else { 0.0 });
+        if low && !was_low {
+            counter!("redgold.disk.low_space_alerts").increment(1);
+            error!("Free disk space {} bytes below minimum {}, pausing non-essential datastore writes", free, min_free);
+        } else if !low && was_low {
+            info!("Free disk space recovered to {} bytes, resuming non-essential datastore writes", free);
+        }
+        Ok(())
+    }
+}
+
+/// Bytes available to unprivileged users on the filesystem containing the path.
+#[cfg(unix)]
+pub fn free_disk_bytes(path: &Path) -> Option<u64> {
+    nix::sys::statvfs::statvfs(path).ok()
+        .map(|s| s.blocks_available() as u64 * s.fragment_size() as u64)
+}
+
+#[cfg(not(unix))]
+pub fn free_disk_bytes(_path: &Path) -> Option<u64> {
+    None
+}
+
+#[async_trait]
+impl IntervalFold for NodeStatusGauges {
+    async fn interval_fold(&mut self) -> RgResult<()> {
+        let uptime = (current_time_millis_i64() - self.start_time) / 1000;
+        gauge!("redgold.node.uptime_seconds").set(uptime as f64);
+        let peers = self.relay.ds.peer_store.active_nodes(None).await?.len();
+        gauge!("redgold.peer.active").set(peers as f64);
+        self.disk_gauges()?;
+        Ok(())
+    }
+}
@@ -2,16 +2,19 @@ use crate::util;
 
 use std::{error::Error, io};
 use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
 use std::time::Duration;
 use tokio::task_local;
 use tracing::{debug, error, info, span, warn, Level, Span, event};
 use tracing_subscriber::fmt::format::{FmtSpan, Format};
+use tracing_subscriber::{EnvFilter, Registry, reload};
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
-use redgold_schema::{EasyJson, error_info, error_message, task_local, task_local_map};
+use redgold_schema::{EasyJson, error_info, error_message, RgResult, task_local, task_local_map};
 use redgold_schema::structs::ErrorInfo;
 use crate::api::public_api::run_server;
 use crate::core::relay::Relay;
+use crate::observability::error_reports::LogTailLayer;
 
 
 #[tracing::instrument]
@@ -185,8 +188,26 @@ tracing_subscriber::registry()
 }
 
 pub fn init_tracing(log_level: &str) {
+    init_tracing_otlp(log_level, None);
+}
 
-    use tracing_subscriber::EnvFilter;
+/// Batch exporting OTLP tracer tagged with the redgold service name.
+fn otlp_tracer(endpoint: String) -> RgResult<opentelemetry_sdk::trace::Tracer> {
+    use opentelemetry_otlp::WithExportConfig;
+    let resource = opentelemetry_sdk::Resource::new(vec![
+        opentelemetry::KeyValue::new("service.name", "redgold")
+    ]);
+    opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(opentelemetry_otlp::new_exporter().tonic().with_endpoint(endpoint.clone()))
+        .with_trace_config(opentelemetry_sdk::trace::config().with_resource(resource))
+        .install_batch(opentelemetry_sdk::runtime::Tokio)
+        .map_err(|e| error_info(format!("Failed to install OTLP exporter for {} {}", endpoint, e.to_string())))
+}
+
+/// Initialize tracing, additionally exporting spans to an OTLP collector (i.e. Jaeger or Tempo)
+/// when an endpoint is supplied.
+pub fn init_tracing_otlp(log_level: &str, otlp_endpoint: Option<String>) {
 
 
     // let fmt_layer = tracing_subscriber::fmt::Layer::builder()
@@ -201,13 +222,106 @@ pub fn init_tracing(log_level: &str) {
         // .with_target(false)
         // .with_level(false);
 
-    let filter_layer = EnvFilter::new(format!(
-        "sqlx=ERROR,warp=WARN,rocket=ERROR,redgold={}", log_level));
+    let filter_state = LogFilterState::new(log_level);
+    let (filter_layer, reload_handle) = reload::Layer::new(EnvFilter::new(filter_state.render()));
+    LOG_FILTER_RELOAD.set((reload_handle, Mutex::new(filter_state))).ok();
+
+    let (tracer, otlp_error) = match otlp_endpoint.map(|e| otlp_tracer(e)) {
+        Some(Ok(t)) => (Some(t), None),
+        Some(Err(e)) => (None, Some(e)),
+        None => (None, None),
+    };
+    let otlp_layer = tracer.map(|t| tracing_opentelemetry::layer().with_tracer(t));
 
     tracing_subscriber::registry()
         .with(filter_layer)
         .with(fmt_layer)
+        .with(LogTailLayer)
+        .with(otlp_layer)
         .init();
+
+    // Reported once the subscriber is installed so the failure reaches the logs
+    if let Some(e) = otlp_error {
+        error!("OTLP export disabled: {}", e.json_or());
+    }
+}
+
+/// Flush any pending OTLP spans, called before shutdown.
+pub fn shutdown_tracing() {
+    opentelemetry::global::shutdown_tracer_provider();
+}
+
+/// Directives backing the reloadable tracing filter, kept so single targets can be adjusted
+/// without losing the rest.
+pub struct LogFilterState {
+    pub redgold_level: String,
+    pub overrides: Vec<(String, String)>,
+}
+
+impl LogFilterState {
+    pub fn new(log_level: &str) -> Self {
+        Self {
+            redgold_level: log_level.to_string(),
+            overrides: vec![],
+        }
+    }
+
+    pub fn render(&self) -> String {
+        let mut directives = vec![
+            "sqlx=ERROR".to_string(),
+            "warp=WARN".to_string(),
+            "rocket=ERROR".to_string(),
+            format!("redgold={}", self.redgold_level),
+        ];
+        for (target, level) in &self.overrides {
+            directives.push(format!("{}={}", target, level));
+        }
+        directives.join(",")
+    }
+
+    pub fn apply(&mut self, level: &str, target: Option<String>) {
+        match target {
+            None => {
+                self.redgold_level = level.to_string();
+            }
+            Some(t) => {
+                self.overrides.retain(|(existing, _)| existing != &t);
+                self.overrides.push((t, level.to_string()));
+            }
+        }
+    }
+}
+
+static LOG_FILTER_RELOAD: OnceLock<(reload::Handle<EnvFilter, Registry>, Mutex<LogFilterState>)> = OnceLock::new();
+
+pub fn current_log_filter() -> RgResult<String> {
+    let (_, state) = LOG_FILTER_RELOAD.get().ok_or(error_info("Tracing not initialized"))?;
+    let state = state.lock().map_err(|e| error_info(format!("Log filter lock poisoned {}", e.to_string())))?;
+    Ok(state.render())
+}
+
+/// Change the runtime log level for all redgold logs or a single target module, returns the
+/// resulting filter.
+pub fn update_log_level(level: &str, target: Option<String>) -> RgResult<String> {
+    level.parse::<Level>().map_err(|e| error_info(format!("Invalid log level {} {}", level, e.to_string())))?;
+    let (handle, state) = LOG_FILTER_RELOAD.get().ok_or(error_info("Tracing not initialized"))?;
+    let mut state = state.lock().map_err(|e| error_info(format!("Log filter lock poisoned {}", e.to_string())))?;
+    state.apply(level, target);
+    let rendered = state.render();
+    let filter = EnvFilter::try_new(rendered.clone())
+        .map_err(|e| error_info(format!("Invalid log filter {} {}", rendered, e.to_string())))?;
+    handle.reload(filter).map_err(|e| error_info(format!("Failed to reload log filter {}", e.to_string())))?;
+    info!("Updated log filter to {}", rendered);
+    Ok(rendered)
+}
+
+#[test]
+fn log_filter_state_render() {
+    let mut state = LogFilterState::new("INFO");
+    state.apply("DEBUG", Some("redgold::core::watcher".to_string()));
+    state.apply("TRACE", Some("redgold::core::watcher".to_string()));
+    state.apply("WARN", None);
+    assert_eq!(state.render(), "sqlx=ERROR,warp=WARN,rocket=ERROR,redgold=WARN,redgold::core::watcher=TRACE");
 }
 
 async fn debug_task() -> ErrorInfo {
@@ -2,6 +2,7 @@
 
 pub mod node_e2e;
 pub mod external_amm_integration;
+pub mod swap_e2e;
 
 fn asdf() {
 
@@ -0,0 +1,40 @@
+use redgold_schema::structs::{CurrencyAmount, NetworkEnvironment, PublicKey};
+use crate::e2e::swap_round_trip::{run_swap_round_trip, SwapRoundTripConfig, verify_round_trip};
+use crate::node_config::NodeConfig;
+use crate::test::external_amm_integration::{amm_public_key, dev_ci_kp};
+
+// Nightly bridge round trip, requires REDGOLD_TEST_WORDS holding BTC testnet funds.
+// REDGOLD_SWAP_E2E_NETWORK selects the environment (default dev) and
+// REDGOLD_SWAP_E2E_PARTY_PUBLIC_KEY overrides the party for locally started networks.
+#[ignore]
+#[tokio::test]
+pub async fn swap_round_trip_e2e() {
+    let network = std::env::var("REDGOLD_SWAP_E2E_NETWORK").ok()
+        .and_then(|n| NetworkEnvironment::parse_safe(n).ok())
+        .unwrap_or(NetworkEnvironment::Dev);
+    let party = std::env::var("REDGOLD_SWAP_E2E_PARTY_PUBLIC_KEY").ok()
+        .map(|pk| PublicKey::from_hex(pk).expect("party public key"))
+        .unwrap_or_else(|| amm_public_key(&network));
+
+    // Explicitly run against a live network, so missing credentials fail rather than pass silently
+    let (privk, kp) = dev_ci_kp().expect("REDGOLD_TEST_WORDS must be set to run the swap round trip");
+    let client = NodeConfig::default_env(network.clone()).await.api_client();
+    let config = SwapRoundTripConfig::new(network, party.clone());
+    let result = run_swap_round_trip(&client, &kp, privk, &config).await.expect("round trip");
+    println!("{:?}", result);
+    verify_round_trip(&result).expect("verify");
+
+    // BTC -> RDG leg paid the depositing key
+    assert!(result.rdg_fulfillment.output_amount_of(&kp.address_typed()) > 0);
+    // RDG -> BTC leg sent the configured amount to the party and was paid out in BTC
+    let withdrawal = CurrencyAmount::from_fractional(config.rdg_withdrawal_amount).expect("amount");
+    let party_address = party.address().expect("party address");
+    assert_eq!(result.rdg_withdrawal.output_swap_amount_of(&party_address), withdrawal.amount);
+    assert!(result.btc_fulfillment_amount > 0);
+    assert_ne!(result.btc_deposit_txid, result.btc_fulfillment_txid);
+    // The party's stored fills record both legs as fulfilled
+    let asks = result.new_fills.iter().filter(|f| f.is_ask).count();
+    let bids = result.new_fills.iter().filter(|f| !f.is_ask && f.rdg_amount as i64 == withdrawal.amount).count();
+    assert!(asks >= 1, "no fulfilled BTC deposit recorded: {:?}", result.new_fills);
+    assert!(bids >= 1, "no fulfilled RDG withdrawal recorded: {:?}", result.new_fills);
+}
@@ -14,7 +14,7 @@ use rand::rngs::OsRng;
 use redgold_keys::util::dhash_str;
 
 use crate::node_config::NodeConfig;
-use crate::observability::trace_setup::init_tracing;
+use crate::observability::trace_setup::{init_tracing, init_tracing_otlp};
 use crate::schema::SafeBytesAccess;
 
 pub mod auto_update;
@@ -29,6 +29,8 @@ pub mod hashviz;
 pub mod keys;
 pub mod test_util;
 pub mod argon_kdf;
+pub mod key_bundle;
+pub mod secrets;
 
 pub fn random_salt() -> i64 {
     let mut rng = rand::thread_rng();
@@ -149,9 +151,9 @@ pub fn init_logger_once() {
     });
 }
 
-pub fn init_logger_main(log_level: String) {
+pub fn init_logger_main(log_level: String, otlp_endpoint: Option<String>) {
     INIT.call_once(|| {
-        init_tracing(&log_level);
+        init_tracing_otlp(&log_level, otlp_endpoint);
     });
 }
 
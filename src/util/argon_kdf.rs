@@ -1,7 +1,6 @@
 use argon2::Algorithm::Argon2d;
 use argon2::{Argon2, Params, Version};
-use redgold_schema::{ErrorInfoContext, RgResult, structs};
-use redgold_schema::structs::ErrorInfo;
+use redgold_schema::{error_info, RgResult, structs};
 use crate::util::current_time_millis;
 
 
@@ -9,14 +8,12 @@ pub fn argon2d_hash(salt: Vec<u8>, password: Vec<u8>, m_cost: u32, t_cost: u32,
                     -> RgResult<Vec<u8>> {
 
     let params = Params::new(m_cost, t_cost, p_cost, Some(32))
-        .expect("");
-        //.error_info("bad parameters")?;
+        .map_err(|e| error_info(format!("Invalid argon2 parameters: {}", e)))?;
     let arg = Argon2::new(Argon2d, Version::V0x13, params);
 
     let mut output_key_material = [0u8; 32]; // Can be any desired size
     arg.hash_password_into(&*password, &*salt, &mut output_key_material)
-        .expect("");
-        // .error_info("hashing failed")?;
+        .map_err(|e| error_info(format!("Argon2 hashing failed: {}", e)))?;
     Ok(output_key_material.to_vec())
 }
 
@@ -19,25 +19,34 @@ use log::{error, info};
 use tokio::runtime::Runtime;
 
 use redgold_data::data_store::DataStore;
+use redgold_keys::util::btc_backend::BitcoinBackendConfig;
+use crate::core::transact::fee_policy::raw_fee_amount;
+use crate::api::access::ApiAccessConfig;
+use crate::util::secrets::SecretsConfig;
 use redgold_keys::util::mnemonic_support::WordsPass;
 use redgold_schema::{error_info, ErrorInfoContext, from_hex, RgResult, SafeBytesAccess, SafeOption};
 use redgold_schema::constants::default_node_internal_derivation_path;
 use redgold_schema::EasyJson;
+use redgold_schema::errors::EnhanceErrorInfo;
 use redgold_schema::seeds::get_seeds_by_env;
+use crate::core::seed_list::{load_seed_list, merge_seeds};
+use crate::util::cli::help::write_completions;
 use redgold_schema::servers::Server;
-use redgold_schema::structs::{ErrorInfo, Hash, PeerId, Seed, TrustData};
+use redgold_schema::structs::{ErrorInfo, Hash, PeerId, PublicKey, Seed, TrustData};
 
 use crate::{e2e, gui, util};
 use crate::api::RgHttpClient;
-use crate::node_config::NodeConfig;
+use crate::node_config::{FEATURE_FLAG_OVERRIDES, FeatureFlags, NodeConfig};
 // use crate::gui::image_capture::debug_capture;
 use crate::observability::logging::Loggable;
 use crate::observability::metrics_registry;
+use crate::observability::metrics_registry::MetricsMode;
 use crate::schema::structs::NetworkEnvironment;
 use crate::util::{init_logger, init_logger_main, ip_lookup, not_local_debug_mode, sha256_vec};
 use crate::util::cli::{args, commands};
 use crate::util::cli::args::{GUI, NodeCli, RgArgs, RgTopLevelSubcommand, TestCaptureCli};
 use crate::util::cli::data_folder::DataFolder;
+use crate::genesis::GenesisManifest;
 
 // https://github.com/mehcode/config-rs/blob/master/examples/simple/src/main.rs
 
@@ -139,9 +148,12 @@ impl ArgTranslate {
         self.immediate_debug();
         self.set_gui_on_empty();
         self.check_load_logger()?;
+        self.secrets().await?;
         self.determine_network()?;
         self.ports();
-        metrics_registry::register_metrics(self.node_config.port_offset);
+        // Only a node binds the scrape endpoint, everything else keeps metrics in process
+        let metrics_mode = if self.is_node() { MetricsMode::Exporter } else { MetricsMode::InProcess };
+        metrics_registry::register_metrics(self.node_config.port_offset, metrics_mode);
         self.data_folder()?;
         self.secure_data_folder();
         self.load_mnemonic().await?;
@@ -150,11 +162,11 @@ impl ArgTranslate {
         self.set_public_key();
         self.load_internal_servers()?;
         self.calculate_executable_checksum_hash();
-        self.guard_faucet();
-        self.e2e_enable();
         self.configure_seeds().await;
         self.set_discovery_interval();
         self.apply_node_opts();
+        self.feature_flags().await?;
+        self.genesis_manifest()?;
         self.genesis();
         self.alias();
 
@@ -174,17 +186,67 @@ impl ArgTranslate {
         Ok(())
     }
 
+    /// Resolves REDGOLD_SECRET_REF_ references into the environment, api keys not passed
+    /// directly are taken from them.
+    async fn secrets(&mut self) -> RgResult<()> {
+        let mut secrets = SecretsConfig::from_vars(std::env::vars())?;
+        secrets.resolve_all().await?;
+        let access = &mut self.node_config.api_access;
+        for (key, name) in [
+            (&mut access.public_read_api_key, "REDGOLD_PUBLIC_API_KEY"),
+            (&mut access.faucet_api_key, "REDGOLD_FAUCET_API_KEY"),
+            (&mut access.control_api_key, "REDGOLD_CONTROL_API_KEY"),
+            (&mut access.client_api_key, "REDGOLD_API_KEY"),
+        ] {
+            if key.is_none() {
+                *key = secrets.get(name);
+            }
+        }
+        self.node_config.secrets = secrets;
+        Ok(())
+    }
+
     fn set_discovery_interval(&mut self) {
         if !self.node_config.is_local_debug() {
             self.node_config.discovery_interval = Duration::from_secs(60)
         }
     }
 
-    fn guard_faucet(&mut self) {
+    async fn feature_flags(&mut self) -> RgResult<()> {
+        let mut flags = FeatureFlags::for_network(&self.node_config.network);
+        if self.is_node() {
+            let ds = self.node_config.data_store().await;
+            let stored = match ds.run_migrations().await {
+                Ok(_) => ds.config_store.get_typed(&FEATURE_FLAG_OVERRIDES).await,
+                Err(e) => Err(e),
+            };
+            match stored {
+                Ok(Some(o)) => flags.apply(&o)?,
+                Ok(None) => {}
+                Err(e) => error!("Failed to load feature flag overrides: {}", e.json_or()),
+            }
+        }
+        // --feature-flags falls back to REDGOLD_FEATURE_FLAGS when not passed
+        if let Some(o) = &self.opts.feature_flags {
+            flags.apply(&FeatureFlags::parse_overrides(o)?)?;
+        }
+        if self.opts.disable_e2e {
+            flags.live_e2e = false;
+        }
+        if self.opts.watcher_dry_run {
+            flags.amm_dry_run = true;
+        }
         // Only enable on main if CLI flag with additional precautions
         if self.node_config.network == NetworkEnvironment::Main {
-            self.node_config.faucet_enabled = false;
-        }
+            let forced = match &self.opts.subcmd {
+                Some(RgTopLevelSubcommand::Node(n)) => n.debug_enable_faucet,
+                _ => false,
+            };
+            flags.faucet = forced;
+        }
+        info!("Feature flags: {:?}", flags);
+        self.node_config.features = flags;
+        Ok(())
     }
 
     async fn lookup_ip(&mut self) {
@@ -418,7 +480,71 @@ impl ArgTranslate {
             }
         }
         if enable_logger {
-            init_logger_main(log_level.clone());
+            init_logger_main(log_level.clone(), self.opts.otlp_endpoint.clone());
+        }
+        self.node_config.telemetry_endpoint = self.opts.telemetry_endpoint.clone();
+        self.node_config.btc_backend = if self.opts.btc_esplora || self.opts.btc_esplora_url.is_some() {
+            BitcoinBackendConfig::Esplora(self.opts.btc_esplora_url.clone())
+        } else {
+            BitcoinBackendConfig::Electrum(self.opts.btc_electrum_url.clone())
+        };
+        self.node_config.api_access = ApiAccessConfig {
+            cors_origins: self.opts.cors_origins.as_ref()
+                .map(|o| o.split(",").map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect())
+                .unwrap_or(vec![]),
+            public_read_api_key: self.opts.public_api_key.clone(),
+            faucet_api_key: self.opts.faucet_api_key.clone(),
+            control_api_key: self.opts.control_api_key.clone(),
+            client_api_key: self.opts.api_key.clone(),
+        };
+        if let Some(t) = self.opts.acceptance_threshold {
+            self.node_config.observation.acceptance_weight_threshold = t;
+        }
+        if let Some(d) = self.opts.retention_days {
+            self.node_config.retention.max_age = Some(Duration::from_secs(d * 60 * 60 * 24));
+        }
+        if let Some(mb) = self.opts.retention_max_mb {
+            self.node_config.retention.max_size_bytes = Some(mb * 1024 * 1024);
+        }
+        if let Some(mb) = self.opts.min_free_disk_mb {
+            self.node_config.disk_space.min_free_bytes = mb * 1024 * 1024;
+        }
+        if let Some(t) = self.opts.signature_verify_threads {
+            self.node_config.tx_config.signature_verify_parallelism = t;
+        }
+        if let Some(a) = self.opts.peer_read_attempts {
+            self.node_config.peer_retry.max_attempts = a.max(1);
+        }
+        if let Some(ms) = self.opts.peer_hedge_after_ms {
+            self.node_config.peer_retry.hedge_after = Duration::from_millis(ms);
+        }
+        if let Some(w) = &self.opts.btc_faucet_words {
+            self.node_config.test_liquidity.btc_faucet_words = Some(w.clone());
+        }
+        if self.opts.disable_test_liquidity {
+            self.node_config.test_liquidity.enabled = false;
+        }
+        if self.opts.from_checkpoint {
+            self.node_config.checkpoint.from_checkpoint = true;
+        }
+        if let Some(ms) = self.opts.peer_request_timeout_ms {
+            self.node_config.peer_rx.request_timeout = Duration::from_millis(ms);
+        }
+        if let Some(s) = &self.opts.seed_list_sources {
+            self.node_config.seed_list.sources = s.split(",").map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty()).collect_vec();
+        }
+        if let Some(k) = &self.opts.seed_list_release_keys {
+            self.node_config.seed_list.release_keys = k.split(",").map(|s| s.trim())
+                .filter(|s| !s.is_empty())
+                .map(|s| PublicKey::from_hex(s).add("Invalid seed list release key"))
+                .collect::<RgResult<Vec<PublicKey>>>()?;
+        }
+        if let Some(f) = self.opts.min_relay_fee {
+            self.node_config.fee_policy.min_relay_fee = raw_fee_amount(f);
+        }
+        if let Some(f) = self.opts.fee_per_kb {
+            self.node_config.fee_policy.fee_per_kb = raw_fee_amount(f);
         }
         self.node_config.enable_logging = enable_logger;
         self.node_config.log_level = log_level.clone();
@@ -458,18 +584,6 @@ impl ArgTranslate {
         Ok(())
     }
 
-    fn e2e_enable(&mut self) {
-
-        if self.opts.disable_e2e {
-            self.node_config.e2e_enabled = false;
-        }
-        // std::env::var("REDGOLD_ENABLE_E2E").ok().map(|b| {
-        //     self.node_config.e2e_enable = true;
-        // }
-        // self.opts.enable_e2e.map(|_| {
-        //     self.node_config.e2e_enable = true;
-        // });
-    }
     async fn configure_seeds(&mut self) {
 
         let seeds = get_seeds_by_env(&self.node_config.network);
@@ -477,6 +591,23 @@ impl ArgTranslate {
             self.node_config.seeds.push(seed);
         }
 
+        if self.is_node() && self.node_config.seed_list.enabled() {
+            let ds = self.node_config.data_store().await;
+            let cfg = self.node_config.seed_list.clone();
+            let loaded = match ds.run_migrations().await {
+                Ok(_) => load_seed_list(&ds, &cfg.sources, &self.node_config.network, &cfg.release_keys).await,
+                Err(e) => Err(e),
+            };
+            match loaded {
+                Ok(Some(l)) => {
+                    info!("Using seed list issued at {} with {} seeds", l.issued_time, l.seeds.len());
+                    self.node_config.seeds = merge_seeds(&self.node_config.seeds, &l.seeds);
+                }
+                Ok(None) => {}
+                Err(e) => error!("Failed to load seed list: {}", e.json_or()),
+            }
+        }
+
 
         let port = self.node_config.public_port();
         // Enrich keys for missing seed info
@@ -485,13 +616,16 @@ impl ArgTranslate {
                 if seed.public_key.is_none() {
                     info!("Querying seed: {}", seed.external_address.clone());
 
-                    let response = RgHttpClient::new(
+                    let mut client = RgHttpClient::new(
                         seed.external_address.clone(),
                                                      port, // TODO: Account for seed listed offset instead of direct.
                                                      // seed.port_offset.map(|p| (p + 1) as u16)
                                                      //     .unwrap_or(port),
                                                      None
-                    ).about().await;
+                    );
+                    // An unreachable seed only leaves its key unknown, so don't hold up startup
+                    client.timeout = Duration::from_secs(5);
+                    let response = client.about().await;
                     if let Ok(response) = response {
                         let nmd = response.peer_node_info.as_ref()
                             .and_then(|n| n.latest_node_transaction.as_ref())
@@ -534,6 +668,7 @@ impl ArgTranslate {
                 trust: vec![TrustData::from_label(1.0)],
                 peer_id: None, // Some(self.node_config.peer_id()),
                 public_key: None, //Some(self.node_config.public_key()),
+                genesis_manifest_hash: None,
             });
         }
 
@@ -545,10 +680,46 @@ impl ArgTranslate {
                 if let Some(i) = &node_cli.live_e2e_interval {
                     self.node_config.live_e2e_interval = Duration::from_secs(i.clone());
                 }
+                self.node_config.seed_mode = node_cli.seed_mode;
             }
             _ => {}
         }
     }
+    fn genesis_manifest(&mut self) -> RgResult<()> {
+        let path = match &self.opts.genesis_manifest {
+            Some(p) => Some(PathBuf::from(p)),
+            None => {
+                let default = self.node_config.env_data_folder().genesis_manifest_path();
+                if default.exists() { Some(default) } else { None }
+            }
+        };
+        let pinned = self.node_config.seeds.iter()
+            .flat_map(|s| s.genesis_manifest_hash.clone())
+            .collect_vec();
+        if path.is_none() {
+            // Only a node builds genesis, CLI commands don't need the manifest
+            if !pinned.is_empty() && self.is_node() {
+                return Err(error_info(
+                    "Seeds pin a genesis manifest hash but no manifest was found, pass --genesis-manifest or \
+                    place it in the data folder"
+                ));
+            }
+            return Ok(());
+        }
+        let path = path.expect("path");
+        let manifest = GenesisManifest::read(&path)?;
+        manifest.validate(&self.node_config.network)?;
+        let hash = manifest.hash()?;
+        if let Some(h) = pinned.iter().find(|h| *h != &hash) {
+            return Err(error_info(format!(
+                "Genesis manifest hash {} does not match seed pinned hash {}", hash.hex(), h.hex()
+            )));
+        }
+        info!("Loaded genesis manifest {} with hash {}", path.to_string_lossy(), hash.hex());
+        self.node_config.genesis_manifest = Some(manifest);
+        Ok(())
+    }
+
     fn genesis(&mut self) {
         if let Some(o) = std::env::var("REDGOLD_GENESIS").ok() {
             if let Ok(b) = o.parse::<bool>() {
@@ -575,7 +746,7 @@ impl ArgTranslate {
         // println!("args: {:?}", self.args.clone());
 
         if self.args.len() == 1 || self.opts.subcmd.is_none() {
-            self.opts.subcmd = Some(RgTopLevelSubcommand::GUI(GUI{}));
+            self.opts.subcmd = Some(RgTopLevelSubcommand::GUI(GUI::default()));
         }
 
     }
@@ -703,6 +874,15 @@ pub async fn immediate_commands(opts: &RgArgs, config: &NodeConfig,
                 RgTopLevelSubcommand::Address(a) => {
                     commands::generate_address(a.clone(), &config).map(|_| ())
                 }
+                RgTopLevelSubcommand::Derive(d) => {
+                    commands::derive(d, &config)
+                }
+                RgTopLevelSubcommand::Sweep(s) => {
+                    commands::sweep(s, &config).await
+                }
+                RgTopLevelSubcommand::LogLevel(l) => {
+                    commands::log_level(l, &config).await
+                }
                 RgTopLevelSubcommand::Send(a) => {
                     commands::send(&a, &config).await
                 }
@@ -725,6 +905,43 @@ pub async fn immediate_commands(opts: &RgArgs, config: &NodeConfig,
                     commands::deploy(d, &config).await.unwrap().abort();
                     Ok(())
                 }
+                RgTopLevelSubcommand::TxValidate(t) => {
+                    commands::tx_validate(t, &config).await
+                }
+                RgTopLevelSubcommand::Config(c) => {
+                    commands::config(c, &config).await
+                }
+                RgTopLevelSubcommand::VerifyMessage(v) => {
+                    commands::verify_message(v)
+                }
+                RgTopLevelSubcommand::Prune(p) => {
+                    commands::prune(p, &config).await
+                }
+                RgTopLevelSubcommand::BridgeReconcile(b) => {
+                    commands::bridge_reconcile(b, &config).await
+                }
+                RgTopLevelSubcommand::NetworkInit(n) => {
+                    commands::network_init(n, &config).await
+                }
+                RgTopLevelSubcommand::AmmSim(a) => {
+                    commands::amm_sim(a)
+                }
+                RgTopLevelSubcommand::ColdSweep(c) => {
+                    commands::cold_sweep(c, &config).await
+                }
+                RgTopLevelSubcommand::BtcCheckpoint(b) => {
+                    commands::btc_checkpoint(b, &config)
+                }
+                RgTopLevelSubcommand::SignSeedList(s) => {
+                    commands::sign_seed_list(s, &config)
+                }
+                RgTopLevelSubcommand::Vectors(v) => {
+                    commands::vectors(v)
+                }
+                RgTopLevelSubcommand::Completions(c) => {
+                    write_completions(c.shell, &mut std::io::stdout());
+                    Ok(())
+                }
                 RgTopLevelSubcommand::TestBitcoinBalance(_b) => {
                     commands::test_btc_balance(args.get(0).unwrap(), config.network.clone()).await;
                     Ok(())
@@ -1,4 +1,5 @@
 use clap::{Args, Parser, Subcommand};
+use crate::util::cli::help::{BALANCE_EXAMPLES, COMPLETIONS_EXAMPLES, DEPLOY_EXAMPLES, FAUCET_EXAMPLES, QUERY_EXAMPLES, SEND_EXAMPLES, TOP_LEVEL_HELP};
 
 
 pub fn empty_args() -> RgArgs {
@@ -15,18 +16,46 @@ pub fn empty_args() -> RgArgs {
         disable_auto_update: false,
         subcmd: None,
         genesis: false,
+        genesis_manifest: None,
         seed_address: None,
         seed_port_offset: None,
         enable_e2e: true,
         disable_e2e: false,
         log_level: None,
         development_mode: false,
+        otlp_endpoint: None,
+        telemetry_endpoint: None,
+        btc_electrum_url: None,
+        btc_esplora: false,
+        btc_esplora_url: None,
+        cors_origins: None,
+        public_api_key: None,
+        faucet_api_key: None,
+        control_api_key: None,
+        api_key: None,
+        acceptance_threshold: None,
+        retention_days: None,
+        retention_max_mb: None,
+        min_free_disk_mb: None,
+        signature_verify_threads: None,
+        peer_read_attempts: None,
+        peer_hedge_after_ms: None,
+        btc_faucet_words: None,
+        disable_test_liquidity: false,
+        from_checkpoint: false,
+        watcher_dry_run: false,
+        feature_flags: None,
+        peer_request_timeout_ms: None,
+        seed_list_sources: None,
+        seed_list_release_keys: None,
+        min_relay_fee: None,
+        fee_per_kb: None,
     }
 }
 
 /// Welcome to Redgold CLI -- here you can run a GUI, node, or use wallet or other CLI commands.
 #[derive(Parser, Debug, Clone)]
-#[clap(author, version, about, long_about = None)]
+#[clap(author, version, about, long_about = None, after_help = TOP_LEVEL_HELP)]
 pub struct RgArgs {
     /// Load configs from a specified path instead of standard path
     #[clap(short, long)]
@@ -62,6 +91,10 @@ pub struct RgArgs {
     /// Used to indicate the node is starting from genesis, only used for manual network
     /// initialization
     pub genesis: bool,
+    #[clap(long, env="REDGOLD_GENESIS_MANIFEST")]
+    /// Path to a genesis manifest json with custom allocations for a non-main network, defaults
+    /// to genesis.json in the network data folder if present
+    pub genesis_manifest: Option<String>,
     #[clap(long)]
     /// Seed network address, only used for local testing and manually connecting to a specific
     /// network
@@ -84,6 +117,98 @@ pub struct RgArgs {
     /// Instead of Main for network for instance.
     #[clap(long, env = "REDGOLD_DEVELOPMENT_MODE")]
     pub development_mode: bool,
+    /// OpenTelemetry OTLP gRPC collector endpoint for exporting traces, i.e. http://localhost:4317
+    #[clap(long, env = "REDGOLD_OTLP_ENDPOINT")]
+    pub otlp_endpoint: Option<String>,
+    /// Opt in to sending crash and critical error reports, including a tail of recent logs,
+    /// as JSON POST requests to this url. Reports are always written to the data folder
+    #[clap(long, env = "REDGOLD_TELEMETRY_ENDPOINT")]
+    pub telemetry_endpoint: Option<String>,
+    /// Electrum server used for bitcoin wallet operations, overrides the network default,
+    /// i.e. tcp://127.0.0.1:50001 for a local regtest electrs
+    #[clap(long, env = "REDGOLD_BTC_ELECTRUM_URL")]
+    pub btc_electrum_url: Option<String>,
+    /// Use an esplora HTTP API for bitcoin wallet operations instead of electrum
+    #[clap(long, env = "REDGOLD_BTC_ESPLORA")]
+    pub btc_esplora: bool,
+    /// Esplora API base url, implies --btc-esplora, i.e. https://blockstream.info/api
+    #[clap(long, env = "REDGOLD_BTC_ESPLORA_URL")]
+    pub btc_esplora_url: Option<String>,
+    /// Comma separated list of origins allowed by CORS on the public and explorer APIs, defaults to any
+    #[clap(long, env = "REDGOLD_CORS_ORIGINS")]
+    pub cors_origins: Option<String>,
+    /// Require this key in the x-api-key header for public read endpoints
+    #[clap(long, env = "REDGOLD_PUBLIC_API_KEY")]
+    pub public_api_key: Option<String>,
+    /// Require this key in the x-api-key header for faucet requests
+    #[clap(long, env = "REDGOLD_FAUCET_API_KEY")]
+    pub faucet_api_key: Option<String>,
+    /// Require this key in the x-api-key header for the control API
+    #[clap(long, env = "REDGOLD_CONTROL_API_KEY")]
+    pub control_api_key: Option<String>,
+    /// Key sent in the x-api-key header when making requests to other nodes
+    #[clap(long, env = "REDGOLD_API_KEY")]
+    pub api_key: Option<String>,
+    /// Summed trust (0-10 per node) of accepting nodes required before a transaction is
+    /// reported as finalized by the API, defaults to 10 i.e. one fully trusted node
+    #[clap(long, env = "REDGOLD_ACCEPTANCE_THRESHOLD")]
+    pub acceptance_threshold: Option<f64>,
+    /// Periodically prune transaction and observation history older than this many days,
+    /// data needed to validate or spend current outputs is always kept
+    #[clap(long, env = "REDGOLD_RETENTION_DAYS")]
+    pub retention_days: Option<u64>,
+    /// Periodically prune the oldest history while the datastore exceeds this size in megabytes
+    #[clap(long, env = "REDGOLD_RETENTION_MAX_MB")]
+    pub retention_max_mb: Option<u64>,
+    /// Pause non-essential datastore writes when free disk space drops below this many megabytes,
+    /// default 512
+    #[clap(long, env = "REDGOLD_MIN_FREE_DISK_MB")]
+    pub min_free_disk_mb: Option<u64>,
+    /// Threads used to verify transaction signatures in parallel, 0 for one per core
+    /// (the default) and 1 to verify sequentially
+    #[clap(long, env = "REDGOLD_SIGNATURE_VERIFY_THREADS")]
+    pub signature_verify_threads: Option<usize>,
+    /// Peers a read only query such as a download may be sent to before failing, default 3
+    #[clap(long, env = "REDGOLD_PEER_READ_ATTEMPTS")]
+    pub peer_read_attempts: Option<usize>,
+    /// Milliseconds without a response before a read only query is also sent to another peer,
+    /// default 2000
+    #[clap(long, env = "REDGOLD_PEER_HEDGE_AFTER_MS")]
+    pub peer_hedge_after_ms: Option<u64>,
+    /// Mnemonic holding testnet BTC used to keep the AMM funded on dev networks
+    #[clap(long, env = "REDGOLD_BTC_FAUCET_WORDS")]
+    pub btc_faucet_words: Option<String>,
+    /// Disable automatic AMM test liquidity top ups on dev networks
+    #[clap(long)]
+    pub disable_test_liquidity: bool,
+    /// On restart only verify history after the latest signed state checkpoint instead of
+    /// re-verifying every retained checkpoint
+    #[clap(long, env = "REDGOLD_FROM_CHECKPOINT")]
+    pub from_checkpoint: bool,
+    /// Run the deposit watcher without signing or broadcasting fulfillments, recording what
+    /// would have been sent instead
+    #[clap(long, env = "REDGOLD_WATCHER_DRY_RUN")]
+    pub watcher_dry_run: bool,
+    /// Comma separated feature flag overrides applied over the network defaults, such as
+    /// faucet=false,amm_dry_run=true
+    #[clap(long, env = "REDGOLD_FEATURE_FLAGS")]
+    pub feature_flags: Option<String>,
+    /// Milliseconds an incoming peer request may take before it's cancelled and a timeout
+    /// error returned, default 30000. Multiparty keygen and signing aren't affected
+    #[clap(long, env = "REDGOLD_PEER_REQUEST_TIMEOUT_MS")]
+    pub peer_request_timeout_ms: Option<u64>,
+    /// Comma separated http(s) urls or dns:<name> TXT records serving signed seed lists
+    #[clap(long, env = "REDGOLD_SEED_LIST_SOURCES")]
+    pub seed_list_sources: Option<String>,
+    /// Comma separated hex public keys trusted to sign seed lists, required to use them
+    #[clap(long, env = "REDGOLD_SEED_LIST_RELEASE_KEYS")]
+    pub seed_list_release_keys: Option<String>,
+    /// Minimum fee in RDG this node requires on transactions sending currency, default 0
+    #[clap(long, env = "REDGOLD_MIN_RELAY_FEE")]
+    pub min_relay_fee: Option<f64>,
+    /// Additional fee in RDG required per kilobyte of serialized transaction, default 0
+    #[clap(long, env = "REDGOLD_FEE_PER_KB")]
+    pub fee_per_kb: Option<f64>,
 
 }
 
@@ -106,19 +231,47 @@ pub enum RgTopLevelSubcommand {
     GenerateWords(GenerateMnemonic),
     GenerateRandomWords(GenerateRandomWords),
     Send(WalletSend),
+    Sweep(SweepCli),
     Address(WalletAddress),
+    Derive(DeriveCli),
+    LogLevel(LogLevelCli),
     Query(QueryCli),
     Faucet(FaucetCli),
     Balance(BalanceCli),
     TestTransaction(TestTransactionCli),
     TestCapture(TestCaptureCli),
     TestBitcoinBalance(TestBitcoinBalanceCli),
+    TxValidate(TxValidateCli),
+    Config(ConfigCli),
+    VerifyMessage(VerifyMessageCli),
+    Prune(PruneCli),
+    BridgeReconcile(BridgeReconcileCli),
+    NetworkInit(NetworkInitCli),
+    AmmSim(AmmSimCli),
+    ColdSweep(ColdSweepCli),
+    BtcCheckpoint(BtcCheckpointCli),
+    SignSeedList(SignSeedListCli),
+    Completions(CompletionsCli),
+    Vectors(VectorsCli),
+}
+
+/// Print a shell completion script for all commands and options
+#[derive(Args, Debug, Clone)]
+#[clap(after_help = COMPLETIONS_EXAMPLES)]
+pub struct CompletionsCli {
+    /// Shell to generate completions for
+    #[clap(value_enum)]
+    pub shell: clap_complete::Shell,
 }
 
 
 /// Run a native gui client
-#[derive(Args, Debug, Clone)]
-pub struct GUI {}
+#[derive(Args, Debug, Clone, Default)]
+pub struct GUI {
+    /// Payment link (redgold: or bitcoin:) to open pre-filled on the send view
+    #[clap(long)]
+    pub open_uri: Option<String>,
+}
 
 /// Run a peer to peer node
 #[derive(Args, Debug, Clone)]
@@ -128,7 +281,10 @@ pub struct NodeCli {
     pub debug_enable_faucet: bool,
     /// E2E test interval
     #[clap(long)]
-    pub live_e2e_interval: Option<u64>
+    pub live_e2e_interval: Option<u64>,
+    /// Run as a public seed node, scoring and pruning peers and only serving essential requests
+    #[clap(long, env = "REDGOLD_SEED_MODE")]
+    pub seed_mode: bool,
 }
 
 
@@ -174,6 +330,7 @@ pub struct RemoveServer {
 
 /// Deploy all servers -- will overwrite existing software if present
 #[derive(Args, Debug, Clone, Default)]
+#[clap(after_help = DEPLOY_EXAMPLES)]
 pub struct Deploy {
     /// Purge stored data
     #[clap(short, long)]
@@ -222,6 +379,23 @@ pub struct Deploy {
     pub mixing_password: Option<String>,
     #[clap(long)]
     pub server_offline_info: Option<String>,
+    /// Docker image tag to deploy, overrides the server manifest tag, defaults to the network name
+    #[clap(long)]
+    pub image_tag: Option<String>,
+    /// Re-deploy the image tag recorded before the most recent deploy of each server
+    #[clap(long)]
+    pub rollback: bool,
+    /// Derive each server's mnemonic with standard BIP-85 (24 words, server index as child index)
+    /// instead of the legacy hash derivation. Changes the derived keys of existing servers
+    #[clap(long)]
+    pub bip85: bool,
+    /// Secret reference for the node env as NAME=reference, repeatable. env:VAR is read locally
+    /// and copied, file:/path and aws-sm:secret_id#key are resolved by the node at runtime
+    #[clap(long)]
+    pub secret: Vec<String>,
+    /// Ops logging stack, elastic (filebeat and elasticsearch, default) or loki (promtail and Loki)
+    #[clap(long)]
+    pub ops_profile: Option<String>,
 
 }
 
@@ -229,14 +403,44 @@ pub struct Deploy {
 
 /// Send a transaction from current wallet to an address
 #[derive(Args, Debug, Clone)]
+#[clap(after_help = SEND_EXAMPLES)]
 pub struct WalletSend {
+    /// Destination address
     #[clap(short, long)]
     pub to: String,
+    /// Amount of RDG to send
     #[clap(short, long)]
     pub amount: f64,
     #[clap(short, long)]
     pub from: Option<String>,
+    /// UTXO selection strategy, one of SmallestFirst, LargestFirst, OldestFirst or
+    /// BranchAndBound, defaults to the node config strategy
+    #[clap(long)]
+    pub coin_selection: Option<String>,
+    /// Fee in RDG paid to the node, estimated from the node's fee policy when not set
+    #[clap(long)]
+    pub fee: Option<f64>,
+    /// Send change to a fresh internal chain address instead of back to the spending address
+    #[clap(long)]
+    pub rotate_change: bool,
+}
 
+/// Sweep all funds controlled by a single key into a destination address
+#[derive(Args, Debug, Clone)]
+pub struct SweepCli {
+    /// Private key hex, a path to a file containing it, or a derivation path (m/...) applied
+    /// to the loaded mnemonic
+    #[clap(long)]
+    pub from_key: String,
+    /// Destination address for swept RDG
+    #[clap(long)]
+    pub to: String,
+    /// Destination address for sweeping BTC held by the key's P2WPKH address, skipped if empty
+    #[clap(long)]
+    pub btc_to: Option<String>,
+    /// Build and print the transactions without broadcasting
+    #[clap(long)]
+    pub dry_run: bool,
 }
 
 /// Generate an address from an existing wallet or key store
@@ -250,15 +454,82 @@ pub struct WalletAddress {
     pub path: Option<String>,
 }
 
+/// Derive addresses and public keys for all supported chains from a mnemonic or xpub, for use
+/// in offline key management.
+#[derive(Args, Debug, Clone)]
+pub struct DeriveCli {
+    /// Mnemonic words to derive from, defaults to the loaded wallet mnemonic if no xpub is given
+    #[clap(long)]
+    pub mnemonic: Option<String>,
+    /// Extended public key to derive from, children are derived at <xpub>/0/<index>
+    #[clap(long)]
+    pub xpub: Option<String>,
+    /// Account level derivation path used with a mnemonic, default m/44'/16180'/0'
+    #[clap(long)]
+    pub path: Option<String>,
+    /// Currency to render addresses for, one of rdg, btc, eth or all, default all
+    #[clap(long)]
+    pub currency: Option<String>,
+    /// Number of sequential child indexes to derive, default 1
+    #[clap(long, default_value = "1")]
+    pub count: u32,
+}
+
+/// Show or change the log level of a running local node through its control port
+#[derive(Args, Debug, Clone)]
+pub struct LogLevelCli {
+    /// New level, i.e. TRACE, DEBUG, INFO, WARN, ERROR, prints the current filter if empty
+    pub level: Option<String>,
+    /// Module to apply the level to, i.e. redgold::core::watcher, default all redgold logs
+    #[clap(long)]
+    pub target: Option<String>,
+}
+
+/// Validate a serialized transaction against a node without submitting it
+#[derive(Args, Debug, Clone)]
+pub struct TxValidateCli {
+    /// Transaction as JSON or hex encoded proto bytes
+    pub transaction: Option<String>,
+    /// Path to a file containing the transaction as JSON or hex encoded proto bytes
+    #[clap(long)]
+    pub path: Option<String>,
+}
+
+/// Verify a signed message proof, printing the signing address if valid
+#[derive(Args, Debug, Clone)]
+pub struct VerifyMessageCli {
+    /// Signed message proof JSON
+    pub proof: Option<String>,
+    /// Path to a file containing the signed message proof JSON
+    #[clap(long)]
+    pub path: Option<String>,
+}
+
+/// Dump or edit entries in the local node config store
+#[derive(Args, Debug, Clone)]
+pub struct ConfigCli {
+    /// Config key to show or edit, dumps all entries if empty
+    pub key: Option<String>,
+    /// New JSON value for the key, typed entries are validated before being stored
+    #[clap(long)]
+    pub set: Option<String>,
+    /// Remove the entry for the key
+    #[clap(long)]
+    pub delete: bool,
+}
+
 /// Query the network for information on a particular hash
 #[derive(Args, Debug, Clone)]
+#[clap(after_help = QUERY_EXAMPLES)]
 pub struct QueryCli {
+    /// Transaction hash, address, or peer id to look up
     #[clap(long)]
     pub hash: String,
 }
 
 /// Request funds from the faucet, returns transaction hash associated with faucet transfer.
 #[derive(Args, Debug, Clone)]
+#[clap(after_help = FAUCET_EXAMPLES)]
 pub struct FaucetCli {
     /// Address to send funds to
     #[clap(short, long)]
@@ -270,6 +541,7 @@ pub struct FaucetCli {
 
 /// Check the balance of an address
 #[derive(Args, Debug, Clone)]
+#[clap(after_help = BALANCE_EXAMPLES)]
 pub struct BalanceCli {
     /// Address to check balance of
     #[clap(short, long)]
@@ -321,3 +593,134 @@ pub struct DebugCanary {
     #[clap(long)]
     pub host: Option<String>,
 }
+
+/// Prune historical transactions and observations from the local datastore, keeping anything
+/// referenced by unspent outputs or multiparty records
+#[derive(Args, Debug, Clone)]
+pub struct PruneCli {
+    /// Remove history older than this many days, defaults to the node's retention setting
+    #[clap(long)]
+    pub days: Option<u64>,
+    /// Keep pruning the oldest history until the datastore is under this size in megabytes
+    #[clap(long)]
+    pub max_mb: Option<u64>,
+    /// Rewrite the whole datastore afterwards to defragment it, blocking it until done
+    #[clap(long)]
+    pub vacuum: bool,
+}
+
+/// Compare multiparty bridge rows against recorded BTC fulfillments, reporting duplicate rows and
+/// broadcast fulfillments without a bridge row
+#[derive(Args, Debug, Clone)]
+pub struct BridgeReconcileCli {
+    /// Remove duplicate rows and insert the missing ones instead of only reporting them
+    #[clap(long)]
+    pub repair: bool,
+}
+
+/// Bootstrap a new private network, writing per server mnemonics / peer transactions, a genesis
+/// manifest and a seed list into an output directory for use with deploy
+#[derive(Args, Debug, Clone)]
+pub struct NetworkInitCli {
+    /// Directory to write the generated network files to
+    #[clap(short, long)]
+    pub output: String,
+    /// Servers file to generate keys for, defaults to the servers file in the secure data folder
+    #[clap(long)]
+    pub servers: Option<String>,
+    /// Mnemonic used to derive the server keys, a new one is generated and saved if not supplied
+    #[clap(long)]
+    pub salt_mnemonic: Option<String>,
+    /// Passphrase applied to the salt mnemonic
+    #[clap(long)]
+    pub passphrase: Option<String>,
+    /// Derive server mnemonics with BIP-85
+    #[clap(long)]
+    pub bip85: bool,
+    /// Genesis RDG allocated to each server, defaults to an even split of the max supply
+    #[clap(long)]
+    pub amount: Option<f64>,
+    /// Portion of each server's allocation placed in a stake output
+    #[clap(long)]
+    pub stake_amount: Option<f64>,
+}
+
+/// Simulate the AMM curve against historical prices to tune curve parameters offline
+#[derive(Args, Debug, Clone)]
+pub struct AmmSimCli {
+    /// CSV of `time,price` rows with a header, price in BTC per RDG
+    #[clap(long)]
+    pub prices: String,
+    /// JSON curve config with divisions and scale, defaults to the watcher's curve
+    #[clap(long)]
+    pub curve: Option<String>,
+    /// Starting RDG inventory
+    #[clap(long, default_value = "10000")]
+    pub initial_rdg: f64,
+    /// Starting BTC inventory
+    #[clap(long, default_value = "1")]
+    pub initial_btc: f64,
+    /// BTC value of each simulated taker order
+    #[clap(long, default_value = "0.001")]
+    pub order_btc: f64,
+    /// Write the JSON report to this path in addition to printing it
+    #[clap(short, long)]
+    pub output: Option<String>,
+}
+
+/// Export the node's BTC wallet checkpoints for backup, or import them from one so a redeployed
+/// AMM node only syncs history since the checkpoint
+#[derive(Args, Debug, Clone)]
+pub struct BtcCheckpointCli {
+    /// Write all stored checkpoints to this file
+    #[clap(long)]
+    pub export: Option<String>,
+    /// Store the checkpoints from this file, replacing existing ones for the same wallets
+    #[clap(long)]
+    pub import: Option<String>,
+}
+
+/// Sign a seed list with the configured words for publishing to seed list sources
+#[derive(Args, Debug, Clone)]
+pub struct SignSeedListCli {
+    /// JSON array of seeds to publish
+    #[clap(long)]
+    pub seeds: String,
+    /// Write the signed list to this path instead of printing it
+    #[clap(short, long)]
+    pub output: Option<String>,
+    /// Also print the hex encoding used for a dns TXT record
+    #[clap(long)]
+    pub dns: bool,
+}
+
+/// Regenerate the canonical serialization test vectors, or check them against the committed ones
+#[derive(Args, Debug, Clone)]
+pub struct VectorsCli {
+    /// Write the vectors to this path instead of printing them
+    #[clap(short, long)]
+    pub output: Option<String>,
+    /// Compare against the vectors committed with this build and fail on any difference
+    #[clap(long)]
+    pub check: bool,
+}
+
+/// Move AMM party funds above the hot balance caps to cold storage through a local node's control port
+#[derive(Args, Debug, Clone)]
+pub struct ColdSweepCli {
+    /// Run keysign and broadcast the sweep, otherwise only print the planned amounts
+    #[clap(long)]
+    pub execute: bool,
+    /// Set the cold storage RDG address
+    #[clap(long)]
+    pub rdg_address: Option<String>,
+    /// Set the cold storage BTC address
+    #[clap(long)]
+    pub btc_address: Option<String>,
+    /// Set the RDG balance kept hot for fulfillments
+    #[clap(long)]
+    pub hot_cap_rdg: Option<f64>,
+    /// Set the BTC balance kept hot for fulfillments
+    #[clap(long)]
+    pub hot_cap_btc: Option<f64>,
+}
@@ -1,5 +1,7 @@
 use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
 
 use log::{error, info};
 use rocket::form::FromForm;
@@ -7,31 +9,50 @@ use tokio::task::JoinHandle;
 
 use redgold_data::data_store::DataStore;
 use redgold_keys::KeyPair;
+use redgold_keys::canonical::{CANONICAL_VECTORS, CanonicalVector, generate_vectors, mismatched_vectors};
+use redgold_keys::message_signing::SignedMessage;
 use redgold_keys::transaction_support::TransactionSupport;
 use redgold_keys::util::btc_wallet::SingleKeyBitcoinWallet;
+use redgold_keys::util::btc_checkpoint::BtcWalletCheckpoint;
 use redgold_keys::util::mnemonic_support::WordsPass;
-use redgold_schema::{error_info, ErrorInfoContext, json, json_from, json_pretty, RgResult, SafeBytesAccess, SafeOption, WithMetadataHashable};
+use redgold_keys::address_external::{ToBitcoinAddress, ToEthereumAddress};
+use redgold_keys::xpub_wrapper::XpubWrapper;
+use redgold_schema::{error_info, ErrorInfoContext, json, json_from, json_pretty, ProtoSerde, RgResult, SafeBytesAccess, SafeOption, WithMetadataHashable};
 use redgold_schema::EasyJson;
+use redgold_schema::constants::{redgold_account_path, redgold_keypair_internal_change_path};
 use redgold_schema::servers::Server;
-use redgold_schema::structs::{Address, CurrencyAmount, ErrorInfo, Hash, NetworkEnvironment, Proof, PublicKey};
+use redgold_schema::structs::{Address, ControlColdSweepRequest, CurrencyAmount, ErrorInfo, Hash, NetworkEnvironment, PeerId, Proof, PublicKey, Seed, Transaction, TrustData};
+use redgold_schema::constants::MAX_COIN_SUPPLY;
 use redgold_schema::transaction::rounded_balance_i64;
+use crate::api::control_api::ControlClient;
+use crate::api::public_api::PublicClient;
 use crate::core::internal_message::{Channel, RecvAsyncErrorInfo};
 
-use crate::core::transact::tx_builder_supports::TransactionBuilder;
+use crate::core::transact::fee_policy::FeeEstimate;
+use crate::core::transact::tx_builder_supports::{CoinSelectionStrategy, TransactionBuilder};
 use crate::core::transact::tx_builder_supports::TransactionBuilderSupport;
 use crate::e2e::tx_submit::TransactionSubmitter;
-use crate::infra::deploy::default_deploy;
-use crate::node_config::NodeConfig;
-use crate::util::cli::args::{AddServer, BalanceCli, Deploy, FaucetCli, GenerateMnemonic, QueryCli, TestTransactionCli, WalletAddress, WalletSend};
+use crate::infra::deploy::{default_deploy, offline_generate_keys_servers};
+use crate::genesis::{GenesisManifest, GenesisManifestEntry};
+use crate::node_config::{FEATURE_FLAG_OVERRIDES, FeatureFlags, NodeConfig};
+use crate::util::cli::args::{AddServer, AmmSimCli, BalanceCli, BridgeReconcileCli, BtcCheckpointCli, ColdSweepCli, ConfigCli, SignSeedListCli, Deploy, DeriveCli, FaucetCli, GenerateMnemonic, LogLevelCli, NetworkInitCli, PruneCli, QueryCli, SweepCli, TestTransactionCli, TxValidateCli, VectorsCli, VerifyMessageCli, WalletAddress, WalletSend};
 use crate::util::cmd::run_cmd;
+use redgold_data::config::{ConfigEnvelope, LOCAL_STORED_STATE, SERVERS};
+use redgold_schema::local_stored_state::LocalStoredState;
+use crate::multiparty::party_audit::{PARTY_AUDIT, PartyAudit};
+use crate::multiparty::bridge_reconciliation::reconcile_bridge;
+use crate::multiparty::btc_checkpoint;
+use crate::multiparty::party_stream::{AMM_VOLUME_SUMMARY, AmmVolumeSummary};
+use crate::multiparty::amm_sim::{read_price_csv, simulate};
+use crate::multiparty::watcher::{CurveConfig, DEPOSIT_WATCHER_CONFIG, DepositWatcherConfig};
+use crate::core::retention::prune_datastore;
+use crate::observability::logging::Loggable;
+use crate::util::current_time_millis_i64;
+use crate::core::seed_list::SignedSeedList;
 
 pub async fn add_server(add_server: &AddServer, config: &NodeConfig) -> Result<(), ErrorInfo>  {
     let ds = config.data_store().await;
-    let config = ds.config_store.select_config("servers".to_string()).await?;
-    let mut servers: Vec<Server> = vec![];
-    if let Some(s) = config {
-        servers = json_from::<Vec<Server>>(&*s)?;
-    }
+    let mut servers = ds.config_store.get_servers().await?;
     let max_index = servers.iter().map(|s| s.index).max().unwrap_or(-1);
     let this_index = add_server.index.unwrap_or(max_index + 1);
     servers.push(Server{
@@ -43,9 +64,10 @@ pub async fn add_server(add_server: &AddServer, config: &NodeConfig) -> Result<(
         index: this_index,
         peer_id_index: add_server.peer_id_index.unwrap_or(this_index),
         network_environment: NetworkEnvironment::All.to_std_string(),
-        external_host: None
+        external_host: None,
+        image_tag: None,
     });
-    ds.config_store.insert_update("servers".to_string(), json(&servers)?).await?;
+    ds.config_store.set_servers(&servers).await?;
     Ok(())
 }
 
@@ -73,12 +95,7 @@ async fn status_debug() {
 
 pub async fn list_servers(config: &NodeConfig) -> Result<Vec<Server>, ErrorInfo>  {
     let ds = config.data_store().await;
-    let config = ds.config_store.select_config("servers".to_string()).await?;
-    let mut servers: Vec<Server> = vec![];
-    if let Some(s) = config {
-        servers = json_from::<Vec<Server>>(&*s)?;
-    }
-    Ok(servers)
+    ds.config_store.get_servers().await
 }
 
 pub fn generate_mnemonic(_generate_mnemonic: &GenerateMnemonic) {
@@ -101,7 +118,396 @@ pub fn generate_address(generate_address: WalletAddress, node_config: &NodeConfi
 }
 
 
+pub const DEFAULT_DERIVE_ACCOUNT_PATH: &str = "m/44'/16180'/0'";
+
+/// Rows of (path, currency, address, public key hex) for each requested child index
+pub fn derive_rows(derive: &DeriveCli, node_config: &NodeConfig) -> RgResult<Vec<Vec<String>>> {
+    let currency = derive.currency.clone().unwrap_or("all".to_string()).to_lowercase();
+    let currencies = match currency.as_str() {
+        "all" => vec!["rdg", "btc", "eth"],
+        "rdg" | "redgold" => vec!["rdg"],
+        "btc" | "bitcoin" => vec!["btc"],
+        "eth" | "ethereum" => vec!["eth"],
+        _ => return Err(error_info(format!("Unsupported currency: {}", currency))),
+    };
+
+    let mut keys: Vec<(String, PublicKey)> = vec![];
+    if let Some(xpub) = &derive.xpub {
+        let w = XpubWrapper::new(xpub.clone());
+        for i in 0..derive.count {
+            keys.push((format!("xpub/0/{}", i), w.public_at(0, i as usize)?));
+        }
+    } else {
+        let words = derive.mnemonic.clone()
+            .map(|m| WordsPass::new(m, None))
+            .unwrap_or(node_config.words());
+        words.validate()?;
+        let account = derive.path.clone().unwrap_or(DEFAULT_DERIVE_ACCOUNT_PATH.to_string());
+        for i in 0..derive.count {
+            let path = format!("{}/0/{}", account, i);
+            keys.push((path.clone(), words.public_at(path)?));
+        }
+    }
+
+    let mut rows = vec![vec!["path".to_string(), "currency".to_string(), "address".to_string(), "public_key".to_string()]];
+    for (path, pk) in keys {
+        for c in currencies.iter() {
+            let address = match *c {
+                "btc" => pk.to_bitcoin_address(&node_config.network)?,
+                "eth" => pk.to_ethereum_address()?,
+                _ => pk.address()?.render_string()?,
+            };
+            rows.push(vec![path.clone(), c.to_string(), address, pk.hex_or()]);
+        }
+    }
+    Ok(rows)
+}
+
+pub fn derive(derive: &DeriveCli, node_config: &NodeConfig) -> RgResult<()> {
+    let rows = derive_rows(derive, node_config)?;
+    for row in rows {
+        println!("{}", row.join("\t"));
+    }
+    Ok(())
+}
+
+#[test]
+fn derive_rows_test() {
+    let nc = NodeConfig::default();
+    let d = DeriveCli {
+        mnemonic: Some(redgold_keys::TestConstants::new().words),
+        xpub: None,
+        path: None,
+        currency: None,
+        count: 2,
+    };
+    let rows = derive_rows(&d, &nc).expect("rows");
+    // Header plus one row per currency per index
+    assert_eq!(rows.len(), 1 + 3 * 2);
+    let words = WordsPass::words(redgold_keys::TestConstants::new().words);
+    let xpub = words.xpub(DEFAULT_DERIVE_ACCOUNT_PATH).expect("xpub").to_string();
+    let mut dx = d.clone();
+    dx.mnemonic = None;
+    dx.xpub = Some(xpub);
+    let xpub_rows = derive_rows(&dx, &nc).expect("rows");
+    let addresses = |r: &Vec<Vec<String>>| r.iter().skip(1).map(|r| r[2].clone()).collect::<Vec<String>>();
+    assert_eq!(addresses(&rows), addresses(&xpub_rows));
+}
+
+pub async fn log_level(cli: &LogLevelCli, node_config: &NodeConfig) -> RgResult<()> {
+    let client = ControlClient::local(node_config.control_port())
+        .with_api_key(node_config.api_access.control_api_key.clone());
+    let res = client.log_level(cli.level.clone(), cli.target.clone()).await?;
+    println!("{}", res.filter);
+    Ok(())
+}
+
+pub async fn cold_sweep(cli: &ColdSweepCli, node_config: &NodeConfig) -> RgResult<()> {
+    let client = ControlClient::local(node_config.control_port())
+        .with_api_key(node_config.api_access.control_api_key.clone());
+    let mut req = ControlColdSweepRequest::default();
+    req.execute = cli.execute;
+    req.rdg_address = cli.rdg_address.clone();
+    req.btc_address = cli.btc_address.clone();
+    req.hot_cap_rdg = cli.hot_cap_rdg.map(|a| CurrencyAmount::from_fractional(a).map(|a| a.amount as u64)).transpose()?;
+    req.hot_cap_btc = cli.hot_cap_btc.map(|a| CurrencyAmount::from_fractional(a).map(|a| a.amount as u64)).transpose()?;
+    let res = client.cold_sweep(req).await?;
+    println!("{}", res.json_pretty_or());
+    if !cli.execute && (res.rdg_amount > 0 || res.btc_amount > 0) {
+        println!("Rerun with --execute to sweep");
+    }
+    Ok(())
+}
+
+pub fn sign_seed_list(cli: &SignSeedListCli, node_config: &NodeConfig) -> RgResult<()> {
+    let contents = std::fs::read_to_string(&cli.seeds).error_info("Failed to read seeds file")?;
+    let seeds = json_from::<Vec<Seed>>(&contents)?;
+    let list = SignedSeedList::sign(&node_config.network, seeds, current_time_millis_i64(), &node_config.keypair())?;
+    let rendered = list.json_or();
+    match &cli.output {
+        Some(o) => std::fs::write(o, &rendered).error_info("Failed to write signed seed list")?,
+        None => println!("{}", rendered),
+    }
+    if cli.dns {
+        println!("{}", hex::encode(rendered.as_bytes()));
+    }
+    println!("Signed by release key {}", node_config.public_key().hex_or());
+    Ok(())
+}
+
+pub fn btc_checkpoint(cli: &BtcCheckpointCli, node_config: &NodeConfig) -> RgResult<()> {
+    let folder = node_config.env_data_folder();
+    if let Some(path) = &cli.import {
+        let contents = std::fs::read_to_string(path).error_info("Failed to read checkpoint file")?;
+        let checkpoints = json_from::<Vec<BtcWalletCheckpoint>>(&contents)?;
+        for cp in checkpoints.iter() {
+            btc_checkpoint::write_checkpoint(&folder, cp)?;
+            println!("Imported checkpoint for {} at height {:?}", cp.descriptor, cp.height());
+        }
+    }
+    if let Some(path) = &cli.export {
+        let checkpoints = btc_checkpoint::all_checkpoints(&folder)?;
+        std::fs::write(path, json_pretty(&checkpoints)?).error_info("Failed to write checkpoint file")?;
+        println!("Exported {} checkpoints", checkpoints.len());
+    }
+    if cli.import.is_none() && cli.export.is_none() {
+        return Err(error_info("Must supply --export or --import"));
+    }
+    Ok(())
+}
+
+pub fn vectors(cli: &VectorsCli) -> RgResult<()> {
+    if cli.check {
+        let expected = json_from::<Vec<CanonicalVector>>(CANONICAL_VECTORS)?;
+        let mismatched = mismatched_vectors(&expected)?;
+        mismatched.iter().for_each(|m| println!("{}", m));
+        if !mismatched.is_empty() {
+            return Err(error_info("Canonical serialization vectors changed"));
+        }
+        println!("{} vectors match", expected.len());
+        return Ok(());
+    }
+    let rendered = json_pretty(&generate_vectors()?)?;
+    match &cli.output {
+        Some(o) => std::fs::write(o, &rendered).error_info("Failed to write vectors")?,
+        None => println!("{}", rendered),
+    }
+    Ok(())
+}
+
+pub async fn tx_validate(cli: &TxValidateCli, node_config: &NodeConfig) -> RgResult<()> {
+    let input = match (cli.path.as_ref(), cli.transaction.as_ref()) {
+        (Some(p), _) => std::fs::read_to_string(p).error_info("Failed to read transaction file")?,
+        (None, Some(t)) => t.clone(),
+        (None, None) => return Err(error_info("Must supply a transaction or --path")),
+    };
+    let input = input.trim().to_string();
+    let tx = json_from::<Transaction>(&input)
+        .or_else(|_| redgold_schema::from_hex(input.clone()).and_then(Transaction::proto_deserialize))?;
+    let res = node_config.api_client().validate_transaction(&tx).await?;
+    println!("{}", res.json_or());
+    if !res.valid {
+        return Err(error_info("Transaction failed validation"));
+    }
+    Ok(())
+}
+
+// Typed entries are parsed as their struct so a bad edit can't break the node on next load
+async fn set_config_entry(ds: &DataStore, key: &String, value: &String) -> RgResult<()> {
+    let cs = &ds.config_store;
+    if key == DEPOSIT_WATCHER_CONFIG.name {
+        cs.set_typed(&DEPOSIT_WATCHER_CONFIG, &json_from::<DepositWatcherConfig>(value)?).await?;
+    } else if key == AMM_VOLUME_SUMMARY.name {
+        cs.set_typed(&AMM_VOLUME_SUMMARY, &json_from::<AmmVolumeSummary>(value)?).await?;
+    } else if key == PARTY_AUDIT.name {
+        cs.set_typed(&PARTY_AUDIT, &json_from::<PartyAudit>(value)?).await?;
+    } else if key == LOCAL_STORED_STATE.name {
+        cs.set_typed(&LOCAL_STORED_STATE, &json_from::<LocalStoredState>(value)?).await?;
+    } else if key == SERVERS.name {
+        cs.set_typed(&SERVERS, &json_from::<Vec<Server>>(value)?).await?;
+    } else if key == FEATURE_FLAG_OVERRIDES.name {
+        let overrides = json_from::<HashMap<String, bool>>(value)?;
+        // Rejects unknown flag names before they're stored
+        FeatureFlags::for_network(&NetworkEnvironment::Main).apply(&overrides)?;
+        cs.set_typed(&FEATURE_FLAG_OVERRIDES, &overrides).await?;
+    } else {
+        cs.insert_update_json(key.clone(), json_from::<serde_json::Value>(value)?).await?;
+    }
+    Ok(())
+}
+
+fn render_config_entry(key: &String, value: &Option<String>) -> String {
+    let rendered = value.as_ref().map(|v| {
+        match ConfigEnvelope::parse(v) {
+            Ok(e) => format!(
+                "(v{}) {}", e.version, serde_json::to_string_pretty(&e.payload).unwrap_or(v.clone())
+            ),
+            Err(_) => v.clone()
+        }
+    }).unwrap_or("<binary>".to_string());
+    format!("{}: {}", key, rendered)
+}
+
+pub async fn config(cli: &ConfigCli, node_config: &NodeConfig) -> RgResult<()> {
+    let ds = node_config.data_store().await;
+    match (cli.key.as_ref(), cli.set.as_ref()) {
+        (None, _) => {
+            for (key, value) in ds.config_store.select_all_config().await? {
+                println!("{}", render_config_entry(&key, &value));
+            }
+        }
+        (Some(key), _) if cli.delete => {
+            let deleted = ds.config_store.delete_config(key.clone()).await?;
+            println!("Deleted {} entries", deleted);
+        }
+        (Some(key), Some(value)) => {
+            set_config_entry(&ds, key, value).await?;
+        }
+        (Some(key), None) => {
+            let value = ds.config_store.select_config(key.clone()).await?;
+            println!("{}", render_config_entry(key, &value));
+        }
+    }
+    Ok(())
+}
+
+pub fn verify_message(cli: &VerifyMessageCli) -> RgResult<()> {
+    let input = match (cli.path.as_ref(), cli.proof.as_ref()) {
+        (Some(p), _) => std::fs::read_to_string(p).error_info("Failed to read proof file")?,
+        (None, Some(m)) => m.clone(),
+        (None, None) => return Err(error_info("Must supply a signed message proof or --path")),
+    };
+    let signed = SignedMessage::parse(&input)?;
+    signed.verify()?;
+    println!("Valid signature from {}", signed.address);
+    Ok(())
+}
+
+pub async fn prune(cli: &PruneCli, node_config: &NodeConfig) -> RgResult<()> {
+    let mut retention = node_config.retention.clone();
+    if let Some(d) = cli.days {
+        retention.max_age = Some(Duration::from_secs(d * 60 * 60 * 24));
+    }
+    if let Some(mb) = cli.max_mb {
+        retention.max_size_bytes = Some(mb * 1024 * 1024);
+    }
+    if !retention.enabled() {
+        return Err(error_info("Must supply --days or --max-mb, or configure a node retention policy"));
+    }
+    let ds = node_config.data_store().await;
+    let mut summary = prune_datastore(&ds, &retention, current_time_millis_i64()).await?;
+    if cli.vacuum {
+        ds.vacuum().await?;
+        summary.bytes_after = ds.file_size_bytes()?;
+    }
+    println!("Removed {} transactions, {} observations, {} observation edges, {} other rows",
+             summary.transactions, summary.observations, summary.observation_edges,
+             summary.total_rows() - summary.transactions - summary.observations - summary.observation_edges);
+    println!("Reclaimed {} bytes, datastore is now {} bytes", summary.reclaimed_bytes(), summary.bytes_after);
+    Ok(())
+}
 
+pub async fn bridge_reconcile(cli: &BridgeReconcileCli, node_config: &NodeConfig) -> RgResult<()> {
+    let ds = node_config.data_store().await;
+    let res = reconcile_bridge(&ds, cli.repair).await?;
+    println!("Found {} duplicate bridge rows, {} fulfillments missing a bridge row",
+             res.duplicate_rows, res.missing.len());
+    for r in res.missing.iter() {
+        println!("Missing bridge row for BTC fulfillment {}", r.txid.clone().unwrap_or_default());
+    }
+    for txid in res.unrepairable.iter() {
+        println!("Unable to repair {}, fulfillment record has no source address", txid);
+    }
+    if cli.repair {
+        println!("Removed {} duplicate rows, inserted {} missing rows", res.removed, res.inserted);
+    }
+    Ok(())
+}
+
+pub async fn network_init(cli: &NetworkInitCli, node_config: &NodeConfig) -> RgResult<()> {
+    let network = node_config.network;
+    if network.is_main() {
+        return Err(error_info("network-init is only for private / test networks"));
+    }
+    let servers_path = cli.servers.clone().map(PathBuf::from)
+        .unwrap_or(node_config.secure_or().all().servers_path());
+    let servers = Server::parse_from_file(servers_path.clone())?;
+    if servers.is_empty() {
+        return Err(error_info("No servers found to initialize network with"));
+    }
+    let output = PathBuf::from(cli.output.clone());
+    std::fs::create_dir_all(&output).error_info("Failed to create output directory")?;
+
+    let salt = match &cli.salt_mnemonic {
+        Some(m) => m.clone(),
+        None => {
+            let generated = WordsPass::generate()?.words;
+            std::fs::write(output.join("salt_mnemonic"), generated.clone())
+                .error_info("Failed to write salt mnemonic")?;
+            generated
+        }
+    };
+    let keys = offline_generate_keys_servers(
+        node_config.clone(), servers.clone(), output.clone(), salt, cli.passphrase.clone(), cli.bip85
+    ).await?;
+
+    let amount = cli.amount.unwrap_or(MAX_COIN_SUPPLY as f64 / servers.len() as f64);
+    let stake_amount = cli.stake_amount.unwrap_or(0.0);
+    if stake_amount >= amount {
+        return Err(error_info("Stake amount must be less than the per server amount"));
+    }
+    let mut entries = vec![];
+    for k in &keys {
+        let words = WordsPass::new(k.words.clone(), None);
+        let label = format!("server_{}", k.server.index);
+        entries.push(GenesisManifestEntry {
+            address: words.keypair_at_change(0)?.address_typed().render_string()?,
+            amount: amount - stake_amount,
+            stake: false,
+            label: Some(label.clone()),
+        });
+        if stake_amount > 0.0 {
+            entries.push(GenesisManifestEntry {
+                address: words.keypair_at_change(1)?.address_typed().render_string()?,
+                amount: stake_amount,
+                stake: true,
+                label: Some(format!("{}_stake", label)),
+            });
+        }
+    }
+    let manifest = GenesisManifest {
+        network: network.to_std_string(),
+        watcher_funding_address: entries.first().map(|e| e.address.clone()),
+        entries,
+    };
+    manifest.validate(&network)?;
+    let manifest_hash = manifest.hash()?;
+
+    let mut seeds = vec![];
+    for k in &keys {
+        let words = WordsPass::new(k.words.clone(), None);
+        seeds.push(Seed {
+            external_address: k.server.external_host.clone().unwrap_or(k.server.host.clone()),
+            environments: vec![network as i32],
+            port_offset: Some(network.default_port_offset() as u32),
+            trust: vec![TrustData::from_label(1.0)],
+            peer_id: Some(PeerId::from_hex(k.peer_id_hex.clone())?),
+            public_key: Some(words.default_public_key()?),
+            genesis_manifest_hash: Some(manifest_hash.clone()),
+        });
+    }
+
+    std::fs::write(output.join("genesis.json"), json_pretty(&manifest)?)
+        .error_info("Failed to write genesis manifest")?;
+    std::fs::write(output.join("seeds.json"), json_pretty(&seeds)?)
+        .error_info("Failed to write seeds")?;
+    std::fs::copy(&servers_path, output.join("servers"))
+        .error_info("Failed to copy servers file")?;
+    println!("Initialized {} network with {} servers in {}", network.to_std_string(), keys.len(), output.to_string_lossy());
+    println!("Genesis manifest hash {}", manifest_hash.hex());
+    Ok(())
+}
+
+pub fn amm_sim(cli: &AmmSimCli) -> RgResult<()> {
+    let points = read_price_csv(&PathBuf::from(cli.prices.clone()))?;
+    let curve = match &cli.curve {
+        Some(p) => json_from::<CurveConfig>(&std::fs::read_to_string(p).error_info("Failed to read curve config")?)?,
+        None => CurveConfig::default(),
+    };
+    let report = simulate(
+        &points,
+        &curve,
+        CurrencyAmount::from_fractional(cli.initial_rdg)?.amount as u64,
+        CurrencyAmount::from_fractional(cli.initial_btc)?.amount as u64,
+        CurrencyAmount::from_fractional(cli.order_btc)?.amount as u64,
+    )?;
+    let rendered = report.json_pretty_or();
+    if let Some(o) = &cli.output {
+        std::fs::write(o, &rendered).error_info("Failed to write simulation report")?;
+    }
+    println!("{}", rendered);
+    Ok(())
+}
 
 pub async fn send(p0: &WalletSend, p1: &NodeConfig) -> Result<(), ErrorInfo> {
     let destination = Address::parse(p0.to.clone())?;
@@ -121,6 +527,21 @@ pub async fn send(p0: &WalletSend, p1: &NodeConfig) -> Result<(), ErrorInfo> {
         hm.insert(x, kp.clone());
     }
 
+    let store = match p1.data_store_all_secure().await {
+        Some(ds) => ds,
+        None => p1.data_store_all().await
+    };
+    let mut stored_state = store.config_store.get_stored_state().await?;
+    let account_xpub = p1.words().xpub(redgold_account_path())?.to_string();
+    let change_index = stored_state.next_change_index(&account_xpub);
+    // Previously used change addresses may also hold funds
+    for i in 0..change_index {
+        let kp = p1.words().keypair_at(redgold_keypair_internal_change_path(i))?;
+        let x1 = kp.address_typed();
+        query_addresses.push(x1.clone());
+        hm.insert(x1.address.safe_bytes()?, kp);
+    }
+
     let client = p1.api_client();
     let result = client.query_address(query_addresses).await?.as_error()?;
     let utxos = result.query_addresses_response.safe_get_msg("missing query_addresses_response")?
@@ -129,28 +550,129 @@ pub async fn send(p0: &WalletSend, p1: &NodeConfig) -> Result<(), ErrorInfo> {
     if utxos.len() == 0 {
         return Err(ErrorInfo::error_info("No UTXOs found for this address"));
     }
-    let option1 = utxos.get(0);
-    let first_uto = option1.safe_get_msg("first")?;
-    let first_addr = first_uto.address()?;
-    let option = hm.get(&first_addr.address.safe_bytes()?);
-    let kp = option.safe_get_msg("keypair")?.clone().clone();
-
-    let utxo = utxos.get(0).expect("first").clone();
-    let b = TransactionBuilder::new(&p1.network)
-        .with_utxo(&utxo)?
-        .with_output(&destination, &CurrencyAmount::from_fractional(p0.amount)?)
-        .build()?
-        .sign(&kp)?;
+    // UTXOs of any derived key are eligible, including rotated change, each input is signed by its own key
+    let signable = utxos.iter()
+        .filter(|u| u.address().ok().and_then(|a| a.address.safe_bytes().ok()).map(|a| hm.contains_key(&a)).unwrap_or(false))
+        .cloned()
+        .collect::<Vec<_>>();
+    let strategy = match &p0.coin_selection {
+        None => p1.coin_selection.clone(),
+        Some(s) => CoinSelectionStrategy::from_str(s)
+            .error_info("Invalid coin selection strategy")?
+    };
+    let mut tb = TransactionBuilder::new(&p1.network);
+    tb.with_utxos(&signable)?
+        .with_coin_selection(&strategy)
+        .with_output(&destination, &CurrencyAmount::from_fractional(p0.amount)?);
+    let estimate = fee_estimate_or_minimum(&client, &p1).await?;
+    match p0.fee {
+        Some(f) => {
+            tb.with_fee(&estimate.fee_address, &CurrencyAmount::from_fractional(f)?)?;
+        }
+        None => {
+            tb.with_auto_fee(&estimate);
+        }
+    }
+    let change_address = p1.words().keypair_at(redgold_keypair_internal_change_path(change_index))?.address_typed();
+    if p0.rotate_change {
+        tb.with_change_address(&change_address);
+    }
+    let mut b = tb.build()?;
+    let input_addresses = b.inputs.iter()
+        .filter_map(|i| i.output.as_ref().and_then(|o| o.address.clone()))
+        .collect::<HashSet<Address>>();
+    for a in input_addresses {
+        let kp = hm.get(&a.address.safe_bytes()?).safe_get_msg("Missing key for input address")?.clone();
+        b = b.sign(&kp)?;
+    }
 
     let response = client.send_transaction(&b, false).await?;
     let tx_hex = response.transaction_hash.safe_get()?.hex();
+    if b.outputs.iter().any(|o| o.address.as_ref() == Some(&change_address)) {
+        stored_state.mark_change_index_used(&account_xpub, change_index);
+        store.config_store.update_stored_state(stored_state).await?;
+    }
     println!("{}", tx_hex);
     Ok(())
 }
 
+/// Resolves a sweep key source, either a derivation path on the loaded mnemonic, a file
+/// containing a private key hex, or the private key hex itself.
+pub fn sweep_keypair(from_key: &String, node_config: &NodeConfig) -> RgResult<KeyPair> {
+    if from_key.starts_with("m/") {
+        return node_config.words().keypair_at(from_key.clone());
+    }
+    let path = PathBuf::from(from_key);
+    let hex = if path.is_file() {
+        std::fs::read_to_string(path).error_info("Failed to read private key file")?
+    } else {
+        from_key.clone()
+    };
+    KeyPair::from_private_hex(hex.trim().to_string())
+}
+
+// Nodes without the fee estimate endpoint fall back to the locally configured minimum, paid to
+// the remote node's own address taken from its latest node metadata
+async fn fee_estimate_or_minimum(client: &PublicClient, node_config: &NodeConfig) -> RgResult<FeeEstimate> {
+    if let Some(estimate) = client.fee_estimate().await.log_error().ok() {
+        return Ok(estimate);
+    }
+    let about = client.about().await?;
+    let fee_address = about.peer_node_info.as_ref()
+        .and_then(|n| n.latest_node_transaction.as_ref())
+        .and_then(|n| n.node_metadata().ok())
+        .and_then(|n| n.public_key)
+        .safe_get_msg("Node did not report its public key for the fee address")?
+        .address()?;
+    Ok(FeeEstimate::minimum(&node_config.fee_policy, &fee_address))
+}
+
+pub async fn sweep(sweep: &SweepCli, node_config: &NodeConfig) -> RgResult<()> {
+    let kp = sweep_keypair(&sweep.from_key, node_config)?;
+    let destination = Address::parse(sweep.to.clone())?;
+    let source = kp.address_typed();
+    println!("Sweeping from address: {}", source.render_string()?);
+
+    let client = node_config.api_client();
+    let result = client.query_address(vec![source.clone()]).await?.as_error()?;
+    let utxos = result.query_addresses_response.safe_get_msg("missing query_addresses_response")?
+        .utxo_entries.clone();
+
+    if utxos.is_empty() {
+        println!("No RDG UTXOs found for source address");
+    } else {
+        let mut tb = TransactionBuilder::new(&node_config.network);
+        tb.with_utxos(&utxos)?
+            .with_auto_fee(&fee_estimate_or_minimum(&client, node_config).await?);
+        let tx = tb.build_sweep(&destination)?.sign(&kp)?;
+        println!("RDG sweep of {} UTXOs totalling {}", tx.inputs.len(), rounded_balance_i64(tx.total_output_amount()));
+        if sweep.dry_run {
+            println!("{}", tx.json_or());
+        } else {
+            let response = client.send_transaction(&tx, false).await?;
+            println!("{}", response.transaction_hash.safe_get()?.hex());
+        }
+    }
+
+    if let Some(btc_to) = &sweep.btc_to {
+        let mut w = SingleKeyBitcoinWallet::new_wallet_backend(
+            kp.public_key(), node_config.network, true, &node_config.btc_backend
+        )?;
+        w.create_drain_transaction(btc_to.clone())?;
+        let rendered = w.local_sign_single(kp.private_hex())?;
+        if sweep.dry_run {
+            println!("{}", rendered);
+        } else {
+            w.broadcast_tx()?;
+            println!("{}", w.txid()?);
+        }
+    }
+    Ok(())
+}
+
 pub async fn faucet(p0: &FaucetCli, p1: &NodeConfig) -> Result<(), ErrorInfo>  {
     let address = Address::parse(p0.to.clone())?;
-    let response = p1.api_client().faucet(&address).await?;
+    let response = p1.sdk_client().faucet(&address).await?;
     let tx = response.submit_transaction_response.safe_get()?.transaction.safe_get()?;
     let tx_hex = tx.hash_hex()?;
     println!("{}", tx_hex);
@@ -158,15 +680,15 @@ pub async fn faucet(p0: &FaucetCli, p1: &NodeConfig) -> Result<(), ErrorInfo>  {
 }
 
 pub async fn balance_lookup(request: &BalanceCli, nc: &NodeConfig) -> Result<(), ErrorInfo> {
-    let response = nc.api_client().query_hash(request.address.clone()).await?;
-    let rounded = rounded_balance_i64(response.address_info.safe_get_msg("missing address_info")?.balance);
+    let address = Address::parse(request.address.clone())?;
+    let rounded = nc.sdk_client().balance(&address).await?;
     println!("{}", rounded.to_string());
     Ok(())
 }
 
 
 pub async fn query(p0: &QueryCli, p1: &NodeConfig) -> Result<(), ErrorInfo> {
-    let response = p1.api_client().query_hash(p0.hash.clone()).await?;
+    let response = p1.sdk_client().hash_search(p0.hash.clone()).await?;
     println!("{}", json(&response)?);
     Ok(())
 }
@@ -212,6 +734,7 @@ pub fn add_server_prompt() -> Server {
         peer_id_index: 0,
         network_environment: NetworkEnvironment::All.to_std_string(),
         external_host: None,
+        image_tag: None,
     }
 }
 
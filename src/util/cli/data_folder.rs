@@ -1,11 +1,14 @@
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use redgold_schema::{ErrorInfoContext, json_from, RgResult, structs};
+use redgold_schema::{error_info, ErrorInfoContext, json_from, json_pretty, RgResult, SafeOption, structs};
 use redgold_schema::structs::{ErrorInfo, NetworkEnvironment, Transaction};
 use redgold_data::data_store::DataStore;
 use redgold_schema::servers::Server;
 
 // TODO: Move everything to use this
 
+const MAX_DEPLOY_TAG_HISTORY: usize = 10;
+
 #[derive(Clone, Debug)]
 pub struct EnvDataFolder {
     pub path: PathBuf
@@ -21,6 +24,18 @@ impl EnvDataFolder {
         self.path.join("data_store.sqlite")
     }
 
+    /// Total size of the sqlite data store including its wal / shm files.
+    pub fn data_store_size_bytes(&self) -> RgResult<u64> {
+        let mut total = 0;
+        for entry in std::fs::read_dir(&self.path).error_info("Bad data folder read")? {
+            let entry = entry.error_info("Bad data folder entry")?;
+            if entry.file_name().to_string_lossy().starts_with("data_store") {
+                total += entry.metadata().error_info("Bad data store metadata")?.len();
+            }
+        }
+        Ok(total)
+    }
+
     pub fn mnemonic_path(&self) -> PathBuf {
         self.path.join("mnemonic")
     }
@@ -46,6 +61,10 @@ impl EnvDataFolder {
         self.path.join("metrics_list")
     }
 
+    pub fn genesis_manifest_path(&self) -> PathBuf {
+        self.path.join("genesis.json")
+    }
+
     pub fn targets(&self) -> PathBuf {
         self.path.join("targets.json")
     }
@@ -58,6 +77,52 @@ impl EnvDataFolder {
         Server::parse_from_file(self.servers_path())
     }
 
+    pub fn btc_checkpoints_path(&self) -> PathBuf {
+        self.path.join("btc_checkpoints")
+    }
+
+    pub fn deploy_tags_path(&self) -> PathBuf {
+        self.path.join("deploy_image_tags.json")
+    }
+
+    /// Image tags deployed per server key, oldest first
+    pub fn deploy_tags(&self) -> RgResult<HashMap<String, Vec<String>>> {
+        if !self.deploy_tags_path().exists() {
+            return Ok(HashMap::new());
+        }
+        let contents = std::fs::read_to_string(self.deploy_tags_path()).error_info("Bad deploy tags read")?;
+        json_from(&*contents)
+    }
+
+    fn write_deploy_tags(&self, tags: &HashMap<String, Vec<String>>) -> RgResult<()> {
+        std::fs::write(self.deploy_tags_path(), json_pretty(tags)?).error_info("Bad deploy tags write")
+    }
+
+    pub fn record_deploy_tag(&self, server_key: &String, tag: &String) -> RgResult<()> {
+        let mut tags = self.deploy_tags()?;
+        let history = tags.entry(server_key.clone()).or_insert(vec![]);
+        if history.last() != Some(tag) {
+            history.push(tag.clone());
+        }
+        if history.len() > MAX_DEPLOY_TAG_HISTORY {
+            history.remove(0);
+        }
+        self.write_deploy_tags(&tags)
+    }
+
+    /// Drops the most recently deployed tag and returns the one deployed before it
+    pub fn rollback_deploy_tag(&self, server_key: &String) -> RgResult<String> {
+        let mut tags = self.deploy_tags()?;
+        let history = tags.get_mut(server_key).ok_msg(format!("No deploy history for {}", server_key))?;
+        if history.len() < 2 {
+            return Err(error_info(format!("No previous deployed tag for {}", server_key)));
+        }
+        history.pop();
+        let previous = history.last().cloned().ok_msg("Missing previous tag")?;
+        self.write_deploy_tags(&tags)?;
+        Ok(previous)
+    }
+
     // Change to cert.pem
     pub fn cert_path(&self) -> PathBuf {
         //self.path.join("certificate.crt")
@@ -97,6 +162,36 @@ impl EnvDataFolder {
         self
     }
 
+    pub fn node_lock_path(&self) -> PathBuf {
+        self.path.join("node.lock")
+    }
+
+    /// Claims the folder for a single running node by writing our pid to a lock file, a lock
+    /// left behind by a process that's no longer alive is taken over.
+    pub fn acquire_node_lock(&self) -> RgResult<()> {
+        let path = self.node_lock_path();
+        if let Ok(contents) = std::fs::read_to_string(&path) {
+            let pid = contents.trim().parse::<u32>().ok();
+            if let Some(pid) = pid.filter(|p| *p != std::process::id() && process_alive(*p)) {
+                return Err(error_info(format!(
+                    "Data folder {} is in use by another node with pid {}", self.path.to_string_lossy(), pid
+                )));
+            }
+        }
+        std::fs::write(&path, std::process::id().to_string()).error_info("Failed to write node lock")
+    }
+
+    pub fn release_node_lock(&self) -> RgResult<()> {
+        let path = self.node_lock_path();
+        let owned = std::fs::read_to_string(&path).ok()
+            .map(|c| c.trim() == std::process::id().to_string())
+            .unwrap_or(false);
+        if owned {
+            std::fs::remove_file(&path).error_info("Failed to remove node lock")?;
+        }
+        Ok(())
+    }
+
     pub async fn data_store(&self) -> DataStore {
         // TODO: From file path
         DataStore::from_file_path(self.data_store_path().to_str().expect("Data store path").to_string()).await
@@ -104,6 +199,16 @@ impl EnvDataFolder {
 
 }
 
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    nix::sys::signal::kill(nix::unistd::Pid::from_raw(pid as i32), None).is_ok()
+}
+
+#[cfg(not(unix))]
+fn process_alive(_pid: u32) -> bool {
+    true
+}
+
 #[derive(Clone, Debug)]
 pub struct DataFolder {
     pub path: PathBuf,
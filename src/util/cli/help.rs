@@ -0,0 +1,50 @@
+use clap::CommandFactory;
+use clap_complete::Shell;
+use crate::util::cli::args::RgArgs;
+
+pub const TOP_LEVEL_HELP: &str = "Shell completion:
+  redgold completions bash > /etc/bash_completion.d/redgold
+  redgold completions zsh > \"${fpath[1]}/_redgold\"
+  redgold completions fish > ~/.config/fish/completions/redgold.fish
+
+Run `redgold help <command>` for examples of each command.";
+
+pub const DEPLOY_EXAMPLES: &str = "Examples:
+  redgold deploy --wizard
+  redgold --network test deploy --server-index 0 --skip-ops
+  redgold deploy --ops --purge-ops
+  redgold deploy --secret RECAPTCHA_SECRET=aws-sm:prod/redgold#recaptcha";
+
+pub const SEND_EXAMPLES: &str = "Examples:
+  redgold send --to <address> --amount 1.5
+  redgold --network test send --to <address> --amount 0.1 --coin-selection OldestFirst";
+
+pub const QUERY_EXAMPLES: &str = "Examples:
+  redgold query --hash <transaction hash>
+  redgold query --hash <address>";
+
+pub const BALANCE_EXAMPLES: &str = "Examples:
+  redgold balance --address <address>";
+
+pub const FAUCET_EXAMPLES: &str = "Examples:
+  redgold --network test faucet --to <address> --amount 5";
+
+pub const COMPLETIONS_EXAMPLES: &str = "Examples:
+  redgold completions bash > /etc/bash_completion.d/redgold
+  redgold completions powershell >> $PROFILE";
+
+/// Writes the completion script for the shell, covering every subcommand and option.
+pub fn write_completions(shell: Shell, out: &mut dyn std::io::Write) {
+    let mut cmd = RgArgs::command();
+    let name = cmd.get_name().to_string();
+    clap_complete::generate(shell, &mut cmd, name, out);
+}
+
+#[test]
+fn cli_definition_and_completions() {
+    RgArgs::command().debug_assert();
+    let mut out = vec![];
+    write_completions(Shell::Bash, &mut out);
+    let script = String::from_utf8(out).expect("utf8");
+    assert!(script.contains("completions") && script.contains("cold-sweep"));
+}
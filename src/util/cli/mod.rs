@@ -1,4 +1,5 @@
 pub mod args;
 pub mod arg_parse_config;
 pub mod commands;
-pub mod data_folder;
\ No newline at end of file
+pub mod data_folder;
+pub mod help;
\ No newline at end of file
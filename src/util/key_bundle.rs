@@ -0,0 +1,206 @@
+use argon2::Params;
+use bdk::bitcoin::hashes::{hmac, sha256, Hash as BitcoinHash, HashEngine};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use redgold_schema::{error_info, from_hex, ErrorInfoContext, RgResult};
+use redgold_schema::local_stored_state::{NamedXpub, StoredMnemonic, StoredPrivateKey};
+use crate::util::argon_kdf::argon2d_hash;
+use crate::util::{current_time_millis_i64, sym_crypt};
+
+pub const KEY_BUNDLE_VERSION: u32 = 1;
+pub const KEY_BUNDLE_KDF: &str = "argon2d";
+// Bundles are read from arbitrary files, so KDF costs beyond these are rejected rather than run
+const MAX_KDF_M_COST: u32 = 1024 * 1024;
+const MAX_KDF_T_COST: u32 = 16;
+const MAX_KDF_P_COST: u32 = 8;
+const MIN_KDF_SALT_BYTES: usize = 8;
+
+/// Secrets and xpubs moved between machines in an encrypted bundle.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct KeyBundleContents {
+    #[serde(default)]
+    pub mnemonics: Vec<StoredMnemonic>,
+    #[serde(default)]
+    pub private_keys: Vec<StoredPrivateKey>,
+    #[serde(default)]
+    pub xpubs: Vec<NamedXpub>,
+}
+
+impl KeyBundleContents {
+    pub fn is_empty(&self) -> bool {
+        self.mnemonics.is_empty() && self.private_keys.is_empty() && self.xpubs.is_empty()
+    }
+
+    pub fn summary(&self) -> String {
+        format!("{} mnemonics, {} private keys, {} xpubs",
+            self.mnemonics.len(), self.private_keys.len(), self.xpubs.len())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct KeyBundleKdf {
+    pub algorithm: String,
+    pub m_cost: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+    pub salt: String,
+}
+
+impl KeyBundleKdf {
+    pub fn new(m_cost: u32, t_cost: u32, p_cost: u32) -> Self {
+        let mut salt = [0u8; 16];
+        OsRng::default().fill_bytes(&mut salt);
+        Self {
+            algorithm: KEY_BUNDLE_KDF.to_string(),
+            m_cost,
+            t_cost,
+            p_cost,
+            salt: hex::encode(salt),
+        }
+    }
+
+    /// Encryption and MAC keys derived from the password.
+    fn derive_keys(&self, password: &str) -> RgResult<(Vec<u8>, Vec<u8>)> {
+        if self.algorithm != KEY_BUNDLE_KDF {
+            return Err(error_info(format!("Unsupported key bundle KDF {}", self.algorithm)));
+        }
+        if self.m_cost > MAX_KDF_M_COST || self.t_cost > MAX_KDF_T_COST || self.p_cost > MAX_KDF_P_COST {
+            return Err(error_info("Key bundle KDF costs are too large"));
+        }
+        let salt = from_hex(self.salt.clone())?;
+        if salt.len() < MIN_KDF_SALT_BYTES {
+            return Err(error_info("Key bundle KDF salt is too short"));
+        }
+        Params::new(self.m_cost, self.t_cost, self.p_cost, Some(32))
+            .error_info("Invalid key bundle KDF parameters")?;
+        let key = argon2d_hash(salt, password.as_bytes().to_vec(), self.m_cost, self.t_cost, self.p_cost)?;
+        let mac_key = hmac_sha256(&key, b"redgold key bundle mac");
+        Ok((key, mac_key))
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut engine = hmac::HmacEngine::<sha256::Hash>::new(key);
+    engine.input(data);
+    hmac::Hmac::<sha256::Hash>::from_engine(engine).into_inner().to_vec()
+}
+
+// Compares every byte regardless of where the first mismatch is, so timing doesn't leak the MAC
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b.iter()).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+impl Default for KeyBundleKdf {
+    fn default() -> Self {
+        Self::new(19 * 1024, 2, 1)
+    }
+}
+
+/// Password encrypted key bundle file, AES-256-CBC with an argon2d derived key. The HMAC-SHA256 covers
+/// the version, KDF parameters, iv and ciphertext, so tampering or a wrong password is detected
+/// before anything is decrypted.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct EncryptedKeyBundle {
+    pub version: u32,
+    pub kdf: KeyBundleKdf,
+    pub iv: String,
+    pub ciphertext: String,
+    pub mac: String,
+    pub created_time: i64,
+}
+
+impl EncryptedKeyBundle {
+
+    pub fn encrypt(contents: &KeyBundleContents, password: &str) -> RgResult<Self> {
+        Self::encrypt_with(contents, password, KeyBundleKdf::default())
+    }
+
+    pub fn encrypt_with(contents: &KeyBundleContents, password: &str, kdf: KeyBundleKdf) -> RgResult<Self> {
+        if password.is_empty() {
+            return Err(error_info("Key bundle password is empty"));
+        }
+        let (key, mac_key) = kdf.derive_keys(password)?;
+        let iv = sym_crypt::get_iv();
+        let plaintext = serde_json::to_vec(contents).error_info("Failed to serialize key bundle")?;
+        let ciphertext = sym_crypt::encrypt(&plaintext, &key, &iv)
+            .map_err(|e| error_info(format!("Key bundle encryption failed: {:?}", e)))?;
+        let mut bundle = Self {
+            version: KEY_BUNDLE_VERSION,
+            kdf,
+            iv: hex::encode(iv),
+            ciphertext: hex::encode(ciphertext),
+            mac: "".to_string(),
+            created_time: current_time_millis_i64(),
+        };
+        bundle.mac = hex::encode(bundle.calculate_mac(&mac_key));
+        Ok(bundle)
+    }
+
+    fn calculate_mac(&self, mac_key: &Vec<u8>) -> Vec<u8> {
+        let input = format!(
+            "{}:{}:{}:{}:{}:{}:{}:{}",
+            self.version, self.kdf.algorithm, self.kdf.m_cost, self.kdf.t_cost, self.kdf.p_cost,
+            self.kdf.salt, self.iv, self.ciphertext
+        );
+        hmac_sha256(mac_key, input.as_bytes())
+    }
+
+    pub fn decrypt(&self, password: &str) -> RgResult<KeyBundleContents> {
+        if self.version != KEY_BUNDLE_VERSION {
+            return Err(error_info(format!(
+                "Unsupported key bundle version {}, expected {}", self.version, KEY_BUNDLE_VERSION
+            )));
+        }
+        let (key, mac_key) = self.kdf.derive_keys(password)?;
+        let mac = hex::decode(&self.mac).error_info("Key bundle MAC is not valid hex")?;
+        if !constant_time_eq(&self.calculate_mac(&mac_key), &mac) {
+            return Err(error_info("Key bundle integrity check failed, wrong password or corrupted file"));
+        }
+        let plaintext = sym_crypt::decrypt(&from_hex(self.ciphertext.clone())?, &key, &from_hex(self.iv.clone())?)
+            .map_err(|e| error_info(format!("Key bundle decryption failed: {:?}", e)))?;
+        serde_json::from_slice(&plaintext).error_info("Failed to deserialize key bundle")
+    }
+}
+
+#[test]
+fn key_bundle_round_trip_and_integrity() {
+    use redgold_schema::{EasyJson, EasyJsonDeser};
+    let contents = KeyBundleContents {
+        mnemonics: vec![StoredMnemonic {
+            name: "m".to_string(),
+            mnemonic: "abandon abandon".to_string(),
+            persist_disk: Some(true),
+            backup_verified: None,
+            backup_verified_time: None,
+            os_keystore: None,
+        }],
+        private_keys: vec![],
+        xpubs: vec![],
+    };
+    let kdf = KeyBundleKdf::new(64, 1, 1);
+    let bundle = EncryptedKeyBundle::encrypt_with(&contents, "pw", kdf).expect("encrypt");
+    let restored = bundle.json_or().json_from::<EncryptedKeyBundle>().expect("json")
+        .decrypt("pw").expect("decrypt");
+    assert!(restored.mnemonics == contents.mnemonics);
+    assert!(bundle.decrypt("wrong").is_err());
+
+    let mut tampered = bundle.clone();
+    tampered.ciphertext.push_str("00");
+    assert!(tampered.decrypt("pw").is_err());
+    let mut future = bundle.clone();
+    future.version = KEY_BUNDLE_VERSION + 1;
+    assert!(future.decrypt("pw").is_err());
+    let mut expensive = bundle.clone();
+    expensive.kdf.m_cost = MAX_KDF_M_COST + 1;
+    assert!(expensive.decrypt("pw").is_err());
+    let mut slow = bundle.clone();
+    slow.kdf.t_cost = MAX_KDF_T_COST + 1;
+    assert!(slow.decrypt("pw").is_err());
+    let mut unsalted = bundle.clone();
+    unsalted.kdf.salt = "".to_string();
+    assert!(unsalted.decrypt("pw").is_err());
+    assert!(constant_time_eq(b"abc", b"abc"));
+    assert!(!constant_time_eq(b"abc", b"abd"));
+    assert!(!constant_time_eq(b"abc", b"ab"));
+}
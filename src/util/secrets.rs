@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use aws_config::meta::region::RegionProviderChain;
+use aws_sdk_secretsmanager::config::Region;
+use aws_sdk_secretsmanager::Client;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use redgold_schema::{error_info, ErrorInfoContext, RgResult, SafeOption};
+
+/// Environment variables holding a secret reference rather than the secret, i.e.
+/// REDGOLD_SECRET_REF_RECAPTCHA_SECRET=aws-sm:prod/redgold#recaptcha
+pub const SECRET_REF_ENV_PREFIX: &str = "REDGOLD_SECRET_REF_";
+/// Secrets passed to deployed nodes, taken from the deploying environment unless configured otherwise
+pub const DEPLOY_SECRETS: [&str; 4] = ["AWS_ACCESS_KEY_ID", "AWS_SECRET_ACCESS_KEY", "ETHERSCAN_API_KEY", "RECAPTCHA_SECRET"];
+const DEFAULT_AWS_REGION: &str = "us-east-1";
+
+/// Where a secret is read from, so config and env files only ever contain the reference.
+/// Written as env:VAR, file:/path or aws-sm:secret_id with an optional #json_key.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum SecretRef {
+    Env(String),
+    File(String),
+    AwsSecretsManager { secret_id: String, key: Option<String> },
+}
+
+impl SecretRef {
+
+    pub fn parse(reference: &str) -> RgResult<Self> {
+        let (scheme, value) = reference.trim().split_once(':')
+            .ok_or(error_info(format!("Secret reference {} missing scheme, expected env:, file: or aws-sm:", reference)))?;
+        if value.is_empty() {
+            return Err(error_info(format!("Secret reference {} is empty", reference)));
+        }
+        match scheme {
+            "env" => Ok(Self::Env(value.to_string())),
+            "file" => Ok(Self::File(value.to_string())),
+            "aws-sm" => {
+                let (secret_id, key) = match value.split_once('#') {
+                    Some((id, k)) => (id.to_string(), Some(k.to_string())),
+                    None => (value.to_string(), None),
+                };
+                Ok(Self::AwsSecretsManager { secret_id, key })
+            }
+            other => Err(error_info(format!("Unsupported secret reference scheme {}", other))),
+        }
+    }
+
+    pub async fn resolve(&self) -> RgResult<String> {
+        match self {
+            Self::Env(var) => std::env::var(var)
+                .error_info(format!("Secret environment variable {} not set", var)),
+            Self::File(path) => std::fs::read_to_string(path)
+                .error_info(format!("Failed to read secret file {}", path))
+                .map(|s| s.trim().to_string()),
+            Self::AwsSecretsManager { secret_id, key } => {
+                let value = aws_secret_string(secret_id).await?;
+                match key {
+                    None => Ok(value),
+                    Some(k) => {
+                        let json = serde_json::from_str::<serde_json::Value>(&value)
+                            .error_info(format!("Secret {} is not a JSON object", secret_id))?;
+                        json.get(k).and_then(|v| v.as_str()).map(|v| v.to_string())
+                            .ok_msg(format!("Secret {} missing key {}", secret_id, k))
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Display for SecretRef {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Env(var) => write!(f, "env:{}", var),
+            Self::File(path) => write!(f, "file:{}", path),
+            Self::AwsSecretsManager { secret_id, key: None } => write!(f, "aws-sm:{}", secret_id),
+            Self::AwsSecretsManager { secret_id, key: Some(k) } => write!(f, "aws-sm:{}#{}", secret_id, k),
+        }
+    }
+}
+
+async fn aws_secret_string(secret_id: &String) -> RgResult<String> {
+    let region_provider = RegionProviderChain::default_provider()
+        .or_else(Region::new(DEFAULT_AWS_REGION));
+    let shared_config = aws_config::from_env().region(region_provider).load().await;
+    let client = Client::new(&shared_config);
+    let response = client.get_secret_value().secret_id(secret_id).send().await
+        .map_err(|e| error_info(format!("Failed to get secret {}: {}", secret_id, e)))?;
+    response.secret_string().map(|s| s.to_string())
+        .ok_msg(format!("Secret {} has no string value", secret_id))
+}
+
+pub fn secret_ref_env_key(name: &str) -> String {
+    format!("{}{}", SECRET_REF_ENV_PREFIX, name)
+}
+
+/// Parses NAME=reference pairs as passed on the command line.
+pub fn parse_secret_args(args: &Vec<String>) -> RgResult<HashMap<String, SecretRef>> {
+    args.iter().map(|a| {
+        let (name, reference) = a.split_once('=')
+            .ok_or(error_info(format!("Secret argument {} should be NAME=reference", a)))?;
+        Ok((name.trim().to_string(), SecretRef::parse(reference)?))
+    }).collect()
+}
+
+/// Adds secrets to a remote env file for anything not already set. Env references are read on
+/// the deploying machine and written as values, file and AWS references are written as
+/// references and resolved by the node, so the value never leaves where it's stored.
+pub fn deploy_secret_env(env: &mut HashMap<String, String>, refs: &HashMap<String, SecretRef>) -> RgResult<()> {
+    let names = DEPLOY_SECRETS.iter().map(|s| s.to_string())
+        .chain(refs.keys().cloned())
+        .unique()
+        .collect_vec();
+    for name in names {
+        if env.contains_key(&name) || env.contains_key(&secret_ref_env_key(&name)) {
+            continue;
+        }
+        match refs.get(&name) {
+            // Unconfigured defaults are only copied when present locally
+            None => if let Ok(v) = std::env::var(&name) {
+                env.insert(name, v);
+            },
+            Some(SecretRef::Env(var)) => {
+                let v = std::env::var(var).error_info(format!("Secret environment variable {} not set", var))?;
+                env.insert(name, v);
+            },
+            Some(r) => {
+                env.insert(secret_ref_env_key(&name), r.to_string());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Secret references available to the node at runtime, keyed by the name the secret would
+/// otherwise be read from as an environment variable.
+#[derive(Clone, Debug, Default)]
+pub struct SecretsConfig {
+    pub refs: HashMap<String, SecretRef>,
+    // Values resolved once at startup, so requests never wait on a secret store
+    values: HashMap<String, String>,
+}
+
+impl SecretsConfig {
+
+    pub fn from_vars(vars: impl Iterator<Item=(String, String)>) -> RgResult<Self> {
+        let mut refs = HashMap::new();
+        for (k, v) in vars {
+            if let Some(name) = k.strip_prefix(SECRET_REF_ENV_PREFIX) {
+                refs.insert(name.to_string(), SecretRef::parse(&v)?);
+            }
+        }
+        Ok(Self { refs, values: HashMap::new() })
+    }
+
+    /// Resolves every reference and exports it under its name, so code and libraries reading the
+    /// plain environment variable see it. Env and file references go first since they may hold
+    /// the AWS credentials the remaining references need.
+    pub async fn resolve_all(&mut self) -> RgResult<()> {
+        let ordered = self.refs.iter()
+            .sorted_by_key(|(_, r)| matches!(r, SecretRef::AwsSecretsManager { .. }))
+            .map(|(k, r)| (k.clone(), r.clone()))
+            .collect_vec();
+        for (name, r) in ordered {
+            let value = r.resolve().await?;
+            std::env::set_var(&name, &value);
+            self.values.insert(name, value);
+        }
+        Ok(())
+    }
+
+    /// The resolved reference, falling back to a plain environment variable.
+    pub fn get(&self, name: &str) -> Option<String> {
+        self.values.get(name).cloned().or(std::env::var(name).ok())
+    }
+}
+
+#[test]
+fn secret_refs_parse_and_deploy_env() {
+    let refs = parse_secret_args(&vec![
+        "RECAPTCHA_SECRET=aws-sm:prod/redgold#recaptcha".to_string(),
+        "ETHERSCAN_API_KEY=file:/root/.rg/etherscan".to_string(),
+    ]).expect("parse");
+    let aws = SecretRef::AwsSecretsManager { secret_id: "prod/redgold".to_string(), key: Some("recaptcha".to_string()) };
+    assert_eq!(refs.get("RECAPTCHA_SECRET"), Some(&aws));
+    assert_eq!(SecretRef::parse(&aws.to_string()).expect("round trip"), aws);
+    assert!(SecretRef::parse("plaintext").is_err());
+    assert!(SecretRef::parse("vault:x").is_err());
+
+    let mut env = HashMap::new();
+    env.insert("AWS_ACCESS_KEY_ID".to_string(), "already set".to_string());
+    deploy_secret_env(&mut env, &refs).expect("env");
+    assert_eq!(env.get("AWS_ACCESS_KEY_ID").map(|s| s.as_str()), Some("already set"));
+    assert_eq!(env.get("REDGOLD_SECRET_REF_ETHERSCAN_API_KEY").map(|s| s.as_str()), Some("file:/root/.rg/etherscan"));
+    assert!(env.get("RECAPTCHA_SECRET").is_none());
+
+    let config = SecretsConfig::from_vars(env.into_iter()).expect("config");
+    assert_eq!(config.refs.len(), 2);
+    assert_eq!(config.refs.get("RECAPTCHA_SECRET"), Some(&aws));
+}
+
+#[tokio::test]
+async fn secret_refs_resolve_once_into_env() {
+    let path = std::env::temp_dir().join(format!("redgold_secret_ref_{}", std::process::id()));
+    std::fs::write(&path, "file-secret\n").expect("write");
+    let name = format!("REDGOLD_TEST_SECRET_{}", std::process::id());
+    let vars = vec![(secret_ref_env_key(&name), format!("file:{}", path.to_string_lossy()))];
+    let mut config = SecretsConfig::from_vars(vars.into_iter()).expect("config");
+    config.resolve_all().await.expect("resolve");
+    std::fs::remove_file(&path).ok();
+    // Served from the cache after the file is gone
+    assert_eq!(config.get(&name), Some("file-secret".to_string()));
+    assert_eq!(std::env::var(&name).ok(), Some("file-secret".to_string()));
+}
@@ -43,6 +43,21 @@ export default {
                 return null;
             }
         },
+        async fetchBalanceHistory(address) {
+            try {
+                let url = this.getUrl()
+                const response = await fetch(`${url}/explorer/balance-history/${address}`);
+
+                if (!response.ok) {
+                    throw new Error(`HTTP error! status: ${response.status}`);
+                }
+
+                return await response.json();
+            } catch (error) {
+                console.error('An error occurred:', error);
+                return null;
+            }
+        },
         async fetchData(offset = null, limit = null) {
             const hash = this.$route.params.param; // get the hash from the route parameter
 